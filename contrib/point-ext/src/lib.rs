@@ -2,17 +2,20 @@
 //!
 //! Demonstrates all extension traits:
 //! - TypeExtension: Point type serialization/deserialization
+//! - CastExtension: Text input cast ('(1,2)'::point)
 //! - OperatorExtension: Distance operator (<->)
-//! - FunctionExtension: magnitude() and distance() scalar functions
+//! - FunctionExtension: point(), magnitude() and distance() scalar functions
 //!
 //! Auto-registers with Flint via inventory pattern (no cfg attributes needed)
 
 use flintdb::extensions::{
-    TypeExtension, OperatorExtension, FunctionExtension, TypeCategory, loader::ExtensionLoader,
-    registry::{TypeRegistry, OperatorRegistry, FunctionRegistry},
+    TypeExtension, CastExtension, CastContext, OperatorExtension, FunctionExtension, TypeCategory,
+    loader::ExtensionLoader,
+    registry::{TypeRegistry, CastRegistry, OperatorRegistry, FunctionRegistry},
 };
 use flintdb::types::{Value, DataType};
 use std::any::Any;
+use std::sync::Arc;
 
 /// 2D Cartesian point (x, y)
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -80,8 +83,21 @@ impl TypeExtension for PointType {
     }
 
     fn to_pgwire_type(&self) -> pgwire::api::Type {
-        // No direct pgwire type for point - use UNKNOWN
-        pgwire::api::Type::UNKNOWN
+        pgwire::api::Type::POINT
+    }
+
+    fn encode_text(&self, value: &dyn Any) -> Result<String, String> {
+        let point = value.downcast_ref::<Point>().ok_or("Invalid point value")?;
+        Ok(format!("({},{})", point.x, point.y))
+    }
+
+    fn encode_binary(&self, value: &dyn Any) -> Result<Vec<u8>, String> {
+        // Postgres sends a point as two big-endian float8s
+        let point = value.downcast_ref::<Point>().ok_or("Invalid point value")?;
+        let mut bytes = Vec::with_capacity(16);
+        bytes.extend_from_slice(&point.x.to_be_bytes());
+        bytes.extend_from_slice(&point.y.to_be_bytes());
+        Ok(bytes)
     }
 }
 
@@ -116,6 +132,39 @@ impl OperatorExtension for DistanceOperator {
     }
 }
 
+/// Point constructor: point(float, float) -> point
+///
+/// Integer arguments are converted to float by the implicit cast before the
+/// call.
+pub struct PointFunc;
+
+impl FunctionExtension for PointFunc {
+    fn name(&self) -> &str {
+        "point"
+    }
+
+    fn execute(&self, args: &[Value]) -> Result<Value, String> {
+        match args {
+            [Value::Float(x), Value::Float(y)] => Ok(Value::Extension {
+                type_oid: 600,
+                data: Arc::new(Point::new(*x, *y)),
+            }),
+            [_, _] => Err("point() expects float arguments".to_string()),
+            _ => Err(format!("point() expects 2 arguments, got {}", args.len())),
+        }
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, String> {
+        match arg_types {
+            [DataType::Float, DataType::Float] => Ok(DataType::Extension {
+                type_oid: 600,
+                type_name: "point".to_string(),
+            }),
+            _ => Err("point() expects two float arguments".to_string()),
+        }
+    }
+}
+
 /// Magnitude function: magnitude(point) -> float
 pub struct MagnitudeFunc;
 
@@ -185,6 +234,40 @@ impl FunctionExtension for DistanceFunc {
     }
 }
 
+/// Text input cast: '(x,y)'::point
+///
+/// Explicit only, like the casts from text to the built-in types. The cast
+/// back to text is the type's text output and needs no registration.
+pub struct TextToPointCast;
+
+impl CastExtension for TextToPointCast {
+    fn can_cast(&self, source: &DataType, target: &DataType) -> bool {
+        matches!(source, DataType::String) && matches!(target, DataType::Extension { type_oid: 600, .. })
+    }
+
+    fn context(&self) -> CastContext {
+        CastContext::Explicit
+    }
+
+    fn execute(&self, value: &Value, _target: &DataType) -> Result<Value, String> {
+        let Value::String(text) = value else {
+            return Err("point input expects text".to_string());
+        };
+        let invalid = || format!("invalid input syntax for type point: \"{}\"", text);
+        let trimmed = text.trim();
+        let inner = trimmed.strip_prefix('(')
+            .and_then(|rest| rest.strip_suffix(')'))
+            .unwrap_or(trimmed);
+        let (x, y) = inner.split_once(',').ok_or_else(invalid)?;
+        let x = x.trim().parse::<f64>().map_err(|_| invalid())?;
+        let y = y.trim().parse::<f64>().map_err(|_| invalid())?;
+        Ok(Value::Extension {
+            type_oid: 600,
+            data: Arc::new(Point::new(x, y)),
+        })
+    }
+}
+
 // ============================================================================
 // Auto-discovery registration via inventory pattern
 // ============================================================================
@@ -207,11 +290,16 @@ impl ExtensionLoader for PointExtLoader {
         registry.register(Box::new(PointType));
     }
 
+    fn load_casts(&self, registry: &mut CastRegistry) {
+        registry.register(Box::new(TextToPointCast));
+    }
+
     fn load_operators(&self, registry: &mut OperatorRegistry) {
         registry.register(Box::new(DistanceOperator));
     }
 
     fn load_functions(&self, registry: &mut FunctionRegistry) {
+        registry.register(Box::new(PointFunc));
         registry.register(Box::new(MagnitudeFunc));
         registry.register(Box::new(DistanceFunc));
     }
@@ -0,0 +1,116 @@
+//! Point functions and operators called from SQL, against a server linking
+//! this extension
+
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use flintdb::config::Config;
+use flintdb::server::Server;
+
+// Link the extension so it registers itself
+use point_ext as _;
+
+/// Start a server in this process, in a fresh directory, once for all tests
+fn start_server() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let dir = std::env::temp_dir().join(format!("flint-point-ext-{}", now.as_nanos()));
+        std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+        std::env::set_current_dir(&dir).expect("failed to enter temp dir");
+
+        thread::spawn(|| {
+            let runtime = tokio::runtime::Runtime::new().expect("failed to start runtime");
+            runtime.block_on(Server::new(Config::from_args()).start());
+        });
+        for _ in 0..50 {
+            if execute_sql("SELECT 1;").is_ok() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        panic!("server failed to start");
+    });
+}
+
+/// Execute SQL via psql, returning its output or the error it printed
+fn execute_sql(sql: &str) -> Result<String, String> {
+    let output = Command::new("psql")
+        .args(["-h", "127.0.0.1", "-U", "postgres", "-d", "postgres", "-c", sql])
+        .output()
+        .map_err(|e| format!("failed to execute psql: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Data rows of psql output, with cells trimmed and joined by `|`
+fn data_rows(result: &str) -> Vec<String> {
+    result
+        .lines()
+        .skip(2)
+        .take_while(|line| !line.starts_with('('))
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.split('|').map(str::trim).collect::<Vec<_>>().join("|"))
+        .collect()
+}
+
+#[test]
+fn test_functions_and_operators() {
+    start_server();
+
+    let result = execute_sql("SELECT point(1.5, 2), magnitude(point(3, 4)), point(0, 0) <-> point(6, 8);")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["(1.5,2)|5.0|10.0"]);
+
+    execute_sql("CREATE TABLE places (id INT, x FLOAT, y FLOAT, PRIMARY KEY (id));").expect("CREATE TABLE failed");
+    execute_sql("INSERT INTO places VALUES (1, 3.0, 4.0), (2, 30.0, 40.0), (3, 0.0, 1.0);").expect("INSERT failed");
+
+    // Calls resolve against column types, in the select list and in WHERE
+    let result = execute_sql(
+        "SELECT id, distance(point(x, y), point(0, 0)) FROM places \
+         WHERE (point(x, y) <-> point(0, 0)) < 10.0 ORDER BY id;",
+    )
+    .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1|5.0", "3|1.0"]);
+
+    // A NULL argument gives NULL without calling the function
+    let result = execute_sql("SELECT 1, magnitude(NULL);").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1|"]);
+}
+
+#[test]
+fn test_casts() {
+    start_server();
+
+    let result = execute_sql("SELECT '(3, 4)'::point <-> point(0, 0), CAST('1,2' AS point), point(1.5, 2)::text;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["5.0|(1,2)|(1.5,2)"]);
+
+    let err = execute_sql("SELECT 'nowhere'::point;").unwrap_err();
+    assert!(err.contains("invalid input syntax for type point: \"nowhere\""), "unexpected error: {}", err);
+
+    // Text to point is explicit only
+    let err = execute_sql("SELECT magnitude('(3,4)');").unwrap_err();
+    assert!(err.contains("function magnitude(text) does not exist"), "unexpected error: {}", err);
+
+    let err = execute_sql("SELECT 1.5::point;").unwrap_err();
+    assert!(err.contains("cannot cast type double precision to point"), "unexpected error: {}", err);
+}
+
+#[test]
+fn test_calls_without_a_match() {
+    start_server();
+
+    let err = execute_sql("SELECT magnitude(1);").unwrap_err();
+    assert!(err.contains("function magnitude(bigint) does not exist"), "unexpected error: {}", err);
+
+    let err = execute_sql("SELECT 1 <-> 2;").unwrap_err();
+    assert!(err.contains("operator does not exist: bigint <-> bigint"), "unexpected error: {}", err);
+
+    let err = execute_sql("SELECT nosuchfunction(point(1, 2));").unwrap_err();
+    assert!(err.contains("function nosuchfunction(point) does not exist"), "unexpected error: {}", err);
+}
@@ -1,6 +1,12 @@
+use std::path::PathBuf;
+
 pub struct Config {
     pub(crate) bind_addr: String,
     pub(crate) port: u16,
+    /// Directory the write-ahead log is kept in
+    pub(crate) data_dir: PathBuf,
+    /// Memory a sort may use before spilling to temporary files, in bytes
+    pub(crate) work_mem: usize,
     #[cfg(feature = "extensions")]
     pub(crate) load_all_extensions: bool,
     #[cfg(feature = "extensions")]
@@ -12,11 +18,88 @@ impl Config {
         Config {
             bind_addr: "127.0.0.1".to_string(),
             port: 5432,
+            data_dir: PathBuf::from("."),
+            work_mem: 4 * 1024 * 1024,
             #[cfg(feature = "extensions")]
             load_all_extensions: false,
             #[cfg(feature = "extensions")]
-            enabled_extensions: vec!["point-ext".into()],
+            enabled_extensions: vec!["point".into()],
+        }
+    }
+}
+
+
+/// Run-time parameters changed with SET and read back with SHOW
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Settings {
+    /// Memory a sort may use before spilling to temporary files, in bytes
+    pub(crate) work_mem: usize,
+    /// Planner may use nested loop joins
+    pub(crate) enable_nestloop: bool,
+    /// Planner may use hash joins
+    pub(crate) enable_hashjoin: bool,
+    /// Planner may use merge joins
+    pub(crate) enable_mergejoin: bool,
+    /// Planner may use index scans and index nested loop joins
+    pub(crate) enable_indexscan: bool,
+    /// Planner may combine indexes with bitmap scans
+    pub(crate) enable_bitmapscan: bool,
+    /// How prepared statements choose between generic and custom plans
+    pub(crate) plan_cache_mode: PlanCacheMode,
+    /// Text format of bytea values in results
+    pub(crate) bytea_output: ByteaOutput,
+}
+
+impl Settings {
+    pub(crate) fn new(config: &Config) -> Self {
+        Settings {
+            work_mem: config.work_mem,
+            enable_nestloop: true,
+            enable_hashjoin: true,
+            enable_mergejoin: true,
+            enable_indexscan: true,
+            enable_bitmapscan: true,
+            plan_cache_mode: PlanCacheMode::Auto,
+            bytea_output: ByteaOutput::Hex,
         }
     }
 }
 
+/// Plans a prepared statement runs with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PlanCacheMode {
+    /// Custom plans at first, then the generic plan unless it costs more
+    Auto,
+    /// The generic plan, made without parameter values, where one can be made
+    ForceGenericPlan,
+    /// A custom plan for the parameter values of each execution
+    ForceCustomPlan,
+}
+
+impl PlanCacheMode {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            PlanCacheMode::Auto => "auto",
+            PlanCacheMode::ForceGenericPlan => "force_generic_plan",
+            PlanCacheMode::ForceCustomPlan => "force_custom_plan",
+        }
+    }
+}
+
+/// How bytea values print as text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ByteaOutput {
+    /// `\x` then two hex digits per byte
+    Hex,
+    /// Printable ASCII as itself, other bytes as backslashed octal
+    Escape,
+}
+
+impl ByteaOutput {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ByteaOutput::Hex => "hex",
+            ByteaOutput::Escape => "escape",
+        }
+    }
+}
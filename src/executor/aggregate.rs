@@ -0,0 +1,439 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use bigdecimal::BigDecimal;
+use sqlparser::ast::{BinaryOperator, Expr};
+use tracing::debug;
+
+use crate::executor::batch::{Batch, Vector};
+use crate::executor::error::ExecutorError;
+use crate::executor::evaluator::{self, Result};
+use crate::executor::numeric;
+use crate::executor::vectorized::CompiledExpr;
+use crate::extensions::registry::Registries;
+use crate::planner::aggregate::{AggregateCall, AggregateFunction};
+use crate::types::{Column, DataType, HashKey, Row, Schema, Value};
+
+/// Group input rows by `group_by` and compute `aggregates` for each group
+///
+/// Input arrives in batches: grouping keys and aggregate arguments are
+/// evaluated a batch at a time, and each group's accumulators are updated
+/// with the batch positions that belong to it. Output rows hold the GROUP BY
+/// values followed by the aggregate results, in the order groups were first
+/// seen. Without GROUP BY a single row is produced, even for empty input.
+pub fn hash_aggregate(
+    input_schema: &Schema,
+    batches: impl Iterator<Item = Result<Batch>>,
+    group_by: &[Expr],
+    aggregates: &[AggregateCall],
+    registries: &Registries,
+) -> Result<(Schema, Vec<Row>)> {
+    debug!(groups = group_by.len(), aggregates = aggregates.len(), "hash aggregate");
+
+    let group_exprs: Vec<_> = group_by.iter()
+        .map(|expr| CompiledExpr::compile(expr, input_schema, registries))
+        .collect();
+    let arg_exprs: Vec<Vec<_>> = aggregates.iter()
+        .map(|call| call.args.iter().map(|arg| CompiledExpr::compile(arg, input_schema, registries)).collect())
+        .collect();
+    let filter_exprs: Vec<_> = aggregates.iter()
+        .map(|call| call.filter.as_ref().map(|filter| CompiledExpr::compile(filter, input_schema, registries)))
+        .collect();
+
+    let mut groups: Vec<(Vec<Value>, Vec<Accumulator>)> = Vec::new();
+    let mut group_index: HashMap<Vec<HashKey>, usize> = HashMap::new();
+
+    if group_by.is_empty() {
+        groups.push((Vec::new(), aggregates.iter().map(|call| Accumulator::new(call, registries)).collect()));
+    }
+
+    for batch in batches {
+        let batch = batch?;
+
+        // Positions of the batch belonging to each group, in input order
+        let mut members: Vec<(usize, Vec<usize>)> = Vec::new();
+        if group_by.is_empty() {
+            members.push((0, (0..batch.len).collect()));
+        } else {
+            let keys = group_exprs.iter()
+                .map(|expr| expr.eval(&batch, input_schema, registries))
+                .collect::<Result<Vec<_>>>()?;
+            let mut batch_groups: HashMap<usize, usize> = HashMap::new();
+            for idx in 0..batch.len {
+                let values: Vec<Value> = keys.iter().map(|key| key.get(idx)).collect();
+                let key = hash_keys(&values)?;
+                let group = *group_index.entry(key).or_insert_with(|| {
+                    groups.push((values, aggregates.iter().map(|call| Accumulator::new(call, registries)).collect()));
+                    groups.len() - 1
+                });
+                let slot = *batch_groups.entry(group).or_insert_with(|| {
+                    members.push((group, Vec::new()));
+                    members.len() - 1
+                });
+                members[slot].1.push(idx);
+            }
+        }
+
+        for (call_idx, (args, filter)) in arg_exprs.iter().zip(&filter_exprs).enumerate() {
+            let evaluated = args.iter()
+                .map(|arg| arg.eval(&batch, input_schema, registries))
+                .collect::<Result<Vec<_>>>()
+                .and_then(|args| Ok((args, filter.as_ref().map(|f| f.eval(&batch, input_schema, registries)).transpose()?)));
+            match evaluated {
+                Ok((args, filter)) => {
+                    for (group, positions) in &members {
+                        groups[*group].1[call_idx].update_batch(&args, filter.as_ref(), positions)?;
+                    }
+                }
+                // Arguments may fail only for rows FILTER excludes: go row by row
+                Err(_) => {
+                    for (group, positions) in &members {
+                        for idx in positions {
+                            groups[*group].1[call_idx].update(&batch.row(*idx), input_schema)?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Output schema: grouping columns then aggregate results
+    let mut columns = Vec::with_capacity(group_by.len() + aggregates.len());
+    for expr in group_by {
+        columns.push(Column {
+            name: crate::planner::aggregate::output_name(expr),
+            data_type: evaluator::infer_type(expr, input_schema, registries),
+            is_primary_key: false,
+        });
+    }
+    for call in aggregates {
+        columns.push(Column {
+            name: call.output_name.clone(),
+            data_type: result_type(call, input_schema, registries),
+            is_primary_key: false,
+        });
+    }
+
+    let output = groups.into_iter()
+        .map(|(mut values, accumulators)| {
+            values.extend(accumulators.into_iter().map(Accumulator::finish));
+            Row::new(values)
+        })
+        .collect();
+
+    Ok((Schema::new(columns), output))
+}
+
+/// Result type of an aggregate call over input rows of `schema`
+pub(crate) fn result_type(call: &AggregateCall, schema: &Schema, registries: &Registries) -> DataType {
+    let arg_type = || call.args.first()
+        .map(|arg| evaluator::infer_type(arg, schema, registries))
+        .unwrap_or(DataType::Null);
+
+    match call.func {
+        AggregateFunction::Count => DataType::Int,
+        // Integers and numerics average and sum exactly, to numerics of any
+        // scale, so sums cannot overflow and averages keep every digit
+        AggregateFunction::Avg | AggregateFunction::Sum if matches!(arg_type(), DataType::Int | DataType::Numeric(_)) => {
+            DataType::Numeric(None)
+        }
+        AggregateFunction::Avg => DataType::Float,
+        AggregateFunction::Sum | AggregateFunction::Min | AggregateFunction::Max => arg_type(),
+        AggregateFunction::BoolAnd | AggregateFunction::BoolOr => DataType::Bool,
+        AggregateFunction::StringAgg => DataType::String,
+    }
+}
+
+fn hash_keys(values: &[Value]) -> Result<Vec<HashKey>> {
+    values.iter()
+        .map(|value| value.hash_key().ok_or_else(|| ExecutorError::Execution(
+            "could not identify an equality operator for extension type".to_string(),
+        )))
+        .collect()
+}
+
+/// Running state of one aggregate call within one group
+#[derive(Clone)]
+enum State {
+    Count(i64),
+    Sum(Option<Value>),
+    /// Integer and numeric inputs are summed exactly in `exact`
+    Avg { sum: f64, exact: Option<BigDecimal>, count: i64 },
+    Min(Option<Value>),
+    Max(Option<Value>),
+    BoolAnd(Option<bool>),
+    BoolOr(Option<bool>),
+    StringAgg(Option<String>),
+}
+
+#[derive(Clone)]
+pub(crate) struct Accumulator<'a> {
+    call: &'a AggregateCall,
+    registries: &'a Registries,
+    state: State,
+    /// Argument values already aggregated (DISTINCT only)
+    seen: Option<HashSet<Vec<HashKey>>>,
+}
+
+impl<'a> Accumulator<'a> {
+    pub(crate) fn new(call: &'a AggregateCall, registries: &'a Registries) -> Self {
+        let state = match call.func {
+            AggregateFunction::Count => State::Count(0),
+            AggregateFunction::Sum => State::Sum(None),
+            AggregateFunction::Avg => State::Avg { sum: 0.0, exact: None, count: 0 },
+            AggregateFunction::Min => State::Min(None),
+            AggregateFunction::Max => State::Max(None),
+            AggregateFunction::BoolAnd => State::BoolAnd(None),
+            AggregateFunction::BoolOr => State::BoolOr(None),
+            AggregateFunction::StringAgg => State::StringAgg(None),
+        };
+        Accumulator {
+            call,
+            registries,
+            state,
+            seen: call.distinct.then(HashSet::new),
+        }
+    }
+
+    pub(crate) fn update(&mut self, row: &Row, schema: &Schema) -> Result<()> {
+        if let Some(filter) = &self.call.filter
+            && !matches!(evaluator::eval_expr(filter, row, schema, self.registries)?, Value::Bool(true))
+        {
+            return Ok(());
+        }
+
+        // COUNT(*) counts rows, not values
+        if self.call.args.is_empty() {
+            if let State::Count(count) = &mut self.state {
+                *count += 1;
+            }
+            return Ok(());
+        }
+
+        let args = self.call.args.iter()
+            .map(|arg| evaluator::eval_expr(arg, row, schema, self.registries))
+            .collect::<Result<Vec<_>>>()?;
+        self.accumulate(&args)
+    }
+
+    /// Aggregate the rows at `positions` of a batch, given the argument and
+    /// FILTER vectors evaluated over the whole batch
+    pub(crate) fn update_batch(&mut self, args: &[Vector], filter: Option<&Vector>, positions: &[usize]) -> Result<()> {
+        let filtered;
+        let positions = match filter {
+            Some(filter) => {
+                filtered = positions.iter()
+                    .copied()
+                    .filter(|idx| matches!(filter.get(*idx), Value::Bool(true)))
+                    .collect::<Vec<_>>();
+                &filtered[..]
+            }
+            None => positions,
+        };
+
+        // Kernels over typed vectors; DISTINCT needs each value
+        if self.seen.is_none() {
+            match (&mut self.state, args.first()) {
+                (State::Count(count), None) => {
+                    *count += positions.len() as i64;
+                    return Ok(());
+                }
+                (State::Count(count), Some(arg)) => {
+                    *count += positions.iter().filter(|idx| !arg.is_null(**idx)).count() as i64;
+                    return Ok(());
+                }
+                (State::Avg { exact, count, .. }, Some(Vector::Int { values, nulls })) => {
+                    let mut partial = 0i128;
+                    for idx in positions.iter().filter(|idx| !nulls.get(**idx)) {
+                        partial += i128::from(values[*idx]);
+                        *count += 1;
+                    }
+                    *exact = Some(match exact.take() {
+                        Some(exact) => numeric::add(&exact, &partial.into())?,
+                        None => partial.into(),
+                    });
+                    return Ok(());
+                }
+                (State::Avg { sum, count, .. }, Some(Vector::Float { values, nulls })) => {
+                    for idx in positions.iter().filter(|idx| !nulls.get(**idx)) {
+                        *sum += values[*idx];
+                        *count += 1;
+                    }
+                    return Ok(());
+                }
+                (State::Sum(_) | State::Min(_) | State::Max(_), Some(Vector::Int { values, nulls })) => {
+                    let present = positions.iter().filter(|idx| !nulls.get(**idx)).map(|idx| values[*idx]);
+                    // A batch of bigints cannot overflow an i128
+                    let partial = match self.call.func {
+                        AggregateFunction::Sum => present
+                            .map(i128::from)
+                            .reduce(|a, b| a + b)
+                            .map(|sum| Value::Numeric(sum.into())),
+                        AggregateFunction::Min => present.min().map(Value::Int),
+                        _ => present.max().map(Value::Int),
+                    };
+                    if let Some(partial) = partial {
+                        self.accumulate(&[partial])?;
+                    }
+                    return Ok(());
+                }
+                // Float sums are added in row order, so they go through accumulate
+                (State::Min(_) | State::Max(_), Some(Vector::Float { values, nulls })) => {
+                    let present = positions.iter().filter(|idx| !nulls.get(**idx)).map(|idx| values[*idx]);
+                    let partial = match self.call.func {
+                        AggregateFunction::Min => present.reduce(|a, b| if b.total_cmp(&a).is_lt() { b } else { a }),
+                        _ => present.reduce(|a, b| if b.total_cmp(&a).is_gt() { b } else { a }),
+                    };
+                    if let Some(partial) = partial {
+                        self.accumulate(&[Value::Float(partial)])?;
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        for idx in positions {
+            let values: Vec<Value> = args.iter().map(|arg| arg.get(*idx)).collect();
+            self.accumulate(&values)?;
+        }
+        Ok(())
+    }
+
+    /// Add one row's argument values
+    fn accumulate(&mut self, args: &[Value]) -> Result<()> {
+        if self.call.args.is_empty() {
+            if let State::Count(count) = &mut self.state {
+                *count += 1;
+            }
+            return Ok(());
+        }
+
+        // Aggregates skip NULL inputs
+        let value = &args[0];
+        if matches!(value, Value::Null) {
+            return Ok(());
+        }
+
+        if let Some(seen) = &mut self.seen
+            && !seen.insert(hash_keys(args)?)
+        {
+            return Ok(());
+        }
+
+        let name = self.call.func.name();
+        match &mut self.state {
+            State::Count(count) => *count += 1,
+            State::Sum(sum) => {
+                *sum = Some(match (sum.take(), value) {
+                    // Integers are summed as numerics
+                    (None, Value::Int(n)) => Value::Numeric((*n).into()),
+                    (None, Value::Float(_) | Value::Numeric(_) | Value::Interval(_)) => value.clone(),
+                    (Some(Value::Float(a)), Value::Int(b)) => Value::Float(a + *b as f64),
+                    (Some(Value::Float(a)), Value::Float(b)) => Value::Float(a + b),
+                    (Some(Value::Numeric(a)), Value::Float(b)) => Value::Float(numeric::to_float(&a) + b),
+                    (Some(Value::Numeric(a)), Value::Int(b)) => Value::Numeric(numeric::add(&a, &(*b).into())?),
+                    (Some(Value::Numeric(a)), Value::Numeric(b)) => Value::Numeric(numeric::add(&a, b)?),
+                    (Some(sum @ Value::Interval(_)), Value::Interval(_)) => {
+                        evaluator::eval_binary_op(&sum, &BinaryOperator::Plus, value, self.registries)?
+                    }
+                    _ => return Err(type_error(name, value)),
+                });
+            }
+            State::Avg { sum, exact, count } => {
+                let n = match value {
+                    Value::Int(n) => BigDecimal::from(*n),
+                    Value::Numeric(n) => n.clone(),
+                    Value::Float(f) => {
+                        *sum += *f;
+                        *count += 1;
+                        return Ok(());
+                    }
+                    _ => return Err(type_error(name, value)),
+                };
+                *exact = Some(match exact.take() {
+                    Some(exact) => numeric::add(&exact, &n)?,
+                    None => n,
+                });
+                *count += 1;
+            }
+            State::Min(current) | State::Max(current) => {
+                let wanted = if self.call.func == AggregateFunction::Min {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+                let replace = match current {
+                    None => true,
+                    Some(current) => evaluator::compare_values(value, current)? == wanted,
+                };
+                if replace {
+                    *current = Some(value.clone());
+                }
+            }
+            State::BoolAnd(result) | State::BoolOr(result) => {
+                let Value::Bool(b) = value else {
+                    return Err(type_error(name, value));
+                };
+                let is_and = self.call.func == AggregateFunction::BoolAnd;
+                *result = Some(match *result {
+                    None => *b,
+                    Some(acc) if is_and => acc && *b,
+                    Some(acc) => acc || *b,
+                });
+            }
+            State::StringAgg(result) => {
+                let Value::String(s) = value else {
+                    return Err(type_error(name, value));
+                };
+                match result {
+                    None => *result = Some(s.clone()),
+                    Some(acc) => {
+                        // A NULL delimiter concatenates without separator
+                        match &args[1] {
+                            Value::String(delimiter) => acc.push_str(delimiter),
+                            Value::Null => {}
+                            other => return Err(type_error(name, other)),
+                        }
+                        acc.push_str(s);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Value {
+        match self.state {
+            State::Count(count) => Value::Int(count),
+            State::Avg { count: 0, .. } => Value::Null,
+            State::Avg { exact: Some(sum), count, .. } => {
+                numeric::divide(&sum, &count.into()).map_or(Value::Null, Value::Numeric)
+            }
+            State::Avg { sum, count, .. } => Value::Float(sum / count as f64),
+            State::Sum(value) | State::Min(value) | State::Max(value) => value.unwrap_or(Value::Null),
+            State::BoolAnd(result) | State::BoolOr(result) => result.map(Value::Bool).unwrap_or(Value::Null),
+            State::StringAgg(result) => result.map(Value::String).unwrap_or(Value::Null),
+        }
+    }
+}
+
+fn type_error(function: &str, value: &Value) -> ExecutorError {
+    let type_name = match value {
+        Value::Null => "unknown",
+        Value::Int(_) => "bigint",
+        Value::Float(_) => "double precision",
+        Value::Numeric(_) => "numeric",
+        Value::String(_) => "text",
+        Value::Bool(_) => "boolean",
+        Value::Date(_) => "date",
+        Value::Time(_) => "time without time zone",
+        Value::Timestamp(_) => "timestamp without time zone",
+        Value::TimestampTz(_) => "timestamp with time zone",
+        Value::Interval(_) => "interval",
+        Value::Uuid(_) => "uuid",
+        Value::Bytea(_) => "bytea",
+        Value::Extension { .. } => "extension",
+    };
+    ExecutorError::Execution(format!("function {}({}) does not exist", function, type_name))
+}
@@ -0,0 +1,129 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use tracing::debug;
+
+use crate::executor::evaluator::{self, Result};
+use crate::storage::catalog::{ColumnStatistics, TableStatistics};
+use crate::types::{HashKey, Row, Schema, Value};
+
+/// Most common values and histogram buckets kept per column
+pub const STATISTICS_TARGET: usize = 100;
+
+/// Gather statistics for the columns at `positions` over every row of a table
+pub fn analyze(
+    schema: &Schema,
+    positions: &[usize],
+    rows: impl Iterator<Item = Result<Row>>,
+    page_count: u64,
+) -> Result<TableStatistics> {
+    let mut row_count = 0u64;
+    let mut columns = positions.iter().map(|_| ColumnCounts::default()).collect::<Vec<_>>();
+    for row in rows {
+        let row = row?;
+        row_count += 1;
+        for (counts, idx) in columns.iter_mut().zip(positions) {
+            counts.add(&row.values[*idx]);
+        }
+    }
+
+    let columns = columns.into_iter()
+        .zip(positions)
+        .map(|(counts, idx)| counts.finish(schema.columns[*idx].name.clone(), row_count))
+        .collect();
+    debug!(rows = row_count, pages = page_count, "table analyzed");
+
+    Ok(TableStatistics {
+        row_count,
+        page_count,
+        columns,
+    })
+}
+
+/// Occurrences of each value of one column
+#[derive(Default)]
+struct ColumnCounts {
+    nulls: u64,
+    values: HashMap<HashKey, (Value, u64)>,
+}
+
+impl ColumnCounts {
+    fn add(&mut self, value: &Value) {
+        if matches!(value, Value::Null) {
+            self.nulls += 1;
+            return;
+        }
+        // Extension values have no hashable form and are left out of the distribution
+        if let Some(key) = value.hash_key() {
+            self.values.entry(key).or_insert_with(|| (value.clone(), 0)).1 += 1;
+        }
+    }
+
+    /// Summarize the counts as a fraction of `row_count` rows
+    ///
+    /// Columns with at most `STATISTICS_TARGET` distinct values are described
+    /// exactly by their most common values. Otherwise values noticeably more
+    /// frequent than average are kept as most common values, and the rest are
+    /// split into equal-population histogram buckets.
+    fn finish(self, name: String, row_count: u64) -> ColumnStatistics {
+        let fraction = |count: u64| if row_count == 0 { 0.0 } else { count as f64 / row_count as f64 };
+        let distinct_count = self.values.len() as u64;
+        let non_null = self.values.values().map(|(_, count)| count).sum::<u64>();
+
+        let mut values = self.values.into_values().collect::<Vec<_>>();
+        values.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| compare(a, b)));
+
+        let common = if values.len() <= STATISTICS_TARGET {
+            values.len()
+        } else {
+            let average = non_null as f64 / distinct_count as f64;
+            values.iter()
+                .take(STATISTICS_TARGET)
+                .take_while(|(_, count)| *count > 1 && *count as f64 > average * 1.25)
+                .count()
+        };
+        let rest = values.split_off(common);
+        let most_common = values.into_iter()
+            .map(|(value, count)| (value, fraction(count)))
+            .collect();
+
+        ColumnStatistics {
+            name,
+            null_fraction: fraction(self.nulls),
+            distinct_count,
+            most_common,
+            histogram: histogram(rest),
+        }
+    }
+}
+
+/// Bounds of up to `STATISTICS_TARGET` buckets holding about as many rows each
+fn histogram(mut values: Vec<(Value, u64)>) -> Vec<Value> {
+    if values.len() < 2 {
+        return Vec::new();
+    }
+    values.sort_by(|(a, _), (b, _)| compare(a, b));
+
+    let total = values.iter().map(|(_, count)| count).sum::<u64>();
+    let buckets = STATISTICS_TARGET.min(values.len() - 1) as u64;
+    let mut bounds: Vec<Value> = Vec::with_capacity(buckets as usize + 1);
+    let mut values = values.into_iter();
+    let (mut value, mut end) = values.next().expect("at least two values");
+    for bucket in 0..=buckets {
+        // Bound `i` is the value at sorted row position i * (total - 1) / buckets
+        let position = bucket * (total - 1) / buckets;
+        while position >= end {
+            let (next, count) = values.next().expect("position within total");
+            value = next;
+            end += count;
+        }
+        if bounds.last().is_none_or(|last| compare(last, &value) != Ordering::Equal) {
+            bounds.push(value.clone());
+        }
+    }
+    bounds
+}
+
+fn compare(a: &Value, b: &Value) -> Ordering {
+    evaluator::compare_values(a, b).unwrap_or(Ordering::Equal)
+}
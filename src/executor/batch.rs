@@ -0,0 +1,224 @@
+use crate::types::{Row, Value};
+
+/// Rows per batch passed between vectorized operators
+pub const BATCH_SIZE: usize = 1024;
+
+/// One bit per row, set for NULL
+#[derive(Debug, Clone, Default)]
+pub struct Bitmap {
+    words: Vec<u64>,
+}
+
+impl Bitmap {
+    pub fn set(&mut self, idx: usize) {
+        let word = idx / 64;
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << (idx % 64);
+    }
+
+    pub fn get(&self, idx: usize) -> bool {
+        self.words.get(idx / 64).is_some_and(|word| word & (1 << (idx % 64)) != 0)
+    }
+
+    /// No bit is set
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+}
+
+/// Values of one column across the rows of a batch
+///
+/// Typed vectors hold a placeholder value at NULL positions. Columns mixing
+/// value types, or holding extension values, use `Values`.
+#[derive(Debug, Clone)]
+pub enum Vector {
+    Int { values: Vec<i64>, nulls: Bitmap },
+    Float { values: Vec<f64>, nulls: Bitmap },
+    Bool { values: Vec<bool>, nulls: Bitmap },
+    String { values: Vec<String>, nulls: Bitmap },
+    Values(Vec<Value>),
+}
+
+impl Vector {
+    /// Build the most specific vector able to hold `values`
+    pub fn from_values(values: Vec<Value>) -> Vector {
+        let mut nulls = Bitmap::default();
+        let first = values.iter().find(|value| !matches!(value, Value::Null));
+        macro_rules! typed {
+            ($variant:ident, $default:expr) => {{
+                let mut typed = Vec::with_capacity(values.len());
+                for (idx, value) in values.iter().enumerate() {
+                    match value {
+                        Value::$variant(v) => typed.push(v.clone()),
+                        Value::Null => {
+                            nulls.set(idx);
+                            typed.push($default);
+                        }
+                        _ => return Vector::Values(values),
+                    }
+                }
+                Vector::$variant { values: typed, nulls }
+            }};
+        }
+        match first {
+            Some(Value::Int(_)) => typed!(Int, 0),
+            Some(Value::Float(_)) => typed!(Float, 0.0),
+            Some(Value::Bool(_)) => typed!(Bool, false),
+            Some(Value::String(_)) => typed!(String, String::new()),
+            _ => Vector::Values(values),
+        }
+    }
+
+    /// `len` copies of one value
+    pub fn constant(value: &Value, len: usize) -> Vector {
+        let nulls = Bitmap::default();
+        match value {
+            Value::Int(n) => Vector::Int { values: vec![*n; len], nulls },
+            Value::Float(f) => Vector::Float { values: vec![*f; len], nulls },
+            Value::Bool(b) => Vector::Bool { values: vec![*b; len], nulls },
+            Value::String(s) => Vector::String { values: vec![s.clone(); len], nulls },
+            value => Vector::Values(vec![value.clone(); len]),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Vector::Int { values, .. } => values.len(),
+            Vector::Float { values, .. } => values.len(),
+            Vector::Bool { values, .. } => values.len(),
+            Vector::String { values, .. } => values.len(),
+            Vector::Values(values) => values.len(),
+        }
+    }
+
+    /// Whether any position is NULL
+    pub fn has_nulls(&self) -> bool {
+        match self {
+            Vector::Int { nulls, .. }
+            | Vector::Float { nulls, .. }
+            | Vector::Bool { nulls, .. }
+            | Vector::String { nulls, .. } => !nulls.is_empty(),
+            Vector::Values(values) => values.iter().any(|value| matches!(value, Value::Null)),
+        }
+    }
+
+    pub fn is_null(&self, idx: usize) -> bool {
+        match self {
+            Vector::Int { nulls, .. }
+            | Vector::Float { nulls, .. }
+            | Vector::Bool { nulls, .. }
+            | Vector::String { nulls, .. } => nulls.get(idx),
+            Vector::Values(values) => matches!(values[idx], Value::Null),
+        }
+    }
+
+    pub fn get(&self, idx: usize) -> Value {
+        match self {
+            Vector::Int { nulls, .. }
+            | Vector::Float { nulls, .. }
+            | Vector::Bool { nulls, .. }
+            | Vector::String { nulls, .. } if nulls.get(idx) => Value::Null,
+            Vector::Int { values, .. } => Value::Int(values[idx]),
+            Vector::Float { values, .. } => Value::Float(values[idx]),
+            Vector::Bool { values, .. } => Value::Bool(values[idx]),
+            Vector::String { values, .. } => Value::String(values[idx].clone()),
+            Vector::Values(values) => values[idx].clone(),
+        }
+    }
+
+    /// Positions holding a non-NULL true
+    pub fn true_positions(&self) -> Vec<usize> {
+        match self {
+            Vector::Bool { values, nulls } => values.iter()
+                .enumerate()
+                .filter(|(idx, value)| **value && !nulls.get(*idx))
+                .map(|(idx, _)| idx)
+                .collect(),
+            Vector::Values(values) => values.iter()
+                .enumerate()
+                .filter(|(_, value)| matches!(value, Value::Bool(true)))
+                .map(|(idx, _)| idx)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The values at `positions`, in order
+    pub fn take(&self, positions: &[usize]) -> Vector {
+        fn pick<T: Clone>(values: &[T], nulls: &Bitmap, positions: &[usize]) -> (Vec<T>, Bitmap) {
+            let mut picked_nulls = Bitmap::default();
+            let picked = positions.iter()
+                .enumerate()
+                .map(|(out, idx)| {
+                    if nulls.get(*idx) {
+                        picked_nulls.set(out);
+                    }
+                    values[*idx].clone()
+                })
+                .collect();
+            (picked, picked_nulls)
+        }
+        match self {
+            Vector::Int { values, nulls } => {
+                let (values, nulls) = pick(values, nulls, positions);
+                Vector::Int { values, nulls }
+            }
+            Vector::Float { values, nulls } => {
+                let (values, nulls) = pick(values, nulls, positions);
+                Vector::Float { values, nulls }
+            }
+            Vector::Bool { values, nulls } => {
+                let (values, nulls) = pick(values, nulls, positions);
+                Vector::Bool { values, nulls }
+            }
+            Vector::String { values, nulls } => {
+                let (values, nulls) = pick(values, nulls, positions);
+                Vector::String { values, nulls }
+            }
+            Vector::Values(values) => Vector::Values(positions.iter().map(|idx| values[*idx].clone()).collect()),
+        }
+    }
+}
+
+/// Up to `BATCH_SIZE` rows stored column by column
+#[derive(Debug, Clone)]
+pub struct Batch {
+    pub columns: Vec<Vector>,
+    /// Row count, kept separately for batches without columns
+    pub len: usize,
+}
+
+impl Batch {
+    /// Transpose rows of `width` values into columns
+    pub fn from_rows(rows: Vec<Row>, width: usize) -> Batch {
+        let len = rows.len();
+        let mut columns: Vec<Vec<Value>> = (0..width).map(|_| Vec::with_capacity(len)).collect();
+        for row in rows {
+            for (column, value) in columns.iter_mut().zip(row.values) {
+                column.push(value);
+            }
+        }
+        Batch {
+            columns: columns.into_iter().map(Vector::from_values).collect(),
+            len,
+        }
+    }
+
+    pub fn row(&self, idx: usize) -> Row {
+        Row::new(self.columns.iter().map(|column| column.get(idx)).collect())
+    }
+
+    pub fn into_rows(self) -> Vec<Row> {
+        (0..self.len).map(|idx| self.row(idx)).collect()
+    }
+
+    /// The rows at `positions`, in order
+    pub fn take(&self, positions: &[usize]) -> Batch {
+        Batch {
+            columns: self.columns.iter().map(|column| column.take(positions)).collect(),
+            len: positions.len(),
+        }
+    }
+}
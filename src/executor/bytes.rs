@@ -0,0 +1,216 @@
+//! UUIDs and byte strings: the UUID and BYTEA types
+//!
+//! A UUID is kept as its 16 bytes and orders by them, as Postgres orders
+//! UUIDs. It reads with or without hyphens (between any groups of four
+//! digits) and braces, and prints in the standard hyphenated form.
+//!
+//! A byte string reads in either of Postgres' text formats: hex, `\x` then
+//! two digits per byte, or escape, where bytes other than backslash stand
+//! for themselves and `\\` or `\nnn` (octal) write the rest. Values print in
+//! the format `bytea_output` selects, hex by default.
+
+use crate::config::ByteaOutput;
+use crate::executor::error::{sqlstate, ExecutorError};
+use crate::executor::evaluator::Result;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const BASE64_DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+/// Characters per line of base64 text, as encode() breaks it
+const BASE64_LINE: usize = 76;
+
+fn invalid(type_name: &str, text: &str) -> ExecutorError {
+    ExecutorError::Sql(sqlstate::INVALID_TEXT_REPRESENTATION, format!(
+        "invalid input syntax for type {}: \"{}\"",
+        type_name,
+        text
+    ))
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+// UUIDs
+
+/// A random (version 4) UUID, as gen_random_uuid() returns
+pub fn random_uuid() -> [u8; 16] {
+    uuid::Uuid::new_v4().into_bytes()
+}
+
+/// Read a UUID such as `a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11`
+pub fn parse_uuid(text: &str) -> Result<[u8; 16]> {
+    let trimmed = text.trim();
+    let digits = match trimmed.strip_prefix('{') {
+        Some(inner) => inner.strip_suffix('}').ok_or_else(|| invalid("uuid", text))?,
+        None => trimmed,
+    };
+    let mut bytes = [0u8; 16];
+    let mut count = 0;
+    let mut chars = digits.bytes().peekable();
+    while let Some(digit) = chars.next() {
+        // A hyphen may follow any group of four digits but the last
+        if digit == b'-' && count % 4 == 0 && count > 0 && count < 32 && chars.peek().is_some_and(|next| *next != b'-') {
+            continue;
+        }
+        let value = hex_value(digit).filter(|_| count < 32).ok_or_else(|| invalid("uuid", text))?;
+        bytes[count / 2] |= value << if count % 2 == 0 { 4 } else { 0 };
+        count += 1;
+    }
+    if count != 32 {
+        return Err(invalid("uuid", text));
+    }
+    Ok(bytes)
+}
+
+pub fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex = format_hex(bytes);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+// Byte strings
+
+/// Read a byte string in hex or escape format
+pub fn parse_bytea(text: &str) -> Result<Vec<u8>> {
+    match text.strip_prefix("\\x").or_else(|| text.strip_prefix("\\X")) {
+        Some(hex) => parse_hex(hex).map_err(|e| ExecutorError::Sql(sqlstate::INVALID_PARAMETER_VALUE, e)),
+        None => parse_escape(text).map_err(|_| invalid("bytea", text)),
+    }
+}
+
+/// Bytes of hex digits, which may be separated by whitespace between bytes
+fn parse_hex(text: &str) -> std::result::Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len() / 2);
+    let mut chars = text.chars().filter(|c| !c.is_ascii_whitespace());
+    while let Some(high) = chars.next() {
+        let digit = |c: char| c.to_digit(16)
+            .map(|value| value as u8)
+            .ok_or_else(|| format!("invalid hexadecimal digit: \"{}\"", c));
+        let high = digit(high)?;
+        let low = chars.next().ok_or_else(|| "invalid hexadecimal data: odd number of digits".to_string())?;
+        bytes.push(high << 4 | digit(low)?);
+    }
+    Ok(bytes)
+}
+
+fn parse_escape(text: &str) -> std::result::Result<Vec<u8>, String> {
+    let source = text.as_bytes();
+    let mut bytes = Vec::with_capacity(source.len());
+    let mut idx = 0;
+    while idx < source.len() {
+        match &source[idx..] {
+            [b'\\', b'\\', ..] => {
+                bytes.push(b'\\');
+                idx += 2;
+            }
+            [b'\\', high @ b'0'..=b'3', mid @ b'0'..=b'7', low @ b'0'..=b'7', ..] => {
+                bytes.push((high - b'0') << 6 | (mid - b'0') << 3 | (low - b'0'));
+                idx += 4;
+            }
+            [b'\\', ..] => return Err("invalid input syntax for type bytea".to_string()),
+            [byte, ..] => {
+                bytes.push(*byte);
+                idx += 1;
+            }
+            [] => break,
+        }
+    }
+    Ok(bytes)
+}
+
+fn format_hex(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        text.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+        text.push(HEX_DIGITS[(byte & 0xf) as usize] as char);
+    }
+    text
+}
+
+/// Printable ASCII as itself, a backslash doubled, other bytes in octal
+fn format_escape(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for byte in bytes {
+        match byte {
+            b'\\' => text.push_str("\\\\"),
+            0x20..=0x7e => text.push(*byte as char),
+            _ => text.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    text
+}
+
+/// Text of a byte string in the given output format
+pub fn format_bytea(bytes: &[u8], output: ByteaOutput) -> String {
+    match output {
+        ByteaOutput::Hex => format!("\\x{}", format_hex(bytes)),
+        ByteaOutput::Escape => format_escape(bytes),
+    }
+}
+
+fn format_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for (idx, chunk) in bytes.chunks(3).enumerate() {
+        if idx > 0 && idx * 4 % BASE64_LINE == 0 {
+            text.push('\n');
+        }
+        let group = chunk.iter().enumerate().fold(0u32, |group, (pos, byte)| group | (*byte as u32) << (16 - 8 * pos));
+        for pos in 0..4 {
+            if pos <= chunk.len() {
+                text.push(BASE64_DIGITS[(group >> (18 - 6 * pos) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+fn parse_base64(text: &str) -> std::result::Result<Vec<u8>, String> {
+    let invalid_symbol = |c: char| format!("invalid symbol \"{}\" found while decoding base64 sequence", c);
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut group, mut bits, mut padding) = (0u32, 0, 0);
+    for c in text.chars().filter(|c| !c.is_ascii_whitespace()) {
+        if c == '=' {
+            padding += 1;
+            continue;
+        }
+        if padding > 0 {
+            return Err(invalid_symbol(c));
+        }
+        let value = BASE64_DIGITS.iter().position(|digit| *digit as char == c).ok_or_else(|| invalid_symbol(c))?;
+        group = group << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((group >> bits) as u8);
+        }
+    }
+    if (bits + padding * 6) % 8 != 0 || padding > 2 {
+        return Err("invalid base64 end sequence".to_string());
+    }
+    Ok(bytes)
+}
+
+fn unrecognized_encoding(format: &str) -> String {
+    format!("unrecognized encoding: \"{}\"", format)
+}
+
+/// encode(bytes, format): bytes as text in `hex`, `escape` or `base64`
+pub fn encode(bytes: &[u8], format: &str) -> std::result::Result<String, String> {
+    match format.to_ascii_lowercase().as_str() {
+        "hex" => Ok(format_hex(bytes)),
+        "escape" => Ok(format_escape(bytes)),
+        "base64" => Ok(format_base64(bytes)),
+        _ => Err(unrecognized_encoding(format)),
+    }
+}
+
+/// decode(text, format): the bytes `encode` wrote as `text`
+pub fn decode(text: &str, format: &str) -> std::result::Result<Vec<u8>, String> {
+    match format.to_ascii_lowercase().as_str() {
+        "hex" => parse_hex(text),
+        "escape" => parse_escape(text),
+        "base64" => parse_base64(text),
+        _ => Err(unrecognized_encoding(format)),
+    }
+}
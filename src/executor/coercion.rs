@@ -0,0 +1,517 @@
+//! Casts between types, and the implicit conversions that resolve function
+//! and operator calls
+//!
+//! Each cast applies in a `CastContext`: implicit casts are made wherever a
+//! value of the target type is expected, assignment casts when storing into
+//! a column, and explicit casts only when written as `CAST(x AS t)` or
+//! `x::t`. Extensions add casts to and from their types with `CastExtension`.
+
+use sqlparser::ast::{BinaryOperator, DataType as SqlDataType, TimezoneInfo};
+
+use std::num::IntErrorKind;
+
+use crate::executor::{bytes, datetime, numeric};
+use crate::executor::error::{sqlstate, ExecutorError};
+use crate::executor::evaluator::{type_name, Result};
+use crate::extensions::registry::Registries;
+use crate::extensions::{CastContext, TypeCategory};
+use crate::types::{Column, DataType, Value};
+
+/// The built-in type a SQL type name stands for
+pub(crate) fn builtin_type(data_type: &SqlDataType) -> Option<DataType> {
+    match data_type {
+        SqlDataType::Int(_)
+        | SqlDataType::Int2(_)
+        | SqlDataType::Int4(_)
+        | SqlDataType::Int8(_)
+        | SqlDataType::BigInt(_)
+        | SqlDataType::SmallInt(_)
+        | SqlDataType::Integer(_) => Some(DataType::Int),
+
+        SqlDataType::Float(_)
+        | SqlDataType::Float4
+        | SqlDataType::Float8
+        | SqlDataType::Real
+        | SqlDataType::Double(_)
+        | SqlDataType::DoublePrecision => Some(DataType::Float),
+
+        // An invalid precision or scale is reported by `sql_data_type`
+        SqlDataType::Numeric(info) | SqlDataType::Decimal(info) => {
+            numeric::typmod(info).ok().map(DataType::Numeric)
+        }
+
+        SqlDataType::Varchar(_)
+        | SqlDataType::CharacterVarying(_)
+        | SqlDataType::Char(_)
+        | SqlDataType::Character(_)
+        | SqlDataType::Text
+        | SqlDataType::String(_) => Some(DataType::String),
+
+        SqlDataType::Boolean | SqlDataType::Bool => Some(DataType::Bool),
+
+        SqlDataType::Date => Some(DataType::Date),
+        SqlDataType::Time(_, TimezoneInfo::None | TimezoneInfo::WithoutTimeZone) => Some(DataType::Time),
+        SqlDataType::Timestamp(_, TimezoneInfo::None | TimezoneInfo::WithoutTimeZone) => Some(DataType::Timestamp),
+        SqlDataType::Timestamp(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => Some(DataType::TimestampTz),
+        SqlDataType::Interval { .. } => Some(DataType::Interval),
+        SqlDataType::Uuid => Some(DataType::Uuid),
+        SqlDataType::Bytea => Some(DataType::Bytea),
+        _ => None,
+    }
+}
+
+/// The type a cast target names: a built-in type, or a type registered by
+/// an extension
+pub fn sql_data_type(data_type: &SqlDataType, registries: &Registries) -> Result<DataType> {
+    if let SqlDataType::Numeric(info) | SqlDataType::Decimal(info) = data_type {
+        return numeric::typmod(info).map(DataType::Numeric);
+    }
+    if let Some(builtin) = builtin_type(data_type) {
+        return Ok(builtin);
+    }
+    let name = data_type.to_string().to_ascii_lowercase();
+    registries.types.get_by_name(&name)
+        .map(|ext| DataType::Extension {
+            type_oid: ext.type_oid(),
+            type_name: ext.type_name().to_string(),
+        })
+        .ok_or_else(|| ExecutorError::Sql(sqlstate::UNDEFINED_OBJECT, format!("type \"{}\" does not exist", name)))
+}
+
+/// Category of a type, None for the type of a bare NULL
+pub fn category(data_type: &DataType, registries: &Registries) -> Option<TypeCategory> {
+    match data_type {
+        DataType::Int | DataType::Float | DataType::Numeric(_) => Some(TypeCategory::Numeric),
+        DataType::String => Some(TypeCategory::String),
+        DataType::Bool => Some(TypeCategory::Boolean),
+        DataType::Date | DataType::Time | DataType::Timestamp | DataType::TimestampTz | DataType::Interval => {
+            Some(TypeCategory::Temporal)
+        }
+        DataType::Uuid | DataType::Bytea => Some(TypeCategory::UserDefined),
+        DataType::Null => None,
+        DataType::Extension { type_oid, .. } => Some(
+            registries.types.get_by_oid(*type_oid)
+                .map_or(TypeCategory::Extension, |ext| ext.type_category())
+        ),
+    }
+}
+
+/// Whether a type is the one its category converts to when a call could go
+/// either way, as double precision is for numbers and text for strings
+fn is_preferred(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Float | DataType::String | DataType::Bool | DataType::TimestampTz)
+}
+
+/// Weakest context a cast from `source` to `target` applies in, None when
+/// there is no such cast
+pub fn cast_context(source: &DataType, target: &DataType, registries: &Registries) -> Option<CastContext> {
+    if source == target || *source == DataType::Null {
+        return Some(CastContext::Implicit);
+    }
+    let builtin = match (source, target) {
+        (DataType::Int, DataType::Float) => Some(CastContext::Implicit),
+        (DataType::Float, DataType::Int) => Some(CastContext::Assignment),
+        // Numerics of any precision convert to one another
+        (DataType::Numeric(_), DataType::Numeric(_))
+        | (DataType::Int, DataType::Numeric(_))
+        | (DataType::Numeric(_), DataType::Float) => Some(CastContext::Implicit),
+        (DataType::Float, DataType::Numeric(_)) | (DataType::Numeric(_), DataType::Int) => Some(CastContext::Assignment),
+        (DataType::Int, DataType::Bool) | (DataType::Bool, DataType::Int) => Some(CastContext::Explicit),
+        (DataType::Int | DataType::Float | DataType::Numeric(_) | DataType::Bool, DataType::String) => {
+            Some(CastContext::Assignment)
+        }
+        (DataType::String, DataType::Int | DataType::Float | DataType::Numeric(_) | DataType::Bool) => {
+            Some(CastContext::Explicit)
+        }
+        (DataType::Date, DataType::Timestamp | DataType::TimestampTz)
+        | (DataType::Timestamp, DataType::TimestampTz)
+        | (DataType::Time, DataType::Interval) => Some(CastContext::Implicit),
+        (DataType::TimestampTz, DataType::Timestamp)
+        | (DataType::Timestamp | DataType::TimestampTz, DataType::Date | DataType::Time)
+        | (DataType::Interval, DataType::Time) => Some(CastContext::Assignment),
+        (source, DataType::String) if datetime::is_temporal_type(source) => Some(CastContext::Assignment),
+        (DataType::String, target) if datetime::is_temporal_type(target) => Some(CastContext::Explicit),
+        (DataType::Uuid | DataType::Bytea, DataType::String) => Some(CastContext::Assignment),
+        (DataType::String, DataType::Uuid | DataType::Bytea) => Some(CastContext::Explicit),
+        _ => None,
+    };
+    builtin.or_else(|| {
+        if let Some(cast) = registries.casts.find(source, target) {
+            return Some(cast.context());
+        }
+        // Every extension type has a text output function
+        match (source, target) {
+            (DataType::Extension { type_oid, .. }, DataType::String) => registries.types.get_by_oid(*type_oid)
+                .map(|_| CastContext::Assignment),
+            _ => None,
+        }
+    })
+}
+
+/// Type both `a` and `b` convert to when one result column holds values of
+/// either, as for the inputs of UNION; None when they cannot be matched
+///
+/// Numerics match whatever their precision and scale. Other types match
+/// within their category, at the type the other converts to implicitly (an
+/// integer and a numeric at numeric, a date and a timestamp at timestamp),
+/// or else at the category's preferred type.
+pub fn common_type(a: &DataType, b: &DataType, registries: &Registries) -> Option<DataType> {
+    match (a, b) {
+        (DataType::Null, other) | (other, DataType::Null) => return Some(other.clone()),
+        (DataType::Numeric(x), DataType::Numeric(y)) => {
+            return Some(DataType::Numeric(if x == y { *x } else { None }));
+        }
+        (a, b) if a == b => return Some(a.clone()),
+        (a, b) if category(a, registries) != category(b, registries) => return None,
+        _ => {}
+    }
+    let implicit = |source, target| cast_context(source, target, registries) == Some(CastContext::Implicit);
+    let common = match (implicit(a, b), implicit(b, a)) {
+        (true, false) => b,
+        (false, true) => a,
+        _ if is_preferred(a) => a,
+        _ if is_preferred(b) => b,
+        _ => return None,
+    };
+    // Mixed with another type, a numeric keeps no precision or scale
+    Some(match common {
+        DataType::Numeric(_) => DataType::Numeric(None),
+        common => common.clone(),
+    })
+}
+
+/// Convert `value` to `target` with a cast allowed in `context`
+pub fn cast(value: &Value, target: &DataType, context: CastContext, registries: &Registries) -> Result<Value> {
+    if matches!(value, Value::Null) {
+        return Ok(Value::Null);
+    }
+    let source = value_type(value, registries);
+    if cast_context(&source, target, registries).is_none_or(|allowed| allowed > context) {
+        return Err(cannot_cast(&source, target));
+    }
+
+    match (value, target) {
+        _ if source == *target => Ok(value.clone()),
+        (Value::Int(n), DataType::Float) => Ok(Value::Float(*n as f64)),
+        (Value::Float(f), DataType::Int) => {
+            // Rounds half to even, as rint() does
+            let rounded = f.round_ties_even();
+            if rounded.is_nan() || rounded < i64::MIN as f64 || rounded >= i64::MAX as f64 {
+                return Err(ExecutorError::out_of_range("bigint"));
+            }
+            Ok(Value::Int(rounded as i64))
+        }
+        // A numeric target rounds to its scale and checks its precision
+        (_, DataType::Numeric(typmod)) => {
+            let n = match value {
+                Value::Numeric(n) => n.clone(),
+                Value::Int(n) => (*n).into(),
+                Value::Float(f) => numeric::from_float(*f)?,
+                Value::String(text) => numeric::parse(text)?,
+                _ => return Err(cannot_cast(&source, target)),
+            };
+            match typmod {
+                Some((precision, scale)) => numeric::apply_typmod(&n, *precision, *scale).map(Value::Numeric),
+                None => Ok(Value::Numeric(n)),
+            }
+        }
+        (Value::Numeric(n), DataType::Float) => Ok(Value::Float(numeric::to_float(n))),
+        (Value::Numeric(n), DataType::Int) => numeric::to_int(n).map(Value::Int),
+        (Value::Int(n), DataType::Bool) => Ok(Value::Bool(*n != 0)),
+        (Value::Bool(b), DataType::Int) => Ok(Value::Int(*b as i64)),
+        (Value::Float(f), DataType::String) if f.is_infinite() => {
+            Ok(Value::String(if *f > 0.0 { "Infinity" } else { "-Infinity" }.to_string()))
+        }
+        (Value::Int(_) | Value::Float(_) | Value::Numeric(_) | Value::Bool(_) | Value::Uuid(_) | Value::Bytea(_), DataType::String) => {
+            Ok(Value::String(value.as_string()))
+        }
+        _ if datetime::is_temporal_type(&source) && *target == DataType::String => Ok(Value::String(value.as_string())),
+        _ if datetime::is_temporal_type(&source) && datetime::is_temporal_type(target) => {
+            datetime::convert(value, target).unwrap_or_else(|| Err(cannot_cast(&source, target)))
+        }
+        (Value::String(text), _) if !matches!(target, DataType::Extension { .. }) => parse_text(text, target, registries),
+        _ => {
+            if let Some(cast) = registries.casts.find(&source, target) {
+                return cast.execute(value, target).map_err(ExecutorError::Execution);
+            }
+            match value {
+                Value::Extension { type_oid, data } => registries.types.get_by_oid(*type_oid)
+                    .ok_or_else(|| cannot_cast(&source, target))?
+                    .encode_text(data.as_ref())
+                    .map(Value::String)
+                    .map_err(ExecutorError::Execution),
+                _ => Err(cannot_cast(&source, target)),
+            }
+        }
+    }
+}
+
+/// Convert a value stored into `column` to the column's type
+///
+/// A bare string literal (`literal`) is read with the type's input function,
+/// as Postgres does for literals of unknown type.
+pub fn assign(value: Value, column: &Column, literal: bool, registries: &Registries) -> Result<Value> {
+    let context = if literal { CastContext::Explicit } else { CastContext::Assignment };
+    if matches!(value, Value::Null) {
+        return Ok(value);
+    }
+    let source = value_type(&value, registries);
+    if cast_context(&source, &column.data_type, registries).is_none_or(|allowed| allowed > context) {
+        return Err(ExecutorError::Sql(sqlstate::DATATYPE_MISMATCH, format!(
+            "column \"{}\" is of type {} but expression is of type {}",
+            column.name,
+            type_name(&column.data_type),
+            type_name(&source)
+        )));
+    }
+    cast(&value, &column.data_type, context, registries)
+}
+
+/// Type a string literal takes as an operand of `op` beside an operand of
+/// type `other`, as a literal of unknown type does in Postgres
+///
+/// The literal takes the other operand's type, a numeric's without its
+/// precision and scale; beside a date/time value `datetime::literal_type`
+/// decides. None when the literal stays text: beside text or an extension
+/// type, and as an operand of `||` unless the other is bytea.
+pub fn literal_type(op: &BinaryOperator, other: &DataType) -> Option<DataType> {
+    match other {
+        DataType::String | DataType::Null | DataType::Extension { .. } => None,
+        _ if *op == BinaryOperator::StringConcat => (*other == DataType::Bytea).then(|| other.clone()),
+        DataType::Numeric(_) => Some(DataType::Numeric(None)),
+        other if datetime::is_temporal_type(other) => datetime::literal_type(op, other),
+        other => Some(other.clone()),
+    }
+}
+
+/// Read a value of a built-in type from its text form, as the type's input
+/// function does
+pub fn parse_text(text: &str, target: &DataType, registries: &Registries) -> Result<Value> {
+    let invalid = || ExecutorError::Sql(sqlstate::INVALID_TEXT_REPRESENTATION, format!(
+        "invalid input syntax for type {}: \"{}\"",
+        type_name(target),
+        text
+    ));
+    let trimmed = text.trim();
+    match target {
+        DataType::Int => trimmed.parse().map(Value::Int).map_err(|e| match e.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => ExecutorError::Sql(
+                sqlstate::NUMERIC_VALUE_OUT_OF_RANGE,
+                format!("value \"{}\" is out of range for type bigint", text),
+            ),
+            _ => invalid(),
+        }),
+        DataType::Float => {
+            let f: f64 = trimmed.parse().map_err(|_| invalid())?;
+            // Literal infinities parse; numbers too large for a double do not
+            if f.is_infinite() && !trimmed.to_ascii_lowercase().contains("inf") {
+                return Err(ExecutorError::Sql(
+                    sqlstate::NUMERIC_VALUE_OUT_OF_RANGE,
+                    format!("\"{}\" is out of range for type double precision", text),
+                ));
+            }
+            Ok(Value::Float(f))
+        }
+        DataType::Bool => {
+            let word = trimmed.to_ascii_lowercase();
+            // Any unique prefix of true, false, yes, no, on or off
+            let spells = |full: &str, shortest: usize| word.len() >= shortest && full.starts_with(word.as_str());
+            if word == "1" || spells("true", 1) || spells("yes", 1) || spells("on", 2) {
+                Ok(Value::Bool(true))
+            } else if word == "0" || spells("false", 1) || spells("no", 1) || spells("off", 2) {
+                Ok(Value::Bool(false))
+            } else {
+                Err(invalid())
+            }
+        }
+        DataType::Numeric(_) => cast(&Value::String(text.to_string()), target, CastContext::Explicit, registries),
+        DataType::Date => datetime::parse_date(text).map(Value::Date),
+        DataType::Time => datetime::parse_time(text).map(Value::Time),
+        DataType::Timestamp => datetime::parse_timestamp(text).map(Value::Timestamp),
+        DataType::TimestampTz => datetime::parse_timestamptz(text).map(Value::TimestampTz),
+        DataType::Interval => datetime::parse_interval(text).map(Value::Interval),
+        DataType::Uuid => bytes::parse_uuid(text).map(Value::Uuid),
+        DataType::Bytea => bytes::parse_bytea(text).map(Value::Bytea),
+        DataType::String | DataType::Null => Ok(Value::String(text.to_string())),
+        DataType::Extension { .. } => cast(&Value::String(text.to_string()), target, CastContext::Explicit, registries),
+    }
+}
+
+/// The key an equality lookup on a column of `column_type` probes for, None
+/// when no value of the column can equal `value`
+///
+/// Index keys are encoded from the column's own type, so a lookup for 1.0
+/// in an integer column has to probe for 1.
+pub fn equal_key(value: Value, column_type: &DataType) -> Option<Value> {
+    match (value, column_type) {
+        (Value::Float(f), DataType::Int) => {
+            (f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64).then_some(Value::Int(f as i64))
+        }
+        (Value::Int(n), DataType::Float) => Some(Value::Float(n as f64)),
+        (Value::Int(n), DataType::Numeric(_)) => Some(Value::Numeric(n.into())),
+        (Value::Float(f), DataType::Numeric(_)) => numeric::from_float(f).ok().map(Value::Numeric),
+        (Value::Numeric(n), DataType::Int) => numeric::to_int(&n).ok().filter(|_| n.is_integer()).map(Value::Int),
+        (Value::Numeric(n), DataType::Float) => Some(Value::Float(numeric::to_float(&n))),
+        (value, column_type) if value_type_of_builtin(&value) == *column_type => Some(value),
+        (value, column_type) if datetime::is_temporal(&value) && datetime::is_temporal_type(column_type) => {
+            // Only a value the column type represents exactly can match a key
+            let key = datetime::convert(&value, column_type)?.ok()?;
+            (datetime::compare(&key, &value) == Some(std::cmp::Ordering::Equal)).then_some(key)
+        }
+        (value, _) => Some(value),
+    }
+}
+
+/// A function or operator chosen for a call, with the types its arguments
+/// are converted to first
+pub struct Resolved<'a, T: ?Sized> {
+    pub target: &'a T,
+    pub arg_types: Vec<DataType>,
+    pub return_type: DataType,
+}
+
+/// Choose among `candidates` the one to call with arguments of `arg_types`
+///
+/// `accepts` gives a candidate's return type for the argument types it
+/// takes. A candidate taking the arguments as they are wins outright.
+/// Otherwise each argument may be converted by implicit casts, and the
+/// candidate keeping the most arguments as they are wins, then the one
+/// converting the most arguments to the preferred type of their category.
+/// Returns None when no candidate applies, and the `ambiguous` error when
+/// two tie.
+pub fn resolve<'a, T: ?Sized>(
+    candidates: impl Iterator<Item = &'a T>,
+    arg_types: &[DataType],
+    registries: &Registries,
+    accepts: impl Fn(&T, &[DataType]) -> Option<DataType>,
+    ambiguous: impl FnOnce() -> ExecutorError,
+) -> Result<Option<Resolved<'a, T>>> {
+    let candidates = candidates.collect::<Vec<_>>();
+    for &candidate in &candidates {
+        if let Some(return_type) = accepts(candidate, arg_types) {
+            return Ok(Some(Resolved { target: candidate, arg_types: arg_types.to_vec(), return_type }));
+        }
+    }
+
+    let targets = arg_types.iter()
+        .map(|data_type| implicit_targets(data_type, registries))
+        .collect::<Vec<_>>();
+    let mut best: Option<((usize, usize), Resolved<'a, T>)> = None;
+    let mut tied = false;
+    for combination in combinations(&targets) {
+        let score = arg_types.iter().zip(&combination).fold((0, 0), |(exact, preferred), (source, target)| {
+            if source == target {
+                (exact + 1, preferred)
+            } else if is_preferred(target) && category(source, registries) == category(target, registries) {
+                (exact, preferred + 1)
+            } else {
+                (exact, preferred)
+            }
+        });
+        for &candidate in &candidates {
+            let Some(return_type) = accepts(candidate, &combination) else {
+                continue;
+            };
+            match &best {
+                Some((best_score, _)) if *best_score > score => {}
+                Some((best_score, _)) if *best_score == score => tied = true,
+                _ => {
+                    tied = false;
+                    best = Some((score, Resolved { target: candidate, arg_types: combination.clone(), return_type }));
+                }
+            }
+        }
+    }
+    if tied {
+        return Err(ambiguous());
+    }
+    Ok(best.map(|(_, resolved)| resolved))
+}
+
+/// Types a value of `source` converts to implicitly, other than itself
+fn implicit_targets(source: &DataType, registries: &Registries) -> Vec<DataType> {
+    if *source == DataType::Null {
+        return vec![DataType::Null];
+    }
+    let extension_types = registries.types.iter().map(|ext| DataType::Extension {
+        type_oid: ext.type_oid(),
+        type_name: ext.type_name().to_string(),
+    });
+    let mut targets = vec![source.clone()];
+    targets.extend(
+        [
+            DataType::Int,
+            DataType::Float,
+            DataType::Numeric(None),
+            DataType::String,
+            DataType::Bool,
+            DataType::Date,
+            DataType::Time,
+            DataType::Timestamp,
+            DataType::TimestampTz,
+            DataType::Interval,
+            DataType::Uuid,
+            DataType::Bytea,
+        ].into_iter()
+            .chain(extension_types)
+            .filter(|target| target != source && cast_context(source, target, registries) == Some(CastContext::Implicit))
+    );
+    targets
+}
+
+/// Every choice of one type from each of `targets`, the first choice
+/// (the argument types themselves) excluded
+fn combinations(targets: &[Vec<DataType>]) -> Vec<Vec<DataType>> {
+    let mut combinations = vec![Vec::new()];
+    for choices in targets {
+        combinations = combinations.into_iter()
+            .flat_map(|prefix: Vec<DataType>| choices.iter().map(move |choice| {
+                let mut combination = prefix.clone();
+                combination.push(choice.clone());
+                combination
+            }))
+            .collect();
+    }
+    combinations.remove(0);
+    combinations
+}
+
+/// Type of a value, naming extension types as their registry does
+pub fn value_type(value: &Value, registries: &Registries) -> DataType {
+    match value {
+        Value::Extension { type_oid, .. } => DataType::Extension {
+            type_oid: *type_oid,
+            type_name: registries.types.get_by_oid(*type_oid)
+                .map(|ext| ext.type_name().to_string())
+                .unwrap_or_default(),
+        },
+        value => value_type_of_builtin(value),
+    }
+}
+
+fn value_type_of_builtin(value: &Value) -> DataType {
+    match value {
+        Value::Null => DataType::Null,
+        Value::Int(_) => DataType::Int,
+        Value::Float(_) => DataType::Float,
+        Value::String(_) => DataType::String,
+        Value::Bool(_) => DataType::Bool,
+        Value::Numeric(_) => DataType::Numeric(None),
+        Value::Date(_) => DataType::Date,
+        Value::Time(_) => DataType::Time,
+        Value::Timestamp(_) => DataType::Timestamp,
+        Value::TimestampTz(_) => DataType::TimestampTz,
+        Value::Interval(_) => DataType::Interval,
+        Value::Uuid(_) => DataType::Uuid,
+        Value::Bytea(_) => DataType::Bytea,
+        Value::Extension { type_oid, .. } => DataType::Extension { type_oid: *type_oid, type_name: String::new() },
+    }
+}
+
+pub(crate) fn cannot_cast(source: &DataType, target: &DataType) -> ExecutorError {
+    ExecutorError::Sql(sqlstate::CANNOT_COERCE, format!(
+        "cannot cast type {} to {}",
+        type_name(source),
+        type_name(target)
+    ))
+}
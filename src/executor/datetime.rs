@@ -0,0 +1,1077 @@
+//! Dates, times, timestamps and intervals
+//!
+//! Values are kept as Postgres keeps them: a date is a count of days and a
+//! time or timestamp a count of microseconds, timestamps counting from
+//! 2000-01-01 00:00:00. Timestamps with time zone hold the UTC instant.
+//!
+//! The session time zone is UTC: timestamps with time zone print with the
+//! offset `+00`, and convert to and from timestamps without time zone
+//! unchanged. `AT TIME ZONE` converts through any other zone.
+
+use bigdecimal::BigDecimal;
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeDelta, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use sqlparser::ast::BinaryOperator;
+
+use std::cmp::Ordering;
+use std::sync::LazyLock;
+
+use crate::executor::error::{sqlstate, ExecutorError};
+use crate::executor::evaluator::Result;
+use crate::types::{DataType, Interval, Value};
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_MINUTE: i64 = 60 * MICROS_PER_SECOND;
+const MICROS_PER_HOUR: i64 = 60 * MICROS_PER_MINUTE;
+const MICROS_PER_DAY: i64 = Interval::MICROS_PER_DAY;
+/// Days from the Unix epoch, 1970-01-01, to 2000-01-01
+const UNIX_EPOCH_DAYS: i64 = 10_957;
+
+fn epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 1).expect("valid date").and_time(NaiveTime::MIN)
+}
+
+fn to_date(days: i32) -> Option<NaiveDate> {
+    epoch().date().checked_add_signed(TimeDelta::days(days as i64))
+}
+
+fn from_date(date: NaiveDate) -> Result<i32> {
+    i32::try_from((date - epoch().date()).num_days()).map_err(|_| out_of_range("date"))
+}
+
+fn to_datetime(micros: i64) -> Option<NaiveDateTime> {
+    epoch().checked_add_signed(TimeDelta::microseconds(micros))
+}
+
+fn from_datetime(datetime: NaiveDateTime) -> Result<i64> {
+    (datetime - epoch()).num_microseconds().ok_or_else(|| out_of_range("timestamp"))
+}
+
+fn out_of_range(type_name: &str) -> ExecutorError {
+    ExecutorError::Sql(sqlstate::DATETIME_FIELD_OVERFLOW, format!("{} out of range", type_name))
+}
+
+/// Whether a value is a date, time, timestamp or interval
+pub fn is_temporal(value: &Value) -> bool {
+    matches!(value, Value::Date(_) | Value::Time(_) | Value::Timestamp(_) | Value::TimestampTz(_) | Value::Interval(_))
+}
+
+/// Whether a type is date, time, timestamp or interval
+pub fn is_temporal_type(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Date | DataType::Time | DataType::Timestamp | DataType::TimestampTz | DataType::Interval)
+}
+
+/// The current time, as a timestamp with time zone
+pub fn now() -> Value {
+    Value::TimestampTz((Utc::now().naive_utc() - epoch()).num_microseconds().unwrap_or(i64::MAX))
+}
+
+// Output
+
+/// Year as Postgres prints it, with the era suffix: year 0 is 1 BC
+fn year_and_era(year: i32) -> (i32, &'static str) {
+    if year <= 0 { (1 - year, " BC") } else { (year, "") }
+}
+
+fn format_fraction(micros: i64) -> String {
+    if micros == 0 {
+        return String::new();
+    }
+    format!(".{:06}", micros).trim_end_matches('0').to_string()
+}
+
+pub fn format_date(days: i32) -> String {
+    match to_date(days) {
+        Some(date) => {
+            let (year, era) = year_and_era(date.year());
+            format!("{:04}-{:02}-{:02}{}", year, date.month(), date.day(), era)
+        }
+        None => "date out of range".to_string(),
+    }
+}
+
+pub fn format_time(micros: i64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}",
+        micros / MICROS_PER_HOUR,
+        micros % MICROS_PER_HOUR / MICROS_PER_MINUTE,
+        micros % MICROS_PER_MINUTE / MICROS_PER_SECOND,
+        format_fraction(micros % MICROS_PER_SECOND)
+    )
+}
+
+fn format_datetime(micros: i64, offset: &str) -> String {
+    match to_datetime(micros) {
+        Some(datetime) => {
+            let (year, era) = year_and_era(datetime.year());
+            format!(
+                "{:04}-{:02}-{:02} {}{}{}",
+                year,
+                datetime.month(),
+                datetime.day(),
+                format_time(micros.rem_euclid(MICROS_PER_DAY)),
+                offset,
+                era
+            )
+        }
+        None => "timestamp out of range".to_string(),
+    }
+}
+
+pub fn format_timestamp(micros: i64) -> String {
+    format_datetime(micros, "")
+}
+
+pub fn format_timestamptz(micros: i64) -> String {
+    format_datetime(micros, "+00")
+}
+
+/// Interval in Postgres' output style, e.g. `1 year 2 mons -3 days 04:05:06`
+///
+/// Once a part is negative, the positive parts after it carry a `+`.
+pub fn format_interval(interval: &Interval) -> String {
+    let mut parts = Vec::new();
+    let mut negative = false;
+    let units = [
+        (interval.months as i64 / 12, "year"),
+        (interval.months as i64 % 12, "mon"),
+        (interval.days as i64, "day"),
+    ];
+    for (count, unit) in units {
+        if count == 0 {
+            continue;
+        }
+        let sign = if count > 0 && negative { "+" } else { "" };
+        let plural = if count == 1 { "" } else { "s" };
+        parts.push(format!("{}{} {}{}", sign, count, unit, plural));
+        negative |= count < 0;
+    }
+    if interval.micros != 0 || parts.is_empty() {
+        let sign = match interval.micros {
+            micros if micros < 0 => "-",
+            _ if negative => "+",
+            _ => "",
+        };
+        let micros = interval.micros.unsigned_abs();
+        parts.push(format!(
+            "{}{:02}:{:02}:{:02}{}",
+            sign,
+            micros / MICROS_PER_HOUR as u64,
+            micros % MICROS_PER_HOUR as u64 / MICROS_PER_MINUTE as u64,
+            micros % MICROS_PER_MINUTE as u64 / MICROS_PER_SECOND as u64,
+            format_fraction((micros % MICROS_PER_SECOND as u64) as i64)
+        ));
+    }
+    parts.join(" ")
+}
+
+/// A value in the protocol's binary format, which is the stored form
+/// in network byte order (intervals as microseconds, days, months)
+pub fn encode_binary(value: &Value) -> Option<Vec<u8>> {
+    match value {
+        Value::Date(days) => Some(days.to_be_bytes().to_vec()),
+        Value::Time(micros) | Value::Timestamp(micros) | Value::TimestampTz(micros) => Some(micros.to_be_bytes().to_vec()),
+        Value::Interval(interval) => {
+            let mut bytes = interval.micros.to_be_bytes().to_vec();
+            bytes.extend(interval.days.to_be_bytes());
+            bytes.extend(interval.months.to_be_bytes());
+            Some(bytes)
+        }
+        _ => None,
+    }
+}
+
+/// Read a value of a date/time type from the protocol's binary format
+pub fn decode_binary(bytes: &[u8], data_type: &DataType) -> Option<Value> {
+    let micros = || bytes.try_into().ok().map(i64::from_be_bytes);
+    match data_type {
+        DataType::Date => bytes.try_into().ok()
+            .map(i32::from_be_bytes)
+            .filter(|days| to_date(*days).is_some())
+            .map(Value::Date),
+        DataType::Time => micros().filter(|micros| (0..MICROS_PER_DAY).contains(micros)).map(Value::Time),
+        DataType::Timestamp => micros().filter(|micros| to_datetime(*micros).is_some()).map(Value::Timestamp),
+        DataType::TimestampTz => micros().filter(|micros| to_datetime(*micros).is_some()).map(Value::TimestampTz),
+        DataType::Interval if bytes.len() == 16 => Some(Value::Interval(Interval {
+            micros: i64::from_be_bytes(bytes[0..8].try_into().ok()?),
+            days: i32::from_be_bytes(bytes[8..12].try_into().ok()?),
+            months: i32::from_be_bytes(bytes[12..16].try_into().ok()?),
+        })),
+        _ => None,
+    }
+}
+
+// Input
+
+/// `date [time] [zone]`, the era split off first
+static TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| Regex::new(
+    r"(?i)^(\d{1,7})-(\d{1,2})-(\d{1,2})(?:(?:\s+|T)(\d{1,2}:\d{1,2}(?::\d{1,2}(?:\.\d+)?)?))?(?:\s*(z|[+-]\d{1,2}(?::?\d{2}){0,2})|\s+([a-z][a-z0-9_/+-]*))?$"
+).expect("valid regex"));
+
+static TIME: LazyLock<Regex> = LazyLock::new(|| Regex::new(
+    r"^(\d{1,2}):(\d{1,2})(?::(\d{1,2})(?:\.(\d+))?)?$"
+).expect("valid regex"));
+
+/// A timestamp as written, before its zone is applied
+struct Parsed {
+    datetime: NaiveDateTime,
+    zone: Option<Zone>,
+}
+
+fn invalid(type_name: &str, text: &str) -> ExecutorError {
+    ExecutorError::Sql(sqlstate::INVALID_DATETIME_FORMAT, format!(
+        "invalid input syntax for type {}: \"{}\"",
+        type_name,
+        text
+    ))
+}
+
+fn field_out_of_range(text: &str) -> ExecutorError {
+    ExecutorError::Sql(sqlstate::DATETIME_FIELD_OVERFLOW, format!(
+        "date/time field value out of range: \"{}\"",
+        text
+    ))
+}
+
+/// Microseconds since midnight of `hh:mm[:ss[.ffffff]]`, None if not a time
+fn parse_time_of_day(text: &str) -> Option<std::result::Result<i64, ()>> {
+    let captures = TIME.captures(text)?;
+    let number = |idx: usize| captures.get(idx).map_or(Some(0), |m| m.as_str().parse::<i64>().ok());
+    let (Some(hour), Some(minute), Some(second)) = (number(1), number(2), number(3)) else {
+        return Some(Err(()));
+    };
+    // Fractions past microseconds are rounded away
+    let fraction = captures.get(4).map_or(0, |m| {
+        (format!("0.{}", m.as_str()).parse::<f64>().unwrap_or(0.0) * MICROS_PER_SECOND as f64).round() as i64
+    });
+    let micros = hour * MICROS_PER_HOUR + minute * MICROS_PER_MINUTE + second * MICROS_PER_SECOND + fraction;
+    // 24:00:00 is the end of the day, as Postgres allows
+    if minute > 59 || second > 59 || micros > MICROS_PER_DAY {
+        return Some(Err(()));
+    }
+    Some(Ok(micros))
+}
+
+/// Split an `AD`/`BC` suffix off a date
+fn strip_era(text: &str) -> (&str, bool) {
+    let lower = text.to_ascii_lowercase();
+    match lower.strip_suffix("bc").or_else(|| lower.strip_suffix("ad")) {
+        Some(rest) if rest.ends_with(char::is_whitespace) => (text[..rest.len()].trim_end(), lower.ends_with("bc")),
+        _ => (text, false),
+    }
+}
+
+fn parse_datetime(text: &str, type_name: &str) -> Result<Parsed> {
+    let trimmed = text.trim();
+    let today = || Utc::now().date_naive().and_time(NaiveTime::MIN);
+    let special = match trimmed.to_ascii_lowercase().as_str() {
+        "epoch" => Some(NaiveDate::from_ymd_opt(1970, 1, 1).expect("valid date").and_time(NaiveTime::MIN)),
+        "now" => Some(Utc::now().naive_utc()),
+        "today" => Some(today()),
+        "tomorrow" => today().checked_add_signed(TimeDelta::days(1)),
+        "yesterday" => today().checked_sub_signed(TimeDelta::days(1)),
+        _ => None,
+    };
+    if let Some(datetime) = special {
+        return Ok(Parsed { datetime, zone: Some(Zone::Fixed(0)) });
+    }
+
+    let (trimmed, bc) = strip_era(trimmed);
+    let captures = TIMESTAMP.captures(trimmed).ok_or_else(|| invalid(type_name, text))?;
+    let number = |idx: usize| captures[idx].parse::<u32>().map_err(|_| field_out_of_range(text));
+    let mut year = number(1)? as i32;
+    if bc {
+        year = 1 - year;
+    }
+    let date = NaiveDate::from_ymd_opt(year, number(2)?, number(3)?).ok_or_else(|| field_out_of_range(text))?;
+    let micros = match captures.get(4) {
+        Some(time) => parse_time_of_day(time.as_str())
+            .ok_or_else(|| invalid(type_name, text))?
+            .map_err(|_| field_out_of_range(text))?,
+        None => 0,
+    };
+    let zone = match captures.get(5).or_else(|| captures.get(6)) {
+        Some(zone) => Some(parse_zone(zone.as_str(), false).ok_or_else(|| ExecutorError::Sql(
+            sqlstate::INVALID_PARAMETER_VALUE,
+            format!("time zone \"{}\" not recognized", zone.as_str()),
+        ))?),
+        None => None,
+    };
+    let datetime = date.and_time(NaiveTime::MIN)
+        .checked_add_signed(TimeDelta::microseconds(micros))
+        .ok_or_else(|| field_out_of_range(text))?;
+    Ok(Parsed { datetime, zone })
+}
+
+pub fn parse_date(text: &str) -> Result<i32> {
+    let parsed = parse_datetime(text, "date")?;
+    from_date(parsed.datetime.date())
+}
+
+pub fn parse_time(text: &str) -> Result<i64> {
+    let trimmed = text.trim();
+    match parse_time_of_day(trimmed) {
+        Some(Ok(micros)) => Ok(micros % MICROS_PER_DAY),
+        Some(Err(())) => Err(field_out_of_range(text)),
+        // The time of a full timestamp
+        None => {
+            let parsed = parse_datetime(trimmed, "time without time zone")?;
+            Ok(parsed.datetime.num_seconds_from_midnight() as i64 * MICROS_PER_SECOND
+                + (parsed.datetime.nanosecond() / 1000) as i64)
+        }
+    }
+}
+
+/// A timestamp without time zone; a zone written after it is ignored, as
+/// Postgres ignores it
+pub fn parse_timestamp(text: &str) -> Result<i64> {
+    from_datetime(parse_datetime(text, "timestamp")?.datetime)
+}
+
+pub fn parse_timestamptz(text: &str) -> Result<i64> {
+    let parsed = parse_datetime(text, "timestamp with time zone")?;
+    let utc = match parsed.zone {
+        Some(zone) => zone.to_utc(parsed.datetime),
+        None => parsed.datetime,
+    };
+    from_datetime(utc).map_err(|_| out_of_range("timestamp"))
+}
+
+/// An interval in Postgres' input style: quantities with units, as in
+/// `1 year 2 months 3 days`, and a time of day `hh:mm:ss` for the rest,
+/// optionally led by `@` and followed by `ago`
+pub fn parse_interval(text: &str) -> Result<Interval> {
+    let invalid = || invalid("interval", text);
+    let lower = text.trim().to_ascii_lowercase();
+    let mut tokens = lower.trim_start_matches('@').split_whitespace().peekable();
+    let mut builder = IntervalBuilder::default();
+    let mut ago = false;
+    let mut seen = false;
+
+    while let Some(token) = tokens.next() {
+        if ago {
+            return Err(invalid());
+        }
+        if token == "ago" && seen {
+            ago = true;
+            continue;
+        }
+        seen = true;
+
+        // A time of day: [+-]hh:mm[:ss[.ffffff]]
+        if token.contains(':') {
+            let (negative, time) = match token.strip_prefix('-') {
+                Some(time) => (true, time),
+                None => (false, token.strip_prefix('+').unwrap_or(token)),
+            };
+            let captures = TIME.captures(time).ok_or_else(invalid)?;
+            let number = |idx: usize| captures.get(idx).map_or(Ok(0.0), |m| m.as_str().parse::<f64>().map_err(|_| invalid()));
+            let seconds = number(1)? * 3600.0 + number(2)? * 60.0 + number(3)?
+                + captures.get(4).map_or(Ok(0.0), |m| format!("0.{}", m.as_str()).parse::<f64>().map_err(|_| invalid()))?;
+            builder.add(if negative { -seconds } else { seconds }, Unit::Micros(MICROS_PER_SECOND))
+                .ok_or_else(interval_out_of_range)?;
+            continue;
+        }
+
+        // A quantity, with its unit attached or as the next word
+        let split = token.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(token.len());
+        let (number, attached) = token.split_at(split);
+        let quantity = number.parse::<f64>().map_err(|_| invalid())?;
+        let unit = match attached {
+            "" => match tokens.peek().and_then(|word| unit(word)) {
+                Some(unit) => {
+                    tokens.next();
+                    unit
+                }
+                // A bare number counts seconds
+                None => Unit::Micros(MICROS_PER_SECOND),
+            },
+            attached => unit(attached).ok_or_else(invalid)?,
+        };
+        builder.add(quantity, unit).ok_or_else(interval_out_of_range)?;
+    }
+    if !seen {
+        return Err(invalid());
+    }
+    let interval = builder.finish().ok_or_else(interval_out_of_range)?;
+    Ok(if ago { negate_interval(&interval)? } else { interval })
+}
+
+/// Interval text with a unit from `INTERVAL '3' DAY`, where the quantity
+/// is written without one
+pub fn parse_interval_with_unit(text: &str, unit: &str) -> Result<Interval> {
+    if text.trim().parse::<f64>().is_ok() {
+        return parse_interval(&format!("{} {}", text.trim(), unit));
+    }
+    parse_interval(text)
+}
+
+fn interval_out_of_range() -> ExecutorError {
+    out_of_range("interval")
+}
+
+/// What an interval unit counts
+#[derive(Clone, Copy)]
+enum Unit {
+    Months(i64),
+    Days(i64),
+    Micros(i64),
+}
+
+fn unit(word: &str) -> Option<Unit> {
+    let word = word.trim_end_matches(',');
+    Some(match word {
+        "microsecond" | "microseconds" | "us" | "usec" | "usecs" => Unit::Micros(1),
+        "millisecond" | "milliseconds" | "ms" | "msec" | "msecs" => Unit::Micros(1000),
+        "second" | "seconds" | "sec" | "secs" | "s" => Unit::Micros(MICROS_PER_SECOND),
+        "minute" | "minutes" | "min" | "mins" | "m" => Unit::Micros(MICROS_PER_MINUTE),
+        "hour" | "hours" | "hr" | "hrs" | "h" => Unit::Micros(MICROS_PER_HOUR),
+        "day" | "days" | "d" => Unit::Days(1),
+        "week" | "weeks" | "w" => Unit::Days(7),
+        "month" | "months" | "mon" | "mons" => Unit::Months(1),
+        "year" | "years" | "yr" | "yrs" | "y" => Unit::Months(12),
+        "decade" | "decades" => Unit::Months(120),
+        "century" | "centuries" => Unit::Months(1200),
+        "millennium" | "millennia" => Unit::Months(12000),
+        _ => return None,
+    })
+}
+
+/// Sums the parts of an interval, carrying fractions of months into days
+/// (a month being 30 days) and fractions of days into microseconds
+#[derive(Default)]
+struct IntervalBuilder {
+    months: f64,
+    days: f64,
+    micros: f64,
+}
+
+impl IntervalBuilder {
+    fn add(&mut self, quantity: f64, unit: Unit) -> Option<()> {
+        match unit {
+            Unit::Months(factor) => {
+                let months = quantity * factor as f64;
+                self.months += months.trunc();
+                self.add(months.fract() * 30.0, Unit::Days(1))?;
+            }
+            Unit::Days(factor) => {
+                let days = quantity * factor as f64;
+                self.days += days.trunc();
+                self.micros += days.fract() * MICROS_PER_DAY as f64;
+            }
+            Unit::Micros(factor) => self.micros += quantity * factor as f64,
+        }
+        (self.months.is_finite() && self.days.is_finite() && self.micros.is_finite()).then_some(())
+    }
+
+    fn finish(self) -> Option<Interval> {
+        let micros = self.micros.round();
+        if self.months.abs() > i32::MAX as f64 || self.days.abs() > i32::MAX as f64 || micros.abs() >= i64::MAX as f64 {
+            return None;
+        }
+        Some(Interval { months: self.months as i32, days: self.days as i32, micros: micros as i64 })
+    }
+}
+
+/// A time zone named in a timestamp or by `AT TIME ZONE`
+#[derive(Clone, Copy)]
+pub enum Zone {
+    /// Seconds east of UTC
+    Fixed(i32),
+    Named(Tz),
+}
+
+/// Read a time zone: `UTC`, `Z`, a name such as `Europe/Paris`, or an
+/// offset such as `+05:30`
+///
+/// `AT TIME ZONE` reads offsets the POSIX way (`posix`), counting hours west
+/// of UTC: there `'+05'` is five hours behind UTC, as in Postgres.
+pub fn parse_zone(text: &str, posix: bool) -> Option<Zone> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("z") || text.eq_ignore_ascii_case("utc") || text.eq_ignore_ascii_case("gmt") {
+        return Some(Zone::Fixed(0));
+    }
+    if let Some(sign) = text.chars().next().filter(|c| matches!(c, '+' | '-')) {
+        let digits = text[1..].replace(':', "");
+        if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        // hh, hhmm or hhmmss; a single digit is an hour
+        let digits = if digits.len() % 2 == 1 { format!("0{}", digits) } else { digits };
+        let part = |idx: usize| digits.get(idx..idx + 2).map_or(0, |part| part.parse::<i32>().unwrap_or(0));
+        let (hours, minutes, seconds) = (part(0), part(2), part(4));
+        if hours > 15 || minutes > 59 || seconds > 59 {
+            return None;
+        }
+        let east = hours * 3600 + minutes * 60 + seconds;
+        let east = if (sign == '-') != posix { -east } else { east };
+        return Some(Zone::Fixed(east));
+    }
+    Tz::from_str_insensitive(text).ok().map(Zone::Named)
+}
+
+impl Zone {
+    /// UTC instant of a local time in this zone
+    ///
+    /// A local time skipped by a daylight saving change is read with the
+    /// offset in effect before it; one repeated by a change is the earlier.
+    fn to_utc(self, local: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Zone::Fixed(east) => local - TimeDelta::seconds(east as i64),
+            Zone::Named(tz) => match tz.from_local_datetime(&local).earliest() {
+                Some(datetime) => datetime.naive_utc(),
+                None => local - TimeDelta::seconds(tz.offset_from_utc_datetime(&local).fix().local_minus_utc() as i64),
+            },
+        }
+    }
+
+    /// Local time in this zone of a UTC instant
+    fn local_time(self, utc: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Zone::Fixed(east) => utc + TimeDelta::seconds(east as i64),
+            Zone::Named(tz) => tz.from_utc_datetime(&utc).naive_local(),
+        }
+    }
+}
+
+/// `timestamp AT TIME ZONE zone`: a timestamp without time zone is read as
+/// local time in `zone`, giving a timestamp with time zone, and one with
+/// time zone gives the local time in `zone`
+pub fn at_time_zone(value: &Value, zone: &Value) -> Result<Value> {
+    let zone = match zone {
+        Value::String(name) => parse_zone(name, true).ok_or_else(|| ExecutorError::Sql(
+            sqlstate::INVALID_PARAMETER_VALUE,
+            format!("time zone \"{}\" not recognized", name),
+        ))?,
+        Value::Interval(interval) if interval.months == 0 && interval.days == 0 => {
+            Zone::Fixed((interval.micros / MICROS_PER_SECOND) as i32)
+        }
+        Value::Interval(interval) => {
+            return Err(ExecutorError::Sql(sqlstate::INVALID_PARAMETER_VALUE, format!(
+                "interval time zone \"{}\" must not include months or days",
+                format_interval(interval)
+            )));
+        }
+        _ => return Err(ExecutorError::Execution("time zone must be text or an interval".to_string())),
+    };
+    match value {
+        Value::Timestamp(micros) => {
+            let local = to_datetime(*micros).ok_or_else(|| out_of_range("timestamp"))?;
+            from_datetime(zone.to_utc(local)).map(Value::TimestampTz)
+        }
+        Value::TimestampTz(micros) => {
+            let utc = to_datetime(*micros).ok_or_else(|| out_of_range("timestamp"))?;
+            from_datetime(zone.local_time(utc)).map(Value::Timestamp)
+        }
+        _ => Err(ExecutorError::Execution("AT TIME ZONE needs a timestamp".to_string())),
+    }
+}
+
+// Casts
+
+/// Convert between date/time types, None when there is no such cast
+///
+/// With the session in UTC, timestamps with and without time zone convert
+/// unchanged.
+pub fn convert(value: &Value, target: &DataType) -> Option<Result<Value>> {
+    let converted = match (value, target) {
+        (Value::Date(days), DataType::Timestamp) => date_micros(*days).map(Value::Timestamp),
+        (Value::Date(days), DataType::TimestampTz) => date_micros(*days).map(Value::TimestampTz),
+        (Value::Timestamp(micros) | Value::TimestampTz(micros), DataType::Date) => {
+            i32::try_from(micros.div_euclid(MICROS_PER_DAY)).map(Value::Date).map_err(|_| out_of_range("date"))
+        }
+        (Value::Timestamp(micros) | Value::TimestampTz(micros), DataType::Time) => {
+            Ok(Value::Time(micros.rem_euclid(MICROS_PER_DAY)))
+        }
+        (Value::Timestamp(micros), DataType::TimestampTz) => Ok(Value::TimestampTz(*micros)),
+        (Value::TimestampTz(micros), DataType::Timestamp) => Ok(Value::Timestamp(*micros)),
+        (Value::Time(micros), DataType::Interval) => Ok(Value::Interval(Interval { months: 0, days: 0, micros: *micros })),
+        (Value::Interval(interval), DataType::Time) => Ok(Value::Time(interval.micros.rem_euclid(MICROS_PER_DAY))),
+        _ => return None,
+    };
+    Some(converted)
+}
+
+fn date_micros(days: i32) -> Result<i64> {
+    (days as i64).checked_mul(MICROS_PER_DAY).ok_or_else(|| out_of_range("timestamp"))
+}
+
+// Operators
+
+/// Microseconds since 2000-01-01 of a date or timestamp, by which the two
+/// compare (a date being its midnight)
+fn instant(value: &Value) -> Option<i128> {
+    match value {
+        Value::Date(days) => Some(*days as i128 * MICROS_PER_DAY as i128),
+        Value::Timestamp(micros) | Value::TimestampTz(micros) => Some(*micros as i128),
+        _ => None,
+    }
+}
+
+/// Order two date/time values, None when they do not compare
+pub fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Time(a), Value::Time(b)) => Some(a.cmp(b)),
+        (Value::Interval(a), Value::Interval(b)) => Some(a.span().cmp(&b.span())),
+        _ => Some(instant(left)?.cmp(&instant(right)?)),
+    }
+}
+
+/// Type a string literal takes as the operand of `op` beside an operand of
+/// type `other`, as a literal of unknown type does in Postgres
+///
+/// A literal compared with or subtracted from a date/time value has its
+/// type; one added to it is an interval. None when `other` is no date/time
+/// type.
+pub fn literal_type(op: &BinaryOperator, other: &DataType) -> Option<DataType> {
+    use BinaryOperator::*;
+
+    if !is_temporal_type(other) {
+        return None;
+    }
+    match op {
+        Eq | NotEq | Lt | LtEq | Gt | GtEq | Minus => Some(other.clone()),
+        Plus => Some(DataType::Interval),
+        _ => None,
+    }
+}
+
+/// Result type of `left op right` on date/time operands, None when the
+/// operator does not apply to them
+pub fn result_type(op: &BinaryOperator, left: &DataType, right: &DataType) -> Option<DataType> {
+    use BinaryOperator::*;
+    use DataType::*;
+
+    // String literals take their type from the other operand
+    let (left, right) = match (left, right) {
+        (String, right) => (literal_type(op, right)?, right.clone()),
+        (left, String) => (left.clone(), literal_type(op, left)?),
+        (left, right) => (left.clone(), right.clone()),
+    };
+    if !is_temporal_type(&left) && !is_temporal_type(&right) {
+        return None;
+    }
+    let point = |data_type: &DataType| matches!(data_type, Date | Timestamp | TimestampTz);
+    // The type of a date or timestamp with an interval or time added
+    let moved = |data_type: &DataType| match data_type {
+        TimestampTz => TimestampTz,
+        _ => Timestamp,
+    };
+    match (op, &left, &right) {
+        (Eq | NotEq | Lt | LtEq | Gt | GtEq, left, right) if point(left) && point(right) => Some(Bool),
+        (Eq | NotEq | Lt | LtEq | Gt | GtEq, Time, Time) | (Eq | NotEq | Lt | LtEq | Gt | GtEq, Interval, Interval) => Some(Bool),
+        (Plus, Date, Int) | (Plus, Int, Date) | (Minus, Date, Int) => Some(Date),
+        (Plus, point @ (Date | Timestamp | TimestampTz), Interval)
+        | (Plus, Interval, point @ (Date | Timestamp | TimestampTz))
+        | (Minus, point @ (Date | Timestamp | TimestampTz), Interval) => Some(moved(point)),
+        (Plus, Date, Time) | (Plus, Time, Date) => Some(Timestamp),
+        (Plus, Time, Interval) | (Plus, Interval, Time) | (Minus, Time, Interval) => Some(Time),
+        (Minus, Date, Date) => Some(Int),
+        (Minus, left, right) if point(left) && point(right) => Some(Interval),
+        (Minus, Time, Time) | (Plus | Minus, Interval, Interval) => Some(Interval),
+        (Multiply, Interval, Int | Float) | (Multiply, Int | Float, Interval) | (Divide, Interval, Int | Float) => Some(Interval),
+        _ => None,
+    }
+}
+
+/// Apply a built-in operator to non-NULL operands, at least one a date/time
+/// value; None when the operator does not apply to them
+pub fn eval_op(left: &Value, op: &BinaryOperator, right: &Value) -> Option<Result<Value>> {
+    use BinaryOperator::*;
+
+    if matches!(op, Eq | NotEq | Lt | LtEq | Gt | GtEq) {
+        let ordering = compare(left, right)?;
+        return Some(Ok(Value::Bool(match op {
+            Eq => ordering.is_eq(),
+            NotEq => ordering.is_ne(),
+            Lt => ordering.is_lt(),
+            LtEq => ordering.is_le(),
+            Gt => ordering.is_gt(),
+            _ => ordering.is_ge(),
+        })));
+    }
+
+    let result = match (op, left, right) {
+        (Plus, Value::Date(days), Value::Int(n)) | (Plus, Value::Int(n), Value::Date(days)) => add_days(*days, *n),
+        (Minus, Value::Date(days), Value::Int(n)) => match n.checked_neg() {
+            Some(n) => add_days(*days, n),
+            None => Err(out_of_range("date")),
+        },
+        (Minus, Value::Date(a), Value::Date(b)) => Ok(Value::Int(*a as i64 - *b as i64)),
+
+        (Plus, Value::Date(days), Value::Interval(interval)) | (Plus, Value::Interval(interval), Value::Date(days)) => {
+            date_micros(*days).and_then(|micros| add_interval(micros, interval)).map(Value::Timestamp)
+        }
+        (Minus, Value::Date(days), Value::Interval(interval)) => date_micros(*days)
+            .and_then(|micros| add_interval(micros, &negate_interval(interval)?))
+            .map(Value::Timestamp),
+        (Plus, Value::Date(days), Value::Time(time)) | (Plus, Value::Time(time), Value::Date(days)) => date_micros(*days)
+            .and_then(|micros| micros.checked_add(*time).ok_or_else(|| out_of_range("timestamp")))
+            .map(Value::Timestamp),
+
+        (Plus, Value::Timestamp(micros), Value::Interval(interval))
+        | (Plus, Value::Interval(interval), Value::Timestamp(micros)) => add_interval(*micros, interval).map(Value::Timestamp),
+        (Plus, Value::TimestampTz(micros), Value::Interval(interval))
+        | (Plus, Value::Interval(interval), Value::TimestampTz(micros)) => add_interval(*micros, interval).map(Value::TimestampTz),
+        (Minus, Value::Timestamp(micros), Value::Interval(interval)) => {
+            negate_interval(interval).and_then(|interval| add_interval(*micros, &interval)).map(Value::Timestamp)
+        }
+        (Minus, Value::TimestampTz(micros), Value::Interval(interval)) => {
+            negate_interval(interval).and_then(|interval| add_interval(*micros, &interval)).map(Value::TimestampTz)
+        }
+        (Minus, left @ (Value::Date(_) | Value::Timestamp(_) | Value::TimestampTz(_)), right @ (Value::Timestamp(_) | Value::TimestampTz(_)))
+        | (Minus, left @ (Value::Timestamp(_) | Value::TimestampTz(_)), right @ Value::Date(_)) => {
+            let difference = instant(left)? - instant(right)?;
+            i64::try_from(difference).map_err(|_| interval_out_of_range()).map(|micros| Value::Interval(Interval {
+                months: 0,
+                days: (micros / MICROS_PER_DAY) as i32,
+                micros: micros % MICROS_PER_DAY,
+            }))
+        }
+
+        (Plus, Value::Time(time), Value::Interval(interval)) | (Plus, Value::Interval(interval), Value::Time(time)) => {
+            Ok(Value::Time((*time as i128 + interval.micros as i128).rem_euclid(MICROS_PER_DAY as i128) as i64))
+        }
+        (Minus, Value::Time(time), Value::Interval(interval)) => {
+            Ok(Value::Time((*time as i128 - interval.micros as i128).rem_euclid(MICROS_PER_DAY as i128) as i64))
+        }
+        (Minus, Value::Time(a), Value::Time(b)) => Ok(Value::Interval(Interval { months: 0, days: 0, micros: a - b })),
+
+        (Plus, Value::Interval(a), Value::Interval(b)) => combine(a, b, i32::checked_add, i64::checked_add),
+        (Minus, Value::Interval(a), Value::Interval(b)) => combine(a, b, i32::checked_sub, i64::checked_sub),
+        (Multiply, Value::Interval(interval), factor) | (Multiply, factor, Value::Interval(interval)) => {
+            scale_interval(interval, number(factor)?)
+        }
+        (Divide, Value::Interval(interval), divisor) => {
+            let divisor = number(divisor)?;
+            if divisor == 0.0 {
+                return Some(Err(ExecutorError::division_by_zero()));
+            }
+            scale_interval(interval, 1.0 / divisor)
+        }
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(n) => Some(*n as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+fn add_days(days: i32, n: i64) -> Result<Value> {
+    let days = (days as i64).checked_add(n).and_then(|days| i32::try_from(days).ok());
+    match days.and_then(to_date) {
+        Some(date) => from_date(date).map(Value::Date),
+        None => Err(out_of_range("date")),
+    }
+}
+
+/// Move a timestamp by an interval: months first, keeping the day of the
+/// month where it exists (and otherwise the month's last day), then days,
+/// then the rest
+fn add_interval(micros: i64, interval: &Interval) -> Result<i64> {
+    let range = || out_of_range("timestamp");
+    let mut datetime = to_datetime(micros).ok_or_else(range)?;
+    let months = Months::new(interval.months.unsigned_abs());
+    datetime = match interval.months {
+        0 => Some(datetime),
+        n if n > 0 => datetime.checked_add_months(months),
+        _ => datetime.checked_sub_months(months),
+    }.ok_or_else(range)?;
+    datetime = datetime.checked_add_signed(TimeDelta::days(interval.days as i64)).ok_or_else(range)?;
+    from_datetime(datetime)?.checked_add(interval.micros).ok_or_else(range)
+}
+
+pub fn negate_interval(interval: &Interval) -> Result<Interval> {
+    match (interval.months.checked_neg(), interval.days.checked_neg(), interval.micros.checked_neg()) {
+        (Some(months), Some(days), Some(micros)) => Ok(Interval { months, days, micros }),
+        _ => Err(interval_out_of_range()),
+    }
+}
+
+fn combine(
+    a: &Interval,
+    b: &Interval,
+    part: fn(i32, i32) -> Option<i32>,
+    micros: fn(i64, i64) -> Option<i64>,
+) -> Result<Value> {
+    match (part(a.months, b.months), part(a.days, b.days), micros(a.micros, b.micros)) {
+        (Some(months), Some(days), Some(micros)) => Ok(Value::Interval(Interval { months, days, micros })),
+        _ => Err(interval_out_of_range()),
+    }
+}
+
+/// Multiply an interval, carrying fractions of months into days and of
+/// days into microseconds as interval input does
+fn scale_interval(interval: &Interval, factor: f64) -> Result<Value> {
+    let mut builder = IntervalBuilder::default();
+    builder.add(interval.months as f64 * factor, Unit::Months(1))
+        .and_then(|_| builder.add(interval.days as f64 * factor, Unit::Days(1)))
+        .and_then(|_| builder.add(interval.micros as f64 * factor, Unit::Micros(1)))
+        .and_then(|_| builder.finish())
+        .map(Value::Interval)
+        .ok_or_else(interval_out_of_range)
+}
+
+// Functions
+
+/// A field `extract`, `date_part` and `date_trunc` name
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Microseconds,
+    Milliseconds,
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+    Decade,
+    Century,
+    Millennium,
+    DayOfWeek,
+    IsoDayOfWeek,
+    DayOfYear,
+    IsoYear,
+    Epoch,
+    Timezone,
+}
+
+fn field(name: &str) -> Option<Field> {
+    Some(match name.trim().to_ascii_lowercase().as_str() {
+        "microsecond" | "microseconds" | "us" | "usec" | "usecs" => Field::Microseconds,
+        "millisecond" | "milliseconds" | "ms" | "msec" | "msecs" => Field::Milliseconds,
+        "second" | "seconds" | "sec" | "secs" | "s" => Field::Second,
+        "minute" | "minutes" | "min" | "mins" | "m" => Field::Minute,
+        "hour" | "hours" | "hr" | "hrs" | "h" => Field::Hour,
+        "day" | "days" | "d" => Field::Day,
+        "week" | "weeks" | "w" => Field::Week,
+        "month" | "months" | "mon" | "mons" => Field::Month,
+        "quarter" | "qtr" => Field::Quarter,
+        "year" | "years" | "yr" | "yrs" | "y" => Field::Year,
+        "decade" | "decades" => Field::Decade,
+        "century" | "centuries" => Field::Century,
+        "millennium" | "millennia" => Field::Millennium,
+        "dow" => Field::DayOfWeek,
+        "isodow" => Field::IsoDayOfWeek,
+        "doy" => Field::DayOfYear,
+        "isoyear" => Field::IsoYear,
+        "epoch" => Field::Epoch,
+        "timezone" => Field::Timezone,
+        _ => return None,
+    })
+}
+
+fn unsupported_unit(name: &str, type_name: &str) -> String {
+    format!("unit \"{}\" not supported for type {}", name.trim().to_ascii_lowercase(), type_name)
+}
+
+fn unrecognized_unit(name: &str, type_name: &str) -> String {
+    format!("unit \"{}\" not recognized for type {}", name.trim().to_ascii_lowercase(), type_name)
+}
+
+/// A field of a date/time value, exactly: `digits` with `scale` decimal
+/// places, so seconds and epochs keep their microseconds
+#[derive(Clone, Copy)]
+struct Part {
+    digits: i128,
+    scale: i64,
+}
+
+impl Part {
+    fn whole(n: impl Into<i128>) -> Part {
+        Part { digits: n.into(), scale: 0 }
+    }
+}
+
+/// `date_part(field, source)`: the field as a double precision
+pub fn date_part(name: &str, value: &Value) -> std::result::Result<Value, String> {
+    let part = part(name, value)?;
+    Ok(Value::Float(part.digits as f64 / 10f64.powi(part.scale as i32)))
+}
+
+/// `extract(field FROM source)`: the field as a numeric, exact to the
+/// microsecond
+pub fn extract(name: &str, value: &Value) -> std::result::Result<Value, String> {
+    let part = part(name, value)?;
+    Ok(Value::Numeric(BigDecimal::new(part.digits.into(), part.scale)))
+}
+
+fn part(name: &str, value: &Value) -> std::result::Result<Part, String> {
+    let type_name = match value {
+        Value::Date(_) => "date",
+        Value::Time(_) => "time without time zone",
+        Value::Timestamp(_) => "timestamp without time zone",
+        Value::TimestampTz(_) => "timestamp with time zone",
+        Value::Interval(_) => "interval",
+        _ => return Err("date_part needs a date/time value".to_string()),
+    };
+    let field = field(name).ok_or_else(|| unrecognized_unit(name, type_name))?;
+    let unsupported = || unsupported_unit(name, type_name);
+
+    Ok(match value {
+        Value::Time(micros) => time_part(field, *micros).ok_or_else(unsupported)?,
+        Value::Interval(interval) => interval_part(field, interval).ok_or_else(unsupported)?,
+        Value::TimestampTz(_) if field == Field::Timezone => Part::whole(0),
+        // A date's epoch is a whole number of seconds
+        Value::Date(days) if field == Field::Epoch => {
+            Part::whole((*days as i64 + UNIX_EPOCH_DAYS) * (MICROS_PER_DAY / MICROS_PER_SECOND))
+        }
+        _ => {
+            let micros = match value {
+                Value::Date(days) => date_micros(*days).map_err(|_| "date out of range".to_string())?,
+                Value::Timestamp(micros) | Value::TimestampTz(micros) => *micros,
+                _ => unreachable!("checked above"),
+            };
+            let datetime = to_datetime(micros).ok_or("timestamp out of range")?;
+            let day_micros = micros.rem_euclid(MICROS_PER_DAY);
+            // Years before 1 AD count down from -1; there is no year 0
+            let year = match datetime.year() {
+                year if year <= 0 => year - 1,
+                year => year,
+            };
+            match field {
+                Field::Microseconds | Field::Milliseconds | Field::Second | Field::Minute | Field::Hour => {
+                    time_part(field, day_micros).expect("time fields")
+                }
+                Field::Day => Part::whole(datetime.day()),
+                Field::Week => Part::whole(datetime.iso_week().week()),
+                Field::Month => Part::whole(datetime.month()),
+                Field::Quarter => Part::whole((datetime.month() - 1) / 3 + 1),
+                Field::Year => Part::whole(year),
+                Field::Decade => Part::whole(datetime.year().div_euclid(10)),
+                Field::Century => Part::whole(match year {
+                    year if year > 0 => (year + 99) / 100,
+                    year => -((99 - year) / 100),
+                }),
+                Field::Millennium => Part::whole(match year {
+                    year if year > 0 => (year + 999) / 1000,
+                    year => -((999 - year) / 1000),
+                }),
+                Field::DayOfWeek => Part::whole(datetime.weekday().num_days_from_sunday()),
+                Field::IsoDayOfWeek => Part::whole(datetime.weekday().number_from_monday()),
+                Field::DayOfYear => Part::whole(datetime.ordinal()),
+                Field::IsoYear => Part::whole(datetime.iso_week().year()),
+                Field::Epoch => Part { digits: micros as i128 + (UNIX_EPOCH_DAYS * MICROS_PER_DAY) as i128, scale: 6 },
+                Field::Timezone => return Err(unsupported()),
+            }
+        }
+    })
+}
+
+fn time_part(field: Field, micros: i64) -> Option<Part> {
+    let seconds = micros % MICROS_PER_MINUTE;
+    Some(match field {
+        Field::Microseconds => Part::whole(seconds),
+        Field::Milliseconds => Part { digits: seconds as i128, scale: 3 },
+        Field::Second => Part { digits: seconds as i128, scale: 6 },
+        Field::Minute => Part::whole(micros % MICROS_PER_HOUR / MICROS_PER_MINUTE),
+        Field::Hour => Part::whole(micros / MICROS_PER_HOUR),
+        Field::Epoch => Part { digits: micros as i128, scale: 6 },
+        _ => return None,
+    })
+}
+
+fn interval_part(field: Field, interval: &Interval) -> Option<Part> {
+    let years = interval.months / 12;
+    Some(match field {
+        Field::Microseconds | Field::Milliseconds | Field::Second | Field::Minute | Field::Hour => {
+            let sign = interval.micros.signum();
+            let part = time_part(field, interval.micros.abs())?;
+            Part { digits: part.digits * sign as i128, ..part }
+        }
+        Field::Day => Part::whole(interval.days),
+        Field::Month => Part::whole(interval.months % 12),
+        Field::Quarter => Part::whole((interval.months % 12) / 3 + 1),
+        Field::Year => Part::whole(years),
+        Field::Decade => Part::whole(years / 10),
+        Field::Century => Part::whole(years / 100),
+        Field::Millennium => Part::whole(years / 1000),
+        // A year being 365.25 days, a month 30
+        Field::Epoch => {
+            let quarter_days = years as i128 * 1461 + (interval.months % 12) as i128 * 120 + interval.days as i128 * 4;
+            Part { digits: quarter_days * MICROS_PER_DAY as i128 / 4 + interval.micros as i128, scale: 6 }
+        }
+        _ => return None,
+    })
+}
+
+/// `date_trunc(field, source)`: a timestamp or interval with the parts
+/// smaller than `field` zeroed (days and months set to their first)
+pub fn date_trunc(name: &str, value: &Value) -> std::result::Result<Value, String> {
+    let type_name = match value {
+        Value::Timestamp(_) => "timestamp without time zone",
+        Value::TimestampTz(_) => "timestamp with time zone",
+        Value::Interval(_) => "interval",
+        _ => return Err("date_trunc needs a timestamp or interval".to_string()),
+    };
+    let field = field(name).ok_or_else(|| unrecognized_unit(name, type_name))?;
+    let unsupported = || unsupported_unit(name, type_name);
+
+    if let Value::Interval(interval) = value {
+        let truncate = |micros: i64, unit: i64| micros - micros % unit;
+        let years = |unit: i32| Interval { months: interval.months - interval.months % (12 * unit), days: 0, micros: 0 };
+        let truncated = match field {
+            Field::Microseconds => *interval,
+            Field::Milliseconds => Interval { micros: truncate(interval.micros, 1000), ..*interval },
+            Field::Second => Interval { micros: truncate(interval.micros, MICROS_PER_SECOND), ..*interval },
+            Field::Minute => Interval { micros: truncate(interval.micros, MICROS_PER_MINUTE), ..*interval },
+            Field::Hour => Interval { micros: truncate(interval.micros, MICROS_PER_HOUR), ..*interval },
+            Field::Day => Interval { micros: 0, ..*interval },
+            Field::Month => Interval { days: 0, micros: 0, ..*interval },
+            Field::Quarter => Interval { months: interval.months - interval.months % 3, days: 0, micros: 0 },
+            Field::Year => years(1),
+            Field::Decade => years(10),
+            Field::Century => years(100),
+            Field::Millennium => years(1000),
+            _ => return Err(unsupported()),
+        };
+        return Ok(Value::Interval(truncated));
+    }
+
+    let (Value::Timestamp(micros) | Value::TimestampTz(micros)) = value else {
+        unreachable!("checked above");
+    };
+    let datetime = to_datetime(*micros).ok_or("timestamp out of range")?;
+    let day = micros - micros.rem_euclid(MICROS_PER_DAY);
+    let first_of = |year: i32, month: u32| NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|date| from_datetime(date.and_time(NaiveTime::MIN)).ok())
+        .ok_or("timestamp out of range");
+    // Years truncate to the first of their decade, century or millennium,
+    // which start at years ending in 0, 01 and 001
+    let first_year = |unit: i32, offset: i32| {
+        let year = datetime.year();
+        if year > 0 {
+            year - (year - offset).rem_euclid(unit)
+        } else {
+            year - (year - 1 + offset).rem_euclid(unit)
+        }
+    };
+    let truncated = match field {
+        Field::Microseconds => *micros,
+        Field::Milliseconds => micros - micros.rem_euclid(1000),
+        Field::Second => micros - micros.rem_euclid(MICROS_PER_SECOND),
+        Field::Minute => micros - micros.rem_euclid(MICROS_PER_MINUTE),
+        Field::Hour => micros - micros.rem_euclid(MICROS_PER_HOUR),
+        Field::Day => day,
+        Field::Week => day - datetime.weekday().num_days_from_monday() as i64 * MICROS_PER_DAY,
+        Field::Month => first_of(datetime.year(), datetime.month())?,
+        Field::Quarter => first_of(datetime.year(), (datetime.month() - 1) / 3 * 3 + 1)?,
+        Field::Year => first_of(datetime.year(), 1)?,
+        Field::Decade => first_of(first_year(10, 0), 1)?,
+        Field::Century => first_of(first_year(100, 1), 1)?,
+        Field::Millennium => first_of(first_year(1000, 1), 1)?,
+        _ => return Err(unsupported()),
+    };
+    Ok(match value {
+        Value::TimestampTz(_) => Value::TimestampTz(truncated),
+        _ => Value::Timestamp(truncated),
+    })
+}
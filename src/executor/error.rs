@@ -5,9 +5,39 @@ pub enum ExecutorError {
     Plan(String),
     Execution(String),
     UnsupportedStatement(String),
+    /// Error reported with its own SQLSTATE (see `sqlstate`)
+    Sql(&'static str, String),
     // StorageError(storage::Error)
 }
 
+/// SQLSTATE codes of errors raised with `ExecutorError::Sql`
+pub mod sqlstate {
+    pub const DATATYPE_MISMATCH: &str = "42804";
+    pub const CANNOT_COERCE: &str = "42846";
+    pub const UNDEFINED_FUNCTION: &str = "42883";
+    pub const AMBIGUOUS_FUNCTION: &str = "42725";
+    pub const UNDEFINED_OBJECT: &str = "42704";
+    pub const INVALID_TEXT_REPRESENTATION: &str = "22P02";
+    pub const DIVISION_BY_ZERO: &str = "22012";
+    pub const NUMERIC_VALUE_OUT_OF_RANGE: &str = "22003";
+    pub const INVALID_ESCAPE_SEQUENCE: &str = "22025";
+    pub const INVALID_REGULAR_EXPRESSION: &str = "2201B";
+    pub const INVALID_DATETIME_FORMAT: &str = "22007";
+    pub const DATETIME_FIELD_OVERFLOW: &str = "22008";
+    pub const INVALID_PARAMETER_VALUE: &str = "22023";
+}
+
+impl ExecutorError {
+    pub(crate) fn division_by_zero() -> Self {
+        ExecutorError::Sql(sqlstate::DIVISION_BY_ZERO, "division by zero".to_string())
+    }
+
+    /// A result that does not fit `type_name`, e.g. "bigint out of range"
+    pub(crate) fn out_of_range(type_name: &str) -> Self {
+        ExecutorError::Sql(sqlstate::NUMERIC_VALUE_OUT_OF_RANGE, format!("{} out of range", type_name))
+    }
+}
+
 impl From<ExecutorError> for PgWireError {
     fn from(e: ExecutorError) -> PgWireError {
         match e {
@@ -30,8 +60,12 @@ impl From<ExecutorError> for PgWireError {
                 "ERROR".to_string(),
                 "XX000".to_string(), // internal_error
                 msg,
-            )))
+            ))),
+            ExecutorError::Sql(code, msg) => PgWireError::UserError(Box::new(ErrorInfo::new(
+                "ERROR".to_string(),
+                code.to_string(),
+                msg,
+            ))),
         }
     }
 }
-
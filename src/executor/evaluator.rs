@@ -1,13 +1,25 @@
-use sqlparser::ast::{Expr, BinaryOperator};
+use bigdecimal::BigDecimal;
+use sqlparser::ast::{BinaryOperator, CastKind, CeilFloorKind, DateTimeField, Expr, Function, FunctionArg, FunctionArgExpr, FunctionArguments, UnaryOperator};
 use tracing::debug;
 
-use crate::executor::error::ExecutorError;
-use crate::types::{Row, Schema, Value};
+use std::cmp::Ordering;
+
+use crate::executor::coercion::{self, value_type};
+use crate::executor::{datetime, numeric};
+use crate::executor::error::{sqlstate, ExecutorError};
+use crate::executor::{is_string_literal, number_literal};
+use crate::executor::pattern;
+use crate::extensions::{CastContext, FunctionExtension, OperatorExtension};
+use crate::extensions::registry::Registries;
+use crate::types::{DataType, Row, Schema, Value};
 
 pub type Result<T> = std::result::Result<T, ExecutorError>;
 
 /// Evaluate a SQL expression against a row
-pub fn eval_expr(expr: &Expr, row: &Row, schema: &Schema) -> Result<Value> {
+///
+/// Functions, operators, casts and extension types resolve against
+/// `registries`, those of the database the query runs on.
+pub fn eval_expr(expr: &Expr, row: &Row, schema: &Schema, registries: &Registries) -> Result<Value> {
     match expr {
         // Literals
         Expr::Value(val) => {
@@ -34,30 +46,229 @@ pub fn eval_expr(expr: &Expr, row: &Row, schema: &Schema) -> Result<Value> {
 
         // Column reference
         Expr::Identifier(ident) => {
-            let col_name = &ident.value;
-            debug!(column = %col_name, "evaluating column reference");
-
-            if let Some(idx) = schema.get_column_index(col_name) {
-                row.get(idx)
-                    .cloned()
-                    .ok_or_else(|| ExecutorError::Execution(format!("Column index out of bounds: {}", col_name)))
-            } else {
-                Err(ExecutorError::Execution(format!(
-                    "Column not found: {}",
-                    col_name
-                )))
+            debug!(column = %ident.value, "evaluating column reference");
+            let idx = column_index(None, &ident.value, schema)?;
+            row.get(idx)
+                .cloned()
+                .ok_or_else(|| ExecutorError::Execution(format!("Column index out of bounds: {}", ident.value)))
+        }
+
+        // Qualified column reference (relation.column)
+        Expr::CompoundIdentifier(idents) => {
+            let (qualifier, name) = split_qualified(idents)?;
+            let idx = column_index(Some(qualifier), name, schema)?;
+            row.get(idx)
+                .cloned()
+                .ok_or_else(|| ExecutorError::Execution(format!("Column index out of bounds: {}.{}", qualifier, name)))
+        }
+
+        // AND and OR skip their right operand once the left decides the result
+        Expr::BinaryOp { left, op: op @ (BinaryOperator::And | BinaryOperator::Or), right } => {
+            let left_val = eval_expr(left, row, schema, registries)?;
+            let decided = matches!(op, BinaryOperator::Or);
+            if truth(&left_val, &op.to_string(), registries)? == Some(decided) {
+                return Ok(Value::Bool(decided));
             }
+            eval_binary_op(&left_val, op, &eval_expr(right, row, schema, registries)?, registries)
         }
 
         // Binary operations
         Expr::BinaryOp { left, op, right } => {
-            let left_val = eval_expr(left, row, schema)?;
-            let right_val = eval_expr(right, row, schema)?;
-            eval_binary_op(&left_val, op, &right_val)
+            let left_val = eval_expr(left, row, schema, registries)?;
+            let right_val = literal_operand(right, eval_expr(right, row, schema, registries)?, op, &left_val, registries)?;
+            let left_val = literal_operand(left, left_val, op, &right_val, registries)?;
+            eval_binary_op(&left_val, op, &right_val, registries)
+        }
+
+        // A minus sign on a literal is part of it, so that the smallest
+        // bigint can be written
+        Expr::UnaryOp { op: UnaryOperator::Minus, expr: operand } => {
+            if let Expr::Value(val) = operand.as_ref()
+                && let sqlparser::ast::Value::Number(n, _) = &val.value
+                && let Ok(i) = format!("-{}", n).parse::<i64>()
+            {
+                return Ok(Value::Int(i));
+            }
+            eval_unary_op(&UnaryOperator::Minus, &eval_expr(operand, row, schema, registries)?, registries)
+        }
+
+        // Unary operations
+        Expr::UnaryOp { op, expr } => eval_unary_op(op, &eval_expr(expr, row, schema, registries)?, registries),
+
+        // NULL and truth tests, which are never NULL themselves
+        Expr::IsNull(inner) => Ok(Value::Bool(matches!(eval_expr(inner, row, schema, registries)?, Value::Null))),
+        Expr::IsNotNull(inner) => Ok(Value::Bool(!matches!(eval_expr(inner, row, schema, registries)?, Value::Null))),
+        Expr::IsTrue(inner) => Ok(Value::Bool(truth(&eval_expr(inner, row, schema, registries)?, "IS TRUE", registries)? == Some(true))),
+        Expr::IsNotTrue(inner) => Ok(Value::Bool(truth(&eval_expr(inner, row, schema, registries)?, "IS NOT TRUE", registries)? != Some(true))),
+        Expr::IsFalse(inner) => Ok(Value::Bool(truth(&eval_expr(inner, row, schema, registries)?, "IS FALSE", registries)? == Some(false))),
+        Expr::IsNotFalse(inner) => Ok(Value::Bool(truth(&eval_expr(inner, row, schema, registries)?, "IS NOT FALSE", registries)? != Some(false))),
+        Expr::IsUnknown(inner) => Ok(Value::Bool(truth(&eval_expr(inner, row, schema, registries)?, "IS UNKNOWN", registries)?.is_none())),
+        Expr::IsNotUnknown(inner) => Ok(Value::Bool(truth(&eval_expr(inner, row, schema, registries)?, "IS NOT UNKNOWN", registries)?.is_some())),
+        Expr::IsDistinctFrom(left, right) => {
+            let (left, right) = (eval_expr(left, row, schema, registries)?, eval_expr(right, row, schema, registries)?);
+            is_distinct(&left, &right, registries).map(Value::Bool)
+        }
+        Expr::IsNotDistinctFrom(left, right) => {
+            let (left, right) = (eval_expr(left, row, schema, registries)?, eval_expr(right, row, schema, registries)?);
+            is_distinct(&left, &right, registries).map(|distinct| Value::Bool(!distinct))
+        }
+
+        // expr [NOT] IN (list): true on a match, else NULL if anything compared was NULL
+        Expr::InList { expr, list, negated } => {
+            let value = eval_expr(expr, row, schema, registries)?;
+            let mut result = Value::Bool(false);
+            for item in list {
+                let item_val = literal_operand(item, eval_expr(item, row, schema, registries)?, &BinaryOperator::Eq, &value, registries)?;
+                match eval_binary_op(&value, &BinaryOperator::Eq, &item_val, registries)? {
+                    Value::Bool(true) => {
+                        result = Value::Bool(true);
+                        break;
+                    }
+                    Value::Null => result = Value::Null,
+                    _ => {}
+                }
+            }
+            negate(result, *negated, registries)
+        }
+
+        // expr [NOT] BETWEEN low AND high
+        Expr::Between { expr, negated, low, high } => {
+            let value = eval_expr(expr, row, schema, registries)?;
+            let low = literal_operand(low, eval_expr(low, row, schema, registries)?, &BinaryOperator::GtEq, &value, registries)?;
+            let high = literal_operand(high, eval_expr(high, row, schema, registries)?, &BinaryOperator::LtEq, &value, registries)?;
+            let above = eval_binary_op(&value, &BinaryOperator::GtEq, &low, registries)?;
+            let below = eval_binary_op(&value, &BinaryOperator::LtEq, &high, registries)?;
+            negate(eval_binary_op(&above, &BinaryOperator::And, &below, registries)?, *negated, registries)
+        }
+
+        // Pattern matching
+        Expr::Like { negated, any: false, expr, pattern, escape_char } => {
+            negate(eval_pattern(expr, pattern, escape_char.as_ref(), PatternKind::Like, row, schema, registries)?, *negated, registries)
+        }
+        Expr::ILike { negated, any: false, expr, pattern, escape_char } => {
+            negate(eval_pattern(expr, pattern, escape_char.as_ref(), PatternKind::ILike, row, schema, registries)?, *negated, registries)
+        }
+        Expr::SimilarTo { negated, expr, pattern, escape_char } => {
+            negate(eval_pattern(expr, pattern, escape_char.as_ref(), PatternKind::Similar, row, schema, registries)?, *negated, registries)
+        }
+
+        // CASE [operand] WHEN ... THEN ... [ELSE ...] END
+        Expr::Case { operand, conditions, else_result, .. } => {
+            let operand = operand.as_ref().map(|operand| eval_expr(operand, row, schema, registries)).transpose()?;
+            for when in conditions {
+                let condition = eval_expr(&when.condition, row, schema, registries)?;
+                let matched = match &operand {
+                    Some(operand) => eval_binary_op(operand, &BinaryOperator::Eq, &condition, registries)?,
+                    None => condition,
+                };
+                if truth(&matched, "CASE/WHEN", registries)? == Some(true) {
+                    return eval_expr(&when.result, row, schema, registries);
+                }
+            }
+            match else_result {
+                Some(else_result) => eval_expr(else_result, row, schema, registries),
+                None => Ok(Value::Null),
+            }
+        }
+
+        // Function call: the conditional functions are built in, the rest
+        // are resolved through the function registry
+        Expr::Function(function) => {
+            let (name, args) = function_call(function)?;
+            match (name.as_str(), args.as_slice()) {
+                ("coalesce", args) if !args.is_empty() => {
+                    for arg in args {
+                        let value = eval_expr(arg, row, schema, registries)?;
+                        if !matches!(value, Value::Null) {
+                            return Ok(value);
+                        }
+                    }
+                    Ok(Value::Null)
+                }
+                ("nullif", [left, right]) => {
+                    let (left, right) = (eval_expr(left, row, schema, registries)?, eval_expr(right, row, schema, registries)?);
+                    match eval_binary_op(&left, &BinaryOperator::Eq, &right, registries)? {
+                        Value::Bool(true) => Ok(Value::Null),
+                        _ => Ok(left),
+                    }
+                }
+                _ => {
+                    let values = args.iter()
+                        .map(|arg| eval_expr(arg, row, schema, registries))
+                        .collect::<Result<Vec<_>>>()?;
+                    eval_function(&name, &values, registries)
+                }
+            }
+        }
+
+        // CAST(x AS t) and x::t
+        Expr::Cast { expr: inner, .. } => {
+            let target = cast_target(expr, registries)?;
+            // A number cast to numeric keeps the digits it was written with
+            if let (DataType::Numeric(_), Some(text)) = (&target, number_literal(inner)) {
+                return coercion::cast(&Value::String(text), &target, CastContext::Explicit, registries);
+            }
+            coercion::cast(&eval_expr(inner, row, schema, registries)?, &target, CastContext::Explicit, registries)
+        }
+
+        // Typed literal, as in BOOLEAN 'yes': read by the type's input function
+        Expr::TypedString(typed) => {
+            let text = typed.value.clone().into_string()
+                .ok_or_else(|| ExecutorError::Execution(format!("Unsupported typed literal: {}", typed)))?;
+            coercion::parse_text(&text, &coercion::sql_data_type(&typed.data_type, registries)?, registries)
+        }
+
+        // INTERVAL '1 day', or INTERVAL '3' DAY with the unit outside
+        Expr::Interval(interval) => {
+            let text = match eval_expr(&interval.value, row, schema, registries)? {
+                Value::String(text) => text,
+                Value::Null => return Ok(Value::Null),
+                other => other.as_string(),
+            };
+            match (&interval.leading_field, &interval.last_field) {
+                (Some(unit), None) => datetime::parse_interval_with_unit(&text, &unit.to_string()),
+                _ => datetime::parse_interval(&text),
+            }.map(Value::Interval)
+        }
+
+        // EXTRACT(field FROM source): date_part('field', source) as a numeric
+        Expr::Extract { field, expr: source, .. } => {
+            let field = match field {
+                DateTimeField::Custom(ident) => ident.value.clone(),
+                field => field.to_string(),
+            };
+            match eval_expr(source, row, schema, registries)? {
+                Value::Null => Ok(Value::Null),
+                source => datetime::extract(&field, &source)
+                    .map_err(|msg| ExecutorError::Sql(sqlstate::INVALID_PARAMETER_VALUE, msg)),
+            }
+        }
+
+        // source AT TIME ZONE zone; a string literal source is read as a
+        // timestamp with time zone
+        Expr::AtTimeZone { timestamp, time_zone } => {
+            let source = match eval_expr(timestamp, row, schema, registries)? {
+                Value::String(text) if is_string_literal(timestamp) => {
+                    coercion::parse_text(&text, &DataType::TimestampTz, registries)?
+                }
+                source => source,
+            };
+            match (source, eval_expr(time_zone, row, schema, registries)?) {
+                (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+                (source, zone) => datetime::at_time_zone(&source, &zone),
+            }
+        }
+
+        // CEIL(x) and FLOOR(x), which parse apart from other function calls
+        Expr::Ceil { expr: arg, field: CeilFloorKind::DateTimeField(DateTimeField::NoDateTime) } => {
+            eval_function("ceil", &[eval_expr(arg, row, schema, registries)?], registries)
+        }
+        Expr::Floor { expr: arg, field: CeilFloorKind::DateTimeField(DateTimeField::NoDateTime) } => {
+            eval_function("floor", &[eval_expr(arg, row, schema, registries)?], registries)
         }
 
         // Parenthesized expression
-        Expr::Nested(inner) => eval_expr(inner, row, schema),
+        Expr::Nested(inner) => eval_expr(inner, row, schema, registries),
 
         // Wildcard (shouldn't reach here in typical evaluation)
         Expr::Wildcard(_) => Ok(Value::Null),
@@ -69,189 +280,658 @@ pub fn eval_expr(expr: &Expr, row: &Row, schema: &Schema) -> Result<Value> {
     }
 }
 
-/// Evaluate a binary operation
-fn eval_binary_op(left: &Value, op: &BinaryOperator, right: &Value) -> Result<Value> {
+/// An operand of `op` as the operator takes it: a string literal is read
+/// as the type `coercion::literal_type` gives it beside the other operand,
+/// and a number literal beside a numeric as a numeric, without the rounding
+/// of a float
+fn literal_operand(expr: &Expr, value: Value, op: &BinaryOperator, other: &Value, registries: &Registries) -> Result<Value> {
+    if let (Value::Float(_), Value::Numeric(_)) = (&value, other)
+        && let Some(text) = number_literal(expr)
+    {
+        return numeric::parse(&text).map(Value::Numeric);
+    }
+    match &value {
+        Value::String(text) if is_string_literal(expr) => {
+            match coercion::literal_type(op, &coercion::value_type(other, registries)) {
+                Some(data_type) => coercion::parse_text(text, &data_type, registries),
+                None => Ok(value),
+            }
+        }
+        _ => Ok(value),
+    }
+}
+
+/// Position of a column reference in `schema`
+pub fn column_index(qualifier: Option<&str>, name: &str, schema: &Schema) -> Result<usize> {
+    match schema.resolve_column(qualifier, name) {
+        Ok(Some(idx)) => Ok(idx),
+        Ok(None) => Err(ExecutorError::Execution(match qualifier {
+            Some(qualifier) => format!("Column not found: {}.{}", qualifier, name),
+            None => format!("Column not found: {}", name),
+        })),
+        Err(e) => Err(ExecutorError::Execution(e)),
+    }
+}
+
+/// Split `relation.column` (or `schema.relation.column`) into relation and column
+pub fn split_qualified(idents: &[sqlparser::ast::Ident]) -> Result<(&str, &str)> {
+    match idents {
+        [.., qualifier, name] => Ok((&qualifier.value, &name.value)),
+        _ => Err(ExecutorError::Execution(format!(
+            "Invalid column reference: {}",
+            idents.iter().map(|ident| ident.value.as_str()).collect::<Vec<_>>().join(".")
+        ))),
+    }
+}
+
+/// Order two non-NULL values of comparable types
+pub fn compare_values(left: &Value, right: &Value) -> Result<Ordering> {
+    let ordering = match (left, right) {
+        (Value::Int(a), Value::Int(b)) => a.cmp(b),
+        (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+        (Value::Int(a), Value::Float(b)) => (*a as f64).total_cmp(b),
+        (Value::Float(a), Value::Int(b)) => a.total_cmp(&(*b as f64)),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Uuid(a), Value::Uuid(b)) => a.cmp(b),
+        (Value::Bytea(a), Value::Bytea(b)) => a.cmp(b),
+        (Value::Numeric(_), _) | (_, Value::Numeric(_)) => match operands(left, right) {
+            Some(Operands::Numeric(a, b)) => numeric::compare(&a, &b),
+            Some(Operands::Float(a, b)) => a.total_cmp(&b),
+            _ => return Err(ExecutorError::Execution("Type mismatch in comparison".to_string())),
+        },
+        _ => return datetime::compare(left, right).ok_or_else(|| ExecutorError::Execution(
+            "Type mismatch in comparison".to_string(),
+        )),
+    };
+    Ok(ordering)
+}
+
+/// Infer the result type of an expression evaluated against `schema`
+/// Returns DataType::Null when the type cannot be determined statically
+pub fn infer_type(expr: &Expr, schema: &Schema, registries: &Registries) -> DataType {
     use BinaryOperator::*;
 
-    match op {
-        // Comparison operators
-        Eq => {
-            let result = match (left, right) {
-                (Value::Int(a), Value::Int(b)) => a == b,
-                (Value::Float(a), Value::Float(b)) => a == b,
-                (Value::Int(a), Value::Float(b)) => *a as f64 == *b,
-                (Value::Float(a), Value::Int(b)) => *a == *b as f64,
-                (Value::String(a), Value::String(b)) => a == b,
-                (Value::Bool(a), Value::Bool(b)) => a == b,
-                (Value::Null, _) | (_, Value::Null) => false, // NULL comparisons are false
-                _ => return Err(ExecutorError::Execution(
-                    "Type mismatch in comparison".to_string(),
-                )),
-            };
-            Ok(Value::Bool(result))
+    match expr {
+        Expr::Value(val) => match &val.value {
+            sqlparser::ast::Value::Number(n, _) if n.parse::<i64>().is_ok() => DataType::Int,
+            sqlparser::ast::Value::Number(_, _) => DataType::Float,
+            sqlparser::ast::Value::SingleQuotedString(_) => DataType::String,
+            sqlparser::ast::Value::Boolean(_) => DataType::Bool,
+            _ => DataType::Null,
+        },
+        Expr::Identifier(ident) => schema.resolve_column(None, &ident.value).ok().flatten()
+            .map(|idx| schema.columns[idx].data_type.clone())
+            .unwrap_or(DataType::Null),
+        Expr::CompoundIdentifier(idents) => split_qualified(idents).ok()
+            .and_then(|(qualifier, name)| schema.resolve_column(Some(qualifier), name).ok().flatten())
+            .map(|idx| schema.columns[idx].data_type.clone())
+            .unwrap_or(DataType::Null),
+        Expr::BinaryOp { left, op, right } if dispatches_to_registry(op, &infer_type(left, schema, registries), &infer_type(right, schema, registries)) => {
+            let (left, right) = (infer_type(left, schema, registries), infer_type(right, schema, registries));
+            resolve_operator(op, &left, &right, registries).unwrap_or(DataType::Null)
         }
-
-        NotEq => {
-            let result = match (left, right) {
-                (Value::Int(a), Value::Int(b)) => a != b,
-                (Value::Float(a), Value::Float(b)) => a != b,
-                (Value::Int(a), Value::Float(b)) => *a as f64 != *b,
-                (Value::Float(a), Value::Int(b)) => *a != *b as f64,
-                (Value::String(a), Value::String(b)) => a != b,
-                (Value::Bool(a), Value::Bool(b)) => a != b,
-                (Value::Null, _) | (_, Value::Null) => false,
-                _ => return Err(ExecutorError::Execution(
-                    "Type mismatch in comparison".to_string(),
-                )),
-            };
-            Ok(Value::Bool(result))
+        Expr::BinaryOp { left: left_expr, op, right: right_expr } => {
+            let (mut left, mut right) = (infer_type(left_expr, schema, registries), infer_type(right_expr, schema, registries));
+            // Literals take their type from the other operand (see `literal_operand`)
+            if is_string_literal(right_expr) && let Some(data_type) = coercion::literal_type(op, &left) {
+                right = data_type;
+            } else if matches!(left, DataType::Numeric(_)) && number_literal(right_expr).is_some() {
+                right = DataType::Numeric(None);
+            }
+            if is_string_literal(left_expr) && let Some(data_type) = coercion::literal_type(op, &right) {
+                left = data_type;
+            } else if matches!(right, DataType::Numeric(_)) && number_literal(left_expr).is_some() {
+                left = DataType::Numeric(None);
+            }
+            if let Some(data_type) = datetime::result_type(op, &left, &right) {
+                return data_type;
+            }
+            match op {
+                Eq | NotEq | Gt | Lt | GtEq | LtEq | And | Or => DataType::Bool,
+                StringConcat if left == DataType::Bytea && right == DataType::Bytea => DataType::Bytea,
+                StringConcat => DataType::String,
+                _ => match (left, right) {
+                    (DataType::Float, _) | (_, DataType::Float) => DataType::Float,
+                    (DataType::Numeric(_), _) | (_, DataType::Numeric(_)) => DataType::Numeric(None),
+                    (DataType::Int, _) | (_, DataType::Int) => DataType::Int,
+                    (left, _) => left,
+                },
+            }
         }
-
-        Gt => {
-            let result = match (left, right) {
-                (Value::Int(a), Value::Int(b)) => a > b,
-                (Value::Float(a), Value::Float(b)) => a > b,
-                (Value::Int(a), Value::Float(b)) => *a as f64 > *b,
-                (Value::Float(a), Value::Int(b)) => *a > *b as f64,
-                (Value::String(a), Value::String(b)) => a > b,
-                (Value::Null, _) | (_, Value::Null) => false,
-                _ => return Err(ExecutorError::Execution(
-                    "Type mismatch in comparison".to_string(),
-                )),
+        Expr::UnaryOp { op: UnaryOperator::Not, .. } => DataType::Bool,
+        Expr::UnaryOp { op: UnaryOperator::Minus, expr: operand } if matches!(
+            operand.as_ref(),
+            Expr::Value(val) if matches!(&val.value, sqlparser::ast::Value::Number(n, _) if format!("-{}", n).parse::<i64>().is_ok())
+        ) => DataType::Int,
+        Expr::UnaryOp { expr, .. } => infer_type(expr, schema, registries),
+        Expr::IsNull(_)
+        | Expr::IsNotNull(_)
+        | Expr::IsTrue(_)
+        | Expr::IsNotTrue(_)
+        | Expr::IsFalse(_)
+        | Expr::IsNotFalse(_)
+        | Expr::IsUnknown(_)
+        | Expr::IsNotUnknown(_)
+        | Expr::IsDistinctFrom(_, _)
+        | Expr::IsNotDistinctFrom(_, _)
+        | Expr::InList { .. }
+        | Expr::Between { .. }
+        | Expr::Like { .. }
+        | Expr::ILike { .. }
+        | Expr::SimilarTo { .. } => DataType::Bool,
+        Expr::Case { conditions, else_result, .. } => common_type(
+            conditions.iter().map(|when| &when.result).chain(else_result.as_deref()),
+            schema,
+            registries,
+        ),
+        Expr::Function(function) => {
+            let Ok((name, args)) = function_call(function) else {
+                return DataType::Null;
             };
-            Ok(Value::Bool(result))
+            match name.as_str() {
+                "coalesce" => common_type(args.into_iter(), schema, registries),
+                "nullif" => args.first().map_or(DataType::Null, |arg| infer_type(arg, schema, registries)),
+                _ => {
+                    let arg_types = args.iter().map(|arg| infer_type(arg, schema, registries)).collect::<Vec<_>>();
+                    resolve_function(&name, &arg_types, registries).unwrap_or(DataType::Null)
+                }
+            }
         }
+        Expr::Cast { .. } => cast_target(expr, registries).unwrap_or(DataType::Null),
+        Expr::TypedString(typed) => coercion::sql_data_type(&typed.data_type, registries).unwrap_or(DataType::Null),
+        Expr::Interval(_) => DataType::Interval,
+        Expr::Extract { .. } => DataType::Numeric(None),
+        Expr::Ceil { expr: arg, .. } => resolve_function("ceil", &[infer_type(arg, schema, registries)], registries).unwrap_or(DataType::Null),
+        Expr::Floor { expr: arg, .. } => resolve_function("floor", &[infer_type(arg, schema, registries)], registries).unwrap_or(DataType::Null),
+        Expr::AtTimeZone { timestamp, .. } => match infer_type(timestamp, schema, registries) {
+            DataType::TimestampTz | DataType::String => DataType::Timestamp,
+            DataType::Timestamp => DataType::TimestampTz,
+            _ => DataType::Null,
+        },
+        Expr::Nested(inner) => infer_type(inner, schema, registries),
+        _ => DataType::Null,
+    }
+}
 
-        Lt => {
-            let result = match (left, right) {
-                (Value::Int(a), Value::Int(b)) => a < b,
-                (Value::Float(a), Value::Float(b)) => a < b,
-                (Value::Int(a), Value::Float(b)) => (*a as f64) < *b,
-                (Value::Float(a), Value::Int(b)) => *a < (*b as f64),
-                (Value::String(a), Value::String(b)) => a < b,
-                (Value::Null, _) | (_, Value::Null) => false,
-                _ => return Err(ExecutorError::Execution(
-                    "Type mismatch in comparison".to_string(),
-                )),
-            };
-            Ok(Value::Bool(result))
-        }
+/// Type of a result chosen from `exprs` (CASE branches, COALESCE arguments)
+///
+/// Integers mix with numerics as numerics, and either with floats as floats;
+/// otherwise the first known type wins.
+fn common_type<'a>(exprs: impl Iterator<Item = &'a Expr>, schema: &Schema, registries: &Registries) -> DataType {
+    exprs.map(|expr| infer_type(expr, schema, registries))
+        .fold(DataType::Null, |common, data_type| match (common, data_type) {
+            (DataType::Null, data_type) => data_type,
+            (DataType::Int | DataType::Numeric(_), DataType::Float) => DataType::Float,
+            (DataType::Int, DataType::Numeric(_)) => DataType::Numeric(None),
+            (common, _) => common,
+        })
+}
 
-        GtEq => {
-            let result = match (left, right) {
-                (Value::Int(a), Value::Int(b)) => a >= b,
-                (Value::Float(a), Value::Float(b)) => a >= b,
-                (Value::Int(a), Value::Float(b)) => *a as f64 >= *b,
-                (Value::Float(a), Value::Int(b)) => *a >= *b as f64,
-                (Value::String(a), Value::String(b)) => a >= b,
-                (Value::Null, _) | (_, Value::Null) => false,
-                _ => return Err(ExecutorError::Execution(
-                    "Type mismatch in comparison".to_string(),
-                )),
-            };
-            Ok(Value::Bool(result))
+/// Check that the function or operator `expr` calls exists for the types of
+/// its arguments (its own arguments are not checked)
+///
+/// Calls with an argument of unknown type, such as a bare NULL or an unbound
+/// parameter, are resolved when they are evaluated instead.
+pub(crate) fn check_call(expr: &Expr, schema: &Schema, registries: &Registries) -> Result<()> {
+    match expr {
+        Expr::Function(function) => {
+            let (name, args) = function_call(function)?;
+            let arg_types = args.iter().map(|arg| infer_type(arg, schema, registries)).collect::<Vec<_>>();
+            match name.as_str() {
+                "coalesce" if !args.is_empty() => Ok(()),
+                "nullif" if args.len() == 2 => Ok(()),
+                "coalesce" | "nullif" => Err(undefined_function(&name, &arg_types)),
+                _ if arg_types.contains(&DataType::Null) => Ok(()),
+                _ => resolve_function(&name, &arg_types, registries).map(|_| ()),
+            }
         }
-
-        LtEq => {
-            let result = match (left, right) {
-                (Value::Int(a), Value::Int(b)) => a <= b,
-                (Value::Float(a), Value::Float(b)) => a <= b,
-                (Value::Int(a), Value::Float(b)) => (*a as f64) <= *b,
-                (Value::Float(a), Value::Int(b)) => *a <= (*b as f64),
-                (Value::String(a), Value::String(b)) => a <= b,
-                (Value::Null, _) | (_, Value::Null) => false,
-                _ => return Err(ExecutorError::Execution(
-                    "Type mismatch in comparison".to_string(),
-                )),
-            };
-            Ok(Value::Bool(result))
+        Expr::BinaryOp { left, op, right } => {
+            let (left, right) = (infer_type(left, schema, registries), infer_type(right, schema, registries));
+            if left == DataType::Null || right == DataType::Null || !dispatches_to_registry(op, &left, &right) {
+                return Ok(());
+            }
+            resolve_operator(op, &left, &right, registries).map(|_| ())
         }
-
-        // Arithmetic operators
-        Plus => {
-            match (left, right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a + b)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a + b)),
-                (Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 + b)),
-                (Value::Float(a), Value::Int(b)) => Ok(Value::Float(a + *b as f64)),
-                _ => Err(ExecutorError::Execution("Type mismatch in +".to_string())),
+        Expr::Cast { expr: inner, .. } => {
+            let (source, target) = (infer_type(inner, schema, registries), cast_target(expr, registries)?);
+            match coercion::cast_context(&source, &target, registries) {
+                Some(_) => Ok(()),
+                None => Err(coercion::cannot_cast(&source, &target)),
             }
         }
+        Expr::TypedString(typed) => coercion::sql_data_type(&typed.data_type, registries).map(|_| ()),
+        _ => Ok(()),
+    }
+}
 
-        Minus => {
-            match (left, right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a - b)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a - b)),
-                (Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 - b)),
-                (Value::Float(a), Value::Int(b)) => Ok(Value::Float(a - *b as f64)),
-                _ => Err(ExecutorError::Execution("Type mismatch in -".to_string())),
-            }
+/// Target type of a `CAST(x AS t)` or `x::t` expression
+fn cast_target(expr: &Expr, registries: &Registries) -> Result<DataType> {
+    match expr {
+        Expr::Cast { kind: CastKind::Cast | CastKind::DoubleColon, data_type, format: None, .. } => {
+            coercion::sql_data_type(data_type, registries)
         }
+        _ => Err(ExecutorError::UnsupportedStatement(format!("Unsupported cast: {}", expr))),
+    }
+}
 
-        Multiply => {
-            match (left, right) {
-                (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a * b)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a * b)),
-                (Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 * b)),
-                (Value::Float(a), Value::Int(b)) => Ok(Value::Float(a * *b as f64)),
-                _ => Err(ExecutorError::Execution("Type mismatch in *".to_string())),
-            }
+/// Name and arguments of a scalar function call
+pub(crate) fn function_call(function: &Function) -> Result<(String, Vec<&Expr>)> {
+    let unsupported = || ExecutorError::UnsupportedStatement(format!("Unsupported function call: {}", function));
+
+    if function.over.is_some() || function.filter.is_some() || !function.within_group.is_empty() {
+        return Err(unsupported());
+    }
+    let name = function.name.0.last()
+        .and_then(|part| part.as_ident())
+        .ok_or_else(unsupported)?;
+    let args = match &function.args {
+        FunctionArguments::None => Vec::new(),
+        FunctionArguments::List(arg_list) if arg_list.duplicate_treatment.is_none() && arg_list.clauses.is_empty() => {
+            arg_list.args.iter()
+                .map(|arg| match arg {
+                    FunctionArg::Unnamed(FunctionArgExpr::Expr(arg)) => Ok(arg),
+                    _ => Err(unsupported()),
+                })
+                .collect::<Result<Vec<_>>>()?
         }
+        _ => return Err(unsupported()),
+    };
+    Ok((name.value.to_ascii_lowercase(), args))
+}
 
-        Divide => {
-            match (left, right) {
-                (Value::Int(a), Value::Int(b)) => {
-                    if *b == 0 {
-                        Err(ExecutorError::Execution("Division by zero".to_string()))
-                    } else {
-                        Ok(Value::Int(a / b))
-                    }
-                }
-                (Value::Float(a), Value::Float(b)) => {
-                    if *b == 0.0 {
-                        Err(ExecutorError::Execution("Division by zero".to_string()))
-                    } else {
-                        Ok(Value::Float(a / b))
-                    }
-                }
-                (Value::Int(a), Value::Float(b)) => {
-                    if *b == 0.0 {
-                        Err(ExecutorError::Execution("Division by zero".to_string()))
-                    } else {
-                        Ok(Value::Float(*a as f64 / b))
-                    }
-                }
-                (Value::Float(a), Value::Int(b)) => {
-                    if *b == 0 {
-                        Err(ExecutorError::Execution("Division by zero".to_string()))
-                    } else {
-                        Ok(Value::Float(a / *b as f64))
-                    }
+/// Call the function registered as `name` that accepts the types of `args`
+///
+/// Functions are strict: a NULL argument makes the result NULL without
+/// calling the function.
+fn eval_function(name: &str, args: &[Value], registries: &Registries) -> Result<Value> {
+    if args.iter().any(|arg| matches!(arg, Value::Null)) {
+        return Ok(Value::Null);
+    }
+    let arg_types = args.iter().map(|arg| value_type(arg, registries)).collect::<Vec<_>>();
+    let function = function_overload(registries, name, &arg_types)?;
+    let args = args.iter()
+        .zip(&function.arg_types)
+        .map(|(arg, target)| coercion::cast(arg, target, CastContext::Implicit, registries))
+        .collect::<Result<Vec<_>>>()?;
+    function.target.execute(&args).map_err(ExecutorError::Execution)
+}
+
+/// Apply the operator registered for `op` and the types of its operands
+///
+/// Like functions, operators are strict.
+fn eval_extension_op(left: &Value, op: &BinaryOperator, right: &Value, registries: &Registries) -> Result<Value> {
+    if matches!(left, Value::Null) || matches!(right, Value::Null) {
+        return Ok(Value::Null);
+    }
+    let (left_type, right_type) = (value_type(left, registries), value_type(right, registries));
+    let operator = operator_overload(registries, op, &left_type, &right_type)?;
+    let left = coercion::cast(left, &operator.arg_types[0], CastContext::Implicit, registries)?;
+    let right = coercion::cast(right, &operator.arg_types[1], CastContext::Implicit, registries)?;
+    operator.target.execute(&left, &right).map_err(ExecutorError::Execution)
+}
+
+/// Return type of the function called by `name(arg_types)`
+fn resolve_function(name: &str, arg_types: &[DataType], registries: &Registries) -> Result<DataType> {
+    function_overload(registries, name, arg_types).map(|function| function.return_type)
+}
+
+/// Return type of the operator `op` applied to operands of the given types
+fn resolve_operator(op: &BinaryOperator, left: &DataType, right: &DataType, registries: &Registries) -> Result<DataType> {
+    operator_overload(registries, op, left, right).map(|operator| operator.return_type)
+}
+
+/// The overload of `name` called with arguments of `arg_types`
+fn function_overload<'a>(
+    registries: &'a Registries,
+    name: &str,
+    arg_types: &[DataType],
+) -> Result<coercion::Resolved<'a, dyn FunctionExtension + 'a>> {
+    coercion::resolve(
+        registries.functions.overloads(name),
+        arg_types,
+        registries,
+        |function, arg_types| function.return_type(arg_types).ok(),
+        || ExecutorError::Sql(sqlstate::AMBIGUOUS_FUNCTION, format!(
+            "function {}({}) is not unique",
+            name,
+            arg_types.iter().map(type_name).collect::<Vec<_>>().join(", ")
+        )),
+    )?
+    .ok_or_else(|| undefined_function(name, arg_types))
+}
+
+/// The operator registered for `op` on operands of the given types
+fn operator_overload<'a>(
+    registries: &'a Registries,
+    op: &BinaryOperator,
+    left: &DataType,
+    right: &DataType,
+) -> Result<coercion::Resolved<'a, dyn OperatorExtension + 'a>> {
+    let symbol = op.to_string();
+    coercion::resolve(
+        registries.operators.candidates(&symbol),
+        &[left.clone(), right.clone()],
+        registries,
+        |operator, types| operator.can_handle(&types[0], &types[1])
+            .then(|| operator.return_type(&types[0], &types[1])),
+        || ExecutorError::Sql(sqlstate::AMBIGUOUS_FUNCTION, format!(
+            "operator is not unique: {} {} {}",
+            type_name(left),
+            op,
+            type_name(right)
+        )),
+    )?
+    .ok_or_else(|| undefined_operator(op, left, right))
+}
+
+/// Whether `op` on operands of these types is left to the operator registry
+///
+/// The built-in operators cover the built-in types; any other operator, or
+/// any operator with an extension-typed operand, comes from extensions.
+fn dispatches_to_registry(op: &BinaryOperator, left: &DataType, right: &DataType) -> bool {
+    use BinaryOperator::*;
+
+    !matches!(op, Eq | NotEq | Gt | Lt | GtEq | LtEq | And | Or | Plus | Minus | Multiply | Divide | Modulo | StringConcat)
+        || matches!(left, DataType::Extension { .. })
+        || matches!(right, DataType::Extension { .. })
+}
+
+fn undefined_function(name: &str, arg_types: &[DataType]) -> ExecutorError {
+    ExecutorError::Sql(sqlstate::UNDEFINED_FUNCTION, format!(
+        "function {}({}) does not exist",
+        name,
+        arg_types.iter().map(type_name).collect::<Vec<_>>().join(", ")
+    ))
+}
+
+fn undefined_operator(op: &BinaryOperator, left: &DataType, right: &DataType) -> ExecutorError {
+    ExecutorError::Sql(sqlstate::UNDEFINED_FUNCTION, format!(
+        "operator does not exist: {} {} {}",
+        type_name(left),
+        op,
+        type_name(right)
+    ))
+}
+
+/// SQL name of a data type, as used in error messages
+pub(crate) fn type_name(data_type: &DataType) -> &str {
+    match data_type {
+        DataType::Int => "bigint",
+        DataType::Float => "double precision",
+        DataType::Numeric(_) => "numeric",
+        DataType::String => "text",
+        DataType::Bool => "boolean",
+        DataType::Null => "unknown",
+        DataType::Date => "date",
+        DataType::Time => "time without time zone",
+        DataType::Timestamp => "timestamp without time zone",
+        DataType::TimestampTz => "timestamp with time zone",
+        DataType::Interval => "interval",
+        DataType::Uuid => "uuid",
+        DataType::Bytea => "bytea",
+        DataType::Extension { type_name, .. } => type_name,
+    }
+}
+
+/// Value of a boolean operand, None for NULL
+///
+/// `context` names the construct for the error raised on other types, as in
+/// "argument of AND must be type boolean".
+fn truth(value: &Value, context: &str, registries: &Registries) -> Result<Option<bool>> {
+    match value {
+        Value::Bool(b) => Ok(Some(*b)),
+        Value::Null => Ok(None),
+        other => Err(ExecutorError::Sql(sqlstate::DATATYPE_MISMATCH, format!(
+            "argument of {} must be type boolean, not type {}",
+            context,
+            type_name(&value_type(other, registries))
+        ))),
+    }
+}
+
+/// Logical negation of a three-valued result when `negated` is set
+fn negate(value: Value, negated: bool, registries: &Registries) -> Result<Value> {
+    if negated {
+        eval_unary_op(&UnaryOperator::Not, &value, registries)
+    } else {
+        Ok(value)
+    }
+}
+
+/// `left IS DISTINCT FROM right`: inequality treating NULLs as equal values
+fn is_distinct(left: &Value, right: &Value, registries: &Registries) -> Result<bool> {
+    match (left, right) {
+        (Value::Null, Value::Null) => Ok(false),
+        (Value::Null, _) | (_, Value::Null) => Ok(true),
+        _ => Ok(!matches!(eval_binary_op(left, &BinaryOperator::Eq, right, registries)?, Value::Bool(true))),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum PatternKind {
+    Like,
+    ILike,
+    Similar,
+}
+
+/// Evaluate `expr LIKE | ILIKE | SIMILAR TO pattern [ESCAPE escape]`
+fn eval_pattern(
+    expr: &Expr,
+    pattern: &Expr,
+    escape: Option<&sqlparser::ast::Value>,
+    kind: PatternKind,
+    row: &Row,
+    schema: &Schema,
+    registries: &Registries,
+) -> Result<Value> {
+    // Without ESCAPE the escape character is a backslash; ESCAPE '' turns it off
+    let escape = match escape {
+        None => Some('\\'),
+        Some(sqlparser::ast::Value::SingleQuotedString(escape)) => {
+            let mut chars = escape.chars();
+            match (chars.next(), chars.next()) {
+                (escape, None) => escape,
+                _ => {
+                    return Err(ExecutorError::Sql(
+                        sqlstate::INVALID_ESCAPE_SEQUENCE,
+                        "invalid escape string".to_string(),
+                    ));
                 }
-                _ => Err(ExecutorError::Execution("Type mismatch in /".to_string())),
             }
         }
+        Some(other) => {
+            return Err(ExecutorError::Sql(
+                sqlstate::DATATYPE_MISMATCH,
+                format!("invalid escape string: {}", other),
+            ));
+        }
+    };
+
+    let (text, pattern) = (eval_expr(expr, row, schema, registries)?, eval_expr(pattern, row, schema, registries)?);
+    let matched = match (&text, &pattern) {
+        (Value::Null, _) | (_, Value::Null) => return Ok(Value::Null),
+        (Value::String(text), Value::String(pattern)) => match kind {
+            PatternKind::Like => pattern::like(text, pattern, escape, false)?,
+            PatternKind::ILike => pattern::like(text, pattern, escape, true)?,
+            PatternKind::Similar => pattern::similar(text, pattern, escape)?,
+        },
+        _ => {
+            let symbol = match kind {
+                PatternKind::Like => "~~",
+                PatternKind::ILike => "~~*",
+                PatternKind::Similar => "~",
+            };
+            return Err(ExecutorError::Sql(sqlstate::UNDEFINED_FUNCTION, format!(
+                "operator does not exist: {} {} {}",
+                type_name(&value_type(&text, registries)),
+                symbol,
+                type_name(&value_type(&pattern, registries))
+            )));
+        }
+    };
+    Ok(Value::Bool(matched))
+}
+
+/// Evaluate a unary operation
+pub(crate) fn eval_unary_op(op: &UnaryOperator, value: &Value, registries: &Registries) -> Result<Value> {
+    match (op, value) {
+        (UnaryOperator::Not, value) => Ok(truth(value, "NOT", registries)?.map_or(Value::Null, |b| Value::Bool(!b))),
+        (UnaryOperator::Minus | UnaryOperator::Plus, Value::Null) => Ok(Value::Null),
+        (UnaryOperator::Minus, Value::Int(n)) => n.checked_neg()
+            .map(Value::Int)
+            .ok_or_else(|| ExecutorError::out_of_range("bigint")),
+        (UnaryOperator::Minus, Value::Float(f)) => Ok(Value::Float(-f)),
+        (UnaryOperator::Minus, Value::Numeric(n)) => Ok(Value::Numeric(-n)),
+        (UnaryOperator::Minus, Value::Interval(interval)) => datetime::negate_interval(interval).map(Value::Interval),
+        (UnaryOperator::Plus, Value::Int(_) | Value::Float(_) | Value::Numeric(_) | Value::Interval(_)) => Ok(value.clone()),
+        (op, value) => Err(ExecutorError::Sql(sqlstate::UNDEFINED_FUNCTION, format!(
+            "operator does not exist: {} {}",
+            op,
+            type_name(&value_type(value, registries))
+        ))),
+    }
+}
 
+/// Result of a float operation, which must stay finite unless an input was not
+fn finite(result: f64, a: f64, b: f64) -> Result<Value> {
+    if result.is_infinite() && a.is_finite() && b.is_finite() {
+        return Err(ExecutorError::Sql(
+            sqlstate::NUMERIC_VALUE_OUT_OF_RANGE,
+            "value out of range: overflow".to_string(),
+        ));
+    }
+    Ok(Value::Float(result))
+}
+
+/// Numeric operands of an arithmetic operator, as floats when either is one,
+/// otherwise as numerics when either is one
+enum Operands {
+    Int(i64, i64),
+    Float(f64, f64),
+    Numeric(BigDecimal, BigDecimal),
+}
+
+fn operands(left: &Value, right: &Value) -> Option<Operands> {
+    match (left, right) {
+        (Value::Int(a), Value::Int(b)) => Some(Operands::Int(*a, *b)),
+        (Value::Float(a), Value::Float(b)) => Some(Operands::Float(*a, *b)),
+        (Value::Int(a), Value::Float(b)) => Some(Operands::Float(*a as f64, *b)),
+        (Value::Float(a), Value::Int(b)) => Some(Operands::Float(*a, *b as f64)),
+        (Value::Numeric(a), Value::Numeric(b)) => Some(Operands::Numeric(a.clone(), b.clone())),
+        (Value::Numeric(a), Value::Int(b)) => Some(Operands::Numeric(a.clone(), (*b).into())),
+        (Value::Int(a), Value::Numeric(b)) => Some(Operands::Numeric((*a).into(), b.clone())),
+        (Value::Numeric(a), Value::Float(b)) => Some(Operands::Float(numeric::to_float(a), *b)),
+        (Value::Float(a), Value::Numeric(b)) => Some(Operands::Float(*a, numeric::to_float(b))),
+        _ => None,
+    }
+}
+
+/// Evaluate a binary operation
+///
+/// Comparisons and arithmetic with a NULL operand are NULL; AND and OR
+/// follow three-valued logic. Operand types the built-in operators do not
+/// cover are looked up in the operator registry.
+pub(crate) fn eval_binary_op(left: &Value, op: &BinaryOperator, right: &Value, registries: &Registries) -> Result<Value> {
+    use BinaryOperator::*;
+
+    if matches!(left, Value::Extension { .. }) || matches!(right, Value::Extension { .. }) {
+        return eval_extension_op(left, op, right, registries);
+    }
+
+    match op {
         // Logical operators
-        And => {
-            match (left, right) {
-                (Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(*a && *b)),
-                (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
-                _ => Err(ExecutorError::Execution("Type mismatch in AND".to_string())),
-            }
+        And => match (truth(left, "AND", registries)?, truth(right, "AND", registries)?) {
+            (Some(false), _) | (_, Some(false)) => Ok(Value::Bool(false)),
+            (Some(true), Some(true)) => Ok(Value::Bool(true)),
+            _ => Ok(Value::Null),
+        },
+        Or => match (truth(left, "OR", registries)?, truth(right, "OR", registries)?) {
+            (Some(true), _) | (_, Some(true)) => Ok(Value::Bool(true)),
+            (Some(false), Some(false)) => Ok(Value::Bool(false)),
+            _ => Ok(Value::Null),
+        },
+
+        // Every other operator is NULL on a NULL operand
+        _ if matches!(left, Value::Null) || matches!(right, Value::Null) => Ok(Value::Null),
+
+        // Date/time arithmetic and comparisons
+        _ if datetime::is_temporal(left) || datetime::is_temporal(right) => match datetime::eval_op(left, op, right) {
+            Some(result) => result,
+            None => eval_extension_op(left, op, right, registries),
+        },
+
+        // Comparison operators; a NaN compares unequal to everything, as
+        // in the vectorized kernels
+        Eq | NotEq | Lt | LtEq | Gt | GtEq => {
+            let ordering = match (left, right) {
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+                (Value::Uuid(a), Value::Uuid(b)) => Some(a.cmp(b)),
+                (Value::Bytea(a), Value::Bytea(b)) => Some(a.cmp(b)),
+                _ => match operands(left, right) {
+                    Some(Operands::Int(a, b)) => Some(a.cmp(&b)),
+                    Some(Operands::Float(a, b)) => a.partial_cmp(&b),
+                    Some(Operands::Numeric(a, b)) => Some(numeric::compare(&a, &b)),
+                    None => return eval_extension_op(left, op, right, registries),
+                },
+            };
+            let result = match ordering {
+                Some(ordering) => match op {
+                    Eq => ordering.is_eq(),
+                    NotEq => ordering.is_ne(),
+                    Lt => ordering.is_lt(),
+                    LtEq => ordering.is_le(),
+                    Gt => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                },
+                None => matches!(op, NotEq),
+            };
+            Ok(Value::Bool(result))
         }
 
-        Or => {
-            match (left, right) {
-                (Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(*a || *b)),
-                (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
-                _ => Err(ExecutorError::Execution("Type mismatch in OR".to_string())),
+        // Arithmetic operators
+        Plus | Minus | Multiply | Divide | Modulo => match operands(left, right) {
+            Some(Operands::Int(a, b)) => {
+                if matches!(op, Divide | Modulo) && b == 0 {
+                    return Err(ExecutorError::division_by_zero());
+                }
+                let result = match op {
+                    Plus => a.checked_add(b),
+                    Minus => a.checked_sub(b),
+                    Multiply => a.checked_mul(b),
+                    Divide => a.checked_div(b),
+                    // Only i64::MIN % -1 overflows, and its remainder is 0
+                    _ => Some(a.checked_rem(b).unwrap_or(0)),
+                };
+                result.map(Value::Int).ok_or_else(|| ExecutorError::out_of_range("bigint"))
             }
-        }
+            Some(Operands::Float(a, b)) => match op {
+                Plus => finite(a + b, a, b),
+                Minus => finite(a - b, a, b),
+                Multiply => finite(a * b, a, b),
+                Divide if b == 0.0 => Err(ExecutorError::division_by_zero()),
+                Divide => finite(a / b, a, b),
+                // Postgres has no float remainder
+                _ => eval_extension_op(left, op, right, registries),
+            },
+            Some(Operands::Numeric(a, b)) => match numeric::eval_op(&a, op, &b) {
+                Some(result) => result,
+                None => eval_extension_op(left, op, right, registries),
+            },
+            None => eval_extension_op(left, op, right, registries),
+        },
 
-        _ => Err(ExecutorError::Execution(format!(
-            "Unsupported binary operator: {:?}",
-            op
-        ))),
+        // String concatenation, with the other operand as text; byte
+        // strings concatenate as bytes
+        StringConcat => match (left, right) {
+            (Value::Bytea(a), Value::Bytea(b)) => Ok(Value::Bytea([a.as_slice(), b.as_slice()].concat())),
+            (Value::String(_), _) | (_, Value::String(_)) => {
+                Ok(Value::String(format!("{}{}", left.as_string(), right.as_string())))
+            }
+            _ => eval_extension_op(left, op, right, registries),
+        },
+
+        _ => eval_extension_op(left, op, right, registries),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::executor::evaluator::Result;
+use crate::executor::sort::SortMethod;
+use crate::planner::Operator;
+use crate::storage;
+use crate::types::Schema;
+
+/// How a plan node ran, over every time it was started
+#[derive(Debug, Clone, Default)]
+pub struct NodeMetrics {
+    /// Times the node was started: once per run of a subquery or probe of an
+    /// index nested loop join
+    pub loops: u64,
+    /// Rows returned over all loops
+    pub rows: u64,
+    /// Time until each loop returned its first row, summed over loops
+    pub startup: Duration,
+    /// Time spent in the node and its inputs, summed over loops
+    pub total: Duration,
+    /// Blocks and pages read by the node and its inputs
+    pub block_reads: u64,
+    /// How the last loop of a sort node sorted its input
+    pub sort_method: Option<SortMethod>,
+}
+
+impl NodeMetrics {
+    /// Record a loop that started at `started` and returned `rows` rows at once
+    pub fn record_loop(&mut self, started: Started, rows: u64) {
+        let (elapsed, reads) = started.finish();
+        self.loops += 1;
+        self.rows += rows;
+        self.startup += elapsed;
+        self.total += elapsed;
+        self.block_reads += reads;
+    }
+}
+
+/// Clock and read counter at the start of some work
+#[derive(Debug, Clone, Copy)]
+pub struct Started {
+    at: Instant,
+    reads: u64,
+}
+
+impl Started {
+    pub fn now() -> Self {
+        Started {
+            at: Instant::now(),
+            reads: storage::block_reads(),
+        }
+    }
+
+    /// Time taken and blocks read since the start
+    fn finish(self) -> (Duration, u64) {
+        (self.at.elapsed(), storage::block_reads() - self.reads)
+    }
+}
+
+/// Wrap every node of a plan, including subquery plans, in an Instrument node
+pub fn instrument(mut plan: Operator) -> Operator {
+    if let Operator::Apply { subplans, .. } = &mut plan {
+        for subplan in subplans {
+            wrap(&mut subplan.plan);
+        }
+    }
+    for child in plan.children_mut() {
+        wrap(child);
+    }
+    Operator::Instrument {
+        input: Box::new(plan),
+        metrics: Arc::default(),
+    }
+}
+
+fn wrap(plan: &mut Operator) {
+    let placeholder = Operator::Values {
+        schema: Schema::new(Vec::new()),
+        rows: Vec::new(),
+    };
+    let inner = std::mem::replace(plan, placeholder);
+    *plan = instrument(inner);
+}
+
+/// A stream of rows or batches that records what it returns
+///
+/// Time is only counted while the stream is producing an item, so time the
+/// consumer spends between items is not charged to this node.
+pub struct Instrumented<T> {
+    input: Box<dyn Iterator<Item = Result<T>> + Send>,
+    metrics: Arc<Mutex<NodeMetrics>>,
+    /// Rows in an item
+    count: fn(&T) -> u64,
+    /// Time spent producing items so far in this loop
+    elapsed: Duration,
+    first: bool,
+}
+
+impl<T> Instrumented<T> {
+    /// Start a loop of the node, whose input took from `started` until now to set up
+    pub fn new(
+        input: Box<dyn Iterator<Item = Result<T>> + Send>,
+        metrics: Arc<Mutex<NodeMetrics>>,
+        started: Started,
+        count: fn(&T) -> u64,
+    ) -> Self {
+        let (elapsed, reads) = started.finish();
+        {
+            let mut metrics = metrics.lock();
+            metrics.loops += 1;
+            metrics.total += elapsed;
+            metrics.block_reads += reads;
+        }
+        Instrumented {
+            input,
+            metrics,
+            count,
+            elapsed,
+            first: true,
+        }
+    }
+}
+
+impl<T> Iterator for Instrumented<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let started = Started::now();
+        let item = self.input.next();
+        let (elapsed, reads) = started.finish();
+        self.elapsed += elapsed;
+
+        let mut metrics = self.metrics.lock();
+        metrics.total += elapsed;
+        metrics.block_reads += reads;
+        if let Some(Ok(item)) = &item {
+            metrics.rows += (self.count)(item);
+            if self.first {
+                metrics.startup += self.elapsed;
+                self.first = false;
+            }
+        }
+        item
+    }
+}
@@ -0,0 +1,451 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use bigdecimal::ToPrimitive;
+use sqlparser::ast::Expr;
+use tracing::debug;
+
+use crate::executor::error::ExecutorError;
+use crate::executor::evaluator::{self, Result};
+use crate::executor::sort;
+use crate::extensions::registry::Registries;
+use crate::planner::SortKey;
+use crate::planner::join::{self, JoinCondition, JoinType};
+use crate::types::{HashKey, Row, Schema, Value};
+
+/// Join by comparing every left row with every right row
+pub fn nested_loop_join(
+    left_schema: &Schema,
+    left_rows: Vec<Row>,
+    right_schema: &Schema,
+    right_rows: Vec<Row>,
+    condition: &JoinCondition,
+    registries: &Registries,
+) -> Result<(Schema, Vec<Row>)> {
+    debug!(left = left_rows.len(), right = right_rows.len(), "nested loop join");
+    let mut joiner = Joiner::new(left_schema, right_schema, condition, registries)?;
+
+    let right_keys = right_rows.iter()
+        .map(|row| joiner.right_keys(row))
+        .collect::<Result<Vec<_>>>()?;
+    let mut right_matched = vec![false; right_rows.len()];
+
+    for left in &left_rows {
+        let left_keys = joiner.left_keys(left)?;
+        let mut matched = false;
+        for (idx, right) in right_rows.iter().enumerate() {
+            if keys_equal(left_keys.as_deref(), right_keys[idx].as_deref())?
+                && joiner.filter_passes(left, right)?
+            {
+                joiner.emit(Some(left), Some(right));
+                matched = true;
+                right_matched[idx] = true;
+                if joiner.first_match_only() {
+                    break;
+                }
+            }
+        }
+        joiner.finish_left(left, matched);
+    }
+
+    joiner.emit_unmatched_right(&right_rows, &right_matched);
+    Ok(joiner.finish())
+}
+
+/// Join through a hash table built over the right input
+pub fn hash_join(
+    left_schema: &Schema,
+    left_rows: Vec<Row>,
+    right_schema: &Schema,
+    right_rows: Vec<Row>,
+    condition: &JoinCondition,
+    registries: &Registries,
+) -> Result<(Schema, Vec<Row>)> {
+    debug!(left = left_rows.len(), right = right_rows.len(), "hash join");
+    let mut joiner = Joiner::new(left_schema, right_schema, condition, registries)?;
+
+    // Rows with a NULL key never match and are not hashed
+    let mut table: HashMap<Vec<HashKey>, Vec<usize>> = HashMap::new();
+    for (idx, row) in right_rows.iter().enumerate() {
+        if let Some(keys) = joiner.right_keys(row)? {
+            table.entry(hash_keys(&keys)?).or_default().push(idx);
+        }
+    }
+    let mut right_matched = vec![false; right_rows.len()];
+
+    for left in &left_rows {
+        let mut matched = false;
+        if let Some(keys) = joiner.left_keys(left)?
+            && let Some(candidates) = table.get(&hash_keys(&keys)?)
+        {
+            for &idx in candidates {
+                let right = &right_rows[idx];
+                if joiner.filter_passes(left, right)? {
+                    joiner.emit(Some(left), Some(right));
+                    matched = true;
+                    right_matched[idx] = true;
+                    if joiner.first_match_only() {
+                        break;
+                    }
+                }
+            }
+        }
+        joiner.finish_left(left, matched);
+    }
+
+    joiner.emit_unmatched_right(&right_rows, &right_matched);
+    Ok(joiner.finish())
+}
+
+/// Join by sorting both inputs on the keys and merging runs of equal keys
+///
+/// Inputs are sorted with the external sort, so neither side has to fit in
+/// `work_mem`. The sorted inputs are read as the join advances: only the
+/// run of right rows sharing the current key is held in memory, and output
+/// rows are returned as each left row is joined.
+pub fn merge_join(
+    left_schema: &Schema,
+    left_rows: impl Iterator<Item = Result<Row>>,
+    right_schema: &Schema,
+    right_rows: impl Iterator<Item = Result<Row>>,
+    condition: &JoinCondition,
+    work_mem: usize,
+    registries: &Registries,
+) -> Result<(Schema, MergeJoin)> {
+    debug!("merge join");
+    let joiner = Joiner::new(left_schema, right_schema, condition, registries)?;
+
+    let sort_keys = |keys: &[Expr]| keys.iter()
+        .map(|expr| SortKey { expr: expr.clone(), descending: false, nulls_first: false })
+        .collect::<Vec<_>>();
+    let left = sort::sort(left_schema, left_rows, &sort_keys(&condition.left_keys), None, work_mem, registries)?;
+    let mut right = sort::sort(right_schema, right_rows, &sort_keys(&condition.right_keys), None, work_mem, registries)?;
+    let right_next = match right.next().transpose()? {
+        Some(row) => Some(joiner.keyed_right(row)?),
+        None => None,
+    };
+
+    Ok((joiner.schema.clone(), MergeJoin {
+        joiner,
+        left,
+        right,
+        right_next,
+        run: Vec::new(),
+        run_key: None,
+        pending: Vec::new().into_iter(),
+        done: false,
+    }))
+}
+
+/// Output of a merge join, produced a left row at a time
+pub struct MergeJoin {
+    joiner: Joiner,
+    left: sort::SortedRows,
+    right: sort::SortedRows,
+    /// Next right row not yet taken into a run, with its keys
+    right_next: Option<(Row, Option<Vec<Value>>)>,
+    /// Right rows with key `run_key`, and whether each has matched
+    run: Vec<(Row, bool)>,
+    run_key: Option<Vec<Value>>,
+    /// Output rows not yet returned
+    pending: std::vec::IntoIter<Row>,
+    done: bool,
+}
+
+impl MergeJoin {
+    /// Take the next right row and read the one after it
+    fn take_right(&mut self) -> Result<Option<(Row, Option<Vec<Value>>)>> {
+        let next = match self.right.next().transpose()? {
+            Some(row) => Some(self.joiner.keyed_right(row)?),
+            None => None,
+        };
+        Ok(std::mem::replace(&mut self.right_next, next))
+    }
+
+    /// Emit the unmatched rows of the current run, for right and full joins
+    fn finish_run(&mut self) {
+        let (rows, matched): (Vec<_>, Vec<_>) = std::mem::take(&mut self.run).into_iter().unzip();
+        self.joiner.emit_unmatched_right(&rows, &matched);
+        self.run_key = None;
+    }
+
+    /// Join the next left row, leaving its output in the joiner
+    /// Returns false once every row of both inputs has been joined
+    fn advance(&mut self) -> Result<bool> {
+        let Some(left) = self.left.next().transpose()? else {
+            // Right rows past the last left key match nothing
+            self.finish_run();
+            while let Some((row, _)) = self.take_right()? {
+                self.joiner.emit_unmatched_right(&[row], &[false]);
+            }
+            return Ok(false);
+        };
+
+        // NULL keys sort last and match nothing
+        let Some(key) = self.joiner.left_keys(&left)? else {
+            self.joiner.finish_left(&left, false);
+            return Ok(true);
+        };
+
+        let same_run = match &self.run_key {
+            Some(run_key) => compare_keys(run_key, &key)? == Ordering::Equal,
+            None => false,
+        };
+        if !same_run {
+            self.finish_run();
+
+            // Skip right rows with smaller keys
+            while let Some((_, Some(right_key))) = &self.right_next
+                && compare_keys(right_key, &key)? == Ordering::Less
+            {
+                if let Some((row, _)) = self.take_right()? {
+                    self.joiner.emit_unmatched_right(&[row], &[false]);
+                }
+            }
+
+            // Run of right rows with this key
+            while let Some((_, Some(right_key))) = &self.right_next
+                && compare_keys(right_key, &key)? == Ordering::Equal
+            {
+                if let Some((row, _)) = self.take_right()? {
+                    self.run.push((row, false));
+                }
+            }
+            self.run_key = Some(key);
+        }
+
+        // The left row pairs with the run
+        let mut matched = false;
+        for (right, right_matched) in &mut self.run {
+            if self.joiner.filter_passes(&left, right)? {
+                self.joiner.emit(Some(&left), Some(right));
+                matched = true;
+                *right_matched = true;
+                if self.joiner.first_match_only() {
+                    break;
+                }
+            }
+        }
+        self.joiner.finish_left(&left, matched);
+        Ok(true)
+    }
+}
+
+impl Iterator for MergeJoin {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.pending.next() {
+                return Some(Ok(row));
+            }
+            if self.done {
+                return None;
+            }
+            match self.advance() {
+                Ok(more) => {
+                    self.done = !more;
+                    self.pending = self.joiner.take_output().into_iter();
+                }
+                Err(e) => {
+                    // Stop after reporting the error
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Join by probing an index on the right table for each left row
+///
+/// `lookup` returns the right rows whose indexed column equals a value. The
+/// first key pair drives the lookup; remaining keys are rechecked.
+pub fn index_nested_loop_join(
+    left_schema: &Schema,
+    left_rows: Vec<Row>,
+    right_schema: &Schema,
+    condition: &JoinCondition,
+    mut lookup: impl FnMut(&Value) -> Result<Vec<Row>>,
+    registries: &Registries,
+) -> Result<(Schema, Vec<Row>)> {
+    debug!(left = left_rows.len(), "index nested loop join");
+    let mut joiner = Joiner::new(left_schema, right_schema, condition, registries)?;
+
+    for left in &left_rows {
+        let mut matched = false;
+        if let Some(keys) = joiner.left_keys(left)? {
+            for right in lookup(&keys[0])? {
+                let right_keys = joiner.right_keys(&right)?;
+                if keys_equal(Some(&keys), right_keys.as_deref())?
+                    && joiner.filter_passes(left, &right)?
+                {
+                    joiner.emit(Some(left), Some(&right));
+                    matched = true;
+                    if joiner.first_match_only() {
+                        break;
+                    }
+                }
+            }
+        }
+        joiner.finish_left(left, matched);
+    }
+
+    Ok(joiner.finish())
+}
+
+/// Shared state for building join output
+struct Joiner {
+    left_schema: Schema,
+    right_schema: Schema,
+    condition: JoinCondition,
+    /// Left columns followed by right columns, for evaluating the filter
+    combined: Schema,
+    /// Left and right positions of each USING column
+    using: Vec<(usize, usize)>,
+    schema: Schema,
+    output: Vec<Row>,
+    registries: Registries,
+}
+
+impl Joiner {
+    fn new(left_schema: &Schema, right_schema: &Schema, condition: &JoinCondition, registries: &Registries) -> Result<Self> {
+        // Semi and anti joins return left rows only
+        let schema = match condition.join_type {
+            JoinType::Semi | JoinType::Anti => left_schema.clone(),
+            _ => join::output_schema(left_schema, right_schema, &condition.using)?,
+        };
+        Ok(Joiner {
+            left_schema: left_schema.clone(),
+            right_schema: right_schema.clone(),
+            condition: condition.clone(),
+            combined: join::output_schema(left_schema, right_schema, &[])?,
+            using: join::using_positions(left_schema, right_schema, &condition.using)?,
+            schema,
+            output: Vec::new(),
+            registries: registries.clone(),
+        })
+    }
+
+    /// Left key values, or None if any is NULL (the row can match nothing)
+    fn left_keys(&self, row: &Row) -> Result<Option<Vec<Value>>> {
+        eval_keys(&self.condition.left_keys, row, &self.left_schema, &self.registries)
+    }
+
+    fn right_keys(&self, row: &Row) -> Result<Option<Vec<Value>>> {
+        eval_keys(&self.condition.right_keys, row, &self.right_schema, &self.registries)
+    }
+
+    /// A right row along with its key values
+    fn keyed_right(&self, row: Row) -> Result<(Row, Option<Vec<Value>>)> {
+        let keys = self.right_keys(&row)?;
+        Ok((row, keys))
+    }
+
+    fn filter_passes(&self, left: &Row, right: &Row) -> Result<bool> {
+        let Some(filter) = &self.condition.filter else {
+            return Ok(true);
+        };
+        let mut values = left.values.clone();
+        values.extend(right.values.iter().cloned());
+        Ok(matches!(evaluator::eval_expr(filter, &Row::new(values), &self.combined, &self.registries)?, Value::Bool(true)))
+    }
+
+    /// Whether a left row's first match decides its output (semi and anti joins)
+    fn first_match_only(&self) -> bool {
+        matches!(self.condition.join_type, JoinType::Semi | JoinType::Anti)
+    }
+
+    /// Append an output row for a matched pair; a missing side is padded with NULLs
+    fn emit(&mut self, left: Option<&Row>, right: Option<&Row>) {
+        if self.first_match_only() {
+            return;
+        }
+        let left_value = |idx: usize| left.map(|row| row.values[idx].clone()).unwrap_or(Value::Null);
+        let right_value = |idx: usize| right.map(|row| row.values[idx].clone()).unwrap_or(Value::Null);
+
+        let mut values = Vec::with_capacity(self.schema.len());
+        for &(left_idx, right_idx) in &self.using {
+            values.push(match (left_value(left_idx), right_value(right_idx)) {
+                (Value::Null, right) if left.is_none() || self.condition.join_type == JoinType::Full => right,
+                (left, _) => left,
+            });
+        }
+        values.extend((0..self.left_schema.len()).map(left_value));
+        values.extend((0..self.right_schema.len()).map(right_value));
+        self.output.push(Row::new(values));
+    }
+
+    /// Output owed for a left row once all its matches have been emitted
+    fn finish_left(&mut self, left: &Row, matched: bool) {
+        match (self.condition.join_type, matched) {
+            (JoinType::Semi, true) | (JoinType::Anti, false) => self.output.push(left.clone()),
+            (JoinType::Left | JoinType::Full, false) => self.emit(Some(left), None),
+            _ => {}
+        }
+    }
+
+    fn emit_unmatched_right(&mut self, right_rows: &[Row], matched: &[bool]) {
+        if matches!(self.condition.join_type, JoinType::Right | JoinType::Full) {
+            for (right, _) in right_rows.iter().zip(matched).filter(|(_, matched)| !**matched) {
+                self.emit(None, Some(right));
+            }
+        }
+    }
+
+    /// Output rows built since the last call
+    fn take_output(&mut self) -> Vec<Row> {
+        std::mem::take(&mut self.output)
+    }
+
+    fn finish(self) -> (Schema, Vec<Row>) {
+        (self.schema, self.output)
+    }
+}
+
+fn eval_keys(keys: &[Expr], row: &Row, schema: &Schema, registries: &Registries) -> Result<Option<Vec<Value>>> {
+    let mut values = Vec::with_capacity(keys.len());
+    for key in keys {
+        match evaluator::eval_expr(key, row, schema, registries)? {
+            Value::Null => return Ok(None),
+            value => values.push(value),
+        }
+    }
+    Ok(Some(values))
+}
+
+/// Whether two key tuples are equal; NULL keys (None) never are
+fn keys_equal(left: Option<&[Value]>, right: Option<&[Value]>) -> Result<bool> {
+    match (left, right) {
+        (Some(left), Some(right)) => Ok(compare_keys(left, right)? == Ordering::Equal),
+        _ => Ok(false),
+    }
+}
+
+fn compare_keys(left: &[Value], right: &[Value]) -> Result<Ordering> {
+    for (left, right) in left.iter().zip(right) {
+        let ordering = evaluator::compare_values(left, right)?;
+        if ordering != Ordering::Equal {
+            return Ok(ordering);
+        }
+    }
+    Ok(Ordering::Equal)
+}
+
+/// Hash keys for join values; integral floats and numerics hash like the
+/// equal integer
+pub fn hash_keys(values: &[Value]) -> Result<Vec<HashKey>> {
+    values.iter()
+        .map(|value| match value {
+            Value::Float(f) if f.fract() == 0.0 && *f >= i64::MIN as f64 && *f < i64::MAX as f64 => {
+                Ok(HashKey::Int(*f as i64))
+            }
+            Value::Numeric(n) if n.is_integer() && n.to_i64().is_some() => {
+                Ok(HashKey::Int(n.to_i64().unwrap_or_default()))
+            }
+            value => value.hash_key().ok_or_else(|| ExecutorError::Execution(
+                "could not identify an equality operator for extension type".to_string(),
+            )),
+        })
+        .collect()
+}
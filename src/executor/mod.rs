@@ -1,36 +1,84 @@
+pub mod aggregate;
+pub mod analyze;
+pub mod batch;
+pub mod bytes;
 pub mod error;
 pub mod evaluator;
+pub mod instrument;
+pub mod join;
+pub mod numeric;
+pub mod coercion;
+pub mod datetime;
+pub mod pattern;
+pub mod plan_cache;
+pub mod set_operation;
+pub mod sort;
+pub mod subquery;
+pub mod vectorized;
+pub mod window;
 
+use bigdecimal::{BigDecimal, Zero};
 use std::sync::Arc;
+use std::time::Instant;
 use futures::stream;
 use pgwire::api::results::{DataRowEncoder, FieldFormat, FieldInfo, QueryResponse, Response, Tag};
+use pgwire::error::PgWireError;
+use pgwire::messages::data::DataRow;
 use pgwire::api::Type;
+use pgwire::api::portal::Format;
 use sqlparser::ast::Statement;
 use tracing::{debug, info};
 
-use crate::config::Config;
+use crate::config::{ByteaOutput, Config, PlanCacheMode, Settings};
+use crate::executor::batch::{Batch, BATCH_SIZE};
 use crate::executor::error::ExecutorError;
-use crate::planner::{self, Operator};
+use crate::executor::instrument::{Instrumented, Started};
+use crate::executor::plan_cache::{CachedPlan, PlanCache, PlanSlot, PlanUse, PreparedPlans};
+use crate::executor::vectorized::CompiledExpr;
+use crate::extensions::registry::{Registries, TypeRegistry};
+use crate::planner::{self, BitmapLookup, Operator, PlanContext, RangeBound, SortKey};
+use crate::planner::join::JoinMethod;
 use crate::parser;
-use crate::storage::Database;
-use crate::types::{Row, Value, Schema};
+use crate::storage::{Database, TuplePointer};
+use crate::storage::bitmap::TupleBitmap;
+use crate::storage::base::BLOCK_SIZE;
+use crate::types::{Column, DataType, Interval, Row, Value, Schema};
 
 pub type Result<T> = std::result::Result<T, ExecutorError>;
 
 pub(crate) struct Executor {
     db: Arc<parking_lot::RwLock<Database>>,
+    /// The database's registries, resolved once here rather than per plan
+    registries: Registries,
+    plan_cache: PlanCache,
+}
+
+/// A statement parsed for the extended query protocol
+#[derive(Debug, Clone)]
+pub(crate) struct Prepared {
+    /// `None` for an empty query string
+    pub statement: Option<Statement>,
+    /// Type of each `$n` parameter
+    pub parameter_types: Vec<DataType>,
+    pub plans: Arc<PreparedPlans>,
 }
 
 impl Executor {
-    pub fn new(config: &Config) -> Self {
-        Executor {
-            db: Arc::new(parking_lot::RwLock::new(Database::new(config)))
-        }
+    /// Open the database `config` describes, failing if its files cannot be opened
+    pub fn new(config: &Config) -> std::result::Result<Self, String> {
+        let db = Database::new(config)?;
+        Ok(Executor {
+            registries: db.registries.clone(),
+            db: Arc::new(parking_lot::RwLock::new(db)),
+            plan_cache: PlanCache::new(),
+        })
     }
 
-    pub fn execute(&self, query: &str) -> Result<Vec<Response>> {
+    /// Execute a query string under a session's `settings`, which its `SET`
+    /// statements change
+    pub fn execute(&self, query: &str, settings: &mut Settings) -> Result<Vec<Response>> {
         debug!("parsing query");
-        let stmts = parser::parse(query)?;
+        let stmts = self.plan_cache.statements(query, parser::parse)?;
 
         if stmts.is_empty() {
             debug!("empty query");
@@ -40,384 +88,1508 @@ impl Executor {
         info!(statement_count = stmts.len(), "parsed statements");
 
         let mut responses = Vec::new();
-        for (idx, stmt) in stmts.iter().enumerate() {
+        for (idx, cached) in stmts.iter().enumerate() {
             debug!(statement_idx = idx, "planning statement");
 
-            // Handle DDL/DML/transactions directly (not via planner)
-            let response = match stmt {
-                Statement::StartTransaction { .. } => {
-                    debug!("executing: start transaction");
-                    Ok(Response::TransactionStart(Tag::new("BEGIN")))
+            // Later statements in the string must not change what this one returns
+            responses.push(self.execute_statement(&cached.statement, Some(&cached.plan), idx + 1 < stmts.len(), &Format::UnifiedText, settings)?);
+        }
+
+        info!(response_count = responses.len(), "execution complete");
+        Ok(responses)
+    }
+
+    /// Execute one statement
+    ///
+    /// A plan for it is looked up in and saved to `slot`. With `buffer`, result
+    /// rows are collected before the response is returned (see `execute_plan`).
+    /// Result columns are sent in `formats`.
+    fn execute_statement(
+        &self,
+        stmt: &Statement,
+        slot: Option<&PlanSlot>,
+        buffer: bool,
+        formats: &Format,
+        settings: &mut Settings,
+    ) -> Result<Response> {
+        // Handle DDL/DML/transactions directly (not via planner)
+        match stmt {
+            Statement::StartTransaction { .. } => {
+                debug!("executing: start transaction");
+                Ok(Response::TransactionStart(Tag::new("BEGIN")))
+            }
+            Statement::Rollback { .. } => {
+                debug!("executing: rollback");
+                Ok(Response::TransactionEnd(Tag::new("ROLLBACK")))
+            }
+            Statement::Commit { .. } => {
+                debug!("executing: commit");
+                Ok(Response::TransactionEnd(Tag::new("COMMIT")))
+            }
+            Statement::Set(set) => {
+                debug!("executing: set");
+                let (name, value) = planner::extract_set(set)?;
+                match name.as_str() {
+                    "work_mem" => settings.work_mem = parse_memory(&value)?,
+                    "enable_nestloop" => settings.enable_nestloop = parse_bool(&name, &value)?,
+                    "enable_hashjoin" => settings.enable_hashjoin = parse_bool(&name, &value)?,
+                    "enable_mergejoin" => settings.enable_mergejoin = parse_bool(&name, &value)?,
+                    "enable_indexscan" => settings.enable_indexscan = parse_bool(&name, &value)?,
+                    "enable_bitmapscan" => settings.enable_bitmapscan = parse_bool(&name, &value)?,
+                    "plan_cache_mode" => settings.plan_cache_mode = parse_plan_cache_mode(&value)?,
+                    "bytea_output" => settings.bytea_output = parse_bytea_output(&value)?,
+                    _ => {
+                        return Err(ExecutorError::Execution(format!(
+                            "unrecognized configuration parameter \"{}\"",
+                            name
+                        )));
+                    }
                 }
-                Statement::Rollback { .. } => {
-                    debug!("executing: rollback");
-                    Ok(Response::TransactionEnd(Tag::new("ROLLBACK")))
+                debug!(parameter = %name, value = %value, "parameter updated");
+                Ok(Response::Execution(Tag::new("SET")))
+            }
+            Statement::ShowVariable { variable } => {
+                debug!("executing: show");
+                let name = variable.iter()
+                    .map(|ident| ident.value.to_ascii_lowercase())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if name == "plan_cache" {
+                    let rows = self.plan_cache.stats().rows().into_iter()
+                        .map(|(statistic, value)| Row::new(vec![Value::String(statistic.to_string()), Value::String(value)]))
+                        .collect();
+                    return self.rows_to_response(materialized(rows), &plan_cache_schema(), formats, settings);
                 }
-                Statement::Commit { .. } => {
-                    debug!("executing: commit");
-                    Ok(Response::TransactionEnd(Tag::new("COMMIT")))
+                let on_off = |enabled: bool| if enabled { "on" } else { "off" }.to_string();
+                let value = match name.as_str() {
+                    "work_mem" => format_memory(settings.work_mem),
+                    "enable_nestloop" => on_off(settings.enable_nestloop),
+                    "enable_hashjoin" => on_off(settings.enable_hashjoin),
+                    "enable_mergejoin" => on_off(settings.enable_mergejoin),
+                    "enable_indexscan" => on_off(settings.enable_indexscan),
+                    "enable_bitmapscan" => on_off(settings.enable_bitmapscan),
+                    "plan_cache_mode" => settings.plan_cache_mode.name().to_string(),
+                    "bytea_output" => settings.bytea_output.name().to_string(),
+                    _ => {
+                        return Err(ExecutorError::Execution(format!(
+                            "unrecognized configuration parameter \"{}\"",
+                            name
+                        )));
+                    }
+                };
+                let schema = Schema::new(vec![crate::types::Column {
+                    name,
+                    data_type: crate::types::DataType::String,
+                    is_primary_key: false,
+                }]);
+                self.rows_to_response(materialized(vec![Row::new(vec![Value::String(value)])]), &schema, formats, settings)
+            }
+            Statement::CreateTable(ct) => {
+                debug!("executing: create table");
+                let (table_name, schema, _primary_key_col) = planner::extract_create_table(ct)?;
+                let mut db = self.db.write();
+                db.create_table(table_name.clone(), schema)
+                    .map_err(|e| ExecutorError::Execution(e))?;
+                self.plan_cache.invalidate();
+                debug!(table = %table_name, "table created");
+                Ok(Response::Execution(Tag::new("CREATE TABLE")))
+            }
+            Statement::Insert(ins) => {
+                debug!("executing: insert");
+                let (table_name, row_exprs) = planner::extract_insert(ins)?;
+
+                // Get the schema from the table
+                let db = self.db.read();
+                let schema = db.get_schema(&table_name)
+                    .map_err(|e| ExecutorError::Execution(e))?;
+                drop(db);
+
+                // Evaluate each row of expressions, converting values to their column's type
+                let mut rows_to_insert = Vec::new();
+                for row_exprs_for_row in row_exprs {
+                    let mut values = Vec::new();
+                    // Create an empty row for schema context (INSERT doesn't reference existing columns)
+                    let empty_row = Row::new(vec![]);
+                    for (idx, expr) in row_exprs_for_row.iter().enumerate() {
+                        let val = evaluator::eval_expr(expr, &empty_row, &schema, &self.registries)?;
+                        let val = match schema.columns.get(idx) {
+                            Some(column) => assign_expr(expr, val, column, &self.registries)?,
+                            None => val,
+                        };
+                        values.push(val);
+                    }
+                    rows_to_insert.push(Row::new(values));
                 }
-                Statement::CreateTable(ct) => {
-                    debug!("executing: create table");
-                    let (table_name, schema, _primary_key_col) = planner::extract_create_table(ct)?;
-                    let mut db = self.db.write();
-                    db.create_table(table_name.clone(), schema)
+
+                // Insert the rows
+                let count = rows_to_insert.len();
+                let mut db = self.db.write();
+                for row in rows_to_insert {
+                    db.insert_row(&table_name, row)
                         .map_err(|e| ExecutorError::Execution(e))?;
-                    debug!(table = %table_name, "table created");
-                    Ok(Response::EmptyQuery)
                 }
-                Statement::Insert(ins) => {
-                    debug!("executing: insert");
-                    let (table_name, row_exprs) = planner::extract_insert(ins)?;
+                debug!(table = %table_name, count, "rows inserted");
+                // The OID field is always 0 since tables have no OIDs
+                Ok(Response::Execution(Tag::new("INSERT").with_oid(0).with_rows(count)))
+            }
+            Statement::Update { .. } => {
+                debug!("executing: update");
+                let (table_name, assignments, selection) = planner::extract_update(stmt)?;
+
+                let mut db = self.db.write();
+                let schema = db.get_schema(&table_name)
+                    .map_err(ExecutorError::Execution)?;
+
+                // Resolve SET targets to column positions
+                let assignments = assignments.into_iter()
+                    .map(|(column, expr)| {
+                        schema.get_column_index(&column)
+                            .map(|idx| (idx, expr))
+                            .ok_or_else(|| ExecutorError::Execution(format!(
+                                "Column \"{}\" of relation \"{}\" does not exist",
+                                column, table_name
+                            )))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                // Collect targets before writing so new versions are not revisited
+                let targets = matching_tuples(&db, &table_name, &schema, selection.as_ref(), &self.registries)?;
+
+                // Evaluate all new rows against the old versions first
+                let mut new_rows = Vec::with_capacity(targets.len());
+                for (ptr, row) in &targets {
+                    let mut new_row = row.clone();
+                    for (idx, expr) in &assignments {
+                        let value = evaluator::eval_expr(expr, row, &schema, &self.registries)?;
+                        new_row.values[*idx] = assign_expr(expr, value, &schema.columns[*idx], &self.registries)?;
+                    }
+                    new_rows.push((*ptr, new_row));
+                }
+
+                let txid = db.allocate_txid();
+                let count = new_rows.len();
+                db.update_rows(&table_name, new_rows, txid)
+                    .map_err(ExecutorError::Execution)?;
+                debug!(table = %table_name, rows = count, "rows updated");
+                Ok(Response::Execution(Tag::new("UPDATE").with_rows(count)))
+            }
+            Statement::Delete(_) => {
+                debug!("executing: delete");
+                let (table_name, selection) = planner::extract_delete(stmt)?;
+
+                let mut db = self.db.write();
+                let schema = db.get_schema(&table_name)
+                    .map_err(ExecutorError::Execution)?;
+
+                let targets = matching_tuples(&db, &table_name, &schema, selection.as_ref(), &self.registries)?;
+
+                let txid = db.allocate_txid();
+                for (ptr, _) in &targets {
+                    db.delete_row(&table_name, *ptr, txid)
+                        .map_err(ExecutorError::Execution)?;
+                }
+                debug!(table = %table_name, rows = targets.len(), "rows deleted");
+                Ok(Response::Execution(Tag::new("DELETE").with_rows(targets.len())))
+            }
+            Statement::Truncate { .. } => {
+                debug!("executing: truncate");
+                let table_names = planner::extract_truncate(stmt)?;
+
+                let mut db = self.db.write();
+                // Validate every table before resetting any of them
+                for table_name in &table_names {
+                    db.get_table(table_name)
+                        .map_err(ExecutorError::Execution)?;
+                }
+                for table_name in &table_names {
+                    db.truncate_table(table_name)
+                        .map_err(ExecutorError::Execution)?;
+                }
+                self.plan_cache.invalidate();
+                debug!(tables = ?table_names, "tables truncated");
+                Ok(Response::Execution(Tag::new("TRUNCATE TABLE")))
+            }
+            Statement::Analyze { .. } => {
+                debug!("executing: analyze");
+                let (table_name, columns) = planner::extract_analyze(stmt)?;
 
-                    // Get the schema from the table
+                let statistics = {
                     let db = self.db.read();
                     let schema = db.get_schema(&table_name)
-                        .map_err(|e| ExecutorError::Execution(e))?;
-                    drop(db);
-
-                    // Evaluate each row of expressions
-                    let mut rows_to_insert = Vec::new();
-                    for row_exprs_for_row in row_exprs {
-                        let mut values = Vec::new();
-                        // Create an empty row for schema context (INSERT doesn't reference existing columns)
-                        let empty_row = Row::new(vec![]);
-                        for expr in &row_exprs_for_row {
-                            let val = evaluator::eval_expr(expr, &empty_row, &schema)?;
-                            values.push(val);
+                        .map_err(ExecutorError::Execution)?;
+                    let positions = if columns.is_empty() {
+                        (0..schema.columns.len()).collect()
+                    } else {
+                        columns.iter()
+                            .map(|column| schema.get_column_index(column)
+                                .ok_or_else(|| ExecutorError::Execution(format!(
+                                    "column \"{}\" of relation \"{}\" does not exist",
+                                    column, table_name
+                                ))))
+                            .collect::<Result<Vec<_>>>()?
+                    };
+                    let page_count = db.estimated_size(&table_name)
+                        .map_err(ExecutorError::Execution)? / BLOCK_SIZE as u64;
+
+                    let mut statistics = analyze::analyze(&schema, &positions, scan_rows(&db, &table_name)?, page_count)?;
+                    // Columns left out of FOR COLUMNS keep their earlier statistics
+                    if let Some(previous) = db.statistics(&table_name) {
+                        for column in &previous.columns {
+                            if !statistics.columns.iter().any(|analyzed| analyzed.name == column.name) {
+                                statistics.columns.push(column.clone());
+                            }
                         }
-                        rows_to_insert.push(Row::new(values));
                     }
+                    statistics
+                };
+
+                self.db.write()
+                    .set_statistics(&table_name, statistics)
+                    .map_err(ExecutorError::Execution)?;
+                self.plan_cache.invalidate();
+                debug!(table = %table_name, "statistics updated");
+                Ok(Response::Execution(Tag::new("ANALYZE")))
+            }
+            Statement::Explain { .. } => {
+                debug!("executing: explain");
+                let (options, statement) = planner::extract_explain(stmt)?;
 
-                    // Insert the rows
-                    let mut db = self.db.write();
-                    for row in rows_to_insert {
-                        db.insert_row(&table_name, row)
-                            .map_err(|e| ExecutorError::Execution(e))?;
+                let planning = Instant::now();
+                let plan = {
+                    let db = self.db.read();
+                    planner::plan(statement, &PlanContext::new(&db, settings))?
+                };
+                let planning = planning.elapsed();
+
+                // Under ANALYZE the plan runs to completion and its rows are discarded
+                let (plan, timings) = if options.analyze {
+                    let plan = instrument::instrument(plan);
+                    let execution = Instant::now();
+                    let (_, rows) = self.execute_plan_stream(plan.clone(), settings)?;
+                    for row in rows {
+                        row?;
                     }
-                    debug!(table = %table_name, "rows inserted");
-                    Ok(Response::EmptyQuery)
-                }
-                Statement::CreateIndex(ci) => {
-                    debug!("executing: create index");
-                    let (table_name, column_name, index_type) = planner::extract_create_index(ci)?;
-
-                    // Extract index name from the CREATE INDEX statement
-                    let index_name = ci.name.as_ref()
-                        .map(|name| name.0.iter()
-                            .filter_map(|part| part.as_ident())
-                            .map(|ident| ident.value.clone())
-                            .collect::<Vec<_>>()
-                            .join("."))
-                        .unwrap_or_else(|| format!("idx_{}", table_name));
-
-                    // Call database to create the secondary index
-                    self.db.write()
-                        .create_secondary_index(
-                            index_name.clone(),
-                            table_name.clone(),
-                            column_name.clone(), 
-                            index_type.clone(),
-                        )
-                        .map_err(|e| ExecutorError::Execution(e))?;
+                    let timings = planner::explain::Timings { planning, execution: execution.elapsed() };
+                    (plan, Some(timings))
+                } else {
+                    (plan, None)
+                };
+
+                let lines = {
+                    let db = self.db.read();
+                    planner::explain::explain(&plan, &options, timings.as_ref(), &PlanContext::new(&db, settings))?
+                };
+                let schema = Schema::new(vec![crate::types::Column {
+                    name: "QUERY PLAN".to_string(),
+                    data_type: crate::types::DataType::String,
+                    is_primary_key: false,
+                }]);
+                let rows = lines.into_iter().map(|line| Row::new(vec![Value::String(line)])).collect();
+                self.rows_to_response(materialized(rows), &schema, formats, settings)
+            }
+            Statement::CreateIndex(ci) => {
+                debug!("executing: create index");
+                let (table_name, column_name, index_type) = planner::extract_create_index(ci)?;
+
+                // Extract index name from the CREATE INDEX statement
+                let index_name = ci.name.as_ref()
+                    .map(|name| name.0.iter()
+                        .filter_map(|part| part.as_ident())
+                        .map(|ident| ident.value.clone())
+                        .collect::<Vec<_>>()
+                        .join("."))
+                    .unwrap_or_else(|| format!("idx_{}", table_name));
+
+                // Call database to create the secondary index
+                self.db.write()
+                    .create_secondary_index(
+                        index_name.clone(),
+                        table_name.clone(),
+                        column_name.clone(), 
+                        index_type.clone(),
+                    )
+                    .map_err(|e| ExecutorError::Execution(e))?;
+                self.plan_cache.invalidate();
+
+                debug!(table = %table_name, column = %column_name, index_type = %index_type, index_name = %index_name, "secondary index created");
+                Ok(Response::Execution(Tag::new("CREATE INDEX")))
+            }
+            _ => {
+                let plan = self.plan_statement(stmt, slot, settings)?;
+                debug!(plan = ?plan, "executing plan");
+                self.execute_plan(plan, buffer, formats, settings)
+            }
+        }
+    }
+
+    /// Parse a statement for the extended query protocol
+    ///
+    /// `declared` holds the parameter types the client gave, `None` where it
+    /// left them to the server. The others are inferred from the statement,
+    /// falling back to text.
+    pub fn prepare(&self, query: &str, declared: &[Option<DataType>]) -> Result<Prepared> {
+        debug!("preparing statement");
+        let mut stmts = parser::parse(query)?;
+        if stmts.len() > 1 {
+            return Err(ExecutorError::Parse(
+                "cannot insert multiple commands into a prepared statement".to_string(),
+            ));
+        }
+        let Some(statement) = stmts.pop() else {
+            return Ok(Prepared { statement: None, parameter_types: Vec::new(), plans: Arc::default() });
+        };
+
+        let inferred = planner::params::parameter_types(&statement, &self.db.read());
+        let parameter_types = (0..inferred.len().max(declared.len()))
+            .map(|idx| declared.get(idx).cloned().flatten()
+                .or_else(|| inferred.get(idx).cloned().flatten())
+                .unwrap_or(DataType::String))
+            .collect::<Vec<_>>();
+        debug!(parameters = ?parameter_types, "statement prepared");
+        Ok(Prepared { statement: Some(statement), parameter_types, plans: Arc::default() })
+    }
 
-                    debug!(table = %table_name, column = %column_name, index_type = %index_type, index_name = %index_name, "secondary index created");
-                    Ok(Response::EmptyQuery)
+    /// Columns a prepared statement returns, without running it
+    ///
+    /// Returns `None` for statements that return no rows. Without bound
+    /// `params`, each parameter stands for an arbitrary value of its type.
+    pub fn describe(&self, prepared: &Prepared, params: Option<&[Value]>, settings: &Settings) -> Result<Option<Schema>> {
+        let text_column = |name: String| Schema::new(vec![crate::types::Column {
+            name,
+            data_type: DataType::String,
+            is_primary_key: false,
+        }]);
+
+        match &prepared.statement {
+            Some(statement @ Statement::Query(_)) => {
+                let values = match params {
+                    Some(params) => params.to_vec(),
+                    None => prepared.parameter_types.iter().map(sample_value).collect(),
+                };
+                let statement = bound(statement, &values)?;
+                let db = self.db.read();
+                let plan = planner::plan(&statement, &PlanContext::new(&db, settings))?;
+                Ok(Some(planner::output_schema(&plan, &db)?))
+            }
+            Some(Statement::Explain { .. }) => Ok(Some(text_column("QUERY PLAN".to_string()))),
+            Some(Statement::ShowVariable { variable }) => {
+                let name = variable.iter()
+                    .map(|ident| ident.value.to_ascii_lowercase())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if name == "plan_cache" {
+                    return Ok(Some(plan_cache_schema()));
                 }
-                _ => {
-                    let plan = planner::plan(stmt)?;
-                    debug!(statement_idx = idx, plan = ?plan, "executing plan");
-                    self.execute_plan(plan)
+                Ok(Some(text_column(name)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Execute a prepared statement with values for its parameters
+    pub fn execute_prepared(
+        &self,
+        prepared: &Prepared,
+        params: &[Value],
+        formats: &Format,
+        settings: &mut Settings,
+    ) -> Result<Response> {
+        let Some(statement) = &prepared.statement else {
+            return Ok(Response::EmptyQuery);
+        };
+        if let Statement::Query(_) = statement {
+            let plan = self.plan_prepared(prepared, statement, params, settings)?;
+            debug!(plan = ?plan, "executing plan");
+            return self.execute_plan(plan, false, formats, settings);
+        }
+        self.execute_statement(&bound(statement, params)?, None, false, formats, settings)
+    }
+
+    /// Plan a statement, reusing the plan in `slot` while it is up to date
+    fn plan_statement(&self, stmt: &Statement, slot: Option<&PlanSlot>, settings: &Settings) -> Result<Operator> {
+        let Some(slot) = slot else {
+            return Ok(self.make_plan(stmt, settings)?.plan);
+        };
+        match self.plan_cache.lookup(slot, settings) {
+            Ok(cached) => {
+                self.plan_cache.record(PlanUse::Hit);
+                debug!("reusing cached plan");
+                return Ok(cached.plan);
+            }
+            Err(plan_use) => self.plan_cache.record(plan_use),
+        }
+        let cached = self.make_plan(stmt, settings)?;
+        self.plan_cache.store(slot, cached.clone());
+        Ok(cached.plan)
+    }
+
+    /// Plan a prepared query for `params`, with its generic plan or a custom one
+    fn plan_prepared(&self, prepared: &Prepared, statement: &Statement, params: &[Value], settings: &Settings) -> Result<Operator> {
+        let mode = settings.plan_cache_mode;
+        let plans = &prepared.plans;
+        let values = params.iter()
+            .map(subquery::literal)
+            .collect::<Result<Vec<_>>>()?;
+
+        let generation = self.plan_cache.generation();
+        if plans.wants_generic(mode, generation) {
+            let generic = match self.plan_cache.lookup(&plans.generic, settings) {
+                Ok(generic) => Some(generic),
+                Err(plan_use) => {
+                    self.plan_cache.record(plan_use);
+                    // Placeholders are planned as values not known yet
+                    match self.make_plan(statement, settings) {
+                        Ok(generic) => {
+                            self.plan_cache.store(&plans.generic, generic.clone());
+                            Some(generic)
+                        }
+                        Err(_) => {
+                            debug!("statement cannot be planned without its parameters");
+                            plans.record_generic_failure(generation);
+                            None
+                        }
+                    }
                 }
             };
-
-            responses.push(response?);
+            if let Some(generic) = generic
+                && plans.prefers_generic(mode, &generic)
+            {
+                self.plan_cache.record(PlanUse::Generic);
+                debug!(cost = generic.cost, "using generic plan");
+                let mut plan = generic.plan;
+                planner::params::bind_plan(&mut plan, &values)?;
+                return Ok(plan);
+            }
         }
 
-        info!(response_count = responses.len(), "execution complete");
-        Ok(responses)
+        let mut bound = statement.clone();
+        planner::params::bind(&mut bound, &values)?;
+        let custom = self.make_plan(&bound, settings)?;
+        plans.record_custom(custom.cost);
+        self.plan_cache.record(PlanUse::Custom);
+        debug!(cost = custom.cost, "using custom plan");
+        Ok(custom.plan)
     }
 
-    fn execute_plan(&self, plan: Operator) -> Result<Response> {
-        // Extract table name if available for schema lookup
-        let table_name = self.extract_table_name(&plan);
+    /// Plan a statement under the current catalog generation
+    fn make_plan(&self, stmt: &Statement, settings: &Settings) -> Result<CachedPlan> {
+        // Read first: a catalog change while planning leaves the plan out of date
+        let generation = self.plan_cache.generation();
+        let db = self.db.read();
+        let ctx = PlanContext::new(&db, settings);
+        let plan = planner::plan(stmt, &ctx)?;
+        let cost = planner::cost::estimate(&plan, &ctx).cost;
+        Ok(CachedPlan::new(plan, cost, generation, settings.clone()))
+    }
+
+    /// Response streaming `rows`, with columns described by `schema` and sent in `formats`
+    fn rows_to_response(&self, mut rows: RowStream, schema: &Schema, formats: &Format, settings: &Settings) -> Result<Response> {
+        // The first row is pulled up front, so that a query failing at once
+        // returns an error rather than a row set. An empty result still
+        // describes its columns, and completes as `SELECT 0`.
+        let first = rows.next().transpose()?;
+        let rows = first.map(Ok).into_iter().chain(rows);
+
+        let fields = Arc::new(self.field_infos(schema, formats)?);
+        let fields_ref = fields.clone();
+        let types = self.types();
+        let bytea_output = settings.bytea_output;
+
+        // Rows are encoded as pgwire pulls them from the stream
+        let data_row_stream = stream::iter(rows.map(move |row| {
+            encode_row(&row?, &fields_ref, &types, bytea_output).map_err(PgWireError::from)
+        }));
+        Ok(Response::Query(QueryResponse::new(fields, data_row_stream)))
+    }
+
+    /// Column metadata for pgwire responses, in the result formats the client asked for
+    pub(crate) fn field_infos(&self, schema: &Schema, formats: &Format) -> Result<Vec<FieldInfo>> {
+        if let Format::Individual(codes) = formats
+            && codes.len() != schema.columns.len()
+        {
+            return Err(ExecutorError::Execution(format!(
+                "bind message has {} result formats but query has {} columns",
+                codes.len(),
+                schema.columns.len()
+            )));
+        }
+        let types = self.types();
+        Ok(schema.columns.iter()
+            .enumerate()
+            .map(|(idx, col)| FieldInfo::new(
+                col.name.clone(),
+                None,
+                None,
+                column_type(&col.data_type, &types),
+                formats.format_for(idx),
+            ))
+            .collect())
+    }
 
-        // Evaluate plan tree to get rows, then convert to Response
-        let rows = self.execute_plan_rows(plan, table_name.clone())?;
+    /// Types, casts, operators and functions registered with the database
+    pub(crate) fn registries(&self) -> &Registries {
+        &self.registries
+    }
+
+    /// Extension types registered with the database
+    pub(crate) fn types(&self) -> Arc<TypeRegistry> {
+        self.registries.types.clone()
+    }
 
-        // Get the actual schema for proper column naming
-        let schema = if let Some(table_name) = table_name {
-            let db = self.db.read();
-            db.get_schema(&table_name).ok()
+    /// Run a plan and stream its rows into a response
+    ///
+    /// Rows are pulled through the operators as the client reads them. With
+    /// `buffer`, they are collected first instead, so that statements later in
+    /// the same query string cannot change what this one returns.
+    fn execute_plan(&self, plan: Operator, buffer: bool, formats: &Format, settings: &Settings) -> Result<Response> {
+        // Described as the planner types it, as for a prepared statement
+        let schema = planner::output_schema(&plan, &self.db.read())?;
+        let (_, rows) = self.execute_plan_stream(plan, settings)?;
+        let rows = if buffer {
+            materialized(rows.collect::<Result<Vec<_>>>()?)
         } else {
-            None
+            rows
         };
+        self.rows_to_response(rows, &schema, formats, settings)
+    }
 
-        rows_to_response(rows, schema)
+    /// Evaluate a plan tree to completion, returning its output schema and rows
+    fn execute_plan_rows(&self, plan: Operator, settings: &Settings) -> Result<(Schema, Vec<Row>)> {
+        let (schema, rows) = self.execute_plan_stream(plan, settings)?;
+        Ok((schema, rows.collect::<Result<Vec<_>>>()?))
     }
 
-    fn extract_table_name(&self, plan: &Operator) -> Option<String> {
+    /// Evaluate a plan tree as a stream of column batches
+    ///
+    /// Scans, filters and projections process a batch at a time with
+    /// expressions compiled once for the query; other operators produce rows
+    /// that are gathered into batches.
+    fn execute_batches(&self, plan: Operator, settings: &Settings) -> Result<(Schema, BatchStream)> {
         match plan {
-            Operator::TableScan { table } if table != "__constant__" => Some(table.clone()),
-            Operator::IndexScan { table, .. } => Some(table.clone()),
-            Operator::Filter { input, .. } => self.extract_table_name(input),
-            Operator::Project { input, .. } => self.extract_table_name(input),
-            Operator::Limit { input, .. } => self.extract_table_name(input),
-            _ => None,
+            Operator::TableScan { table, alias } if table != "__constant__" => {
+                debug!(table = %table, "executing table scan");
+                let db = self.db.read();
+                let schema = db.get_schema(&table)
+                    .map_err(ExecutorError::Execution)?;
+                let rows = scan_rows(&db, &table)?;
+                let batches = Batches::new(rows, schema.columns.len());
+                match alias {
+                    Some(alias) => Ok((schema.qualified(&alias), Box::new(batches))),
+                    None => Ok((schema, Box::new(batches))),
+                }
+            }
+            Operator::Filter { input, predicate } => {
+                debug!("executing filter");
+                let (schema, batches) = self.execute_batches(*input, settings)?;
+                let compiled = CompiledExpr::compile(&predicate, &schema, &self.registries);
+                let input_schema = schema.clone();
+                let registries = self.registries.clone();
+                let filtered = batches
+                    .map(move |batch| batch.and_then(|batch| vectorized::filter(batch, &compiled, &predicate, &input_schema, &registries)))
+                    .filter(|batch| !matches!(batch, Ok(batch) if batch.len == 0));
+                Ok((schema, Box::new(filtered)))
+            }
+            Operator::Project { input, columns, names } => {
+                debug!("executing projection with {} columns", columns.len());
+                let (schema, batches) = self.execute_batches(*input, settings)?;
+
+                let output_columns = columns.iter()
+                    .zip(names)
+                    .map(|(col_expr, name)| crate::types::Column {
+                        name,
+                        data_type: evaluator::infer_type(col_expr, &schema, &self.registries),
+                        is_primary_key: false,
+                    })
+                    .collect();
+
+                let compiled: Vec<_> = columns.iter()
+                    .map(|expr| CompiledExpr::compile(expr, &schema, &self.registries))
+                    .collect();
+                let registries = self.registries.clone();
+                let projected = batches.flat_map(move |batch| match batch {
+                    Ok(batch) => {
+                        // Rows before a failing one are still passed on
+                        let (done, error) = vectorized::project(&batch, &compiled, &columns, &schema, &registries);
+                        done.map(Ok).into_iter().chain(error.map(Err)).collect::<Vec<_>>()
+                    }
+                    Err(e) => vec![Err(e)],
+                });
+                Ok((Schema::new(output_columns), Box::new(projected)))
+            }
+            // Instrumented sorts go through the row path, which records the sort method
+            Operator::Instrument { input, metrics } if !matches!(*input, Operator::Sort { .. }) => {
+                let started = Started::now();
+                let (schema, batches) = self.execute_batches(*input, settings)?;
+                let batches = Instrumented::new(batches, metrics, started, |batch| batch.len as u64);
+                Ok((schema, Box::new(batches)))
+            }
+            plan => {
+                let (schema, rows) = self.execute_plan_stream(plan, settings)?;
+                let width = schema.columns.len();
+                Ok((schema, Box::new(Batches::new(rows, width))))
+            }
         }
     }
 
-    fn execute_plan_rows(&self, plan: Operator, table_name: Option<String>) -> Result<Vec<Row>> {
+    /// Collect the tuple pointers of a bitmap index scan, BitmapAnd or BitmapOr
+    fn execute_bitmap(&self, plan: Operator) -> Result<TupleBitmap> {
         match plan {
-            Operator::TableScan { table } if table == "__constant__" => {
-                // Constant expression like SELECT 1
+            Operator::BitmapIndexScan { table, column, lookup } => {
+                debug!(table = %table, column = %column, "executing bitmap index scan");
+                let db = self.db.read();
+                let schema = db.get_schema(&table)
+                    .map_err(ExecutorError::Execution)?;
+                let pointers = match lookup {
+                    BitmapLookup::Equal(value) => {
+                        let value = key_value(&value, &schema, &column, &self.registries)?;
+                        match equal_key(value, &schema, &column) {
+                            Some(key) => db.index_pointers(&table, &column, &key),
+                            None => Ok(Some(Vec::new())),
+                        }
+                    }
+                    BitmapLookup::Range { lower, upper } => match range_keys(lower, upper, &schema, &column, &self.registries)? {
+                        Some((start, end)) => db.index_range_pointers(&table, &column, start, end),
+                        None => Ok(Some(Vec::new())),
+                    },
+                };
+                let pointers = pointers
+                    .map_err(ExecutorError::Execution)?
+                    .ok_or_else(|| ExecutorError::Execution(format!(
+                        "no index on column \"{}\" of table \"{}\"",
+                        column, table
+                    )))?;
+                Ok(pointers.into_iter().collect())
+            }
+            Operator::BitmapAnd { inputs } => {
+                let mut inputs = inputs.into_iter();
+                let first = inputs.next()
+                    .ok_or_else(|| ExecutorError::Execution("BitmapAnd without inputs".to_string()))?;
+                let first = self.execute_bitmap(first)?;
+                inputs.try_fold(first, |bitmap, input| {
+                    // Nothing left to intersect
+                    if bitmap.is_empty() {
+                        return Ok(bitmap);
+                    }
+                    Ok(bitmap.intersect(&self.execute_bitmap(input)?))
+                })
+            }
+            Operator::BitmapOr { inputs } => inputs.into_iter()
+                .try_fold(TupleBitmap::new(), |bitmap, input| Ok(bitmap.union(self.execute_bitmap(input)?))),
+            Operator::Instrument { input, metrics } => {
+                let started = Started::now();
+                let bitmap = self.execute_bitmap(*input)?;
+                metrics.lock().record_loop(started, bitmap.len() as u64);
+                Ok(bitmap)
+            }
+            plan => Err(ExecutorError::Execution(format!("expected a bitmap, found {:?}", plan))),
+        }
+    }
+
+    /// Evaluate a plan tree, returning its output schema and a stream of rows
+    ///
+    /// Scans, filters, projections and limits pass rows along one at a time,
+    /// so a LIMIT stops reading its input once satisfied. Sorts, joins,
+    /// aggregates and other operators that need their whole input collect it
+    /// before producing rows.
+    fn execute_plan_stream(&self, plan: Operator, settings: &Settings) -> Result<(Schema, RowStream)> {
+        match plan {
+            Operator::TableScan { table, .. } if table == "__constant__" => {
+                // Constant expression like SELECT 1: a single row with no columns
                 debug!("executing constant scan");
-                Ok(vec![Row::new(vec![Value::Int(1)])])
+                Ok((Schema::new(Vec::new()), materialized(vec![Row::new(Vec::new())])))
             }
-            Operator::IndexScan { table, column, value } => {
+            Operator::IndexScan { table, alias, column, value } => {
                 debug!(table = %table, column = %column, "executing index scan");
                 let db = self.db.read();
 
                 // Evaluate the value expression
                 let schema = db.get_schema(&table)
                     .map_err(|e| ExecutorError::Execution(e))?;
-                let empty_row = Row::new(vec![]);
-                let lookup_val = evaluator::eval_expr(&value, &empty_row, &schema)?;
-
-                // Convert value to u64 key for index lookup
-                let key = match lookup_val {
-                    Value::Int(n) => n as u64,
-                    Value::Float(f) => f.to_bits(),
-                    Value::String(s) => {
-                        use std::collections::hash_map::DefaultHasher;
-                        use std::hash::{Hash, Hasher};
-                        let mut hasher = DefaultHasher::new();
-                        s.hash(&mut hasher);
-                        hasher.finish()
-                    }
-                    _ => return Err(ExecutorError::Execution("Cannot use NULL/Bool as index key".to_string())),
+                let lookup_val = key_value(&value, &schema, &column, &self.registries)?;
+
+                let output_schema = match &alias {
+                    Some(alias) => schema.qualified(alias),
+                    None => schema.clone(),
                 };
 
-                // Try to find a secondary index for this column, fall back to primary
-                let result = db.search_secondary_index(&table, &column, key)
-                    .or_else(|_| {
-                        // If secondary index search fails, try primary index
-                        debug!(column = %column, "secondary index not found or search failed, falling back to primary");
-                        db.get_by_key(&table, key)
-                    })
-                    .map_err(|e| ExecutorError::Execution(e))?;
+                let Some(lookup_val) = equal_key(lookup_val, &schema, &column) else {
+                    return Ok((output_schema, materialized(Vec::new())));
+                };
 
-                // Fetch the row using the pointer if found
-                match result {
-                    Some(tuple_ptr) => {
-                        let seg_id = tuple_ptr.segment_id;
-                        let block_id = tuple_ptr.block_id;
-                        let slot_id = tuple_ptr.slot_id;
-
-                        // Read the block and extract the row
-                        let block = db.read_block(seg_id, block_id)
-                            .map_err(|e| ExecutorError::Execution(e))?;
-
-                        if let Some(tuple_bytes) = block.read_tuple(slot_id) {
-                            let (row, _): (Row, usize) = bincode::decode_from_slice(tuple_bytes, bincode::config::standard())
-                                .map_err(|e| ExecutorError::Execution(format!("Deserialization error: {}", e)))?;
-                            Ok(vec![row])
-                        } else {
-                            Ok(Vec::new())
-                        }
-                    }
+                match db.index_lookup(&table, &column, &lookup_val)
+                    .map_err(ExecutorError::Execution)? {
+                    Some(rows) => Ok((output_schema, materialized(rows.into_iter().map(|(_, row)| row).collect()))),
                     None => {
-                        debug!("key not found in any index");
-                        Ok(Vec::new())
+                        // Column is not indexed: fall back to scanning for the value
+                        debug!(column = %column, "no index on column, scanning table");
+                        let predicate = sqlparser::ast::Expr::BinaryOp {
+                            left: Box::new(sqlparser::ast::Expr::Identifier(sqlparser::ast::Ident::new(&column))),
+                            op: sqlparser::ast::BinaryOperator::Eq,
+                            right: Box::new(value),
+                        };
+                        let rows = scan_rows(&db, &table)?;
+                        Ok((output_schema, filter_rows(rows, predicate, schema, self.registries.clone())))
                     }
                 }
             }
-            Operator::TableScan { table } => {
-                debug!(table = %table, "executing table scan");
+            Operator::IndexRangeScan { table, alias, column, lower, upper, descending } => {
+                debug!(table = %table, column = %column, descending, "executing index range scan");
                 let db = self.db.read();
-                let rows = db.scan_table(&table)
-                    .map_err(|e| ExecutorError::Execution(e))?;
-                // Note: Schema information is lost here, but will be recovered
-                // in Project when needed via the actual table schema from DB
-                Ok(rows)
-            }
-            Operator::Filter { input, predicate } => {
-                debug!("executing filter");
-                let rows = self.execute_plan_rows(*input, table_name)?;
-                let schema = self.infer_schema(&rows);
-
-                let filtered = rows
-                    .into_iter()
-                    .filter(|row| {
-                        match evaluator::eval_expr(&predicate, row, &schema) {
-                            Ok(Value::Bool(true)) => true,
-                            Ok(Value::Bool(false)) => false,
-                            Ok(Value::Null) => false,
-                            _ => false,
-                        }
-                    })
-                    .collect();
-                Ok(filtered)
+                let schema = db.get_schema(&table)
+                    .map_err(ExecutorError::Execution)?;
+                let output_schema = match &alias {
+                    Some(alias) => schema.qualified(alias),
+                    None => schema.clone(),
+                };
+
+                // A scan for ORDER BY alone returns the rows where the column is NULL too
+                let nulls = lower.is_none() && upper.is_none();
+                let Some((start, end)) = range_keys(lower, upper, &schema, &column, &self.registries)? else {
+                    return Ok((output_schema, materialized(Vec::new())));
+                };
+
+                let scan = db.index_range(&table, &column, start, end, descending, nulls)
+                    .map_err(ExecutorError::Execution)?
+                    .ok_or_else(|| ExecutorError::Execution(format!(
+                        "no index on column \"{}\" of table \"{}\"",
+                        column, table
+                    )))?;
+                let rows = scan.map(|tuple| tuple.map(|(_, row)| row).map_err(ExecutorError::Execution));
+                Ok((output_schema, Box::new(rows)))
             }
-            Operator::Project { input, columns } => {
-                debug!("executing projection with {} columns", columns.len());
-                let rows = self.execute_plan_rows(*input, table_name.clone())?;
-                // Try to use actual table schema if available
-                let schema = if let Some(table_name) = &table_name {
-                    let db = self.db.read();
-                    db.get_schema(table_name).unwrap_or_else(|_| self.infer_schema(&rows))
-                } else {
-                    self.infer_schema(&rows)
+            Operator::BitmapHeapScan { table, alias, bitmap, recheck } => {
+                let bitmap = self.execute_bitmap(*bitmap)?;
+                debug!(table = %table, tuples = bitmap.len(), "executing bitmap heap scan");
+                let db = self.db.read();
+                let schema = db.get_schema(&table)
+                    .map_err(ExecutorError::Execution)?;
+                let output_schema = match &alias {
+                    Some(alias) => schema.qualified(alias),
+                    None => schema,
                 };
 
-                // Expand wildcards to actual column names
-                let expanded_columns = columns.iter()
-                    .flat_map(|col_expr| {
-                        match col_expr {
-                            sqlparser::ast::Expr::Identifier(ident) if &ident.value == "*" => {
-                                // Replace wildcard with actual column expressions
-                                schema.columns.iter()
-                                    .map(|col| sqlparser::ast::Expr::Identifier(sqlparser::ast::Ident::new(&col.name)))
-                                    .collect::<Vec<_>>()
-                            }
-                            _ => vec![col_expr.clone()]
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                let projected: Result<Vec<Row>> = rows
-                    .iter()
-                    .map(|row| {
-                        let mut new_values = Vec::new();
-                        for col_expr in &expanded_columns {
-                            let val = evaluator::eval_expr(col_expr, row, &schema)?;
-                            new_values.push(val);
+                // Index entries may be stale or, for hashed keys, shared by
+                // other values: check the predicates again on each row
+                let scan = db.bitmap_heap_scan(&table, &bitmap)
+                    .map_err(ExecutorError::Execution)?;
+                let rows = Box::new(scan.map(|tuple| tuple.map(|(_, row)| row).map_err(ExecutorError::Execution)));
+                Ok((output_schema.clone(), filter_rows(rows, recheck, output_schema, self.registries.clone())))
+            }
+            Operator::BitmapIndexScan { .. } | Operator::BitmapAnd { .. } | Operator::BitmapOr { .. } => {
+                Err(ExecutorError::Execution("bitmaps can only be read by a bitmap heap scan".to_string()))
+            }
+            // Scans, filters and projections run on batches
+            plan @ (Operator::TableScan { .. } | Operator::Filter { .. } | Operator::Project { .. }) => {
+                let (schema, batches) = self.execute_batches(plan, settings)?;
+                Ok((schema, batch_rows(batches)))
+            }
+            Operator::Join { left, right, method, condition } => {
+                debug!(method = ?method, join_type = ?condition.join_type, "executing join");
+                if let JoinMethod::Merge = method {
+                    // Both inputs are sorted as they are read
+                    let (left_schema, left_rows) = self.execute_plan_stream(*left, settings)?;
+                    let (right_schema, right_rows) = self.execute_plan_stream(*right, settings)?;
+                    let work_mem = settings.work_mem;
+                    let (schema, joined) = join::merge_join(&left_schema, left_rows, &right_schema, right_rows, &condition, work_mem, &self.registries)?;
+                    return Ok((schema, Box::new(joined)));
+                }
+
+                let (left_schema, left_rows) = self.execute_plan_rows(*left, settings)?;
+                if let JoinMethod::IndexNestedLoop { column } = &method {
+                    // Under EXPLAIN ANALYZE each probe counts as a loop of the inner scan
+                    let (right, metrics) = match *right {
+                        Operator::Instrument { input, metrics } => (*input, Some(metrics)),
+                        right => (right, None),
+                    };
+                    let Operator::TableScan { table, alias } = right else {
+                        return Err(ExecutorError::Execution(
+                            "index nested loop join requires a table scan as inner input".to_string(),
+                        ));
+                    };
+                    let db = self.db.read();
+                    let schema = db.get_schema(&table)
+                        .map_err(ExecutorError::Execution)?;
+                    let right_schema = match &alias {
+                        Some(alias) => schema.qualified(alias),
+                        None => schema,
+                    };
+                    return join::index_nested_loop_join(&left_schema, left_rows, &right_schema, &condition, |value| {
+                        let started = Started::now();
+                        let rows: Vec<Row> = db.index_lookup(&table, column, value)
+                            .map_err(ExecutorError::Execution)?
+                            .map(|rows| rows.into_iter().map(|(_, row)| row).collect())
+                            .ok_or_else(|| ExecutorError::Execution(format!(
+                                "no index on column \"{}\" of table \"{}\"",
+                                column, table
+                            )))?;
+                        if let Some(metrics) = &metrics {
+                            metrics.lock().record_loop(started, rows.len() as u64);
                         }
-                        Ok(Row::new(new_values))
-                    })
-                    .collect();
-                projected
+                        Ok(rows)
+                    }, &self.registries).map(into_stream);
+                }
+
+                let (right_schema, right_rows) = self.execute_plan_rows(*right, settings)?;
+                let joined = match method {
+                    JoinMethod::NestedLoop | JoinMethod::IndexNestedLoop { .. } => {
+                        join::nested_loop_join(&left_schema, left_rows, &right_schema, right_rows, &condition, &self.registries)
+                    }
+                    JoinMethod::Hash => {
+                        join::hash_join(&left_schema, left_rows, &right_schema, right_rows, &condition, &self.registries)
+                    }
+                    JoinMethod::Merge => unreachable!("handled above"),
+                };
+                joined.map(into_stream)
             }
-            Operator::Aggregate { input, group_by: _, aggregates: _ } => {
+            Operator::Aggregate { input, group_by, aggregates } => {
                 debug!("executing aggregate");
-                let _rows = self.execute_plan_rows(*input, table_name)?;
-                // TODO: Implement aggregation
-                Ok(Vec::new())
+                let (schema, batches) = self.execute_batches(*input, settings)?;
+                aggregate::hash_aggregate(&schema, batches, &group_by, &aggregates, &self.registries).map(into_stream)
+            }
+            Operator::Sort { input, keys, limit } => {
+                let (schema, sorted) = self.execute_sort(*input, &keys, limit, settings)?;
+                Ok((schema, Box::new(sorted)))
+            }
+            Operator::Window { input, partition_by, order_by, calls } => {
+                debug!(partition_by = partition_by.len(), calls = calls.len(), "executing window");
+                let (schema, rows) = self.execute_plan_rows(*input, settings)?;
+                let work_mem = settings.work_mem;
+                window::window(&schema, rows, &partition_by, &order_by, &calls, work_mem, &self.registries).map(into_stream)
+            }
+            Operator::Distinct { input, keys } => {
+                debug!(keys = keys.len(), "executing distinct");
+                let (schema, rows) = self.execute_plan_stream(*input, settings)?;
+                let mut seen = set_operation::Seen::new(schema.clone(), keys, self.registries.clone());
+                let rows = rows.filter_map(move |row| match row.and_then(|row| seen.is_new(&row).map(|new| (row, new))) {
+                    Ok((row, true)) => Some(Ok(row)),
+                    Ok((_, false)) => None,
+                    Err(e) => Some(Err(e)),
+                });
+                Ok((schema, Box::new(rows)))
+            }
+            Operator::SetOperation { op, all, left, right } => {
+                debug!(op = op.name(), all, "executing set operation");
+                let (left_schema, left_rows) = self.execute_plan_rows(*left, settings)?;
+                let (right_schema, right_rows) = self.execute_plan_rows(*right, settings)?;
+                set_operation::set_operation(op, all, left_schema, left_rows, &right_schema, right_rows, &self.registries).map(into_stream)
             }
             Operator::Limit { input, limit, offset } => {
                 debug!("executing limit {} offset {:?}", limit, offset);
-                let rows = self.execute_plan_rows(*input, table_name)?;
+                let (schema, rows) = self.execute_plan_stream(*input, settings)?;
                 let skip = offset.unwrap_or(0) as usize;
-                Ok(rows.into_iter()
-                    .skip(skip)
-                    .take(limit as usize)
-                    .collect())
+                // Input rows past the limit are never pulled
+                Ok((schema, Box::new(rows.skip(skip).take(limit as usize))))
+            }
+            Operator::SubqueryScan { input, alias, columns } => {
+                debug!(alias = %alias, "executing subquery scan");
+                let (schema, rows) = self.execute_plan_stream(*input, settings)?;
+                let columns = schema.columns.into_iter()
+                    .zip(&columns)
+                    .map(|(column, name)| crate::types::Column {
+                        name: format!("{}.{}", alias, name),
+                        ..column
+                    })
+                    .collect();
+                Ok((Schema::new(columns), rows))
+            }
+            Operator::Apply { input, subplans } => {
+                debug!(subqueries = subplans.len(), "executing apply");
+                let (schema, rows) = self.execute_plan_rows(*input, settings)?;
+                subquery::apply(&schema, rows, &subplans, |plan| self.execute_plan_rows(plan, settings), &self.registries).map(into_stream)
+            }
+            Operator::RecursiveUnion { name, anchor, recursive, distinct } => {
+                debug!(cte = %name, distinct, "executing recursive union");
+                subquery::recursive_union(&name, *anchor, &recursive, distinct, |plan| self.execute_plan_rows(plan, settings))
+                    .map(into_stream)
+            }
+            Operator::WorkTableScan { name, .. } => Err(ExecutorError::Execution(format!(
+                "working table of \"{}\" read outside its recursive query",
+                name
+            ))),
+            Operator::Values { schema, rows } => Ok((schema, materialized(rows))),
+            Operator::Instrument { input, metrics } => {
+                let started = Started::now();
+                let (schema, rows): (Schema, RowStream) = match *input {
+                    // Sorts also report how they sorted
+                    Operator::Sort { input, keys, limit } => {
+                        let (schema, sorted) = self.execute_sort(*input, &keys, limit, settings)?;
+                        metrics.lock().sort_method = Some(sorted.method());
+                        (schema, Box::new(sorted))
+                    }
+                    input => self.execute_plan_stream(input, settings)?,
+                };
+                Ok((schema, Box::new(Instrumented::new(rows, metrics, started, |_| 1))))
             }
         }
     }
 
-    fn infer_schema(&self, rows: &[Row]) -> Schema {
-        // For now, create a schema with generic column names
-        if rows.is_empty() {
-            return Schema::new(Vec::new());
+    /// Sort the rows of `input`, spilling to disk past `work_mem`
+    fn execute_sort(&self, input: Operator, keys: &[SortKey], limit: Option<u64>, settings: &Settings) -> Result<(Schema, sort::SortedRows)> {
+        debug!(keys = keys.len(), limit = ?limit, "executing sort");
+        let (schema, rows) = self.execute_plan_stream(input, settings)?;
+        let work_mem = settings.work_mem;
+        let sorted = sort::sort(&schema, rows, keys, limit, work_mem, &self.registries)?;
+        Ok((schema, sorted))
+    }
+}
+
+/// Rows pulled one at a time from an operator
+pub(crate) type RowStream = Box<dyn Iterator<Item = Result<Row>> + Send>;
+
+/// Stream rows that have already been computed
+fn materialized(rows: Vec<Row>) -> RowStream {
+    Box::new(rows.into_iter().map(Ok))
+}
+
+fn into_stream((schema, rows): (Schema, Vec<Row>)) -> (Schema, RowStream) {
+    (schema, materialized(rows))
+}
+
+/// Stream the live rows of a table, reading blocks as rows are pulled
+fn scan_rows(db: &Database, table: &str) -> Result<RowStream> {
+    let scan = db.scan(table).map_err(ExecutorError::Execution)?;
+    Ok(Box::new(scan.map(|tuple| tuple.map(|(_, row)| row).map_err(ExecutorError::Execution))))
+}
+
+/// Evaluate the bounds of an index range on `column` into an inclusive
+/// range of integer keys (None when the range is empty)
+fn range_keys(
+    lower: Option<RangeBound>,
+    upper: Option<RangeBound>,
+    schema: &Schema,
+    column: &str,
+    registries: &Registries,
+) -> Result<Option<(i64, i64)>> {
+    let column_type = schema.get_column_index(column).map(|idx| &schema.columns[idx].data_type);
+    let bound = |bound: Option<RangeBound>| bound
+        .map(|bound| {
+            let value = key_value(&bound.value, schema, column, registries)?;
+            let value = match column_type {
+                Some(column_type) if datetime::is_temporal_type(column_type) => temporal_bound(value, column_type),
+                _ => value,
+            };
+            Ok((value, bound.inclusive))
+        })
+        .transpose();
+    key_range(bound(lower)?, bound(upper)?)
+}
+
+/// A range bound on a date, time or timestamp column as a number comparable
+/// with the column's integer keys
+///
+/// A timestamp bounding a date column falls between two days unless it is
+/// midnight, so it becomes the day it falls on plus a half.
+fn temporal_bound(value: Value, column_type: &DataType) -> Value {
+    match (value, column_type) {
+        (Value::Date(d), DataType::Date) => Value::Int(d as i64),
+        (Value::Date(d), DataType::Timestamp | DataType::TimestampTz) => {
+            Value::Int(d as i64 * Interval::MICROS_PER_DAY)
+        }
+        (Value::Timestamp(m) | Value::TimestampTz(m), DataType::Date) => {
+            let days = m.div_euclid(Interval::MICROS_PER_DAY);
+            match m.rem_euclid(Interval::MICROS_PER_DAY) {
+                0 => Value::Int(days),
+                _ => Value::Float(days as f64 + 0.5),
+            }
         }
+        (Value::Time(m), DataType::Time)
+        | (Value::Timestamp(m) | Value::TimestampTz(m), DataType::Timestamp | DataType::TimestampTz) => Value::Int(m),
+        (value, _) => value,
+    }
+}
 
-        let num_cols = rows[0].len();
-        let mut columns = Vec::new();
-        for i in 0..num_cols {
-            columns.push(crate::types::Column {
-                name: format!("col{}", i),
-                data_type: crate::types::DataType::Int,
-                is_primary_key: false,
-            });
+/// Evaluate the key expression of an index lookup on `column`, reading a
+/// string literal as the column's type (see `coercion::literal_type`)
+fn key_value(expr: &sqlparser::ast::Expr, schema: &Schema, column: &str, registries: &Registries) -> Result<Value> {
+    let value = evaluator::eval_expr(expr, &Row::new(vec![]), schema, registries)?;
+    let column_type = schema.get_column_index(column).map(|idx| &schema.columns[idx].data_type);
+    match (value, column_type) {
+        (Value::String(text), Some(column_type)) if is_string_literal(expr) => {
+            match coercion::literal_type(&sqlparser::ast::BinaryOperator::Eq, column_type) {
+                Some(data_type) => coercion::parse_text(&text, &data_type, registries),
+                None => Ok(Value::String(text)),
+            }
         }
-        Schema::new(columns)
+        (value, _) => Ok(value),
     }
 }
 
-fn rows_to_response(rows: Vec<Row>, schema: Option<Schema>) -> Result<Response> {
-    // Convert Row data to pgwire Response
-    if rows.is_empty() {
-        return Ok(Response::EmptyQuery);
+/// The key an equality lookup on `column` probes the index for (see
+/// `coercion::equal_key`)
+fn equal_key(value: Value, schema: &Schema, column: &str) -> Option<Value> {
+    match schema.get_column_index(column) {
+        Some(idx) => coercion::equal_key(value, &schema.columns[idx].data_type),
+        None => Some(value),
     }
+}
 
-    // Build column metadata for pgwire response
-    let row_len = rows[0].len();
-    let mut field_infos = Vec::new();
+/// Convert the value of `expr` stored into `column` (see `coercion::assign`)
+///
+/// A number literal stored into a numeric column is read from its digits,
+/// not rounded through a float.
+fn assign_expr(expr: &sqlparser::ast::Expr, value: Value, column: &Column, registries: &Registries) -> Result<Value> {
+    match number_literal(expr) {
+        Some(text) if matches!(column.data_type, DataType::Numeric(_)) => coercion::assign(Value::String(text), column, true, registries),
+        _ => coercion::assign(value, column, is_string_literal(expr), registries),
+    }
+}
 
-    if let Some(schema) = &schema {
-        // Use actual column names from schema
-        for col in &schema.columns {
-            let pgwire_type = match col.data_type {
-                crate::types::DataType::Int => Type::INT4,
-                crate::types::DataType::Float => Type::FLOAT8,
-                crate::types::DataType::String => Type::VARCHAR,
-                crate::types::DataType::Bool => Type::BOOL,
-                crate::types::DataType::Null => Type::UNKNOWN,
-                crate::types::DataType::Extension { .. } => Type::UNKNOWN,
-            };
-            field_infos.push(FieldInfo::new(
-                col.name.clone().into(),
-                None,
-                None,
-                pgwire_type,
-                FieldFormat::Text,
-            ));
+/// Whether `expr` is a bare string literal, whose type is taken from where
+/// it is used
+pub(crate) fn is_string_literal(expr: &sqlparser::ast::Expr) -> bool {
+    matches!(expr, sqlparser::ast::Expr::Value(val) if matches!(val.value, sqlparser::ast::Value::SingleQuotedString(_)))
+}
+
+/// Text of a number literal, negated or not, as written
+pub(crate) fn number_literal(expr: &sqlparser::ast::Expr) -> Option<String> {
+    use sqlparser::ast::{Expr, UnaryOperator};
+
+    match expr {
+        Expr::Value(val) => match &val.value {
+            sqlparser::ast::Value::Number(n, _) => Some(n.clone()),
+            _ => None,
+        },
+        Expr::UnaryOp { op: UnaryOperator::Minus, expr } => number_literal(expr).map(|n| format!("-{}", n)),
+        Expr::Nested(expr) => number_literal(expr),
+        _ => None,
+    }
+}
+
+/// Integer keys from `lower` to `upper`, each a value and whether it is
+/// included, as an inclusive range (None when no integer lies in between)
+fn key_range(lower: Option<(Value, bool)>, upper: Option<(Value, bool)>) -> Result<Option<(i64, i64)>> {
+    let start = match lower {
+        None => Some(i64::MIN),
+        Some((value, inclusive)) => match key_bound(value)? {
+            None => None,
+            Some(KeyBound::Int(n)) if inclusive => Some(n),
+            Some(KeyBound::Int(n)) => n.checked_add(1),
+            Some(KeyBound::Float(f)) => {
+                let start = if inclusive { f.ceil() } else { f.floor() + 1.0 };
+                // Saturates below i64::MIN
+                (start < i64::MAX as f64).then_some(start as i64)
+            }
+        },
+    };
+    let end = match upper {
+        None => Some(i64::MAX),
+        Some((value, inclusive)) => match key_bound(value)? {
+            None => None,
+            Some(KeyBound::Int(n)) if inclusive => Some(n),
+            Some(KeyBound::Int(n)) => n.checked_sub(1),
+            Some(KeyBound::Float(f)) => {
+                let end = if inclusive { f.floor() } else { f.ceil() - 1.0 };
+                // Saturates above i64::MAX
+                (end >= i64::MIN as f64).then_some(end as i64)
+            }
+        },
+    };
+    Ok(start.zip(end).filter(|(start, end)| start <= end))
+}
+
+enum KeyBound {
+    Int(i64),
+    Float(f64),
+}
+
+/// A range bound compared with integer keys (None for NULL, which no key is
+/// compared true with)
+fn key_bound(value: Value) -> Result<Option<KeyBound>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Int(n) => Ok(Some(KeyBound::Int(n))),
+        // NaN sorts above every number
+        Value::Float(f) if f.is_nan() => Ok(Some(KeyBound::Float(f64::INFINITY))),
+        Value::Float(f) => Ok(Some(KeyBound::Float(f))),
+        value => evaluator::compare_values(&Value::Int(0), &value).map(|_| None),
+    }
+}
+
+/// Column batches pulled one at a time from an operator
+pub(crate) type BatchStream = Box<dyn Iterator<Item = Result<Batch>> + Send>;
+
+/// Gathers a row stream into batches of up to `BATCH_SIZE` rows
+struct Batches {
+    rows: RowStream,
+    width: usize,
+    /// Error that ended the previous batch early
+    error: Option<ExecutorError>,
+}
+
+impl Batches {
+    fn new(rows: RowStream, width: usize) -> Self {
+        Batches { rows, width, error: None }
+    }
+}
+
+impl Iterator for Batches {
+    type Item = Result<Batch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+        let mut rows = Vec::with_capacity(BATCH_SIZE);
+        while rows.len() < BATCH_SIZE {
+            match self.rows.next() {
+                Some(Ok(row)) => rows.push(row),
+                Some(Err(e)) if rows.is_empty() => return Some(Err(e)),
+                Some(Err(e)) => {
+                    // Pass on the rows read so far, then the error
+                    self.error = Some(e);
+                    break;
+                }
+                None => break,
+            }
         }
+        (!rows.is_empty()).then(|| Ok(Batch::from_rows(rows, self.width)))
+    }
+}
+
+/// Stream the rows of a batch stream
+fn batch_rows(batches: BatchStream) -> RowStream {
+    Box::new(batches.flat_map(|batch| match batch {
+        Ok(batch) => batch.into_rows().into_iter().map(Ok).collect::<Vec<_>>(),
+        Err(e) => vec![Err(e)],
+    }))
+}
+
+/// Keep the rows for which `predicate` is true
+/// A row whose predicate fails yields the error in its place
+fn filter_rows(rows: RowStream, predicate: sqlparser::ast::Expr, schema: Schema, registries: Registries) -> RowStream {
+    Box::new(rows.filter_map(move |row| match row {
+        Ok(row) => match evaluator::eval_expr(&predicate, &row, &schema, &registries) {
+            Ok(Value::Bool(true)) => Some(Ok(row)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        },
+        // Errors are passed on to the consumer
+        Err(e) => Some(Err(e)),
+    }))
+}
+
+/// Parse a Postgres memory setting such as `64kB`, `4MB` or `1GB` into bytes
+/// A bare number is in kilobytes
+fn parse_memory(value: &str) -> Result<usize> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let multiplier = match unit.trim() {
+        "" | "kB" => 1024,
+        "B" => 1,
+        "MB" => 1024 * 1024,
+        "GB" => 1024 * 1024 * 1024,
+        _ => 0,
+    };
+
+    digits.parse::<usize>().ok()
+        .filter(|_| multiplier > 0)
+        .and_then(|amount| amount.checked_mul(multiplier))
+        .filter(|bytes| *bytes >= 64 * 1024)
+        .ok_or_else(|| ExecutorError::Execution(format!(
+            "invalid value for parameter \"work_mem\": \"{}\" (minimum is 64kB)",
+            value
+        )))
+}
+
+/// Parse a boolean setting (`on`/`off`, `true`/`false`, `yes`/`no`, `1`/`0`)
+fn parse_bool(name: &str, value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "on" | "true" | "yes" | "1" => Ok(true),
+        "off" | "false" | "no" | "0" => Ok(false),
+        _ => Err(ExecutorError::Execution(format!(
+            "parameter \"{}\" requires a Boolean value",
+            name
+        ))),
+    }
+}
+
+fn parse_plan_cache_mode(value: &str) -> Result<PlanCacheMode> {
+    match value.to_ascii_lowercase().as_str() {
+        "auto" => Ok(PlanCacheMode::Auto),
+        "force_generic_plan" => Ok(PlanCacheMode::ForceGenericPlan),
+        "force_custom_plan" => Ok(PlanCacheMode::ForceCustomPlan),
+        _ => Err(ExecutorError::Execution(format!(
+            "invalid value for parameter \"plan_cache_mode\": \"{}\"",
+            value
+        ))),
+    }
+}
+
+fn parse_bytea_output(value: &str) -> Result<ByteaOutput> {
+    match value.to_ascii_lowercase().as_str() {
+        "hex" => Ok(ByteaOutput::Hex),
+        "escape" => Ok(ByteaOutput::Escape),
+        _ => Err(ExecutorError::Execution(format!(
+            "invalid value for parameter \"bytea_output\": \"{}\"",
+            value
+        ))),
+    }
+}
+
+/// Columns of `SHOW plan_cache`
+fn plan_cache_schema() -> Schema {
+    Schema::new(["statistic", "value"].into_iter()
+        .map(|name| crate::types::Column {
+            name: name.to_string(),
+            data_type: DataType::String,
+            is_primary_key: false,
+        })
+        .collect())
+}
+
+/// Format a byte count the way Postgres displays memory settings
+fn format_memory(bytes: usize) -> String {
+    const MB: usize = 1024 * 1024;
+    if bytes.is_multiple_of(MB) {
+        format!("{}MB", bytes / MB)
     } else {
-        // Fall back to generic names if no schema available
-        for i in 0..row_len {
-            field_infos.push(FieldInfo::new(
-                format!("?column?{}", i).into(),
-                None,
-                None,
-                Type::INT4,
-                FieldFormat::Text,
-            ));
+        format!("{}kB", bytes / 1024)
+    }
+}
+
+/// Collect the live tuples of a table matching an optional WHERE predicate
+/// Uses an index for `col = const` predicates when one exists
+fn matching_tuples(
+    db: &Database,
+    table_name: &str,
+    schema: &Schema,
+    selection: Option<&sqlparser::ast::Expr>,
+    registries: &Registries,
+) -> Result<Vec<(TuplePointer, Row)>> {
+    let empty_row = Row::new(vec![]);
+    let indexed = match selection.and_then(planner::try_extract_equality) {
+        // The value side may reference columns, in which case it cannot be probed
+        Some((column, value_expr)) => match evaluator::eval_expr(&value_expr, &empty_row, schema, registries) {
+            Ok(value) => db.index_lookup(table_name, &column, &value)
+                .map_err(ExecutorError::Execution)?,
+            Err(_) => None,
+        },
+        None => None,
+    };
+
+    let candidates = match indexed {
+        Some(candidates) => candidates,
+        None => db.scan_table_versions(table_name)
+            .map_err(ExecutorError::Execution)?,
+    };
+
+    let Some(predicate) = selection else {
+        return Ok(candidates);
+    };
+
+    let mut matched = Vec::new();
+    for (ptr, row) in candidates {
+        if let Value::Bool(true) = evaluator::eval_expr(predicate, &row, schema, registries)? {
+            matched.push((ptr, row));
         }
     }
+    Ok(matched)
+}
 
-    let schema = Arc::new(field_infos);
-    let schema_ref = schema.clone();
+/// The pgwire type values of a built-in data type are sent as
+pub(crate) fn pg_type(data_type: &DataType) -> Type {
+    match data_type {
+        DataType::Int => Type::INT8,
+        DataType::Float => Type::FLOAT8,
+        DataType::Numeric(_) => Type::NUMERIC,
+        DataType::String => Type::VARCHAR,
+        DataType::Bool => Type::BOOL,
+        DataType::Date => Type::DATE,
+        DataType::Time => Type::TIME,
+        DataType::Timestamp => Type::TIMESTAMP,
+        DataType::TimestampTz => Type::TIMESTAMPTZ,
+        DataType::Interval => Type::INTERVAL,
+        DataType::Uuid => Type::UUID,
+        DataType::Bytea => Type::BYTEA,
+        // Like Postgres, columns of unknown type (a bare NULL) are text
+        DataType::Null => Type::TEXT,
+        DataType::Extension { .. } => Type::UNKNOWN,
+    }
+}
 
-    // Encode rows
-    let mut encoded_rows = Vec::new();
-    for row in rows {
-        let mut encoder = DataRowEncoder::new(schema_ref.clone());
-        for value in &row.values {
-            match value {
-                Value::Int(n) => {
-                    encoder.encode_field(&(*n as i32))
-                        .map_err(|e| ExecutorError::Execution(format!("Encoding error: {:?}", e)))?;
-                }
-                Value::Float(f) => {
-                    encoder.encode_field(f)
-                        .map_err(|e| ExecutorError::Execution(format!("Encoding error: {:?}", e)))?;
-                }
-                Value::String(s) => {
-                    encoder.encode_field(s)
-                        .map_err(|e| ExecutorError::Execution(format!("Encoding error: {:?}", e)))?;
-                }
-                Value::Bool(b) => {
-                    encoder.encode_field(b)
-                        .map_err(|e| ExecutorError::Execution(format!("Encoding error: {:?}", e)))?;
-                }
-                Value::Null => {
-                    encoder.encode_field(&None::<i32>)
-                        .map_err(|e| ExecutorError::Execution(format!("Encoding error: {:?}", e)))?;
-                }
-                Value::Extension { type_oid, .. } => {
-                    // Extension values cannot be directly serialized to pgwire
-                    // They require the TypeExtension trait for proper encoding
-                    debug!("skipping extension value (type_oid: {})", type_oid);
-                    encoder.encode_field(&None::<i32>)
-                        .map_err(|e| ExecutorError::Execution(format!("Encoding error: {:?}", e)))?;
-                }
+/// The pgwire type of a result column, extension types included
+fn column_type(data_type: &DataType, types: &TypeRegistry) -> Type {
+    match data_type {
+        DataType::Extension { type_oid, .. } => types.get_by_oid(*type_oid)
+            .map(|extension| extension.to_pgwire_type())
+            .unwrap_or(Type::UNKNOWN),
+        data_type => pg_type(data_type),
+    }
+}
+
+/// The data type values of a pgwire type are read as, if supported
+pub(crate) fn data_type(pg_type: &Type) -> Option<DataType> {
+    match *pg_type {
+        Type::INT2 | Type::INT4 | Type::INT8 => Some(DataType::Int),
+        Type::FLOAT4 | Type::FLOAT8 => Some(DataType::Float),
+        Type::NUMERIC => Some(DataType::Numeric(None)),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => Some(DataType::String),
+        Type::BOOL => Some(DataType::Bool),
+        Type::DATE => Some(DataType::Date),
+        Type::TIME => Some(DataType::Time),
+        Type::TIMESTAMP => Some(DataType::Timestamp),
+        Type::TIMESTAMPTZ => Some(DataType::TimestampTz),
+        Type::INTERVAL => Some(DataType::Interval),
+        Type::UUID => Some(DataType::Uuid),
+        Type::BYTEA => Some(DataType::Bytea),
+        _ => None,
+    }
+}
+
+/// Decode a parameter value sent by the client in text or binary format
+pub(crate) fn decode_parameter(bytes: Option<&[u8]>, binary: bool, data_type: &DataType, registries: &Registries) -> Result<Value> {
+    let Some(bytes) = bytes else {
+        return Ok(Value::Null);
+    };
+    let invalid = || ExecutorError::Execution(format!(
+        "invalid input for parameter of type {}",
+        pg_type(data_type).name()
+    ));
+
+    if binary {
+        return match (data_type, bytes.len()) {
+            (DataType::Int, 2) => Ok(Value::Int(i16::from_be_bytes([bytes[0], bytes[1]]) as i64)),
+            (DataType::Int, 4) => Ok(Value::Int(i32::from_be_bytes(bytes.try_into().unwrap()) as i64)),
+            (DataType::Int, 8) => Ok(Value::Int(i64::from_be_bytes(bytes.try_into().unwrap()))),
+            (DataType::Float, 4) => Ok(Value::Float(f32::from_be_bytes(bytes.try_into().unwrap()) as f64)),
+            (DataType::Float, 8) => Ok(Value::Float(f64::from_be_bytes(bytes.try_into().unwrap()))),
+            (DataType::Numeric(_), _) => numeric::decode_binary(bytes).map(Value::Numeric).ok_or_else(invalid),
+            (DataType::Bool, 1) => Ok(Value::Bool(bytes[0] != 0)),
+            (DataType::Uuid, 16) => Ok(Value::Uuid(bytes.try_into().unwrap())),
+            (DataType::Bytea, _) => Ok(Value::Bytea(bytes.to_vec())),
+            (DataType::String, _) => String::from_utf8(bytes.to_vec()).map(Value::String).map_err(|_| invalid()),
+            (data_type, _) if datetime::is_temporal_type(data_type) => {
+                datetime::decode_binary(bytes, data_type).ok_or_else(invalid)
             }
+            _ => Err(invalid()),
+        };
+    }
+
+    let text = std::str::from_utf8(bytes).map_err(|_| invalid())?;
+    match data_type {
+        DataType::Int => text.trim().parse().map(Value::Int).map_err(|_| ExecutorError::Execution(format!(
+            "invalid input syntax for type integer: \"{}\"",
+            text
+        ))),
+        DataType::Float => text.trim().parse().map(Value::Float).map_err(|_| ExecutorError::Execution(format!(
+            "invalid input syntax for type double precision: \"{}\"",
+            text
+        ))),
+        DataType::Bool => match text.trim().to_ascii_lowercase().as_str() {
+            "t" | "true" | "y" | "yes" | "on" | "1" => Ok(Value::Bool(true)),
+            "f" | "false" | "n" | "no" | "off" | "0" => Ok(Value::Bool(false)),
+            _ => Err(ExecutorError::Execution(format!(
+                "invalid input syntax for type boolean: \"{}\"",
+                text
+            ))),
+        },
+        DataType::String | DataType::Null => Ok(Value::String(text.to_string())),
+        DataType::Numeric(_) | DataType::Date | DataType::Time | DataType::Timestamp | DataType::TimestampTz | DataType::Interval
+        | DataType::Uuid | DataType::Bytea => {
+            coercion::parse_text(text, data_type, registries)
         }
-        encoded_rows.push(encoder.finish());
+        DataType::Extension { .. } => Err(invalid()),
     }
+}
+
+/// A value of the given type, for planning a statement before its parameters are bound
+fn sample_value(data_type: &DataType) -> Value {
+    match data_type {
+        DataType::Int => Value::Int(0),
+        DataType::Float => Value::Float(0.0),
+        DataType::Numeric(_) => Value::Numeric(BigDecimal::zero()),
+        DataType::String => Value::String(String::new()),
+        DataType::Bool => Value::Bool(false),
+        DataType::Date => Value::Date(0),
+        DataType::Time => Value::Time(0),
+        DataType::Timestamp => Value::Timestamp(0),
+        DataType::TimestampTz => Value::TimestampTz(0),
+        DataType::Interval => Value::Interval(Interval::default()),
+        DataType::Uuid => Value::Uuid([0; 16]),
+        DataType::Bytea => Value::Bytea(Vec::new()),
+        DataType::Null | DataType::Extension { .. } => Value::Null,
+    }
+}
 
-    let data_row_stream = stream::iter(encoded_rows);
-    Ok(Response::Query(QueryResponse::new(schema, data_row_stream)))
+/// Copy of a statement with its `$n` parameters replaced by `params`
+fn bound(statement: &Statement, params: &[Value]) -> Result<Statement> {
+    let values = params.iter()
+        .map(subquery::literal)
+        .collect::<Result<Vec<_>>>()?;
+    let mut statement = statement.clone();
+    planner::params::bind(&mut statement, &values)?;
+    Ok(statement)
+}
+
+/// Encode a row in the types and formats of its fields, bytea text in the
+/// `bytea_output` format
+fn encode_row(row: &Row, fields: &Arc<Vec<FieldInfo>>, types: &TypeRegistry, bytea_output: ByteaOutput) -> Result<DataRow> {
+    let encoding_error = |e: PgWireError| ExecutorError::Execution(format!("Encoding error: {:?}", e));
+    let mut encoder = DataRowEncoder::new(fields.clone());
+    for (value, field) in row.values.iter().zip(fields.iter()) {
+        // Values are sent as the type their column is described with
+        let value = match value {
+            Value::Int(n) if *field.datatype() == Type::FLOAT8 => &Value::Float(*n as f64),
+            Value::Numeric(n) if *field.datatype() == Type::FLOAT8 => &Value::Float(numeric::to_float(n)),
+            Value::Int(n) if *field.datatype() == Type::NUMERIC => &Value::Numeric((*n).into()),
+            value => value,
+        };
+        match value {
+            Value::Int(n) => encoder.encode_field(n).map_err(encoding_error)?,
+            Value::Float(f) => encoder.encode_field(f).map_err(encoding_error)?,
+            Value::String(s) => encoder.encode_field(s).map_err(encoding_error)?,
+            Value::Bool(b) => encoder.encode_field(b).map_err(encoding_error)?,
+            Value::Null => encoder.encode_field(&None::<i64>).map_err(encoding_error)?,
+            Value::Numeric(n) => {
+                let bytes = match field.format() {
+                    FieldFormat::Text => numeric::format(n).into_bytes(),
+                    FieldFormat::Binary => numeric::encode_binary(n),
+                };
+                encoder.encode_field_with_type_and_format(&bytes, field.datatype(), FieldFormat::Binary, field.format_options())
+                    .map_err(encoding_error)?;
+            }
+            Value::Uuid(uuid) => {
+                let bytes = match field.format() {
+                    FieldFormat::Text => bytes::format_uuid(uuid).into_bytes(),
+                    FieldFormat::Binary => uuid.to_vec(),
+                };
+                encoder.encode_field_with_type_and_format(&bytes, field.datatype(), FieldFormat::Binary, field.format_options())
+                    .map_err(encoding_error)?;
+            }
+            Value::Bytea(data) => {
+                let bytes = match field.format() {
+                    FieldFormat::Text => bytes::format_bytea(data, bytea_output).into_bytes(),
+                    FieldFormat::Binary => data.clone(),
+                };
+                encoder.encode_field_with_type_and_format(&bytes, field.datatype(), FieldFormat::Binary, field.format_options())
+                    .map_err(encoding_error)?;
+            }
+            Value::Date(_) | Value::Time(_) | Value::Timestamp(_) | Value::TimestampTz(_) | Value::Interval(_) => {
+                let bytes = match field.format() {
+                    FieldFormat::Text => value.as_string().into_bytes(),
+                    FieldFormat::Binary => datetime::encode_binary(value)
+                        .ok_or_else(|| ExecutorError::Execution("Encoding error: not a date/time value".to_string()))?,
+                };
+                encoder.encode_field_with_type_and_format(&bytes, field.datatype(), FieldFormat::Binary, field.format_options())
+                    .map_err(encoding_error)?;
+            }
+            Value::Extension { type_oid, data } => {
+                let extension = types.get_by_oid(*type_oid)
+                    .ok_or_else(|| ExecutorError::Execution(format!(
+                        "no extension registered for type oid {}",
+                        type_oid
+                    )))?;
+                // The extension's output function produces the bytes of either format
+                let bytes = match field.format() {
+                    FieldFormat::Text => extension.encode_text(data.as_ref()).map(String::into_bytes),
+                    FieldFormat::Binary => extension.encode_binary(data.as_ref()),
+                }.map_err(ExecutorError::Execution)?;
+                encoder.encode_field_with_type_and_format(&bytes, field.datatype(), FieldFormat::Binary, field.format_options())
+                    .map_err(encoding_error)?;
+            }
+        }
+    }
+    encoder.finish()
+        .map_err(|e| ExecutorError::Execution(format!("Encoding error: {:?}", e)))
 }
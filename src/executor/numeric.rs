@@ -0,0 +1,344 @@
+//! Exact decimal numbers: the NUMERIC type
+//!
+//! A numeric value is an arbitrary-precision decimal that keeps its scale,
+//! the count of digits after the decimal point, as Postgres does: 1.50
+//! equals 1.5 but prints as 1.50. Sums, differences and products are exact
+//! and keep the scales of their operands; quotients are rounded to a scale
+//! chosen the way Postgres chooses it.
+//!
+//! A column declared `NUMERIC(p, s)` rounds what is stored into it to `s`
+//! decimal places and rejects values with more than `p - s` digits before
+//! the point.
+
+use bigdecimal::num_bigint::{BigInt, Sign};
+use bigdecimal::{BigDecimal, RoundingMode, Signed, ToPrimitive, Zero};
+use regex::Regex;
+use sqlparser::ast::{BinaryOperator, ExactNumberInfo};
+
+use std::cmp::Ordering;
+use std::sync::LazyLock;
+
+use crate::executor::error::{sqlstate, ExecutorError};
+use crate::executor::evaluator::Result;
+use crate::types::Value;
+
+/// Largest precision a column may declare
+pub const MAX_PRECISION: u32 = 1000;
+/// Significant digits a quotient keeps at least
+const MIN_SIG_DIGITS: i64 = 16;
+/// Largest scale a quotient is given
+const MAX_DISPLAY_SCALE: i64 = 1000;
+/// Limits of the Postgres format on digits before and after the point
+const MAX_WEIGHT_DIGITS: i64 = 131_072;
+const MAX_SCALE: i64 = 16_383;
+/// Decimal digits in one digit of the base-10000 binary format
+const DEC_DIGITS: usize = 4;
+
+fn overflow() -> ExecutorError {
+    ExecutorError::Sql(sqlstate::NUMERIC_VALUE_OUT_OF_RANGE, "value overflows numeric format".to_string())
+}
+
+/// A result within the limits of the format
+fn checked(value: BigDecimal) -> Result<BigDecimal> {
+    let (digits, scale) = value.as_bigint_and_exponent();
+    if scale > MAX_SCALE || (!digits.is_zero() && digits.to_string().trim_start_matches('-').len() as i64 - scale > MAX_WEIGHT_DIGITS) {
+        return Err(overflow());
+    }
+    Ok(value)
+}
+
+/// Precision and scale of `NUMERIC(p, s)`, None for a bare `NUMERIC`
+pub fn typmod(info: &ExactNumberInfo) -> Result<Option<(u32, u32)>> {
+    let (precision, scale) = match info {
+        ExactNumberInfo::None => return Ok(None),
+        ExactNumberInfo::Precision(precision) => (*precision, 0),
+        ExactNumberInfo::PrecisionAndScale(precision, scale) => (*precision, *scale),
+    };
+    if precision < 1 || precision > MAX_PRECISION as u64 {
+        return Err(ExecutorError::Sql(sqlstate::INVALID_PARAMETER_VALUE, format!(
+            "NUMERIC precision {} must be between 1 and {}",
+            precision, MAX_PRECISION
+        )));
+    }
+    if scale < 0 || scale as u64 > precision {
+        return Err(ExecutorError::Sql(sqlstate::INVALID_PARAMETER_VALUE, format!(
+            "NUMERIC scale {} must be between 0 and precision {}",
+            scale, precision
+        )));
+    }
+    Ok(Some((precision as u32, scale as u32)))
+}
+
+/// Round `value` to the scale of `NUMERIC(precision, scale)`, rejecting it
+/// when too many digits remain before the point
+pub fn apply_typmod(value: &BigDecimal, precision: u32, scale: u32) -> Result<BigDecimal> {
+    let rounded = value.with_scale_round(scale as i64, RoundingMode::HalfUp);
+    let limit = BigDecimal::new(BigInt::from(1), -((precision - scale) as i64));
+    if rounded.abs() >= limit {
+        return Err(ExecutorError::Sql(
+            sqlstate::NUMERIC_VALUE_OUT_OF_RANGE,
+            "numeric field overflow".to_string(),
+        ));
+    }
+    Ok(rounded)
+}
+
+// Input and output
+
+static SYNTAX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([+-]?)(\d*)(?:\.(\d*))?(?:[eE]([+-]?\d+))?$").expect("valid regex")
+});
+
+/// Read a numeric from text such as `-12.50`, `.5` or `1.2e3`
+pub fn parse(text: &str) -> Result<BigDecimal> {
+    let invalid = || ExecutorError::Sql(sqlstate::INVALID_TEXT_REPRESENTATION, format!(
+        "invalid input syntax for type numeric: \"{}\"",
+        text
+    ));
+    let captures = SYNTAX.captures(text.trim()).ok_or_else(invalid)?;
+    let integer = captures.get(2).map_or("", |m| m.as_str());
+    let fraction = captures.get(3).map_or("", |m| m.as_str());
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+    let exponent = match captures.get(4) {
+        Some(exponent) => exponent.as_str().parse::<i64>()
+            .ok()
+            .filter(|exponent| exponent.abs() <= MAX_WEIGHT_DIGITS)
+            .ok_or_else(overflow)?,
+        None => 0,
+    };
+    let digits = format!("{}{}{}", captures.get(1).map_or("", |m| m.as_str()), integer, fraction);
+    let digits = digits.parse::<BigInt>().map_err(|_| invalid())?;
+    let value = BigDecimal::new(digits, fraction.len() as i64 - exponent);
+    // An exponent adds zeros before the point rather than a negative scale
+    checked(value.with_scale(value.fractional_digit_count().max(0)))
+}
+
+/// Text of a numeric, with as many decimal places as its scale
+pub fn format(value: &BigDecimal) -> String {
+    let (digits, scale) = value.as_bigint_and_exponent();
+    if scale <= 0 {
+        return value.with_scale(0).as_bigint_and_exponent().0.to_string();
+    }
+    let scale = scale as usize;
+    let magnitude = format!("{:0>width$}", digits.magnitude().to_string(), width = scale + 1);
+    let (integer, fraction) = magnitude.split_at(magnitude.len() - scale);
+    let sign = if digits.sign() == Sign::Minus { "-" } else { "" };
+    format!("{}{}.{}", sign, integer, fraction)
+}
+
+/// Form of a numeric on which equal values agree, for hashing
+pub fn normalized(value: &BigDecimal) -> String {
+    match value.is_zero() {
+        true => "0".to_string(),
+        false => format(&value.normalized().with_scale(value.normalized().fractional_digit_count().max(0))),
+    }
+}
+
+/// Encode a numeric in the Postgres binary format: the count of base-10000
+/// digits, the weight of the first, the sign and the display scale, then the
+/// digits themselves
+pub fn encode_binary(value: &BigDecimal) -> Vec<u8> {
+    let (digits, scale) = value.with_scale(value.fractional_digit_count().max(0)).into_bigint_and_exponent();
+    let scale = scale as usize;
+    let magnitude = format!("{:0>width$}", digits.magnitude().to_string(), width = scale + 1);
+    let (integer, fraction) = magnitude.split_at(magnitude.len() - scale);
+
+    // Group the digits in fours outward from the point
+    let integer = format!("{:0>width$}", integer, width = integer.len().div_ceil(DEC_DIGITS) * DEC_DIGITS);
+    let fraction = format!("{:0<width$}", fraction, width = fraction.len().div_ceil(DEC_DIGITS) * DEC_DIGITS);
+    let group = |chunk: &[u8]| std::str::from_utf8(chunk).ok().and_then(|chunk| chunk.parse::<u16>().ok()).unwrap_or(0);
+    let mut groups = integer.as_bytes().chunks(DEC_DIGITS).map(group).collect::<Vec<_>>();
+    let mut weight = groups.len() as i16 - 1;
+    groups.extend(fraction.as_bytes().chunks(DEC_DIGITS).map(group));
+
+    let leading = groups.iter().take_while(|group| **group == 0).count();
+    groups.drain(..leading);
+    weight -= leading as i16;
+    while groups.last() == Some(&0) {
+        groups.pop();
+    }
+    if groups.is_empty() {
+        weight = 0;
+    }
+
+    let sign: u16 = if digits.sign() == Sign::Minus { 0x4000 } else { 0 };
+    let mut bytes = Vec::with_capacity(8 + 2 * groups.len());
+    bytes.extend((groups.len() as i16).to_be_bytes());
+    bytes.extend(weight.to_be_bytes());
+    bytes.extend(sign.to_be_bytes());
+    bytes.extend((scale as u16).to_be_bytes());
+    for group in groups {
+        bytes.extend(group.to_be_bytes());
+    }
+    bytes
+}
+
+/// Decode a numeric sent in the Postgres binary format, None when malformed
+/// (or NaN, which has no numeric value here)
+pub fn decode_binary(bytes: &[u8]) -> Option<BigDecimal> {
+    let word = |idx: usize| bytes.get(idx..idx + 2).map(|word| u16::from_be_bytes([word[0], word[1]]));
+    let count = word(0)? as usize;
+    let weight = word(2)? as i16;
+    let sign = word(4)?;
+    let scale = word(6)?;
+    if bytes.len() != 8 + 2 * count || !matches!(sign, 0 | 0x4000) || scale as i64 > MAX_SCALE {
+        return None;
+    }
+    let mut digits = BigInt::zero();
+    for idx in 0..count {
+        let group = word(8 + 2 * idx)?;
+        if group >= 10_000 {
+            return None;
+        }
+        digits = digits * 10_000 + group;
+    }
+    if sign == 0x4000 {
+        digits = -digits;
+    }
+    // The last digit counts units of 10000^(weight - count + 1)
+    let exponent = (weight as i64 - count as i64 + 1) * DEC_DIGITS as i64;
+    Some(BigDecimal::new(digits, -exponent).with_scale(scale as i64))
+}
+
+// Conversions
+
+/// A double as a numeric, to the 15 significant digits a double holds
+pub fn from_float(f: f64) -> Result<BigDecimal> {
+    if f.is_nan() || f.is_infinite() {
+        return Err(ExecutorError::Sql(sqlstate::NUMERIC_VALUE_OUT_OF_RANGE, format!(
+            "cannot convert {} to numeric",
+            if f.is_nan() { "NaN" } else { "infinity" }
+        )));
+    }
+    let value = parse(&format!("{:.14e}", f))?;
+    // Trailing zeros of the rounded digits are not significant
+    let scale = value.normalized().fractional_digit_count().max(0);
+    Ok(value.with_scale(scale))
+}
+
+pub fn to_float(value: &BigDecimal) -> f64 {
+    format(value).parse().unwrap_or(f64::NAN)
+}
+
+/// A numeric rounded to the nearest integer, halves away from zero
+pub fn to_int(value: &BigDecimal) -> Result<i64> {
+    value.with_scale_round(0, RoundingMode::HalfUp)
+        .to_i64()
+        .ok_or_else(|| ExecutorError::out_of_range("bigint"))
+}
+
+/// Round to `places` decimal places (negative to round left of the point)
+/// with the given mode: `HalfUp` for round(), `Down` for trunc()
+pub fn round(value: &BigDecimal, places: i64, mode: RoundingMode) -> BigDecimal {
+    let places = places.clamp(-MAX_DISPLAY_SCALE, MAX_DISPLAY_SCALE);
+    let rounded = value.with_scale_round(places, mode);
+    if places < 0 { rounded.with_scale(0) } else { rounded }
+}
+
+// Operators
+
+/// Weight of a number's first base-10000 digit, and that digit, as Postgres
+/// stores the number
+fn first_digit(value: &BigDecimal) -> (i64, u32) {
+    if value.is_zero() {
+        return (0, 0);
+    }
+    let (digits, scale) = value.as_bigint_and_exponent();
+    let magnitude = digits.magnitude().to_string();
+    let exponent = magnitude.len() as i64 - 1 - scale;
+    let weight = exponent.div_euclid(DEC_DIGITS as i64);
+    let width = (exponent - weight * DEC_DIGITS as i64 + 1) as usize;
+    let first = format!("{:0<width$}", &magnitude[..width.min(magnitude.len())], width = width);
+    (weight, first.parse().unwrap_or(0))
+}
+
+/// Scale of `a / b`: enough for 16 significant digits, and no less than
+/// either operand's, as Postgres gives quotients
+fn division_scale(a: &BigDecimal, b: &BigDecimal) -> i64 {
+    let (weight_a, first_a) = first_digit(a);
+    let (weight_b, first_b) = first_digit(b);
+    let mut weight = weight_a - weight_b;
+    if first_a < first_b {
+        weight -= 1;
+    }
+    (MIN_SIG_DIGITS - weight * DEC_DIGITS as i64)
+        .max(a.fractional_digit_count())
+        .max(b.fractional_digit_count())
+        .clamp(0, MAX_DISPLAY_SCALE)
+}
+
+/// `a / b` rounded, halves away from zero, to `scale` places
+fn divide_to_scale(a: &BigDecimal, b: &BigDecimal, scale: i64) -> BigDecimal {
+    let (a_digits, a_scale) = a.as_bigint_and_exponent();
+    let (b_digits, b_scale) = b.as_bigint_and_exponent();
+    let shift = scale - a_scale + b_scale;
+    let ten = |power: i64| BigInt::from(10).pow(power as u32);
+    let (numerator, denominator) = if shift >= 0 {
+        (a_digits * ten(shift), b_digits)
+    } else {
+        (a_digits, b_digits * ten(-shift))
+    };
+    let (mut quotient, remainder) = (&numerator / &denominator, &numerator % &denominator);
+    if remainder.abs() * 2 >= denominator.abs() {
+        quotient += if numerator.sign() == denominator.sign() { 1 } else { -1 };
+    }
+    BigDecimal::new(quotient, scale)
+}
+
+pub fn divide(a: &BigDecimal, b: &BigDecimal) -> Result<BigDecimal> {
+    if b.is_zero() {
+        return Err(ExecutorError::division_by_zero());
+    }
+    checked(divide_to_scale(a, b, division_scale(a, b)))
+}
+
+/// Digits of `a` and `b` at the larger of their scales, and that scale
+fn aligned(a: &BigDecimal, b: &BigDecimal) -> (BigInt, BigInt, i64) {
+    let scale = a.fractional_digit_count().max(b.fractional_digit_count()).max(0);
+    let (a_digits, _) = a.with_scale(scale).into_bigint_and_exponent();
+    let (b_digits, _) = b.with_scale(scale).into_bigint_and_exponent();
+    (a_digits, b_digits, scale)
+}
+
+/// Remainder of `a / b` truncated toward zero, at the larger scale
+fn remainder(a: &BigDecimal, b: &BigDecimal) -> Result<BigDecimal> {
+    if b.is_zero() {
+        return Err(ExecutorError::division_by_zero());
+    }
+    let (a_digits, b_digits, scale) = aligned(a, b);
+    Ok(BigDecimal::new(a_digits % b_digits, scale))
+}
+
+pub fn add(a: &BigDecimal, b: &BigDecimal) -> Result<BigDecimal> {
+    let (a_digits, b_digits, scale) = aligned(a, b);
+    checked(BigDecimal::new(a_digits + b_digits, scale))
+}
+
+/// Apply an arithmetic operator to numerics
+///
+/// Sums and differences take the larger scale of the operands, products the
+/// sum of their scales. The digits are combined directly, as `BigDecimal`'s
+/// own operators drop the scale of a product with one.
+pub fn eval_op(a: &BigDecimal, op: &BinaryOperator, b: &BigDecimal) -> Option<Result<Value>> {
+    let (a_digits, a_scale) = a.as_bigint_and_exponent();
+    let (b_digits, b_scale) = b.as_bigint_and_exponent();
+    let result = match op {
+        BinaryOperator::Plus => add(a, b),
+        BinaryOperator::Minus => {
+            let (a_digits, b_digits, scale) = aligned(a, b);
+            checked(BigDecimal::new(a_digits - b_digits, scale))
+        }
+        BinaryOperator::Multiply => checked(BigDecimal::new(a_digits * b_digits, a_scale + b_scale)),
+        BinaryOperator::Divide => divide(a, b),
+        BinaryOperator::Modulo => remainder(a, b),
+        _ => return None,
+    };
+    Some(result.map(Value::Numeric))
+}
+
+/// Order two numerics by value, whatever their scales
+pub fn compare(a: &BigDecimal, b: &BigDecimal) -> Ordering {
+    a.cmp(b)
+}
@@ -0,0 +1,127 @@
+//! Pattern matching for LIKE, ILIKE and SIMILAR TO
+
+use regex::Regex;
+
+use crate::executor::error::{sqlstate, ExecutorError};
+use crate::executor::evaluator::Result;
+
+/// One element of a LIKE pattern
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    /// `%`: any run of characters, possibly empty
+    Any,
+    /// `_`: exactly one character
+    One,
+    Literal(char),
+}
+
+/// Whether `text` matches the LIKE `pattern`
+///
+/// `escape` makes the character after it literal; Postgres defaults it to a
+/// backslash, and an empty ESCAPE clause (None here) turns escaping off.
+pub fn like(text: &str, pattern: &str, escape: Option<char>, case_insensitive: bool) -> Result<bool> {
+    let tokens = like_tokens(pattern, escape)?;
+    let text = text.chars().collect::<Vec<_>>();
+    let same = |a: char, b: char| a == b || (case_insensitive && a.to_lowercase().eq(b.to_lowercase()));
+
+    // Greedy match that backtracks to the most recent `%` on a mismatch
+    let (mut t, mut p) = (0, 0);
+    let mut retry: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match tokens.get(p) {
+            Some(Token::Any) => {
+                retry = Some((p, t));
+                p += 1;
+            }
+            Some(Token::One) => {
+                t += 1;
+                p += 1;
+            }
+            Some(Token::Literal(c)) if same(*c, text[t]) => {
+                t += 1;
+                p += 1;
+            }
+            _ => match retry {
+                // Let the `%` absorb one more character and try again
+                Some((any, start)) => {
+                    p = any + 1;
+                    t = start + 1;
+                    retry = Some((any, start + 1));
+                }
+                None => return Ok(false),
+            },
+        }
+    }
+    Ok(tokens[p..].iter().all(|token| *token == Token::Any))
+}
+
+fn like_tokens(pattern: &str, escape: Option<char>) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if Some(c) == escape => match chars.next() {
+                Some(escaped) => Token::Literal(escaped),
+                None => {
+                    return Err(ExecutorError::Sql(
+                        sqlstate::INVALID_ESCAPE_SEQUENCE,
+                        "LIKE pattern must not end with escape character".to_string(),
+                    ));
+                }
+            },
+            '%' => Token::Any,
+            '_' => Token::One,
+            c => Token::Literal(c),
+        };
+        // Consecutive `%` match the same as one
+        if !(token == Token::Any && tokens.last() == Some(&Token::Any)) {
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+/// Whether `text` matches the SIMILAR TO `pattern`
+///
+/// The pattern is a regular expression in which `%` and `_` stand for `.*`
+/// and `.`, and which must match the whole string.
+pub fn similar(text: &str, pattern: &str, escape: Option<char>) -> Result<bool> {
+    let mut regex = String::from("(?s)^(?:");
+    let mut chars = pattern.chars();
+    let mut in_brackets = false;
+    while let Some(c) = chars.next() {
+        match c {
+            c if Some(c) == escape => match chars.next() {
+                Some(escaped) => regex.push_str(&regex::escape(&escaped.to_string())),
+                None => {
+                    return Err(ExecutorError::Sql(
+                        sqlstate::INVALID_ESCAPE_SEQUENCE,
+                        "SIMILAR TO pattern must not end with escape character".to_string(),
+                    ));
+                }
+            },
+            // Bracket expressions are copied as they are
+            _ if in_brackets => {
+                if c == ']' {
+                    in_brackets = false;
+                }
+                regex.push(c);
+            }
+            '[' => {
+                in_brackets = true;
+                regex.push(c);
+            }
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            '|' | '*' | '+' | '?' | '{' | '}' | '(' | ')' => regex.push(c),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push_str(")$");
+
+    let regex = Regex::new(&regex).map_err(|e| ExecutorError::Sql(
+        sqlstate::INVALID_REGULAR_EXPRESSION,
+        format!("invalid regular expression: {}", e),
+    ))?;
+    Ok(regex.is_match(text))
+}
@@ -0,0 +1,380 @@
+//! Parsed statements and plans kept between executions
+//!
+//! Query strings sent as simple queries are cached by their normalized text,
+//! with the plan last made for each statement. Prepared statements keep
+//! their own plans. A plan is reused only under the catalog generation and
+//! settings it was made with: DDL and ANALYZE start a new generation.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::Mutex;
+use sqlparser::ast::Statement;
+
+use crate::config::{PlanCacheMode, Settings};
+use crate::planner::Operator;
+use crate::planner::cost::CPU_OPERATOR_COST;
+
+/// Query strings kept before the least recently used is dropped
+const CAPACITY: usize = 256;
+/// Custom plans made for a prepared statement before a generic plan is tried
+const CUSTOM_PLANS_FIRST: u64 = 5;
+
+/// A plan and the conditions it was made under
+#[derive(Debug, Clone)]
+pub(crate) struct CachedPlan {
+    pub plan: Operator,
+    /// Estimated total cost
+    pub cost: f64,
+    generation: u64,
+    settings: Settings,
+}
+
+impl CachedPlan {
+    pub fn new(plan: Operator, cost: f64, generation: u64, settings: Settings) -> Self {
+        CachedPlan { plan, cost, generation, settings }
+    }
+
+    fn is_valid(&self, generation: u64, settings: &Settings) -> bool {
+        self.generation == generation && self.settings == *settings
+    }
+}
+
+/// Where the plan for one statement is kept
+#[derive(Debug, Default)]
+pub(crate) struct PlanSlot(Mutex<Option<CachedPlan>>);
+
+/// A statement of a cached query string
+#[derive(Debug)]
+pub(crate) struct CachedStatement {
+    pub statement: Statement,
+    pub plan: PlanSlot,
+}
+
+/// Plans of a prepared statement, and how its custom plans have cost so far
+#[derive(Debug, Default)]
+pub(crate) struct PreparedPlans {
+    pub generic: PlanSlot,
+    choice: Mutex<Choice>,
+}
+
+#[derive(Debug, Default)]
+struct Choice {
+    custom_plans: u64,
+    /// Sum of the estimated costs of the custom plans
+    custom_cost: f64,
+    /// Generation in which the statement could not be planned without its
+    /// parameter values (say, for `LIMIT $1`)
+    generic_failed: Option<u64>,
+}
+
+impl PreparedPlans {
+    /// Whether to look for a generic plan before making a custom one
+    pub fn wants_generic(&self, mode: PlanCacheMode, generation: u64) -> bool {
+        let choice = self.choice.lock();
+        match mode {
+            PlanCacheMode::ForceCustomPlan => false,
+            _ if choice.generic_failed == Some(generation) => false,
+            PlanCacheMode::ForceGenericPlan => true,
+            PlanCacheMode::Auto => choice.custom_plans >= CUSTOM_PLANS_FIRST,
+        }
+    }
+
+    /// Whether the generic `plan` should be used over a custom plan
+    ///
+    /// Like Postgres, the generic plan wins unless it costs more than custom
+    /// plans have on average, counting what planning each of those cost.
+    pub fn prefers_generic(&self, mode: PlanCacheMode, plan: &CachedPlan) -> bool {
+        if mode == PlanCacheMode::ForceGenericPlan {
+            return true;
+        }
+        let choice = self.choice.lock();
+        if choice.custom_plans == 0 {
+            return true;
+        }
+        let planning = 1000.0 * CPU_OPERATOR_COST * (relations(&plan.plan) + 1) as f64;
+        plan.cost <= choice.custom_cost / choice.custom_plans as f64 + planning
+    }
+
+    pub fn record_custom(&self, cost: f64) {
+        let mut choice = self.choice.lock();
+        choice.custom_plans += 1;
+        choice.custom_cost += cost;
+    }
+
+    pub fn record_generic_failure(&self, generation: u64) {
+        self.choice.lock().generic_failed = Some(generation);
+    }
+}
+
+/// Number of table reads in a plan
+fn relations(plan: &Operator) -> usize {
+    let own = match plan {
+        Operator::TableScan { table, .. } => usize::from(table != "__constant__"),
+        Operator::IndexScan { .. } | Operator::IndexRangeScan { .. } | Operator::BitmapHeapScan { .. } => 1,
+        _ => 0,
+    };
+    own + plan.children().into_iter().map(relations).sum::<usize>()
+}
+
+/// Cached query strings, the catalog generation and hit counters
+#[derive(Default)]
+pub(crate) struct PlanCache {
+    queries: Mutex<Queries>,
+    generation: AtomicU64,
+    counters: Counters,
+}
+
+#[derive(Default)]
+struct Queries {
+    entries: HashMap<String, (Arc<Vec<CachedStatement>>, u64)>,
+    /// Ticks on every lookup; entries record the tick they were last used at
+    clock: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    query_hits: AtomicU64,
+    query_misses: AtomicU64,
+    plan_hits: AtomicU64,
+    plan_misses: AtomicU64,
+    invalidations: AtomicU64,
+    generic_plans: AtomicU64,
+    custom_plans: AtomicU64,
+}
+
+/// Snapshot of the cache counters
+#[derive(Debug, Clone)]
+pub(crate) struct CacheStats {
+    pub entries: usize,
+    pub query_hits: u64,
+    pub query_misses: u64,
+    pub plan_hits: u64,
+    pub plan_misses: u64,
+    pub invalidations: u64,
+    pub generic_plans: u64,
+    pub custom_plans: u64,
+}
+
+impl CacheStats {
+    /// Statistics as `SHOW plan_cache` lists them
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let rate = |hits: u64, misses: u64| match hits + misses {
+            0 => "0.00".to_string(),
+            total => format!("{:.2}", hits as f64 / total as f64),
+        };
+        vec![
+            ("entries", self.entries.to_string()),
+            ("query_hits", self.query_hits.to_string()),
+            ("query_misses", self.query_misses.to_string()),
+            ("query_hit_rate", rate(self.query_hits, self.query_misses)),
+            ("plan_hits", self.plan_hits.to_string()),
+            ("plan_misses", self.plan_misses.to_string()),
+            ("plan_hit_rate", rate(self.plan_hits, self.plan_misses)),
+            ("invalidations", self.invalidations.to_string()),
+            ("generic_plans", self.generic_plans.to_string()),
+            ("custom_plans", self.custom_plans.to_string()),
+        ]
+    }
+}
+
+/// Plans counted by `PlanCache::record`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PlanUse {
+    /// A cached plan was reused
+    Hit,
+    /// A statement was planned, none being cached
+    Miss,
+    /// A statement was replanned, its cached plan being out of date
+    Invalidated,
+    /// A prepared statement ran with its generic plan
+    Generic,
+    /// A prepared statement was planned for its parameter values
+    Custom,
+}
+
+impl PlanCache {
+    pub fn new() -> Self {
+        PlanCache::default()
+    }
+
+    /// Current catalog generation
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Start a new catalog generation, so that every cached plan is replanned
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Statements of a query string, parsed with `parse` unless cached
+    ///
+    /// Only query strings made of queries are cached: other statements are
+    /// not planned, and bulk INSERTs would crowd the cache out.
+    pub fn statements<E>(
+        &self,
+        query: &str,
+        parse: impl FnOnce(&str) -> Result<Vec<Statement>, E>,
+    ) -> Result<Arc<Vec<CachedStatement>>, E> {
+        let key = normalize(query);
+        {
+            let mut queries = self.queries.lock();
+            queries.clock += 1;
+            let now = queries.clock;
+            if let Some((statements, last_used)) = queries.entries.get_mut(&key) {
+                *last_used = now;
+                self.counters.query_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(statements.clone());
+            }
+        }
+        let statements = parse(query)?;
+        let cacheable = !statements.is_empty() && statements.iter().all(|stmt| matches!(stmt, Statement::Query(_)));
+        let statements = Arc::new(statements.into_iter()
+            .map(|statement| CachedStatement { statement, plan: PlanSlot::default() })
+            .collect::<Vec<_>>());
+        if cacheable {
+            self.counters.query_misses.fetch_add(1, Ordering::Relaxed);
+            let mut queries = self.queries.lock();
+            if queries.entries.len() >= CAPACITY
+                && let Some(oldest) = queries.entries.iter()
+                    .min_by_key(|(_, (_, last_used))| *last_used)
+                    .map(|(key, _)| key.clone())
+            {
+                queries.entries.remove(&oldest);
+            }
+            let now = queries.clock;
+            queries.entries.insert(key, (statements.clone(), now));
+        }
+        Ok(statements)
+    }
+
+    /// The plan in `slot`, if it was made under the current generation and
+    /// `settings`
+    ///
+    /// Otherwise says whether the slot was empty (`Miss`) or its plan out of
+    /// date (`Invalidated`).
+    pub fn lookup(&self, slot: &PlanSlot, settings: &Settings) -> Result<CachedPlan, PlanUse> {
+        match slot.0.lock().as_ref() {
+            Some(plan) if plan.is_valid(self.generation(), settings) => Ok(plan.clone()),
+            Some(_) => Err(PlanUse::Invalidated),
+            None => Err(PlanUse::Miss),
+        }
+    }
+
+    pub fn store(&self, slot: &PlanSlot, plan: CachedPlan) {
+        *slot.0.lock() = Some(plan);
+    }
+
+    pub fn record(&self, plan_use: PlanUse) {
+        let counter = match plan_use {
+            PlanUse::Hit => &self.counters.plan_hits,
+            PlanUse::Miss => &self.counters.plan_misses,
+            PlanUse::Invalidated => {
+                self.counters.plan_misses.fetch_add(1, Ordering::Relaxed);
+                &self.counters.invalidations
+            }
+            PlanUse::Generic => &self.counters.generic_plans,
+            PlanUse::Custom => &self.counters.custom_plans,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        CacheStats {
+            entries: self.queries.lock().entries.len(),
+            query_hits: load(&self.counters.query_hits),
+            query_misses: load(&self.counters.query_misses),
+            plan_hits: load(&self.counters.plan_hits),
+            plan_misses: load(&self.counters.plan_misses),
+            invalidations: load(&self.counters.invalidations),
+            generic_plans: load(&self.counters.generic_plans),
+            custom_plans: load(&self.counters.custom_plans),
+        }
+    }
+}
+
+/// Key under which a query string is cached
+///
+/// Comments and runs of whitespace outside quoted strings, dollar-quoted
+/// strings and identifiers become a single space and trailing semicolons are
+/// dropped, so texts that differ only in layout share an entry.
+pub(crate) fn normalize(query: &str) -> String {
+    let mut key = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+    let mut space = false;
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => space = true,
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                space = true;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                space = true;
+            }
+            _ => {
+                if space && !key.is_empty() {
+                    key.push(' ');
+                }
+                space = false;
+                // Backslashes escape quotes only in E'...' strings
+                let escapes = c == '\'' && key.ends_with(['E', 'e']);
+                // A `$` inside an identifier does not open a string
+                let dollar = c == '$' && !key.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '$');
+                key.push(c);
+                if dollar && let Some(tag) = dollar_tag(&chars) {
+                    let delimiter = format!("${}$", tag);
+                    key.extend(chars.by_ref().take(tag.chars().count() + 1));
+                    let body = key.len();
+                    while !key[body..].ends_with(&delimiter) && let Some(inner) = chars.next() {
+                        key.push(inner);
+                    }
+                } else if c == '\'' || c == '"' {
+                    while let Some(inner) = chars.next() {
+                        key.push(inner);
+                        if escapes && inner == '\\' {
+                            if let Some(escaped) = chars.next() {
+                                key.push(escaped);
+                            }
+                        } else if inner == c {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let trimmed = key.trim_end_matches([';', ' ']).len();
+    key.truncate(trimmed);
+    key
+}
+
+/// Tag of a dollar-quoted string whose opening `$` was just read, if the
+/// text after it completes a `$tag$` delimiter; `$1` is a parameter
+fn dollar_tag(rest: &std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let mut tag = String::new();
+    for c in rest.clone() {
+        match c {
+            '$' => return Some(tag),
+            c if c.is_ascii_digit() && tag.is_empty() => return None,
+            c if c.is_alphanumeric() || c == '_' => tag.push(c),
+            _ => return None,
+        }
+    }
+    None
+}
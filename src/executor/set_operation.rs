@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+
+use sqlparser::ast::Expr;
+use tracing::debug;
+
+use crate::executor::coercion;
+use crate::executor::error::{sqlstate, ExecutorError};
+use crate::executor::evaluator::{self, Result};
+use crate::executor::join::hash_keys;
+use crate::extensions::CastContext;
+use crate::extensions::registry::Registries;
+use crate::planner::SetOperator;
+use crate::types::{Column, HashKey, Row, Schema};
+
+/// Keep the first row for each distinct value of `keys`, preserving input order
+///
+/// With no keys whole rows are compared. NULLs compare equal to each other.
+pub fn distinct(schema: &Schema, rows: Vec<Row>, keys: &[Expr], registries: &Registries) -> Result<Vec<Row>> {
+    let mut seen = Seen::new(schema.clone(), keys.to_vec(), registries.clone());
+    let mut kept = Vec::new();
+    for row in rows {
+        if seen.is_new(&row)? {
+            kept.push(row);
+        }
+    }
+    Ok(kept)
+}
+
+/// Distinct values of `keys` among the rows checked so far
+pub struct Seen {
+    schema: Schema,
+    keys: Vec<Expr>,
+    registries: Registries,
+    seen: HashSet<Vec<HashKey>>,
+}
+
+impl Seen {
+    pub fn new(schema: Schema, keys: Vec<Expr>, registries: Registries) -> Self {
+        Seen {
+            schema,
+            keys,
+            registries,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Record the row's key, returning whether it was not seen before
+    pub fn is_new(&mut self, row: &Row) -> Result<bool> {
+        let key = if self.keys.is_empty() {
+            hash_keys(&row.values)?
+        } else {
+            let values = self.keys.iter()
+                .map(|expr| evaluator::eval_expr(expr, row, &self.schema, &self.registries))
+                .collect::<Result<Vec<_>>>()?;
+            hash_keys(&values)?
+        };
+        Ok(self.seen.insert(key))
+    }
+}
+
+/// Combine the rows of two inputs with UNION, INTERSECT or EXCEPT
+///
+/// Without `all` the result holds each distinct row once. With `all`, a row
+/// appearing m times on the left and n times on the right appears m + n times
+/// (UNION), min(m, n) times (INTERSECT) or max(m - n, 0) times (EXCEPT).
+/// Output columns take the left input's names and the common type of both.
+pub fn set_operation(
+    op: SetOperator,
+    all: bool,
+    left_schema: Schema,
+    left_rows: Vec<Row>,
+    right_schema: &Schema,
+    right_rows: Vec<Row>,
+    registries: &Registries,
+) -> Result<(Schema, Vec<Row>)> {
+    debug!(op = op.name(), all, left = left_rows.len(), right = right_rows.len(), "set operation");
+
+    let schema = common_schema(op, left_schema, right_schema, registries)?;
+    let left_rows = coerce_rows(&schema, left_rows, registries)?;
+    let right_rows = coerce_rows(&schema, right_rows, registries)?;
+
+    let rows = match op {
+        SetOperator::Union => {
+            let mut rows = left_rows;
+            rows.extend(right_rows);
+            if all {
+                rows
+            } else {
+                distinct(&schema, rows, &[], registries)?
+            }
+        }
+        SetOperator::Intersect | SetOperator::Except => {
+            // Occurrences of each right row still available to match
+            let mut counts: HashMap<Vec<HashKey>, usize> = HashMap::new();
+            for row in &right_rows {
+                *counts.entry(hash_keys(&row.values)?).or_default() += 1;
+            }
+
+            let mut seen = HashSet::new();
+            let mut rows = Vec::new();
+            for row in left_rows {
+                let key = hash_keys(&row.values)?;
+                let matched = match counts.get_mut(&key) {
+                    Some(count) if *count > 0 => {
+                        // ALL consumes one right occurrence per matched left row
+                        if all {
+                            *count -= 1;
+                        }
+                        true
+                    }
+                    _ => false,
+                };
+                let keep = matched == (op == SetOperator::Intersect);
+                if keep && (all || seen.insert(key)) {
+                    rows.push(row);
+                }
+            }
+            rows
+        }
+    };
+
+    Ok((schema, rows))
+}
+
+/// Output schema of a set operation: left column names, common column types
+/// (see `coercion::common_type`)
+pub(crate) fn common_schema(op: SetOperator, left: Schema, right: &Schema, registries: &Registries) -> Result<Schema> {
+    let columns = left.columns.into_iter()
+        .zip(&right.columns)
+        .map(|(column, other)| {
+            let data_type = coercion::common_type(&column.data_type, &other.data_type, registries)
+                .ok_or_else(|| ExecutorError::Sql(sqlstate::DATATYPE_MISMATCH, format!(
+                    "{} types {} and {} cannot be matched",
+                    op.name(),
+                    evaluator::type_name(&column.data_type),
+                    evaluator::type_name(&other.data_type)
+                )))?;
+            Ok(Column {
+                name: column.name,
+                data_type,
+                is_primary_key: false,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Schema::new(columns))
+}
+
+/// Convert values to their column's common type, so equal values compare
+/// equal and every row of a column has its type
+fn coerce_rows(schema: &Schema, mut rows: Vec<Row>, registries: &Registries) -> Result<Vec<Row>> {
+    for row in &mut rows {
+        for (value, column) in row.values.iter_mut().zip(&schema.columns) {
+            if coercion::value_type(value, registries) != column.data_type {
+                *value = coercion::cast(value, &column.data_type, CastContext::Implicit, registries)?;
+            }
+        }
+    }
+    Ok(rows)
+}
@@ -0,0 +1,407 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+use tracing::debug;
+
+use crate::executor::error::ExecutorError;
+use crate::executor::evaluator::{self, Result};
+use crate::extensions::registry::Registries;
+use crate::planner::SortKey;
+use crate::types::{Row, Schema, Value};
+
+/// Counter for unique spill file names within this process
+static NEXT_SPILL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Direction of one sort key
+#[derive(Debug, Clone, Copy)]
+struct KeyOrder {
+    descending: bool,
+    nulls_first: bool,
+}
+
+/// How a sort ran, as EXPLAIN ANALYZE reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMethod {
+    /// Only the first `limit` rows were kept, in a bounded heap
+    TopN,
+    /// Every row fit in `work_mem`
+    InMemory,
+    /// Sorted runs were spilled to disk and merged
+    External {
+        runs: usize,
+        /// Bytes written to the spill files
+        disk_bytes: u64,
+    },
+}
+
+impl SortMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortMethod::TopN => "top-N heapsort",
+            SortMethod::InMemory => "quicksort",
+            SortMethod::External { .. } => "external merge",
+        }
+    }
+}
+
+/// Sort rows by `keys`
+///
+/// Rows are pulled from the input into an in-memory run of at most
+/// `work_mem` bytes. Each time the run fills up it is sorted and spilled to
+/// a temporary file, so the input is never held in memory as a whole. The
+/// returned rows are merged from the spilled runs as they are read. With a
+/// `limit`, only the first `limit` rows are kept in a bounded heap and
+/// nothing is spilled.
+pub fn sort(
+    schema: &Schema,
+    rows: impl Iterator<Item = Result<Row>>,
+    keys: &[SortKey],
+    limit: Option<u64>,
+    work_mem: usize,
+    registries: &Registries,
+) -> Result<SortedRows> {
+    let order: Arc<[KeyOrder]> = keys.iter()
+        .map(|key| KeyOrder { descending: key.descending, nulls_first: key.nulls_first })
+        .collect();
+    let mut checker = KeyTypeChecker::new(keys.len());
+
+    if let Some(limit) = limit {
+        let sorted = top_n(schema, rows, keys, &order, &mut checker, limit as usize, registries)?;
+        return Ok(SortedRows {
+            method: SortMethod::TopN,
+            source: SortedSource::Memory(sorted.into_iter()),
+        });
+    }
+
+    let mut runs = Vec::new();
+    let mut run: Vec<SortEntry> = Vec::new();
+    let mut run_bytes = 0;
+
+    for (seq, row) in rows.enumerate() {
+        let entry = SortEntry::new(schema, row?, keys, &order, &mut checker, seq, registries)?;
+        run_bytes += entry.estimated_size();
+        run.push(entry);
+
+        if run_bytes > work_mem {
+            runs.push(SpillRun::write(std::mem::take(&mut run))?);
+            run_bytes = 0;
+        }
+    }
+
+    // Everything fit in work_mem: plain in-memory sort
+    if runs.is_empty() {
+        run.sort();
+        return Ok(SortedRows {
+            method: SortMethod::InMemory,
+            source: SortedSource::Memory(run.into_iter()),
+        });
+    }
+    if !run.is_empty() {
+        runs.push(SpillRun::write(run)?);
+    }
+
+    debug!(runs = runs.len(), work_mem, "external sort: merging spilled runs");
+    let method = SortMethod::External {
+        runs: runs.len(),
+        disk_bytes: runs.iter().map(|run| run.bytes).sum(),
+    };
+    Ok(SortedRows {
+        method,
+        source: SortedSource::Merge(MergeRuns::new(runs, order)?),
+    })
+}
+
+/// Keep the first `limit` rows in a max-heap, evicting the largest entry
+fn top_n(
+    schema: &Schema,
+    rows: impl Iterator<Item = Result<Row>>,
+    keys: &[SortKey],
+    order: &Arc<[KeyOrder]>,
+    checker: &mut KeyTypeChecker,
+    limit: usize,
+    registries: &Registries,
+) -> Result<Vec<SortEntry>> {
+    debug!(limit, "top-N sort");
+    let mut heap = BinaryHeap::new();
+
+    for (seq, row) in rows.enumerate() {
+        let entry = SortEntry::new(schema, row?, keys, order, checker, seq, registries)?;
+        if heap.len() < limit {
+            heap.push(entry);
+        } else if let Some(mut largest) = heap.peek_mut()
+            && entry < *largest
+        {
+            *largest = entry;
+        }
+    }
+
+    Ok(heap.into_sorted_vec())
+}
+
+/// Sorted rows, read from memory or merged from spilled runs
+pub struct SortedRows {
+    method: SortMethod,
+    source: SortedSource,
+}
+
+enum SortedSource {
+    Memory(std::vec::IntoIter<SortEntry>),
+    Merge(MergeRuns),
+}
+
+impl SortedRows {
+    pub fn method(&self) -> SortMethod {
+        self.method
+    }
+}
+
+impl Iterator for SortedRows {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.source {
+            SortedSource::Memory(entries) => entries.next().map(|entry| Ok(entry.row)),
+            SortedSource::Merge(merge) => merge.next_row().transpose(),
+        }
+    }
+}
+
+/// K-way merge of sorted runs, reading each run as its rows are needed
+struct MergeRuns {
+    readers: Vec<SpillReader>,
+    /// Smallest unread entry of each run that has one left
+    heap: BinaryHeap<std::cmp::Reverse<(SortEntry, usize)>>,
+    order: Arc<[KeyOrder]>,
+}
+
+impl MergeRuns {
+    fn new(runs: Vec<SpillRun>, order: Arc<[KeyOrder]>) -> Result<Self> {
+        let mut readers = Vec::with_capacity(runs.len());
+        let mut heap = BinaryHeap::with_capacity(runs.len());
+
+        for (run_idx, run) in runs.into_iter().enumerate() {
+            let mut reader = run.into_reader()?;
+            if let Some(entry) = reader.next_entry(&order)? {
+                heap.push(std::cmp::Reverse((entry, run_idx)));
+            }
+            readers.push(reader);
+        }
+        Ok(MergeRuns { readers, heap, order })
+    }
+
+    fn next_row(&mut self) -> Result<Option<Row>> {
+        let Some(std::cmp::Reverse((entry, run_idx))) = self.heap.pop() else {
+            return Ok(None);
+        };
+        match self.readers[run_idx].next_entry(&self.order) {
+            Ok(Some(next)) => self.heap.push(std::cmp::Reverse((next, run_idx))),
+            Ok(None) => {}
+            Err(e) => {
+                // Stop after reporting the error
+                self.heap.clear();
+                return Err(e);
+            }
+        }
+        Ok(Some(entry.row))
+    }
+}
+
+/// A row with its evaluated sort keys
+struct SortEntry {
+    keys: Vec<Value>,
+    row: Row,
+    /// Input position, to keep equal keys in input order
+    seq: usize,
+    order: Arc<[KeyOrder]>,
+}
+
+impl SortEntry {
+    fn new(
+        schema: &Schema,
+        row: Row,
+        keys: &[SortKey],
+        order: &Arc<[KeyOrder]>,
+        checker: &mut KeyTypeChecker,
+        seq: usize,
+        registries: &Registries,
+    ) -> Result<Self> {
+        let key_values = keys.iter()
+            .map(|key| evaluator::eval_expr(&key.expr, &row, schema, registries))
+            .collect::<Result<Vec<_>>>()?;
+        checker.check(&key_values)?;
+        Ok(SortEntry {
+            keys: key_values,
+            row,
+            seq,
+            order: order.clone(),
+        })
+    }
+
+    /// Rough in-memory footprint, used to decide when to spill
+    fn estimated_size(&self) -> usize {
+        let value_size = |value: &Value| match value {
+            Value::String(s) => std::mem::size_of::<Value>() + s.len(),
+            _ => std::mem::size_of::<Value>(),
+        };
+        std::mem::size_of::<Self>()
+            + self.keys.iter().map(value_size).sum::<usize>()
+            + self.row.values.iter().map(value_size).sum::<usize>()
+    }
+}
+
+impl Ord for SortEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for ((left, right), key_order) in self.keys.iter().zip(&other.keys).zip(self.order.iter()) {
+            let ordering = match (left, right) {
+                (Value::Null, Value::Null) => Ordering::Equal,
+                (Value::Null, _) if key_order.nulls_first => Ordering::Less,
+                (Value::Null, _) => Ordering::Greater,
+                (_, Value::Null) if key_order.nulls_first => Ordering::Greater,
+                (_, Value::Null) => Ordering::Less,
+                // Key types were checked when the entry was built
+                _ => {
+                    let ordering = evaluator::compare_values(left, right).unwrap_or(Ordering::Equal);
+                    if key_order.descending { ordering.reverse() } else { ordering }
+                }
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        self.seq.cmp(&other.seq)
+    }
+}
+
+impl PartialOrd for SortEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortEntry {}
+
+/// Ensures every sort key holds mutually comparable values, so that
+/// comparisons during the sort itself cannot fail
+struct KeyTypeChecker {
+    /// Type class of the first non-NULL value seen for each key
+    classes: Vec<Option<u8>>,
+}
+
+impl KeyTypeChecker {
+    fn new(key_count: usize) -> Self {
+        KeyTypeChecker { classes: vec![None; key_count] }
+    }
+
+    fn check(&mut self, keys: &[Value]) -> Result<()> {
+        for (value, seen) in keys.iter().zip(&mut self.classes) {
+            let class = match value {
+                Value::Null => continue,
+                Value::Int(_) | Value::Float(_) | Value::Numeric(_) => 0,
+                Value::String(_) => 1,
+                Value::Bool(_) => 2,
+                // Dates order among timestamps
+                Value::Date(_) | Value::Timestamp(_) | Value::TimestampTz(_) => 3,
+                Value::Time(_) => 4,
+                Value::Interval(_) => 5,
+                Value::Uuid(_) => 6,
+                Value::Bytea(_) => 7,
+                Value::Extension { .. } => {
+                    return Err(ExecutorError::Execution(
+                        "could not identify an ordering operator for extension type".to_string(),
+                    ));
+                }
+            };
+            match seen {
+                None => *seen = Some(class),
+                Some(seen) if *seen != class => {
+                    return Err(ExecutorError::Execution("Type mismatch in ORDER BY".to_string()));
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A sorted run written to a temporary file, removed on drop
+struct SpillRun {
+    path: PathBuf,
+    /// Bytes written to the file
+    bytes: u64,
+}
+
+impl SpillRun {
+    fn write(mut run: Vec<SortEntry>) -> Result<Self> {
+        run.sort();
+
+        let id = NEXT_SPILL_ID.fetch_add(1, AtomicOrdering::Relaxed);
+        let path = std::env::temp_dir().join(format!("flint_sort_{}_{}.tmp", std::process::id(), id));
+        let mut spill = SpillRun { path, bytes: 0 };
+        debug!(path = %spill.path.display(), rows = run.len(), "spilling sorted run");
+
+        let file = File::create(&spill.path).map_err(spill_error)?;
+        let mut writer = BufWriter::new(file);
+        for entry in run {
+            let record = (Row::new(entry.keys), entry.row, entry.seq as u64);
+            spill.bytes += bincode::encode_into_std_write(&record, &mut writer, bincode::config::standard())
+                .map_err(|e| ExecutorError::Execution(format!("Failed to spill sort run: {}", e)))? as u64;
+        }
+        writer.flush().map_err(spill_error)?;
+
+        Ok(spill)
+    }
+
+    fn into_reader(self) -> Result<SpillReader> {
+        let file = File::open(&self.path).map_err(spill_error)?;
+        Ok(SpillReader {
+            reader: BufReader::new(file),
+            _run: self,
+        })
+    }
+}
+
+impl Drop for SpillRun {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+struct SpillReader {
+    reader: BufReader<File>,
+    /// Removes the file once the reader is done with it
+    _run: SpillRun,
+}
+
+impl SpillReader {
+    fn next_entry(&mut self, order: &Arc<[KeyOrder]>) -> Result<Option<SortEntry>> {
+        let decoded: std::result::Result<(Row, Row, u64), _> =
+            bincode::decode_from_std_read(&mut self.reader, bincode::config::standard());
+        match decoded {
+            Ok((keys, row, seq)) => Ok(Some(SortEntry {
+                keys: keys.values,
+                row,
+                seq: seq as usize,
+                order: order.clone(),
+            })),
+            Err(bincode::error::DecodeError::UnexpectedEnd { .. }) => Ok(None),
+            Err(bincode::error::DecodeError::Io { inner, .. })
+                if inner.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(ExecutorError::Execution(format!("Failed to read sort run: {}", e))),
+        }
+    }
+}
+
+fn spill_error(e: std::io::Error) -> ExecutorError {
+    ExecutorError::Execution(format!("Sort spill file error: {}", e))
+}
@@ -0,0 +1,262 @@
+use std::collections::{HashMap, HashSet};
+
+use sqlparser::ast::{CastKind, DataType as SqlDataType, ExactNumberInfo, Expr, TimezoneInfo, Value as SqlValue};
+use tracing::debug;
+
+use crate::executor::error::ExecutorError;
+use crate::executor::evaluator::{self, Result};
+use crate::executor::join::hash_keys;
+use crate::extensions::registry::Registries;
+use crate::planner::{self, Operator};
+use crate::planner::subquery::{self, SubPlan, SubqueryKind};
+use crate::types::{Column, DataType, HashKey, Row, Schema, Value};
+
+/// Evaluate `subplans` for each input row and append their results as columns
+///
+/// `execute` runs a subquery plan. Results are cached per distinct set of
+/// outer values, so an uncorrelated subquery runs once.
+pub fn apply(
+    schema: &Schema,
+    rows: Vec<Row>,
+    subplans: &[SubPlan],
+    mut execute: impl FnMut(Operator) -> Result<(Schema, Vec<Row>)>,
+    registries: &Registries,
+) -> Result<(Schema, Vec<Row>)> {
+    debug!(rows = rows.len(), subqueries = subplans.len(), "apply");
+    let mut caches: Vec<HashMap<Vec<HashKey>, Outcome>> = vec![HashMap::new(); subplans.len()];
+    let mut types: Vec<DataType> = subplans.iter()
+        .map(|subplan| match subplan.kind {
+            SubqueryKind::Scalar => DataType::Null,
+            SubqueryKind::Exists { .. } | SubqueryKind::In { .. } => DataType::Bool,
+        })
+        .collect();
+
+    let mut output = Vec::with_capacity(rows.len());
+    for row in rows {
+        let mut values = row.values.clone();
+        for (idx, subplan) in subplans.iter().enumerate() {
+            let params = subplan.params.iter()
+                .map(|param| evaluator::eval_expr(param, &row, schema, registries))
+                .collect::<Result<Vec<_>>>()?;
+            let key = hash_keys(&params)?;
+
+            if !caches[idx].contains_key(&key) {
+                let mut plan = subplan.plan.clone();
+                bind_params(&mut plan, &params)?;
+                let (result_schema, result_rows) = execute(plan)?;
+                if let (SubqueryKind::Scalar, Some(column)) = (&subplan.kind, result_schema.columns.first())
+                    && types[idx] == DataType::Null
+                {
+                    types[idx] = column.data_type.clone();
+                }
+                caches[idx].insert(key.clone(), Outcome::new(&subplan.kind, result_rows)?);
+            }
+            values.push(caches[idx][&key].result(&subplan.kind, &row, schema, registries)?);
+        }
+        output.push(Row::new(values));
+    }
+
+    let mut columns = schema.columns.clone();
+    columns.extend(subplans.iter().zip(types).map(|(subplan, data_type)| Column {
+        name: subplan.output_name.clone(),
+        data_type,
+        is_primary_key: false,
+    }));
+    Ok((Schema::new(columns), output))
+}
+
+/// A subquery result, reduced to what its kind of expression needs
+#[derive(Clone)]
+enum Outcome {
+    Value(Value),
+    Exists(bool),
+    /// Distinct non-NULL values and whether a NULL was returned
+    Set { values: HashSet<Vec<HashKey>>, has_null: bool },
+}
+
+impl Outcome {
+    fn new(kind: &SubqueryKind, rows: Vec<Row>) -> Result<Self> {
+        match kind {
+            SubqueryKind::Scalar => {
+                if rows.len() > 1 {
+                    return Err(ExecutorError::Execution(
+                        "more than one row returned by a subquery used as an expression".to_string(),
+                    ));
+                }
+                let value = rows.into_iter().next()
+                    .and_then(|row| row.values.into_iter().next())
+                    .unwrap_or(Value::Null);
+                Ok(Outcome::Value(value))
+            }
+            SubqueryKind::Exists { .. } => Ok(Outcome::Exists(!rows.is_empty())),
+            SubqueryKind::In { .. } => {
+                let mut values = HashSet::new();
+                let mut has_null = false;
+                for row in rows {
+                    match &row.values[0] {
+                        Value::Null => has_null = true,
+                        value => {
+                            values.insert(hash_keys(std::slice::from_ref(value))?);
+                        }
+                    }
+                }
+                Ok(Outcome::Set { values, has_null })
+            }
+        }
+    }
+
+    /// Value of the subquery expression for `row`
+    fn result(&self, kind: &SubqueryKind, row: &Row, schema: &Schema, registries: &Registries) -> Result<Value> {
+        match (self, kind) {
+            (Outcome::Value(value), _) => Ok(value.clone()),
+            (Outcome::Exists(exists), SubqueryKind::Exists { negated }) => Ok(Value::Bool(exists != negated)),
+            (Outcome::Set { values, has_null }, SubqueryKind::In { expr, negated }) => {
+                // x IN (...) is NULL when x is NULL or only a NULL could have matched
+                let found = match evaluator::eval_expr(expr, row, schema, registries)? {
+                    _ if values.is_empty() && !has_null => Some(false),
+                    Value::Null => None,
+                    value if values.contains(&hash_keys(std::slice::from_ref(&value))?) => Some(true),
+                    _ if *has_null => None,
+                    _ => Some(false),
+                };
+                Ok(found.map_or(Value::Null, |found| Value::Bool(found != *negated)))
+            }
+            _ => Err(ExecutorError::Execution("subquery result does not match its expression".to_string())),
+        }
+    }
+}
+
+/// Replace the `$outerN` placeholders of a subquery plan with outer values
+///
+/// Subplans of nested Apply operators are not visited: their placeholders
+/// refer to this plan's columns and are bound when the nested Apply runs.
+fn bind_params(plan: &mut Operator, values: &[Value]) -> Result<()> {
+    if values.is_empty() {
+        return Ok(());
+    }
+    for expr in plan.exprs_mut() {
+        bind_expr(expr, values)?;
+    }
+    for child in plan.children_mut() {
+        bind_params(child, values)?;
+    }
+    Ok(())
+}
+
+fn bind_expr(expr: &mut Expr, values: &[Value]) -> Result<()> {
+    if let Some(idx) = subquery::param_index(expr) {
+        *expr = literal(&values[idx])?;
+        return Ok(());
+    }
+    for child in planner::children_mut(expr) {
+        bind_expr(child, values)?;
+    }
+    Ok(())
+}
+
+/// Literal expression for a value bound into a plan
+pub(crate) fn literal(value: &Value) -> Result<Expr> {
+    let value = match value {
+        Value::Null => SqlValue::Null,
+        Value::Int(n) => SqlValue::Number(n.to_string(), false),
+        // Debug formatting keeps a decimal point, so the literal stays a float
+        Value::Float(f) => SqlValue::Number(format!("{:?}", f), false),
+        Value::String(s) => SqlValue::SingleQuotedString(s.clone()),
+        Value::Bool(b) => SqlValue::Boolean(*b),
+        // Numerics, date/time, uuid and bytea values are their text cast to
+        // the type, which keeps a numeric's digits exactly
+        Value::Numeric(_) | Value::Date(_) | Value::Time(_) | Value::Timestamp(_) | Value::TimestampTz(_) | Value::Interval(_)
+        | Value::Uuid(_) | Value::Bytea(_) => {
+            let data_type = match value {
+                Value::Numeric(_) => SqlDataType::Numeric(ExactNumberInfo::None),
+                Value::Uuid(_) => SqlDataType::Uuid,
+                Value::Bytea(_) => SqlDataType::Bytea,
+                Value::Date(_) => SqlDataType::Date,
+                Value::Time(_) => SqlDataType::Time(None, TimezoneInfo::None),
+                Value::Timestamp(_) => SqlDataType::Timestamp(None, TimezoneInfo::None),
+                Value::TimestampTz(_) => SqlDataType::Timestamp(None, TimezoneInfo::WithTimeZone),
+                _ => SqlDataType::Interval { fields: None, precision: None },
+            };
+            return Ok(Expr::Cast {
+                kind: CastKind::DoubleColon,
+                expr: Box::new(Expr::value(SqlValue::SingleQuotedString(value.as_string()))),
+                data_type,
+                format: None,
+            });
+        }
+        Value::Extension { .. } => {
+            return Err(ExecutorError::UnsupportedStatement(
+                "extension type values as query parameters not supported".to_string(),
+            ));
+        }
+    };
+    Ok(Expr::value(value))
+}
+
+/// Evaluate a recursive WITH query
+///
+/// The anchor runs once; the recursive term then runs over the rows the
+/// previous iteration added until an iteration adds none. With `distinct`
+/// (UNION rather than UNION ALL), rows already returned are discarded.
+pub fn recursive_union(
+    name: &str,
+    anchor: Operator,
+    recursive: &Operator,
+    distinct: bool,
+    mut execute: impl FnMut(Operator) -> Result<(Schema, Vec<Row>)>,
+) -> Result<(Schema, Vec<Row>)> {
+    let (schema, rows) = execute(anchor)?;
+    let mut seen = HashSet::new();
+    let mut working = new_rows(rows, distinct, &mut seen)?;
+    let mut output = working.clone();
+
+    let mut iterations = 0;
+    while !working.is_empty() {
+        iterations += 1;
+        let mut plan = recursive.clone();
+        bind_work_table(&mut plan, name, &schema, &working);
+        let (_, rows) = execute(plan)?;
+        working = new_rows(rows, distinct, &mut seen)?;
+        output.extend(working.iter().cloned());
+    }
+
+    debug!(cte = %name, iterations, rows = output.len(), "recursive union complete");
+    Ok((schema, output))
+}
+
+fn new_rows(rows: Vec<Row>, distinct: bool, seen: &mut HashSet<Vec<HashKey>>) -> Result<Vec<Row>> {
+    if !distinct {
+        return Ok(rows);
+    }
+    let mut kept = Vec::with_capacity(rows.len());
+    for row in rows {
+        if seen.insert(hash_keys(&row.values)?) {
+            kept.push(row);
+        }
+    }
+    Ok(kept)
+}
+
+/// Replace scans of the working table `name` with the given rows
+fn bind_work_table(plan: &mut Operator, name: &str, schema: &Schema, rows: &[Row]) {
+    if let Operator::WorkTableScan { name: table, columns } = plan
+        && table == name
+    {
+        let columns = columns.iter()
+            .zip(&schema.columns)
+            .map(|(name, column)| Column {
+                name: name.clone(),
+                data_type: column.data_type.clone(),
+                is_primary_key: false,
+            })
+            .collect();
+        *plan = Operator::Values {
+            schema: Schema::new(columns),
+            rows: rows.to_vec(),
+        };
+        return;
+    }
+    for child in plan.children_mut() {
+        bind_work_table(child, name, schema, rows);
+    }
+}
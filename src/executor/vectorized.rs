@@ -0,0 +1,286 @@
+use sqlparser::ast::{BinaryOperator, Expr};
+
+use crate::executor::batch::{Batch, Bitmap, Vector};
+use crate::executor::{coercion, numeric};
+use crate::executor::error::{sqlstate, ExecutorError};
+use crate::executor::evaluator::{self, Result};
+use crate::executor::{is_string_literal, number_literal};
+use crate::extensions::registry::Registries;
+use crate::types::{DataType, Row, Schema, Value};
+
+/// An expression resolved against an input schema once per query
+///
+/// Column references become positions and literals become values. Operators
+/// without a batch kernel keep the original expression and are interpreted
+/// row by row.
+#[derive(Debug, Clone)]
+pub enum CompiledExpr {
+    Column(usize),
+    Constant(Value),
+    Binary {
+        left: Box<CompiledExpr>,
+        op: BinaryOperator,
+        right: Box<CompiledExpr>,
+    },
+    Interpreted(Box<Expr>),
+}
+
+impl CompiledExpr {
+    pub fn compile(expr: &Expr, schema: &Schema, registries: &Registries) -> CompiledExpr {
+        match expr {
+            Expr::Identifier(ident) => match evaluator::column_index(None, &ident.value, schema) {
+                Ok(idx) => CompiledExpr::Column(idx),
+                // Reported when evaluated, as the interpreter would
+                Err(_) => CompiledExpr::Interpreted(Box::new(expr.clone())),
+            },
+            Expr::CompoundIdentifier(idents) => match evaluator::split_qualified(idents)
+                .and_then(|(qualifier, name)| evaluator::column_index(Some(qualifier), name, schema))
+            {
+                Ok(idx) => CompiledExpr::Column(idx),
+                Err(_) => CompiledExpr::Interpreted(Box::new(expr.clone())),
+            },
+            Expr::Value(_) => match evaluator::eval_expr(expr, &Row::new(Vec::new()), schema, registries) {
+                Ok(value) => CompiledExpr::Constant(value),
+                Err(_) => CompiledExpr::Interpreted(Box::new(expr.clone())),
+            },
+            Expr::Nested(inner) => CompiledExpr::compile(inner, schema, registries),
+            Expr::BinaryOp { left, op, right } if has_kernel(op) => {
+                let operand = |expr: &Expr, other: &Expr| match typed_literal(expr, op, other, schema, registries) {
+                    None => Some(CompiledExpr::compile(expr, schema, registries)),
+                    Some(Ok(value)) => Some(CompiledExpr::Constant(value)),
+                    // Reported when evaluated, as the interpreter would
+                    Some(Err(_)) => None,
+                };
+                match (operand(left, right), operand(right, left)) {
+                    (Some(left), Some(right)) => CompiledExpr::Binary {
+                        left: Box::new(left),
+                        op: op.clone(),
+                        right: Box::new(right),
+                    },
+                    _ => CompiledExpr::Interpreted(Box::new(expr.clone())),
+                }
+            }
+            _ => CompiledExpr::Interpreted(Box::new(expr.clone())),
+        }
+    }
+
+    /// Evaluate the expression for every row of `batch`
+    pub fn eval(&self, batch: &Batch, schema: &Schema, registries: &Registries) -> Result<Vector> {
+        match self {
+            CompiledExpr::Column(idx) => Ok(batch.columns[*idx].clone()),
+            CompiledExpr::Constant(value) => Ok(Vector::constant(value, batch.len)),
+            CompiledExpr::Binary { left, op, right } => {
+                let left = left.eval(batch, schema, registries)?;
+                let right = right.eval(batch, schema, registries)?;
+                binary(&left, op, &right, registries)
+            }
+            CompiledExpr::Interpreted(expr) => {
+                let values = (0..batch.len)
+                    .map(|idx| evaluator::eval_expr(expr, &batch.row(idx), schema, registries))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Vector::from_values(values))
+            }
+        }
+    }
+}
+
+/// A literal operand of `op` read as the type the other operand gives it:
+/// a string as the type `coercion::literal_type` names, and a number beside
+/// a numeric as a numeric. None when `expr` is not such a literal
+fn typed_literal(
+    expr: &Expr,
+    op: &BinaryOperator,
+    other: &Expr,
+    schema: &Schema,
+    registries: &Registries,
+) -> Option<Result<Value>> {
+    let other_type = evaluator::infer_type(other, schema, registries);
+    if let (DataType::Numeric(_), Some(text)) = (&other_type, number_literal(expr)) {
+        return Some(numeric::parse(&text).map(Value::Numeric));
+    }
+    if !is_string_literal(expr) {
+        return None;
+    }
+    let data_type = coercion::literal_type(op, &other_type)?;
+    match evaluator::eval_expr(expr, &Row::new(Vec::new()), schema, registries) {
+        Ok(Value::String(text)) => Some(coercion::parse_text(&text, &data_type, registries)),
+        _ => None,
+    }
+}
+
+fn has_kernel(op: &BinaryOperator) -> bool {
+    use BinaryOperator::*;
+    matches!(op, Plus | Minus | Multiply | Divide | Modulo | Eq | NotEq | Lt | LtEq | Gt | GtEq | And | Or)
+}
+
+/// Apply a binary operator position by position
+///
+/// Typed kernels cover NULL-free operands of matching types; anything else
+/// goes through the interpreter's operator so results are identical.
+fn binary(left: &Vector, op: &BinaryOperator, right: &Vector, registries: &Registries) -> Result<Vector> {
+    use BinaryOperator::*;
+
+    if !left.has_nulls() && !right.has_nulls() {
+        let typed = match (left, right) {
+            (Vector::Int { values: a, .. }, Vector::Int { values: b, .. }) => int_kernel(a, op, b)?,
+            (Vector::Float { values: a, .. }, Vector::Float { values: b, .. }) => float_kernel(a, op, b)?,
+            (Vector::Int { values: a, .. }, Vector::Float { values: b, .. }) => {
+                float_kernel(&a.iter().map(|n| *n as f64).collect::<Vec<_>>(), op, b)?
+            }
+            (Vector::Float { values: a, .. }, Vector::Int { values: b, .. }) => {
+                float_kernel(a, op, &b.iter().map(|n| *n as f64).collect::<Vec<_>>())?
+            }
+            (Vector::String { values: a, .. }, Vector::String { values: b, .. }) => compare(a, op, b),
+            (Vector::Bool { values: a, .. }, Vector::Bool { values: b, .. }) => match op {
+                And => Some(bools(a.iter().zip(b).map(|(a, b)| *a && *b))),
+                Or => Some(bools(a.iter().zip(b).map(|(a, b)| *a || *b))),
+                _ => compare(a, op, b),
+            },
+            _ => None,
+        };
+        if let Some(vector) = typed {
+            return Ok(vector);
+        }
+    }
+
+    let values = (0..left.len())
+        .map(|idx| evaluator::eval_binary_op(&left.get(idx), op, &right.get(idx), registries))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Vector::from_values(values))
+}
+
+fn bools(values: impl Iterator<Item = bool>) -> Vector {
+    Vector::Bool { values: values.collect(), nulls: Bitmap::default() }
+}
+
+/// Comparison of ordered values; None for other operators
+fn compare<T: PartialOrd>(a: &[T], op: &BinaryOperator, b: &[T]) -> Option<Vector> {
+    use BinaryOperator::*;
+    let cmp: fn(&T, &T) -> bool = match op {
+        Eq => |a, b| a == b,
+        NotEq => |a, b| a != b,
+        Lt => |a, b| a < b,
+        LtEq => |a, b| a <= b,
+        Gt => |a, b| a > b,
+        GtEq => |a, b| a >= b,
+        _ => return None,
+    };
+    Some(bools(a.iter().zip(b).map(|(a, b)| cmp(a, b))))
+}
+
+fn int_kernel(a: &[i64], op: &BinaryOperator, b: &[i64]) -> Result<Option<Vector>> {
+    use BinaryOperator::*;
+    let checked: fn(i64, i64) -> Option<i64> = match op {
+        Plus => i64::checked_add,
+        Minus => i64::checked_sub,
+        Multiply => i64::checked_mul,
+        Divide => {
+            if b.contains(&0) {
+                return Err(ExecutorError::division_by_zero());
+            }
+            i64::checked_div
+        }
+        Modulo => {
+            if b.contains(&0) {
+                return Err(ExecutorError::division_by_zero());
+            }
+            // Only i64::MIN % -1 overflows, and its remainder is 0
+            |a, b| Some(a.checked_rem(b).unwrap_or(0))
+        }
+        _ => return Ok(compare(a, op, b)),
+    };
+    let values = a.iter()
+        .zip(b)
+        .map(|(a, b)| checked(*a, *b)
+            .ok_or_else(|| ExecutorError::out_of_range("bigint")))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(Vector::Int { values, nulls: Bitmap::default() }))
+}
+
+fn float_kernel(a: &[f64], op: &BinaryOperator, b: &[f64]) -> Result<Option<Vector>> {
+    use BinaryOperator::*;
+    let apply: fn(f64, f64) -> f64 = match op {
+        Plus => |a, b| a + b,
+        Minus => |a, b| a - b,
+        Multiply => |a, b| a * b,
+        Divide => {
+            if b.contains(&0.0) {
+                return Err(ExecutorError::division_by_zero());
+            }
+            |a, b| a / b
+        }
+        _ => return Ok(compare(a, op, b)),
+    };
+    let values = a.iter().zip(b).map(|(a, b)| apply(*a, *b)).collect::<Vec<_>>();
+    let overflowed = values.iter().zip(a.iter().zip(b))
+        .any(|(result, (a, b))| result.is_infinite() && a.is_finite() && b.is_finite());
+    if overflowed {
+        return Err(ExecutorError::Sql(sqlstate::NUMERIC_VALUE_OUT_OF_RANGE, "value out of range: overflow".to_string()));
+    }
+    Ok(Some(Vector::Float { values, nulls: Bitmap::default() }))
+}
+
+/// Rows of `batch` for which `predicate` is true
+///
+/// If the predicate fails for the batch, rows are re-evaluated one at a time:
+/// the batch evaluates every operand for every row, so it can fail on a row
+/// whose evaluation on its own stops early (`id <> 1 AND 10 / (id - 1) > 0`).
+/// The error of the first row that fails on its own is returned.
+pub fn filter(
+    batch: Batch,
+    predicate: &CompiledExpr,
+    expr: &Expr,
+    schema: &Schema,
+    registries: &Registries,
+) -> Result<Batch> {
+    let positions = match predicate.eval(&batch, schema, registries) {
+        Ok(vector) => vector.true_positions(),
+        Err(_) => {
+            let mut positions = Vec::new();
+            for idx in 0..batch.len {
+                if matches!(evaluator::eval_expr(expr, &batch.row(idx), schema, registries)?, Value::Bool(true)) {
+                    positions.push(idx);
+                }
+            }
+            positions
+        }
+    };
+    if positions.len() == batch.len {
+        Ok(batch)
+    } else {
+        Ok(batch.take(&positions))
+    }
+}
+
+/// Evaluate `columns` over `batch`
+///
+/// If any expression fails, rows are evaluated one at a time instead; the
+/// rows before the first failing one are returned along with its error, so
+/// that a consumer which stops early never sees errors from later rows.
+pub fn project(
+    batch: &Batch,
+    columns: &[CompiledExpr],
+    exprs: &[Expr],
+    schema: &Schema,
+    registries: &Registries,
+) -> (Option<Batch>, Option<ExecutorError>) {
+    let vectors = columns.iter()
+        .map(|column| column.eval(batch, schema, registries))
+        .collect::<Result<Vec<_>>>();
+    if let Ok(columns) = vectors {
+        return (Some(Batch { columns, len: batch.len }), None);
+    }
+
+    let mut rows = Vec::new();
+    for idx in 0..batch.len {
+        let row = batch.row(idx);
+        match exprs.iter().map(|expr| evaluator::eval_expr(expr, &row, schema, registries)).collect::<Result<Vec<_>>>() {
+            Ok(values) => rows.push(Row::new(values)),
+            Err(e) => {
+                let done = (!rows.is_empty()).then(|| Batch::from_rows(rows, exprs.len()));
+                return (done, Some(e));
+            }
+        }
+    }
+    (Some(Batch::from_rows(rows, exprs.len())), None)
+}
@@ -0,0 +1,423 @@
+use std::cmp::Ordering;
+
+use sqlparser::ast::Expr;
+use tracing::debug;
+
+use crate::executor::aggregate::{self, Accumulator};
+use crate::executor::error::ExecutorError;
+use crate::executor::evaluator::{self, Result};
+use crate::executor::sort;
+use crate::extensions::registry::Registries;
+use crate::planner::SortKey;
+use crate::planner::window::{FrameBound, FrameUnits, WindowCall, WindowFrame, WindowFunction};
+use crate::types::{Column, DataType, Row, Schema, Value};
+
+/// Compute window functions over partitions of the input
+///
+/// Rows are sorted by the partition keys and then `order_by` with the same
+/// external sort as ORDER BY, so each partition is a run of adjacent rows.
+/// Output rows keep that order and gain one column per call.
+pub fn window(
+    schema: &Schema,
+    rows: Vec<Row>,
+    partition_by: &[Expr],
+    order_by: &[SortKey],
+    calls: &[WindowCall],
+    work_mem: usize,
+    registries: &Registries,
+) -> Result<(Schema, Vec<Row>)> {
+    debug!(rows = rows.len(), partition_by = partition_by.len(), calls = calls.len(), "window");
+
+    let keys = partition_by.iter()
+        .map(|expr| SortKey { expr: expr.clone(), descending: false, nulls_first: false })
+        .chain(order_by.iter().cloned())
+        .collect::<Vec<_>>();
+    let rows = if keys.is_empty() {
+        rows
+    } else {
+        sort::sort(schema, rows.into_iter().map(Ok), &keys, None, work_mem, registries)?.collect::<Result<Vec<_>>>()?
+    };
+
+    let eval_all = |exprs: &[Expr], row: &Row| exprs.iter()
+        .map(|expr| evaluator::eval_expr(expr, row, schema, registries))
+        .collect::<Result<Vec<_>>>();
+    let order_exprs = order_by.iter().map(|key| key.expr.clone()).collect::<Vec<_>>();
+    let partition_values = rows.iter().map(|row| eval_all(partition_by, row)).collect::<Result<Vec<_>>>()?;
+    let order_values = rows.iter().map(|row| eval_all(&order_exprs, row)).collect::<Result<Vec<_>>>()?;
+
+    let mut results: Vec<Vec<Value>> = vec![Vec::with_capacity(calls.len()); rows.len()];
+    let mut start = 0;
+    while start < rows.len() {
+        let mut end = start + 1;
+        while end < rows.len() && values_equal(&partition_values[end], &partition_values[start])? {
+            end += 1;
+        }
+
+        let partition = Partition::new(schema, &rows[start..end], &order_values[start..end], order_by, registries)?;
+        for call in calls {
+            for (idx, value) in partition.evaluate(call)?.into_iter().enumerate() {
+                results[start + idx].push(value);
+            }
+        }
+        start = end;
+    }
+
+    let mut columns = schema.columns.clone();
+    columns.extend(calls.iter().map(|call| Column {
+        name: call.output_name.clone(),
+        data_type: result_type(call, schema, registries),
+        is_primary_key: false,
+    }));
+    let output = rows.into_iter()
+        .zip(results)
+        .map(|(row, results)| {
+            let mut values = row.values;
+            values.extend(results);
+            Row::new(values)
+        })
+        .collect();
+    Ok((Schema::new(columns), output))
+}
+
+pub(crate) fn result_type(call: &WindowCall, schema: &Schema, registries: &Registries) -> DataType {
+    match &call.func {
+        WindowFunction::RowNumber | WindowFunction::Rank | WindowFunction::DenseRank | WindowFunction::Ntile => {
+            DataType::Int
+        }
+        WindowFunction::PercentRank | WindowFunction::CumeDist => DataType::Float,
+        WindowFunction::Lag
+        | WindowFunction::Lead
+        | WindowFunction::FirstValue
+        | WindowFunction::LastValue
+        | WindowFunction::NthValue => evaluator::infer_type(&call.args[0], schema, registries),
+        WindowFunction::Aggregate(aggregate) => aggregate::result_type(aggregate, schema, registries),
+    }
+}
+
+/// Whether two key tuples are equal, treating NULLs as equal to each other
+fn values_equal(left: &[Value], right: &[Value]) -> Result<bool> {
+    for (left, right) in left.iter().zip(right) {
+        let equal = match (left, right) {
+            (Value::Null, Value::Null) => true,
+            (Value::Null, _) | (_, Value::Null) => false,
+            (left, right) => evaluator::compare_values(left, right)? == Ordering::Equal,
+        };
+        if !equal {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// The sorted rows of one partition
+struct Partition<'a> {
+    schema: &'a Schema,
+    rows: &'a [Row],
+    order_values: &'a [Vec<Value>],
+    order_by: &'a [SortKey],
+    registries: &'a Registries,
+    /// For each row, the bounds of its peer group (rows with equal ORDER BY values)
+    peers: Vec<(usize, usize)>,
+    /// For each row, the number of peer groups before its own
+    group_numbers: Vec<usize>,
+}
+
+impl<'a> Partition<'a> {
+    fn new(
+        schema: &'a Schema,
+        rows: &'a [Row],
+        order_values: &'a [Vec<Value>],
+        order_by: &'a [SortKey],
+        registries: &'a Registries,
+    ) -> Result<Self> {
+        let mut peers = Vec::with_capacity(rows.len());
+        let mut group_numbers = Vec::with_capacity(rows.len());
+        let mut start = 0;
+        let mut group = 0;
+        while start < rows.len() {
+            let mut end = start + 1;
+            while end < rows.len() && values_equal(&order_values[end], &order_values[start])? {
+                end += 1;
+            }
+            peers.extend(std::iter::repeat_n((start, end), end - start));
+            group_numbers.extend(std::iter::repeat_n(group, end - start));
+            group += 1;
+            start = end;
+        }
+        Ok(Partition { schema, rows, order_values, order_by, registries, peers, group_numbers })
+    }
+
+    fn eval(&self, expr: &Expr, idx: usize) -> Result<Value> {
+        evaluator::eval_expr(expr, &self.rows[idx], self.schema, self.registries)
+    }
+
+    /// Results of a window function call for each row of the partition
+    fn evaluate(&self, call: &WindowCall) -> Result<Vec<Value>> {
+        let count = self.rows.len();
+        let mut results = Vec::with_capacity(count);
+        match &call.func {
+            WindowFunction::RowNumber => results.extend((1..=count as i64).map(Value::Int)),
+            WindowFunction::Rank => results.extend(self.peers.iter().map(|(start, _)| Value::Int(*start as i64 + 1))),
+            WindowFunction::DenseRank => results.extend(self.group_numbers.iter().map(|group| Value::Int(*group as i64 + 1))),
+            WindowFunction::PercentRank => results.extend(self.peers.iter().map(|(start, _)| {
+                Value::Float(if count > 1 { *start as f64 / (count - 1) as f64 } else { 0.0 })
+            })),
+            WindowFunction::CumeDist => results.extend(self.peers.iter().map(|(_, end)| Value::Float(*end as f64 / count as f64))),
+            WindowFunction::Ntile => {
+                for idx in 0..count {
+                    let buckets = match self.eval(&call.args[0], idx)? {
+                        Value::Null => {
+                            results.push(Value::Null);
+                            continue;
+                        }
+                        Value::Int(buckets) if buckets > 0 => buckets as usize,
+                        _ => {
+                            return Err(ExecutorError::Execution(
+                                "argument of ntile must be greater than zero".to_string(),
+                            ));
+                        }
+                    };
+                    // The first count % buckets buckets get one extra row
+                    let size = count / buckets;
+                    let extra = count % buckets;
+                    let bucket = if idx < extra * (size + 1) {
+                        idx / (size + 1)
+                    } else {
+                        extra + (idx - extra * (size + 1)) / size
+                    };
+                    results.push(Value::Int(bucket as i64 + 1));
+                }
+            }
+            WindowFunction::Lag | WindowFunction::Lead => {
+                for idx in 0..count {
+                    let offset = match call.args.get(1) {
+                        Some(offset) => match self.eval(offset, idx)? {
+                            Value::Null => {
+                                results.push(Value::Null);
+                                continue;
+                            }
+                            Value::Int(offset) => offset,
+                            other => return Err(offset_type_error(call.func.name(), &other)),
+                        },
+                        None => 1,
+                    };
+                    let offset = if call.func == WindowFunction::Lag { -offset } else { offset };
+                    let target = (idx as i64).checked_add(offset).filter(|target| (0..count as i64).contains(target));
+                    results.push(match (target, call.args.get(2)) {
+                        (Some(target), _) => self.eval(&call.args[0], target as usize)?,
+                        (None, Some(default)) => self.eval(default, idx)?,
+                        (None, None) => Value::Null,
+                    });
+                }
+            }
+            WindowFunction::FirstValue | WindowFunction::LastValue | WindowFunction::NthValue => {
+                let frames = self.frames(&call.frame)?;
+                for (idx, (start, end)) in frames.into_iter().enumerate() {
+                    let target = match call.func {
+                        WindowFunction::FirstValue => (start < end).then_some(start),
+                        WindowFunction::LastValue => (start < end).then(|| end - 1),
+                        _ => match self.eval(&call.args[1], idx)? {
+                            Value::Null => None,
+                            Value::Int(n) if n > 0 => start.checked_add(n as usize - 1).filter(|target| *target < end),
+                            Value::Int(_) => {
+                                return Err(ExecutorError::Execution(
+                                    "argument of nth_value must be greater than zero".to_string(),
+                                ));
+                            }
+                            other => return Err(offset_type_error(call.func.name(), &other)),
+                        },
+                    };
+                    results.push(match target {
+                        Some(target) => self.eval(&call.args[0], target)?,
+                        None => Value::Null,
+                    });
+                }
+            }
+            WindowFunction::Aggregate(aggregate) => {
+                let frames = self.frames(&call.frame)?;
+                if call.frame.start == FrameBound::UnboundedPreceding {
+                    // Frames only grow: keep one running accumulator
+                    let mut accumulator = Accumulator::new(aggregate, self.registries);
+                    let mut added = 0;
+                    for (_, end) in frames {
+                        while added < end {
+                            accumulator.update(&self.rows[added], self.schema)?;
+                            added += 1;
+                        }
+                        results.push(accumulator.clone().finish());
+                    }
+                } else {
+                    for (start, end) in frames {
+                        let mut accumulator = Accumulator::new(aggregate, self.registries);
+                        for row in &self.rows[start..end] {
+                            accumulator.update(row, self.schema)?;
+                        }
+                        results.push(accumulator.finish());
+                    }
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Frame of each row as a range of partition positions (empty if start >= end)
+    fn frames(&self, frame: &WindowFrame) -> Result<Vec<(usize, usize)>> {
+        let start_offset = self.offset(&frame.start, "starting")?;
+        let end_offset = self.offset(&frame.end, "ending")?;
+        if frame.units == FrameUnits::Rows
+            && let Some(offset) = [&start_offset, &end_offset].into_iter().flatten().find(|offset| !matches!(offset, Value::Int(_)))
+        {
+            return Err(offset_type_error("ROWS", offset));
+        }
+
+        let count = self.rows.len();
+        let mut frames = Vec::with_capacity(count);
+        for idx in 0..count {
+            let (peer_start, peer_end) = self.peers[idx];
+            let (start, end) = match frame.units {
+                FrameUnits::Rows => {
+                    let start = match (&frame.start, &start_offset) {
+                        (FrameBound::UnboundedPreceding, _) => 0,
+                        (FrameBound::Preceding(_), Some(Value::Int(n))) => idx.saturating_sub(*n as usize),
+                        (FrameBound::Following(_), Some(Value::Int(n))) => idx.saturating_add(*n as usize).min(count),
+                        _ => idx,
+                    };
+                    let end = match (&frame.end, &end_offset) {
+                        (FrameBound::UnboundedFollowing, _) => count,
+                        (FrameBound::Preceding(_), Some(Value::Int(n))) => (idx + 1).saturating_sub(*n as usize),
+                        (FrameBound::Following(_), Some(Value::Int(n))) => idx.saturating_add(*n as usize + 1).min(count),
+                        _ => idx + 1,
+                    };
+                    (start, end)
+                }
+                FrameUnits::Range => {
+                    let start = match (&frame.start, &start_offset) {
+                        (FrameBound::UnboundedPreceding, _) => 0,
+                        (FrameBound::Preceding(_), Some(offset)) => self.range_bound(idx, offset, true, true)?,
+                        (FrameBound::Following(_), Some(offset)) => self.range_bound(idx, offset, false, true)?,
+                        _ => peer_start,
+                    };
+                    let end = match (&frame.end, &end_offset) {
+                        (FrameBound::UnboundedFollowing, _) => count,
+                        (FrameBound::Preceding(_), Some(offset)) => self.range_bound(idx, offset, true, false)?,
+                        (FrameBound::Following(_), Some(offset)) => self.range_bound(idx, offset, false, false)?,
+                        _ => peer_end,
+                    };
+                    (start, end)
+                }
+            };
+            frames.push((start, end));
+        }
+        Ok(frames)
+    }
+
+    /// Value of a PRECEDING/FOLLOWING offset, evaluated once per partition
+    fn offset(&self, bound: &FrameBound, which: &str) -> Result<Option<Value>> {
+        let (FrameBound::Preceding(offset) | FrameBound::Following(offset)) = bound else {
+            return Ok(None);
+        };
+        let value = evaluator::eval_expr(offset, &Row::new(Vec::new()), &Schema::new(Vec::new()), self.registries)?;
+        let negative = match &value {
+            Value::Null => {
+                return Err(ExecutorError::Execution(format!("frame {} offset must not be null", which)));
+            }
+            Value::Int(n) => *n < 0,
+            Value::Float(f) => *f < 0.0,
+            other => return Err(offset_type_error("frame offset", other)),
+        };
+        if negative {
+            return Err(ExecutorError::Execution(format!("frame {} offset must not be negative", which)));
+        }
+        Ok(Some(value))
+    }
+
+    /// Partition position where a RANGE frame bound `offset` before or after
+    /// the current row's ORDER BY value falls
+    ///
+    /// For a start bound, the first row not before the bound value; for an end
+    /// bound, the first row after it. A row with a NULL value frames its peers.
+    fn range_bound(&self, idx: usize, offset: &Value, preceding: bool, is_start: bool) -> Result<usize> {
+        let key = &self.order_by[0];
+        let current = &self.order_values[idx][0];
+        let (peer_start, peer_end) = self.peers[idx];
+        let bound = match (current, offset) {
+            (Value::Null, _) => return Ok(if is_start { peer_start } else { peer_end }),
+            (Value::Int(value), Value::Int(offset)) => {
+                // Moving towards earlier rows means smaller values, unless descending
+                let towards_smaller = preceding != key.descending;
+                let bound = if towards_smaller { value.checked_sub(*offset) } else { value.checked_add(*offset) };
+                bound.map(Value::Int).unwrap_or(Value::Float(
+                    if towards_smaller { *value as f64 - *offset as f64 } else { *value as f64 + *offset as f64 },
+                ))
+            }
+            (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+                let as_float = |value: &Value| match value {
+                    Value::Int(n) => *n as f64,
+                    Value::Float(f) => *f,
+                    _ => 0.0,
+                };
+                let towards_smaller = preceding != key.descending;
+                let (value, offset) = (as_float(current), as_float(offset));
+                Value::Float(if towards_smaller { value - offset } else { value + offset })
+            }
+            (other, _) => {
+                return Err(ExecutorError::UnsupportedStatement(format!(
+                    "RANGE with offset PRECEDING/FOLLOWING is not supported for {}",
+                    type_name(other)
+                )));
+            }
+        };
+
+        // Whether a row sorts strictly before (or, for end bounds, at or before) the bound value
+        let before = |values: &Vec<Value>| -> Result<bool> {
+            let Some(value) = values.first() else {
+                return Ok(false);
+            };
+            if matches!(value, Value::Null) {
+                return Ok(key.nulls_first);
+            }
+            let ordering = evaluator::compare_values(value, &bound)?;
+            let ordering = if key.descending { ordering.reverse() } else { ordering };
+            Ok(if is_start { ordering == Ordering::Less } else { ordering != Ordering::Greater })
+        };
+
+        // Rows are sorted, so the rows before the bound form a prefix
+        let (mut low, mut high) = (0, self.order_values.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            if before(&self.order_values[mid])? {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "unknown",
+        Value::Int(_) => "bigint",
+        Value::Float(_) => "double precision",
+        Value::Numeric(_) => "numeric",
+        Value::String(_) => "text",
+        Value::Bool(_) => "boolean",
+        Value::Date(_) => "date",
+        Value::Time(_) => "time without time zone",
+        Value::Timestamp(_) => "timestamp without time zone",
+        Value::TimestampTz(_) => "timestamp with time zone",
+        Value::Interval(_) => "interval",
+        Value::Uuid(_) => "uuid",
+        Value::Bytea(_) => "bytea",
+        Value::Extension { .. } => "extension",
+    }
+}
+
+fn offset_type_error(function: &str, value: &Value) -> ExecutorError {
+    ExecutorError::Execution(format!(
+        "argument of {} must be type bigint, not type {}",
+        function,
+        type_name(value)
+    ))
+}
@@ -1,6 +1,9 @@
-use super::{TypeExtension, TypeCategory};
+use super::{FunctionExtension, TypeExtension, TypeCategory};
 use std::any::Any;
+use bigdecimal::RoundingMode;
 use pgwire::api::Type;
+use crate::executor::{bytes, datetime, numeric};
+use crate::types::{DataType, Value};
 use crate::storage::index::{IndexBuilder, Index};
 use crate::storage::PageId;
 use crate::storage::index::btree::BTree;
@@ -207,6 +210,215 @@ impl IndexBuilder for HashIndexBuilder {
     }
 }
 
+/// Current date and time functions: now(), current_timestamp, current_date,
+/// localtimestamp and the like
+///
+/// Each call reads the clock, so two calls in one statement may differ.
+/// With the session in UTC, current_time gives the time without time zone.
+pub struct CurrentTimeFunc {
+    name: &'static str,
+    data_type: DataType,
+}
+
+impl FunctionExtension for CurrentTimeFunc {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn execute(&self, args: &[Value]) -> Result<Value, String> {
+        if !args.is_empty() {
+            return Err(format!("{}() expects no arguments, got {}", self.name, args.len()));
+        }
+        let now = datetime::now();
+        match datetime::convert(&now, &self.data_type) {
+            Some(value) => value.map_err(|_| "timestamp out of range".to_string()),
+            None => Ok(now),
+        }
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, String> {
+        match arg_types {
+            [] => Ok(self.data_type.clone()),
+            _ => Err(format!("{}() expects no arguments", self.name)),
+        }
+    }
+}
+
+/// date_part(text, source) -> double precision, the field EXTRACT returns as a numeric
+pub struct DatePartFunc;
+
+impl FunctionExtension for DatePartFunc {
+    fn name(&self) -> &str {
+        "date_part"
+    }
+
+    fn execute(&self, args: &[Value]) -> Result<Value, String> {
+        match args {
+            [Value::String(field), source] => datetime::date_part(field, source),
+            _ => Err("date_part() expects a field name and a date/time value".to_string()),
+        }
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, String> {
+        match arg_types {
+            [DataType::String, source] if datetime::is_temporal_type(source) => Ok(DataType::Float),
+            _ => Err("date_part() expects a field name and a date/time value".to_string()),
+        }
+    }
+}
+
+/// date_trunc(text, source) -> source's type, for timestamps and intervals
+///
+/// A date is truncated as a timestamp with time zone, its implicit cast.
+pub struct DateTruncFunc;
+
+impl FunctionExtension for DateTruncFunc {
+    fn name(&self) -> &str {
+        "date_trunc"
+    }
+
+    fn execute(&self, args: &[Value]) -> Result<Value, String> {
+        match args {
+            [Value::String(field), source] => datetime::date_trunc(field, source),
+            _ => Err("date_trunc() expects a field name and a timestamp or interval".to_string()),
+        }
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, String> {
+        match arg_types {
+            [DataType::String, source @ (DataType::Timestamp | DataType::TimestampTz | DataType::Interval)] => {
+                Ok(source.clone())
+            }
+            _ => Err("date_trunc() expects a field name and a timestamp or interval".to_string()),
+        }
+    }
+}
+
+/// timezone(zone, timestamp), the function form of `timestamp AT TIME ZONE zone`
+pub struct TimezoneFunc;
+
+impl FunctionExtension for TimezoneFunc {
+    fn name(&self) -> &str {
+        "timezone"
+    }
+
+    fn execute(&self, args: &[Value]) -> Result<Value, String> {
+        match args {
+            [zone, source] => datetime::at_time_zone(source, zone).map_err(|_| match zone {
+                Value::String(name) => format!("time zone \"{}\" not recognized", name),
+                _ => "interval time zone must not include months or days".to_string(),
+            }),
+            _ => Err(format!("timezone() expects 2 arguments, got {}", args.len())),
+        }
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, String> {
+        match arg_types {
+            [DataType::String | DataType::Interval, DataType::Timestamp] => Ok(DataType::TimestampTz),
+            [DataType::String | DataType::Interval, DataType::TimestampTz] => Ok(DataType::Timestamp),
+            _ => Err("timezone() expects a time zone and a timestamp".to_string()),
+        }
+    }
+}
+
+/// round, trunc, ceil/ceiling and floor of numerics and doubles
+///
+/// round and trunc take an optional count of decimal places for numerics,
+/// negative to round left of the point. Numerics round halves away from
+/// zero; doubles round them to even, as rint() does.
+pub struct RoundFunc {
+    name: &'static str,
+    mode: RoundingMode,
+}
+
+impl RoundFunc {
+    fn takes_places(&self) -> bool {
+        matches!(self.mode, RoundingMode::HalfUp | RoundingMode::Down)
+    }
+}
+
+impl FunctionExtension for RoundFunc {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn execute(&self, args: &[Value]) -> Result<Value, String> {
+        match args {
+            [Value::Numeric(n)] => Ok(Value::Numeric(numeric::round(n, 0, self.mode))),
+            [Value::Numeric(n), Value::Int(places)] if self.takes_places() => {
+                Ok(Value::Numeric(numeric::round(n, *places, self.mode)))
+            }
+            [Value::Float(f)] => Ok(Value::Float(match self.mode {
+                RoundingMode::HalfUp => f.round_ties_even(),
+                RoundingMode::Down => f.trunc(),
+                RoundingMode::Ceiling => f.ceil(),
+                _ => f.floor(),
+            })),
+            _ => Err(format!("{}() expects a numeric or double precision value", self.name)),
+        }
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, String> {
+        match arg_types {
+            [DataType::Numeric(_)] => Ok(DataType::Numeric(None)),
+            [DataType::Numeric(_), DataType::Int] if self.takes_places() => Ok(DataType::Numeric(None)),
+            [DataType::Float] => Ok(DataType::Float),
+            _ => Err(format!("{}() expects a numeric or double precision value", self.name)),
+        }
+    }
+}
+
+/// gen_random_uuid() -> uuid, a random version 4 UUID
+pub struct GenRandomUuidFunc;
+
+impl FunctionExtension for GenRandomUuidFunc {
+    fn name(&self) -> &str {
+        "gen_random_uuid"
+    }
+
+    fn execute(&self, args: &[Value]) -> Result<Value, String> {
+        if !args.is_empty() {
+            return Err(format!("gen_random_uuid() expects no arguments, got {}", args.len()));
+        }
+        Ok(Value::Uuid(bytes::random_uuid()))
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, String> {
+        match arg_types {
+            [] => Ok(DataType::Uuid),
+            _ => Err("gen_random_uuid() expects no arguments".to_string()),
+        }
+    }
+}
+
+/// encode(bytea, format) -> text and decode(text, format) -> bytea, with
+/// format `hex`, `escape` or `base64`
+pub struct EncodeFunc {
+    decode: bool,
+}
+
+impl FunctionExtension for EncodeFunc {
+    fn name(&self) -> &str {
+        if self.decode { "decode" } else { "encode" }
+    }
+
+    fn execute(&self, args: &[Value]) -> Result<Value, String> {
+        match args {
+            [Value::Bytea(data), Value::String(format)] if !self.decode => bytes::encode(data, format).map(Value::String),
+            [Value::String(text), Value::String(format)] if self.decode => bytes::decode(text, format).map(Value::Bytea),
+            _ => Err(format!("{}() expects a value and an encoding name", self.name())),
+        }
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, String> {
+        match arg_types {
+            [DataType::Bytea, DataType::String] if !self.decode => Ok(DataType::String),
+            [DataType::String, DataType::String] if self.decode => Ok(DataType::Bytea),
+            _ => Err(format!("{}() expects a value and an encoding name", self.name())),
+        }
+    }
+}
+
 /// Register all built-in type extensions
 pub fn register_builtin_types(registry: &mut super::registry::TypeRegistry) {
     registry.register(Box::new(IntType));
@@ -221,3 +433,37 @@ pub fn register_builtin_indexes(registry: &mut crate::storage::index::IndexBuild
     registry.register("btree", Box::new(BTreeBuilder));
     registry.register("hash", Box::new(HashIndexBuilder));
 }
+
+/// Register all built-in functions
+pub fn register_builtin_functions(registry: &mut super::registry::FunctionRegistry) {
+    let current = [
+        ("now", DataType::TimestampTz),
+        ("current_timestamp", DataType::TimestampTz),
+        ("transaction_timestamp", DataType::TimestampTz),
+        ("statement_timestamp", DataType::TimestampTz),
+        ("clock_timestamp", DataType::TimestampTz),
+        ("localtimestamp", DataType::Timestamp),
+        ("current_date", DataType::Date),
+        ("current_time", DataType::Time),
+        ("localtime", DataType::Time),
+    ];
+    for (name, data_type) in current {
+        registry.register(Box::new(CurrentTimeFunc { name, data_type }));
+    }
+    registry.register(Box::new(DatePartFunc));
+    registry.register(Box::new(DateTruncFunc));
+    registry.register(Box::new(TimezoneFunc));
+    let rounding = [
+        ("round", RoundingMode::HalfUp),
+        ("trunc", RoundingMode::Down),
+        ("ceil", RoundingMode::Ceiling),
+        ("ceiling", RoundingMode::Ceiling),
+        ("floor", RoundingMode::Floor),
+    ];
+    for (name, mode) in rounding {
+        registry.register(Box::new(RoundFunc { name, mode }));
+    }
+    registry.register(Box::new(GenRandomUuidFunc));
+    registry.register(Box::new(EncodeFunc { decode: false }));
+    registry.register(Box::new(EncodeFunc { decode: true }));
+}
@@ -4,7 +4,7 @@
 //! Extensions self-register by implementing ExtensionLoader and using
 //! inventory::submit! macro. No cfg attributes needed.
 
-use crate::extensions::registry::{TypeRegistry, OperatorRegistry, FunctionRegistry};
+use crate::extensions::registry::{TypeRegistry, CastRegistry, OperatorRegistry, FunctionRegistry};
 
 /// Trait for self-registering extensions
 pub trait ExtensionLoader: Send + Sync {
@@ -14,6 +14,9 @@ pub trait ExtensionLoader: Send + Sync {
     /// Load types into registry
     fn load_types(&self, _registry: &mut TypeRegistry) {}
 
+    /// Load casts into registry
+    fn load_casts(&self, _registry: &mut CastRegistry) {}
+
     /// Load operators into registry
     fn load_operators(&self, _registry: &mut OperatorRegistry) {}
 
@@ -29,6 +32,7 @@ inventory::collect!(&'static dyn ExtensionLoader);
 /// If `enabled_names` is None, load all registered extensions.
 pub fn load_all_extensions(
     type_registry: &mut TypeRegistry,
+    cast_registry: &mut CastRegistry,
     operator_registry: &mut OperatorRegistry,
     function_registry: &mut FunctionRegistry,
     enabled_names: Option<&[String]>,
@@ -44,6 +48,7 @@ pub fn load_all_extensions(
 
         tracing::info!("Loading extension: {}", loader.name());
         loader.load_types(type_registry);
+        loader.load_casts(cast_registry);
         loader.load_operators(operator_registry);
         loader.load_functions(function_registry);
     }
@@ -16,6 +16,21 @@ pub enum TypeCategory {
     Array,
     Composite,
     Extension,
+    /// Types that belong with no others, as uuid and bytea
+    UserDefined,
+}
+
+/// Where a cast applies without being written out, from weakest to strongest
+///
+/// A cast allowed in one context is allowed in every later one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CastContext {
+    /// Anywhere a value of the target type is expected, e.g. an operator argument
+    Implicit,
+    /// When storing into a column of the target type
+    Assignment,
+    /// Only with `CAST(x AS t)` or `x::t`
+    Explicit,
 }
 
 /// Extension trait for custom data types
@@ -37,6 +52,16 @@ pub trait TypeExtension: Send + Sync {
 
     /// Convert to PostgreSQL type for protocol
     fn to_pgwire_type(&self) -> pgwire::api::Type;
+
+    /// Encode a value in the protocol's text format
+    fn encode_text(&self, _value: &dyn Any) -> Result<String, String> {
+        Err(format!("type {} has no text output", self.type_name()))
+    }
+
+    /// Encode a value in the protocol's binary format (the type's send function)
+    fn encode_binary(&self, _value: &dyn Any) -> Result<Vec<u8>, String> {
+        Err(format!("type {} has no binary output", self.type_name()))
+    }
 }
 
 /// Extension trait for custom operators
@@ -66,6 +91,18 @@ pub trait FunctionExtension: Send + Sync {
     fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, String>;
 }
 
+/// Extension trait for casts to or from custom types
+pub trait CastExtension: Send + Sync {
+    /// Check if this cast converts `source` values to `target`
+    fn can_cast(&self, source: &DataType, target: &DataType) -> bool;
+
+    /// Weakest context the cast applies in
+    fn context(&self) -> CastContext;
+
+    /// Convert a non-NULL value to `target`
+    fn execute(&self, value: &Value, target: &DataType) -> Result<Value, String>;
+}
+
 /// Extension trait for custom index types
 pub trait IndexExtension: Send + Sync {
     /// Index type name (e.g., "hnsw", "ivfflat", "gin")
@@ -1,6 +1,7 @@
-use super::{TypeExtension, OperatorExtension, FunctionExtension, IndexExtension};
+use super::{TypeExtension, OperatorExtension, FunctionExtension, CastExtension, IndexExtension};
 use crate::types::DataType;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Registry for type extensions
 pub struct TypeRegistry {
@@ -33,6 +34,10 @@ impl TypeRegistry {
             .and_then(|oid| self.types.get(oid))
             .map(|b| &**b)
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn TypeExtension> {
+        self.types.values().map(|b| &**b)
+    }
 }
 
 /// Registry for operator extensions
@@ -62,11 +67,22 @@ impl OperatorRegistry {
             .find(|op| op.operator_symbol() == symbol && op.can_handle(left, right))
             .map(|b| &**b)
     }
+
+    /// Operators registered for `symbol`, in registration order
+    pub fn candidates(&self, symbol: &str) -> impl Iterator<Item = &dyn OperatorExtension> {
+        let symbol = symbol.to_string();
+        self.operators
+            .iter()
+            .filter(move |op| op.operator_symbol() == symbol)
+            .map(|b| &**b)
+    }
 }
 
 /// Registry for function extensions
+///
+/// Several functions may share a name; calls pick among them by argument types.
 pub struct FunctionRegistry {
-    functions: HashMap<String, Box<dyn FunctionExtension>>,
+    functions: HashMap<String, Vec<Box<dyn FunctionExtension>>>,
 }
 
 impl FunctionRegistry {
@@ -77,14 +93,79 @@ impl FunctionRegistry {
     }
 
     pub fn register(&mut self, ext: Box<dyn FunctionExtension>) {
-        self.functions.insert(ext.name().to_string(), ext);
+        self.functions
+            .entry(ext.name().to_ascii_lowercase())
+            .or_default()
+            .push(ext);
     }
 
     pub fn get(&self, name: &str) -> Option<&dyn FunctionExtension> {
-        self.functions.get(name).map(|b| &**b)
+        self.functions
+            .get(&name.to_ascii_lowercase())
+            .and_then(|overloads| overloads.first())
+            .map(|b| &**b)
+    }
+
+    /// Functions registered as `name`, in registration order
+    pub fn overloads(&self, name: &str) -> impl Iterator<Item = &dyn FunctionExtension> {
+        self.functions
+            .get(&name.to_ascii_lowercase())
+            .into_iter()
+            .flatten()
+            .map(|b| &**b)
+    }
+
+    /// The function called by `name(arg_types)` and its return type
+    ///
+    /// The first function registered under the name that accepts the
+    /// argument types wins.
+    pub fn resolve(&self, name: &str, arg_types: &[DataType]) -> Option<(&dyn FunctionExtension, DataType)> {
+        self.functions
+            .get(&name.to_ascii_lowercase())?
+            .iter()
+            .find_map(|f| f.return_type(arg_types).ok().map(|return_type| (&**f, return_type)))
+    }
+}
+
+/// Registry for cast extensions
+pub struct CastRegistry {
+    casts: Vec<Box<dyn CastExtension>>,
+}
+
+impl CastRegistry {
+    pub fn new() -> Self {
+        CastRegistry {
+            casts: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, ext: Box<dyn CastExtension>) {
+        self.casts.push(ext);
+    }
+
+    pub fn find(&self, source: &DataType, target: &DataType) -> Option<&dyn CastExtension> {
+        self.casts
+            .iter()
+            .find(|cast| cast.can_cast(source, target))
+            .map(|b| &**b)
+    }
+}
+
+impl Default for CastRegistry {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+/// Registries SQL expressions resolve types, casts, operators and functions against
+#[derive(Clone)]
+pub struct Registries {
+    pub types: Arc<TypeRegistry>,
+    pub casts: Arc<CastRegistry>,
+    pub operators: Arc<OperatorRegistry>,
+    pub functions: Arc<FunctionRegistry>,
+}
+
 /// Registry for index builders
 /// NOTE: Using a placeholder design for Phase 1. Full implementation with function pointers or enum dispatch
 /// will be added in Phase 2 to maintain Send+Sync for Arc<Database>.
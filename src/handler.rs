@@ -2,28 +2,40 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use futures::Sink;
-use pgwire::api::{ClientInfo, ClientPortalStore, NoopHandler, PgWireServerHandlers};
-use pgwire::api::query::SimpleQueryHandler;
-use pgwire::api::results::Response;
-use pgwire::error::PgWireResult;
+use parking_lot::Mutex;
+use futures::{Sink, SinkExt};
+use pgwire::api::{ClientInfo, ClientPortalStore, NoopHandler, PgWireServerHandlers, Type, DEFAULT_NAME};
+use pgwire::api::portal::{Format, Portal};
+use pgwire::api::query::{send_describe_response, ExtendedQueryHandler, SimpleQueryHandler};
+use pgwire::api::results::{DescribePortalResponse, DescribeStatementResponse, Response};
+use pgwire::api::stmt::{QueryParser, StoredStatement};
+use pgwire::api::store::PortalStore;
+use pgwire::error::{PgWireError, PgWireResult};
 use pgwire::messages::PgWireBackendMessage;
+use pgwire::messages::data::{NoData, ParameterDescription};
+use pgwire::messages::extendedquery::{Describe, TARGET_TYPE_BYTE_STATEMENT};
 use tracing::{info, span, Level};
 use ulid::Ulid;
 
-use crate::executor::Executor;
+use crate::executor::{self, Executor, Prepared};
+use crate::executor::error::ExecutorError;
+use crate::extensions::registry::Registries;
+use crate::types::Value;
 
-use crate::config::Config;
+use crate::config::Settings;
 
+/// Handlers for one client connection
 pub(crate) struct HandlerFactory {
     handler: Arc<Handler>
 }
 
 impl HandlerFactory {
-    pub fn new(config: &Config) -> Self {
-        let executor = Arc::new(Executor::new(config));
+    /// Handlers sharing `executor` with other connections, with a session
+    /// starting from `settings`
+    pub fn new(executor: Arc<Executor>, settings: Settings) -> Self {
+        let parser = Arc::new(StatementParser { executor: executor.clone() });
         HandlerFactory {
-            handler: Arc::new(Handler { executor })
+            handler: Arc::new(Handler { executor, parser, settings: Mutex::new(settings) })
         }
     }
 }
@@ -33,6 +45,10 @@ impl PgWireServerHandlers for HandlerFactory {
         self.handler.clone()
     }
 
+    fn extended_query_handler(&self) -> Arc<impl ExtendedQueryHandler> {
+        self.handler.clone()
+    }
+
     fn startup_handler(&self) -> Arc<impl pgwire::api::auth::StartupHandler> {
         Arc::new(NoopHandler)
     }
@@ -40,6 +56,9 @@ impl PgWireServerHandlers for HandlerFactory {
 
 struct Handler {
     executor: Arc<Executor>,
+    parser: Arc<StatementParser>,
+    /// Parameters of this connection's session, changed with `SET`
+    settings: Mutex<Settings>,
 }
 
 #[async_trait]
@@ -56,6 +75,151 @@ impl SimpleQueryHandler for Handler {
         let _enter = span.enter();
 
         info!(query = %query, "received query");
-        self.executor.execute(query).map_err(|e| e.into())
+        self.executor.execute(query, &mut self.settings.lock()).map_err(|e| e.into())
+    }
+}
+
+/// Parses the statements of the extended query protocol's Parse messages
+struct StatementParser {
+    executor: Arc<Executor>,
+}
+
+#[async_trait]
+impl QueryParser for StatementParser {
+    type Statement = Prepared;
+
+    async fn parse_sql<C>(&self, _client: &C, sql: &str, types: &[Type]) -> PgWireResult<Prepared>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        info!(query = %sql, "received statement");
+        // Unspecified (zero) and unsupported type OIDs are left to inference
+        let declared = types.iter().map(executor::data_type).collect::<Vec<_>>();
+        self.executor.prepare(sql, &declared).map_err(|e| e.into())
+    }
+}
+
+#[async_trait]
+impl ExtendedQueryHandler for Handler {
+    type Statement = Prepared;
+    type QueryParser = StatementParser;
+
+    fn query_parser(&self) -> Arc<StatementParser> {
+        self.parser.clone()
+    }
+
+    async fn on_describe<C>(&self, client: &mut C, message: Describe) -> PgWireResult<()>
+    where
+        C: ClientInfo + ClientPortalStore + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::PortalStore: PortalStore<Statement = Prepared>,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        if message.target_type != TARGET_TYPE_BYTE_STATEMENT {
+            return self._on_describe(client, message).await;
+        }
+        let name = message.name.as_deref().unwrap_or(DEFAULT_NAME);
+        let statement = client.portal_store()
+            .get_statement(name)
+            .ok_or_else(|| PgWireError::StatementNotFound(name.to_owned()))?;
+        let response = self.do_describe_statement(client, &statement).await?;
+        if !response.fields.is_empty() {
+            return send_describe_response(client, &response).await;
+        }
+
+        // Statements without result columns are described with NoData even
+        // when they take parameters
+        let oids = response.parameters.iter().map(|t| t.oid()).collect();
+        client.send(PgWireBackendMessage::ParameterDescription(ParameterDescription::new(oids))).await?;
+        client.send(PgWireBackendMessage::NoData(NoData::new())).await?;
+        Ok(())
+    }
+
+    async fn do_describe_statement<C>(
+        &self,
+        _client: &mut C,
+        target: &StoredStatement<Prepared>,
+    ) -> PgWireResult<DescribeStatementResponse>
+    where
+        C: ClientInfo + ClientPortalStore + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::PortalStore: PortalStore<Statement = Prepared>,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        let prepared = &target.statement;
+        // Types the client declared are described as it declared them
+        let parameters = prepared.parameter_types.iter()
+            .enumerate()
+            .map(|(idx, data_type)| match target.parameter_types.get(idx) {
+                Some(declared) if executor::data_type(declared).is_some() => declared.clone(),
+                _ => executor::pg_type(data_type),
+            })
+            .collect();
+        // Result formats are chosen only when the statement is bound
+        let fields = match self.executor.describe(prepared, None, &self.settings.lock())? {
+            Some(schema) => self.executor.field_infos(&schema, &Format::UnifiedText)?,
+            None => Vec::new(),
+        };
+        Ok(DescribeStatementResponse::new(parameters, fields))
+    }
+
+    async fn do_describe_portal<C>(
+        &self,
+        _client: &mut C,
+        target: &Portal<Prepared>,
+    ) -> PgWireResult<DescribePortalResponse>
+    where
+        C: ClientInfo + ClientPortalStore + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::PortalStore: PortalStore<Statement = Prepared>,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        let params = parameters(target, self.executor.registries())?;
+        let fields = match self.executor.describe(&target.statement.statement, Some(&params), &self.settings.lock())? {
+            Some(schema) => self.executor.field_infos(&schema, &target.result_column_format)?,
+            None => Vec::new(),
+        };
+        Ok(DescribePortalResponse::new(fields))
+    }
+
+    async fn do_query<C>(&self, client: &mut C, portal: &Portal<Prepared>, _max_rows: usize) -> PgWireResult<Response>
+    where
+        C: ClientInfo + ClientPortalStore + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::PortalStore: PortalStore<Statement = Prepared>,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        let query_id = Ulid::new();
+        let client_addr = client.socket_addr();
+        let span = span!(Level::INFO, "query", query_id = %query_id, client_addr = %client_addr);
+        let _enter = span.enter();
+
+        info!(statement = %portal.statement.id, portal = %portal.name, "executing portal");
+        let params = parameters(portal, self.executor.registries())?;
+        // Rows past `max_rows` stay in the response stream until the portal is executed again
+        let mut settings = self.settings.lock();
+        self.executor.execute_prepared(&portal.statement.statement, &params, &portal.result_column_format, &mut settings)
+            .map_err(|e| e.into())
+    }
+}
+
+/// Decode the parameter values bound to a portal
+fn parameters(portal: &Portal<Prepared>, registries: &Registries) -> PgWireResult<Vec<Value>> {
+    let types = &portal.statement.statement.parameter_types;
+    if portal.parameters.len() != types.len() {
+        return Err(ExecutorError::Execution(format!(
+            "bind message supplies {} parameters, but prepared statement \"{}\" requires {}",
+            portal.parameters.len(),
+            portal.statement.id,
+            types.len()
+        )).into());
     }
+    portal.parameters.iter()
+        .zip(types)
+        .enumerate()
+        .map(|(idx, (bytes, data_type))| {
+            executor::decode_parameter(bytes.as_deref(), portal.parameter_format.is_binary(idx), data_type, registries)
+                .map_err(|e| e.into())
+        })
+        .collect()
 }
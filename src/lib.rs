@@ -11,6 +11,6 @@ mod planner;
 
 // Re-export extension types and registries for convenience
 #[cfg(feature = "extensions")]
-pub use extensions::registry::{TypeRegistry, OperatorRegistry, FunctionRegistry, IndexBuilderRegistry};
+pub use extensions::registry::{TypeRegistry, CastRegistry, OperatorRegistry, FunctionRegistry, IndexBuilderRegistry};
 #[cfg(feature = "extensions")]
-pub use extensions::{TypeExtension, OperatorExtension, FunctionExtension, IndexExtension, TypeCategory};
\ No newline at end of file
+pub use extensions::{TypeExtension, CastExtension, CastContext, OperatorExtension, FunctionExtension, IndexExtension, TypeCategory};
\ No newline at end of file
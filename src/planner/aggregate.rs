@@ -0,0 +1,222 @@
+use sqlparser::ast::{Expr, FunctionArg, FunctionArgExpr, FunctionArguments, DuplicateTreatment};
+
+use crate::executor::error::ExecutorError;
+use super::children_mut;
+
+/// Built-in aggregate functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    BoolAnd,
+    BoolOr,
+    StringAgg,
+}
+
+impl AggregateFunction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "count" => Some(AggregateFunction::Count),
+            "sum" => Some(AggregateFunction::Sum),
+            "avg" => Some(AggregateFunction::Avg),
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            "bool_and" | "every" => Some(AggregateFunction::BoolAnd),
+            "bool_or" => Some(AggregateFunction::BoolOr),
+            "string_agg" => Some(AggregateFunction::StringAgg),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::BoolAnd => "bool_and",
+            AggregateFunction::BoolOr => "bool_or",
+            AggregateFunction::StringAgg => "string_agg",
+        }
+    }
+
+    /// Number of arguments the function takes (COUNT(*) is represented with none)
+    fn arity(&self) -> usize {
+        match self {
+            AggregateFunction::StringAgg => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// A resolved aggregate call computed by `Operator::Aggregate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateCall {
+    pub func: AggregateFunction,
+    /// Argument expressions, evaluated against input rows (empty for COUNT(*))
+    pub args: Vec<Expr>,
+    /// Aggregate over distinct argument values only
+    pub distinct: bool,
+    /// FILTER (WHERE ...) clause
+    pub filter: Option<Expr>,
+    /// Name of the Aggregate output column holding the result
+    pub output_name: String,
+}
+
+/// Name of the Aggregate output column for a GROUP BY expression or aggregate call
+pub fn output_name(expr: &Expr) -> String {
+    expr.to_string()
+}
+
+/// Resolve `expr` into an aggregate call
+/// Returns None if the expression is not an aggregate function call
+pub fn resolve_call(expr: &Expr) -> Result<Option<AggregateCall>, ExecutorError> {
+    let Some(func) = aggregate_function(expr) else {
+        return Ok(None);
+    };
+    let Expr::Function(function) = expr else {
+        return Ok(None);
+    };
+
+    let FunctionArguments::List(arg_list) = &function.args else {
+        return Err(ExecutorError::Plan(format!(
+            "{} requires an argument list",
+            func.name()
+        )));
+    };
+    if !arg_list.clauses.is_empty() || !function.within_group.is_empty() {
+        return Err(ExecutorError::UnsupportedStatement(format!(
+            "Clauses inside {}() not yet supported",
+            func.name()
+        )));
+    }
+
+    let mut args = Vec::new();
+    let mut star = false;
+    for arg in &arg_list.args {
+        match arg {
+            FunctionArg::Unnamed(FunctionArgExpr::Expr(arg_expr)) => {
+                if contains_aggregate(arg_expr) {
+                    return Err(ExecutorError::Plan(
+                        "aggregate function calls cannot be nested".to_string(),
+                    ));
+                }
+                if super::window::contains_window(arg_expr) {
+                    return Err(ExecutorError::Plan(
+                        "aggregate function calls cannot contain window function calls".to_string(),
+                    ));
+                }
+                args.push(arg_expr.clone());
+            }
+            FunctionArg::Unnamed(FunctionArgExpr::Wildcard) => star = true,
+            _ => {
+                return Err(ExecutorError::UnsupportedStatement(format!(
+                    "Unsupported argument to {}(): {}",
+                    func.name(),
+                    arg
+                )));
+            }
+        }
+    }
+
+    let distinct = matches!(arg_list.duplicate_treatment, Some(DuplicateTreatment::Distinct));
+    let arg_count_ok = if star {
+        // Only COUNT(*) accepts a star, and only on its own
+        func == AggregateFunction::Count && args.is_empty() && !distinct
+    } else {
+        args.len() == func.arity()
+    };
+    if !arg_count_ok {
+        return Err(ExecutorError::Plan(format!(
+            "function {}({}) does not exist",
+            func.name(),
+            arg_list.args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().join(", ")
+        )));
+    }
+
+    let filter = match &function.filter {
+        Some(filter) if contains_aggregate(filter) => {
+            return Err(ExecutorError::Plan(
+                "aggregate functions are not allowed in FILTER".to_string(),
+            ));
+        }
+        Some(filter) => Some((**filter).clone()),
+        None => None,
+    };
+
+    Ok(Some(AggregateCall {
+        func,
+        args,
+        distinct,
+        filter,
+        output_name: output_name(expr),
+    }))
+}
+
+/// The aggregate function called by `expr`, if it is an aggregate call
+fn aggregate_function(expr: &Expr) -> Option<AggregateFunction> {
+    let Expr::Function(function) = expr else {
+        return None;
+    };
+    // Calls with OVER are window functions
+    if function.over.is_some() {
+        return None;
+    }
+    let name = function.name.0.last().and_then(|part| part.as_ident())?;
+    AggregateFunction::from_name(&name.value)
+}
+
+/// Check whether an expression contains an aggregate call
+pub fn contains_aggregate(expr: &Expr) -> bool {
+    if aggregate_function(expr).is_some() {
+        return true;
+    }
+    let mut expr = expr.clone();
+    children_mut(&mut expr).into_iter().any(|child| contains_aggregate(child))
+}
+
+/// Rewrite an expression evaluated above an Aggregate so that GROUP BY
+/// expressions and aggregate calls become references to its output columns
+///
+/// Aggregate calls found along the way are appended to `aggregates`.
+pub fn rewrite(expr: &Expr, group_by: &[Expr], aggregates: &mut Vec<AggregateCall>) -> Result<Expr, ExecutorError> {
+    if group_by.contains(expr) {
+        return Ok(column_ref(&output_name(expr)));
+    }
+    if let Some(call) = resolve_call(expr)? {
+        let column = column_ref(&call.output_name);
+        if !aggregates.iter().any(|existing| existing.output_name == call.output_name) {
+            aggregates.push(call);
+        }
+        return Ok(column);
+    }
+
+    match expr {
+        Expr::Identifier(ident) => Err(ungrouped_column(&ident.value)),
+        Expr::CompoundIdentifier(idents) => Err(ungrouped_column(
+            &idents.iter().map(|ident| ident.value.as_str()).collect::<Vec<_>>().join("."),
+        )),
+        _ => {
+            let mut rewritten = expr.clone();
+            for child in children_mut(&mut rewritten) {
+                *child = rewrite(child, group_by, aggregates)?;
+            }
+            Ok(rewritten)
+        }
+    }
+}
+
+fn ungrouped_column(name: &str) -> ExecutorError {
+    ExecutorError::Plan(format!(
+        "column \"{}\" must appear in the GROUP BY clause or be used in an aggregate function",
+        name
+    ))
+}
+
+fn column_ref(name: &str) -> Expr {
+    Expr::Identifier(sqlparser::ast::Ident::new(name))
+}
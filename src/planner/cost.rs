@@ -0,0 +1,633 @@
+use std::cmp::Ordering;
+
+use sqlparser::ast::{BinaryOperator, Expr, Ident, UnaryOperator};
+
+use crate::executor::evaluator;
+use crate::extensions::registry::Registries;
+use crate::storage::base::BLOCK_SIZE;
+use crate::storage::catalog::{ColumnStatistics, TableStatistics};
+use crate::types::{Row, Schema, Value};
+
+use super::join::{bare_column, split_conjuncts, JoinCondition, JoinMethod, JoinType};
+use super::{BitmapLookup, Operator, PlanContext, RangeBound, SetOperator};
+
+/// Cost of reading a page as part of a sequential scan
+pub const SEQ_PAGE_COST: f64 = 1.0;
+/// Cost of reading a page out of order, as index lookups do
+pub const RANDOM_PAGE_COST: f64 = 4.0;
+/// Cost of processing a row
+pub const CPU_TUPLE_COST: f64 = 0.01;
+/// Cost of processing an index entry
+pub const CPU_INDEX_TUPLE_COST: f64 = 0.005;
+/// Cost of evaluating an operator or predicate on a row
+pub const CPU_OPERATOR_COST: f64 = 0.0025;
+
+/// Selectivity of `column = value` when statistics cannot tell
+const DEFAULT_EQ_SEL: f64 = 0.005;
+/// Selectivity of a range comparison when statistics cannot tell
+const DEFAULT_INEQ_SEL: f64 = 1.0 / 3.0;
+/// Selectivity of a range bounded on both sides when statistics cannot tell
+const DEFAULT_RANGE_INEQ_SEL: f64 = 0.005;
+/// Selectivity of any other predicate
+const DEFAULT_SEL: f64 = 0.5;
+/// Distinct values assumed for a column that was never analyzed
+const DEFAULT_NUM_DISTINCT: f64 = 200.0;
+/// Row width in bytes assumed for tables that were never analyzed
+pub const DEFAULT_ROW_WIDTH: f64 = 64.0;
+
+/// Row and page estimates for a base table
+pub(crate) struct TableEstimate<'a> {
+    pub rows: f64,
+    pub pages: f64,
+    pub statistics: Option<&'a TableStatistics>,
+}
+
+/// Estimate a table's size from its current pages and its last ANALYZE
+///
+/// The row density seen by ANALYZE is applied to the current page count, so
+/// estimates follow a table as it grows. Tables never analyzed are assumed to
+/// hold rows of `DEFAULT_ROW_WIDTH` bytes.
+pub(crate) fn table_estimate<'a>(table: &str, ctx: &PlanContext<'a>) -> TableEstimate<'a> {
+    let pages = ctx.db.estimated_size(table).unwrap_or(0) as f64 / BLOCK_SIZE as f64;
+    let statistics = ctx.db.statistics(table);
+    let rows = match statistics {
+        Some(statistics) if statistics.page_count > 0 => {
+            statistics.row_count as f64 / statistics.page_count as f64 * pages
+        }
+        Some(statistics) => statistics.row_count as f64,
+        None => pages * BLOCK_SIZE as f64 / DEFAULT_ROW_WIDTH,
+    };
+    TableEstimate { rows, pages, statistics }
+}
+
+/// Cost of reading every page of a table and checking `predicates` predicates per row
+pub(crate) fn seq_scan_cost(table: &TableEstimate, predicates: usize) -> f64 {
+    table.pages * SEQ_PAGE_COST
+        + table.rows * (CPU_TUPLE_COST + predicates as f64 * CPU_OPERATOR_COST)
+}
+
+/// Cost of an index lookup returning `matched` rows, checking `predicates`
+/// remaining predicates on each
+///
+/// Every row fetched may be on a different page, but no page is read more
+/// than once.
+pub(crate) fn index_scan_cost(table: &TableEstimate, matched: f64, predicates: usize) -> f64 {
+    RANDOM_PAGE_COST
+        + matched.min(table.pages.max(1.0)) * RANDOM_PAGE_COST
+        + matched * (CPU_INDEX_TUPLE_COST + CPU_TUPLE_COST + predicates as f64 * CPU_OPERATOR_COST)
+}
+
+/// Cost of fetching `matched` rows of a bitmap, checking `predicates`
+/// predicates on each
+///
+/// Rows are read in physical order, so each page is read at most once, and
+/// the more pages are read the closer that comes to a sequential scan.
+pub(crate) fn bitmap_heap_cost(table: &TableEstimate, matched: f64, predicates: usize) -> f64 {
+    let pages = table.pages.max(1.0);
+    let pages_fetched = (2.0 * pages * matched / (2.0 * pages + matched)).min(pages);
+    let page_cost = RANDOM_PAGE_COST - (RANDOM_PAGE_COST - SEQ_PAGE_COST) * (pages_fetched / pages).sqrt();
+    pages_fetched * page_cost + matched * (CPU_TUPLE_COST + predicates as f64 * CPU_OPERATOR_COST)
+}
+
+/// Base tables whose statistics describe the columns an expression may reference
+pub(crate) struct Columns<'s, 'a> {
+    tables: Vec<(&'s Schema, Option<&'a TableStatistics>)>,
+    /// Resolves the functions and operators of constants compared against
+    registries: &'a Registries,
+}
+
+impl<'s, 'a> Columns<'s, 'a> {
+    /// No columns, for predicates over constants only
+    pub fn new(registries: &'a Registries) -> Self {
+        Columns { tables: Vec::new(), registries }
+    }
+
+    /// Columns of a single table, qualified as in `schema`
+    pub fn table(schema: &'s Schema, statistics: Option<&'a TableStatistics>, registries: &'a Registries) -> Self {
+        Columns { tables: vec![(schema, statistics)], registries }
+    }
+
+    /// Add the columns of another table
+    pub fn push(&mut self, schema: &'s Schema, statistics: Option<&'a TableStatistics>) {
+        self.tables.push((schema, statistics));
+    }
+
+    /// Statistics of the column `expr` refers to, if it was analyzed
+    fn statistics(&self, expr: &Expr) -> Option<&'a ColumnStatistics> {
+        let (name, statistics) = self.tables.iter()
+            .find_map(|(schema, statistics)| Some((bare_column(expr, schema)?, *statistics)))?;
+        statistics?.columns.iter().find(|column| column.name.eq_ignore_ascii_case(&name))
+    }
+}
+
+/// Fraction of a relation's rows for which `predicate` holds
+pub(crate) fn selectivity(predicate: &Expr, columns: &Columns) -> f64 {
+    let selectivity = match predicate {
+        Expr::Nested(inner) => selectivity(inner, columns),
+        Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+            selectivity(left, columns) * selectivity(right, columns)
+        }
+        Expr::BinaryOp { left, op: BinaryOperator::Or, right } => {
+            let left = selectivity(left, columns);
+            let right = selectivity(right, columns);
+            left + right - left * right
+        }
+        Expr::UnaryOp { op: UnaryOperator::Not, expr } => 1.0 - selectivity(expr, columns),
+        Expr::IsNull(expr) => match columns.statistics(expr) {
+            Some(column) => column.null_fraction,
+            None => DEFAULT_EQ_SEL,
+        },
+        Expr::IsNotNull(expr) => match columns.statistics(expr) {
+            Some(column) => 1.0 - column.null_fraction,
+            None => 1.0 - DEFAULT_EQ_SEL,
+        },
+        Expr::Between { expr, negated, low, high } => {
+            let low = RangeBound { value: low.clone(), inclusive: true };
+            let high = RangeBound { value: high.clone(), inclusive: true };
+            let inside = range_scan_selectivity(expr, Some(&low), Some(&high), columns);
+            if *negated { 1.0 - inside } else { inside }
+        }
+        Expr::BinaryOp { left, op, right } => match comparison(left, op, right, columns.registries) {
+            Some((column, op, value)) => {
+                let column = columns.statistics(column);
+                match op {
+                    BinaryOperator::Eq => eq_selectivity(column, &value),
+                    BinaryOperator::NotEq => {
+                        let nulls = column.map_or(0.0, |column| column.null_fraction);
+                        1.0 - eq_selectivity(column, &value) - nulls
+                    }
+                    op => range_selectivity(column, &op, &value),
+                }
+            }
+            None if *op == BinaryOperator::Eq => DEFAULT_EQ_SEL,
+            None if matches!(op, BinaryOperator::Lt | BinaryOperator::LtEq | BinaryOperator::Gt | BinaryOperator::GtEq) => DEFAULT_INEQ_SEL,
+            None => DEFAULT_SEL,
+        },
+        _ => DEFAULT_SEL,
+    };
+    selectivity.clamp(0.0, 1.0)
+}
+
+/// Fraction of rows whose `column` lies between the bounds of a range
+///
+/// The rows below the lower bound and those above the upper bound cannot
+/// overlap, so with both bounds their fractions are subtracted from all rows.
+pub(crate) fn range_scan_selectivity(
+    column: &Expr,
+    lower: Option<&RangeBound>,
+    upper: Option<&RangeBound>,
+    columns: &Columns,
+) -> f64 {
+    let bound = |bound: &RangeBound, inclusive: BinaryOperator, exclusive: BinaryOperator| {
+        let predicate = Expr::BinaryOp {
+            left: Box::new(column.clone()),
+            op: if bound.inclusive { inclusive } else { exclusive },
+            right: bound.value.clone(),
+        };
+        selectivity(&predicate, columns)
+    };
+    let above = |lower| bound(lower, BinaryOperator::GtEq, BinaryOperator::Gt);
+    let below = |upper| bound(upper, BinaryOperator::LtEq, BinaryOperator::Lt);
+
+    match (lower, upper) {
+        (None, None) => 1.0,
+        (Some(lower), None) => above(lower),
+        (None, Some(upper)) => below(upper),
+        (Some(_), Some(_)) if columns.statistics(column).is_none() => DEFAULT_RANGE_INEQ_SEL,
+        (Some(lower), Some(upper)) => (above(lower) + below(upper) - 1.0).max(0.0),
+    }
+}
+
+/// Fraction of the pairs of rows from two relations that an equality
+/// predicate between their columns matches
+///
+/// Each non-NULL value of the side with fewer distinct values is assumed to
+/// match some value of the other side.
+pub(crate) fn join_selectivity(left: &Expr, right: &Expr, columns: &Columns) -> f64 {
+    let left = columns.statistics(left);
+    let right = columns.statistics(right);
+    let non_null = |column: Option<&ColumnStatistics>| column.map_or(1.0, |column| 1.0 - column.null_fraction);
+    non_null(left) * non_null(right) / distinct(left).max(distinct(right))
+}
+
+/// Split `column op constant` (either way around) into the column, the
+/// operator as seen from the column, and the constant's value
+fn comparison<'a>(
+    left: &'a Expr,
+    op: &BinaryOperator,
+    right: &'a Expr,
+    registries: &Registries,
+) -> Option<(&'a Expr, BinaryOperator, Value)> {
+    use BinaryOperator::*;
+
+    let flipped = match op {
+        Eq => Eq,
+        NotEq => NotEq,
+        Lt => Gt,
+        LtEq => GtEq,
+        Gt => Lt,
+        GtEq => LtEq,
+        _ => return None,
+    };
+    let constant = |expr: &Expr| evaluator::eval_expr(expr, &Row::new(Vec::new()), &Schema::new(Vec::new()), registries).ok();
+    if is_column(left) && !is_column(right) {
+        Some((left, op.clone(), constant(right)?))
+    } else if is_column(right) && !is_column(left) {
+        Some((right, flipped, constant(left)?))
+    } else {
+        None
+    }
+}
+
+fn is_column(expr: &Expr) -> bool {
+    matches!(expr, Expr::Identifier(_) | Expr::CompoundIdentifier(_))
+}
+
+/// Distinct non-NULL values of a column
+fn distinct(column: Option<&ColumnStatistics>) -> f64 {
+    column.map_or(DEFAULT_NUM_DISTINCT, |column| (column.distinct_count as f64).max(1.0))
+}
+
+/// Fraction of rows equal to `value`
+fn eq_selectivity(column: Option<&ColumnStatistics>, value: &Value) -> f64 {
+    let Some(column) = column else {
+        return DEFAULT_EQ_SEL;
+    };
+    if matches!(value, Value::Null) {
+        return 0.0;
+    }
+    if let Some((_, fraction)) = column.most_common.iter().find(|(common, _)| same(common, value)) {
+        return *fraction;
+    }
+
+    // Values outside the most common list share what is left evenly
+    let others = column.distinct_count.saturating_sub(column.most_common.len() as u64);
+    if others == 0 {
+        return 0.0;
+    }
+    remaining_fraction(column) / others as f64
+}
+
+/// Fraction of rows for which `column op value` holds, for a range comparison
+fn range_selectivity(column: Option<&ColumnStatistics>, op: &BinaryOperator, value: &Value) -> f64 {
+    let Some(column) = column else {
+        return DEFAULT_INEQ_SEL;
+    };
+    if matches!(value, Value::Null) {
+        return 0.0;
+    }
+    let holds = |candidate: &Value| match evaluator::compare_values(candidate, value) {
+        Ok(ordering) => match op {
+            BinaryOperator::Lt => ordering == Ordering::Less,
+            BinaryOperator::LtEq => ordering != Ordering::Greater,
+            BinaryOperator::Gt => ordering == Ordering::Greater,
+            _ => ordering != Ordering::Less,
+        },
+        Err(_) => false,
+    };
+
+    let common = column.most_common.iter()
+        .filter(|(common, _)| holds(common))
+        .map(|(_, fraction)| fraction)
+        .sum::<f64>();
+    let below = histogram_fraction_below(&column.histogram, value).unwrap_or(DEFAULT_INEQ_SEL);
+    let histogram = match op {
+        BinaryOperator::Lt | BinaryOperator::LtEq => below,
+        _ => 1.0 - below,
+    };
+    common + histogram * remaining_fraction(column)
+}
+
+/// Fraction of the rows a histogram describes that are below `value`
+///
+/// Within a bucket numeric values are interpolated linearly; others are
+/// assumed to fall halfway.
+fn histogram_fraction_below(histogram: &[Value], value: &Value) -> Option<f64> {
+    let (first, last) = (histogram.first()?, histogram.last()?);
+    if histogram.len() < 2 {
+        return None;
+    }
+    if evaluator::compare_values(value, first).ok()? != Ordering::Greater {
+        return Some(0.0);
+    }
+    if evaluator::compare_values(value, last).ok()? != Ordering::Less {
+        return Some(1.0);
+    }
+
+    let buckets = (histogram.len() - 1) as f64;
+    let bucket = histogram.windows(2).position(|bounds| {
+        evaluator::compare_values(value, &bounds[1]).is_ok_and(|ordering| ordering == Ordering::Less)
+    })?;
+    let within = match (numeric(&histogram[bucket]), numeric(value), numeric(&histogram[bucket + 1])) {
+        (Some(low), Some(value), Some(high)) if high > low => (value - low) / (high - low),
+        _ => 0.5,
+    };
+    Some((bucket as f64 + within) / buckets)
+}
+
+/// Fraction of rows that are neither NULL nor one of the most common values
+fn remaining_fraction(column: &ColumnStatistics) -> f64 {
+    let common = column.most_common.iter().map(|(_, fraction)| fraction).sum::<f64>();
+    (1.0 - column.null_fraction - common).max(0.0)
+}
+
+fn numeric(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(n) => Some(*n as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+fn same(a: &Value, b: &Value) -> bool {
+    evaluator::compare_values(a, b).is_ok_and(|ordering| ordering == Ordering::Equal)
+}
+
+/// Estimated output rows of a planned operator and the cost of running it
+/// to completion, inputs included
+#[derive(Debug, Clone, Copy)]
+pub struct Estimate {
+    pub rows: f64,
+    pub cost: f64,
+}
+
+/// Estimate the rows and cost of a plan, the way the planner costs the
+/// alternatives it chooses between
+pub(crate) fn estimate(plan: &Operator, ctx: &PlanContext) -> Estimate {
+    match plan {
+        Operator::TableScan { table, .. } if table == "__constant__" => Estimate { rows: 1.0, cost: 0.0 },
+        Operator::TableScan { table, .. } => {
+            let table = table_estimate(table, ctx);
+            Estimate { rows: table.rows, cost: seq_scan_cost(&table, 0) }
+        }
+        Operator::IndexScan { table, alias, column, value } => {
+            let predicate = Expr::BinaryOp {
+                left: Box::new(Expr::Identifier(Ident::new(column))),
+                op: BinaryOperator::Eq,
+                right: Box::new(value.clone()),
+            };
+            index_scan_estimate(table, alias, ctx, |columns| selectivity(&predicate, columns))
+        }
+        Operator::IndexRangeScan { table, alias, column, lower, upper, .. } => {
+            let column = Expr::Identifier(Ident::new(column));
+            index_scan_estimate(table, alias, ctx, |columns| {
+                range_scan_selectivity(&column, lower.as_ref(), upper.as_ref(), columns)
+            })
+        }
+        Operator::BitmapHeapScan { table, alias, bitmap, recheck } => {
+            let bitmap = estimate(bitmap, ctx);
+            let mut predicates = Vec::new();
+            split_conjuncts(recheck, &mut predicates);
+            let cost = bitmap.cost + bitmap_heap_cost(&table_estimate(table, ctx), bitmap.rows, predicates.len());
+            let rows = index_scan_estimate(table, alias, ctx, |columns| selectivity(recheck, columns)).rows;
+            Estimate { rows, cost }
+        }
+        Operator::BitmapIndexScan { table, column, lookup } => {
+            let column = Expr::Identifier(Ident::new(column));
+            let rows = index_scan_estimate(table, &None, ctx, |columns| match lookup {
+                BitmapLookup::Equal(value) => {
+                    let predicate = Expr::BinaryOp {
+                        left: Box::new(column.clone()),
+                        op: BinaryOperator::Eq,
+                        right: value.clone(),
+                    };
+                    selectivity(&predicate, columns)
+                }
+                BitmapLookup::Range { lower, upper } => range_scan_selectivity(&column, lower.as_ref(), upper.as_ref(), columns),
+            }).rows;
+            Estimate { rows, cost: RANDOM_PAGE_COST + rows * CPU_INDEX_TUPLE_COST }
+        }
+        Operator::BitmapAnd { inputs } | Operator::BitmapOr { inputs } => {
+            let inputs = inputs.iter().map(|input| estimate(input, ctx)).collect::<Vec<_>>();
+            let table_rows = bitmap_table(plan).map_or(1.0, |table| table_estimate(table, ctx).rows.max(1.0));
+            // Inputs are assumed to match independent fractions of the table
+            let rows = match plan {
+                Operator::BitmapAnd { .. } => inputs.iter().map(|input| input.rows / table_rows).product::<f64>() * table_rows,
+                _ => {
+                    let missed = inputs.iter().map(|input| 1.0 - input.rows / table_rows).product::<f64>();
+                    (1.0 - missed) * table_rows
+                }
+            };
+            let combined = inputs.iter().map(|input| input.rows).sum::<f64>() * CPU_OPERATOR_COST;
+            Estimate { rows, cost: inputs.iter().map(|input| input.cost).sum::<f64>() + combined }
+        }
+        Operator::Filter { input, predicate } => {
+            let input_estimate = estimate(input, ctx);
+            let tables = base_tables(input, ctx);
+            let rows = input_estimate.rows * selectivity(predicate, &columns(&tables, ctx));
+            Estimate { rows, cost: input_estimate.cost + input_estimate.rows * CPU_OPERATOR_COST }
+        }
+        Operator::Project { input, columns, .. } => {
+            let input = estimate(input, ctx);
+            Estimate { rows: input.rows, cost: input.cost + input.rows * columns.len() as f64 * CPU_OPERATOR_COST }
+        }
+        Operator::Join { left, right, method, condition } => join_estimate(left, right, method, condition, ctx),
+        Operator::Aggregate { input, group_by, aggregates } => {
+            let input_estimate = estimate(input, ctx);
+            let rows = group_count(input, group_by, input_estimate.rows, ctx);
+            let per_row = (group_by.len() + aggregates.len()) as f64 * CPU_OPERATOR_COST;
+            Estimate { rows, cost: input_estimate.cost + input_estimate.rows * per_row + rows * CPU_TUPLE_COST }
+        }
+        Operator::Sort { input, limit, .. } => {
+            let input = estimate(input, ctx);
+            let rows = limit.map_or(input.rows, |limit| input.rows.min(limit as f64));
+            Estimate { rows, cost: input.cost + sort_cost(input.rows) }
+        }
+        Operator::Window { input, calls, .. } => {
+            let input = estimate(input, ctx);
+            let per_row = calls.len() as f64 * CPU_OPERATOR_COST;
+            Estimate { rows: input.rows, cost: input.cost + sort_cost(input.rows) + input.rows * per_row }
+        }
+        Operator::Distinct { input, keys } => {
+            let input_estimate = estimate(input, ctx);
+            let rows = if keys.is_empty() {
+                input_estimate.rows
+            } else {
+                group_count(input, keys, input_estimate.rows, ctx)
+            };
+            Estimate { rows, cost: input_estimate.cost + input_estimate.rows * CPU_OPERATOR_COST }
+        }
+        Operator::SetOperation { op, left, right, .. } => {
+            let (left, right) = (estimate(left, ctx), estimate(right, ctx));
+            let rows = match op {
+                SetOperator::Union => left.rows + right.rows,
+                SetOperator::Intersect => left.rows.min(right.rows),
+                SetOperator::Except => left.rows,
+            };
+            Estimate { rows, cost: left.cost + right.cost + (left.rows + right.rows) * CPU_OPERATOR_COST }
+        }
+        Operator::Limit { input, limit, offset } => {
+            let input = estimate(input, ctx);
+            let rows = (input.rows - offset.unwrap_or(0) as f64).clamp(0.0, *limit as f64);
+            Estimate { rows, cost: input.cost }
+        }
+        Operator::SubqueryScan { input, .. } | Operator::Instrument { input, .. } => estimate(input, ctx),
+        Operator::Apply { input, subplans } => {
+            let input = estimate(input, ctx);
+            // A correlated subquery runs once per input row, others once
+            let cost = subplans.iter()
+                .map(|subplan| {
+                    let runs = if subplan.params.is_empty() { 1.0 } else { input.rows };
+                    estimate(&subplan.plan, ctx).cost * runs
+                })
+                .sum::<f64>();
+            Estimate { rows: input.rows, cost: input.cost + cost }
+        }
+        Operator::RecursiveUnion { anchor, recursive, .. } => {
+            let (anchor, recursive) = (estimate(anchor, ctx), estimate(recursive, ctx));
+            Estimate {
+                rows: anchor.rows * RECURSIVE_ITERATIONS,
+                cost: anchor.cost + recursive.cost * RECURSIVE_ITERATIONS,
+            }
+        }
+        Operator::WorkTableScan { .. } => Estimate { rows: DEFAULT_WORK_TABLE_ROWS, cost: DEFAULT_WORK_TABLE_ROWS * CPU_TUPLE_COST },
+        Operator::Values { rows, .. } => Estimate { rows: rows.len() as f64, cost: rows.len() as f64 * CPU_TUPLE_COST },
+    }
+}
+
+/// Iterations assumed for a recursive WITH query
+const RECURSIVE_ITERATIONS: f64 = 10.0;
+/// Rows assumed for each iteration's working table
+const DEFAULT_WORK_TABLE_ROWS: f64 = 10.0;
+
+fn join_estimate(left: &Operator, right: &Operator, method: &JoinMethod, condition: &JoinCondition, ctx: &PlanContext) -> Estimate {
+    let left_estimate = estimate(left, ctx);
+    let right_estimate = estimate(right, ctx);
+    let (left_rows, right_rows) = (left_estimate.rows, right_estimate.rows);
+
+    let (keys, filter) = condition_selectivity(left, right, condition, ctx);
+    let matched = left_rows * right_rows * keys * filter;
+
+    let rows = match condition.join_type {
+        JoinType::Inner => matched,
+        JoinType::Left => matched.max(left_rows),
+        JoinType::Right => matched.max(right_rows),
+        JoinType::Full => matched.max(left_rows + right_rows),
+        // Fraction of left rows with at least one match
+        JoinType::Semi => left_rows * (right_rows * keys * filter).min(1.0),
+        JoinType::Anti => left_rows * (1.0 - (right_rows * keys * filter).min(1.0)),
+    };
+
+    let predicates = condition.left_keys.len() + usize::from(condition.filter.is_some());
+    let join_cost = match method {
+        JoinMethod::NestedLoop => right_estimate.cost + left_rows * right_rows * predicates.max(1) as f64 * CPU_OPERATOR_COST,
+        // The right table is probed once per left row rather than scanned
+        JoinMethod::IndexNestedLoop { .. } => left_rows * probe(right, right_rows * keys, condition, ctx).cost,
+        JoinMethod::Hash => right_estimate.cost + (left_rows + right_rows) * (CPU_TUPLE_COST + CPU_OPERATOR_COST),
+        JoinMethod::Merge => right_estimate.cost + sort_cost(left_rows) + sort_cost(right_rows),
+    };
+    Estimate { rows, cost: left_estimate.cost + join_cost + rows * CPU_TUPLE_COST }
+}
+
+/// Selectivities of the keys and of the filter of a join `condition` among
+/// the rows of its inputs
+fn condition_selectivity(left: &Operator, right: &Operator, condition: &JoinCondition, ctx: &PlanContext) -> (f64, f64) {
+    let left_tables = base_tables(left, ctx);
+    let right_tables = base_tables(right, ctx);
+    let mut columns = columns(&left_tables, ctx);
+    for (schema, statistics) in &right_tables {
+        columns.push(schema, *statistics);
+    }
+    let keys = condition.left_keys.iter()
+        .zip(&condition.right_keys)
+        .map(|(left, right)| join_selectivity(left, right, &columns))
+        .product::<f64>();
+    let filter = condition.filter.as_ref().map_or(1.0, |filter| selectivity(filter, &columns));
+    (keys, filter)
+}
+
+/// Estimate of one probe of the right side of an index nested loop join,
+/// the index lookup of the right rows matching the keys of one left row
+pub(crate) fn probe_estimate(left: &Operator, right: &Operator, condition: &JoinCondition, ctx: &PlanContext) -> Estimate {
+    let (keys, _) = condition_selectivity(left, right, condition, ctx);
+    probe(right, estimate(right, ctx).rows * keys, condition, ctx)
+}
+
+fn probe(right: &Operator, rows: f64, condition: &JoinCondition, ctx: &PlanContext) -> Estimate {
+    let table = match right {
+        Operator::TableScan { table, .. } => table_estimate(table, ctx),
+        _ => TableEstimate { rows: estimate(right, ctx).rows, pages: 0.0, statistics: None },
+    };
+    // The first key drives the lookup and the rest are rechecked
+    let predicates = condition.left_keys.len() + usize::from(condition.filter.is_some());
+    Estimate { rows, cost: index_scan_cost(&table, rows, predicates.saturating_sub(1)) }
+}
+
+/// Estimate of an index scan of `table` returning the fraction of its rows
+/// that `selectivity` finds among its columns
+fn index_scan_estimate(
+    table: &str,
+    alias: &Option<String>,
+    ctx: &PlanContext,
+    selectivity: impl FnOnce(&Columns) -> f64,
+) -> Estimate {
+    let estimate = table_estimate(table, ctx);
+    let Ok(schema) = ctx.db.get_schema(table) else {
+        return Estimate { rows: estimate.rows, cost: seq_scan_cost(&estimate, 1) };
+    };
+    let schema = match alias {
+        Some(alias) => schema.qualified(alias),
+        None => schema,
+    };
+    let rows = estimate.rows * selectivity(&Columns::table(&schema, estimate.statistics, &ctx.db.registries));
+    Estimate { rows, cost: index_scan_cost(&estimate, rows, 0) }
+}
+
+/// Table a bitmap collects pointers to
+fn bitmap_table(plan: &Operator) -> Option<&str> {
+    match plan {
+        Operator::BitmapIndexScan { table, .. } => Some(table),
+        Operator::BitmapAnd { inputs } | Operator::BitmapOr { inputs } => inputs.first().and_then(bitmap_table),
+        _ => None,
+    }
+}
+
+/// Cost of sorting `rows` rows
+fn sort_cost(rows: f64) -> f64 {
+    2.0 * CPU_OPERATOR_COST * rows * rows.max(2.0).log2()
+}
+
+/// Distinct combinations of `keys` among `rows` rows of `input`
+fn group_count(input: &Operator, keys: &[Expr], rows: f64, ctx: &PlanContext) -> f64 {
+    if keys.is_empty() {
+        return 1.0;
+    }
+    let tables = base_tables(input, ctx);
+    let columns = columns(&tables, ctx);
+    keys.iter()
+        .map(|key| distinct(columns.statistics(key)))
+        .product::<f64>()
+        .min(rows)
+        .max(1.0)
+}
+
+/// Alias-qualified schemas and statistics of the tables scanned below `plan`,
+/// whose columns are still visible in its output
+fn base_tables<'a>(plan: &Operator, ctx: &PlanContext<'a>) -> Vec<(Schema, Option<&'a TableStatistics>)> {
+    match plan {
+        Operator::TableScan { table, alias }
+        | Operator::IndexScan { table, alias, .. }
+        | Operator::IndexRangeScan { table, alias, .. }
+        | Operator::BitmapHeapScan { table, alias, .. } => {
+            let Ok(schema) = ctx.db.get_schema(table) else {
+                return Vec::new();
+            };
+            let schema = match alias {
+                Some(alias) => schema.qualified(alias),
+                None => schema,
+            };
+            vec![(schema, ctx.db.statistics(table))]
+        }
+        // Columns of these are renamed or computed
+        Operator::SubqueryScan { .. } | Operator::Project { .. } | Operator::Aggregate { .. } => Vec::new(),
+        plan => plan.children().into_iter().flat_map(|child| base_tables(child, ctx)).collect(),
+    }
+}
+
+fn columns<'s, 'a>(tables: &'s [(Schema, Option<&'a TableStatistics>)], ctx: &PlanContext<'a>) -> Columns<'s, 'a> {
+    let mut columns = Columns::new(&ctx.db.registries);
+    for (schema, statistics) in tables {
+        columns.push(schema, *statistics);
+    }
+    columns
+}
@@ -0,0 +1,576 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use sqlparser::ast::Expr;
+
+use crate::executor::error::ExecutorError;
+use crate::executor::instrument::NodeMetrics;
+use crate::executor::sort::SortMethod;
+use super::cost::{self, Estimate};
+use super::join::{self, JoinCondition, JoinMethod, JoinType};
+use super::{BitmapLookup, Operator, PlanContext, RangeBound, SetOperator, SortKey};
+
+/// Output format of EXPLAIN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainFormat {
+    /// One line per node and property, indented to show the tree
+    Text,
+    /// A single JSON document
+    Json,
+}
+
+/// Options of an EXPLAIN statement
+#[derive(Debug, Clone, Copy)]
+pub struct ExplainOptions {
+    /// Run the statement and report what each node actually did
+    pub analyze: bool,
+    /// Show the output columns of each node
+    pub verbose: bool,
+    pub format: ExplainFormat,
+}
+
+/// Times reported at the end of EXPLAIN ANALYZE
+pub struct Timings {
+    pub planning: Duration,
+    pub execution: Duration,
+}
+
+/// Describe a plan as the rows of EXPLAIN output
+///
+/// Instrument nodes are not shown; what they recorded is reported on the node
+/// they wrap. `timings` is given under ANALYZE.
+pub fn explain(
+    plan: &Operator,
+    options: &ExplainOptions,
+    timings: Option<&Timings>,
+    ctx: &PlanContext,
+) -> Result<Vec<String>, ExecutorError> {
+    let mut subplans = 0;
+    let root = describe(plan, options, ctx, &mut subplans)?;
+
+    match options.format {
+        ExplainFormat::Text => {
+            let mut lines = Vec::new();
+            root.text(0, false, &mut lines);
+            if let Some(timings) = timings {
+                lines.push(format!("Planning Time: {:.3} ms", millis(timings.planning)));
+                lines.push(format!("Execution Time: {:.3} ms", millis(timings.execution)));
+            }
+            Ok(lines)
+        }
+        ExplainFormat::Json => {
+            let mut fields = vec![("Plan", root.json())];
+            if let Some(timings) = timings {
+                fields.push(("Planning Time", Json::Number(format!("{:.3}", millis(timings.planning)))));
+                fields.push(("Execution Time", Json::Number(format!("{:.3}", millis(timings.execution)))));
+            }
+            let mut document = String::new();
+            Json::Array(vec![Json::Object(fields)]).write(0, &mut document);
+            Ok(vec![document])
+        }
+    }
+}
+
+/// A plan node as EXPLAIN shows it
+struct Node {
+    node_type: String,
+    relation: Option<String>,
+    alias: Option<String>,
+    index: Option<String>,
+    /// Label of a subquery plan under the node that runs it
+    subplan: Option<String>,
+    properties: Vec<(&'static str, Property)>,
+    estimate: Estimate,
+    /// What the node did, under ANALYZE
+    metrics: Option<NodeMetrics>,
+    children: Vec<Node>,
+}
+
+enum Property {
+    Text(String),
+    List(Vec<String>),
+}
+
+fn describe(
+    plan: &Operator,
+    options: &ExplainOptions,
+    ctx: &PlanContext,
+    subplans: &mut usize,
+) -> Result<Node, ExecutorError> {
+    let (plan, metrics) = match plan {
+        Operator::Instrument { input, metrics } => (input.as_ref(), Some(metrics.lock().clone())),
+        plan => (plan, None),
+    };
+    let mut node = Node {
+        node_type: String::new(),
+        relation: None,
+        alias: None,
+        index: None,
+        subplan: None,
+        properties: Vec::new(),
+        estimate: cost::estimate(plan, ctx),
+        metrics,
+        children: Vec::new(),
+    };
+    if options.verbose {
+        node.properties.push(("Output", Property::List(super::output_names(plan, ctx.db)?)));
+    }
+
+    match plan {
+        Operator::TableScan { table, .. } if table == "__constant__" => node.node_type = "Result".to_string(),
+        Operator::TableScan { table, alias } => {
+            node.node_type = "Seq Scan".to_string();
+            node.scan(table, alias);
+        }
+        Operator::IndexScan { table, alias, column, value } => {
+            let cond = format!("({} = {})", column, value);
+            // Without an index the executor scans for the value instead
+            match ctx.db.index_name(table, column).map_err(ExecutorError::Plan)? {
+                Some(index) => {
+                    node.node_type = "Index Scan".to_string();
+                    node.index = Some(index);
+                    node.properties.push(("Index Cond", Property::Text(cond)));
+                }
+                None => {
+                    node.node_type = "Seq Scan".to_string();
+                    node.properties.push(("Filter", Property::Text(cond)));
+                }
+            }
+            node.scan(table, alias);
+        }
+        Operator::IndexRangeScan { table, alias, column, lower, upper, descending } => {
+            node.node_type = if *descending { "Index Scan Backward" } else { "Index Scan" }.to_string();
+            node.index = ctx.db.range_index_name(table, column).map_err(ExecutorError::Plan)?;
+            if let Some(cond) = range_condition(column, lower.as_ref(), upper.as_ref()) {
+                node.properties.push(("Index Cond", Property::Text(cond)));
+            }
+            node.scan(table, alias);
+        }
+        Operator::BitmapHeapScan { table, alias, recheck, .. } => {
+            node.node_type = "Bitmap Heap Scan".to_string();
+            node.properties.push(("Recheck Cond", Property::Text(condition(recheck))));
+            node.scan(table, alias);
+        }
+        Operator::BitmapIndexScan { table, column, lookup } => {
+            node.node_type = "Bitmap Index Scan".to_string();
+            let cond = match lookup {
+                BitmapLookup::Equal(value) => {
+                    node.index = ctx.db.index_name(table, column).map_err(ExecutorError::Plan)?;
+                    Some(format!("({} = {})", column, value))
+                }
+                BitmapLookup::Range { lower, upper } => {
+                    node.index = ctx.db.range_index_name(table, column).map_err(ExecutorError::Plan)?;
+                    range_condition(column, lower.as_ref(), upper.as_ref())
+                }
+            };
+            if let Some(cond) = cond {
+                node.properties.push(("Index Cond", Property::Text(cond)));
+            }
+        }
+        Operator::BitmapAnd { .. } => node.node_type = "BitmapAnd".to_string(),
+        Operator::BitmapOr { .. } => node.node_type = "BitmapOr".to_string(),
+        Operator::Filter { predicate, .. } => {
+            node.node_type = "Filter".to_string();
+            node.properties.push(("Filter", Property::Text(condition(predicate))));
+        }
+        Operator::Project { .. } => node.node_type = "Project".to_string(),
+        Operator::Join { left, right, method, condition: join } => {
+            node.node_type = join_name(method, join.join_type);
+            let keys = join.left_keys.iter().zip(&join.right_keys).collect::<Vec<_>>();
+            let mut filters = Vec::new();
+            match method {
+                JoinMethod::NestedLoop => filters.extend(keys.iter().map(|(left, right)| format!("({} = {})", left, right))),
+                JoinMethod::Hash => node.properties.push(("Hash Cond", Property::Text(key_condition(&keys)))),
+                JoinMethod::Merge => node.properties.push(("Merge Cond", Property::Text(key_condition(&keys)))),
+                // The first key drives the index probe and the others are rechecked
+                JoinMethod::IndexNestedLoop { .. } => {
+                    filters.extend(keys.iter().skip(1).map(|(left, right)| format!("({} = {})", left, right)));
+                }
+            }
+            filters.extend(join.filter.iter().map(condition));
+            if !filters.is_empty() {
+                node.properties.push(("Join Filter", Property::Text(filters.join(" AND "))));
+            }
+
+            node.children.push(describe(left, options, ctx, subplans)?);
+            let right = match method {
+                JoinMethod::IndexNestedLoop { column } => describe_probe(left, right, column, join, options, ctx)?,
+                _ => describe(right, options, ctx, subplans)?,
+            };
+            node.children.push(right);
+            return Ok(node);
+        }
+        Operator::Aggregate { group_by, .. } => {
+            if group_by.is_empty() {
+                node.node_type = "Aggregate".to_string();
+            } else {
+                node.node_type = "HashAggregate".to_string();
+                node.properties.push(("Group Key", Property::List(group_by.iter().map(Expr::to_string).collect())));
+            }
+        }
+        Operator::Sort { keys, limit, .. } => {
+            node.node_type = "Sort".to_string();
+            node.properties.push(("Sort Key", Property::List(keys.iter().map(sort_key).collect())));
+            // ANALYZE reports the method the sort actually used
+            if limit.is_some() && node.metrics.is_none() {
+                node.properties.push(("Sort Method", Property::Text("top-N heapsort".to_string())));
+            }
+        }
+        Operator::Window { partition_by, order_by, .. } => {
+            node.node_type = "WindowAgg".to_string();
+            if !partition_by.is_empty() {
+                node.properties.push(("Partition By", Property::List(partition_by.iter().map(Expr::to_string).collect())));
+            }
+            if !order_by.is_empty() {
+                node.properties.push(("Order By", Property::List(order_by.iter().map(sort_key).collect())));
+            }
+        }
+        Operator::Distinct { keys, .. } => {
+            node.node_type = "Distinct".to_string();
+            if !keys.is_empty() {
+                node.properties.push(("Distinct Key", Property::List(keys.iter().map(Expr::to_string).collect())));
+            }
+        }
+        Operator::SetOperation { op, all, .. } => {
+            let op = match op {
+                SetOperator::Union => "Union",
+                SetOperator::Intersect => "Intersect",
+                SetOperator::Except => "Except",
+            };
+            node.node_type = if *all { format!("{} All", op) } else { op.to_string() };
+        }
+        Operator::Limit { offset, .. } => {
+            node.node_type = "Limit".to_string();
+            if let Some(offset) = offset {
+                node.properties.push(("Offset", Property::Text(offset.to_string())));
+            }
+        }
+        Operator::SubqueryScan { alias, .. } => {
+            node.node_type = "Subquery Scan".to_string();
+            node.alias = Some(alias.clone());
+        }
+        Operator::Apply { .. } => node.node_type = "Apply".to_string(),
+        Operator::RecursiveUnion { distinct, .. } => {
+            node.node_type = if *distinct { "Recursive Union" } else { "Recursive Union All" }.to_string();
+        }
+        Operator::WorkTableScan { name, .. } => {
+            node.node_type = "WorkTable Scan".to_string();
+            node.alias = Some(name.clone());
+        }
+        Operator::Values { .. } => node.node_type = "Values Scan".to_string(),
+        Operator::Instrument { .. } => unreachable!("unwrapped above"),
+    }
+
+    for child in plan.children() {
+        node.children.push(describe(child, options, ctx, subplans)?);
+    }
+    if let Operator::Apply { subplans: plans, .. } = plan {
+        for subplan in plans {
+            *subplans += 1;
+            let label = format!("SubPlan {}", subplans);
+            let mut child = describe(&subplan.plan, options, ctx, subplans)?;
+            child.subplan = Some(label);
+            node.children.push(child);
+        }
+    }
+    Ok(node)
+}
+
+/// Describe the inner table of an index nested loop join, which is probed
+/// through the index on `column` once per outer row
+fn describe_probe(
+    left: &Operator,
+    plan: &Operator,
+    column: &str,
+    join: &JoinCondition,
+    options: &ExplainOptions,
+    ctx: &PlanContext,
+) -> Result<Node, ExecutorError> {
+    let (scan, metrics) = match plan {
+        Operator::Instrument { input, metrics } => (input.as_ref(), Some(metrics.lock().clone())),
+        plan => (plan, None),
+    };
+    let (Operator::TableScan { table, alias }, Some(left_key)) = (scan, join.left_keys.first()) else {
+        return describe(plan, options, ctx, &mut 0);
+    };
+
+    // One probe finds the rows of a lookup by a value of unknown selectivity
+    let probe = Operator::IndexScan {
+        table: table.clone(),
+        alias: alias.clone(),
+        column: column.to_string(),
+        value: left_key.clone(),
+    };
+    let mut node = describe(&probe, options, ctx, &mut 0)?;
+    node.estimate = cost::probe_estimate(left, scan, join, ctx);
+    node.metrics = metrics;
+    if let Some(right_key) = join.right_keys.first()
+        && let Some((_, cond)) = node.properties.iter_mut().find(|(name, _)| *name == "Index Cond")
+    {
+        *cond = Property::Text(format!("({} = {})", right_key, left_key));
+    }
+    Ok(node)
+}
+
+impl Node {
+    fn scan(&mut self, table: &str, alias: &Option<String>) {
+        self.relation = Some(table.to_string());
+        self.alias = Some(alias.clone().unwrap_or_else(|| table.to_string()));
+    }
+
+    /// Node type followed by the index and relation it reads
+    fn title(&self) -> String {
+        let mut title = self.node_type.clone();
+        match (&self.index, &self.relation) {
+            // Bitmap index scans read the index alone
+            (Some(index), None) => {
+                let _ = write!(title, " on {}", index);
+            }
+            (Some(index), Some(_)) => {
+                let _ = write!(title, " using {}", index);
+            }
+            (None, _) => {}
+        }
+        match (&self.relation, &self.alias) {
+            (Some(relation), Some(alias)) if alias != relation => {
+                let _ = write!(title, " on {} {}", relation, alias);
+            }
+            (Some(name), _) | (None, Some(name)) => {
+                let _ = write!(title, " on {}", name);
+            }
+            (None, None) => {}
+        }
+        title
+    }
+
+    /// Append the node's lines, with the node line `indent` spaces in
+    fn text(&self, indent: usize, child: bool, lines: &mut Vec<String>) {
+        let mut line = " ".repeat(indent);
+        if child {
+            line.push_str("->  ");
+        }
+        line.push_str(&self.title());
+        let _ = write!(line, "  (cost={:.2} rows={:.0})", self.estimate.cost, self.estimate.rows.max(1.0));
+        match &self.metrics {
+            Some(metrics) if metrics.loops == 0 => line.push_str(" (never executed)"),
+            Some(metrics) => {
+                // Times and rows are averages over loops
+                let loops = metrics.loops as f64;
+                let _ = write!(
+                    line,
+                    " (actual time={:.3}..{:.3} rows={:.0} loops={})",
+                    millis(metrics.startup) / loops,
+                    millis(metrics.total) / loops,
+                    metrics.rows as f64 / loops,
+                    metrics.loops
+                );
+            }
+            None => {}
+        }
+        lines.push(line);
+
+        let detail = if child { indent + 6 } else { indent + 2 };
+        let pad = " ".repeat(detail);
+        for (name, value) in &self.properties {
+            let value = match value {
+                Property::Text(text) => text.clone(),
+                Property::List(items) => items.join(", "),
+            };
+            lines.push(format!("{}{}: {}", pad, name, value));
+        }
+        if let Some(method) = self.metrics.as_ref().and_then(|metrics| metrics.sort_method) {
+            match method {
+                SortMethod::External { disk_bytes, .. } => {
+                    lines.push(format!("{}Sort Method: {}  Disk: {}kB", pad, method.name(), disk_bytes.div_ceil(1024)));
+                }
+                _ => lines.push(format!("{}Sort Method: {}", pad, method.name())),
+            }
+        }
+        if let Some(metrics) = &self.metrics
+            && metrics.block_reads > 0
+        {
+            lines.push(format!("{}Blocks: read={}", pad, metrics.block_reads));
+        }
+
+        for child in &self.children {
+            match &child.subplan {
+                Some(label) => {
+                    lines.push(format!("{}{}", pad, label));
+                    child.text(detail + 2, true, lines);
+                }
+                None => child.text(detail, true, lines),
+            }
+        }
+    }
+
+    fn json(&self) -> Json {
+        let mut fields = vec![("Node Type", Json::String(self.node_type.clone()))];
+        if let Some(label) = &self.subplan {
+            fields.push(("Parent Relationship", Json::String("SubPlan".to_string())));
+            fields.push(("Subplan Name", Json::String(label.clone())));
+        }
+        if let Some(relation) = &self.relation {
+            fields.push(("Relation Name", Json::String(relation.clone())));
+        }
+        if let Some(alias) = &self.alias {
+            fields.push(("Alias", Json::String(alias.clone())));
+        }
+        if let Some(index) = &self.index {
+            fields.push(("Index Name", Json::String(index.clone())));
+        }
+        fields.push(("Total Cost", Json::Number(format!("{:.2}", self.estimate.cost))));
+        fields.push(("Plan Rows", Json::Number(format!("{:.0}", self.estimate.rows.max(1.0)))));
+        if let Some(metrics) = &self.metrics {
+            let loops = metrics.loops.max(1) as f64;
+            fields.push(("Actual Startup Time", Json::Number(format!("{:.3}", millis(metrics.startup) / loops))));
+            fields.push(("Actual Total Time", Json::Number(format!("{:.3}", millis(metrics.total) / loops))));
+            fields.push(("Actual Rows", Json::Number(format!("{:.0}", metrics.rows as f64 / loops))));
+            fields.push(("Actual Loops", Json::Number(metrics.loops.to_string())));
+            fields.push(("Blocks Read", Json::Number(metrics.block_reads.to_string())));
+            if let Some(method) = metrics.sort_method {
+                fields.push(("Sort Method", Json::String(method.name().to_string())));
+                if let SortMethod::External { disk_bytes, .. } = method {
+                    fields.push(("Sort Space Used", Json::Number(disk_bytes.div_ceil(1024).to_string())));
+                    fields.push(("Sort Space Type", Json::String("Disk".to_string())));
+                }
+            }
+        }
+        for (name, value) in &self.properties {
+            let value = match value {
+                Property::Text(text) => Json::String(text.clone()),
+                Property::List(items) => Json::Array(items.iter().cloned().map(Json::String).collect()),
+            };
+            fields.push((name, value));
+        }
+        if !self.children.is_empty() {
+            fields.push(("Plans", Json::Array(self.children.iter().map(Node::json).collect())));
+        }
+        Json::Object(fields)
+    }
+}
+
+fn join_name(method: &JoinMethod, join_type: JoinType) -> String {
+    let method = match method {
+        JoinMethod::NestedLoop | JoinMethod::IndexNestedLoop { .. } => "Nested Loop",
+        JoinMethod::Hash => "Hash",
+        JoinMethod::Merge => "Merge",
+    };
+    let join_type = match join_type {
+        JoinType::Inner if method == "Nested Loop" => return method.to_string(),
+        JoinType::Inner => "",
+        JoinType::Left => " Left",
+        JoinType::Right => " Right",
+        JoinType::Full => " Full",
+        JoinType::Semi => " Semi",
+        JoinType::Anti => " Anti",
+    };
+    format!("{}{} Join", method, join_type)
+}
+
+/// Equi-join key pairs as one condition
+fn key_condition(keys: &[(&Expr, &Expr)]) -> String {
+    let pairs = keys.iter()
+        .map(|(left, right)| format!("({} = {})", left, right))
+        .collect::<Vec<_>>();
+    match pairs.as_slice() {
+        [pair] => pair.clone(),
+        pairs => format!("({})", pairs.join(" AND ")),
+    }
+}
+
+/// A predicate in parentheses
+/// The comparisons of `column` with the bounds of an index range, ANDed
+fn range_condition(column: &str, lower: Option<&RangeBound>, upper: Option<&RangeBound>) -> Option<String> {
+    let bounds = join::range_comparisons(column, lower, upper)
+        .iter()
+        .map(condition)
+        .collect::<Vec<_>>();
+    match bounds.as_slice() {
+        [] => None,
+        [bound] => Some(bound.clone()),
+        bounds => Some(format!("({})", bounds.join(" AND "))),
+    }
+}
+
+fn condition(expr: &Expr) -> String {
+    match expr {
+        Expr::Nested(_) => expr.to_string(),
+        expr => format!("({})", expr),
+    }
+}
+
+fn sort_key(key: &SortKey) -> String {
+    let mut text = key.expr.to_string();
+    if key.descending {
+        text.push_str(" DESC");
+    }
+    if key.nulls_first != key.descending {
+        text.push_str(if key.nulls_first { " NULLS FIRST" } else { " NULLS LAST" });
+    }
+    text
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// JSON value of the JSON output format
+enum Json {
+    String(String),
+    /// A number, already formatted
+    Number(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    /// Write the value indented by `indent` levels of two spaces
+    fn write(&self, indent: usize, out: &mut String) {
+        match self {
+            Json::String(text) => write_string(text, out),
+            Json::Number(number) => out.push_str(number),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Array(items) => {
+                out.push('[');
+                for (idx, item) in items.iter().enumerate() {
+                    out.push_str(if idx == 0 { "\n" } else { ",\n" });
+                    out.push_str(&"  ".repeat(indent + 1));
+                    item.write(indent + 1, out);
+                }
+                out.push('\n');
+                out.push_str(&"  ".repeat(indent));
+                out.push(']');
+            }
+            Json::Object(fields) => {
+                out.push('{');
+                for (idx, (name, value)) in fields.iter().enumerate() {
+                    out.push_str(if idx == 0 { "\n" } else { ",\n" });
+                    out.push_str(&"  ".repeat(indent + 1));
+                    write_string(name, out);
+                    out.push_str(": ");
+                    value.write(indent + 1, out);
+                }
+                out.push('\n');
+                out.push_str(&"  ".repeat(indent));
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(text: &str, out: &mut String) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
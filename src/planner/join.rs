@@ -0,0 +1,1387 @@
+use std::collections::BTreeSet;
+
+use sqlparser::ast::{BinaryOperator, Expr, Ident, JoinConstraint, JoinOperator, TableFactor, TableWithJoins};
+use tracing::debug;
+
+use crate::executor::error::ExecutorError;
+use crate::extensions::registry::Registries;
+use crate::types::{Column, DataType, Schema};
+use crate::storage::Database;
+use crate::storage::catalog::TableStatistics;
+use super::{cost, subquery, BitmapLookup, Operator, PlanContext, RangeBound, SortKey};
+
+/// Rows a join emits besides matching pairs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinType {
+    Inner,
+    /// Unmatched left rows, padded with NULLs
+    Left,
+    /// Unmatched right rows, padded with NULLs
+    Right,
+    /// Unmatched rows of both sides
+    Full,
+    /// Left rows with at least one match, once each (EXISTS, IN)
+    Semi,
+    /// Left rows without a match (NOT EXISTS)
+    Anti,
+}
+
+/// Algorithm used to execute a join
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoinMethod {
+    /// Compare every left row with every right row
+    NestedLoop,
+    /// Look up matching right rows through an index on `column`
+    /// The right input is always a TableScan
+    IndexNestedLoop { column: String },
+    /// Build a hash table on the right input's keys and probe it with left rows
+    Hash,
+    /// Sort both inputs on the keys and merge them
+    Merge,
+}
+
+/// Join predicate, split into equi-join keys and a residual filter
+#[derive(Debug, Clone)]
+pub struct JoinCondition {
+    pub join_type: JoinType,
+    /// Key expressions over left rows, pairwise equal to `right_keys`
+    pub left_keys: Vec<Expr>,
+    /// Key expressions over right rows
+    pub right_keys: Vec<Expr>,
+    /// Rest of the join condition, evaluated on the left row followed by the right row
+    pub filter: Option<Expr>,
+    /// USING columns, each merged into one leading output column
+    pub using: Vec<String>,
+}
+
+/// Positions of each USING column in the left and right schemas
+pub fn using_positions(left: &Schema, right: &Schema, using: &[String]) -> Result<Vec<(usize, usize)>, ExecutorError> {
+    using.iter()
+        .map(|column| {
+            let find = |schema: &Schema, side: &str| schema.resolve_column(None, column)
+                .map_err(ExecutorError::Plan)?
+                .ok_or_else(|| ExecutorError::Plan(format!(
+                    "column \"{}\" specified in USING clause does not exist in {} table",
+                    column, side
+                )));
+            Ok((find(left, "left")?, find(right, "right")?))
+        })
+        .collect()
+}
+
+/// Output schema of a join: merged USING columns, then the left and right columns
+pub fn output_schema(left: &Schema, right: &Schema, using: &[String]) -> Result<Schema, ExecutorError> {
+    let mut columns = Vec::with_capacity(using.len() + left.len() + right.len());
+    for (column, (left_idx, _)) in using.iter().zip(using_positions(left, right, using)?) {
+        let mut merged = left.columns[left_idx].clone();
+        merged.name = column.clone();
+        columns.push(merged);
+    }
+    columns.extend(left.columns.iter().cloned());
+    columns.extend(right.columns.iter().cloned());
+    Ok(Schema::new(columns))
+}
+
+/// Plan of a FROM item: operator, output schema and `*` expansion
+type PlannedItem = (Operator, Schema, Vec<(Expr, String)>);
+
+/// A table, subquery or WITH query named in a FROM clause
+pub(crate) struct Relation {
+    source: Source,
+    alias: String,
+    /// Columns qualified by the alias
+    schema: Schema,
+    /// Estimated size in bytes
+    size: u64,
+    /// Estimated row count
+    rows: f64,
+}
+
+impl Relation {
+    /// Estimated bytes per row
+    fn width(&self) -> f64 {
+        if self.rows >= 1.0 {
+            self.size as f64 / self.rows
+        } else {
+            cost::DEFAULT_ROW_WIDTH
+        }
+    }
+}
+
+/// Where a relation's rows come from
+enum Source {
+    Table(String),
+    /// Planned subquery or WITH query
+    Subquery(Box<Operator>),
+}
+
+/// Tables and columns made visible by a FROM clause
+pub(crate) struct FromClause {
+    /// What `SELECT *` expands to: column expressions and output names
+    pub wildcard: Vec<(Expr, String)>,
+    /// Output schema of the planned FROM clause
+    pub schema: Schema,
+    relations: Vec<Relation>,
+}
+
+impl FromClause {
+    /// What `alias.*` expands to
+    pub fn qualified_wildcard(&self, alias: &str) -> Result<Vec<(Expr, String)>, ExecutorError> {
+        let relation = self.relations.iter()
+            .find(|relation| relation.alias.eq_ignore_ascii_case(alias))
+            .ok_or_else(|| missing_relation(alias))?;
+        Ok(relation_columns(relation))
+    }
+}
+
+/// Resolve the relations named by a FROM clause, in the order written
+pub(crate) fn resolve_from(from: &[TableWithJoins], ctx: &PlanContext) -> Result<Vec<Relation>, ExecutorError> {
+    let mut relations = Vec::new();
+    for item in from {
+        relations.push(resolve_relation(&item.relation, ctx)?);
+        for join in &item.joins {
+            relations.push(resolve_relation(&join.relation, ctx)?);
+        }
+    }
+    for (idx, relation) in relations.iter().enumerate() {
+        if relations[..idx].iter().any(|other| other.alias.eq_ignore_ascii_case(&relation.alias)) {
+            return Err(ExecutorError::Plan(format!(
+                "table name \"{}\" specified more than once",
+                relation.alias
+            )));
+        }
+    }
+    Ok(relations)
+}
+
+/// Columns of all `relations`, for resolving names before the joins are planned
+pub(crate) fn scope_schema(relations: &[Relation]) -> Schema {
+    Schema::new(relations.iter().flat_map(|relation| relation.schema.columns.iter().cloned()).collect())
+}
+
+/// Plan the FROM clause of a SELECT along with its WHERE clause
+///
+/// Inner and cross joins are reordered and WHERE conjuncts pushed down into
+/// scans and joins. Outer joins and USING/NATURAL joins are planned in the
+/// order written; their WHERE clause is returned for the caller to apply.
+pub(crate) fn plan_from(
+    from: &[TableWithJoins],
+    relations: Vec<Relation>,
+    selection: Option<&Expr>,
+    ctx: &PlanContext,
+) -> Result<(Operator, FromClause, Option<Expr>), ExecutorError> {
+
+    let reorderable = from.iter()
+        .flat_map(|item| &item.joins)
+        .all(|join| matches!(
+            &join.join_operator,
+            JoinOperator::Join(JoinConstraint::On(_) | JoinConstraint::None)
+                | JoinOperator::Inner(JoinConstraint::On(_) | JoinConstraint::None)
+                | JoinOperator::CrossJoin(JoinConstraint::None)
+        ));
+
+    if reorderable {
+        let mut conjuncts = Vec::new();
+        if let Some(selection) = selection {
+            split_conjuncts(selection, &mut conjuncts);
+        }
+        for join in from.iter().flat_map(|item| &item.joins) {
+            if let JoinOperator::Join(JoinConstraint::On(on)) | JoinOperator::Inner(JoinConstraint::On(on)) = &join.join_operator {
+                split_conjuncts(on, &mut conjuncts);
+            }
+        }
+
+        let (plan, schema) = plan_inner_joins(&relations, conjuncts, ctx)?;
+        let wildcard = relations.iter().flat_map(relation_columns).collect();
+        return Ok((plan, FromClause { wildcard, schema, relations }, None));
+    }
+
+    debug!("plan: joins in written order");
+    let mut relation_iter = relations.iter();
+    let mut result: Option<PlannedItem> = None;
+    for item in from {
+        let relation = relation_iter.next().expect("relation per FROM item");
+        let mut current = (scan(relation, Vec::new(), ctx), relation.schema.clone(), relation_columns(relation));
+
+        for join in &item.joins {
+            let right = relation_iter.next().expect("relation per join");
+            current = plan_join(current, right, &join.join_operator, ctx)?;
+        }
+
+        // Comma-separated FROM items are cross joined
+        result = Some(match result {
+            None => current,
+            Some(left) => cross_join(left, current)?,
+        });
+    }
+
+    let (plan, schema, wildcard) = result.expect("FROM clause has at least one item");
+    Ok((plan, FromClause { wildcard, schema, relations }, selection.cloned()))
+}
+
+/// Resolve a FROM item to a table, subquery or WITH query with its alias and schema
+fn resolve_relation(factor: &TableFactor, ctx: &PlanContext) -> Result<Relation, ExecutorError> {
+    let (source, name, mut schema, alias) = match factor {
+        TableFactor::Table { name, alias, args, .. } => {
+            if args.is_some() {
+                return Err(ExecutorError::UnsupportedStatement(
+                    "Table functions not supported".to_string(),
+                ));
+            }
+
+            let parts = name.0.iter()
+                .filter_map(|part| part.as_ident())
+                .map(|ident| ident.value.clone())
+                .collect::<Vec<_>>();
+            let table = parts.join(".");
+
+            // WITH queries shadow tables of the same name
+            match subquery::plan_cte(&table, ctx)? {
+                Some((plan, columns)) => {
+                    let schema = subquery_schema(&plan, columns, ctx.db)?;
+                    (Source::Subquery(Box::new(plan)), table, schema, alias)
+                }
+                None => {
+                    let schema = ctx.db.get_schema(&table)
+                        .map_err(ExecutorError::Plan)?;
+                    let name = parts.last().cloned().unwrap_or_default();
+                    (Source::Table(table), name, schema, alias)
+                }
+            }
+        }
+        TableFactor::Derived { lateral, subquery, alias } => {
+            if *lateral {
+                return Err(ExecutorError::UnsupportedStatement(
+                    "LATERAL subqueries not supported".to_string(),
+                ));
+            }
+            let Some(alias_name) = alias else {
+                return Err(ExecutorError::Plan("subquery in FROM must have an alias".to_string()));
+            };
+            let plan = super::plan_select(subquery, &ctx.scoped(None))?;
+            let schema = super::output_schema(&plan, ctx.db)?;
+            (Source::Subquery(Box::new(plan)), alias_name.name.value.clone(), schema, alias)
+        }
+        _ => {
+            return Err(ExecutorError::UnsupportedStatement(format!(
+                "Unsupported FROM item: {}",
+                factor
+            )));
+        }
+    };
+
+    // Columns may be renamed with `AS alias (a, b, ...)`
+    if let Some(alias) = alias {
+        if alias.columns.len() > schema.len() {
+            return Err(ExecutorError::Plan(format!(
+                "table \"{}\" has {} columns available but {} columns specified",
+                alias.name.value,
+                schema.len(),
+                alias.columns.len()
+            )));
+        }
+        for (column, renamed) in schema.columns.iter_mut().zip(&alias.columns) {
+            column.name = renamed.name.value.clone();
+        }
+    }
+
+    let alias = match alias {
+        Some(alias) => alias.name.value.clone(),
+        None => name,
+    };
+    let (size, rows) = match &source {
+        Source::Table(table) => {
+            let size = ctx.db.estimated_size(table)
+                .map_err(ExecutorError::Execution)?;
+            (size, cost::table_estimate(table, ctx).rows)
+        }
+        Source::Subquery(plan) => {
+            let size = plan_size(plan, ctx);
+            (size, size as f64 / cost::DEFAULT_ROW_WIDTH)
+        }
+    };
+
+    Ok(Relation {
+        schema: schema.qualified(&alias),
+        source,
+        alias,
+        size,
+        rows,
+    })
+}
+
+/// Schema of a WITH query's output columns, under the names it was given
+fn subquery_schema(plan: &Operator, columns: Vec<String>, db: &Database) -> Result<Schema, ExecutorError> {
+    let types = super::output_schema(plan, db)?.columns.into_iter().map(|column| column.data_type);
+    Ok(Schema::new(columns.into_iter()
+        .zip(types.chain(std::iter::repeat(DataType::Null)))
+        .map(|(name, data_type)| Column {
+            name,
+            data_type,
+            is_primary_key: false,
+        })
+        .collect()))
+}
+
+/// Column expressions and names of a relation, as `*` expands them
+fn relation_columns(relation: &Relation) -> Vec<(Expr, String)> {
+    relation.schema.columns.iter()
+        .map(|column| {
+            let name = column.name.rsplit_once('.').map(|(_, name)| name).unwrap_or(&column.name);
+            let expr = Expr::CompoundIdentifier(vec![Ident::new(&relation.alias), Ident::new(name)]);
+            (expr, name.to_string())
+        })
+        .collect()
+}
+
+/// Join relations connected by inner joins, choosing the join order
+///
+/// The order minimizes the estimated rows produced by the joins along the
+/// way (see `join_order`), with estimates taken from ANALYZE statistics.
+fn plan_inner_joins(relations: &[Relation], conjuncts: Vec<Expr>, ctx: &PlanContext) -> Result<(Operator, Schema), ExecutorError> {
+    let schemas = relations.iter().map(|relation| &relation.schema).collect::<Vec<_>>();
+
+    // Predicates on a single relation are pushed into its scan
+    let mut scan_predicates = vec![Vec::new(); relations.len()];
+    let mut pending = Vec::new();
+    for conjunct in conjuncts {
+        let refs = referenced(&conjunct, &schemas)?;
+        match refs.len() {
+            0 => scan_predicates[0].push(conjunct),
+            1 => scan_predicates[*refs.first().expect("one relation")].push(conjunct),
+            _ => pending.push((conjunct, refs)),
+        }
+    }
+
+    let statistics = relations.iter()
+        .map(|relation| match &relation.source {
+            Source::Table(table) => ctx.db.statistics(table),
+            Source::Subquery(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let rows = relations.iter()
+        .zip(&scan_predicates)
+        .zip(&statistics)
+        .map(|((relation, predicates), statistics)| {
+            predicates.iter()
+                .map(|predicate| cost::selectivity(predicate, &cost::Columns::table(&relation.schema, *statistics, &ctx.db.registries)))
+                .fold(relation.rows, |rows, selectivity| rows * selectivity)
+                .max(1.0)
+        })
+        .collect::<Vec<_>>();
+    let join_predicates = pending.iter()
+        .map(|(conjunct, refs)| JoinPredicate {
+            selectivity: join_predicate_selectivity(conjunct, refs, relations, &statistics, &ctx.db.registries),
+            refs: refs.clone(),
+            equality: matches!(conjunct, Expr::BinaryOp { op: BinaryOperator::Eq, .. }),
+        })
+        .collect::<Vec<_>>();
+
+    let order = join_order(&rows, &join_predicates);
+    let first = order[0];
+    debug!(relation = %relations[first].alias, rows = rows[first], "plan: join order starts");
+    let mut joined = BTreeSet::from([first]);
+    let mut plan = scan(&relations[first], std::mem::take(&mut scan_predicates[first]), ctx);
+    let mut schema = relations[first].schema.clone();
+    let mut joined_rows = rows[first];
+    let mut width = relations[first].width();
+
+    for &next in &order[1..] {
+        // Conjuncts that become evaluable once `next` is joined
+        let mut condition = JoinCondition {
+            join_type: JoinType::Inner,
+            left_keys: Vec::new(),
+            right_keys: Vec::new(),
+            filter: None,
+            using: Vec::new(),
+        };
+        let mut filters = Vec::new();
+        let right_schema = &relations[next].schema;
+        for (conjunct, _) in pending.extract_if(.., |(_, refs)| refs.iter().all(|r| *r == next || joined.contains(r))) {
+            match equi_join_keys(&conjunct, &schema, right_schema)? {
+                Some((left_key, right_key)) => {
+                    condition.left_keys.push(left_key);
+                    condition.right_keys.push(right_key);
+                }
+                None => filters.push(conjunct),
+            }
+        }
+        joined.insert(next);
+
+        let size = (joined_rows * width) as u64;
+        let right_size = (rows[next] * relations[next].width()) as u64;
+        let right_predicates = std::mem::take(&mut scan_predicates[next]);
+        let method = choose_method(&condition, size, right_size, &relations[next], ctx)?;
+
+        // An index probe replaces the scan, so its predicates move into the join
+        let right = if matches!(method, JoinMethod::IndexNestedLoop { .. }) {
+            filters.extend(right_predicates);
+            scan(&relations[next], Vec::new(), ctx)
+        } else {
+            scan(&relations[next], right_predicates, ctx)
+        };
+        condition.filter = conjunction(filters);
+
+        debug!(relation = %relations[next].alias, method = ?method, keys = condition.left_keys.len(), "plan: adding join");
+        let left_schema = schema;
+        schema = output_schema(&left_schema, right_schema, &[])?;
+        plan = make_join(plan, right, method, condition, size, right_size);
+        joined_rows = extended_rows(|idx| joined.contains(&idx) && idx != next, joined_rows, next, &rows, &join_predicates);
+        width += relations[next].width();
+    }
+
+    Ok((plan, schema))
+}
+
+/// A predicate joining two or more relations, for estimating join orders
+struct JoinPredicate {
+    selectivity: f64,
+    refs: BTreeSet<usize>,
+    equality: bool,
+}
+
+/// Relations joined exhaustively; larger joins are ordered greedily
+const EXHAUSTIVE_JOIN_LIMIT: usize = 10;
+
+/// Order in which to join relations estimated to hold `rows` rows each
+///
+/// Each relation joined must be connected to the ones before it by an
+/// equality predicate where possible, so cross products come last. Among
+/// such orders, the one producing the fewest intermediate rows in total is
+/// found by dynamic programming over subsets of relations. Joins of more than
+/// `EXHAUSTIVE_JOIN_LIMIT` relations instead start from the smallest relation
+/// and repeatedly join the one giving the smallest result.
+fn join_order(rows: &[f64], predicates: &[JoinPredicate]) -> Vec<usize> {
+    let n = rows.len();
+    let candidates = |joined: &dyn Fn(usize) -> bool| {
+        let remaining = (0..n).filter(|idx| !joined(*idx)).collect::<Vec<_>>();
+        let connected = remaining.iter().copied()
+            .filter(|idx| predicates.iter().any(|predicate| {
+                predicate.equality && applies(predicate, joined, *idx)
+            }))
+            .collect::<Vec<_>>();
+        if connected.is_empty() { remaining } else { connected }
+    };
+
+    if n > EXHAUSTIVE_JOIN_LIMIT {
+        let first = (0..n).min_by(|a, b| rows[*a].total_cmp(&rows[*b])).expect("at least one relation");
+        let mut order = vec![first];
+        let mut joined_rows = rows[first];
+        while order.len() < n {
+            let joined = |idx: usize| order.contains(&idx);
+            let (next, next_rows) = candidates(&joined).into_iter()
+                .map(|idx| (idx, extended_rows(joined, joined_rows, idx, rows, predicates)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .expect("remaining relations");
+            order.push(next);
+            joined_rows = next_rows;
+        }
+        return order;
+    }
+
+    // Cheapest order found for each set of relations: (total rows, rows, order)
+    let mut best: Vec<Option<(f64, f64, Vec<usize>)>> = vec![None; 1 << n];
+    for (idx, rows) in rows.iter().enumerate() {
+        best[1 << idx] = Some((0.0, *rows, vec![idx]));
+    }
+    for set in 1..(1usize << n) {
+        let Some((cost, set_rows, order)) = best[set].clone() else {
+            continue;
+        };
+        let joined = |idx: usize| set & (1 << idx) != 0;
+        for next in candidates(&joined) {
+            let next_rows = extended_rows(joined, set_rows, next, rows, predicates);
+            let next_cost = cost + next_rows;
+            let entry = &mut best[set | (1 << next)];
+            if entry.as_ref().is_none_or(|(best_cost, ..)| next_cost < *best_cost) {
+                let mut order = order.clone();
+                order.push(next);
+                *entry = Some((next_cost, next_rows, order));
+            }
+        }
+    }
+    best.pop().flatten().map(|(.., order)| order).expect("every relation joined")
+}
+
+/// Whether a predicate becomes evaluable when `next` joins the `joined` relations
+fn applies(predicate: &JoinPredicate, joined: &dyn Fn(usize) -> bool, next: usize) -> bool {
+    predicate.refs.contains(&next) && predicate.refs.iter().all(|idx| *idx == next || joined(*idx))
+}
+
+/// Estimated rows after joining relation `next` to `joined_rows` rows of the
+/// `joined` relations
+fn extended_rows(joined: impl Fn(usize) -> bool, joined_rows: f64, next: usize, rows: &[f64], predicates: &[JoinPredicate]) -> f64 {
+    predicates.iter()
+        .filter(|predicate| applies(predicate, &joined, next))
+        .fold(joined_rows * rows[next], |rows, predicate| rows * predicate.selectivity)
+        .max(1.0)
+}
+
+/// Selectivity of a predicate over the relations it references
+fn join_predicate_selectivity(
+    conjunct: &Expr,
+    refs: &BTreeSet<usize>,
+    relations: &[Relation],
+    statistics: &[Option<&TableStatistics>],
+    registries: &Registries,
+) -> f64 {
+    if let Expr::BinaryOp { left, op: BinaryOperator::Eq, right } = conjunct
+        && let [a, b] = refs.iter().copied().collect::<Vec<_>>()[..]
+    {
+        let side = |expr: &Expr| [a, b].into_iter().find(|idx| bare_column(expr, &relations[*idx].schema).is_some());
+        if let (Some(l), Some(r)) = (side(left), side(right))
+            && l != r
+        {
+            let mut columns = cost::Columns::table(&relations[l].schema, statistics[l], registries);
+            columns.push(&relations[r].schema, statistics[r]);
+            return cost::join_selectivity(left, right, &columns);
+        }
+    }
+    cost::selectivity(conjunct, &cost::Columns::new(registries))
+}
+
+/// Join a relation to the plan so far with an explicit join operator
+fn plan_join(
+    (left, left_schema, left_wildcard): (Operator, Schema, Vec<(Expr, String)>),
+    relation: &Relation,
+    join_operator: &JoinOperator,
+    ctx: &PlanContext,
+) -> Result<PlannedItem, ExecutorError> {
+    let (join_type, constraint) = match join_operator {
+        JoinOperator::Join(constraint) | JoinOperator::Inner(constraint) => (JoinType::Inner, constraint),
+        JoinOperator::Left(constraint) | JoinOperator::LeftOuter(constraint) => (JoinType::Left, constraint),
+        JoinOperator::Right(constraint) | JoinOperator::RightOuter(constraint) => (JoinType::Right, constraint),
+        JoinOperator::FullOuter(constraint) => (JoinType::Full, constraint),
+        JoinOperator::CrossJoin(constraint @ JoinConstraint::None) => (JoinType::Inner, constraint),
+        other => {
+            return Err(ExecutorError::UnsupportedStatement(format!(
+                "Unsupported join: {:?}",
+                other
+            )));
+        }
+    };
+
+    let right_schema = &relation.schema;
+    let using = match constraint {
+        JoinConstraint::Using(columns) => columns.iter()
+            .map(|name| name.0.iter()
+                .filter_map(|part| part.as_ident())
+                .map(|ident| ident.value.clone())
+                .next_back()
+                .unwrap_or_default())
+            .collect(),
+        // NATURAL joins on every column name both sides share
+        JoinConstraint::Natural => right_schema.columns.iter()
+            .filter_map(|column| column.name.rsplit_once('.').map(|(_, name)| name))
+            .filter(|name| left_wildcard.iter().any(|(_, visible)| visible.eq_ignore_ascii_case(name)))
+            .map(str::to_string)
+            .collect(),
+        JoinConstraint::On(_) | JoinConstraint::None => Vec::new(),
+    };
+
+    let mut condition = JoinCondition {
+        join_type,
+        left_keys: Vec::new(),
+        right_keys: Vec::new(),
+        filter: None,
+        using: using.clone(),
+    };
+    let positions = using_positions(&left_schema, right_schema, &using)?;
+    for (left_idx, right_idx) in &positions {
+        condition.left_keys.push(column_ref(&left_schema.columns[*left_idx].name));
+        condition.right_keys.push(column_ref(&right_schema.columns[*right_idx].name));
+    }
+    if let JoinConstraint::On(on) = constraint {
+        let mut conjuncts = Vec::new();
+        split_conjuncts(on, &mut conjuncts);
+        let mut filters = Vec::new();
+        for conjunct in conjuncts {
+            match equi_join_keys(&conjunct, &left_schema, right_schema)? {
+                Some((left_key, right_key)) => {
+                    condition.left_keys.push(left_key);
+                    condition.right_keys.push(right_key);
+                }
+                None => {
+                    // Validates column references
+                    referenced(&conjunct, &[&left_schema, right_schema])?;
+                    filters.push(conjunct);
+                }
+            }
+        }
+        condition.filter = conjunction(filters);
+    }
+
+    let left_size = plan_size(&left, ctx);
+    let method = choose_method(&condition, left_size, relation.size, relation, ctx)?;
+    debug!(relation = %relation.alias, join_type = ?join_type, method = ?method, "plan: adding join");
+
+    let schema = output_schema(&left_schema, right_schema, &using)?;
+
+    // Merged USING columns come first and hide the columns they were merged from
+    let mut wildcard = using.iter()
+        .map(|column| (Expr::Identifier(Ident::new(column)), column.clone()))
+        .collect::<Vec<_>>();
+    let hidden_left = positions.iter().map(|(left_idx, _)| *left_idx).collect::<Vec<_>>();
+    let hidden_right = positions.iter().map(|(_, right_idx)| *right_idx).collect::<Vec<_>>();
+    for (expr, name) in left_wildcard {
+        if !resolves_to(&expr, &left_schema, &hidden_left) {
+            wildcard.push((expr, name));
+        }
+    }
+    for (expr, name) in relation_columns(relation) {
+        if !resolves_to(&expr, right_schema, &hidden_right) {
+            wildcard.push((expr, name));
+        }
+    }
+
+    let right = scan(relation, Vec::new(), ctx);
+    let plan = make_join(left, right, method, condition, left_size, relation.size);
+    Ok((plan, schema, wildcard))
+}
+
+/// Cross join two planned FROM items
+fn cross_join(
+    (left, left_schema, mut wildcard): (Operator, Schema, Vec<(Expr, String)>),
+    (right, right_schema, right_wildcard): (Operator, Schema, Vec<(Expr, String)>),
+) -> Result<PlannedItem, ExecutorError> {
+    let schema = output_schema(&left_schema, &right_schema, &[])?;
+    wildcard.extend(right_wildcard);
+    let plan = Operator::Join {
+        left: Box::new(left),
+        right: Box::new(right),
+        method: JoinMethod::NestedLoop,
+        condition: JoinCondition {
+            join_type: JoinType::Inner,
+            left_keys: Vec::new(),
+            right_keys: Vec::new(),
+            filter: None,
+            using: Vec::new(),
+        },
+    };
+    Ok((plan, schema, wildcard))
+}
+
+/// Build a join operator; inner hash joins build on the smaller input
+fn make_join(left: Operator, right: Operator, method: JoinMethod, condition: JoinCondition, left_size: u64, right_size: u64) -> Operator {
+    let mut condition = condition;
+    let (left, right) = if method == JoinMethod::Hash
+        && condition.join_type == JoinType::Inner
+        && condition.using.is_empty()
+        && condition.filter.is_none()
+        && left_size < right_size
+    {
+        std::mem::swap(&mut condition.left_keys, &mut condition.right_keys);
+        (right, left)
+    } else {
+        (left, right)
+    };
+
+    Operator::Join {
+        left: Box::new(left),
+        right: Box::new(right),
+        method,
+        condition,
+    }
+}
+
+/// Pick the join algorithm for a join with `right` as its inner relation
+fn choose_method(
+    condition: &JoinCondition,
+    left_size: u64,
+    right_size: u64,
+    right: &Relation,
+    ctx: &PlanContext,
+) -> Result<JoinMethod, ExecutorError> {
+    let settings = ctx.settings;
+    if condition.left_keys.is_empty() {
+        return Ok(JoinMethod::NestedLoop);
+    }
+
+    // Index nested loop: the right side must be a base table whose rows are
+    // only ever matched, never emitted unmatched (INNER/LEFT)
+    let index_column = match &right.source {
+        Source::Table(table) if matches!(condition.join_type, JoinType::Inner | JoinType::Left) && condition.using.is_empty() => {
+            match condition.right_keys.first().and_then(|key| bare_column(key, &right.schema)) {
+                Some(column) if ctx.db.has_index(table, &column).map_err(ExecutorError::Execution)? => Some(column),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    if let Some(column) = index_column
+        && settings.enable_nestloop
+        && settings.enable_indexscan
+        && (left_size.saturating_mul(10) < right_size || (!settings.enable_hashjoin && !settings.enable_mergejoin))
+    {
+        return Ok(JoinMethod::IndexNestedLoop { column });
+    }
+
+    Ok(equi_join_method(left_size, right_size, ctx))
+}
+
+/// Pick hash, merge or nested loop join for an equi-join of planned inputs
+pub(crate) fn equi_join_method(left_size: u64, right_size: u64, ctx: &PlanContext) -> JoinMethod {
+    let settings = ctx.settings;
+
+    // A hash table over the smaller input must fit in work_mem, otherwise
+    // merge join sorts both inputs with spilling
+    let fits_in_memory = left_size.min(right_size) <= settings.work_mem as u64;
+    match (settings.enable_hashjoin, settings.enable_mergejoin, settings.enable_nestloop) {
+        (true, false, _) => JoinMethod::Hash,
+        (true, true, _) if fits_in_memory => JoinMethod::Hash,
+        (_, true, _) => JoinMethod::Merge,
+        (false, false, true) => JoinMethod::NestedLoop,
+        // Every method disabled: hashing is still the cheapest
+        (false, false, false) => JoinMethod::Hash,
+    }
+}
+
+/// Scan a relation, applying predicates pushed down to it
+fn scan(relation: &Relation, predicates: Vec<Expr>, ctx: &PlanContext) -> Operator {
+    let alias = Some(relation.alias.clone());
+    let mut predicates = predicates;
+
+    let mut plan = match &relation.source {
+        Source::Table(table) => {
+            // A `column = constant` predicate on an indexed column may be
+            // answered by an index lookup, when cheaper than a full scan
+            let index_predicate = ctx.settings.enable_indexscan
+                .then(|| index_predicate(table, &relation.schema, &predicates, ctx))
+                .flatten();
+
+            // Otherwise range predicates on a column with an ordered index
+            // may be answered by an index range scan
+            let index_range = match index_predicate {
+                None if ctx.settings.enable_indexscan => index_range(table, &relation.schema, &predicates, ctx),
+                _ => None,
+            };
+
+            // Predicates on several indexed columns may be answered together
+            // by combining bitmaps from each index
+            let index_cost = index_predicate.map(|(_, cost)| cost)
+                .or(index_range.as_ref().map(|(_, cost)| *cost));
+            let bitmap_scan = ctx.settings.enable_bitmapscan
+                .then(|| bitmap_scan(table, &relation.schema, &predicates, ctx))
+                .flatten()
+                .filter(|(_, bitmap_cost)| index_cost.is_none_or(|index_cost| *bitmap_cost < index_cost));
+
+            if let Some((bitmap_scan, _)) = bitmap_scan {
+                let mut recheck = Vec::new();
+                for position in bitmap_scan.predicates.iter().rev() {
+                    recheck.push(predicates.remove(*position));
+                }
+                recheck.reverse();
+                debug!(table = %table, "plan: bitmap heap scan");
+                Operator::BitmapHeapScan {
+                    table: table.clone(),
+                    alias,
+                    bitmap: Box::new(bitmap_scan.bitmap),
+                    recheck: conjunction(recheck).expect("bitmap built from predicates"),
+                }
+            } else if let Some((position, _)) = index_predicate {
+                let predicate = predicates.remove(position);
+                let (column, value) = index_equality(&predicate, &relation.schema).expect("checked above");
+                debug!(table = %table, column = %column, "plan: index scan");
+                Operator::IndexScan {
+                    table: table.clone(),
+                    alias,
+                    column,
+                    value,
+                }
+            } else if let Some((range, _)) = index_range {
+                for position in range.predicates.iter().rev() {
+                    predicates.remove(*position);
+                }
+                debug!(table = %table, column = %range.column, "plan: index range scan");
+                Operator::IndexRangeScan {
+                    table: table.clone(),
+                    alias,
+                    column: range.column,
+                    lower: range.lower,
+                    upper: range.upper,
+                    descending: false,
+                }
+            } else {
+                debug!(table = %table, "plan: table scan");
+                Operator::TableScan {
+                    table: table.clone(),
+                    alias,
+                }
+            }
+        }
+        Source::Subquery(plan) => {
+            debug!(alias = %relation.alias, "plan: subquery scan");
+            Operator::SubqueryScan {
+                input: plan.clone(),
+                alias: relation.alias.clone(),
+                columns: relation.schema.columns.iter()
+                    .map(|column| column.name.rsplit_once('.').map(|(_, name)| name).unwrap_or(&column.name).to_string())
+                    .collect(),
+            }
+        }
+    };
+
+    if let Some(predicate) = conjunction(predicates) {
+        debug!("plan: adding filter");
+        plan = Operator::Filter {
+            input: Box::new(plan),
+            predicate,
+        };
+    }
+    plan
+}
+
+/// Position of the indexed `column = constant` predicate whose lookup costs
+/// least, and that cost, if less than scanning the whole table
+fn index_predicate(table: &str, schema: &Schema, predicates: &[Expr], ctx: &PlanContext) -> Option<(usize, f64)> {
+    let estimate = cost::table_estimate(table, ctx);
+    let scan_cost = cost::seq_scan_cost(&estimate, predicates.len());
+    let (position, index_cost) = predicates.iter()
+        .enumerate()
+        .filter_map(|(position, predicate)| {
+            let (column, _) = index_equality(predicate, schema)?;
+            if !ctx.db.has_index(table, &column).ok()? {
+                return None;
+            }
+            let matched = estimate.rows * cost::selectivity(predicate, &cost::Columns::table(schema, estimate.statistics, &ctx.db.registries));
+            Some((position, cost::index_scan_cost(&estimate, matched, predicates.len() - 1)))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    debug!(table = %table, scan_cost, index_cost, "plan: costed index lookup");
+    (index_cost < scan_cost).then_some((position, index_cost))
+}
+
+/// Range of an indexed column that some predicates of a scan bound
+struct IndexRange {
+    column: String,
+    lower: Option<RangeBound>,
+    upper: Option<RangeBound>,
+    /// Positions of the predicates the bounds come from, ascending
+    predicates: Vec<usize>,
+}
+
+/// The range of a column with an ordered index whose scan costs least, and
+/// that cost, if less than scanning the whole table
+///
+/// Each column takes the first lower and first upper bound found among the
+/// predicates; other bounds on it are left to be checked on each row.
+fn index_range(table: &str, schema: &Schema, predicates: &[Expr], ctx: &PlanContext) -> Option<(IndexRange, f64)> {
+    let mut ranges: Vec<IndexRange> = Vec::new();
+    for (position, predicate) in predicates.iter().enumerate() {
+        let Some((column, lower, upper)) = range_bounds(predicate, schema) else {
+            continue;
+        };
+        let range = match ranges.iter().position(|range| range.column == column) {
+            Some(idx) => &mut ranges[idx],
+            None => {
+                if ctx.db.range_index_name(table, &column).ok()?.is_none() {
+                    continue;
+                }
+                ranges.push(IndexRange { column, lower: None, upper: None, predicates: Vec::new() });
+                ranges.last_mut().expect("just pushed")
+            }
+        };
+        // A predicate is only dropped when the range takes all its bounds
+        if (lower.is_some() && range.lower.is_some()) || (upper.is_some() && range.upper.is_some()) {
+            continue;
+        }
+        range.lower = range.lower.take().or(lower);
+        range.upper = range.upper.take().or(upper);
+        range.predicates.push(position);
+    }
+
+    let estimate = cost::table_estimate(table, ctx);
+    let scan_cost = cost::seq_scan_cost(&estimate, predicates.len());
+    let columns = cost::Columns::table(schema, estimate.statistics, &ctx.db.registries);
+    let (range, index_cost) = ranges.into_iter()
+        .map(|range| {
+            let column = Expr::Identifier(Ident::new(&range.column));
+            let selectivity = cost::range_scan_selectivity(&column, range.lower.as_ref(), range.upper.as_ref(), &columns);
+            let remaining = predicates.len() - range.predicates.len();
+            let index_cost = cost::index_scan_cost(&estimate, estimate.rows * selectivity, remaining);
+            (range, index_cost)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    debug!(table = %table, scan_cost, index_cost, "plan: costed index range scan");
+    (index_cost < scan_cost).then_some((range, index_cost))
+}
+
+/// Bitmap combining indexes that answers some predicates of a scan
+struct BitmapScan {
+    bitmap: Operator,
+    /// Positions of the predicates the bitmap is built from, ascending
+    predicates: Vec<usize>,
+}
+
+/// The bitmap scan over several indexes that costs least, and that cost, if
+/// less than scanning the whole table
+///
+/// Predicates join the bitmap from the most selective one for as long as
+/// each makes the scan cheaper. A bitmap of a single index is left to the
+/// index scans.
+fn bitmap_scan(table: &str, schema: &Schema, predicates: &[Expr], ctx: &PlanContext) -> Option<(BitmapScan, f64)> {
+    let estimate = cost::table_estimate(table, ctx);
+    let scan_cost = cost::seq_scan_cost(&estimate, predicates.len());
+    // Each row fetched is checked against every predicate, rechecks included
+    let fetch_cost = |bitmap: &Operator| {
+        let bitmap = cost::estimate(bitmap, ctx);
+        bitmap.cost + cost::bitmap_heap_cost(&estimate, bitmap.rows, predicates.len())
+    };
+
+    let mut paths = predicates.iter()
+        .enumerate()
+        .filter_map(|(position, predicate)| {
+            let path = bitmap_path(table, schema, predicate, ctx)?;
+            let rows = cost::estimate(&path, ctx).rows;
+            Some((position, path, rows))
+        })
+        .collect::<Vec<_>>();
+    paths.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    let mut paths = paths.into_iter();
+    let (position, mut bitmap, _) = paths.next()?;
+    let mut positions = vec![position];
+    let mut bitmap_cost = fetch_cost(&bitmap);
+    for (position, path, _) in paths {
+        let mut inputs = match bitmap.clone() {
+            Operator::BitmapAnd { inputs } => inputs,
+            input => vec![input],
+        };
+        push_conjunct(&mut inputs, path);
+        let combined = match inputs.len() {
+            1 => inputs.pop().expect("one input"),
+            _ => Operator::BitmapAnd { inputs },
+        };
+        let combined_cost = fetch_cost(&combined);
+        if combined_cost < bitmap_cost {
+            bitmap = combined;
+            bitmap_cost = combined_cost;
+            positions.push(position);
+        }
+    }
+    if matches!(bitmap, Operator::BitmapIndexScan { .. }) {
+        return None;
+    }
+    positions.sort_unstable();
+
+    debug!(table = %table, scan_cost, bitmap_cost, "plan: costed bitmap scan");
+    (bitmap_cost < scan_cost).then_some((BitmapScan { bitmap, predicates: positions }, bitmap_cost))
+}
+
+/// Bitmap of the rows of `table` that may match `predicate`, built from its
+/// indexes
+///
+/// Equality and range comparisons on an indexed column read its index. OR
+/// needs a bitmap for each of its arms, AND for at least one.
+fn bitmap_path(table: &str, schema: &Schema, predicate: &Expr, ctx: &PlanContext) -> Option<Operator> {
+    match predicate {
+        Expr::Nested(inner) => bitmap_path(table, schema, inner, ctx),
+        Expr::BinaryOp { left, op: BinaryOperator::Or, right } => {
+            let mut inputs = Vec::new();
+            for arm in [left, right] {
+                match bitmap_path(table, schema, arm, ctx)? {
+                    Operator::BitmapOr { inputs: arms } => inputs.extend(arms),
+                    input => inputs.push(input),
+                }
+            }
+            Some(Operator::BitmapOr { inputs })
+        }
+        Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+            let mut inputs = Vec::new();
+            for conjunct in [left, right] {
+                match bitmap_path(table, schema, conjunct, ctx) {
+                    Some(Operator::BitmapAnd { inputs: conjuncts }) => {
+                        for input in conjuncts {
+                            push_conjunct(&mut inputs, input);
+                        }
+                    }
+                    Some(input) => push_conjunct(&mut inputs, input),
+                    None => {}
+                }
+            }
+            match inputs.len() {
+                0 => None,
+                1 => inputs.pop(),
+                _ => Some(Operator::BitmapAnd { inputs }),
+            }
+        }
+        predicate => {
+            if let Some((column, value)) = index_equality(predicate, schema)
+                && ctx.db.has_index(table, &column).ok()?
+            {
+                return Some(Operator::BitmapIndexScan {
+                    table: table.to_string(),
+                    column,
+                    lookup: BitmapLookup::Equal(Box::new(value)),
+                });
+            }
+            let (column, lower, upper) = range_bounds(predicate, schema)?;
+            ctx.db.range_index_name(table, &column).ok()??;
+            Some(Operator::BitmapIndexScan {
+                table: table.to_string(),
+                column,
+                lookup: BitmapLookup::Range { lower, upper },
+            })
+        }
+    }
+}
+
+/// Match a range predicate (`column > constant` either way around, or
+/// `column BETWEEN constant AND constant`) on a column of `schema`
+/// Returns the unqualified column name and the lower and upper bound set
+fn range_bounds(expr: &Expr, schema: &Schema) -> Option<(String, Option<RangeBound>, Option<RangeBound>)> {
+    match expr {
+        Expr::Nested(inner) => range_bounds(inner, schema),
+        Expr::Between { expr, negated: false, low, high } => {
+            if has_column_refs(low) || has_column_refs(high) {
+                return None;
+            }
+            let lower = RangeBound { value: low.clone(), inclusive: true };
+            let upper = RangeBound { value: high.clone(), inclusive: true };
+            Some((bare_column(expr, schema)?, Some(lower), Some(upper)))
+        }
+        Expr::BinaryOp { left, op, right } => {
+            let flipped = match op {
+                BinaryOperator::Lt => BinaryOperator::Gt,
+                BinaryOperator::LtEq => BinaryOperator::GtEq,
+                BinaryOperator::Gt => BinaryOperator::Lt,
+                BinaryOperator::GtEq => BinaryOperator::LtEq,
+                _ => return None,
+            };
+            [(left, op, right), (right, &flipped, left)].into_iter().find_map(|(column, op, value)| {
+                if has_column_refs(value) {
+                    return None;
+                }
+                let column = bare_column(column, schema)?;
+                let bound = |inclusive| RangeBound { value: value.clone(), inclusive };
+                match op {
+                    BinaryOperator::Gt => Some((column, Some(bound(false)), None)),
+                    BinaryOperator::GtEq => Some((column, Some(bound(true)), None)),
+                    BinaryOperator::Lt => Some((column, None, Some(bound(false)))),
+                    _ => Some((column, None, Some(bound(true)))),
+                }
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Add a bitmap to those a BitmapAnd intersects, merging a range into one
+/// on the same column when their bounds do not overlap
+fn push_conjunct(inputs: &mut Vec<Operator>, input: Operator) {
+    if let Operator::BitmapIndexScan { column, lookup: BitmapLookup::Range { lower, upper }, .. } = &input
+        && let Some(Operator::BitmapIndexScan { lookup: BitmapLookup::Range { lower: merged_lower, upper: merged_upper }, .. }) =
+            inputs.iter_mut().find(|other| matches!(
+                other,
+                Operator::BitmapIndexScan { column: other_column, lookup: BitmapLookup::Range { .. }, .. } if other_column == column
+            ))
+        && (lower.is_none() || merged_lower.is_none())
+        && (upper.is_none() || merged_upper.is_none())
+    {
+        *merged_lower = merged_lower.take().or(lower.clone());
+        *merged_upper = merged_upper.take().or(upper.clone());
+        return;
+    }
+    inputs.push(input);
+}
+
+/// The comparisons of `column` with each bound of a range
+pub(crate) fn range_comparisons(column: &str, lower: Option<&RangeBound>, upper: Option<&RangeBound>) -> Vec<Expr> {
+    let compare = |bound: &RangeBound, inclusive: BinaryOperator, exclusive: BinaryOperator| Expr::BinaryOp {
+        left: Box::new(Expr::Identifier(Ident::new(column))),
+        op: if bound.inclusive { inclusive } else { exclusive },
+        right: bound.value.clone(),
+    };
+    lower.map(|bound| compare(bound, BinaryOperator::GtEq, BinaryOperator::Gt))
+        .into_iter()
+        .chain(upper.map(|bound| compare(bound, BinaryOperator::LtEq, BinaryOperator::Lt)))
+        .collect()
+}
+
+/// Have the table scan under `plan` return rows in ORDER BY `keys` order by
+/// reading an ordered index, when that is cheaper than sorting them
+///
+/// Only a single key on an indexed column qualifies, with nothing but
+/// filters between the scan and the sort. A whole index returns NULLs last,
+/// or first when read backward, so the key must place them there. `limit`
+/// is the number of rows needed, if bounded. Returns whether `plan` now
+/// produces that order.
+pub(crate) fn index_order(plan: &mut Operator, keys: &[SortKey], limit: Option<u64>, ctx: &PlanContext) -> bool {
+    let [key] = keys else {
+        return false;
+    };
+    let mut scan: &Operator = plan;
+    while let Operator::Filter { input, .. } = scan {
+        scan = input;
+    }
+    let (Operator::TableScan { table, alias } | Operator::IndexRangeScan { table, alias, .. }) = scan else {
+        return false;
+    };
+    let Ok(schema) = ctx.db.get_schema(table) else {
+        return false;
+    };
+    let schema = match alias {
+        Some(alias) => schema.qualified(alias),
+        None => schema,
+    };
+    let Some(column) = bare_column(&key.expr, &schema) else {
+        return false;
+    };
+
+    match scan {
+        // The range is read in either direction at the same cost
+        Operator::IndexRangeScan { column: indexed, .. } if *indexed == column => {
+            if let Operator::IndexRangeScan { descending, .. } = scan_mut(plan) {
+                *descending = key.descending;
+            }
+            true
+        }
+        Operator::TableScan { table, alias } if ctx.settings.enable_indexscan => {
+            if key.nulls_first != key.descending
+                || !ctx.db.range_index_name(table, &column).is_ok_and(|index| index.is_some())
+            {
+                return false;
+            }
+            let mut ordered = plan.clone();
+            *scan_mut(&mut ordered) = Operator::IndexRangeScan {
+                table: table.clone(),
+                alias: alias.clone(),
+                column,
+                lower: None,
+                upper: None,
+                descending: key.descending,
+            };
+
+            let sorted = Operator::Sort {
+                input: Box::new(plan.clone()),
+                keys: keys.to_vec(),
+                limit,
+            };
+            let sort_cost = cost::estimate(&sorted, ctx).cost;
+            // Reading in order stops once `limit` rows came through
+            let estimate = cost::estimate(&ordered, ctx);
+            let fraction = limit.map_or(1.0, |limit| (limit as f64 / estimate.rows.max(1.0)).min(1.0));
+            let index_cost = estimate.cost * fraction;
+
+            debug!(table = %table, sort_cost, index_cost, "plan: costed index order");
+            if index_cost < sort_cost {
+                *plan = ordered;
+            }
+            index_cost < sort_cost
+        }
+        _ => false,
+    }
+}
+
+/// The scan under the filters of `plan`
+fn scan_mut(plan: &mut Operator) -> &mut Operator {
+    match plan {
+        Operator::Filter { input, .. } => scan_mut(input),
+        plan => plan,
+    }
+}
+
+/// Match `column = constant` (either way around) on a column of `schema`
+/// Returns the unqualified column name and the constant expression
+fn index_equality(expr: &Expr, schema: &Schema) -> Option<(String, Expr)> {
+    let Expr::BinaryOp { left, op: BinaryOperator::Eq, right } = expr else {
+        return None;
+    };
+    [(left, right), (right, left)].into_iter().find_map(|(column, value)| {
+        if has_column_refs(value) {
+            return None;
+        }
+        Some((bare_column(column, schema)?, (**value).clone()))
+    })
+}
+
+/// Unqualified column name if `expr` is a plain reference to a column of `schema`
+pub(crate) fn bare_column(expr: &Expr, schema: &Schema) -> Option<String> {
+    let idx = match expr {
+        Expr::Identifier(ident) => schema.resolve_column(None, &ident.value).ok()??,
+        Expr::CompoundIdentifier(idents) => {
+            let [.., qualifier, name] = idents.as_slice() else {
+                return None;
+            };
+            schema.resolve_column(Some(&qualifier.value), &name.value).ok()??
+        }
+        _ => return None,
+    };
+    let name = &schema.columns[idx].name;
+    Some(name.rsplit_once('.').map(|(_, name)| name).unwrap_or(name).to_string())
+}
+
+/// Split an `a = b` conjunct into a left key and a right key, if each side
+/// only references one of the two inputs
+fn equi_join_keys(expr: &Expr, left: &Schema, right: &Schema) -> Result<Option<(Expr, Expr)>, ExecutorError> {
+    let Expr::BinaryOp { left: a, op: BinaryOperator::Eq, right: b } = expr else {
+        return Ok(None);
+    };
+    let schemas = [left, right];
+    let a_refs = referenced(a, &schemas)?;
+    let b_refs = referenced(b, &schemas)?;
+    let only = |refs: &BTreeSet<usize>, side: usize| refs.len() == 1 && refs.contains(&side);
+
+    if only(&a_refs, 0) && only(&b_refs, 1) {
+        Ok(Some(((**a).clone(), (**b).clone())))
+    } else if only(&a_refs, 1) && only(&b_refs, 0) {
+        Ok(Some(((**b).clone(), (**a).clone())))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Indexes of the schemas that the column references in `expr` resolve to
+fn referenced(expr: &Expr, schemas: &[&Schema]) -> Result<BTreeSet<usize>, ExecutorError> {
+    let mut found = BTreeSet::new();
+    let mut stack = vec![expr.clone()];
+    while let Some(mut expr) = stack.pop() {
+        let (qualifier, name) = match &expr {
+            Expr::Identifier(ident) => (None, ident.value.clone()),
+            Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+                [.., qualifier, name] => (Some(qualifier.value.clone()), name.value.clone()),
+                _ => continue,
+            },
+            _ => {
+                stack.extend(super::children_mut(&mut expr).into_iter().map(|child| child.clone()));
+                continue;
+            }
+        };
+
+        let mut matches = Vec::new();
+        for (idx, schema) in schemas.iter().enumerate() {
+            if schema.resolve_column(qualifier.as_deref(), &name).map_err(ExecutorError::Plan)?.is_some() {
+                matches.push(idx);
+            }
+        }
+        match (matches.as_slice(), &qualifier) {
+            ([idx], _) => {
+                found.insert(*idx);
+            }
+            ([], Some(qualifier)) if !schemas.iter().any(|schema| has_relation(schema, qualifier)) => {
+                return Err(missing_relation(qualifier));
+            }
+            ([], Some(qualifier)) => {
+                return Err(ExecutorError::Plan(format!("column {}.{} does not exist", qualifier, name)));
+            }
+            ([], None) => {
+                return Err(ExecutorError::Plan(format!("column \"{}\" does not exist", name)));
+            }
+            _ => {
+                return Err(ExecutorError::Plan(format!("column reference \"{}\" is ambiguous", name)));
+            }
+        }
+    }
+    Ok(found)
+}
+
+fn has_column_refs(expr: &Expr) -> bool {
+    match expr {
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => true,
+        _ => {
+            let mut expr = expr.clone();
+            super::children_mut(&mut expr).into_iter().any(|child| has_column_refs(child))
+        }
+    }
+}
+
+/// Whether `expr` resolves to one of `positions` in `schema`
+fn resolves_to(expr: &Expr, schema: &Schema, positions: &[usize]) -> bool {
+    let idx = match expr {
+        Expr::Identifier(ident) => schema.resolve_column(None, &ident.value),
+        Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+            [.., qualifier, name] => schema.resolve_column(Some(&qualifier.value), &name.value),
+            _ => return false,
+        },
+        _ => return false,
+    };
+    matches!(idx, Ok(Some(idx)) if positions.contains(&idx))
+}
+
+fn has_relation(schema: &Schema, alias: &str) -> bool {
+    schema.columns.iter().any(|column| {
+        column.name.rsplit_once('.').is_some_and(|(relation, _)| relation.eq_ignore_ascii_case(alias))
+    })
+}
+
+fn missing_relation(alias: &str) -> ExecutorError {
+    ExecutorError::Plan(format!("missing FROM-clause entry for table \"{}\"", alias))
+}
+
+/// Reference to a column by its (possibly qualified) schema name
+pub(crate) fn column_ref(name: &str) -> Expr {
+    match name.rsplit_once('.') {
+        Some((qualifier, name)) => Expr::CompoundIdentifier(vec![Ident::new(qualifier), Ident::new(name)]),
+        None => Expr::Identifier(Ident::new(name)),
+    }
+}
+
+/// Split a predicate on AND into `out`
+pub(crate) fn split_conjuncts(expr: &Expr, out: &mut Vec<Expr>) {
+    match expr {
+        Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+            split_conjuncts(left, out);
+            split_conjuncts(right, out);
+        }
+        Expr::Nested(inner) if matches!(**inner, Expr::BinaryOp { op: BinaryOperator::And, .. }) => {
+            split_conjuncts(inner, out);
+        }
+        _ => out.push(expr.clone()),
+    }
+}
+
+/// AND predicates back together
+pub(crate) fn conjunction(predicates: Vec<Expr>) -> Option<Expr> {
+    predicates.into_iter().reduce(|left, right| Expr::BinaryOp {
+        left: Box::new(left),
+        op: BinaryOperator::And,
+        right: Box::new(right),
+    })
+}
+
+/// Size estimate after applying `predicates` filters (10% selectivity each)
+fn filtered_size(size: u64, predicates: usize) -> u64 {
+    (0..predicates).fold(size, |size, _| size / 10).max(1)
+}
+
+/// Size estimate of a join: equi-joins are assumed to match each row about
+/// once, cross products multiply
+fn joined_size(left: u64, right: u64, cross: bool) -> u64 {
+    if cross { left.saturating_mul(right) } else { left.max(right) }
+}
+
+/// Size estimate of an already planned input
+pub(crate) fn plan_size(plan: &Operator, ctx: &PlanContext) -> u64 {
+    match plan {
+        Operator::TableScan { table, .. }
+        | Operator::IndexScan { table, .. }
+        | Operator::IndexRangeScan { table, .. }
+        | Operator::BitmapHeapScan { table, .. } => {
+            ctx.db.estimated_size(table).unwrap_or(0)
+        }
+        Operator::Filter { input, .. } => filtered_size(plan_size(input, ctx), 1),
+        Operator::Aggregate { input, .. } => filtered_size(plan_size(input, ctx), 1),
+        Operator::Project { input, .. }
+        | Operator::Sort { input, .. }
+        | Operator::Limit { input, .. }
+        | Operator::Window { input, .. }
+        | Operator::Distinct { input, .. }
+        | Operator::SubqueryScan { input, .. }
+        | Operator::Apply { input, .. }
+        | Operator::Instrument { input, .. } => plan_size(input, ctx),
+        Operator::SetOperation { left, right, .. } => plan_size(left, ctx).saturating_add(plan_size(right, ctx)),
+        Operator::RecursiveUnion { anchor, .. } => plan_size(anchor, ctx).saturating_mul(10),
+        Operator::Join { left, condition, .. } if matches!(condition.join_type, JoinType::Semi | JoinType::Anti) => {
+            plan_size(left, ctx)
+        }
+        Operator::Join { left, right, condition, .. } => joined_size(
+            plan_size(left, ctx),
+            plan_size(right, ctx),
+            condition.left_keys.is_empty(),
+        ),
+        _ => 0,
+    }
+}
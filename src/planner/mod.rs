@@ -1,21 +1,110 @@
+pub mod aggregate;
+pub mod cost;
+pub mod explain;
+pub mod join;
+pub mod params;
+pub mod subquery;
+pub mod window;
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
 use sqlparser::ast::{Statement, CreateTable, Insert, CreateIndex};
 use tracing::debug;
 
+use crate::config::Settings;
+use crate::executor::{coercion, numeric};
 use crate::executor::error::ExecutorError;
-use crate::types::{Schema, Column, DataType};
+use crate::executor::instrument::NodeMetrics;
+use crate::storage::Database;
+use crate::types::{Row, Schema, Column, DataType};
+use aggregate::AggregateCall;
+use join::{JoinCondition, JoinMethod, JoinType};
+use subquery::{CommonTable, OuterScope, SubPlan};
+use window::WindowCall;
+
+/// Catalog, settings and query scope consulted while planning
+pub struct PlanContext<'a> {
+    pub db: &'a Database,
+    pub settings: &'a Settings,
+    /// WITH queries visible to the statement, innermost last
+    pub(crate) ctes: Vec<CommonTable>,
+    /// Columns of the enclosing query, when planning a subquery expression
+    pub(crate) outer: Option<&'a OuterScope>,
+}
+
+impl<'a> PlanContext<'a> {
+    pub fn new(db: &'a Database, settings: &'a Settings) -> Self {
+        PlanContext {
+            db,
+            settings,
+            ctes: Vec::new(),
+            outer: None,
+        }
+    }
 
-#[derive(Debug)]
+    /// Context for planning a nested query with the given enclosing scope
+    pub(crate) fn scoped<'b>(&'b self, outer: Option<&'b OuterScope>) -> PlanContext<'b> {
+        PlanContext {
+            db: self.db,
+            settings: self.settings,
+            ctes: self.ctes.clone(),
+            outer,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Operator {
     /// Scan all rows from a table
     TableScan {
         table: String,
+        /// Relation name the output columns are qualified with
+        alias: Option<String>,
     },
     /// Index scan for exact key lookup
     IndexScan {
         table: String,
+        alias: Option<String>,
         column: String,
         value: sqlparser::ast::Expr,
     },
+    /// Index scan over a range of key values, returning rows in key order
+    IndexRangeScan {
+        table: String,
+        alias: Option<String>,
+        column: String,
+        /// Smallest value in the range (None: unbounded)
+        lower: Option<RangeBound>,
+        /// Largest value in the range (None: unbounded)
+        upper: Option<RangeBound>,
+        /// Return rows from the largest key down
+        descending: bool,
+    },
+    /// Fetch the rows of a bitmap built by bitmap index scans, reading each
+    /// block once in physical order
+    BitmapHeapScan {
+        table: String,
+        alias: Option<String>,
+        /// BitmapIndexScan, BitmapAnd or BitmapOr
+        bitmap: Box<Operator>,
+        /// Predicates the bitmap was built from, checked again on each row
+        recheck: sqlparser::ast::Expr,
+    },
+    /// Pointers to the rows an index finds, for a BitmapHeapScan
+    BitmapIndexScan {
+        table: String,
+        column: String,
+        lookup: BitmapLookup,
+    },
+    /// Pointers found by every input bitmap
+    BitmapAnd {
+        inputs: Vec<Operator>,
+    },
+    /// Pointers found by any input bitmap
+    BitmapOr {
+        inputs: Vec<Operator>,
+    },
     /// Filter rows with a predicate
     Filter {
         input: Box<Operator>,
@@ -25,12 +114,53 @@ pub enum Operator {
     Project {
         input: Box<Operator>,
         columns: Vec<sqlparser::ast::Expr>,
+        /// Output column name for each projected expression
+        names: Vec<String>,
+    },
+    /// Join two inputs
+    /// Output rows hold the merged USING columns, then the left and right columns
+    Join {
+        left: Box<Operator>,
+        right: Box<Operator>,
+        method: JoinMethod,
+        condition: JoinCondition,
     },
-    /// Aggregate with GROUP BY
+    /// Hash aggregate with GROUP BY
+    /// Output rows hold the group_by values followed by the aggregate results
     Aggregate {
         input: Box<Operator>,
         group_by: Vec<sqlparser::ast::Expr>,
-        aggregates: Vec<sqlparser::ast::Expr>,
+        aggregates: Vec<AggregateCall>,
+    },
+    /// Sort rows by ORDER BY keys, spilling to disk above work_mem
+    Sort {
+        input: Box<Operator>,
+        keys: Vec<SortKey>,
+        /// Only the first `limit` rows are needed (bounded top-N sort)
+        limit: Option<u64>,
+    },
+    /// Compute window functions over partitions of the input
+    /// Output rows are sorted by partition_by then order_by, and hold the input
+    /// columns followed by one result per call
+    Window {
+        input: Box<Operator>,
+        partition_by: Vec<sqlparser::ast::Expr>,
+        order_by: Vec<SortKey>,
+        calls: Vec<WindowCall>,
+    },
+    /// Keep the first row for each distinct value of `keys` (the whole row when empty)
+    Distinct {
+        input: Box<Operator>,
+        keys: Vec<sqlparser::ast::Expr>,
+    },
+    /// UNION, INTERSECT or EXCEPT of two inputs with the same number of columns
+    /// Output columns are named after the left input
+    SetOperation {
+        op: SetOperator,
+        /// Keep duplicate rows (`ALL`)
+        all: bool,
+        left: Box<Operator>,
+        right: Box<Operator>,
     },
     /// Limit/offset rows
     Limit {
@@ -38,13 +168,213 @@ pub enum Operator {
         limit: u64,
         offset: Option<u64>,
     },
+    /// Output of a subquery in FROM or a WITH query, with columns named `alias.column`
+    SubqueryScan {
+        input: Box<Operator>,
+        alias: String,
+        columns: Vec<String>,
+    },
+    /// Evaluate subquery expressions for each input row
+    /// Output rows hold the input columns followed by one result per subquery
+    Apply {
+        input: Box<Operator>,
+        subplans: Vec<SubPlan>,
+    },
+    /// WITH RECURSIVE: run `anchor`, then rerun `recursive` over the rows the
+    /// previous iteration produced until it produces none
+    RecursiveUnion {
+        /// Name of the WITH query, read by `WorkTableScan`
+        name: String,
+        anchor: Box<Operator>,
+        recursive: Box<Operator>,
+        /// UNION rather than UNION ALL: duplicate rows are discarded
+        distinct: bool,
+    },
+    /// Rows produced by the previous iteration of a recursive WITH query
+    WorkTableScan {
+        name: String,
+        columns: Vec<String>,
+    },
+    /// Rows computed before execution (a recursive WITH query's working table)
+    Values {
+        schema: Schema,
+        rows: Vec<Row>,
+    },
+    /// Pass on the rows of `input`, recording how it ran for EXPLAIN ANALYZE
+    Instrument {
+        input: Box<Operator>,
+        /// Shared by copies of the node, so reruns of a subquery add up
+        metrics: Arc<Mutex<NodeMetrics>>,
+    },
 }
 
-pub fn plan(stmt: &Statement) -> Result<Operator, ExecutorError> {
+impl Operator {
+    /// Direct inputs of the operator (not the subquery plans of an Apply)
+    pub(crate) fn children(&self) -> Vec<&Operator> {
+        match self {
+            Operator::TableScan { .. }
+            | Operator::IndexScan { .. }
+            | Operator::IndexRangeScan { .. }
+            | Operator::BitmapIndexScan { .. }
+            | Operator::WorkTableScan { .. }
+            | Operator::Values { .. } => Vec::new(),
+            Operator::Filter { input, .. }
+            | Operator::Project { input, .. }
+            | Operator::Aggregate { input, .. }
+            | Operator::Sort { input, .. }
+            | Operator::Limit { input, .. }
+            | Operator::Window { input, .. }
+            | Operator::Distinct { input, .. }
+            | Operator::SubqueryScan { input, .. }
+            | Operator::Apply { input, .. }
+            | Operator::Instrument { input, .. } => vec![input.as_ref()],
+            Operator::BitmapHeapScan { bitmap, .. } => vec![bitmap.as_ref()],
+            Operator::BitmapAnd { inputs } | Operator::BitmapOr { inputs } => inputs.iter().collect(),
+            Operator::Join { left, right, .. } => vec![left.as_ref(), right.as_ref()],
+            Operator::RecursiveUnion { anchor, recursive, .. } => vec![anchor.as_ref(), recursive.as_ref()],
+            Operator::SetOperation { left, right, .. } => vec![left.as_ref(), right.as_ref()],
+        }
+    }
+
+    /// Direct inputs of the operator (not the subquery plans of an Apply)
+    pub(crate) fn children_mut(&mut self) -> Vec<&mut Operator> {
+        match self {
+            Operator::TableScan { .. }
+            | Operator::IndexScan { .. }
+            | Operator::IndexRangeScan { .. }
+            | Operator::BitmapIndexScan { .. }
+            | Operator::WorkTableScan { .. }
+            | Operator::Values { .. } => Vec::new(),
+            Operator::Filter { input, .. }
+            | Operator::Project { input, .. }
+            | Operator::Aggregate { input, .. }
+            | Operator::Sort { input, .. }
+            | Operator::Limit { input, .. }
+            | Operator::Window { input, .. }
+            | Operator::Distinct { input, .. }
+            | Operator::SubqueryScan { input, .. }
+            | Operator::Apply { input, .. }
+            | Operator::Instrument { input, .. } => vec![input.as_mut()],
+            Operator::BitmapHeapScan { bitmap, .. } => vec![bitmap.as_mut()],
+            Operator::BitmapAnd { inputs } | Operator::BitmapOr { inputs } => inputs.iter_mut().collect(),
+            Operator::Join { left, right, .. } => vec![left.as_mut(), right.as_mut()],
+            Operator::RecursiveUnion { anchor, recursive, .. } => vec![anchor.as_mut(), recursive.as_mut()],
+            Operator::SetOperation { left, right, .. } => vec![left.as_mut(), right.as_mut()],
+        }
+    }
+
+    /// Expressions the operator evaluates itself (not those of its inputs)
+    pub(crate) fn exprs_mut(&mut self) -> Vec<&mut sqlparser::ast::Expr> {
+        match self {
+            Operator::IndexScan { value, .. } => vec![value],
+            Operator::IndexRangeScan { lower, upper, .. } => lower.iter_mut()
+                .chain(upper.iter_mut())
+                .map(|bound| bound.value.as_mut())
+                .collect(),
+            Operator::BitmapHeapScan { recheck, .. } => vec![recheck],
+            Operator::BitmapIndexScan { lookup: BitmapLookup::Equal(value), .. } => vec![value.as_mut()],
+            Operator::BitmapIndexScan { lookup: BitmapLookup::Range { lower, upper }, .. } => lower.iter_mut()
+                .chain(upper.iter_mut())
+                .map(|bound| bound.value.as_mut())
+                .collect(),
+            Operator::Filter { predicate, .. } => vec![predicate],
+            Operator::Project { columns, .. } => columns.iter_mut().collect(),
+            Operator::Join { condition, .. } => condition.left_keys.iter_mut()
+                .chain(condition.right_keys.iter_mut())
+                .chain(condition.filter.iter_mut())
+                .collect(),
+            Operator::Aggregate { group_by, aggregates, .. } => group_by.iter_mut()
+                .chain(aggregates.iter_mut().flat_map(|call| call.args.iter_mut().chain(call.filter.iter_mut())))
+                .collect(),
+            Operator::Sort { keys, .. } => keys.iter_mut().map(|key| &mut key.expr).collect(),
+            Operator::Distinct { keys, .. } => keys.iter_mut().collect(),
+            Operator::Window { partition_by, order_by, calls, .. } => {
+                let mut exprs: Vec<&mut sqlparser::ast::Expr> = partition_by.iter_mut()
+                    .chain(order_by.iter_mut().map(|key| &mut key.expr))
+                    .collect();
+                for call in calls {
+                    exprs.extend(call.args.iter_mut());
+                    if let window::WindowFunction::Aggregate(aggregate) = &mut call.func {
+                        exprs.extend(aggregate.args.iter_mut().chain(aggregate.filter.iter_mut()));
+                    }
+                    for bound in [&mut call.frame.start, &mut call.frame.end] {
+                        if let window::FrameBound::Preceding(offset) | window::FrameBound::Following(offset) = bound {
+                            exprs.push(offset);
+                        }
+                    }
+                }
+                exprs
+            }
+            Operator::Apply { subplans, .. } => subplans.iter_mut()
+                .flat_map(|subplan| {
+                    let lhs = match &mut subplan.kind {
+                        subquery::SubqueryKind::In { expr, .. } => Some(expr.as_mut()),
+                        _ => None,
+                    };
+                    subplan.params.iter_mut().chain(lhs)
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Set operation combining the rows of two queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperator {
+    Union,
+    Intersect,
+    Except,
+}
+
+impl SetOperator {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SetOperator::Union => "UNION",
+            SetOperator::Intersect => "INTERSECT",
+            SetOperator::Except => "EXCEPT",
+        }
+    }
+}
+
+/// ORDER BY key
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub expr: sqlparser::ast::Expr,
+    pub descending: bool,
+    /// NULLs sort before non-NULL values (default: only for DESC)
+    pub nulls_first: bool,
+}
+
+/// One end of the key range of an index range scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeBound {
+    pub value: Box<sqlparser::ast::Expr>,
+    /// Keys equal to the value are in the range
+    pub inclusive: bool,
+}
+
+/// Index entries a bitmap index scan collects
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitmapLookup {
+    /// `column = value`
+    Equal(Box<sqlparser::ast::Expr>),
+    /// Keys of an ordered index between the bounds
+    Range {
+        lower: Option<RangeBound>,
+        upper: Option<RangeBound>,
+    },
+}
+
+pub fn plan(stmt: &Statement, ctx: &PlanContext) -> Result<Operator, ExecutorError> {
     debug!("planning statement");
 
     match stmt {
-        Statement::Query(query) => plan_select(query),
+        Statement::Query(query) => {
+            let mut plan = plan_select(query, ctx)?;
+            check_calls(&mut plan, ctx.db)?;
+            Ok(plan)
+        }
         Statement::StartTransaction { .. } => {
             debug!("plan: start transaction (handled by executor)");
             Err(ExecutorError::UnsupportedStatement(
@@ -73,118 +403,749 @@ pub fn plan(stmt: &Statement) -> Result<Operator, ExecutorError> {
     }
 }
 
-fn plan_select(query: &sqlparser::ast::Query) -> Result<Operator, ExecutorError> {
-    if let sqlparser::ast::SetExpr::Select(select) = &*query.body {
-        // Start with TableScan if there's a FROM clause
-        let (mut plan, table_name_opt) = if select.from.is_empty() {
-            // No FROM = constant expression (e.g., SELECT 1)
-            debug!("plan: constant select (no FROM)");
-            (Operator::TableScan {
-                table: "__constant__".to_string(),
-            }, None)
-        } else if select.from.len() == 1 {
-            let table_name = extract_table_name(&select.from[0])?;
-            debug!(table = %table_name, "plan: table scan");
-            (Operator::TableScan { table: table_name.clone() }, Some(table_name))
-        } else {
+pub(crate) fn plan_select(query: &sqlparser::ast::Query, ctx: &PlanContext) -> Result<Operator, ExecutorError> {
+    // WITH queries are visible throughout the query they are attached to
+    let with_ctx;
+    let ctx = match &query.with {
+        Some(with) => {
+            with_ctx = subquery::with_ctes(with, ctx)?;
+            &with_ctx
+        }
+        None => ctx,
+    };
+
+    let limit = extract_limit(query)?;
+    let sqlparser::ast::SetExpr::Select(select) = &*query.body else {
+        let plan = plan_set_expr(&query.body, ctx)?;
+        return plan_result_order(plan, query.order_by.as_ref(), limit, ctx);
+    };
+    plan_query_select(select, query.order_by.as_ref(), limit, ctx)
+}
+
+/// Plan a query body that is not a plain SELECT: a set operation or a
+/// parenthesized query
+fn plan_set_expr(body: &sqlparser::ast::SetExpr, ctx: &PlanContext) -> Result<Operator, ExecutorError> {
+    use sqlparser::ast::{SetExpr, SetQuantifier};
+
+    match body {
+        SetExpr::Select(select) => plan_query_select(select, None, None, ctx),
+        SetExpr::Query(query) => plan_select(query, ctx),
+        SetExpr::SetOperation { op, set_quantifier, left, right } => {
+            let op = match op {
+                sqlparser::ast::SetOperator::Union => SetOperator::Union,
+                sqlparser::ast::SetOperator::Intersect => SetOperator::Intersect,
+                sqlparser::ast::SetOperator::Except | sqlparser::ast::SetOperator::Minus => SetOperator::Except,
+            };
+            let all = match set_quantifier {
+                SetQuantifier::All => true,
+                SetQuantifier::Distinct | SetQuantifier::None => false,
+                other => {
+                    return Err(ExecutorError::UnsupportedStatement(format!(
+                        "{} {} not supported",
+                        op.name(),
+                        other
+                    )));
+                }
+            };
+
+            let left = plan_set_expr(left, ctx)?;
+            let right = plan_set_expr(right, ctx)?;
+            if output_names(&left, ctx.db)?.len() != output_names(&right, ctx.db)?.len() {
+                return Err(ExecutorError::Plan(format!(
+                    "each {} query must have the same number of columns",
+                    op.name()
+                )));
+            }
+
+            debug!(op = op.name(), all, "plan: adding set operation");
+            Ok(Operator::SetOperation {
+                op,
+                all,
+                left: Box::new(left),
+                right: Box::new(right),
+            })
+        }
+        _ => Err(ExecutorError::UnsupportedStatement(format!(
+            "Unsupported query: {}",
+            body
+        ))),
+    }
+}
+
+/// Apply ORDER BY and LIMIT to the result of a set operation
+/// Sort keys may only name or number result columns
+fn plan_result_order(
+    mut plan: Operator,
+    order_by: Option<&sqlparser::ast::OrderBy>,
+    limit: Option<(u64, Option<u64>)>,
+    ctx: &PlanContext,
+) -> Result<Operator, ExecutorError> {
+    if let Some(order_by) = order_by {
+        let sqlparser::ast::OrderByKind::Expressions(exprs) = &order_by.kind else {
             return Err(ExecutorError::UnsupportedStatement(
-                "Multiple tables not yet supported".to_string(),
+                "ORDER BY ALL not supported".to_string(),
             ));
         };
-
-        // Try to use IndexScan for equality predicates on primary key
-        if let Some(selection) = &select.selection {
-            if let Some(table_name) = &table_name_opt {
-                // Check if selection is a simple equality (col = value)
-                if let Some((col_name, value_expr)) = try_extract_equality(selection) {
-                    debug!(column = %col_name, "plan: attempting index scan");
-                    plan = Operator::IndexScan {
-                        table: table_name.clone(),
-                        column: col_name,
-                        value: value_expr,
-                    };
-                } else {
-                    debug!("plan: adding filter (not index-able)");
-                    plan = Operator::Filter {
-                        input: Box::new(plan),
-                        predicate: selection.clone(),
-                    };
+        let columns = output_names(&plan, ctx.db)?.iter()
+            .map(|name| join::column_ref(name))
+            .collect::<Vec<_>>();
+        let keys = exprs.iter()
+            .map(|order_expr| {
+                let key = resolve_sort_key(order_expr, &columns, &[])?;
+                if !columns.contains(&key.expr) {
+                    return Err(ExecutorError::Plan(format!(
+                        "invalid UNION/INTERSECT/EXCEPT ORDER BY clause: {}",
+                        order_expr.expr
+                    )));
                 }
-            } else {
-                debug!("plan: adding filter");
-                plan = Operator::Filter {
-                    input: Box::new(plan),
-                    predicate: selection.clone(),
+                Ok(key)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let sort_limit = limit.map(|(limit, offset)| limit.saturating_add(offset.unwrap_or(0)));
+        debug!(keys = keys.len(), limit = ?sort_limit, "plan: adding sort");
+        plan = Operator::Sort {
+            input: Box::new(plan),
+            keys,
+            limit: sort_limit,
+        };
+    }
+
+    if let Some((limit, offset)) = limit {
+        debug!(limit, offset = ?offset, "plan: adding limit");
+        plan = Operator::Limit {
+            input: Box::new(plan),
+            limit,
+            offset,
+        };
+    }
+    Ok(plan)
+}
+
+fn plan_query_select(
+    select: &sqlparser::ast::Select,
+    order_by: Option<&sqlparser::ast::OrderBy>,
+    limit: Option<(u64, Option<u64>)>,
+    ctx: &PlanContext,
+) -> Result<Operator, ExecutorError> {
+    let relations = join::resolve_from(&select.from, ctx)?;
+
+    // In a subquery, columns of the enclosing query become parameters
+    let bound;
+    let (select, order_by) = match ctx.outer {
+        Some(outer) => {
+            bound = subquery::bind_outer_refs(select, order_by, &join::scope_schema(&relations), outer);
+            (&bound.0, bound.1.as_ref())
+        }
+        None => (select, order_by),
+    };
+
+    if let Some(selection) = &select.selection
+        && aggregate::contains_aggregate(selection)
+    {
+        return Err(ExecutorError::Plan(
+            "aggregate functions are not allowed in WHERE".to_string(),
+        ));
+    }
+    // Window functions are computed after grouping and filtering
+    for (clause, expr) in [("WHERE", &select.selection), ("HAVING", &select.having)] {
+        if expr.as_ref().is_some_and(window::contains_window) {
+            return Err(ExecutorError::Plan(format!(
+                "window functions are not allowed in {}",
+                clause
+            )));
+        }
+    }
+
+    // WHERE conjuncts with subqueries are applied after the joins
+    let mut conjuncts = Vec::new();
+    if let Some(selection) = &select.selection {
+        join::split_conjuncts(selection, &mut conjuncts);
+    }
+    let (subquery_conjuncts, conjuncts): (Vec<_>, Vec<_>) = conjuncts.into_iter()
+        .partition(subquery::contains_subquery);
+    let selection = join::conjunction(conjuncts);
+
+    // Scan and join the FROM items, applying the WHERE clause
+    let (mut plan, from) = if select.from.is_empty() {
+        // No FROM = constant expression (e.g., SELECT 1)
+        debug!("plan: constant select (no FROM)");
+        let mut plan = Operator::TableScan {
+            table: "__constant__".to_string(),
+            alias: None,
+        };
+        if let Some(selection) = selection {
+            debug!("plan: adding filter");
+            plan = Operator::Filter {
+                input: Box::new(plan),
+                predicate: selection,
+            };
+        }
+        (plan, None)
+    } else {
+        let (mut plan, from, selection) = join::plan_from(&select.from, relations, selection.as_ref(), ctx)?;
+        if let Some(selection) = selection {
+            debug!("plan: adding filter");
+            plan = Operator::Filter {
+                input: Box::new(plan),
+                predicate: selection,
+            };
+        }
+        (plan, Some(from))
+    };
+
+    // Subqueries see the columns of the FROM clause
+    let scope = from.as_ref().map(|from| from.schema.clone()).unwrap_or_else(|| Schema::new(Vec::new()));
+    let mut subquery_count = 0;
+    let mut subplans = Vec::new();
+    let mut deferred = Vec::new();
+    for conjunct in subquery_conjuncts {
+        match subquery::semi_join(&conjunct, &plan, &scope, ctx)? {
+            Some((right, method, condition)) => {
+                debug!(join_type = ?condition.join_type, method = ?method, "plan: subquery as join");
+                plan = Operator::Join {
+                    left: Box::new(plan),
+                    right: Box::new(right),
+                    method,
+                    condition,
                 };
             }
+            None => {
+                let mut conjunct = conjunct;
+                subquery::extract(&mut conjunct, &scope, ctx, &mut subplans, &mut subquery_count)?;
+                deferred.push(conjunct);
+            }
         }
+    }
+    if !subplans.is_empty() {
+        debug!(subqueries = subplans.len(), "plan: adding apply");
+        plan = Operator::Apply {
+            input: Box::new(plan),
+            subplans: std::mem::take(&mut subplans),
+        };
+    }
+    if let Some(predicate) = join::conjunction(deferred) {
+        debug!("plan: adding filter");
+        plan = Operator::Filter {
+            input: Box::new(plan),
+            predicate,
+        };
+    }
 
-        // Add projection (SELECT columns)
-        if !select.projection.is_empty() {
-            let columns = select
-                .projection
-                .iter()
-                .map(|item| match item {
-                    sqlparser::ast::SelectItem::UnnamedExpr(expr) => expr.clone(),
-                    sqlparser::ast::SelectItem::ExprWithAlias { expr, .. } => expr.clone(),
-                    sqlparser::ast::SelectItem::QualifiedWildcard(_, _) => {
-                        // Placeholder for wildcard - will expand columns during execution
-                        sqlparser::ast::Expr::Identifier(sqlparser::ast::Ident::new("*"))
-                    }
-                    sqlparser::ast::SelectItem::Wildcard(_) => {
-                        sqlparser::ast::Expr::Identifier(sqlparser::ast::Ident::new("*"))
+    // Add projection (SELECT columns)
+    let mut columns = Vec::with_capacity(select.projection.len());
+    let mut names = Vec::with_capacity(select.projection.len());
+    let mut aliases = Vec::new();
+    for item in &select.projection {
+        let (expr, name) = match item {
+            sqlparser::ast::SelectItem::UnnamedExpr(expr) => (expr.clone(), column_name(expr)),
+            sqlparser::ast::SelectItem::ExprWithAlias { expr, alias } => {
+                aliases.push((alias.value.clone(), expr.clone()));
+                (expr.clone(), alias.value.clone())
+            }
+            sqlparser::ast::SelectItem::Wildcard(_) => {
+                let Some(from) = &from else {
+                    return Err(ExecutorError::Plan(
+                        "SELECT * with no tables specified is not valid".to_string(),
+                    ));
+                };
+                for (expr, name) in from.wildcard.iter().cloned() {
+                    columns.push(expr);
+                    names.push(name);
+                }
+                continue;
+            }
+            sqlparser::ast::SelectItem::QualifiedWildcard(kind, _) => {
+                let qualifier = match kind {
+                    sqlparser::ast::SelectItemQualifiedWildcardKind::ObjectName(name) => name.0.iter()
+                        .filter_map(|part| part.as_ident())
+                        .map(|ident| ident.value.clone())
+                        .next_back()
+                        .unwrap_or_default(),
+                    sqlparser::ast::SelectItemQualifiedWildcardKind::Expr(expr) => {
+                        return Err(ExecutorError::UnsupportedStatement(format!(
+                            "Unsupported wildcard: {}.*",
+                            expr
+                        )));
                     }
+                };
+                let Some(from) = &from else {
+                    return Err(ExecutorError::Plan(format!(
+                        "missing FROM-clause entry for table \"{}\"",
+                        qualifier
+                    )));
+                };
+                for (expr, name) in from.qualified_wildcard(&qualifier)? {
+                    columns.push(expr);
+                    names.push(name);
+                }
+                continue;
+            }
+        };
+        columns.push(expr);
+        names.push(name);
+    }
+
+    // Add aggregation if the query groups rows or calls aggregate functions
+    let group_by = match &select.group_by {
+        sqlparser::ast::GroupByExpr::Expressions(exprs, modifiers) if modifiers.is_empty() => exprs.iter()
+            .map(|expr| resolve_group_by(expr, &columns))
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => {
+            return Err(ExecutorError::UnsupportedStatement(format!(
+                "Unsupported GROUP BY clause: {}",
+                select.group_by
+            )));
+        }
+    };
+    if group_by.iter().any(window::contains_window) {
+        return Err(ExecutorError::Plan(
+            "window functions are not allowed in GROUP BY".to_string(),
+        ));
+    }
+    let mut sort_keys = match order_by {
+        Some(order_by) => match &order_by.kind {
+            sqlparser::ast::OrderByKind::Expressions(exprs) => exprs.iter()
+                .map(|order_expr| resolve_sort_key(order_expr, &columns, &aliases))
+                .collect::<Result<Vec<_>, _>>()?,
+            sqlparser::ast::OrderByKind::All(_) => {
+                return Err(ExecutorError::UnsupportedStatement(
+                    "ORDER BY ALL not supported".to_string(),
+                ));
+            }
+        },
+        None => Vec::new(),
+    };
+
+    // DISTINCT ON keys name or number select items like ORDER BY keys
+    let mut distinct_on = None;
+    match &select.distinct {
+        None => {}
+        Some(sqlparser::ast::Distinct::Distinct) if sort_keys.iter().any(|key| !columns.contains(&key.expr)) => {
+            return Err(ExecutorError::Plan(
+                "for SELECT DISTINCT, ORDER BY expressions must appear in select list".to_string(),
+            ));
+        }
+        Some(sqlparser::ast::Distinct::Distinct) => {}
+        Some(sqlparser::ast::Distinct::On(exprs)) => {
+            let keys = exprs.iter()
+                .map(|expr| {
+                    let order_expr = sqlparser::ast::OrderByExpr {
+                        expr: expr.clone(),
+                        options: sqlparser::ast::OrderByOptions { asc: None, nulls_first: None },
+                        with_fill: None,
+                    };
+                    resolve_sort_key(&order_expr, &columns, &aliases).map(|key| key.expr)
                 })
-                .collect::<Vec<_>>();
-            debug!(column_count = columns.len(), "plan: adding projection");
-            plan = Operator::Project {
+                .collect::<Result<Vec<_>, _>>()?;
+            // ORDER BY must lead with the DISTINCT ON keys, in any order
+            let mut pending = keys.clone();
+            for key in &sort_keys {
+                if pending.is_empty() {
+                    break;
+                }
+                let Some(idx) = pending.iter().position(|expr| *expr == key.expr) else {
+                    return Err(ExecutorError::Plan(
+                        "SELECT DISTINCT ON expressions must match initial ORDER BY expressions".to_string(),
+                    ));
+                };
+                pending.swap_remove(idx);
+            }
+            distinct_on = Some(keys);
+        }
+    }
+
+    let is_aggregate = !group_by.is_empty()
+        || select.having.is_some()
+        || columns.iter().any(aggregate::contains_aggregate);
+    let mut having = select.having.clone();
+    let mut aggregates = Vec::new();
+    if is_aggregate {
+        // Expressions above the Aggregate read its output columns instead
+        having = having.as_ref()
+            .map(|having| aggregate::rewrite(having, &group_by, &mut aggregates))
+            .transpose()?;
+        columns = columns.iter()
+            .map(|expr| aggregate::rewrite(expr, &group_by, &mut aggregates))
+            .collect::<Result<Vec<_>, _>>()?;
+        for key in &mut sort_keys {
+            key.expr = aggregate::rewrite(&key.expr, &group_by, &mut aggregates)?;
+        }
+        for expr in distinct_on.iter_mut().flatten() {
+            *expr = aggregate::rewrite(expr, &group_by, &mut aggregates)?;
+        }
+    }
+
+    // Subqueries in the select list, HAVING and ORDER BY
+    for expr in columns.iter_mut()
+        .chain(having.iter_mut())
+        .chain(sort_keys.iter_mut().map(|key| &mut key.expr))
+    {
+        subquery::extract(expr, &scope, ctx, &mut subplans, &mut subquery_count)?;
+    }
+
+    // Window functions in the select list and ORDER BY
+    let mut windows = Vec::new();
+    let mut window_count = 0;
+    for expr in columns.iter_mut().chain(sort_keys.iter_mut().map(|key| &mut key.expr)) {
+        window::extract(expr, &select.named_window, &mut windows, &mut window_count)?;
+    }
+
+    if is_aggregate {
+        // Windows partition and order the Aggregate output
+        for window in &mut windows {
+            for expr in window.partition_by.iter_mut().chain(window.order_by.iter_mut().map(|key| &mut key.expr)) {
+                *expr = aggregate::rewrite(expr, &group_by, &mut aggregates)?;
+            }
+        }
+
+        // Outer references of subqueries are read from the Aggregate output too
+        for param in subplans.iter_mut().flat_map(|subplan| subplan.params.iter_mut()) {
+            *param = aggregate::rewrite(param, &group_by, &mut aggregates)?;
+        }
+
+        debug!(group_by = group_by.len(), aggregates = aggregates.len(), "plan: adding aggregate");
+        plan = Operator::Aggregate {
+            input: Box::new(plan),
+            group_by,
+            aggregates,
+        };
+    }
+
+    if !subplans.is_empty() {
+        debug!(subqueries = subplans.len(), "plan: adding apply");
+        plan = Operator::Apply {
+            input: Box::new(plan),
+            subplans,
+        };
+    }
+
+    if let Some(having) = having {
+        debug!("plan: adding having filter");
+        plan = Operator::Filter {
+            input: Box::new(plan),
+            predicate: having,
+        };
+    }
+
+    // Each window sorts its input; the last one's order may already satisfy ORDER BY
+    let mut window_order = Vec::new();
+    for window in windows {
+        debug!(partition_by = window.partition_by.len(), calls = window.calls.len(), "plan: adding window");
+        window_order = window.output_order();
+        plan = Operator::Window {
+            input: Box::new(plan),
+            partition_by: window.partition_by,
+            order_by: window.order_by,
+            calls: window.calls,
+        };
+    }
+
+    // Sort below the projection so keys can reference any input column
+    if !sort_keys.is_empty() && !window_order.starts_with(&sort_keys) {
+        // LIMIT n OFFSET m needs only the first n + m sorted rows, unless
+        // duplicates are removed after sorting
+        let sort_limit = limit
+            .filter(|_| select.distinct.is_none())
+            .map(|(limit, offset)| limit.saturating_add(offset.unwrap_or(0)));
+        if join::index_order(&mut plan, &sort_keys, sort_limit, ctx) {
+            debug!("plan: rows read in index order");
+        } else {
+            debug!(keys = sort_keys.len(), limit = ?sort_limit, "plan: adding sort");
+            plan = Operator::Sort {
                 input: Box::new(plan),
-                columns,
+                keys: sort_keys,
+                limit: sort_limit,
             };
         }
+    }
 
-        // Add LIMIT if present
-        if let Some(limit_clause) = &query.limit_clause {
-            if let sqlparser::ast::LimitClause::LimitOffset { limit: Some(limit_expr), offset, .. } = limit_clause {
-                // Extract limit value from expression
-                if let sqlparser::ast::Expr::Value(val) = limit_expr {
-                    if let sqlparser::ast::Value::Number(num_str, _) = &val.value {
-                        if let Ok(limit_val) = num_str.parse::<u64>() {
-                            let offset_val = if let Some(off) = offset {
-                                match off {
-                                    sqlparser::ast::Offset { value: sqlparser::ast::Expr::Value(v), .. } => {
-                                        if let sqlparser::ast::Value::Number(off_str, _) = &v.value {
-                                            off_str.parse::<u64>().ok()
-                                        } else {
-                                            None
-                                        }
-                                    }
-                                    _ => None
-                                }
-                            } else {
-                                None
-                            };
-
-                            debug!(limit = limit_val, offset = ?offset_val, "plan: adding limit");
-                            plan = Operator::Limit {
-                                input: Box::new(plan),
-                                limit: limit_val,
-                                offset: offset_val,
-                            };
-                        }
-                    }
-                }
+    // DISTINCT ON keeps the first row of each group in ORDER BY order
+    if let Some(keys) = distinct_on {
+        debug!(keys = keys.len(), "plan: adding distinct on");
+        plan = Operator::Distinct {
+            input: Box::new(plan),
+            keys,
+        };
+    }
+
+    if !columns.is_empty() {
+        debug!(column_count = columns.len(), "plan: adding projection");
+        plan = Operator::Project {
+            input: Box::new(plan),
+            columns,
+            names,
+        };
+    }
+
+    if matches!(select.distinct, Some(sqlparser::ast::Distinct::Distinct)) {
+        debug!("plan: adding distinct");
+        plan = Operator::Distinct {
+            input: Box::new(plan),
+            keys: Vec::new(),
+        };
+    }
+
+    // Add LIMIT if present
+    if let Some((limit_val, offset_val)) = limit {
+        debug!(limit = limit_val, offset = ?offset_val, "plan: adding limit");
+        plan = Operator::Limit {
+            input: Box::new(plan),
+            limit: limit_val,
+            offset: offset_val,
+        };
+    }
+
+    Ok(plan)
+}
+
+/// Output column names of a plan, without running it
+pub(crate) fn output_names(plan: &Operator, db: &Database) -> Result<Vec<String>, ExecutorError> {
+    Ok(output_schema(plan, db)?.columns.into_iter().map(|column| column.name).collect())
+}
+
+/// Output columns of a plan, named and typed as the rows it returns, without
+/// running it
+///
+/// Types that depend on values not known yet, such as the columns of a
+/// recursive query's working table, are `DataType::Null`.
+pub(crate) fn output_schema(plan: &Operator, db: &Database) -> Result<Schema, ExecutorError> {
+    use crate::executor::{aggregate as aggregate_exec, evaluator, set_operation, window as window_exec};
+
+    let column = |name: String, data_type: DataType| Column { name, data_type, is_primary_key: false };
+
+    match plan {
+        Operator::TableScan { table, .. } if table == "__constant__" => Ok(Schema::new(Vec::new())),
+        Operator::TableScan { table, alias }
+        | Operator::IndexScan { table, alias, .. }
+        | Operator::IndexRangeScan { table, alias, .. }
+        | Operator::BitmapHeapScan { table, alias, .. } => {
+            let schema = db.get_schema(table).map_err(ExecutorError::Plan)?;
+            Ok(match alias {
+                Some(alias) => schema.qualified(alias),
+                None => schema,
+            })
+        }
+        // Bitmaps hold pointers, not rows
+        Operator::BitmapIndexScan { .. } | Operator::BitmapAnd { .. } | Operator::BitmapOr { .. } => {
+            Ok(Schema::new(Vec::new()))
+        }
+        Operator::Filter { input, .. }
+        | Operator::Sort { input, .. }
+        | Operator::Limit { input, .. }
+        | Operator::Distinct { input, .. }
+        | Operator::Instrument { input, .. } => output_schema(input, db),
+        Operator::Project { input, columns, names } => {
+            let input = output_schema(input, db)?;
+            Ok(Schema::new(columns.iter()
+                .zip(names)
+                .map(|(expr, name)| column(name.clone(), evaluator::infer_type(expr, &input, &db.registries)))
+                .collect()))
+        }
+        Operator::Window { input, calls, .. } => {
+            let input = output_schema(input, db)?;
+            let mut columns = input.columns.clone();
+            columns.extend(calls.iter()
+                .map(|call| column(call.output_name.clone(), window_exec::result_type(call, &input, &db.registries))));
+            Ok(Schema::new(columns))
+        }
+        Operator::Join { left, condition, .. } if matches!(condition.join_type, JoinType::Semi | JoinType::Anti) => {
+            output_schema(left, db)
+        }
+        Operator::Join { left, right, condition, .. } => {
+            join::output_schema(&output_schema(left, db)?, &output_schema(right, db)?, &condition.using)
+        }
+        Operator::Aggregate { input, group_by, aggregates } => {
+            let input = output_schema(input, db)?;
+            Ok(Schema::new(group_by.iter()
+                .map(|expr| column(aggregate::output_name(expr), evaluator::infer_type(expr, &input, &db.registries)))
+                .chain(aggregates.iter()
+                    .map(|call| column(call.output_name.clone(), aggregate_exec::result_type(call, &input, &db.registries))))
+                .collect()))
+        }
+        Operator::SubqueryScan { input, alias, columns } => {
+            let input = output_schema(input, db)?;
+            Ok(Schema::new(columns.iter()
+                .zip(input.columns)
+                .map(|(name, inner)| column(format!("{}.{}", alias, name), inner.data_type))
+                .collect()))
+        }
+        Operator::Apply { input, subplans } => {
+            let mut columns = output_schema(input, db)?.columns;
+            for subplan in subplans {
+                let data_type = match subplan.kind {
+                    subquery::SubqueryKind::Scalar => output_schema(&subplan.plan, db)?.columns.into_iter()
+                        .next()
+                        .map(|column| column.data_type)
+                        .unwrap_or(DataType::Null),
+                    subquery::SubqueryKind::Exists { .. } | subquery::SubqueryKind::In { .. } => DataType::Bool,
+                };
+                columns.push(column(subplan.output_name.clone(), data_type));
             }
+            Ok(Schema::new(columns))
+        }
+        Operator::RecursiveUnion { anchor, .. } => output_schema(anchor, db),
+        Operator::SetOperation { op, left, right, .. } => {
+            set_operation::common_schema(*op, output_schema(left, db)?, &output_schema(right, db)?, &db.registries)
         }
+        Operator::WorkTableScan { columns, .. } => Ok(Schema::new(columns.iter()
+            .map(|name| column(name.clone(), DataType::Null))
+            .collect())),
+        Operator::Values { schema, .. } => Ok(schema.clone()),
+    }
+}
 
-        Ok(plan)
-    } else {
-        Err(ExecutorError::UnsupportedStatement(
-            "Only SELECT queries supported".to_string(),
-        ))
+/// Check that the functions and operators a plan calls exist for the types
+/// of their arguments, so a bad call fails before any row is read
+fn check_calls(plan: &mut Operator, db: &Database) -> Result<(), ExecutorError> {
+    use crate::executor::evaluator;
+
+    fn check_expr(expr: &mut sqlparser::ast::Expr, schema: &Schema, db: &Database) -> Result<(), ExecutorError> {
+        for child in children_mut(expr) {
+            check_expr(child, schema, db)?;
+        }
+        evaluator::check_call(expr, schema, &db.registries)
+    }
+
+    // Expressions of an operator see the columns of its inputs
+    let columns = plan.children().into_iter()
+        .map(|input| output_schema(input, db).map(|schema| schema.columns))
+        .collect::<Result<Vec<_>, _>>()?;
+    let schema = Schema::new(columns.into_iter().flatten().collect());
+    for expr in plan.exprs_mut() {
+        check_expr(expr, &schema, db)?;
+    }
+
+    if let Operator::Apply { subplans, .. } = plan {
+        for subplan in subplans.iter_mut() {
+            check_calls(&mut subplan.plan, db)?;
+        }
+    }
+    for input in plan.children_mut() {
+        check_calls(input, db)?;
+    }
+    Ok(())
+}
+
+/// Extract a constant `LIMIT n [OFFSET m]` clause
+///
+/// A `$n` placeholder cannot be planned before its value is bound.
+fn extract_limit(query: &sqlparser::ast::Query) -> Result<Option<(u64, Option<u64>)>, ExecutorError> {
+    let Some(sqlparser::ast::LimitClause::LimitOffset { limit: Some(limit_expr), offset, .. }) = &query.limit_clause else {
+        return Ok(None);
+    };
+    let offset_expr = offset.as_ref().map(|offset| &offset.value);
+    if [Some(limit_expr), offset_expr].into_iter().flatten().any(|expr| params::placeholder_index(expr).is_some()) {
+        return Err(ExecutorError::Plan("LIMIT and OFFSET parameters must be bound before planning".to_string()));
+    }
+
+    // Extract limit value from expression
+    let sqlparser::ast::Expr::Value(val) = limit_expr else {
+        return Ok(None);
+    };
+    let sqlparser::ast::Value::Number(num_str, _) = &val.value else {
+        return Ok(None);
+    };
+    let Ok(limit_val) = num_str.parse::<u64>() else {
+        return Ok(None);
+    };
+
+    let offset_val = match offset_expr {
+        Some(sqlparser::ast::Expr::Value(v)) => {
+            if let sqlparser::ast::Value::Number(off_str, _) = &v.value {
+                off_str.parse::<u64>().ok()
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+
+    Ok(Some((limit_val, offset_val)))
+}
+
+/// Resolve an ORDER BY item against the select list
+/// Output column positions (`ORDER BY 1`) and aliases refer to SELECT expressions
+fn resolve_sort_key(
+    order_expr: &sqlparser::ast::OrderByExpr,
+    columns: &[sqlparser::ast::Expr],
+    aliases: &[(String, sqlparser::ast::Expr)],
+) -> Result<SortKey, ExecutorError> {
+    let expr = match &order_expr.expr {
+        sqlparser::ast::Expr::Value(sqlparser::ast::ValueWithSpan { value: sqlparser::ast::Value::Number(num, _), .. }) => {
+            let position = num.parse::<usize>().ok()
+                .filter(|position| (1..=columns.len()).contains(position))
+                .ok_or_else(|| ExecutorError::Plan(format!(
+                    "ORDER BY position {} is not in select list",
+                    num
+                )))?;
+            columns[position - 1].clone()
+        }
+        sqlparser::ast::Expr::Identifier(ident) => aliases.iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(&ident.value))
+            .map(|(_, expr)| expr.clone())
+            .unwrap_or_else(|| order_expr.expr.clone()),
+        expr => expr.clone(),
+    };
+
+    if order_expr.with_fill.is_some() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "ORDER BY ... WITH FILL not supported".to_string(),
+        ));
+    }
+
+    let descending = order_expr.options.asc == Some(false);
+    Ok(SortKey {
+        expr,
+        descending,
+        nulls_first: order_expr.options.nulls_first.unwrap_or(descending),
+    })
+}
+
+/// Output column name of an unaliased SELECT expression (Postgres rules)
+fn column_name(expr: &sqlparser::ast::Expr) -> String {
+    match expr {
+        sqlparser::ast::Expr::Identifier(ident) => ident.value.clone(),
+        sqlparser::ast::Expr::CompoundIdentifier(idents) => idents.last()
+            .map(|ident| ident.value.clone())
+            .unwrap_or_else(|| "?column?".to_string()),
+        sqlparser::ast::Expr::Function(function) => function.name.0.last()
+            .and_then(|part| part.as_ident())
+            .map(|ident| ident.value.to_ascii_lowercase())
+            .unwrap_or_else(|| "?column?".to_string()),
+        sqlparser::ast::Expr::Cast { expr, .. } | sqlparser::ast::Expr::Nested(expr) => column_name(expr),
+        // A scalar subquery is named after its only column
+        sqlparser::ast::Expr::Subquery(query) => match &*query.body {
+            sqlparser::ast::SetExpr::Select(select) => match select.projection.first() {
+                Some(sqlparser::ast::SelectItem::UnnamedExpr(expr)) => column_name(expr),
+                Some(sqlparser::ast::SelectItem::ExprWithAlias { alias, .. }) => alias.value.clone(),
+                _ => "?column?".to_string(),
+            },
+            _ => "?column?".to_string(),
+        },
+        sqlparser::ast::Expr::Exists { .. } => "exists".to_string(),
+        sqlparser::ast::Expr::Case { .. } => "case".to_string(),
+        _ => "?column?".to_string(),
     }
 }
 
+/// Resolve a GROUP BY item, replacing output column positions (`GROUP BY 1`)
+/// with the corresponding SELECT expression
+fn resolve_group_by(expr: &sqlparser::ast::Expr, columns: &[sqlparser::ast::Expr]) -> Result<sqlparser::ast::Expr, ExecutorError> {
+    if let sqlparser::ast::Expr::Value(val) = expr
+        && let sqlparser::ast::Value::Number(num, _) = &val.value
+    {
+        let position = num.parse::<usize>().ok()
+            .filter(|position| (1..=columns.len()).contains(position))
+            .ok_or_else(|| ExecutorError::Plan(format!(
+                "GROUP BY position {} is not in select list",
+                num
+            )))?;
+        return Ok(columns[position - 1].clone());
+    }
+    Ok(expr.clone())
+}
+
 fn extract_table_name(table_with_joins: &sqlparser::ast::TableWithJoins) -> Result<String, ExecutorError> {
     match &table_with_joins.relation {
         sqlparser::ast::TableFactor::Table { name, .. } => {
@@ -334,6 +1295,289 @@ pub fn extract_insert(stmt: &Insert) -> Result<(String, Vec<Vec<sqlparser::ast::
     Ok((table_name, rows))
 }
 
+/// SET target column and the expression assigned to it
+pub type Assignment = (String, sqlparser::ast::Expr);
+
+pub fn extract_update(stmt: &Statement) -> Result<(String, Vec<Assignment>, Option<sqlparser::ast::Expr>), ExecutorError> {
+    debug!("extracting update statement");
+
+    let Statement::Update { table, assignments, from, selection, returning, limit, .. } = stmt else {
+        return Err(ExecutorError::Execution("Expected UPDATE statement".to_string()));
+    };
+
+    if from.is_some() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "UPDATE ... FROM not yet supported".to_string(),
+        ));
+    }
+    if returning.is_some() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "UPDATE ... RETURNING not yet supported".to_string(),
+        ));
+    }
+    if limit.is_some() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "UPDATE ... LIMIT not supported".to_string(),
+        ));
+    }
+    if !table.joins.is_empty() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "UPDATE with joins not supported".to_string(),
+        ));
+    }
+
+    let table_name = extract_table_name(table)?;
+    if table_name.is_empty() {
+        return Err(ExecutorError::Execution("Table name is empty".to_string()));
+    }
+
+    debug!(table = %table_name, "extracting assignments");
+
+    // Extract SET column = expr pairs
+    let mut columns = Vec::new();
+    for assignment in assignments {
+        let column_name = match &assignment.target {
+            sqlparser::ast::AssignmentTarget::ColumnName(name) => name.0.iter()
+                .filter_map(|part| part.as_ident())
+                .map(|ident| ident.value.clone())
+                .next_back()
+                .ok_or_else(|| ExecutorError::Execution("Assignment column is empty".to_string()))?,
+            sqlparser::ast::AssignmentTarget::Tuple(_) => {
+                return Err(ExecutorError::UnsupportedStatement(
+                    "Tuple assignments in UPDATE not yet supported".to_string(),
+                ));
+            }
+        };
+
+        if columns.iter().any(|(name, _): &Assignment| name.eq_ignore_ascii_case(&column_name)) {
+            return Err(ExecutorError::Execution(format!(
+                "Multiple assignments to same column \"{}\"",
+                column_name
+            )));
+        }
+        columns.push((column_name, assignment.value.clone()));
+    }
+
+    if columns.is_empty() {
+        return Err(ExecutorError::Execution("UPDATE requires at least one assignment".to_string()));
+    }
+
+    Ok((table_name, columns, selection.clone()))
+}
+
+pub fn extract_delete(stmt: &Statement) -> Result<(String, Option<sqlparser::ast::Expr>), ExecutorError> {
+    debug!("extracting delete statement");
+
+    let Statement::Delete(delete) = stmt else {
+        return Err(ExecutorError::Execution("Expected DELETE statement".to_string()));
+    };
+
+    if delete.using.is_some() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "DELETE ... USING not yet supported".to_string(),
+        ));
+    }
+    if delete.returning.is_some() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "DELETE ... RETURNING not yet supported".to_string(),
+        ));
+    }
+    if !delete.order_by.is_empty() || delete.limit.is_some() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "DELETE ... ORDER BY/LIMIT not supported".to_string(),
+        ));
+    }
+
+    let from = match &delete.from {
+        sqlparser::ast::FromTable::WithFromKeyword(tables) => tables,
+        sqlparser::ast::FromTable::WithoutKeyword(tables) => tables,
+    };
+    let [table] = from.as_slice() else {
+        return Err(ExecutorError::UnsupportedStatement(
+            "DELETE from multiple tables not supported".to_string(),
+        ));
+    };
+    if !table.joins.is_empty() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "DELETE with joins not supported".to_string(),
+        ));
+    }
+
+    let table_name = extract_table_name(table)?;
+    if table_name.is_empty() {
+        return Err(ExecutorError::Execution("Table name is empty".to_string()));
+    }
+
+    Ok((table_name, delete.selection.clone()))
+}
+
+pub fn extract_truncate(stmt: &Statement) -> Result<Vec<String>, ExecutorError> {
+    debug!("extracting truncate statement");
+
+    let Statement::Truncate { table_names, partitions, .. } = stmt else {
+        return Err(ExecutorError::Execution("Expected TRUNCATE statement".to_string()));
+    };
+
+    if partitions.is_some() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "TRUNCATE ... PARTITION not supported".to_string(),
+        ));
+    }
+
+    let tables = table_names.iter()
+        .map(|target| target.name.0.iter()
+            .filter_map(|part| part.as_ident())
+            .map(|ident| ident.value.clone())
+            .collect::<Vec<_>>()
+            .join("."))
+        .collect::<Vec<_>>();
+
+    if tables.iter().any(|name| name.is_empty()) {
+        return Err(ExecutorError::Execution("Table name is empty".to_string()));
+    }
+
+    Ok(tables)
+}
+
+/// Extract `ANALYZE table [FOR COLUMNS a, b, ...]` as a table name and the
+/// columns to analyze (empty for all of them)
+pub fn extract_analyze(stmt: &Statement) -> Result<(String, Vec<String>), ExecutorError> {
+    debug!("extracting analyze statement");
+
+    let Statement::Analyze { table_name, partitions, columns, cache_metadata, noscan, .. } = stmt else {
+        return Err(ExecutorError::Execution("Expected ANALYZE statement".to_string()));
+    };
+
+    if partitions.is_some() || *cache_metadata || *noscan {
+        return Err(ExecutorError::UnsupportedStatement(
+            "ANALYZE options other than FOR COLUMNS not supported".to_string(),
+        ));
+    }
+
+    let table = table_name.0.iter()
+        .filter_map(|part| part.as_ident())
+        .map(|ident| ident.value.clone())
+        .collect::<Vec<_>>()
+        .join(".");
+    if table.is_empty() {
+        return Err(ExecutorError::Execution("Table name is empty".to_string()));
+    }
+
+    Ok((table, columns.iter().map(|ident| ident.value.clone()).collect()))
+}
+
+/// Extract `EXPLAIN [ANALYZE] [VERBOSE] [FORMAT TEXT|JSON] statement` or
+/// `EXPLAIN (option [value], ...) statement` as its options and the statement
+/// to explain
+pub fn extract_explain(stmt: &Statement) -> Result<(explain::ExplainOptions, &Statement), ExecutorError> {
+    use sqlparser::ast::{AnalyzeFormat, AnalyzeFormatKind, Expr};
+    use explain::ExplainFormat;
+
+    debug!("extracting explain statement");
+
+    let Statement::Explain { analyze, verbose, query_plan, estimate, statement, format, options, .. } = stmt else {
+        return Err(ExecutorError::Execution("Expected EXPLAIN statement".to_string()));
+    };
+    if *query_plan || *estimate {
+        return Err(ExecutorError::UnsupportedStatement(
+            "EXPLAIN QUERY PLAN and EXPLAIN ESTIMATE not supported".to_string(),
+        ));
+    }
+
+    let mut explain = explain::ExplainOptions {
+        analyze: *analyze,
+        verbose: *verbose,
+        format: ExplainFormat::Text,
+    };
+    if let Some(AnalyzeFormatKind::Keyword(format) | AnalyzeFormatKind::Assignment(format)) = format {
+        explain.format = match format {
+            AnalyzeFormat::TEXT => ExplainFormat::Text,
+            AnalyzeFormat::JSON => ExplainFormat::Json,
+            other => {
+                return Err(ExecutorError::UnsupportedStatement(format!(
+                    "EXPLAIN format {} not supported",
+                    other
+                )));
+            }
+        };
+    }
+
+    for option in options.iter().flatten() {
+        let name = option.name.value.to_ascii_lowercase();
+        let value = option.arg.as_ref().map(|arg| match arg {
+            Expr::Identifier(ident) => ident.value.to_ascii_lowercase(),
+            Expr::Value(value) => match &value.value {
+                sqlparser::ast::Value::SingleQuotedString(text) => text.to_ascii_lowercase(),
+                value => value.to_string().to_ascii_lowercase(),
+            },
+            arg => arg.to_string(),
+        });
+        let flag = |value: Option<String>| match value.as_deref() {
+            None | Some("true" | "on" | "1") => Ok(true),
+            Some("false" | "off" | "0") => Ok(false),
+            Some(_) => Err(ExecutorError::Plan(format!(
+                "EXPLAIN option \"{}\" requires a Boolean value",
+                name
+            ))),
+        };
+        match name.as_str() {
+            "analyze" => explain.analyze = flag(value)?,
+            "verbose" => explain.verbose = flag(value)?,
+            "format" => {
+                explain.format = match value.as_deref() {
+                    Some("text") => ExplainFormat::Text,
+                    Some("json") => ExplainFormat::Json,
+                    value => {
+                        return Err(ExecutorError::Plan(format!(
+                            "unrecognized value for EXPLAIN option \"format\": \"{}\"",
+                            value.unwrap_or_default()
+                        )));
+                    }
+                };
+            }
+            _ => {
+                return Err(ExecutorError::Plan(format!(
+                    "unrecognized EXPLAIN option \"{}\"",
+                    name
+                )));
+            }
+        }
+    }
+
+    Ok((explain, statement))
+}
+
+/// Extract `SET name = value` as a lowercase parameter name and its value text
+pub fn extract_set(set: &sqlparser::ast::Set) -> Result<(String, String), ExecutorError> {
+    let sqlparser::ast::Set::SingleAssignment { variable, values, .. } = set else {
+        return Err(ExecutorError::UnsupportedStatement(format!(
+            "Unsupported SET statement: {}",
+            set
+        )));
+    };
+
+    let name = variable.0.iter()
+        .filter_map(|part| part.as_ident())
+        .map(|ident| ident.value.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join(".");
+
+    let [value] = values.as_slice() else {
+        return Err(ExecutorError::Execution(format!("SET {} takes only one argument", name)));
+    };
+    let value = match value {
+        sqlparser::ast::Expr::Value(val) => match &val.value {
+            sqlparser::ast::Value::Number(n, _) => n.clone(),
+            sqlparser::ast::Value::SingleQuotedString(s) => s.clone(),
+            other => other.to_string(),
+        },
+        sqlparser::ast::Expr::Identifier(ident) => ident.value.clone(),
+        other => other.to_string(),
+    };
+
+    Ok((name, value))
+}
+
 pub fn extract_create_index(stmt: &CreateIndex) -> Result<(String, String, String), ExecutorError> {
     debug!("extracting create index");
 
@@ -412,37 +1656,21 @@ pub fn extract_create_index(stmt: &CreateIndex) -> Result<(String, String, Strin
 fn sql_type_to_data_type(data_type: &sqlparser::ast::DataType) -> Result<DataType, ExecutorError> {
     use sqlparser::ast::DataType as SqlDataType;
 
-    match data_type {
-        SqlDataType::Int(_)
-        | SqlDataType::BigInt(_)
-        | SqlDataType::SmallInt(_)
-        | SqlDataType::Integer(_) => Ok(DataType::Int),
-
-        SqlDataType::Float(_)
-        | SqlDataType::Real
-        | SqlDataType::Double(_)
-        | SqlDataType::Numeric(_)
-        | SqlDataType::Decimal(_) => Ok(DataType::Float),
-
-        SqlDataType::Varchar(_)
-        | SqlDataType::Char(_)
-        | SqlDataType::Text
-        | SqlDataType::String(_) => Ok(DataType::String),
-
-        SqlDataType::Boolean => Ok(DataType::Bool),
-        _ => {
-            debug!(data_type = ?data_type, "unsupported data type");
-            Err(ExecutorError::UnsupportedStatement(format!(
-                "Unsupported data type: {:?}",
-                data_type
-            )))
-        }
+    if let SqlDataType::Numeric(info) | SqlDataType::Decimal(info) = data_type {
+        return numeric::typmod(info).map(DataType::Numeric);
     }
+    coercion::builtin_type(data_type).ok_or_else(|| {
+        debug!(data_type = ?data_type, "unsupported data type");
+        ExecutorError::UnsupportedStatement(format!(
+            "Unsupported data type: {:?}",
+            data_type
+        ))
+    })
 }
 
 /// Try to extract a simple equality predicate (col = value) from a WHERE clause
 /// Returns Some((column_name, value_expr)) if matched, None otherwise
-fn try_extract_equality(expr: &sqlparser::ast::Expr) -> Option<(String, sqlparser::ast::Expr)> {
+pub(crate) fn try_extract_equality(expr: &sqlparser::ast::Expr) -> Option<(String, sqlparser::ast::Expr)> {
     use sqlparser::ast::{BinaryOperator, Expr};
 
     match expr {
@@ -460,4 +1688,62 @@ fn try_extract_equality(expr: &sqlparser::ast::Expr) -> Option<(String, sqlparse
         }
         _ => None,
     }
-}
\ No newline at end of file
+}
+
+/// Direct subexpressions of an expression
+pub(crate) fn children_mut(expr: &mut sqlparser::ast::Expr) -> Vec<&mut sqlparser::ast::Expr> {
+    use sqlparser::ast::{Expr, FunctionArg, FunctionArgExpr, FunctionArguments};
+
+    match expr {
+        Expr::BinaryOp { left, right, .. } => vec![left.as_mut(), right.as_mut()],
+        Expr::UnaryOp { expr, .. }
+        | Expr::Nested(expr)
+        | Expr::Cast { expr, .. }
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::IsTrue(expr)
+        | Expr::IsNotTrue(expr)
+        | Expr::IsFalse(expr)
+        | Expr::IsNotFalse(expr)
+        | Expr::IsUnknown(expr)
+        | Expr::IsNotUnknown(expr)
+        | Expr::InSubquery { expr, .. } => vec![expr.as_mut()],
+        Expr::IsDistinctFrom(left, right) | Expr::IsNotDistinctFrom(left, right) => {
+            vec![left.as_mut(), right.as_mut()]
+        }
+        Expr::Between { expr, low, high, .. } => vec![expr.as_mut(), low.as_mut(), high.as_mut()],
+        Expr::InList { expr, list, .. } => {
+            let mut children = vec![expr.as_mut()];
+            children.extend(list.iter_mut());
+            children
+        }
+        Expr::Like { expr, pattern, .. }
+        | Expr::ILike { expr, pattern, .. }
+        | Expr::SimilarTo { expr, pattern, .. } => vec![expr.as_mut(), pattern.as_mut()],
+        Expr::Case { operand, conditions, else_result, .. } => {
+            let mut children = Vec::new();
+            if let Some(operand) = operand {
+                children.push(operand.as_mut());
+            }
+            for when in conditions.iter_mut() {
+                children.push(&mut when.condition);
+                children.push(&mut when.result);
+            }
+            if let Some(else_result) = else_result {
+                children.push(else_result.as_mut());
+            }
+            children
+        }
+        Expr::Function(function) => match &mut function.args {
+            FunctionArguments::List(arg_list) => arg_list.args.iter_mut()
+                .filter_map(|arg| match arg {
+                    FunctionArg::Unnamed(FunctionArgExpr::Expr(arg_expr)) => Some(arg_expr),
+                    FunctionArg::Named { arg: FunctionArgExpr::Expr(arg_expr), .. } => Some(arg_expr),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,406 @@
+//! `$n` parameters of statements prepared through the extended query protocol
+
+use sqlparser::ast::{
+    BinaryOperator, Expr, FromTable, GroupByExpr, JoinConstraint, JoinOperator, LimitClause, ObjectName,
+    OrderByKind, Query, Select, SelectItem, SetExpr, Statement, TableAlias, TableFactor, TableObject,
+    TableWithJoins, UnaryOperator, Value as SqlValue,
+};
+
+use crate::executor::coercion;
+use crate::executor::error::ExecutorError;
+use crate::executor::evaluator::infer_type;
+use crate::extensions::registry::Registries;
+use crate::planner::Operator;
+use crate::storage::Database;
+use crate::types::{Column, DataType, Schema};
+
+/// Position of a `$n` parameter placeholder (`$1` is 0)
+pub fn placeholder_index(expr: &Expr) -> Option<usize> {
+    let Expr::Value(value) = expr else {
+        return None;
+    };
+    let SqlValue::Placeholder(placeholder) = &value.value else {
+        return None;
+    };
+    placeholder.strip_prefix('$')?.parse::<usize>().ok()?.checked_sub(1)
+}
+
+/// Types of the parameters of a statement, one per `$n` up to the highest
+///
+/// A parameter takes the type of the column it is inserted into or assigned
+/// to, or of the operand it is compared or combined with; LIMIT and OFFSET
+/// take integers. Parameters without such a context are left `None`.
+pub fn parameter_types(stmt: &Statement, db: &Database) -> Vec<Option<DataType>> {
+    let mut stmt = stmt.clone();
+    let mut relations = Relations { db, columns: Vec::new() };
+    walk_statement(&mut stmt, &mut relations);
+
+    let mut inference = Inference {
+        schema: Schema::new(relations.columns),
+        registries: db.registries.clone(),
+        types: Vec::new(),
+    };
+    // Target columns decide first, over anything the expressions suggest
+    match &stmt {
+        Statement::Insert(insert) => {
+            if let Ok((table, rows)) = super::extract_insert(insert)
+                && let Ok(schema) = db.get_schema(&table)
+            {
+                for row in &rows {
+                    for (expr, column) in row.iter().zip(&schema.columns) {
+                        inference.assign(expr, &column.data_type);
+                    }
+                }
+            }
+        }
+        Statement::Update { .. } => {
+            if let Ok((table, assignments, _)) = super::extract_update(&stmt)
+                && let Ok(schema) = db.get_schema(&table)
+            {
+                for (column, expr) in &assignments {
+                    if let Some(idx) = schema.get_column_index(column) {
+                        inference.assign(expr, &schema.columns[idx].data_type);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    walk_statement(&mut stmt, &mut inference);
+    inference.types
+}
+
+/// Replace the `$n` placeholders of a statement with `values`
+pub fn bind(stmt: &mut Statement, values: &[Expr]) -> Result<(), ExecutorError> {
+    let mut binder = Binder { values, missing: None };
+    walk_statement(stmt, &mut binder);
+    match binder.missing {
+        Some(idx) => Err(ExecutorError::Plan(format!("there is no parameter ${}", idx + 1))),
+        None => Ok(()),
+    }
+}
+
+/// Replace the `$n` placeholders of a plan, subquery plans included, with `values`
+///
+/// A plan made from a statement with placeholders is generic: it runs with
+/// any values once they are bound.
+pub fn bind_plan(plan: &mut Operator, values: &[Expr]) -> Result<(), ExecutorError> {
+    let mut binder = Binder { values, missing: None };
+    bind_operator(plan, &mut binder);
+    match binder.missing {
+        Some(idx) => Err(ExecutorError::Plan(format!("there is no parameter ${}", idx + 1))),
+        None => Ok(()),
+    }
+}
+
+fn bind_operator(plan: &mut Operator, binder: &mut Binder) {
+    for expr in plan.exprs_mut() {
+        walk_expr(expr, None, binder);
+    }
+    if let Operator::Apply { subplans, .. } = plan {
+        for subplan in subplans {
+            bind_operator(&mut subplan.plan, binder);
+        }
+    }
+    for child in plan.children_mut() {
+        bind_operator(child, binder);
+    }
+}
+
+/// Receives the relations and expressions of a statement as it is walked
+trait Visitor {
+    /// Called for every expression, outermost first; `expected` is the type
+    /// its position requires, if any
+    fn expr(&mut self, expr: &mut Expr, expected: Option<&DataType>);
+
+    fn relation(&mut self, _name: &ObjectName, _alias: Option<&TableAlias>) {}
+}
+
+/// Collects the columns of every table a statement reads or writes
+///
+/// Scopes are not kept apart: a column name is only resolved where it is
+/// unambiguous across the whole statement.
+struct Relations<'a> {
+    db: &'a Database,
+    columns: Vec<Column>,
+}
+
+impl Visitor for Relations<'_> {
+    fn expr(&mut self, _expr: &mut Expr, _expected: Option<&DataType>) {}
+
+    fn relation(&mut self, name: &ObjectName, alias: Option<&TableAlias>) {
+        let table = name.0.iter()
+            .filter_map(|part| part.as_ident())
+            .map(|ident| ident.value.clone())
+            .collect::<Vec<_>>()
+            .join(".");
+        let Ok(schema) = self.db.get_schema(&table) else {
+            return;
+        };
+        let relation = alias.map(|alias| alias.name.value.clone()).unwrap_or(table);
+        // A table read again under the same name (say in a subquery) adds nothing
+        let prefix = format!("{}.", relation);
+        if !self.columns.iter().any(|column| column.name.starts_with(&prefix)) {
+            self.columns.extend(schema.qualified(&relation).columns);
+        }
+    }
+}
+
+/// Infers parameter types from the expressions around them
+struct Inference {
+    /// Columns of the statement's relations
+    schema: Schema,
+    registries: Registries,
+    types: Vec<Option<DataType>>,
+}
+
+impl Inference {
+    /// Give `expr` the type `data_type` if it is a parameter without one
+    fn assign(&mut self, expr: &Expr, data_type: &DataType) {
+        let expr = unnested(expr);
+        let Some(idx) = placeholder_index(expr) else {
+            return;
+        };
+        if self.types.len() <= idx {
+            self.types.resize(idx + 1, None);
+        }
+        if self.types[idx].is_none() && *data_type != DataType::Null {
+            self.types[idx] = Some(data_type.clone());
+        }
+    }
+
+    /// Type of the first of `exprs` whose type is known
+    fn known_type<'e>(&self, exprs: impl IntoIterator<Item = &'e Expr>) -> DataType {
+        exprs.into_iter()
+            .map(|expr| infer_type(expr, &self.schema, &self.registries))
+            .find(|data_type| *data_type != DataType::Null)
+            .unwrap_or(DataType::Null)
+    }
+}
+
+impl Visitor for Inference {
+    fn expr(&mut self, expr: &mut Expr, expected: Option<&DataType>) {
+        use BinaryOperator::*;
+
+        match expected {
+            Some(data_type) => self.assign(expr, data_type),
+            // Counted even without a type, so every parameter up to the highest is described
+            None => self.assign(expr, &DataType::Null),
+        }
+        match expr {
+            Expr::BinaryOp { left, op: And | Or, right } => {
+                self.assign(left, &DataType::Bool);
+                self.assign(right, &DataType::Bool);
+            }
+            Expr::BinaryOp { left, op: StringConcat, right } => {
+                self.assign(left, &DataType::String);
+                self.assign(right, &DataType::String);
+            }
+            Expr::BinaryOp { left, right, .. } => {
+                let left_type = infer_type(left, &self.schema, &self.registries);
+                let right_type = infer_type(right, &self.schema, &self.registries);
+                self.assign(left, &right_type);
+                self.assign(right, &left_type);
+            }
+            Expr::UnaryOp { op: UnaryOperator::Not, expr } => self.assign(expr, &DataType::Bool),
+            Expr::Between { expr, low, high, .. } => {
+                let data_type = self.known_type([&**expr, &**low, &**high]);
+                for operand in [&**expr, &**low, &**high] {
+                    self.assign(operand, &data_type);
+                }
+            }
+            Expr::InList { expr, list, .. } => {
+                let data_type = self.known_type(std::iter::once(&**expr).chain(list.iter()));
+                for operand in std::iter::once(&**expr).chain(list.iter()) {
+                    self.assign(operand, &data_type);
+                }
+            }
+            Expr::Like { expr, pattern, .. } | Expr::ILike { expr, pattern, .. } => {
+                self.assign(expr, &DataType::String);
+                self.assign(pattern, &DataType::String);
+            }
+            // $1::int reads the parameter as the cast's type
+            Expr::Cast { expr, data_type, .. } => {
+                if let Some(data_type) = coercion::builtin_type(data_type) {
+                    self.assign(expr, &data_type);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Substitutes values for parameters
+struct Binder<'a> {
+    values: &'a [Expr],
+    /// A parameter no value was given for
+    missing: Option<usize>,
+}
+
+impl Visitor for Binder<'_> {
+    fn expr(&mut self, expr: &mut Expr, _expected: Option<&DataType>) {
+        if let Some(idx) = placeholder_index(expr) {
+            match self.values.get(idx) {
+                Some(value) => *expr = value.clone(),
+                None => self.missing = Some(idx),
+            }
+        }
+    }
+}
+
+fn unnested(mut expr: &Expr) -> &Expr {
+    while let Expr::Nested(inner) = expr {
+        expr = inner;
+    }
+    expr
+}
+
+fn walk_statement(stmt: &mut Statement, visitor: &mut dyn Visitor) {
+    match stmt {
+        Statement::Query(query) => walk_query(query, visitor),
+        Statement::Insert(insert) => {
+            if let TableObject::TableName(name) = &insert.table {
+                visitor.relation(name, None);
+            }
+            if let Some(source) = &mut insert.source {
+                walk_query(source, visitor);
+            }
+        }
+        Statement::Update { table, assignments, selection, .. } => {
+            walk_table(table, visitor);
+            for assignment in assignments {
+                walk_expr(&mut assignment.value, None, visitor);
+            }
+            if let Some(selection) = selection {
+                walk_expr(selection, Some(&DataType::Bool), visitor);
+            }
+        }
+        Statement::Delete(delete) => {
+            let (FromTable::WithFromKeyword(tables) | FromTable::WithoutKeyword(tables)) = &mut delete.from;
+            for table in tables {
+                walk_table(table, visitor);
+            }
+            if let Some(selection) = &mut delete.selection {
+                walk_expr(selection, Some(&DataType::Bool), visitor);
+            }
+        }
+        Statement::Explain { statement, .. } => walk_statement(statement, visitor),
+        _ => {}
+    }
+}
+
+fn walk_query(query: &mut Query, visitor: &mut dyn Visitor) {
+    if let Some(with) = &mut query.with {
+        for cte in &mut with.cte_tables {
+            walk_query(&mut cte.query, visitor);
+        }
+    }
+    walk_set_expr(&mut query.body, visitor);
+    if let Some(order_by) = &mut query.order_by
+        && let OrderByKind::Expressions(exprs) = &mut order_by.kind
+    {
+        for order_by_expr in exprs {
+            walk_expr(&mut order_by_expr.expr, None, visitor);
+        }
+    }
+    match &mut query.limit_clause {
+        Some(LimitClause::LimitOffset { limit, offset, .. }) => {
+            if let Some(limit) = limit {
+                walk_expr(limit, Some(&DataType::Int), visitor);
+            }
+            if let Some(offset) = offset {
+                walk_expr(&mut offset.value, Some(&DataType::Int), visitor);
+            }
+        }
+        Some(LimitClause::OffsetCommaLimit { offset, limit }) => {
+            walk_expr(offset, Some(&DataType::Int), visitor);
+            walk_expr(limit, Some(&DataType::Int), visitor);
+        }
+        None => {}
+    }
+    if let Some(fetch) = &mut query.fetch
+        && let Some(quantity) = &mut fetch.quantity
+    {
+        walk_expr(quantity, Some(&DataType::Int), visitor);
+    }
+}
+
+fn walk_set_expr(body: &mut SetExpr, visitor: &mut dyn Visitor) {
+    match body {
+        SetExpr::Select(select) => walk_select(select, visitor),
+        SetExpr::Query(query) => walk_query(query, visitor),
+        SetExpr::SetOperation { left, right, .. } => {
+            walk_set_expr(left, visitor);
+            walk_set_expr(right, visitor);
+        }
+        SetExpr::Values(values) => {
+            for row in &mut values.rows {
+                for expr in row {
+                    walk_expr(expr, None, visitor);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn walk_select(select: &mut Select, visitor: &mut dyn Visitor) {
+    for table in &mut select.from {
+        walk_table(table, visitor);
+    }
+    for item in &mut select.projection {
+        if let SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } = item {
+            walk_expr(expr, None, visitor);
+        }
+    }
+    if let Some(selection) = &mut select.selection {
+        walk_expr(selection, Some(&DataType::Bool), visitor);
+    }
+    if let GroupByExpr::Expressions(exprs, _) = &mut select.group_by {
+        for expr in exprs {
+            walk_expr(expr, None, visitor);
+        }
+    }
+    for condition in [&mut select.having, &mut select.qualify].into_iter().flatten() {
+        walk_expr(condition, Some(&DataType::Bool), visitor);
+    }
+}
+
+fn walk_table(table: &mut TableWithJoins, visitor: &mut dyn Visitor) {
+    walk_factor(&mut table.relation, visitor);
+    for join in &mut table.joins {
+        walk_factor(&mut join.relation, visitor);
+        if let JoinOperator::Join(JoinConstraint::On(on))
+        | JoinOperator::Inner(JoinConstraint::On(on))
+        | JoinOperator::Left(JoinConstraint::On(on))
+        | JoinOperator::LeftOuter(JoinConstraint::On(on))
+        | JoinOperator::Right(JoinConstraint::On(on))
+        | JoinOperator::RightOuter(JoinConstraint::On(on))
+        | JoinOperator::FullOuter(JoinConstraint::On(on)) = &mut join.join_operator
+        {
+            walk_expr(on, Some(&DataType::Bool), visitor);
+        }
+    }
+}
+
+fn walk_factor(factor: &mut TableFactor, visitor: &mut dyn Visitor) {
+    match factor {
+        TableFactor::Table { name, alias, .. } => visitor.relation(name, alias.as_ref()),
+        TableFactor::Derived { subquery, .. } => walk_query(subquery, visitor),
+        TableFactor::NestedJoin { table_with_joins, .. } => walk_table(table_with_joins, visitor),
+        _ => {}
+    }
+}
+
+fn walk_expr(expr: &mut Expr, expected: Option<&DataType>, visitor: &mut dyn Visitor) {
+    visitor.expr(expr, expected);
+    match expr {
+        Expr::Subquery(query) | Expr::Exists { subquery: query, .. } | Expr::InSubquery { subquery: query, .. } => {
+            walk_query(query, visitor);
+        }
+        _ => {}
+    }
+    for child in super::children_mut(expr) {
+        walk_expr(child, None, visitor);
+    }
+}
@@ -0,0 +1,551 @@
+use std::cell::RefCell;
+
+use sqlparser::ast::{
+    BinaryOperator, Expr, GroupByExpr, Ident, OrderBy, OrderByKind, Query, Select, SelectItem, SetExpr,
+    SetOperator, SetQuantifier, TableFactor, With,
+};
+use tracing::debug;
+
+use crate::executor::error::ExecutorError;
+use crate::types::Schema;
+use super::join::{self, JoinCondition, JoinMethod, JoinType};
+use super::{aggregate, children_mut, Operator, PlanContext};
+
+/// Prefix of the placeholders standing in for outer column references
+const PARAM_PREFIX: &str = "$outer";
+
+/// What a subquery expression computes for each outer row
+#[derive(Debug, Clone)]
+pub enum SubqueryKind {
+    /// The single value returned, or NULL if no row is returned
+    Scalar,
+    /// Whether any row is returned
+    Exists { negated: bool },
+    /// Whether `expr` equals a returned value (NULL if unknown)
+    In { expr: Box<Expr>, negated: bool },
+}
+
+/// A subquery expression evaluated by `Operator::Apply`
+#[derive(Debug, Clone)]
+pub struct SubPlan {
+    pub kind: SubqueryKind,
+    pub plan: Operator,
+    /// Outer expressions bound to the `$outerN` placeholders of `plan`, in order
+    /// Empty for an uncorrelated subquery, which only needs to run once
+    pub params: Vec<Expr>,
+    /// Name of the Apply output column holding the result
+    pub output_name: String,
+}
+
+/// Columns of the enclosing query that a subquery may reference
+pub(crate) struct OuterScope {
+    schema: Schema,
+    /// Outer references found so far; the i-th is bound to `$outer{i+1}`
+    params: RefCell<Vec<Expr>>,
+}
+
+/// A WITH query visible while planning
+#[derive(Debug, Clone)]
+pub(crate) struct CommonTable {
+    name: String,
+    /// Column names listed after the query name (may be fewer than it returns)
+    columns: Vec<String>,
+    body: CteBody,
+}
+
+#[derive(Debug, Clone)]
+enum CteBody {
+    Query(Box<Query>),
+    /// `anchor UNION [ALL] recursive`, where `recursive` reads the query itself
+    Recursive {
+        anchor: Box<Query>,
+        recursive: Box<Query>,
+        distinct: bool,
+    },
+    /// Rows of the previous iteration, while planning a recursive term
+    WorkTable,
+}
+
+/// Planning context with the queries of a WITH clause added
+pub(crate) fn with_ctes<'a>(with: &With, ctx: &PlanContext<'a>) -> Result<PlanContext<'a>, ExecutorError> {
+    let mut ctes = ctx.ctes.clone();
+    for (idx, cte) in with.cte_tables.iter().enumerate() {
+        let name = cte.alias.name.value.clone();
+        if with.cte_tables[..idx].iter().any(|other| other.alias.name.value.eq_ignore_ascii_case(&name)) {
+            return Err(ExecutorError::Plan(format!(
+                "WITH query name \"{}\" specified more than once",
+                name
+            )));
+        }
+
+        let body = if with.recursive && references_table(&cte.query.body, &name) {
+            recursive_body(&cte.query, &name)?
+        } else {
+            CteBody::Query(cte.query.clone())
+        };
+        ctes.push(CommonTable {
+            columns: cte.alias.columns.iter().map(|column| column.name.value.clone()).collect(),
+            name,
+            body,
+        });
+    }
+
+    Ok(PlanContext {
+        db: ctx.db,
+        settings: ctx.settings,
+        ctes,
+        outer: ctx.outer,
+    })
+}
+
+/// Split a self-referencing WITH query into its anchor and recursive terms
+fn recursive_body(query: &Query, name: &str) -> Result<CteBody, ExecutorError> {
+    let malformed = || ExecutorError::Plan(format!(
+        "recursive query \"{}\" does not have the form non-recursive-term UNION [ALL] recursive-term",
+        name
+    ));
+    let SetExpr::SetOperation { op: SetOperator::Union, set_quantifier, left, right } = &*query.body else {
+        return Err(malformed());
+    };
+    if references_table(left, name) {
+        return Err(malformed());
+    }
+    if query.order_by.is_some() || query.limit_clause.is_some() || query.fetch.is_some() {
+        return Err(ExecutorError::UnsupportedStatement(
+            "ORDER BY/LIMIT in a recursive query not supported".to_string(),
+        ));
+    }
+
+    let distinct = match set_quantifier {
+        SetQuantifier::All => false,
+        SetQuantifier::Distinct | SetQuantifier::None => true,
+        other => {
+            return Err(ExecutorError::UnsupportedStatement(format!(
+                "UNION {} not supported",
+                other
+            )));
+        }
+    };
+    let term = |body: &SetExpr| {
+        let mut term = query.clone();
+        term.body = Box::new(body.clone());
+        term
+    };
+
+    Ok(CteBody::Recursive {
+        anchor: Box::new(term(left)),
+        recursive: Box::new(term(right)),
+        distinct,
+    })
+}
+
+/// Whether the FROM clauses of `body` read table `name`
+fn references_table(body: &SetExpr, name: &str) -> bool {
+    let factor_references = |factor: &TableFactor| match factor {
+        TableFactor::Table { name: table, .. } => matches!(
+            table.0.as_slice(),
+            [part] if part.as_ident().is_some_and(|ident| ident.value.eq_ignore_ascii_case(name))
+        ),
+        TableFactor::Derived { subquery, .. } => references_table(&subquery.body, name),
+        _ => false,
+    };
+
+    match body {
+        SetExpr::Select(select) => select.from.iter().any(|item| {
+            factor_references(&item.relation) || item.joins.iter().any(|join| factor_references(&join.relation))
+        }),
+        SetExpr::Query(query) => references_table(&query.body, name),
+        SetExpr::SetOperation { left, right, .. } => references_table(left, name) || references_table(right, name),
+        _ => false,
+    }
+}
+
+/// Plan a reference to the WITH query `name`, if one is visible
+/// Returns the plan and its output column names
+pub(crate) fn plan_cte(name: &str, ctx: &PlanContext) -> Result<Option<(Operator, Vec<String>)>, ExecutorError> {
+    let Some(position) = ctx.ctes.iter().rposition(|cte| cte.name.eq_ignore_ascii_case(name)) else {
+        return Ok(None);
+    };
+    let cte = &ctx.ctes[position];
+
+    // A WITH query sees the ones defined before it, and no outer columns
+    let scope = PlanContext {
+        db: ctx.db,
+        settings: ctx.settings,
+        ctes: ctx.ctes[..position].to_vec(),
+        outer: None,
+    };
+
+    debug!(cte = %cte.name, "plan: WITH query");
+    let (plan, columns) = match &cte.body {
+        CteBody::Query(query) => {
+            let plan = super::plan_select(query, &scope)?;
+            let columns = rename_columns(super::output_names(&plan, ctx.db)?, cte)?;
+            (plan, columns)
+        }
+        CteBody::Recursive { anchor, recursive, distinct } => {
+            let anchor = super::plan_select(anchor, &scope)?;
+            let columns = rename_columns(super::output_names(&anchor, ctx.db)?, cte)?;
+
+            let mut recursive_scope = scope;
+            recursive_scope.ctes.push(CommonTable {
+                name: cte.name.clone(),
+                columns: columns.clone(),
+                body: CteBody::WorkTable,
+            });
+            let recursive = super::plan_select(recursive, &recursive_scope)?;
+            if super::output_names(&recursive, ctx.db)?.len() != columns.len() {
+                return Err(ExecutorError::Plan(
+                    "each UNION query must have the same number of columns".to_string(),
+                ));
+            }
+
+            let plan = Operator::RecursiveUnion {
+                name: cte.name.clone(),
+                anchor: Box::new(anchor),
+                recursive: Box::new(recursive),
+                distinct: *distinct,
+            };
+            (plan, columns)
+        }
+        CteBody::WorkTable => {
+            let plan = Operator::WorkTableScan {
+                name: cte.name.clone(),
+                columns: cte.columns.clone(),
+            };
+            (plan, cte.columns.clone())
+        }
+    };
+    Ok(Some((plan, columns)))
+}
+
+/// Apply the column names listed after a WITH query's name
+fn rename_columns(mut names: Vec<String>, cte: &CommonTable) -> Result<Vec<String>, ExecutorError> {
+    if cte.columns.len() > names.len() {
+        return Err(ExecutorError::Plan(format!(
+            "WITH query \"{}\" has {} columns available but {} columns specified",
+            cte.name,
+            names.len(),
+            cte.columns.len()
+        )));
+    }
+    names[..cte.columns.len()].clone_from_slice(&cte.columns);
+    Ok(names)
+}
+
+/// Whether `expr` contains a subquery expression
+pub(crate) fn contains_subquery(expr: &Expr) -> bool {
+    match expr {
+        Expr::Subquery(_) | Expr::Exists { .. } | Expr::InSubquery { .. } => true,
+        _ => {
+            let mut expr = expr.clone();
+            children_mut(&mut expr).into_iter().any(|child| contains_subquery(child))
+        }
+    }
+}
+
+/// Replace references to the enclosing query's columns with `$outerN` placeholders
+///
+/// `inner` holds the columns of the subquery's own FROM clause, which take
+/// precedence. Nested subqueries are left alone; they bind their own scope.
+pub(crate) fn bind_outer_refs(
+    select: &Select,
+    order_by: Option<&OrderBy>,
+    inner: &Schema,
+    outer: &OuterScope,
+) -> (Select, Option<OrderBy>) {
+    let mut select = select.clone();
+    let mut order_by = order_by.cloned();
+
+    let mut exprs = Vec::new();
+    exprs.extend(select.selection.iter_mut());
+    exprs.extend(select.having.iter_mut());
+    for item in &mut select.projection {
+        if let SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } = item {
+            exprs.push(expr);
+        }
+    }
+    if let GroupByExpr::Expressions(group_by, _) = &mut select.group_by {
+        exprs.extend(group_by.iter_mut());
+    }
+    if let Some(OrderBy { kind: OrderByKind::Expressions(items), .. }) = &mut order_by {
+        exprs.extend(items.iter_mut().map(|item| &mut item.expr));
+    }
+
+    for expr in exprs {
+        bind_expr(expr, inner, outer);
+    }
+    (select, order_by)
+}
+
+fn bind_expr(expr: &mut Expr, inner: &Schema, outer: &OuterScope) {
+    let Some((qualifier, name)) = column_reference(expr) else {
+        for child in children_mut(expr) {
+            bind_expr(child, inner, outer);
+        }
+        return;
+    };
+
+    let is_outer = matches!(inner.resolve_column(qualifier, name), Ok(None))
+        && matches!(outer.schema.resolve_column(qualifier, name), Ok(Some(_)));
+    if is_outer {
+        let mut params = outer.params.borrow_mut();
+        let position = match params.iter().position(|param| param == expr) {
+            Some(position) => position,
+            None => {
+                params.push(expr.clone());
+                params.len() - 1
+            }
+        };
+        *expr = Expr::value(sqlparser::ast::Value::Placeholder(format!("{}{}", PARAM_PREFIX, position + 1)));
+    }
+}
+
+/// Index into `SubPlan::params` of a `$outerN` placeholder
+pub fn param_index(expr: &Expr) -> Option<usize> {
+    let Expr::Value(value) = expr else {
+        return None;
+    };
+    let sqlparser::ast::Value::Placeholder(placeholder) = &value.value else {
+        return None;
+    };
+    placeholder.strip_prefix(PARAM_PREFIX)?.parse::<usize>().ok()?.checked_sub(1)
+}
+
+/// Plan the subquery expressions in `expr` for an Apply
+///
+/// Each subquery is replaced by a reference to the Apply output column that
+/// will hold its result. `scope` is the schema of the Apply input, which the
+/// subqueries may reference.
+pub(crate) fn extract(
+    expr: &mut Expr,
+    scope: &Schema,
+    ctx: &PlanContext,
+    subplans: &mut Vec<SubPlan>,
+    count: &mut usize,
+) -> Result<(), ExecutorError> {
+    let (query, kind) = match expr {
+        Expr::Subquery(query) => (query, SubqueryKind::Scalar),
+        Expr::Exists { subquery, negated } => (subquery, SubqueryKind::Exists { negated: *negated }),
+        Expr::InSubquery { expr: operand, subquery, negated } => {
+            (subquery, SubqueryKind::In { expr: operand.clone(), negated: *negated })
+        }
+        _ => {
+            for child in children_mut(expr) {
+                extract(child, scope, ctx, subplans, count)?;
+            }
+            return Ok(());
+        }
+    };
+
+    let outer = OuterScope {
+        schema: scope.clone(),
+        params: RefCell::new(Vec::new()),
+    };
+    let plan = super::plan_select(query, &ctx.scoped(Some(&outer)))?;
+
+    let columns = super::output_names(&plan, ctx.db)?.len();
+    match kind {
+        SubqueryKind::Scalar if columns != 1 => {
+            return Err(ExecutorError::Plan("subquery must return only one column".to_string()));
+        }
+        SubqueryKind::In { .. } if columns > 1 => {
+            return Err(ExecutorError::Plan("subquery has too many columns".to_string()));
+        }
+        SubqueryKind::In { .. } if columns == 0 => {
+            return Err(ExecutorError::Plan("subquery has too few columns".to_string()));
+        }
+        _ => {}
+    }
+
+    *count += 1;
+    let output_name = format!("$subquery{}", count);
+    let params = outer.params.into_inner();
+    debug!(subquery = %output_name, correlated = !params.is_empty(), "plan: subquery");
+    subplans.push(SubPlan {
+        kind,
+        plan,
+        params,
+        output_name: output_name.clone(),
+    });
+    *expr = Expr::Identifier(Ident::new(output_name));
+    Ok(())
+}
+
+/// Plan a WHERE conjunct `[NOT] EXISTS (...)` or `expr IN (...)` as a semi or
+/// anti join against `left`, whose output schema is `outer`
+///
+/// Equality predicates between the subquery and the outer query become join
+/// keys, other correlated predicates the join filter. Returns None when the
+/// subquery cannot be flattened (aggregates, LIMIT, set operations, ...) and
+/// has to be evaluated per row instead.
+pub(crate) fn semi_join(
+    conjunct: &Expr,
+    left: &Operator,
+    outer: &Schema,
+    ctx: &PlanContext,
+) -> Result<Option<(Operator, JoinMethod, JoinCondition)>, ExecutorError> {
+    // NOT IN is not an anti join: a NULL in the subquery makes it unknown
+    let (query, negated, operand) = match conjunct {
+        Expr::Exists { subquery, negated } => (subquery, *negated, None),
+        Expr::InSubquery { expr, subquery, negated: false } => (subquery, false, Some(expr.as_ref())),
+        _ => return Ok(None),
+    };
+    if query.with.is_some() || query.limit_clause.is_some() || query.fetch.is_some() {
+        return Ok(None);
+    }
+    let SetExpr::Select(select) = &*query.body else {
+        return Ok(None);
+    };
+    let plain_item = |item: &SelectItem| match item {
+        SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+            !aggregate::contains_aggregate(expr) && !contains_subquery(expr)
+        }
+        _ => true,
+    };
+    let flattenable = !select.from.is_empty()
+        && select.having.is_none()
+        && matches!(&select.group_by, GroupByExpr::Expressions(exprs, modifiers) if exprs.is_empty() && modifiers.is_empty())
+        && select.projection.iter().all(plain_item)
+        && !select.selection.as_ref().is_some_and(contains_subquery);
+    if !flattenable {
+        return Ok(None);
+    }
+
+    // For IN, the subquery's only column is compared with the operand
+    let value = match operand {
+        Some(_) => match select.projection.as_slice() {
+            [SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. }] => Some(expr),
+            _ => return Ok(None),
+        },
+        None => None,
+    };
+
+    let relations = join::resolve_from(&select.from, ctx)?;
+    let inner = join::scope_schema(&relations);
+
+    // Both sides' columns must stay distinguishable once joined
+    if inner.columns.iter().any(|column| outer.get_column_index(&column.name).is_some()) {
+        return Ok(None);
+    }
+
+    let mut condition = JoinCondition {
+        join_type: if negated { JoinType::Anti } else { JoinType::Semi },
+        left_keys: Vec::new(),
+        right_keys: Vec::new(),
+        filter: None,
+        using: Vec::new(),
+    };
+    if let (Some(operand), Some(value)) = (operand, value) {
+        let empty = Schema::new(Vec::new());
+        if sides(operand, &empty, outer).is_none() || sides(value, &inner, outer) != Some((true, false)) {
+            return Ok(None);
+        }
+        condition.left_keys.push(qualify(operand, &empty, outer));
+        condition.right_keys.push(qualify(value, &inner, outer));
+    }
+
+    let mut conjuncts = Vec::new();
+    if let Some(selection) = &select.selection {
+        join::split_conjuncts(selection, &mut conjuncts);
+    }
+    let mut inner_conjuncts = Vec::new();
+    let mut filters = Vec::new();
+    for conjunct in conjuncts {
+        let Some((_, uses_outer)) = sides(&conjunct, &inner, outer) else {
+            return Ok(None);
+        };
+        if !uses_outer {
+            inner_conjuncts.push(conjunct);
+            continue;
+        }
+
+        if let Expr::BinaryOp { left: a, op: BinaryOperator::Eq, right: b } = &conjunct {
+            let (a_sides, b_sides) = (sides(a, &inner, outer), sides(b, &inner, outer));
+            let (outer_key, inner_key) = match (a_sides, b_sides) {
+                (Some((false, true)), Some((true, false))) => (Some(a), Some(b)),
+                (Some((true, false)), Some((false, true))) => (Some(b), Some(a)),
+                _ => (None, None),
+            };
+            if let (Some(outer_key), Some(inner_key)) = (outer_key, inner_key) {
+                condition.left_keys.push(qualify(outer_key, &inner, outer));
+                condition.right_keys.push(qualify(inner_key, &inner, outer));
+                continue;
+            }
+        }
+        filters.push(qualify(&conjunct, &inner, outer));
+    }
+    condition.filter = join::conjunction(filters);
+
+    let (mut right, _, selection) = join::plan_from(&select.from, relations, join::conjunction(inner_conjuncts).as_ref(), ctx)?;
+    if let Some(selection) = selection {
+        right = Operator::Filter {
+            input: Box::new(right),
+            predicate: selection,
+        };
+    }
+
+    let method = if condition.left_keys.is_empty() {
+        JoinMethod::NestedLoop
+    } else {
+        join::equi_join_method(join::plan_size(left, ctx), join::plan_size(&right, ctx), ctx)
+    };
+    Ok(Some((right, method, condition)))
+}
+
+/// Whether `expr` references columns of the subquery and of the outer query
+/// Returns None if a column resolves to neither (or is ambiguous)
+fn sides(expr: &Expr, inner: &Schema, outer: &Schema) -> Option<(bool, bool)> {
+    let mut found = (false, false);
+    let mut stack = vec![expr.clone()];
+    while let Some(mut expr) = stack.pop() {
+        let Some((qualifier, name)) = column_reference(&expr) else {
+            stack.extend(children_mut(&mut expr).into_iter().map(|child| child.clone()));
+            continue;
+        };
+        if inner.resolve_column(qualifier, name).ok()?.is_some() {
+            found.0 = true;
+        } else if outer.resolve_column(qualifier, name).ok()?.is_some() {
+            found.1 = true;
+        } else {
+            return None;
+        }
+    }
+    Some(found)
+}
+
+/// Rewrite column references to the exact names of the columns they resolve
+/// to, so they stay unambiguous over the joined schema
+fn qualify(expr: &Expr, inner: &Schema, outer: &Schema) -> Expr {
+    let mut expr = expr.clone();
+    qualify_refs(&mut expr, inner, outer);
+    expr
+}
+
+fn qualify_refs(expr: &mut Expr, inner: &Schema, outer: &Schema) {
+    let Some((qualifier, name)) = column_reference(expr) else {
+        for child in children_mut(expr) {
+            qualify_refs(child, inner, outer);
+        }
+        return;
+    };
+    let resolved = [inner, outer].into_iter().find_map(|schema| {
+        let idx = schema.resolve_column(qualifier, name).ok()??;
+        Some(schema.columns[idx].name.clone())
+    });
+    if let Some(resolved) = resolved {
+        *expr = join::column_ref(&resolved);
+    }
+}
+
+/// Qualifier and name of a column reference
+fn column_reference(expr: &Expr) -> Option<(Option<&str>, &str)> {
+    match expr {
+        Expr::Identifier(ident) => Some((None, &ident.value)),
+        Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+            [.., qualifier, name] => Some((Some(&qualifier.value), &name.value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,365 @@
+use sqlparser::ast::{
+    Expr, FunctionArg, FunctionArgExpr, FunctionArguments, Ident, NamedWindowDefinition, NamedWindowExpr,
+    NullTreatment, OrderByExpr, WindowFrameBound, WindowFrameUnits, WindowSpec, WindowType,
+};
+
+use crate::executor::error::ExecutorError;
+use super::aggregate::{self, AggregateCall, AggregateFunction};
+use super::{children_mut, SortKey};
+
+/// Built-in window functions
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowFunction {
+    RowNumber,
+    Rank,
+    DenseRank,
+    PercentRank,
+    CumeDist,
+    Ntile,
+    Lag,
+    Lead,
+    FirstValue,
+    LastValue,
+    NthValue,
+    /// An aggregate function computed over the window frame
+    Aggregate(Box<AggregateCall>),
+}
+
+impl WindowFunction {
+    /// Non-aggregate window function with this name
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "row_number" => Some(WindowFunction::RowNumber),
+            "rank" => Some(WindowFunction::Rank),
+            "dense_rank" => Some(WindowFunction::DenseRank),
+            "percent_rank" => Some(WindowFunction::PercentRank),
+            "cume_dist" => Some(WindowFunction::CumeDist),
+            "ntile" => Some(WindowFunction::Ntile),
+            "lag" => Some(WindowFunction::Lag),
+            "lead" => Some(WindowFunction::Lead),
+            "first_value" => Some(WindowFunction::FirstValue),
+            "last_value" => Some(WindowFunction::LastValue),
+            "nth_value" => Some(WindowFunction::NthValue),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindowFunction::RowNumber => "row_number",
+            WindowFunction::Rank => "rank",
+            WindowFunction::DenseRank => "dense_rank",
+            WindowFunction::PercentRank => "percent_rank",
+            WindowFunction::CumeDist => "cume_dist",
+            WindowFunction::Ntile => "ntile",
+            WindowFunction::Lag => "lag",
+            WindowFunction::Lead => "lead",
+            WindowFunction::FirstValue => "first_value",
+            WindowFunction::LastValue => "last_value",
+            WindowFunction::NthValue => "nth_value",
+            WindowFunction::Aggregate(call) => call.func.name(),
+        }
+    }
+
+    /// Accepted argument counts (LAG and LEAD take an optional offset and default)
+    fn arity(&self) -> std::ops::RangeInclusive<usize> {
+        match self {
+            WindowFunction::Ntile | WindowFunction::FirstValue | WindowFunction::LastValue => 1..=1,
+            WindowFunction::Lag | WindowFunction::Lead => 1..=3,
+            WindowFunction::NthValue => 2..=2,
+            _ => 0..=0,
+        }
+    }
+}
+
+/// Units of a window frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameUnits {
+    /// Offsets count rows
+    Rows,
+    /// Offsets are distances from the current row's ORDER BY value; CURRENT ROW includes its peers
+    Range,
+}
+
+/// One end of a window frame
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameBound {
+    UnboundedPreceding,
+    Preceding(Expr),
+    CurrentRow,
+    Following(Expr),
+    UnboundedFollowing,
+}
+
+/// Rows of the partition a window function sees for each row
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowFrame {
+    pub units: FrameUnits,
+    pub start: FrameBound,
+    pub end: FrameBound,
+}
+
+impl Default for WindowFrame {
+    /// `RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW`: the partition up to
+    /// the current row's last peer, or all of it without ORDER BY
+    fn default() -> Self {
+        WindowFrame {
+            units: FrameUnits::Range,
+            start: FrameBound::UnboundedPreceding,
+            end: FrameBound::CurrentRow,
+        }
+    }
+}
+
+/// A resolved window function call computed by `Operator::Window`
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowCall {
+    pub func: WindowFunction,
+    /// Arguments of non-aggregate functions (aggregate calls carry their own)
+    pub args: Vec<Expr>,
+    pub frame: WindowFrame,
+    /// Name of the Window output column holding the result
+    pub output_name: String,
+}
+
+/// Window function calls sharing a PARTITION BY and ORDER BY, computed by one
+/// Window operator
+#[derive(Debug, Clone)]
+pub struct Window {
+    pub partition_by: Vec<Expr>,
+    pub order_by: Vec<SortKey>,
+    pub calls: Vec<WindowCall>,
+}
+
+impl Window {
+    /// Order of the Window output: partition keys, then the window ordering
+    pub fn output_order(&self) -> Vec<SortKey> {
+        self.partition_by.iter()
+            .map(|expr| SortKey { expr: expr.clone(), descending: false, nulls_first: false })
+            .chain(self.order_by.iter().cloned())
+            .collect()
+    }
+}
+
+/// Check whether an expression contains a window function call
+pub fn contains_window(expr: &Expr) -> bool {
+    if let Expr::Function(function) = expr
+        && function.over.is_some()
+    {
+        return true;
+    }
+    let mut expr = expr.clone();
+    children_mut(&mut expr).into_iter().any(|child| contains_window(child))
+}
+
+/// Replace window function calls in `expr` with references to the Window
+/// output columns that will hold their results
+///
+/// Calls are added to the entry of `windows` with the same PARTITION BY and
+/// ORDER BY, or to a new one. `named` holds the query's WINDOW clause.
+pub fn extract(
+    expr: &mut Expr,
+    named: &[NamedWindowDefinition],
+    windows: &mut Vec<Window>,
+    count: &mut usize,
+) -> Result<(), ExecutorError> {
+    let over = match expr {
+        Expr::Function(function) => function.over.clone(),
+        _ => None,
+    };
+    let Some(over) = over else {
+        for child in children_mut(expr) {
+            extract(child, named, windows, count)?;
+        }
+        return Ok(());
+    };
+    let Expr::Function(function) = expr else {
+        unreachable!("only function calls have OVER");
+    };
+
+    let name = function.name.0.last()
+        .and_then(|part| part.as_ident())
+        .map(|ident| ident.value.to_ascii_lowercase())
+        .unwrap_or_default();
+    if matches!(function.null_treatment, Some(NullTreatment::IgnoreNulls)) {
+        return Err(ExecutorError::UnsupportedStatement(format!(
+            "IGNORE NULLS in {}() not supported",
+            name
+        )));
+    }
+
+    let mut args = Vec::new();
+    let func = if AggregateFunction::from_name(&name).is_some() {
+        let mut plain = function.clone();
+        plain.over = None;
+        let call = aggregate::resolve_call(&Expr::Function(plain))?
+            .ok_or_else(|| ExecutorError::Plan(format!("function {} does not exist", name)))?;
+        if call.distinct {
+            return Err(ExecutorError::UnsupportedStatement(
+                "DISTINCT is not implemented for window functions".to_string(),
+            ));
+        }
+        if call.args.iter().chain(call.filter.iter()).any(contains_window) {
+            return Err(ExecutorError::Plan("window function calls cannot be nested".to_string()));
+        }
+        WindowFunction::Aggregate(Box::new(call))
+    } else {
+        let func = WindowFunction::from_name(&name).ok_or_else(|| ExecutorError::Plan(format!(
+            "OVER specified, but {} is not a window function nor an aggregate function",
+            name
+        )))?;
+        if function.filter.is_some() {
+            return Err(ExecutorError::UnsupportedStatement(
+                "FILTER is not implemented for non-aggregate window functions".to_string(),
+            ));
+        }
+        if let FunctionArguments::List(arg_list) = &function.args {
+            for arg in &arg_list.args {
+                let FunctionArg::Unnamed(FunctionArgExpr::Expr(arg)) = arg else {
+                    return Err(ExecutorError::UnsupportedStatement(format!(
+                        "Unsupported argument to {}(): {}",
+                        name, arg
+                    )));
+                };
+                if contains_window(arg) {
+                    return Err(ExecutorError::Plan("window function calls cannot be nested".to_string()));
+                }
+                args.push(arg.clone());
+            }
+        }
+        if !func.arity().contains(&args.len()) {
+            return Err(ExecutorError::Plan(format!("function {}() with {} arguments does not exist", name, args.len())));
+        }
+        func
+    };
+
+    let (partition_by, order_by, frame) = match &over {
+        WindowType::WindowSpec(spec) => resolve_spec(spec, named, named.len())?,
+        WindowType::NamedWindow(window) => named_window(window, named, named.len())?,
+    };
+    let order_by = order_by.iter().map(sort_key).collect::<Vec<_>>();
+    let frame = resolve_frame(frame.as_ref(), &order_by)?;
+
+    *count += 1;
+    let output_name = format!("$window{}", count);
+    let call = WindowCall {
+        func,
+        args,
+        frame,
+        output_name: output_name.clone(),
+    };
+    match windows.iter_mut().find(|window| window.partition_by == partition_by && window.order_by == order_by) {
+        Some(window) => window.calls.push(call),
+        None => windows.push(Window {
+            partition_by,
+            order_by,
+            calls: vec![call],
+        }),
+    }
+
+    *expr = Expr::Identifier(Ident::new(output_name));
+    Ok(())
+}
+
+type ResolvedSpec = (Vec<Expr>, Vec<OrderByExpr>, Option<sqlparser::ast::WindowFrame>);
+
+/// PARTITION BY, ORDER BY and frame of a window specification, which may
+/// build on a named window
+///
+/// Only the first `visible` WINDOW clause entries may be referenced, so a
+/// definition cannot refer to itself or a later one.
+fn resolve_spec(spec: &WindowSpec, named: &[NamedWindowDefinition], visible: usize) -> Result<ResolvedSpec, ExecutorError> {
+    let Some(base_name) = &spec.window_name else {
+        return Ok((spec.partition_by.clone(), spec.order_by.clone(), spec.window_frame.clone()));
+    };
+
+    let (partition_by, order_by, frame) = named_window(base_name, named, visible)?;
+    if frame.is_some() {
+        return Err(ExecutorError::Plan(format!(
+            "cannot copy window \"{}\" because it has a frame clause",
+            base_name.value
+        )));
+    }
+    if !spec.partition_by.is_empty() {
+        return Err(ExecutorError::Plan(format!(
+            "cannot override PARTITION BY clause of window \"{}\"",
+            base_name.value
+        )));
+    }
+    if !order_by.is_empty() && !spec.order_by.is_empty() {
+        return Err(ExecutorError::Plan(format!(
+            "cannot override ORDER BY clause of window \"{}\"",
+            base_name.value
+        )));
+    }
+    let order_by = if order_by.is_empty() { spec.order_by.clone() } else { order_by };
+    Ok((partition_by, order_by, spec.window_frame.clone()))
+}
+
+fn named_window(name: &Ident, named: &[NamedWindowDefinition], visible: usize) -> Result<ResolvedSpec, ExecutorError> {
+    let position = named[..visible].iter()
+        .position(|definition| definition.0.value.eq_ignore_ascii_case(&name.value))
+        .ok_or_else(|| ExecutorError::Plan(format!("window \"{}\" does not exist", name.value)))?;
+    match &named[position].1 {
+        NamedWindowExpr::WindowSpec(spec) => resolve_spec(spec, named, position),
+        NamedWindowExpr::NamedWindow(base) => named_window(base, named, position),
+    }
+}
+
+fn sort_key(order_expr: &OrderByExpr) -> SortKey {
+    let descending = order_expr.options.asc == Some(false);
+    SortKey {
+        expr: order_expr.expr.clone(),
+        descending,
+        nulls_first: order_expr.options.nulls_first.unwrap_or(descending),
+    }
+}
+
+/// Resolve and validate a frame clause
+fn resolve_frame(frame: Option<&sqlparser::ast::WindowFrame>, order_by: &[SortKey]) -> Result<WindowFrame, ExecutorError> {
+    let Some(frame) = frame else {
+        return Ok(WindowFrame::default());
+    };
+
+    let units = match frame.units {
+        WindowFrameUnits::Rows => FrameUnits::Rows,
+        WindowFrameUnits::Range => FrameUnits::Range,
+        WindowFrameUnits::Groups => {
+            return Err(ExecutorError::UnsupportedStatement("GROUPS frames not supported".to_string()));
+        }
+    };
+    let bound = |bound: &WindowFrameBound| match bound {
+        WindowFrameBound::CurrentRow => FrameBound::CurrentRow,
+        WindowFrameBound::Preceding(None) => FrameBound::UnboundedPreceding,
+        WindowFrameBound::Preceding(Some(offset)) => FrameBound::Preceding((**offset).clone()),
+        WindowFrameBound::Following(None) => FrameBound::UnboundedFollowing,
+        WindowFrameBound::Following(Some(offset)) => FrameBound::Following((**offset).clone()),
+    };
+    let start = bound(&frame.start_bound);
+    let end = frame.end_bound.as_ref().map(bound).unwrap_or(FrameBound::CurrentRow);
+
+    let invalid = match (&start, &end) {
+        (FrameBound::UnboundedFollowing, _) => Some("frame start cannot be UNBOUNDED FOLLOWING"),
+        (_, FrameBound::UnboundedPreceding) => Some("frame end cannot be UNBOUNDED PRECEDING"),
+        (FrameBound::CurrentRow, FrameBound::Preceding(_)) => {
+            Some("frame starting from current row cannot have preceding rows")
+        }
+        (FrameBound::Following(_), FrameBound::Preceding(_) | FrameBound::CurrentRow) => {
+            Some("frame starting from following row cannot have preceding rows")
+        }
+        _ => None,
+    };
+    if let Some(message) = invalid {
+        return Err(ExecutorError::Plan(message.to_string()));
+    }
+
+    let has_offset = [&start, &end].into_iter()
+        .any(|bound| matches!(bound, FrameBound::Preceding(_) | FrameBound::Following(_)));
+    if units == FrameUnits::Range && has_offset && order_by.len() != 1 {
+        return Err(ExecutorError::Plan(
+            "RANGE with offset PRECEDING/FOLLOWING requires exactly one ORDER BY column".to_string(),
+        ));
+    }
+
+    Ok(WindowFrame { units, start, end })
+}
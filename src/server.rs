@@ -4,7 +4,8 @@ use pgwire::tokio::process_socket;
 use tokio::net::TcpListener;
 use tracing::{debug, error, info, span, Level};
 
-use crate::config::Config;
+use crate::config::{Config, Settings};
+use crate::executor::Executor;
 use crate::handler::HandlerFactory;
 
 pub struct Server {
@@ -17,7 +18,14 @@ impl Server {
     }
 
     pub async fn start(&self) {
-        let factory = Arc::new(HandlerFactory::new(&self.config));
+        let executor = match Executor::new(&self.config) {
+            Ok(executor) => Arc::new(executor),
+            Err(e) => {
+                error!(error = %e, "failed to open database");
+                return;
+            }
+        };
+        let settings = Settings::new(&self.config);
 
         let server_addr = format!("{}:{}", self.config.bind_addr, self.config.port);
         let listener = TcpListener::bind(&server_addr).await.unwrap();
@@ -28,7 +36,8 @@ impl Server {
             let incoming_socket = listener.accept().await.unwrap();
             let client_addr = incoming_socket.1;
 
-            let factory_ref = factory.clone();
+            // Each connection has its own session settings
+            let factory_ref = Arc::new(HandlerFactory::new(executor.clone(), settings.clone()));
             tokio::spawn(async move {
                 let span = span!(Level::INFO, "connection", client_addr = %client_addr);
                 let _enter = span.enter();
@@ -52,17 +52,43 @@ impl TuplePointer {
 }
 
 /// MVCC metadata for each tuple
-#[derive(Debug, Clone, Copy)]
+/// Stored in front of every tuple's payload inside its block slot
+/// zerocopy-verified safe layout: IntoBytes + FromBytes guarantee no padding between fields
+#[derive(Debug, Clone, Copy, IntoBytes, FromBytes, Immutable, KnownLayout)]
+#[repr(C)]
 pub struct TupleMeta {
     /// Transaction ID that created this tuple
     pub xmin: TxId,
     /// Transaction ID that deleted this tuple (0 if not deleted)
     pub xmax: TxId,
+    /// Newer version of this tuple (valid when TUPLE_HAS_NEXT is set)
+    pub next_segment_id: SegmentId,
+    pub next_slot_id: SlotId,
+    pub next_block_id: BlockId,
+    /// Version chain flags (TUPLE_*)
+    pub flags: u8,
 }
 
+pub const TUPLE_META_SIZE: usize = 24;
+const _: () = assert!(size_of::<TupleMeta>() == TUPLE_META_SIZE);
+
+/// Tuple has a newer version (next_* fields are valid)
+pub const TUPLE_HAS_NEXT: u8 = 0x01;
+/// Tuple was updated and its newer version lives in the same block (HOT chain)
+pub const TUPLE_HOT_UPDATED: u8 = 0x02;
+/// Tuple is only reachable through a HOT chain, indexes point at an older version
+pub const TUPLE_HEAP_ONLY: u8 = 0x04;
+
 impl TupleMeta {
     pub fn new(xmin: TxId) -> Self {
-        TupleMeta { xmin, xmax: 0 }
+        TupleMeta {
+            xmin,
+            xmax: 0,
+            next_segment_id: 0,
+            next_slot_id: 0,
+            next_block_id: 0,
+            flags: 0,
+        }
     }
 
     pub fn is_deleted(&self) -> bool {
@@ -72,6 +98,27 @@ impl TupleMeta {
     pub fn mark_deleted(&mut self, xmax: TxId) {
         self.xmax = xmax;
     }
+
+    /// Mark this version as superseded by `next` (written by transaction `xmax`)
+    pub fn mark_updated(&mut self, xmax: TxId, next: TuplePointer, hot: bool) {
+        self.xmax = xmax;
+        self.next_segment_id = next.segment_id;
+        self.next_block_id = next.block_id;
+        self.next_slot_id = next.slot_id;
+        self.flags |= TUPLE_HAS_NEXT;
+        if hot {
+            self.flags |= TUPLE_HOT_UPDATED;
+        }
+    }
+
+    /// Pointer to the newer version of this tuple, if it was updated
+    pub fn next(&self) -> Option<TuplePointer> {
+        if self.flags & TUPLE_HAS_NEXT != 0 {
+            Some(TuplePointer::new(self.next_segment_id, self.next_block_id, self.next_slot_id))
+        } else {
+            None
+        }
+    }
 }
 
 /// Segment header (64KB at start of each segment)
@@ -257,6 +304,9 @@ impl Block {
 
     /// Read tuple data at slot
     pub fn read_tuple(&self, slot_id: SlotId) -> Option<&[u8]> {
+        if slot_id >= self.header().slot_count {
+            return None;
+        }
         let slot = self.slot(slot_id);
         if slot.is_empty() {
             return None;
@@ -299,6 +349,35 @@ impl Block {
 
         Some(slot_id)
     }
+
+    /// Read a versioned tuple at slot: MVCC header + payload
+    pub fn read_versioned_tuple(&self, slot_id: SlotId) -> Option<(TupleMeta, &[u8])> {
+        let bytes = self.read_tuple(slot_id)?;
+        TupleMeta::read_from_prefix(bytes).ok()
+    }
+
+    /// Append a versioned tuple (MVCC header + payload) to block
+    pub fn append_versioned_tuple(&mut self, meta: &TupleMeta, payload: &[u8]) -> Option<SlotId> {
+        let mut data = Vec::with_capacity(TUPLE_META_SIZE + payload.len());
+        data.extend_from_slice(meta.as_bytes());
+        data.extend_from_slice(payload);
+        self.append_tuple(&data)
+    }
+
+    /// Overwrite the MVCC header of the tuple at slot in place
+    /// Returns false if the slot is empty
+    pub fn set_tuple_meta(&mut self, slot_id: SlotId, meta: &TupleMeta) -> bool {
+        if slot_id >= self.header().slot_count {
+            return false;
+        }
+        let slot = self.slot(slot_id);
+        if slot.is_empty() || (slot.length as usize) < TUPLE_META_SIZE {
+            return false;
+        }
+        let start = slot.offset as usize;
+        self.as_bytes_mut()[start..start + TUPLE_META_SIZE].copy_from_slice(meta.as_bytes());
+        true
+    }
 }
 
 /// Page identifier for index pages (4KB)
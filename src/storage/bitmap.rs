@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use super::base::{BlockId, SegmentId, SlotId, TuplePointer};
+
+/// Set of tuple pointers, kept as a bit per slot of each block
+///
+/// Blocks are visited in (segment, block) order, so fetching the tuples of a
+/// bitmap reads each block once, in the order the table file lays them out.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TupleBitmap {
+    blocks: BTreeMap<(SegmentId, BlockId), Vec<u64>>,
+}
+
+impl TupleBitmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, ptr: TuplePointer) {
+        let words = self.blocks.entry((ptr.segment_id, ptr.block_id)).or_default();
+        let (word, bit) = (ptr.slot_id as usize / 64, ptr.slot_id % 64);
+        if words.len() <= word {
+            words.resize(word + 1, 0);
+        }
+        words[word] |= 1 << bit;
+    }
+
+    /// Tuples in either bitmap
+    pub fn union(mut self, other: TupleBitmap) -> TupleBitmap {
+        for (block, words) in other.blocks {
+            let mine = self.blocks.entry(block).or_default();
+            if mine.len() < words.len() {
+                mine.resize(words.len(), 0);
+            }
+            for (mine, word) in mine.iter_mut().zip(words) {
+                *mine |= word;
+            }
+        }
+        self
+    }
+
+    /// Tuples in both bitmaps
+    pub fn intersect(mut self, other: &TupleBitmap) -> TupleBitmap {
+        self.blocks.retain(|block, mine| {
+            let Some(words) = other.blocks.get(block) else {
+                return false;
+            };
+            mine.truncate(words.len());
+            for (mine, word) in mine.iter_mut().zip(words) {
+                *mine &= word;
+            }
+            mine.iter().any(|word| *word != 0)
+        });
+        self
+    }
+
+    /// Number of tuples in the bitmap
+    pub fn len(&self) -> usize {
+        self.blocks.values().flatten().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Blocks holding tuples of the bitmap, in physical order, with their slots
+    pub fn blocks(&self) -> impl Iterator<Item = ((SegmentId, BlockId), Vec<SlotId>)> + '_ {
+        self.blocks.iter().map(|(block, words)| {
+            let slots = words.iter()
+                .enumerate()
+                .flat_map(|(word_idx, word)| {
+                    (0..64).filter(move |bit| word & (1 << bit) != 0).map(move |bit| (word_idx * 64 + bit) as SlotId)
+                })
+                .collect();
+            (*block, slots)
+        })
+    }
+}
+
+impl FromIterator<TuplePointer> for TupleBitmap {
+    fn from_iter<I: IntoIterator<Item = TuplePointer>>(iter: I) -> Self {
+        let mut bitmap = TupleBitmap::new();
+        for ptr in iter {
+            bitmap.insert(ptr);
+        }
+        bitmap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slots(bitmap: &TupleBitmap) -> Vec<(SegmentId, BlockId, SlotId)> {
+        bitmap.blocks()
+            .flat_map(|((segment, block), slots)| slots.into_iter().map(move |slot| (segment, block, slot)))
+            .collect()
+    }
+
+    #[test]
+    fn test_bitmap_and_or() {
+        let a: TupleBitmap = [(1, 0, 3), (0, 2, 70), (0, 2, 1), (0, 0, 5)].into_iter()
+            .map(|(segment, block, slot)| TuplePointer::new(segment, block, slot))
+            .collect();
+        let b: TupleBitmap = [(0, 2, 70), (1, 0, 4), (0, 0, 5)].into_iter()
+            .map(|(segment, block, slot)| TuplePointer::new(segment, block, slot))
+            .collect();
+
+        // Physical order regardless of insertion order
+        assert_eq!(slots(&a), vec![(0, 0, 5), (0, 2, 1), (0, 2, 70), (1, 0, 3)]);
+        assert_eq!(slots(&a.clone().intersect(&b)), vec![(0, 0, 5), (0, 2, 70)]);
+        let union = a.union(b);
+        assert_eq!(slots(&union), vec![(0, 0, 5), (0, 2, 1), (0, 2, 70), (1, 0, 3), (1, 0, 4)]);
+        assert_eq!(union.len(), 5);
+        assert!(union.intersect(&TupleBitmap::new()).is_empty());
+    }
+}
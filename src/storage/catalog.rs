@@ -3,7 +3,7 @@ use std::io::{self, Result};
 use std::sync::atomic::{AtomicU8, Ordering};
 use serde::{Serialize, Deserialize};
 use bincode::{Encode, Decode};
-use crate::types::Schema;
+use crate::types::{Schema, Value};
 
 /// Metadata about a single index file
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
@@ -20,8 +20,36 @@ pub struct IndexFileMetadata {
     pub root_page_offset: u16,
 }
 
+/// Statistics about a table's contents, gathered by ANALYZE
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+#[bincode(decode_context = "()")]
+pub struct TableStatistics {
+    /// Live rows when the table was analyzed
+    pub row_count: u64,
+    /// Heap pages when the table was analyzed
+    pub page_count: u64,
+    /// One entry per column, in schema order
+    pub columns: Vec<ColumnStatistics>,
+}
+
+/// Value distribution of a single column
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+#[bincode(decode_context = "()")]
+pub struct ColumnStatistics {
+    pub name: String,
+    /// Fraction of rows that are NULL
+    pub null_fraction: f64,
+    /// Number of distinct non-NULL values
+    pub distinct_count: u64,
+    /// Most common values with the fraction of rows holding each, most common first
+    pub most_common: Vec<(Value, f64)>,
+    /// Bounds of equal-population buckets over the values not in `most_common`, ascending
+    pub histogram: Vec<Value>,
+}
+
 /// Metadata about a single table file
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+#[bincode(decode_context = "()")]
 pub struct TableFileMetadata {
     /// Table name
     pub name: String,
@@ -35,6 +63,8 @@ pub struct TableFileMetadata {
     pub primary_index: Option<IndexFileMetadata>,
     /// Secondary indexes
     pub secondary_indexes: Vec<IndexFileMetadata>,
+    /// Statistics from the last ANALYZE (None if never analyzed)
+    pub statistics: Option<TableStatistics>,
 }
 
 /// Global catalog header
@@ -42,6 +72,8 @@ pub struct TableFileMetadata {
 pub struct CatalogHeader {
     /// Catalog version
     pub version: u32,
+    /// Incremented on every save; the segment with the highest generation is current
+    pub generation: u64,
     /// Number of tables
     pub num_tables: u32,
     /// Checksum of metadata bytes
@@ -51,7 +83,8 @@ pub struct CatalogHeader {
 impl CatalogHeader {
     pub fn new() -> Self {
         CatalogHeader {
-            version: 1,
+            version: 2,
+            generation: 0,
             num_tables: 0,
             checksum: 0,
         }
@@ -63,6 +96,8 @@ impl CatalogHeader {
 pub struct Catalog {
     /// Active metadata segment (0 or 1)
     active_segment: AtomicU8,
+    /// Generation of the last save
+    generation: u64,
     /// All table metadata indexed by name
     tables: HashMap<String, TableFileMetadata>,
 }
@@ -72,10 +107,25 @@ impl Catalog {
     pub fn new() -> Self {
         Catalog {
             active_segment: AtomicU8::new(0),
+            generation: 0,
             tables: HashMap::new(),
         }
     }
 
+    /// Load the newest valid catalog from the contents of segments 0 and 1
+    ///
+    /// A segment that is missing or fails its checksum (e.g. a torn write) is
+    /// skipped. The loaded segment becomes active, so the next save goes to
+    /// the other one. Returns None if neither segment holds a catalog.
+    pub fn load(segments: [Option<Vec<u8>>; 2]) -> Option<Self> {
+        let (segment, catalog) = segments.into_iter()
+            .enumerate()
+            .filter_map(|(segment, data)| Some((segment, Catalog::deserialize(&data?).ok()?)))
+            .max_by_key(|(_, catalog)| catalog.generation)?;
+        catalog.active_segment.store(segment as u8, Ordering::SeqCst);
+        Some(catalog)
+    }
+
     /// Get the active metadata segment (0 or 1)
     pub fn active_segment(&self) -> u8 {
         self.active_segment.load(Ordering::SeqCst)
@@ -104,6 +154,11 @@ impl Catalog {
         Ok(self.tables.get(name))
     }
 
+    /// Get mutable table metadata by name
+    pub fn get_table_mut(&mut self, name: &str) -> Option<&mut TableFileMetadata> {
+        self.tables.get_mut(name)
+    }
+
     /// Get all tables
     pub fn all_tables(&self) -> Vec<&TableFileMetadata> {
         self.tables.values().collect()
@@ -114,9 +169,15 @@ impl Catalog {
         Ok(self.tables.remove(name))
     }
 
+    /// Start a new generation, to be written by the next `serialize`
+    pub fn advance_generation(&mut self) {
+        self.generation += 1;
+    }
+
     /// Serialize catalog to bytes for persistence
     pub fn serialize(&self) -> Result<Vec<u8>> {
         let mut header = CatalogHeader::new();
+        header.generation = self.generation;
         header.num_tables = self.tables.len() as u32;
 
         // Serialize all table metadata
@@ -156,6 +217,7 @@ impl Catalog {
 
         // Deserialize tables
         let mut catalog = Catalog::new();
+        catalog.generation = header.generation;
         let mut offset = 0;
         for _ in 0..header.num_tables {
             let (metadata, bytes_read): (TableFileMetadata, usize) =
@@ -175,3 +237,29 @@ fn compute_checksum(data: &[u8]) -> u64 {
         acc.wrapping_mul(31).wrapping_add(byte as u64)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_newest_segment() {
+        let mut catalog = Catalog::new();
+        catalog.advance_generation();
+        let older = catalog.serialize().expect("Failed to serialize catalog");
+        catalog.advance_generation();
+        let newer = catalog.serialize().expect("Failed to serialize catalog");
+
+        let loaded = Catalog::load([Some(older.clone()), Some(newer.clone())]).expect("Failed to load catalog");
+        assert_eq!(loaded.generation, 2);
+        assert_eq!(loaded.active_segment(), 1);
+
+        // A torn write of the newer segment falls back to the older one
+        let torn = newer[..newer.len() / 2].to_vec();
+        let loaded = Catalog::load([Some(torn), Some(older)]).expect("Failed to load catalog");
+        assert_eq!(loaded.generation, 1);
+        assert_eq!(loaded.active_segment(), 1);
+
+        assert!(Catalog::load([None, None]).is_none());
+    }
+}
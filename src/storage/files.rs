@@ -23,10 +23,14 @@ impl TableFile {
         let disk = Disk::open(&path)?;
         let path = path.as_ref().to_path_buf();
 
+        // Recover the segment allocation point from the file length
+        let file_len = std::fs::metadata(&path)?.len();
+        let next_segment_id = file_len.div_ceil(SEGMENT_SIZE as u64) as u32;
+
         Ok(TableFile {
             disk,
             path,
-            next_segment_id: Mutex::new(0),
+            next_segment_id: Mutex::new(next_segment_id),
         })
     }
 
@@ -88,11 +92,14 @@ impl TableFile {
         }
 
         let offset = Self::block_offset(segment_id, block_id);
+        // Direct I/O needs a 4KB-aligned buffer, then copy into the Vec<u32> block
+        let mut buf = alloc_aligned(BLOCK_SIZE);
+        self.disk.read_at(offset, &mut buf)?;
+
         // Allocate as Vec<u32> to ensure 4-byte alignment for zerocopy
         let num_u32s = BLOCK_SIZE / std::mem::size_of::<u32>();
         let mut data = vec![0u32; num_u32s];
-        let data_bytes = data.as_mut_bytes();
-        self.disk.read_at(offset, data_bytes)?;
+        data.as_mut_bytes().copy_from_slice(&buf[..BLOCK_SIZE]);
 
         Ok(Block { data })
     }
@@ -107,7 +114,10 @@ impl TableFile {
         }
 
         let offset = Self::block_offset(segment_id, block_id);
-        self.disk.write_at(offset, block.as_bytes())?;
+        // Direct I/O needs a 4KB-aligned buffer
+        let mut buf = alloc_aligned(BLOCK_SIZE);
+        buf.copy_from_slice(block.as_bytes());
+        self.disk.write_at(offset, &buf)?;
         Ok(())
     }
 
@@ -182,6 +192,14 @@ impl TableFile {
         Ok(())
     }
 
+    /// Drop every segment, leaving an empty file
+    pub fn truncate(&self) -> Result<()> {
+        let mut next_id = self.next_segment_id.lock().unwrap();
+        self.disk.set_len(0)?;
+        *next_id = 0;
+        Ok(())
+    }
+
     /// Free a block in segment
     pub fn free_block(&self, segment_id: u32, block_id: u8) -> Result<()> {
         let mut header = self.read_segment_header(segment_id)?;
@@ -211,10 +229,14 @@ impl IndexFile {
         let disk = Disk::open(&path)?;
         let path = path.as_ref().to_path_buf();
 
+        // Recover the page allocation point from the file length
+        let file_len = std::fs::metadata(&path)?.len();
+        let next_page_id = file_len.div_ceil(PAGE_SIZE as u64) as u32;
+
         Ok(IndexFile {
             disk,
             path,
-            next_page_id: Mutex::new(0),
+            next_page_id: Mutex::new(next_page_id),
         })
     }
 
@@ -241,7 +263,18 @@ impl IndexFile {
         }
 
         let offset = Self::page_offset(page_id.raw());
-        self.disk.write_at(offset, data)?;
+        // Direct I/O needs a 4KB-aligned buffer
+        let mut buf = alloc_aligned(PAGE_SIZE);
+        buf.copy_from_slice(data);
+        self.disk.write_at(offset, &buf)?;
+        Ok(())
+    }
+
+    /// Drop every page, leaving an empty file
+    pub fn truncate(&self) -> Result<()> {
+        let mut next_id = self.next_page_id.lock().unwrap();
+        self.disk.set_len(0)?;
+        *next_id = 0;
         Ok(())
     }
 
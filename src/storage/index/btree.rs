@@ -2,7 +2,7 @@ use std::io::{self, Result as IoResult};
 use crate::storage::base::TuplePointer;
 use crate::storage::files::IndexFile;
 use crate::storage::base::PageId;
-use super::page::{IndexEntry, IndexPage, IndexPageHeader, NodeType};
+use super::page::{IndexEntry, IndexPage, NodeType};
 
 /// Represents a split result when a node overflows
 #[derive(Debug)]
@@ -38,24 +38,8 @@ impl BTree {
         key: u64,
         tuple_ptr: TuplePointer,
     ) -> IoResult<Option<SplitResult>> {
-        let (found, pos) = page.binary_search(key)?;
-
-        // If key already exists, update it (replace old value)
-        if found {
-            let entry = IndexEntry::new(key, tuple_ptr);
-            let header_size = std::mem::size_of::<IndexPageHeader>();
-            let entry_size = std::mem::size_of::<IndexEntry>();
-            let offset = header_size + pos * entry_size;
-
-            let entry_bytes = unsafe {
-                std::slice::from_raw_parts(
-                    &entry as *const IndexEntry as *const u8,
-                    entry_size,
-                )
-            };
-            page.data[offset..offset + entry_size].copy_from_slice(entry_bytes);
-            return Ok(None);
-        }
+        // Entries for a key that is already present go after them
+        let pos = page.upper_bound(key)?;
 
         // Try to insert at position
         let entry = IndexEntry::new(key, tuple_ptr);
@@ -108,18 +92,6 @@ impl BTree {
         }))
     }
 
-    /// Find a value by key in a page (returns Option<TuplePointer> if leaf)
-    pub fn search_page(page: &IndexPage, key: u64) -> IoResult<Option<TuplePointer>> {
-        let (found, pos) = page.binary_search(key)?;
-
-        if !found {
-            return Ok(None);
-        }
-
-        let entry = page.get_entry(pos)?;
-        Ok(Some(entry.as_tuple_pointer()))
-    }
-
     /// Range scan in a leaf page - get all entries in [start_key, end_key]
     pub fn range_scan_page(
         page: &IndexPage,
@@ -148,7 +120,25 @@ impl BTree {
             .collect())
     }
 
-    /// Find the leaf page containing a given key by traversing internal nodes
+    /// Position of the entry of an internal node whose child a new entry for
+    /// `key` goes into
+    /// Each entry holds the smallest key of its child's subtree, so this is
+    /// the last entry with a key <= `key`, or the first for smaller keys.
+    fn child_position(page: &IndexPage, key: u64) -> IoResult<usize> {
+        Ok(page.upper_bound(key)?.saturating_sub(1))
+    }
+
+    /// Position of the entry of an internal node whose child is the first
+    /// that may hold `key`
+    /// Entries for one key can run over several children, and a child may end
+    /// with the key its right neighbour starts with, so this is the last entry
+    /// with a key < `key`, or the first if there is none.
+    fn first_child_position(page: &IndexPage, key: u64) -> IoResult<usize> {
+        Ok(page.lower_bound(key)?.saturating_sub(1))
+    }
+
+    /// Find the leftmost leaf page that may contain a given key by traversing
+    /// internal nodes
     fn find_leaf_page(
         &self,
         key: u64,
@@ -167,30 +157,79 @@ impl BTree {
             if header.is_leaf() {
                 return Ok(current_page);
             }
+            if header.num_keys == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Internal node has no keys",
+                ));
+            }
 
-            // Internal node: find child to traverse
-            let (found, pos) = current_page.binary_search(key)?;
-
-            // For internal nodes, position tells us which child to follow
-            // If found, child is at pos; if not found, child is at pos
-            let child_index = if found { pos } else { pos };
-
-            // Get the entry at child_index to find next page
-            let entry = if child_index < header.num_keys as usize {
-                current_page.get_entry(child_index)?
-            } else {
-                // Key is greater than all keys in this node, use rightmost child
-                if header.num_keys == 0 {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "Internal node has no keys",
-                    ));
-                }
-                current_page.get_entry((header.num_keys - 1) as usize)?
+            let entry = current_page.get_entry(Self::first_child_position(&current_page, key)?)?;
+            current_page_id = entry.as_child_page_id();
+        }
+    }
+
+    /// Insert a key into the subtree rooted at `page_id`
+    /// Returns the smallest key and page ID of the new right sibling if the node split
+    fn insert_into_subtree(
+        page_id: PageId,
+        key: u64,
+        tuple_ptr: TuplePointer,
+        disk_mgr: &IndexFile,
+    ) -> IoResult<Option<(u64, PageId)>> {
+        let mut page = IndexPage { data: disk_mgr.read_page(page_id)? };
+        let is_leaf = page.header()?.is_leaf();
+        // Splitting rewrites the header, so remember the leaf's siblings
+        let prev = page.prev_sibling()?;
+        let next = page.next_sibling()?;
+
+        let split = if is_leaf {
+            Self::insert_into_page(&mut page, key, tuple_ptr)?
+        } else {
+            if page.header()?.num_keys == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Internal node has no keys",
+                ));
+            }
+            let pos = Self::child_position(&page, key)?;
+            let child_id = page.get_entry(pos)?.as_child_page_id();
+            let Some((separator, right_id)) = Self::insert_into_subtree(child_id, key, tuple_ptr, disk_mgr)? else {
+                return Ok(None);
             };
 
-            current_page_id = entry.as_child_page_id();
+            // The child split: point at its new right sibling after it
+            let entry = IndexEntry::new_internal(separator, right_id);
+            match page.insert_at(pos + 1, entry) {
+                Ok(()) => None,
+                Err(e) if e.kind() == io::ErrorKind::Other => Self::split_page(&mut page, pos + 1, entry)?,
+                Err(e) => return Err(e),
+            }
+        };
+
+        let Some(split) = split else {
+            disk_mgr.write_page(page_id, &page.data)?;
+            return Ok(None);
+        };
+
+        let right_id = disk_mgr.allocate_page()?;
+        let mut right_page = split.right_page;
+        if is_leaf {
+            // Link the new leaf between this page and its old next sibling
+            page.set_prev_sibling(prev)?;
+            page.set_next_sibling(Some(right_id))?;
+            right_page.set_prev_sibling(Some(page_id))?;
+            right_page.set_next_sibling(next)?;
+            if let Some(next_id) = next {
+                let mut next_page = IndexPage { data: disk_mgr.read_page(next_id)? };
+                next_page.set_prev_sibling(Some(right_id))?;
+                disk_mgr.write_page(next_id, &next_page.data)?;
+            }
         }
+        disk_mgr.write_page(right_id, &right_page.data)?;
+        disk_mgr.write_page(page_id, &page.data)?;
+
+        Ok(Some((split.promoted_key, right_id)))
     }
 }
 
@@ -199,51 +238,91 @@ impl super::Index for BTree {
         "btree"
     }
 
+    fn capability(&self) -> super::IndexCapability {
+        super::IndexCapability::Ordered
+    }
+
+    /// Splits are absorbed into the tree, so this never returns a split
     fn insert(
         &mut self,
         key: u64,
         pointer: TuplePointer,
         disk_mgr: &IndexFile,
     ) -> IoResult<Option<super::IndexSplit>> {
-        // Read root page
-        let root_id = self.root_page_id.unwrap();
-        let page_data = disk_mgr.read_page(root_id)?;
-        let mut root_page = super::page::IndexPage { data: page_data };
-
-        // Insert into root
-        match Self::insert_into_page(&mut root_page, key, pointer)? {
-            None => {
-                // No split, just write back
-                disk_mgr.write_page(root_id, &root_page.data)?;
-                Ok(None)
-            }
-            Some(split) => {
-                // Root split - create new root
-                // Write left page (current root becomes left child)
-                disk_mgr.write_page(root_id, &root_page.data)?;
-
-                // Allocate right sibling
-                let right_id = disk_mgr.allocate_page()?;
-                disk_mgr.write_page(right_id, &split.right_page.data)?;
-
-                // For now, signal split to caller
-                // Full B+ tree would create new parent here
-                Ok(Some(super::IndexSplit {
-                    promoted_key: split.promoted_key,
-                    right_sibling_data: split.right_page.data.to_vec(),
-                }))
-            }
+        let root_id = self.root_page_id
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No root page"))?;
+
+        let Some((separator, right_id)) = Self::insert_into_subtree(root_id, key, pointer, disk_mgr)? else {
+            return Ok(None);
+        };
+
+        // Root split: the catalog records the root page ID, so the root keeps
+        // it. Its entries move to a new left child and it becomes an internal
+        // node over the two halves.
+        let root_page = IndexPage { data: disk_mgr.read_page(root_id)? };
+        let left_id = disk_mgr.allocate_page()?;
+        disk_mgr.write_page(left_id, &root_page.data)?;
+        if root_page.header()?.is_leaf() {
+            let mut right_page = IndexPage { data: disk_mgr.read_page(right_id)? };
+            right_page.set_prev_sibling(Some(left_id))?;
+            disk_mgr.write_page(right_id, &right_page.data)?;
         }
+
+        let left_key = root_page.get_entry(0)?.key;
+        let mut new_root = IndexPage::new(NodeType::Internal);
+        new_root.set_entries(NodeType::Internal, vec![
+            IndexEntry::new_internal(left_key, left_id),
+            IndexEntry::new_internal(separator, right_id),
+        ])?;
+        disk_mgr.write_page(root_id, &new_root.data)?;
+
+        Ok(None)
     }
 
     fn search(
         &self,
         key: u64,
         disk_mgr: &IndexFile,
-    ) -> IoResult<Option<TuplePointer>> {
-        // Find the leaf page containing the key
-        let leaf_page = self.find_leaf_page(key, disk_mgr)?;
-        Self::search_page(&leaf_page, key)
+    ) -> IoResult<Vec<TuplePointer>> {
+        // Entries for the key may span several leaves
+        Ok(super::Index::range_scan(self, key, key, disk_mgr)?
+            .into_iter()
+            .map(|(_, ptr)| ptr)
+            .collect())
+    }
+
+    fn range_scan(
+        &self,
+        start_key: u64,
+        end_key: u64,
+        disk_mgr: &IndexFile,
+    ) -> IoResult<Vec<(u64, TuplePointer)>> {
+        let mut results = Vec::new();
+        if start_key > end_key {
+            return Ok(results);
+        }
+
+        // Start at the leaf that would hold start_key and follow the sibling
+        // links until a key passes end_key
+        let mut leaf_page = self.find_leaf_page(start_key, disk_mgr)?;
+        loop {
+            for entry in leaf_page.entries()? {
+                if entry.key > end_key {
+                    return Ok(results);
+                }
+                if entry.key >= start_key {
+                    results.push((entry.key, entry.as_tuple_pointer()));
+                }
+            }
+            match leaf_page.next_sibling()? {
+                Some(next_id) => leaf_page = IndexPage { data: disk_mgr.read_page(next_id)? },
+                None => return Ok(results),
+            }
+        }
+    }
+
+    fn full_scan(&self, disk_mgr: &IndexFile) -> IoResult<Vec<(u64, TuplePointer)>> {
+        super::Index::range_scan(self, 0, u64::MAX, disk_mgr)
     }
 }
 
@@ -254,23 +333,36 @@ impl super::OrderedIndex for BTree {
         end_key: u64,
         disk_mgr: &IndexFile,
     ) -> IoResult<Vec<(u64, TuplePointer)>> {
-        // Find the leftmost leaf containing start_key
-        let leaf_page = self.find_leaf_page(start_key, disk_mgr)?;
-        Self::range_scan_page(&leaf_page, start_key, end_key)
+        super::Index::range_scan(self, start_key, end_key, disk_mgr)
     }
 
     fn full_scan(&self, disk_mgr: &IndexFile) -> IoResult<Vec<(u64, TuplePointer)>> {
-        // Find the leftmost leaf by searching for key 0
-        let leaf_page = self.find_leaf_page(0, disk_mgr)?;
-        Self::scan_page(&leaf_page)
-        // NOTE: Without sibling pointers, we only scan the first leaf found.
-        // Full implementation would need B+ tree sibling links to scan all leaves.
+        super::Index::full_scan(self, disk_mgr)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::index::Index;
+    use std::fs;
+
+    /// A BTree over a fresh index file with an empty leaf root
+    fn open_btree(path: &str) -> (BTree, IndexFile) {
+        let _ = fs::remove_file(path);
+        let file = IndexFile::open(path).expect("Failed to create index file");
+        let root = file.allocate_page().expect("Failed to allocate root page");
+        file.write_page(root, &IndexPage::new(NodeType::Leaf).data).expect("Failed to write root page");
+        (BTree::new(Some(root)), file)
+    }
+
+    fn pointer(key: u64) -> TuplePointer {
+        TuplePointer {
+            segment_id: key as u32,
+            block_id: 0,
+            slot_id: 0,
+        }
+    }
 
     #[test]
     fn test_btree_creation_empty() {
@@ -284,4 +376,83 @@ mod tests {
         let btree = BTree::new(Some(page_id));
         assert_eq!(btree.root_page_id(), Some(page_id));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_btree_splits_beyond_one_leaf() {
+        let path = "test_btree_splits.idx";
+        let (mut btree, file) = open_btree(path);
+
+        // Interleave keys from both ends so splits happen all over the tree
+        let keys: Vec<u64> = (0..1500u64).map(|i| if i % 2 == 0 { i * 7 } else { 20_000 - i * 3 }).collect();
+        for &key in &keys {
+            btree.insert(key, pointer(key), &file).expect("Failed to insert");
+        }
+        assert_eq!(btree.capability(), crate::storage::index::IndexCapability::Ordered);
+
+        for &key in &keys {
+            assert_eq!(btree.search(key, &file).expect("Failed to search"), vec![pointer(key)]);
+        }
+        assert!(btree.search(1, &file).expect("Failed to search").is_empty());
+
+        // Scans cross leaves in key order
+        let mut sorted = keys.clone();
+        sorted.sort();
+        let all: Vec<u64> = btree.full_scan(&file).expect("Failed to scan").into_iter().map(|(key, _)| key).collect();
+        assert_eq!(all, sorted);
+
+        let range: Vec<u64> = btree.range_scan(700, 15_000, &file).expect("Failed to scan")
+            .into_iter().map(|(key, _)| key).collect();
+        let expected: Vec<u64> = sorted.iter().copied().filter(|key| (700..=15_000).contains(key)).collect();
+        assert_eq!(range, expected);
+        assert!(btree.range_scan(10, 5, &file).expect("Failed to scan").is_empty());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_btree_internal_node_splits() {
+        let path = "test_btree_internal.idx";
+        let (mut btree, file) = open_btree(path);
+
+        // Ascending inserts leave half-full leaves, so this overflows the
+        // root's internal node too
+        let count = 40_000u64;
+        for key in 0..count {
+            btree.insert(key, pointer(key), &file).expect("Failed to insert");
+        }
+        for key in [0, 1, 126, 127, 20_000, count - 1] {
+            assert_eq!(btree.search(key, &file).expect("Failed to search"), vec![pointer(key)]);
+        }
+        let all = btree.full_scan(&file).expect("Failed to scan");
+        assert_eq!(all.len() as u64, count);
+        assert!(all.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_btree_duplicate_keys() {
+        let path = "test_btree_duplicates.idx";
+        let (mut btree, file) = open_btree(path);
+
+        // Runs of equal keys long enough to span several leaves, inserted
+        // out of order
+        for i in 0..3000u64 {
+            let key = (i * 7919) % 3 * 100;
+            btree.insert(key, pointer(i), &file).expect("Failed to insert");
+        }
+        btree.insert(150, pointer(9999), &file).expect("Failed to insert");
+
+        for key in [0, 100, 200] {
+            let mut found = btree.search(key, &file).expect("Failed to search");
+            found.sort_by_key(|ptr| ptr.segment_id);
+            let expected: Vec<_> = (0..3000u64).filter(|i| (i * 7919) % 3 * 100 == key).map(pointer).collect();
+            assert_eq!(found, expected);
+        }
+        assert_eq!(btree.search(150, &file).expect("Failed to search"), vec![pointer(9999)]);
+        assert!(btree.search(50, &file).expect("Failed to search").is_empty());
+        assert_eq!(btree.range_scan(100, 150, &file).expect("Failed to scan").len(), 1001);
+
+        let _ = fs::remove_file(path);
+    }
+}
@@ -113,42 +113,6 @@ impl HashIndex {
             }
         }
     }
-
-    /// Search within a bucket page for a key
-    fn search_in_page(page: &IndexPage, key: u64) -> IoResult<Option<usize>> {
-        let header = page.header()?;
-        for i in 0..header.num_keys as usize {
-            let entry = page.get_entry(i)?;
-            if entry.key == key {
-                return Ok(Some(i));
-            }
-        }
-        Ok(None)
-    }
-
-    /// Update entry at position in page
-    fn update_entry(page: &mut IndexPage, pos: usize, entry: &IndexEntry) -> IoResult<()> {
-        let header = page.header()?;
-        if pos >= header.num_keys as usize {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Entry position out of range",
-            ));
-        }
-
-        let header_size = std::mem::size_of::<super::page::IndexPageHeader>();
-        let entry_size = std::mem::size_of::<IndexEntry>();
-        let offset = header_size + pos * entry_size;
-
-        let entry_bytes = unsafe {
-            std::slice::from_raw_parts(
-                entry as *const IndexEntry as *const u8,
-                entry_size,
-            )
-        };
-        page.data[offset..offset + entry_size].copy_from_slice(entry_bytes);
-        Ok(())
-    }
 }
 
 impl super::Index for HashIndex {
@@ -165,21 +129,13 @@ impl super::Index for HashIndex {
         let bucket_hash = self.hash_key(key);
         let first_page_id = self.get_bucket_page(bucket_hash, disk_mgr)?;
 
-        // Search through bucket chain (first page and any overflow pages)
+        // Append to the first page of the bucket chain with room: entries
+        // for a key that is already present are kept alongside the new one
         let mut current_id = first_page_id;
         loop {
             let page_data = disk_mgr.read_page(current_id)?;
             let mut current_page = IndexPage { data: page_data };
 
-            // Check if key already exists in this page
-            if let Some(pos) = Self::search_in_page(&current_page, key)? {
-                // Update existing entry
-                let entry = IndexEntry::new(key, pointer);
-                Self::update_entry(&mut current_page, pos, &entry)?;
-                disk_mgr.write_page(current_id, &current_page.data)?;
-                return Ok(None);
-            }
-
             // Try to insert at end of this page
             let entry = IndexEntry::new(key, pointer);
             let header = current_page.header()?;
@@ -223,32 +179,32 @@ impl super::Index for HashIndex {
         &self,
         key: u64,
         disk_mgr: &IndexFile,
-    ) -> IoResult<Option<TuplePointer>> {
+    ) -> IoResult<Vec<TuplePointer>> {
         let bucket_hash = self.hash_key(key);
+        let mut results = Vec::new();
 
         // Get first page for bucket, or return not found if bucket doesn't exist
         let first_page_id = match self.bucket_pages.get(&bucket_hash) {
             Some(&page_id) => page_id,
-            None => return Ok(None),
+            None => return Ok(results),
         };
 
-        // Search through bucket chain
+        // Collect the entries for the key from every page of the bucket chain
         let mut current_id = first_page_id;
         loop {
             let page_data = disk_mgr.read_page(current_id)?;
             let current_page = IndexPage { data: page_data };
 
-            // Search for key in this page
-            if let Some(pos) = Self::search_in_page(&current_page, key)? {
-                let entry = current_page.get_entry(pos)?;
-                return Ok(Some(entry.as_tuple_pointer()));
+            for entry in current_page.entries()? {
+                if entry.key == key {
+                    results.push(entry.as_tuple_pointer());
+                }
             }
 
-            // Not found in this page, check next
             match current_page.next_sibling()? {
                 Some(next_id) => current_id = next_id,
-                None => return Ok(None),
+                None => return Ok(results),
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -39,8 +39,10 @@ pub trait Index: Send + Sync {
     /// Returns None if no split occurred, Some(IndexSplit) if the index node split
     fn insert(&mut self, key: u64, pointer: TuplePointer, disk_mgr: &IndexFile) -> io::Result<Option<IndexSplit>>;
 
-    /// Search for a value by key
-    fn search(&self, key: u64, disk_mgr: &IndexFile) -> io::Result<Option<TuplePointer>>;
+    /// Search for the values stored under a key
+    /// A key may be inserted more than once (non-unique columns, new tuple
+    /// versions), and every pointer inserted for it is returned
+    fn search(&self, key: u64, disk_mgr: &IndexFile) -> io::Result<Vec<TuplePointer>>;
 
     /// Range scan - return all entries in [start_key, end_key] inclusive
     /// Default implementation: returns empty vec (override for ordered indexes)
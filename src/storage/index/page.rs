@@ -189,6 +189,19 @@ impl IndexPage {
         }
     }
 
+    /// Get prev sibling page ID (0 if no sibling)
+    pub fn prev_sibling(&self) -> io::Result<Option<crate::storage::base::PageId>> {
+        let header = self.header()?;
+        if header.prev_page_id == 0 {
+            Ok(None)
+        } else {
+            let raw = header.prev_page_id;
+            let segment = (raw >> 16) as u16;
+            let offset = (raw & 0xFFFF) as u16;
+            Ok(Some(crate::storage::base::PageId::new(segment, offset)))
+        }
+    }
+
     /// Set next sibling page ID
     pub fn set_next_sibling(&mut self, next_id: Option<crate::storage::base::PageId>) -> io::Result<()> {
         let mut header = self.header()?;
@@ -233,33 +246,32 @@ impl IndexPage {
         Ok(entry)
     }
 
-    /// Binary search for key position
-    /// Returns: (found, position_to_insert)
-    pub fn binary_search(&self, key: u64) -> io::Result<(bool, usize)> {
-        let header = self.header()?;
-        let count = header.num_keys as usize;
+    /// Position of the first entry with a key >= `key`
+    pub fn lower_bound(&self, key: u64) -> io::Result<usize> {
+        self.partition_point(|entry_key| entry_key < key)
+    }
 
-        if count == 0 {
-            return Ok((false, 0));
-        }
+    /// Position of the first entry with a key > `key`
+    /// Entries with equal keys are kept in insertion order, so this is where
+    /// another entry for `key` goes.
+    pub fn upper_bound(&self, key: u64) -> io::Result<usize> {
+        self.partition_point(|entry_key| entry_key <= key)
+    }
 
+    /// Number of leading entries whose key satisfies `pred`, for a predicate
+    /// that holds for a prefix of the page
+    fn partition_point(&self, pred: impl Fn(u64) -> bool) -> io::Result<usize> {
         let mut left = 0;
-        let mut right = count;
-
+        let mut right = self.header()?.num_keys as usize;
         while left < right {
             let mid = (left + right) / 2;
-            let mid_entry = self.get_entry(mid)?;
-
-            if mid_entry.key == key {
-                return Ok((true, mid));
-            } else if mid_entry.key < key {
+            if pred(self.get_entry(mid)?.key) {
                 left = mid + 1;
             } else {
                 right = mid;
             }
         }
-
-        Ok((false, left))
+        Ok(left)
     }
 
     /// Insert entry at position (shifts others right)
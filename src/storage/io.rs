@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::fs::{File, OpenOptions};
 use std::io::{self, Result};
 use std::os::unix::fs::FileExt;
@@ -7,6 +8,19 @@ use std::path::Path;
 /// Alignment requirement for Direct I/O (4KB on most systems)
 pub const ALIGNMENT: usize = 4096;
 
+thread_local! {
+    /// Blocks and pages read from disk by this thread
+    static READS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Number of blocks and pages the current thread has read from disk so far
+///
+/// Nothing is cached between reads, so every read goes to disk. Callers take
+/// the difference between two calls to count the reads of an operation.
+pub fn reads() -> u64 {
+    READS.with(Cell::get)
+}
+
 pub struct Disk {
     file: File,
 }
@@ -73,6 +87,7 @@ impl Disk {
             ));
         }
 
+        READS.with(|reads| reads.set(reads.get() + 1));
         self.file.read_at(buf, offset)
     }
 
@@ -103,9 +118,14 @@ impl Disk {
 
         self.file.write_at(buf, offset)
     }
+
+    /// Truncate or extend the underlying file to `len` bytes
+    pub fn set_len(&self, len: u64) -> Result<()> {
+        self.file.set_len(len)
+    }
 }
 
-/// Allocate an aligned buffer for Direct I/O
+/// Allocate a zeroed, aligned buffer for Direct I/O
 pub fn alloc_aligned(size: usize) -> Vec<u8> {
     // Ensure size is aligned
     let aligned_size = (size + ALIGNMENT - 1) / ALIGNMENT * ALIGNMENT;
@@ -115,7 +135,7 @@ pub fn alloc_aligned(size: usize) -> Vec<u8> {
         .expect("invalid layout");
 
     unsafe {
-        let ptr = std::alloc::alloc(layout);
+        let ptr = std::alloc::alloc_zeroed(layout);
         if ptr.is_null() {
             std::alloc::handle_alloc_error(layout);
         }
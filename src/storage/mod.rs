@@ -5,28 +5,104 @@ pub mod index;
 pub mod files;
 pub mod catalog;
 pub mod wal;
+pub mod bitmap;
 
 // Re-export for extension types
 pub use self::base::TuplePointer;
 pub use base::PageId;
+pub use self::io::reads as block_reads;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, atomic::{AtomicU8, Ordering}};
 use std::path::PathBuf;
 use parking_lot::{Mutex, RwLock};
 use serde::{Serialize, Deserialize};
 use bincode::{Encode, Decode};
 use tracing::debug;
-use crate::types::{Row, Schema};
+use zerocopy::IntoBytes;
+use crate::types::{DataType, Row, Schema, Value};
 use crate::config::Config;
+use crate::executor::numeric;
 #[cfg(feature = "extensions")]
-use crate::extensions::registry::{TypeRegistry, OperatorRegistry, FunctionRegistry};
+use crate::extensions::registry::{TypeRegistry, CastRegistry, OperatorRegistry, FunctionRegistry, Registries};
+use self::base::{Block, TupleMeta, TxId, TUPLE_HEAP_ONLY};
 use self::index::IndexBuilderRegistry;
+use self::index::page::{IndexPage, NodeType};
 use self::files::{TableFile, IndexFile};
-use self::catalog::Catalog;
+use self::catalog::{Catalog, TableStatistics};
+use self::wal::{TupleRecord, WalEntry, WalEntryType, WalFile};
+use self::bitmap::TupleBitmap;
 
 pub type Result<T> = std::result::Result<T, String>;
 
+/// Convert a column value into the u64 key stored in indexes
+/// Returns None for NULL (never indexed). Strings, numerics, intervals, UUIDs and
+/// byte strings are hashed, so callers must recheck the fetched row against the
+/// probe value.
+pub fn index_key(value: &Value) -> Result<Option<u64>> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    match value {
+        Value::Int(n) => Ok(Some(*n as u64)),
+        // Dates, times and timestamps key by their day or microsecond count
+        Value::Date(d) => Ok(Some(*d as i64 as u64)),
+        Value::Time(m) | Value::Timestamp(m) | Value::TimestampTz(m) => Ok(Some(*m as u64)),
+        Value::Float(f) => Ok(Some(f.to_bits())),
+        Value::Bool(b) => Ok(Some(*b as u64)),
+        Value::String(s) => {
+            let mut hasher = DefaultHasher::new();
+            s.hash(&mut hasher);
+            Ok(Some(hasher.finish()))
+        }
+        // Equal numerics (1.5 and 1.50) have equal normalized forms
+        Value::Numeric(n) => {
+            let mut hasher = DefaultHasher::new();
+            numeric::normalized(n).hash(&mut hasher);
+            Ok(Some(hasher.finish()))
+        }
+        // Equal intervals (1 day and 24 hours) have equal spans
+        Value::Interval(interval) => {
+            let mut hasher = DefaultHasher::new();
+            interval.span().hash(&mut hasher);
+            Ok(Some(hasher.finish()))
+        }
+        Value::Uuid(uuid) => {
+            let mut hasher = DefaultHasher::new();
+            uuid.hash(&mut hasher);
+            Ok(Some(hasher.finish()))
+        }
+        Value::Bytea(bytes) => {
+            let mut hasher = DefaultHasher::new();
+            bytes.hash(&mut hasher);
+            Ok(Some(hasher.finish()))
+        }
+        Value::Null => Ok(None),
+        Value::Extension { type_oid, .. } => Err(format!(
+            "Extension values (type_oid: {}) cannot be used as index keys",
+            type_oid
+        )),
+    }
+}
+
+/// Position of the primary key column (first column if none is flagged)
+fn primary_key_position(schema: &Schema) -> usize {
+    schema.columns.iter().position(|col| col.is_primary_key).unwrap_or(0)
+}
+
+/// Serialize a row into a tuple payload
+fn encode_row(row: &Row) -> Result<Vec<u8>> {
+    bincode::encode_to_vec(row, bincode::config::standard())
+        .map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Deserialize a tuple payload back into a row
+fn decode_row(payload: &[u8]) -> Result<Row> {
+    bincode::decode_from_slice(payload, bincode::config::standard())
+        .map(|(row, _)| row)
+        .map_err(|e| format!("Deserialization error: {}", e))
+}
+
 /// Compute simple checksum for metadata validation
 fn compute_checksum(data: &[u8]) -> u64 {
     data.iter().fold(0u64, |acc, &byte| {
@@ -115,20 +191,24 @@ pub struct Database {
     catalog: Catalog,
     /// Index builder registry (always available with builtins)
     pub index_builder_registry: Arc<IndexBuilderRegistry>,
-    /// Extension registries for types, operators, functions
-    #[cfg(feature = "extensions")]
-    pub type_registry: Arc<TypeRegistry>,
+    /// Types, casts, operators and functions SQL expressions resolve against
     #[cfg(feature = "extensions")]
-    pub operator_registry: Arc<OperatorRegistry>,
-    #[cfg(feature = "extensions")]
-    pub function_registry: Arc<FunctionRegistry>,
+    pub registries: Registries,
+    /// Write-ahead log for tuple changes
+    wal: WalFile,
+    /// Next transaction ID handed out to a write statement
+    next_txid: TxId,
 }
 
 impl Database {
-    pub fn new(config: &Config) -> Self {
+    pub fn new(config: &Config) -> Result<Self> {
         // Initialize global catalog from catalog.db or create new
         let catalog = Catalog::new();
 
+        let wal_path = config.data_dir.join("wal.log");
+        let wal = WalFile::open(&wal_path)
+            .map_err(|e| format!("Failed to open write-ahead log {}: {}", wal_path.display(), e))?;
+
         // Always initialize index_builder_registry with builtins
         let mut index_builder_registry = IndexBuilderRegistry::new();
         crate::extensions::builtin::register_builtin_indexes(&mut index_builder_registry);
@@ -139,8 +219,10 @@ impl Database {
             let mut type_registry = TypeRegistry::new();
             crate::extensions::builtin::register_builtin_types(&mut type_registry);
 
+            let mut cast_registry = CastRegistry::new();
             let mut operator_registry = OperatorRegistry::new();
             let mut function_registry = FunctionRegistry::new();
+            crate::extensions::builtin::register_builtin_functions(&mut function_registry);
 
             // Load extensions based on config
             let enabled_extensions = if config.load_all_extensions {
@@ -152,6 +234,7 @@ impl Database {
             // Load all registered extensions (auto-discovered via inventory)
             crate::extensions::loader::load_all_extensions(
                 &mut type_registry,
+                &mut cast_registry,
                 &mut operator_registry,
                 &mut function_registry,
                 enabled_extensions,
@@ -162,10 +245,15 @@ impl Database {
                 index_files: HashMap::new(),
                 tables: HashMap::new(),
                 catalog,
-                type_registry: Arc::new(type_registry),
-                operator_registry: Arc::new(operator_registry),
-                function_registry: Arc::new(function_registry),
+                registries: Registries {
+                    types: Arc::new(type_registry),
+                    casts: Arc::new(cast_registry),
+                    operators: Arc::new(operator_registry),
+                    functions: Arc::new(function_registry),
+                },
                 index_builder_registry: Arc::new(index_builder_registry),
+                wal,
+                next_txid: 1,
             }
         };
 
@@ -176,102 +264,79 @@ impl Database {
             tables: HashMap::new(),
             catalog,
             index_builder_registry: Arc::new(index_builder_registry),
+            wal,
+            next_txid: 1,
         };
 
         // Try to load catalog from disk (TODO: implement catalog.db disk I/O)
         let _ = db.load_catalog_from_disk();
 
-        db
+        Ok(db)
     }
 
     /// Load catalog from catalog.db file
     fn load_catalog_from_disk(&mut self) -> Result<()> {
         use std::fs;
 
-        // Try to load from active segment (0 or 1)
-        let active_seg = self.catalog.active_segment();
-        let catalog_path = format!("catalog_{}.db", active_seg);
-
-        let data = match fs::read(&catalog_path) {
-            Ok(data) => data,
-            Err(_) => return Ok(()), // No catalog file yet, start with empty
-        };
-
-        match catalog::Catalog::deserialize(&data) {
-            Ok(loaded_catalog) => {
-                // Replace catalog with loaded version
-                self.catalog = loaded_catalog;
-
-                // Reconstruct runtime metadata and indexes from catalog
-                for table_meta in self.catalog.all_tables() {
-                    // Open table file
-                    let table_path = PathBuf::from(&table_meta.file_path);
-                    let table_file = TableFile::open(&table_path)
-                        .map_err(|e| format!("Failed to open table file during recovery: {}", e))?;
-
-                    // Reconstruct primary index if it exists
-                    let primary_index = if let Some(index_meta) = &table_meta.primary_index {
-                        let index_path = PathBuf::from(&index_meta.file_path);
-                        let index_file = IndexFile::open(&index_path)
-                            .map_err(|e| format!("Failed to open index file during recovery: {}", e))?;
-
-                        let root_page_id = base::PageId::new(index_meta.root_page_segment, index_meta.root_page_offset);
-                        let index = self.index_builder_registry.create_index(&index_meta.index_type, Some(root_page_id))
-                            .ok_or_else(|| format!("Failed to create {} index during recovery", index_meta.index_type))?;
-
-                        self.index_files.insert(table_meta.name.clone(), Arc::new(index_file));
-
-                        // Get primary key column from schema
-                        let pk_column = table_meta.schema.columns.iter()
-                            .find(|col| col.is_primary_key)
-                            .or_else(|| table_meta.schema.columns.first())
-                            .map(|col| col.name.clone())
-                            .unwrap_or_else(|| "".to_string());
-
-                        Some(IndexMetadata {
-                            name: index_meta.name.clone(),
-                            column: pk_column,
-                            index_type: index_meta.index_type.clone(),
-                            index: Arc::new(Mutex::new(index)),
-                        })
-                    } else {
-                        None
-                    };
-
-                    // Build runtime table metadata
-                    let runtime_meta = TableMetadata {
-                        name: table_meta.name.clone(),
-                        file_path: table_path,
-                        schema: table_meta.schema.clone(),
-                        primary_index,
-                        secondary_indexes: Vec::new(),
-                    };
-
-                    self.tables.insert(table_meta.name.clone(), Arc::new(RwLock::new(runtime_meta)));
-                    self.table_files.insert(table_meta.name.clone(), Arc::new(table_file));
-                }
-
-                Ok(())
-            }
-            Err(_) => {
-                // Corruption in active segment, try inactive
-                let inactive_seg = self.catalog.inactive_segment();
-                let fallback_path = format!("catalog_{}.db", inactive_seg);
-
-                let fallback_data = fs::read(&fallback_path)
-                    .map_err(|_| "Failed to load catalog from either segment".to_string())?;
-
-                let fallback_catalog = catalog::Catalog::deserialize(&fallback_data)
-                    .map_err(|e| format!("Failed to deserialize fallback catalog: {}", e))?;
+        // Both segments are read; the newest valid one wins
+        let segments = [0, 1].map(|segment| fs::read(format!("catalog_{}.db", segment)).ok());
+        if segments.iter().all(Option::is_none) {
+            return Ok(()); // No catalog file yet, start with empty
+        }
+        self.catalog = catalog::Catalog::load(segments)
+            .ok_or_else(|| "Failed to load catalog from either segment".to_string())?;
+        debug!(segment = self.catalog.active_segment(), "catalog loaded");
+
+        // Reconstruct runtime metadata and indexes from catalog
+        for table_meta in self.catalog.all_tables() {
+            // Open table file
+            let table_path = PathBuf::from(&table_meta.file_path);
+            let table_file = TableFile::open(&table_path)
+                .map_err(|e| format!("Failed to open table file during recovery: {}", e))?;
+
+            // Reconstruct primary index if it exists
+            let primary_index = if let Some(index_meta) = &table_meta.primary_index {
+                let index_path = PathBuf::from(&index_meta.file_path);
+                let index_file = IndexFile::open(&index_path)
+                    .map_err(|e| format!("Failed to open index file during recovery: {}", e))?;
+
+                let root_page_id = base::PageId::new(index_meta.root_page_segment, index_meta.root_page_offset);
+                let index = self.index_builder_registry.create_index(&index_meta.index_type, Some(root_page_id))
+                    .ok_or_else(|| format!("Failed to create {} index during recovery", index_meta.index_type))?;
+
+                self.index_files.insert(table_meta.name.clone(), Arc::new(index_file));
+
+                // Get primary key column from schema
+                let pk_column = table_meta.schema.columns.iter()
+                    .find(|col| col.is_primary_key)
+                    .or_else(|| table_meta.schema.columns.first())
+                    .map(|col| col.name.clone())
+                    .unwrap_or_else(|| "".to_string());
+
+                Some(IndexMetadata {
+                    name: index_meta.name.clone(),
+                    column: pk_column,
+                    index_type: index_meta.index_type.clone(),
+                    index: Arc::new(Mutex::new(index)),
+                })
+            } else {
+                None
+            };
 
-                // Use fallback catalog and flip segment
-                self.catalog = fallback_catalog;
-                self.catalog.flip_segment();
+            // Build runtime table metadata
+            let runtime_meta = TableMetadata {
+                name: table_meta.name.clone(),
+                file_path: table_path,
+                schema: table_meta.schema.clone(),
+                primary_index,
+                secondary_indexes: Vec::new(),
+            };
 
-                // Recursively load with fallback catalog
-                self.load_catalog_from_disk()
-            }
+            self.tables.insert(table_meta.name.clone(), Arc::new(RwLock::new(runtime_meta)));
+            self.table_files.insert(table_meta.name.clone(), Arc::new(table_file));
         }
+
+        Ok(())
     }
 
     /// Save catalog to catalog.db file with atomic flip
@@ -285,6 +350,7 @@ impl Database {
         let final_path = format!("catalog_{}.db", inactive_seg);
 
         // Serialize catalog
+        self.catalog.advance_generation();
         let data = self.catalog.serialize()
             .map_err(|e| format!("Failed to serialize catalog: {}", e))?;
 
@@ -329,9 +395,11 @@ impl Database {
         let index_file = IndexFile::open(&index_file_path)
             .map_err(|e| format!("Failed to open index file: {}", e))?;
 
-        // Allocate root page for the primary index
+        // Allocate and initialize root page for the primary index
         let root_page_id = index_file.allocate_page()
             .map_err(|e| format!("Failed to allocate index root page: {}", e))?;
+        index_file.write_page(root_page_id, &IndexPage::new(NodeType::Leaf).data)
+            .map_err(|e| format!("Failed to initialize index root page: {}", e))?;
 
         // Create BTree index via registry
         let index = self.index_builder_registry.create_index("btree", Some(root_page_id))
@@ -339,7 +407,7 @@ impl Database {
 
         let primary_index = Some(IndexMetadata {
             name: "pk".to_string(),
-            column: "".to_string(), // Primary key column determined by schema
+            column: schema.columns[primary_key_position(&schema)].name.clone(),
             index_type: "btree".to_string(),
             index: Arc::new(Mutex::new(index)),
         });
@@ -376,6 +444,7 @@ impl Database {
             next_segment_id: 1, // We allocated segment 0
             primary_index: Some(primary_index_meta),
             secondary_indexes: Vec::new(),
+            statistics: None,
         };
 
         self.catalog.add_table(table_meta)
@@ -393,14 +462,194 @@ impl Database {
             .ok_or_else(|| format!("Table not found: {}", name))
     }
 
+    /// Allocate a transaction ID for a write statement
+    pub fn allocate_txid(&mut self) -> TxId {
+        let txid = self.next_txid;
+        self.next_txid += 1;
+        txid
+    }
+
+    fn get_table_file(&self, table_name: &str) -> Result<Arc<TableFile>> {
+        self.table_files.get(table_name)
+            .cloned()
+            .ok_or_else(|| format!("Table not found: {}", table_name))
+    }
+
+    fn get_index_file(&self, key: &str) -> Result<Arc<IndexFile>> {
+        self.index_files.get(key)
+            .cloned()
+            .ok_or_else(|| format!("Index file not found: {}", key))
+    }
+
+    /// Append an Insert/Update/Delete record to the write-ahead log
+    fn log_tuple(&mut self, entry_type: WalEntryType, record: &TupleRecord) -> Result<()> {
+        let payload = record.encode()
+            .map_err(|e| format!("Failed to encode WAL record: {}", e))?;
+        let lsn = self.wal.next_offset();
+        self.wal.append(&WalEntry::new(entry_type, payload, lsn))
+            .map_err(|e| format!("Failed to append to WAL: {}", e))?;
+        Ok(())
+    }
+
+    /// Write a tuple version into the table heap
+    /// Appends to the last used block, then falls back to new blocks and segments
+    fn place_tuple(table_file: &TableFile, tuple: &[u8]) -> Result<TuplePointer> {
+        if table_file.next_segment_id() == 0 {
+            table_file.allocate_segment()
+                .map_err(|e| format!("Failed to allocate segment: {}", e))?;
+        }
+        let segment_id = table_file.next_segment_id() - 1;
+
+        let header = table_file.read_segment_header(segment_id)
+            .map_err(|e| format!("Failed to read segment header: {}", e))?;
+
+        // Segment 0 block 0 is reserved for the table header
+        let start_block = if segment_id == 0 { 1 } else { 0 };
+        let last_used = (start_block..base::BLOCKS_PER_UNCOMPRESSED_SEGMENT as u8)
+            .rev()
+            .find(|&block_id| !header.is_block_free(block_id));
+
+        if let Some(block_id) = last_used {
+            let mut block = table_file.read_block(segment_id, block_id)
+                .map_err(|e| format!("Failed to read block: {}", e))?;
+            if let Some(slot_id) = block.append_tuple(tuple) {
+                table_file.write_block(segment_id, block_id, &block)
+                    .map_err(|e| format!("Failed to write block: {}", e))?;
+                return Ok(TuplePointer::new(segment_id, block_id, slot_id));
+            }
+        }
+
+        // Last block is full: take a fresh block, growing the file if the segment is full
+        let (segment_id, block_id) = match table_file.allocate_block(segment_id)
+            .map_err(|e| format!("Failed to allocate block: {}", e))? {
+            Some(block_id) => (segment_id, block_id),
+            None => {
+                let segment_id = table_file.allocate_segment()
+                    .map_err(|e| format!("Failed to allocate segment: {}", e))?;
+                let block_id = table_file.allocate_block(segment_id)
+                    .map_err(|e| format!("Failed to allocate block: {}", e))?
+                    .ok_or_else(|| "Newly allocated segment has no free blocks".to_string())?;
+                (segment_id, block_id)
+            }
+        };
+
+        let mut block = Block::new();
+        let slot_id = block.append_tuple(tuple)
+            .ok_or_else(|| format!("Tuple of {} bytes does not fit in a block", tuple.len()))?;
+        table_file.write_block(segment_id, block_id, &block)
+            .map_err(|e| format!("Failed to write block: {}", e))?;
+
+        Ok(TuplePointer::new(segment_id, block_id, slot_id))
+    }
+
+    /// Follow the version chain starting at `ptr` to the live version of the tuple
+    /// Returns None if the tuple (and all its successors) are dead
+    fn resolve_version(table_file: &TableFile, ptr: TuplePointer) -> Result<Option<(TuplePointer, Row)>> {
+        let mut current = ptr;
+        loop {
+            let block = table_file.read_block(current.segment_id, current.block_id)
+                .map_err(|e| format!("Failed to read block: {}", e))?;
+            let (meta, payload) = match block.read_versioned_tuple(current.slot_id) {
+                Some(tuple) => tuple,
+                None => return Ok(None),
+            };
+
+            if !meta.is_deleted() {
+                return Ok(Some((current, decode_row(payload)?)));
+            }
+
+            match meta.next() {
+                Some(next) => current = next,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Look up the live tuples for `key` in an index, rechecking the key
+    /// against each row
+    /// Several entries may lead to the same live version (an older version
+    /// and its successor), which is returned once.
+    fn find_live_by_key(
+        table_file: &TableFile,
+        index_meta: &IndexMetadata,
+        index_file: &IndexFile,
+        column_idx: usize,
+        key: u64,
+    ) -> Result<Vec<(TuplePointer, Row)>> {
+        let ptrs = index_meta.index.lock().search(key, index_file)
+            .map_err(|e| format!("Index search error: {}", e))?;
+
+        let mut seen = HashSet::new();
+        let mut tuples = Vec::new();
+        for ptr in ptrs {
+            if let Some((ptr, row)) = Self::live_version_with_key(table_file, ptr, column_idx, key)?
+                && seen.insert(ptr)
+            {
+                tuples.push((ptr, row));
+            }
+        }
+        Ok(tuples)
+    }
+
+    /// Resolve the tuple an index entry for `key` points at to its live
+    /// version, if that still has the key
+    fn live_version_with_key(
+        table_file: &TableFile,
+        ptr: TuplePointer,
+        column_idx: usize,
+        key: u64,
+    ) -> Result<Option<(TuplePointer, Row)>> {
+        // Index entries may point at an older version whose successor changed the key
+        match Self::resolve_version(table_file, ptr)? {
+            Some((ptr, row)) => {
+                let row_key = row.get(column_idx).map(index_key).transpose()?.flatten();
+                if row_key == Some(key) {
+                    Ok(Some((ptr, row)))
+                } else {
+                    Ok(None)
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Insert `ptr` into every secondary index of the table for the given row
+    fn insert_secondary_keys(&self, metadata: &TableMetadata, row: &Row, ptr: TuplePointer) -> Result<()> {
+        for idx_meta in &metadata.secondary_indexes {
+            let col_idx = metadata.schema.get_column_index(&idx_meta.column)
+                .ok_or_else(|| format!("Indexed column not found: {}", idx_meta.column))?;
+
+            // NULLs are not indexed
+            let Some(key) = row.get(col_idx).map(index_key).transpose()?.flatten() else {
+                continue;
+            };
+
+            let index_file = self.get_index_file(&format!("{}_{}", metadata.name, idx_meta.name))?;
+            idx_meta.index.lock().insert(key, ptr, &index_file)
+                .map_err(|e| format!("Failed to insert into index {}: {}", idx_meta.name, e))?;
+        }
+        Ok(())
+    }
+
+    /// Extract the primary key of a row as an index key
+    fn primary_key(schema: &Schema, row: &Row) -> Result<u64> {
+        let key_value = row.get(primary_key_position(schema))
+            .ok_or_else(|| "Row must have at least one column for primary key".to_string())?;
+
+        match key_value {
+            Value::Int(n) | Value::Time(n) | Value::Timestamp(n) | Value::TimestampTz(n) => Ok(*n as u64),
+            Value::Date(d) => Ok(*d as i64 as u64),
+            // UUID keys are hashed, as in secondary indexes
+            Value::Uuid(_) => index_key(key_value).map(Option::unwrap_or_default),
+            Value::Null => Err("Primary key cannot be NULL".to_string()),
+            _ => Err(format!("Primary key must be Int type, got {:?}", key_value)),
+        }
+    }
+
     pub fn insert_row(&mut self, table_name: &str, row: Row) -> Result<()> {
-        let table_file = self.table_files.get(table_name)
-            .ok_or_else(|| format!("Table not found: {}", table_name))?
-            .clone();
+        let table_file = self.get_table_file(table_name)?;
 
-        let metadata_arc = self.tables.get(table_name)
-            .ok_or_else(|| format!("Table not found: {}", table_name))?
-            .clone();
+        let metadata_arc = self.get_table(table_name)?;
         let metadata = metadata_arc.read();
 
         // Validate row against schema
@@ -412,197 +661,538 @@ impl Database {
             ));
         }
 
-        // Serialize row to bytes
-        let row_bytes = bincode::encode_to_vec(&row, bincode::config::standard())
-            .map_err(|e| format!("Serialization error: {}", e))?;
-
-        // Try to allocate block in segment 0 (first segment)
-        let segment_id = 0u32;
-        let block_id = table_file.allocate_block(segment_id)
-            .map_err(|e| format!("Failed to allocate block: {}", e))?
-            .ok_or_else(|| "Segment full - need to allocate new segment".to_string())?;
+        // Enforce primary key uniqueness before touching the heap
+        let pk = match &metadata.primary_index {
+            Some(primary_index_meta) => {
+                let key = Self::primary_key(&metadata.schema, &row)?;
+                let index_file = self.get_index_file(table_name)?;
+                let pk_pos = primary_key_position(&metadata.schema);
+                if !Self::find_live_by_key(&table_file, primary_index_meta, &index_file, pk_pos, key)?.is_empty() {
+                    return Err(format!(
+                        "duplicate key value violates unique constraint \"{}_pkey\"",
+                        table_name
+                    ));
+                }
+                Some((primary_index_meta, index_file, key))
+            }
+            None => None,
+        };
 
-        // Read block, append tuple, write back
-        let mut block = table_file.read_block(segment_id, block_id)
-            .map_err(|e| format!("Failed to read block: {}", e))?;
+        // Serialize row to bytes, prefixed with its MVCC header
+        let txid = self.next_txid;
+        let mut tuple = TupleMeta::new(txid).as_bytes().to_vec();
+        tuple.extend_from_slice(&encode_row(&row)?);
 
-        let slot_id = block.append_tuple(&row_bytes)
-            .ok_or_else(|| "Block full".to_string())?;
+        let tuple_ptr = Self::place_tuple(&table_file, &tuple)?;
 
-        table_file.write_block(segment_id, block_id, &block)
-            .map_err(|e| format!("Failed to write block: {}", e))?;
+        // Update primary key index if table has one
+        if let Some((primary_index_meta, index_file, key)) = pk {
+            let mut index_guard = primary_index_meta.index.lock();
+            index_guard.insert(key, tuple_ptr, &index_file)
+                .map_err(|e| format!("Failed to insert into primary index: {}", e))?;
+        }
 
-        // Create tuple pointer for the inserted row
-        let tuple_ptr = TuplePointer::new(segment_id, block_id, slot_id);
+        self.insert_secondary_keys(&metadata, &row, tuple_ptr)?;
+        drop(metadata);
+
+        self.next_txid += 1;
+        self.log_tuple(WalEntryType::Insert, &TupleRecord {
+            txid,
+            table: table_name.to_string(),
+            old: None,
+            new: Some(tuple_ptr),
+            data: tuple,
+        })
+    }
 
-        // Update primary key index if table has one
-        if let Some(primary_index_meta) = &metadata.primary_index {
-            // Extract primary key from first column (TODO: assume first column is PK)
-            let key_value = row.get(0)
-                .ok_or_else(|| "Row must have at least one column for primary key".to_string())?;
-
-            // Convert Value to u64 key (handle Int type)
-            let key = match key_value {
-                crate::types::Value::Int(n) => *n as u64,
-                crate::types::Value::Null => return Err("Primary key cannot be NULL".to_string()),
-                _ => return Err(format!("Primary key must be Int type, got {:?}", key_value)),
-            };
+    /// Write new versions of the live tuples in `updates` as one statement
+    ///
+    /// Every new primary key is checked before any version is written, so a
+    /// conflicting statement leaves the table unchanged.
+    pub fn update_rows(&mut self, table_name: &str, updates: Vec<(TuplePointer, Row)>, txid: TxId) -> Result<()> {
+        self.check_primary_keys(table_name, &updates)?;
+        for (ptr, new_row) in updates {
+            self.update_row(table_name, ptr, new_row, txid)?;
+        }
+        Ok(())
+    }
 
-            // Get index file
-            let index_file = self.index_files.get(table_name)
-                .ok_or_else(|| format!("Index file not found for table: {}", table_name))?;
+    /// Check that the primary keys an UPDATE leaves behind are unique
+    ///
+    /// A new key may reuse one the statement itself moves away from, but not
+    /// one still held by a row outside `updates` or given to two updated rows.
+    fn check_primary_keys(&self, table_name: &str, updates: &[(TuplePointer, Row)]) -> Result<()> {
+        let table_file = self.get_table_file(table_name)?;
+        let metadata_arc = self.get_table(table_name)?;
+        let metadata = metadata_arc.read();
 
-            // Lock index and insert
-            let mut index_guard = primary_index_meta.index.lock();
-            index_guard.insert(key, tuple_ptr, index_file)
-                .map_err(|e| format!("Failed to insert into primary index: {}", e))?;
+        for (_, new_row) in updates {
+            if new_row.len() != metadata.schema.len() {
+                return Err(format!(
+                    "Row has {} columns but schema expects {}",
+                    new_row.len(),
+                    metadata.schema.len()
+                ));
+            }
         }
 
+        let Some(primary_index_meta) = &metadata.primary_index else {
+            return Ok(());
+        };
+        let index_file = self.get_index_file(table_name)?;
+        let pk_pos = primary_key_position(&metadata.schema);
+        let duplicate = || format!(
+            "duplicate key value violates unique constraint \"{}_pkey\"",
+            table_name
+        );
+
+        let updated: HashSet<TuplePointer> = updates.iter().map(|(ptr, _)| *ptr).collect();
+        let mut new_keys = HashSet::new();
+        for (ptr, new_row) in updates {
+            let new_key = Self::primary_key(&metadata.schema, new_row)?;
+            if !new_keys.insert(new_key) {
+                return Err(duplicate());
+            }
+            let holders = Self::find_live_by_key(&table_file, primary_index_meta, &index_file, pk_pos, new_key)?;
+            if holders.iter().any(|(holder, _)| holder != ptr && !updated.contains(holder)) {
+                return Err(duplicate());
+            }
+        }
         Ok(())
     }
 
-    pub fn scan_table(&self, table_name: &str) -> Result<Vec<Row>> {
-        let table_file = self.table_files.get(table_name)
-            .ok_or_else(|| format!("Table not found: {}", table_name))?;
+    /// Write a new version of the tuple at `ptr` (MVCC update)
+    ///
+    /// When no indexed column changes, the new version is written to the same
+    /// block as a heap-only tuple (HOT) and indexes keep pointing at the old
+    /// version. Otherwise the new version goes through the normal insert path
+    /// and the primary and secondary indexes are pointed at it. Primary key
+    /// conflicts are checked for the whole statement by `update_rows`.
+    fn update_row(&mut self, table_name: &str, ptr: TuplePointer, new_row: Row, txid: TxId) -> Result<TuplePointer> {
+        let table_file = self.get_table_file(table_name)?;
 
-        let mut rows = Vec::new();
+        let metadata_arc = self.get_table(table_name)?;
+        let metadata = metadata_arc.read();
 
-        // Scan segment 0 (first segment allocated)
-        let segment_id = 0u32;
-        let header = table_file.read_segment_header(segment_id)
-            .map_err(|e| format!("Failed to read segment header: {}", e))?;
+        let mut block = table_file.read_block(ptr.segment_id, ptr.block_id)
+            .map_err(|e| format!("Failed to read block: {}", e))?;
+        let (mut old_meta, old_payload) = block.read_versioned_tuple(ptr.slot_id)
+            .ok_or_else(|| format!("Tuple not found at {:?}", ptr))?;
+        if old_meta.is_deleted() {
+            return Err("could not serialize access due to concurrent update".to_string());
+        }
+        let old_row = decode_row(old_payload)?;
 
-        // Scan all used blocks
-        for block_id in 0..base::BLOCKS_PER_UNCOMPRESSED_SEGMENT as u8 {
-            if !header.is_block_free(block_id) {
-                let block = table_file.read_block(segment_id, block_id)
-                    .map_err(|e| format!("Failed to read block: {}", e))?;
-
-                // Read all slots in block
-                let slot_count = block.header().slot_count;
-                for slot_id in 0..slot_count {
-                    if let Some(tuple_bytes) = block.read_tuple(slot_id) {
-                        let (row, _): (Row, usize) = bincode::decode_from_slice(tuple_bytes, bincode::config::standard())
-                            .map_err(|e| format!("Deserialization error: {}", e))?;
-                        rows.push(row);
-                    }
-                }
+        // Indexed columns: primary key + every secondary index column
+        let pk_pos = primary_key_position(&metadata.schema);
+        let mut indexed = Vec::new();
+        if metadata.primary_index.is_some() {
+            indexed.push(pk_pos);
+        }
+        for idx_meta in &metadata.secondary_indexes {
+            if let Some(col_idx) = metadata.schema.get_column_index(&idx_meta.column) {
+                indexed.push(col_idx);
+            }
+        }
+        let indexed_changed = indexed.iter().any(|&col_idx| {
+            let old_key = old_row.get(col_idx).map(index_key);
+            let new_key = new_row.get(col_idx).map(index_key);
+            old_key != new_key
+        });
+
+        let mut new_meta = TupleMeta::new(txid);
+        let payload = encode_row(&new_row)?;
+
+        // HOT path: same block, indexes untouched
+        if !indexed_changed {
+            new_meta.flags |= TUPLE_HEAP_ONLY;
+            if let Some(slot_id) = block.append_versioned_tuple(&new_meta, &payload) {
+                let new_ptr = TuplePointer::new(ptr.segment_id, ptr.block_id, slot_id);
+                old_meta.mark_updated(txid, new_ptr, true);
+                block.set_tuple_meta(ptr.slot_id, &old_meta);
+                table_file.write_block(ptr.segment_id, ptr.block_id, &block)
+                    .map_err(|e| format!("Failed to write block: {}", e))?;
+
+                drop(metadata);
+                let mut tuple = new_meta.as_bytes().to_vec();
+                tuple.extend_from_slice(&payload);
+                self.log_tuple(WalEntryType::Update, &TupleRecord {
+                    txid,
+                    table: table_name.to_string(),
+                    old: Some(ptr),
+                    new: Some(new_ptr),
+                    data: tuple,
+                })?;
+                return Ok(new_ptr);
             }
+            // Block is full, indexes must learn about the new location
+            new_meta.flags &= !TUPLE_HEAP_ONLY;
         }
 
-        Ok(rows)
+        let pk = match &metadata.primary_index {
+            Some(primary_index_meta) => {
+                let new_key = Self::primary_key(&metadata.schema, &new_row)?;
+                Some((primary_index_meta, self.get_index_file(table_name)?, new_key))
+            }
+            None => None,
+        };
+
+        let mut tuple = new_meta.as_bytes().to_vec();
+        tuple.extend_from_slice(&payload);
+        let new_ptr = Self::place_tuple(&table_file, &tuple)?;
+
+        // Re-read the old block: placing the new version may have written to it
+        let mut block = table_file.read_block(ptr.segment_id, ptr.block_id)
+            .map_err(|e| format!("Failed to read block: {}", e))?;
+        old_meta.mark_updated(txid, new_ptr, false);
+        block.set_tuple_meta(ptr.slot_id, &old_meta);
+        table_file.write_block(ptr.segment_id, ptr.block_id, &block)
+            .map_err(|e| format!("Failed to write block: {}", e))?;
+
+        if let Some((primary_index_meta, index_file, key)) = pk {
+            primary_index_meta.index.lock().insert(key, new_ptr, &index_file)
+                .map_err(|e| format!("Failed to insert into primary index: {}", e))?;
+        }
+        self.insert_secondary_keys(&metadata, &new_row, new_ptr)?;
+        drop(metadata);
+
+        self.log_tuple(WalEntryType::Update, &TupleRecord {
+            txid,
+            table: table_name.to_string(),
+            old: Some(ptr),
+            new: Some(new_ptr),
+            data: tuple,
+        })?;
+
+        Ok(new_ptr)
     }
 
-    pub fn get_schema(&self, table_name: &str) -> Result<Schema> {
+    /// Mark the tuple at `ptr` as deleted by transaction `txid`
+    ///
+    /// The slot and its index entries stay in place: lookups resolve to a dead
+    /// version and skip it, and reclaiming the space is left to vacuum.
+    pub fn delete_row(&mut self, table_name: &str, ptr: TuplePointer, txid: TxId) -> Result<()> {
+        let table_file = self.get_table_file(table_name)?;
+
+        let mut block = table_file.read_block(ptr.segment_id, ptr.block_id)
+            .map_err(|e| format!("Failed to read block: {}", e))?;
+        let (mut meta, _) = block.read_versioned_tuple(ptr.slot_id)
+            .ok_or_else(|| format!("Tuple not found at {:?}", ptr))?;
+        if meta.is_deleted() {
+            return Err("could not serialize access due to concurrent update".to_string());
+        }
+
+        meta.mark_deleted(txid);
+        block.set_tuple_meta(ptr.slot_id, &meta);
+        table_file.write_block(ptr.segment_id, ptr.block_id, &block)
+            .map_err(|e| format!("Failed to write block: {}", e))?;
+
+        self.log_tuple(WalEntryType::Delete, &TupleRecord {
+            txid,
+            table: table_name.to_string(),
+            old: Some(ptr),
+            new: None,
+            data: Vec::new(),
+        })
+    }
+
+    /// Remove every row of a table by resetting its segments and indexes
+    pub fn truncate_table(&mut self, table_name: &str) -> Result<()> {
+        let table_file = self.get_table_file(table_name)?;
         let metadata_arc = self.get_table(table_name)?;
         let metadata = metadata_arc.read();
-        Ok(metadata.schema.clone())
-    }
 
-    /// Read a block from storage (for index/executor use)
-    pub fn read_block(&self, _segment_id: u32, _block_id: u8) -> Result<base::Block> {
-        // TODO: Implement per-table access to blocks
-        Err("read_block not yet implemented with per-file architecture".to_string())
+        table_file.truncate()
+            .map_err(|e| format!("Failed to truncate table file: {}", e))?;
+        // Segment 0 holds the table header and always exists
+        table_file.allocate_segment()
+            .map_err(|e| format!("Failed to allocate segment: {}", e))?;
+
+        if let Some(primary_index_meta) = &metadata.primary_index {
+            let index_file = self.get_index_file(table_name)?;
+            self.reset_index(primary_index_meta, &index_file)?;
+        }
+        for idx_meta in &metadata.secondary_indexes {
+            let index_file = self.get_index_file(&format!("{}_{}", table_name, idx_meta.name))?;
+            self.reset_index(idx_meta, &index_file)?;
+        }
+
+        Ok(())
     }
 
-    /// Update primary key index when a row is inserted (STUB)
-    fn update_primary_index(&mut self, _table_name: &str, _key: u64, _tuple_ptr: TuplePointer) -> Result<()> {
-        // TODO: Implement index updates when we wire up IndexFile
+    /// Replace an index with an empty one rooted at the first page of its file
+    /// The root page ID is unchanged, so the catalog entry stays valid
+    fn reset_index(&self, index_meta: &IndexMetadata, index_file: &IndexFile) -> Result<()> {
+        index_file.truncate()
+            .map_err(|e| format!("Failed to truncate index file: {}", e))?;
+        let root_page_id = index_file.allocate_page()
+            .map_err(|e| format!("Failed to allocate index root page: {}", e))?;
+        index_file.write_page(root_page_id, &IndexPage::new(NodeType::Leaf).data)
+            .map_err(|e| format!("Failed to initialize index root page: {}", e))?;
+
+        let index = self.index_builder_registry.create_index(&index_meta.index_type, Some(root_page_id))
+            .ok_or_else(|| format!("Failed to create {} index", index_meta.index_type))?;
+        *index_meta.index.lock() = index;
         Ok(())
     }
 
-    /// Point lookup using primary index
-    pub fn get_by_key(&self, table_name: &str, key: u64) -> Result<Option<TuplePointer>> {
+    /// Scan all live tuples of a table along with their addresses
+    pub fn scan_table_versions(&self, table_name: &str) -> Result<Vec<(TuplePointer, Row)>> {
+        self.scan(table_name)?.collect()
+    }
+
+    /// Iterate over the live tuples of a table, reading one block at a time
+    pub fn scan(&self, table_name: &str) -> Result<TableScan> {
+        let table_file = self.get_table_file(table_name)?;
+        Ok(TableScan {
+            segment_count: table_file.next_segment_id(),
+            table_file,
+            segment_id: 0,
+            header: None,
+            block_id: 0,
+            pending: Vec::new().into_iter(),
+        })
+    }
+
+    pub fn get_schema(&self, table_name: &str) -> Result<Schema> {
         let metadata_arc = self.get_table(table_name)?;
         let metadata = metadata_arc.read();
+        Ok(metadata.schema.clone())
+    }
 
-        // Return None if table has no primary index
-        let primary_index_meta = match &metadata.primary_index {
-            Some(idx) => idx,
-            None => {
-                debug!(table_name, "no primary index found on table");
-                return Ok(None)
-            },
+    /// Point lookup of `column = value` through the primary key or a secondary index
+    /// Returns None if the column is not indexed (caller must scan instead)
+    pub fn index_lookup(&self, table_name: &str, column: &str, value: &Value) -> Result<Option<Vec<(TuplePointer, Row)>>> {
+        let table_file = self.get_table_file(table_name)?;
+        let metadata_arc = self.get_table(table_name)?;
+        let metadata = metadata_arc.read();
+
+        let Some((col_idx, index_meta, index_file)) = self.lookup_index(&metadata, column)? else {
+            return Ok(None);
         };
 
-        // Get index file
-        let index_file = self.index_files.get(table_name)
-            .ok_or_else(|| format!("Index file not found for table: {}", table_name))?;
+        // NULL never compares equal
+        let Some(key) = index_key(value)? else {
+            return Ok(Some(Vec::new()));
+        };
 
-        // Lock index and search
-        let index_guard = primary_index_meta.index.lock();
-        index_guard.search(key, index_file)
-            .map_err(|e| format!("Failed to search primary index: {}", e))
+        Ok(Some(Self::find_live_by_key(&table_file, index_meta, &index_file, col_idx, key)?))
     }
 
-    /// Range scan using primary index
-    /// Returns all tuple pointers for keys in [start_key, end_key] inclusive
-    /// Returns empty vec if table has no primary index or index doesn't support range scans
-    pub fn range_scan_index(&self, table_name: &str, start_key: u64, end_key: u64) -> Result<Vec<TuplePointer>> {
+    /// Pointers the index `index_lookup` uses finds for `column = value`,
+    /// without reading the table
+    /// Entries may point at older versions of a tuple, or at versions whose
+    /// successor has another value, so callers must recheck the rows fetched.
+    pub fn index_pointers(&self, table_name: &str, column: &str, value: &Value) -> Result<Option<Vec<TuplePointer>>> {
         let metadata_arc = self.get_table(table_name)?;
         let metadata = metadata_arc.read();
 
-        // Return empty if table has no primary index
-        let primary_index_meta = match &metadata.primary_index {
-            Some(idx) => idx,
-            None => {
-                debug!(table_name, "no primary index found on table");
-                return Ok(Vec::new())
-            },
+        let Some((_, index_meta, index_file)) = self.lookup_index(&metadata, column)? else {
+            return Ok(None);
         };
+        let Some(key) = index_key(value)? else {
+            return Ok(Some(Vec::new()));
+        };
+        let ptrs = index_meta.index.lock().search(key, &index_file)
+            .map_err(|e| format!("Index search error: {}", e))?;
+        Ok(Some(ptrs))
+    }
 
-        // Check if index supports range scans
-        if primary_index_meta.index.lock().capability() != index::IndexCapability::Ordered {
-            // TODO do we just proceed with a full table scan?
-            return Ok(Vec::new());
+    /// Position of `column` and the index that answers `column = value` on it:
+    /// a secondary index on the column, else the primary key
+    fn lookup_index<'m>(&self, metadata: &'m TableMetadata, column: &str) -> Result<Option<(usize, &'m IndexMetadata, Arc<IndexFile>)>> {
+        let Some(col_idx) = metadata.schema.get_column_index(column) else {
+            return Ok(None);
+        };
+
+        if let Some(idx_meta) = metadata.secondary_indexes.iter()
+            .find(|idx| idx.column.eq_ignore_ascii_case(column))
+        {
+            let index_file = self.get_index_file(&format!("{}_{}", metadata.name, idx_meta.name))?;
+            return Ok(Some((col_idx, idx_meta, index_file)));
         }
+        match &metadata.primary_index {
+            Some(pk_meta) if col_idx == primary_key_position(&metadata.schema) => {
+                Ok(Some((col_idx, pk_meta, self.get_index_file(&metadata.name)?)))
+            }
+            _ => Ok(None),
+        }
+    }
 
-        // Get index file
-        let index_file = self.index_files.get(table_name)
-            .ok_or_else(|| format!("Index file not found for table: {}", table_name))?;
+    /// Scan the rows whose `column` lies in `start..=end` in order of the
+    /// column, ascending or (`backward`) descending
+    /// With `nulls`, rows where the column is NULL follow the others (precede
+    /// them when `backward`), as ORDER BY places them; no index entry leads to
+    /// them, so they are read from the table once the entries run out.
+    /// Returns None if the column has no ordered index (caller must scan
+    /// instead), see `range_index`.
+    pub fn index_range(&self, table_name: &str, column: &str, start: i64, end: i64, backward: bool, nulls: bool) -> Result<Option<IndexRangeScan>> {
+        let metadata_arc = self.get_table(table_name)?;
+        let metadata = metadata_arc.read();
+        let Some((column_idx, index_meta, index_file)) = self.range_index(&metadata, column)? else {
+            return Ok(None);
+        };
+        let mut entries = Self::range_entries(index_meta, &index_file, start, end)?;
+        if backward {
+            entries.reverse();
+        }
+        // The primary key is never NULL
+        let is_primary = metadata.primary_index.as_ref().is_some_and(|pk| std::ptr::eq(pk, index_meta));
+        let nulls = match nulls && !is_primary {
+            true => Some(Box::new(self.scan(table_name)?)),
+            false => None,
+        };
 
-        // Lock index and perform range scan
-        let index_guard = primary_index_meta.index.lock();
-        index_guard.range_scan(start_key, end_key, index_file)
-            .map(|results| results.into_iter().map(|(_, ptr)| ptr).collect())
-            .map_err(|e| format!("Failed to range scan primary index: {}", e))
+        Ok(Some(IndexRangeScan {
+            table_file: self.get_table_file(table_name)?,
+            column_idx,
+            entries: entries.into_iter(),
+            seen: HashSet::new(),
+            nulls,
+            nulls_first: backward,
+        }))
     }
 
-    /// Find a secondary index by table name and column name
-    /// Returns (index_name, IndexMetadata) if found
-    pub fn find_secondary_index(&self, table_name: &str, column_name: &str) -> Result<Option<(String, Arc<Mutex<Box<dyn index::Index>>>)>> {
+    /// Pointers the index `index_range` uses finds for `start..=end`, without
+    /// reading the table
+    /// As with `index_pointers`, callers must recheck the rows fetched.
+    pub fn index_range_pointers(&self, table_name: &str, column: &str, start: i64, end: i64) -> Result<Option<Vec<TuplePointer>>> {
         let metadata_arc = self.get_table(table_name)?;
         let metadata = metadata_arc.read();
+        let Some((_, index_meta, index_file)) = self.range_index(&metadata, column)? else {
+            return Ok(None);
+        };
+        Ok(Some(Self::range_entries(index_meta, &index_file, start, end)?
+            .into_iter()
+            .map(|(_, ptr)| ptr)
+            .collect()))
+    }
 
-        // Search secondary indexes for matching column
-        for idx_meta in &metadata.secondary_indexes {
-            if idx_meta.column == column_name {
-                return Ok(Some((idx_meta.name.clone(), idx_meta.index.clone())));
+    /// Entries of an ordered index for keys in `start..=end`, in ascending
+    /// order of the column
+    fn range_entries(index_meta: &IndexMetadata, index_file: &IndexFile, start: i64, end: i64) -> Result<Vec<(u64, TuplePointer)>> {
+        // Keys are stored as `n as u64`, which puts negative numbers after the
+        // positive ones: scan the negative part of the range first
+        let mut key_ranges = Vec::new();
+        if start < 0 && start <= end {
+            key_ranges.push((start as u64, end.min(-1) as u64));
+        }
+        if end >= 0 && start <= end {
+            key_ranges.push((start.max(0) as u64, end as u64));
+        }
+
+        let mut entries = Vec::new();
+        for (start_key, end_key) in key_ranges {
+            entries.extend(index_meta.index.lock().range_scan(start_key, end_key, index_file)
+                .map_err(|e| format!("Failed to range scan index {}: {}", index_meta.name, e))?);
+        }
+        Ok(entries)
+    }
+
+    /// Position of `column` and the ordered index over it that `index_range`
+    /// reads: the primary key, else an ordered secondary index
+    /// Only integer and date/time columns qualify, as other keys are not
+    /// stored in value order. A secondary index holds an entry for every
+    /// version of each tuple with a non-NULL key, so its entries are checked
+    /// against the live version of the row they lead to.
+    fn range_index<'m>(&self, metadata: &'m TableMetadata, column: &str) -> Result<Option<(usize, &'m IndexMetadata, Arc<IndexFile>)>> {
+        let Some(col_idx) = metadata.schema.get_column_index(column) else {
+            return Ok(None);
+        };
+        if !matches!(
+            metadata.schema.columns[col_idx].data_type,
+            DataType::Int | DataType::Date | DataType::Time | DataType::Timestamp | DataType::TimestampTz
+        ) {
+            return Ok(None);
+        }
+        let ordered = |index_meta: &IndexMetadata| index_meta.index.lock().capability() == index::IndexCapability::Ordered;
+
+        if let Some(pk_meta) = &metadata.primary_index
+            && col_idx == primary_key_position(&metadata.schema)
+            && ordered(pk_meta)
+        {
+            return Ok(Some((col_idx, pk_meta, self.get_index_file(&metadata.name)?)));
+        }
+        match metadata.secondary_indexes.iter()
+            .find(|idx| idx.column.eq_ignore_ascii_case(column) && ordered(idx))
+        {
+            Some(idx_meta) => {
+                let index_file = self.get_index_file(&format!("{}_{}", metadata.name, idx_meta.name))?;
+                Ok(Some((col_idx, idx_meta, index_file)))
             }
+            None => Ok(None),
         }
+    }
+
+    /// Fetch the live versions of the tuples in a bitmap, block by block in
+    /// physical order
+    pub fn bitmap_heap_scan(&self, table_name: &str, bitmap: &TupleBitmap) -> Result<BitmapHeapScan> {
+        Ok(BitmapHeapScan {
+            table_file: self.get_table_file(table_name)?,
+            blocks: bitmap.blocks().collect::<Vec<_>>().into_iter(),
+            pending: Vec::new().into_iter(),
+            seen: HashSet::new(),
+        })
+    }
+
+    /// Name of the index `index_range` uses for `column`, if any
+    pub fn range_index_name(&self, table_name: &str, column: &str) -> Result<Option<String>> {
+        let metadata_arc = self.get_table(table_name)?;
+        let metadata = metadata_arc.read();
+
+        Ok(self.range_index(&metadata, column)?.map(|(_, index_meta, _)| {
+            match metadata.primary_index.as_ref().is_some_and(|pk| std::ptr::eq(pk, index_meta)) {
+                true => format!("{}_pkey", table_name),
+                false => index_meta.name.clone(),
+            }
+        }))
+    }
 
-        Ok(None)
+    /// Estimated heap size of a table in bytes (used blocks times block size)
+    pub fn estimated_size(&self, table_name: &str) -> Result<u64> {
+        let table_file = self.get_table_file(table_name)?;
+
+        let mut used_blocks = 0u64;
+        for segment_id in 0..table_file.next_segment_id() {
+            let header = table_file.read_segment_header(segment_id)
+                .map_err(|e| format!("Failed to read segment header: {}", e))?;
+            used_blocks += (0..base::BLOCKS_PER_UNCOMPRESSED_SEGMENT as u8)
+                .filter(|block_id| !header.is_block_free(*block_id))
+                .count() as u64;
+        }
+        Ok(used_blocks * base::BLOCK_SIZE as u64)
     }
 
-    /// Search a secondary index by table and column name
-    /// Returns Some(TuplePointer) if found, None if not found
-    pub fn search_secondary_index(&self, table_name: &str, column_name: &str, key: u64) -> Result<Option<TuplePointer>> {
-        // Find the secondary index
-        let index_opt = self.find_secondary_index(table_name, column_name)?;
+    /// Whether `index_lookup` can answer `column = value` from an index
+    pub fn has_index(&self, table_name: &str, column: &str) -> Result<bool> {
+        Ok(self.index_name(table_name, column)?.is_some())
+    }
 
-        if let Some((index_name, index_arc)) = index_opt {
-            // Get the index file using the table and index name
-            let index_file_key = format!("{}_{}", table_name, index_name);
-            let index_file = self.index_files.get(&index_file_key)
-                .ok_or_else(|| format!("Index file not found for secondary index {}", index_name))?;
+    /// Name of the index `index_lookup` uses for `column`, if any
+    /// The primary key index is named `<table>_pkey`, as Postgres names it
+    pub fn index_name(&self, table_name: &str, column: &str) -> Result<Option<String>> {
+        let metadata_arc = self.get_table(table_name)?;
+        let metadata = metadata_arc.read();
 
-            // Search the index
-            let mut index = index_arc.lock();
-            index.search(key, index_file)
-                .map_err(|e| format!("Index search error: {}", e))
-        } else {
-            Ok(None)
+        if let Some(idx) = metadata.secondary_indexes.iter().find(|idx| idx.column.eq_ignore_ascii_case(column)) {
+            return Ok(Some(idx.name.clone()));
         }
+        let primary = metadata.primary_index.is_some()
+            && metadata.schema.get_column_index(column) == Some(primary_key_position(&metadata.schema));
+        Ok(primary.then(|| format!("{}_pkey", table_name)))
+    }
+
+    /// Statistics from the last ANALYZE of a table, if any
+    pub fn statistics(&self, table_name: &str) -> Option<&TableStatistics> {
+        self.catalog.get_table(table_name).ok().flatten()?.statistics.as_ref()
+    }
+
+    /// Record statistics gathered by ANALYZE in the catalog
+    pub fn set_statistics(&mut self, table_name: &str, statistics: TableStatistics) -> Result<()> {
+        let table_meta = self.catalog.get_table_mut(table_name)
+            .ok_or_else(|| format!("Table not found: {}", table_name))?;
+        table_meta.statistics = Some(statistics);
+        self.save_catalog_to_disk()
     }
 
     /// Create a secondary index on a table
@@ -615,14 +1205,27 @@ impl Database {
         let index_file = IndexFile::open(&index_file_path)
             .map_err(|e| format!("Failed to open index file: {}", e))?;
 
-        // Allocate root page for the secondary index
+        // Allocate and initialize root page for the secondary index
         let root_page_id = index_file.allocate_page()
             .map_err(|e| format!("Failed to allocate index root page: {}", e))?;
+        index_file.write_page(root_page_id, &IndexPage::new(NodeType::Leaf).data)
+            .map_err(|e| format!("Failed to initialize index root page: {}", e))?;
 
         // Create index instance via registry
         let index = self.index_builder_registry.create_index(&index_type, Some(root_page_id))
             .ok_or_else(|| format!("Failed to create {} index", index_type))?;
 
+        // Build index over existing rows
+        let col_idx = metadata_arc.read().schema.get_column_index(&column_name)
+            .ok_or_else(|| format!("Column not found: {}", column_name))?;
+        let mut index = index;
+        for (ptr, row) in self.scan_table_versions(&table_name)? {
+            if let Some(key) = row.get(col_idx).map(index_key).transpose()?.flatten() {
+                index.insert(key, ptr, &index_file)
+                    .map_err(|e| format!("Failed to build index {}: {}", index_name, e))?;
+            }
+        }
+
         // Create index metadata
         let index_meta = IndexMetadata {
             name: index_name.clone(),
@@ -646,4 +1249,240 @@ impl Database {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Scan over the live tuples of a range of index entries, in index order
+///
+/// The index entries are read up front; each tuple is read from the table
+/// as the iterator reaches it, so a consumer that stops early skips the rest.
+pub struct IndexRangeScan {
+    table_file: Arc<TableFile>,
+    /// Indexed column, to recheck each tuple's key against its entry
+    column_idx: usize,
+    entries: std::vec::IntoIter<(u64, TuplePointer)>,
+    /// Live versions returned so far
+    seen: HashSet<TuplePointer>,
+    /// Scan for the rows where the column is NULL, if they are returned too
+    /// (boxed, as a table scan holds a whole segment header)
+    nulls: Option<Box<TableScan>>,
+    /// Whether those rows come before the entries rather than after them
+    nulls_first: bool,
+}
+
+impl IndexRangeScan {
+    /// The next row where the indexed column is NULL, if any are left
+    fn next_null(&mut self) -> Option<Result<(TuplePointer, Row)>> {
+        let scan = self.nulls.as_mut()?;
+        for tuple in scan.by_ref() {
+            match tuple {
+                Ok((ptr, row)) if matches!(row.get(self.column_idx), Some(Value::Null)) => return Some(Ok((ptr, row))),
+                Ok(_) => continue,
+                Err(e) => {
+                    self.nulls = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.nulls = None;
+        None
+    }
+}
+
+impl Iterator for IndexRangeScan {
+    type Item = Result<(TuplePointer, Row)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.nulls_first && let Some(tuple) = self.next_null() {
+            return Some(tuple);
+        }
+        for (key, ptr) in self.entries.by_ref() {
+            match Database::live_version_with_key(&self.table_file, ptr, self.column_idx, key) {
+                Ok(Some(tuple)) if self.seen.insert(tuple.0) => return Some(Ok(tuple)),
+                Ok(_) => continue,
+                Err(e) => {
+                    // Stop after reporting the error
+                    self.entries = Vec::new().into_iter();
+                    self.nulls = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.next_null()
+    }
+}
+
+/// Scan over the live versions of the tuples of a bitmap
+///
+/// Each block of the bitmap is read once, in physical order, as the iterator
+/// reaches it. Tuples that were updated are followed to their live version,
+/// which is returned once even if several pointers lead to it.
+pub struct BitmapHeapScan {
+    table_file: Arc<TableFile>,
+    blocks: std::vec::IntoIter<((base::SegmentId, base::BlockId), Vec<base::SlotId>)>,
+    /// Tuples of the current block not yet returned
+    pending: std::vec::IntoIter<(TuplePointer, Row)>,
+    /// Live versions returned so far
+    seen: HashSet<TuplePointer>,
+}
+
+impl BitmapHeapScan {
+    /// Resolve the tuples of the next block of the bitmap into `pending`
+    /// Returns false once every block has been read
+    fn next_block(&mut self) -> Result<bool> {
+        let Some(((segment_id, block_id), slots)) = self.blocks.next() else {
+            return Ok(false);
+        };
+        let block = self.table_file.read_block(segment_id, block_id)
+            .map_err(|e| format!("Failed to read block: {}", e))?;
+
+        let mut tuples = Vec::new();
+        for slot_id in slots {
+            let Some((meta, payload)) = block.read_versioned_tuple(slot_id) else {
+                continue;
+            };
+            let tuple = if !meta.is_deleted() {
+                Some((TuplePointer::new(segment_id, block_id, slot_id), decode_row(payload)?))
+            } else {
+                match meta.next() {
+                    Some(next) => Database::resolve_version(&self.table_file, next)?,
+                    None => None,
+                }
+            };
+            if let Some((ptr, row)) = tuple
+                && self.seen.insert(ptr)
+            {
+                tuples.push((ptr, row));
+            }
+        }
+        self.pending = tuples.into_iter();
+        Ok(true)
+    }
+}
+
+impl Iterator for BitmapHeapScan {
+    type Item = Result<(TuplePointer, Row)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(tuple) = self.pending.next() {
+                return Some(Ok(tuple));
+            }
+            match self.next_block() {
+                Ok(true) => continue,
+                Ok(false) => return None,
+                Err(e) => {
+                    // Stop after reporting the error
+                    self.blocks = Vec::new().into_iter();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Sequential scan over the live tuples of a table
+///
+/// Blocks are read as the iterator advances, so a consumer that stops early
+/// never reads the rest of the table. Tuples added to segments allocated
+/// after the scan started are not visited.
+pub struct TableScan {
+    table_file: Arc<TableFile>,
+    segment_count: u32,
+    segment_id: u32,
+    /// Header of the current segment, read on entering it
+    header: Option<base::SegmentHeader>,
+    block_id: u8,
+    /// Tuples of the current block not yet returned
+    pending: std::vec::IntoIter<(TuplePointer, Row)>,
+}
+
+impl TableScan {
+    /// Decode the live tuples of the next used block into `pending`
+    /// Returns false once every segment has been read
+    fn next_block(&mut self) -> Result<bool> {
+        while self.segment_id < self.segment_count {
+            let header = match &self.header {
+                Some(header) => header,
+                None => {
+                    let header = self.table_file.read_segment_header(self.segment_id)
+                        .map_err(|e| format!("Failed to read segment header: {}", e))?;
+                    self.header.insert(header)
+                }
+            };
+
+            if self.block_id as usize >= base::BLOCKS_PER_UNCOMPRESSED_SEGMENT {
+                self.segment_id += 1;
+                self.block_id = 0;
+                self.header = None;
+                continue;
+            }
+            let block_id = self.block_id;
+            self.block_id += 1;
+            if header.is_block_free(block_id) {
+                continue;
+            }
+
+            let block = self.table_file.read_block(self.segment_id, block_id)
+                .map_err(|e| format!("Failed to read block: {}", e))?;
+
+            // Read all slots in block, skipping dead versions
+            let mut tuples = Vec::new();
+            for slot_id in 0..block.header().slot_count {
+                if let Some((meta, payload)) = block.read_versioned_tuple(slot_id)
+                    && !meta.is_deleted()
+                {
+                    let ptr = TuplePointer::new(self.segment_id, block_id, slot_id);
+                    tuples.push((ptr, decode_row(payload)?));
+                }
+            }
+            self.pending = tuples.into_iter();
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+impl Iterator for TableScan {
+    type Item = Result<(TuplePointer, Row)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(tuple) = self.pending.next() {
+                return Some(Ok(tuple));
+            }
+            match self.next_block() {
+                Ok(true) => continue,
+                Ok(false) => return None,
+                Err(e) => {
+                    // Stop after reporting the error
+                    self.segment_id = self.segment_count;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_wal_opened_in_data_dir() {
+        let dir = std::env::temp_dir().join(format!("flint-data-dir-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("Failed to create data dir");
+
+        let mut config = Config::from_args();
+        config.data_dir = dir.clone();
+        Database::new(&config).expect("Failed to open database");
+        assert!(dir.join("wal.log").exists());
+
+        // A missing data dir is reported rather than panicking
+        config.data_dir = dir.join("missing");
+        assert!(Database::new(&config).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,7 @@
 use std::io::{self, Result};
 use std::path::{Path, PathBuf};
 use crate::storage::io::{Disk, alloc_aligned};
+use crate::storage::base::{TuplePointer, TxId};
 use bincode::{Encode, Decode};
 
 /// WAL entry type
@@ -19,6 +20,7 @@ pub enum WalEntryType {
 }
 
 impl WalEntryType {
+    #[cfg(test)]
     pub fn from_u8(val: u8) -> Option<Self> {
         match val {
             1 => Some(WalEntryType::Insert),
@@ -52,6 +54,9 @@ pub struct WalEntryHeader {
 impl WalEntryHeader {
     const MAGIC: u32 = 0x574C4F47; // "WLOG"
 
+    /// On-disk size of the header (fields are packed, no struct padding)
+    pub const SIZE: usize = 48;
+
     pub fn new(entry_type: WalEntryType, payload_len: u32, lsn: u64) -> Self {
         WalEntryHeader {
             magic: Self::MAGIC,
@@ -63,6 +68,40 @@ impl WalEntryHeader {
         }
     }
 
+    /// Encode header into its packed 48-byte on-disk form
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut buf = [0u8; Self::SIZE];
+        buf[0..4].copy_from_slice(&self.magic.to_le_bytes());
+        buf[4] = self.entry_type;
+        buf[5..9].copy_from_slice(&self.payload_len.to_le_bytes());
+        buf[9..17].copy_from_slice(&self.lsn.to_le_bytes());
+        buf[17..21].copy_from_slice(&self.crc32.to_le_bytes());
+        buf[21..48].copy_from_slice(&self._reserved);
+        buf
+    }
+
+    /// Decode header from its packed 48-byte on-disk form
+    #[cfg(test)]
+    pub fn from_bytes(buf: &[u8]) -> Result<Self> {
+        if buf.len() < Self::SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "WAL entry header truncated",
+            ));
+        }
+        let mut reserved = [0u8; 27];
+        reserved.copy_from_slice(&buf[21..48]);
+        Ok(WalEntryHeader {
+            magic: u32::from_le_bytes(buf[0..4].try_into().unwrap()),
+            entry_type: buf[4],
+            payload_len: u32::from_le_bytes(buf[5..9].try_into().unwrap()),
+            lsn: u64::from_le_bytes(buf[9..17].try_into().unwrap()),
+            crc32: u32::from_le_bytes(buf[17..21].try_into().unwrap()),
+            _reserved: reserved,
+        })
+    }
+
+    #[cfg(test)]
     pub fn validate(&self) -> Result<()> {
         if self.magic != Self::MAGIC {
             return Err(io::Error::new(
@@ -97,8 +136,35 @@ impl WalEntry {
     }
 }
 
+/// Payload of Insert/Update/Delete entries
+/// `data` holds the encoded tuple (MVCC header + row) of the new version
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct TupleRecord {
+    pub txid: TxId,
+    pub table: String,
+    /// Version that was superseded or deleted (Update/Delete)
+    pub old: Option<TuplePointer>,
+    /// Version that was written (Insert/Update)
+    pub new: Option<TuplePointer>,
+    pub data: Vec<u8>,
+}
+
+impl TupleRecord {
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        bincode::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+}
+
+/// Size an entry occupies in the log, padded to the Direct I/O alignment
+#[cfg(test)]
+fn entry_span(payload_len: usize) -> u64 {
+    use crate::storage::io::ALIGNMENT;
+    (WalEntryHeader::SIZE + payload_len).div_ceil(ALIGNMENT) as u64 * ALIGNMENT as u64
+}
+
 /// WalFile manages append-only write-ahead log
-/// Writes are sequential and buffered for performance
+/// Entries are padded to 4KB boundaries so every write is Direct I/O aligned
 pub struct WalFile {
     disk: Disk,
     path: PathBuf,
@@ -127,73 +193,61 @@ impl WalFile {
 
     /// Append a WAL entry to the log
     pub fn append(&mut self, entry: &WalEntry) -> Result<u64> {
-        let header_size = std::mem::size_of::<WalEntryHeader>();
+        let header_size = WalEntryHeader::SIZE;
         let total_size = header_size + entry.payload.len();
 
-        // Allocate aligned buffer
+        // Allocate aligned (zero padded) buffer
         let mut buf = alloc_aligned(total_size);
 
-        // Write header
-        let header_bytes = unsafe {
-            std::slice::from_raw_parts(
-                &entry.header as *const WalEntryHeader as *const u8,
-                header_size,
-            )
-        };
-        buf[..header_size].copy_from_slice(header_bytes);
-
-        // Write payload
-        buf[header_size..].copy_from_slice(&entry.payload);
+        // Write header (CRC zeroed) and payload
+        let mut header = entry.header;
+        header.crc32 = 0;
+        buf[..header_size].copy_from_slice(&header.to_bytes());
+        buf[header_size..total_size].copy_from_slice(&entry.payload);
 
         // Compute CRC32 (for integrity checking during recovery)
-        let crc = compute_crc32(&buf[..total_size]);
-
-        // Update header with CRC (in-memory only)
-        let mut header_with_crc = entry.header;
-        header_with_crc.crc32 = crc;
-        let header_with_crc_bytes = unsafe {
-            std::slice::from_raw_parts(
-                &header_with_crc as *const WalEntryHeader as *const u8,
-                header_size,
-            )
-        };
-        buf[..header_size].copy_from_slice(header_with_crc_bytes);
+        header.crc32 = compute_crc32(&buf[..total_size]);
+        buf[..header_size].copy_from_slice(&header.to_bytes());
 
         // Write to disk at current offset
         self.disk.write_at(self.next_offset, &buf)?;
 
         let entry_offset = self.next_offset;
-        self.next_offset += total_size as u64;
+        self.next_offset += buf.len() as u64;
 
         Ok(entry_offset)
     }
 
     /// Read a WAL entry at given offset
+    ///
+    /// Nothing replays the log yet, so reading it back is only built for tests.
+    #[cfg(test)]
     pub fn read_at(&self, offset: u64) -> Result<Option<WalEntry>> {
-        let header_size = std::mem::size_of::<WalEntryHeader>();
-        let mut header_buf = alloc_aligned(header_size);
-
-        // Read header
-        match self.disk.read_at(offset, &mut header_buf) {
-            Ok(_) => {}
-            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
-            Err(e) => return Err(e),
+        let header_size = WalEntryHeader::SIZE;
+        let mut buf = alloc_aligned(header_size);
+
+        // Read first aligned chunk (contains the header)
+        if self.disk.read_at(offset, &mut buf)? == 0 {
+            return Ok(None);
         }
 
-        let header = unsafe { std::ptr::read(header_buf.as_ptr() as *const WalEntryHeader) };
+        let header = WalEntryHeader::from_bytes(&buf)?;
         header.validate()?;
 
-        // Read payload
+        // Read the rest of the entry if it spans more than one chunk
         let payload_len = header.payload_len as usize;
-        let mut payload = alloc_aligned(payload_len);
-        self.disk.read_at(offset + header_size as u64, &mut payload)?;
+        let total_size = header_size + payload_len;
+        if total_size > buf.len() {
+            buf = alloc_aligned(total_size);
+            self.disk.read_at(offset, &mut buf)?;
+        }
 
-        // Verify CRC
-        let mut verify_buf = alloc_aligned(header_size + payload_len);
-        verify_buf[..header_size].copy_from_slice(&header_buf);
-        verify_buf[header_size..].copy_from_slice(&payload[..payload_len]);
+        // Verify CRC (computed with the CRC field zeroed)
+        let mut zeroed = header;
+        zeroed.crc32 = 0;
+        buf[..header_size].copy_from_slice(&zeroed.to_bytes());
 
-        let expected_crc = compute_crc32(&verify_buf[..header_size + payload_len]);
+        let expected_crc = compute_crc32(&buf[..total_size]);
         if header.crc32 != expected_crc {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -201,10 +255,12 @@ impl WalFile {
             ));
         }
 
+        let payload = buf[header_size..total_size].to_vec();
         Ok(Some(WalEntry { header, payload }))
     }
 
     /// Iterate through all entries in the log starting from offset
+    #[cfg(test)]
     pub fn iter_from(&self, start_offset: u64) -> WalIterator<'_> {
         WalIterator {
             wal: self,
@@ -235,19 +291,20 @@ impl WalFile {
 }
 
 /// Iterator for WAL entries
+#[cfg(test)]
 pub struct WalIterator<'a> {
     wal: &'a WalFile,
     current_offset: u64,
 }
 
+#[cfg(test)]
 impl<'a> Iterator for WalIterator<'a> {
     type Item = Result<WalEntry>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.wal.read_at(self.current_offset) {
             Ok(Some(entry)) => {
-                let header_size = std::mem::size_of::<WalEntryHeader>();
-                self.current_offset += header_size as u64 + entry.payload.len() as u64;
+                self.current_offset += entry_span(entry.payload.len());
                 Some(Ok(entry))
             }
             Ok(None) => None,
@@ -280,7 +337,6 @@ mod tests {
     use std::fs;
 
     #[test]
-    #[ignore]
     fn test_wal_file_creation() {
         let path = "test_wal.log";
         let _ = fs::remove_file(path);
@@ -292,7 +348,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore]
     fn test_wal_append_and_read() {
         let path = "test_wal_write.log";
         let _ = fs::remove_file(path);
@@ -315,7 +370,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore]
     fn test_wal_iterator() {
         let path = "test_wal_iter.log";
         let _ = fs::remove_file(path);
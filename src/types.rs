@@ -1,8 +1,12 @@
 use serde::{Serialize, Deserialize};
 use std::sync::Arc;
 use std::any::Any;
+use bigdecimal::BigDecimal;
 use bincode::{Encode, Decode};
 
+use crate::config::ByteaOutput;
+use crate::executor::{bytes, datetime, numeric};
+
 /// A single column value
 #[derive(Debug, Clone)]
 pub enum Value {
@@ -11,6 +15,19 @@ pub enum Value {
     Float(f64),
     String(String),
     Bool(bool),
+    /// Days since 2000-01-01, the Postgres epoch
+    Date(i32),
+    /// Microseconds since midnight
+    Time(i64),
+    /// Microseconds since 2000-01-01 00:00:00
+    Timestamp(i64),
+    /// Microseconds since 2000-01-01 00:00:00 UTC
+    TimestampTz(i64),
+    Interval(Interval),
+    /// Exact decimal, keeping its scale
+    Numeric(BigDecimal),
+    Uuid([u8; 16]),
+    Bytea(Vec<u8>),
     // Extension type values (stored as Arc<dyn Any> for type-safe downcasting)
     Extension {
         type_oid: u32,
@@ -18,6 +35,27 @@ pub enum Value {
     },
 }
 
+/// A span of time in months, days and microseconds
+///
+/// The parts are kept apart, as Postgres does, because months and days vary
+/// in length: adding `1 month` to January 31 gives February's last day.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub micros: i64,
+}
+
+impl Interval {
+    pub const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+    /// Length in microseconds, counting a month as 30 days and a day as 24
+    /// hours, by which intervals are compared
+    pub fn span(&self) -> i128 {
+        (self.months as i128 * 30 + self.days as i128) * Self::MICROS_PER_DAY as i128 + self.micros as i128
+    }
+}
+
 // Manual serde impl to handle non-serializable Extension variant
 impl Serialize for Value {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -30,6 +68,10 @@ impl Serialize for Value {
             Value::Float(f) => serializer.serialize_f64(*f),
             Value::String(s) => serializer.serialize_str(s),
             Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Date(_) | Value::Time(_) | Value::Timestamp(_) | Value::TimestampTz(_) | Value::Interval(_)
+            | Value::Numeric(_) | Value::Uuid(_) | Value::Bytea(_) => {
+                serializer.serialize_str(&self.as_string())
+            }
             Value::Extension { .. } => {
                 Err(serde::ser::Error::custom(
                     "Extension values must be serialized through TypeExtension trait",
@@ -107,6 +149,42 @@ impl Encode for Value {
                 4u8.encode(encoder)?;
                 b.encode(encoder)?;
             }
+            Value::Date(days) => {
+                6u8.encode(encoder)?;
+                days.encode(encoder)?;
+            }
+            Value::Time(micros) => {
+                7u8.encode(encoder)?;
+                micros.encode(encoder)?;
+            }
+            Value::Timestamp(micros) => {
+                8u8.encode(encoder)?;
+                micros.encode(encoder)?;
+            }
+            Value::TimestampTz(micros) => {
+                9u8.encode(encoder)?;
+                micros.encode(encoder)?;
+            }
+            Value::Interval(interval) => {
+                10u8.encode(encoder)?;
+                interval.months.encode(encoder)?;
+                interval.days.encode(encoder)?;
+                interval.micros.encode(encoder)?;
+            }
+            Value::Numeric(n) => {
+                11u8.encode(encoder)?;
+                let (digits, scale) = n.as_bigint_and_exponent();
+                scale.encode(encoder)?;
+                digits.to_signed_bytes_le().encode(encoder)?;
+            }
+            Value::Uuid(uuid) => {
+                12u8.encode(encoder)?;
+                uuid.encode(encoder)?;
+            }
+            Value::Bytea(bytes) => {
+                13u8.encode(encoder)?;
+                bytes.encode(encoder)?;
+            }
             Value::Extension { type_oid, .. } => {
                 // Extension values cannot be persisted in Phase 1
                 // Store as Null with marker
@@ -148,11 +226,32 @@ impl Decode<()> for Value {
                 let _type_oid = u32::decode(decoder)?;
                 Ok(Value::Null)
             }
+            6 => Ok(Value::Date(i32::decode(decoder)?)),
+            7 => Ok(Value::Time(i64::decode(decoder)?)),
+            8 => Ok(Value::Timestamp(i64::decode(decoder)?)),
+            9 => Ok(Value::TimestampTz(i64::decode(decoder)?)),
+            10 => Ok(Value::Interval(Interval {
+                months: i32::decode(decoder)?,
+                days: i32::decode(decoder)?,
+                micros: i64::decode(decoder)?,
+            })),
+            11 => {
+                let scale = i64::decode(decoder)?;
+                let digits = Vec::<u8>::decode(decoder)?;
+                Ok(Value::Numeric(BigDecimal::new(
+                    bigdecimal::num_bigint::BigInt::from_signed_bytes_le(&digits),
+                    scale,
+                )))
+            }
+            12 => Ok(Value::Uuid(<[u8; 16]>::decode(decoder)?)),
+            13 => Ok(Value::Bytea(Vec::<u8>::decode(decoder)?)),
             _ => Err(bincode::error::DecodeError::OtherString("Invalid Value tag".into())),
         }
     }
 }
 
+bincode::impl_borrow_decode_with_context!(Value, ());
+
 impl Value {
     pub fn as_i32(&self) -> Option<i32> {
         match self {
@@ -168,9 +267,62 @@ impl Value {
             Value::Float(f) => f.to_string(),
             Value::String(s) => s.clone(),
             Value::Bool(b) => b.to_string(),
+            Value::Date(days) => datetime::format_date(*days),
+            Value::Time(micros) => datetime::format_time(*micros),
+            Value::Timestamp(micros) => datetime::format_timestamp(*micros),
+            Value::TimestampTz(micros) => datetime::format_timestamptz(*micros),
+            Value::Interval(interval) => datetime::format_interval(interval),
+            Value::Numeric(n) => numeric::format(n),
+            Value::Uuid(uuid) => bytes::format_uuid(uuid),
+            Value::Bytea(data) => bytes::format_bytea(data, ByteaOutput::Hex),
             Value::Extension { type_oid, .. } => format!("<extension {}>", type_oid),
         }
     }
+
+    /// Hashable form of the value for grouping and DISTINCT
+    /// Returns None for extension values, which have no equality to hash on
+    pub fn hash_key(&self) -> Option<HashKey> {
+        match self {
+            Value::Null => Some(HashKey::Null),
+            Value::Int(n) => Some(HashKey::Int(*n)),
+            // -0.0 and 0.0 are equal, and all NaNs group together
+            Value::Float(f) if *f == 0.0 => Some(HashKey::Float(0.0f64.to_bits())),
+            Value::Float(f) if f.is_nan() => Some(HashKey::Float(f64::NAN.to_bits())),
+            Value::Float(f) => Some(HashKey::Float(f.to_bits())),
+            Value::String(s) => Some(HashKey::String(s.clone())),
+            Value::Bool(b) => Some(HashKey::Bool(*b)),
+            Value::Date(days) => Some(HashKey::Date(*days)),
+            Value::Time(micros) => Some(HashKey::Time(*micros)),
+            Value::Timestamp(micros) => Some(HashKey::Timestamp(*micros)),
+            Value::TimestampTz(micros) => Some(HashKey::TimestampTz(*micros)),
+            // Intervals of the same length are equal, as `1 day` and `24 hours` are
+            Value::Interval(interval) => Some(HashKey::Interval(interval.span())),
+            // 1.5 and 1.50 are equal
+            Value::Numeric(n) => Some(HashKey::Numeric(numeric::normalized(n))),
+            Value::Uuid(uuid) => Some(HashKey::Uuid(*uuid)),
+            Value::Bytea(data) => Some(HashKey::Bytea(data.clone())),
+            Value::Extension { .. } => None,
+        }
+    }
+}
+
+/// Hashable form of a Value with total equality
+/// NULLs compare equal to each other, as grouping requires
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HashKey {
+    Null,
+    Int(i64),
+    Float(u64),
+    String(String),
+    Bool(bool),
+    Date(i32),
+    Time(i64),
+    Timestamp(i64),
+    TimestampTz(i64),
+    Interval(i128),
+    Numeric(String),
+    Uuid([u8; 16]),
+    Bytea(Vec<u8>),
 }
 
 /// A single row (ordered list of values)
@@ -234,6 +386,15 @@ pub enum DataType {
         type_oid: u32,
         type_name: String,
     },
+    Date,
+    Time,
+    Timestamp,
+    TimestampTz,
+    Interval,
+    /// `NUMERIC(precision, scale)`, or unconstrained `NUMERIC`
+    Numeric(Option<(u32, u32)>),
+    Uuid,
+    Bytea,
 }
 
 /// Table schema
@@ -253,6 +414,41 @@ impl Schema {
             .position(|c| c.name.eq_ignore_ascii_case(name))
     }
 
+    /// Resolve a column reference, optionally qualified by a relation name
+    ///
+    /// Columns produced by a FROM clause are named `relation.column`. An
+    /// unqualified name matches a column with exactly that name first, and
+    /// otherwise the single qualified column it names. Errors if more than one
+    /// qualified column matches.
+    pub fn resolve_column(&self, qualifier: Option<&str>, name: &str) -> Result<Option<usize>, String> {
+        if let Some(qualifier) = qualifier {
+            return Ok(self.get_column_index(&format!("{}.{}", qualifier, name)));
+        }
+        if let Some(idx) = self.get_column_index(name) {
+            return Ok(Some(idx));
+        }
+
+        let mut matches = self.columns.iter()
+            .enumerate()
+            .filter(|(_, c)| c.name.rsplit_once('.').is_some_and(|(_, column)| column.eq_ignore_ascii_case(name)))
+            .map(|(idx, _)| idx);
+        match (matches.next(), matches.next()) {
+            (Some(_), Some(_)) => Err(format!("column reference \"{}\" is ambiguous", name)),
+            (found, _) => Ok(found),
+        }
+    }
+
+    /// Copy of the schema with every column qualified by `relation`
+    pub fn qualified(&self, relation: &str) -> Schema {
+        Schema::new(self.columns.iter()
+            .map(|c| Column {
+                name: format!("{}.{}", relation, c.name),
+                data_type: c.data_type.clone(),
+                is_primary_key: c.is_primary_key,
+            })
+            .collect())
+    }
+
     pub fn len(&self) -> usize {
         self.columns.len()
     }
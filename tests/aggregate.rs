@@ -0,0 +1,137 @@
+mod common;
+
+use common::{data_rows, TestDb};
+use serial_test::serial;
+
+fn setup_employees(db: &TestDb) {
+    db.execute_sql("CREATE TABLE emp (id INT, dept STRING, salary INT, active BOOL, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql(
+        "INSERT INTO emp VALUES (1, 'eng', 100, true), (2, 'eng', 200, false), \
+         (3, 'ops', 50, true), (4, 'ops', NULL, true), (5, 'hr', 70, true);",
+    )
+    .expect("INSERT failed");
+}
+
+#[test]
+#[serial]
+fn test_aggregates_without_group_by() {
+    let db = TestDb::new();
+    setup_employees(&db);
+
+    let result = db
+        .execute_sql("SELECT COUNT(*), COUNT(salary), SUM(salary), MIN(salary), MAX(salary) FROM emp;")
+        .expect("SELECT failed");
+    assert!(result.contains("count | count | sum | min | max"), "wrong column names: {}", result);
+    assert!(result.contains("5 |     4 | 420 |  50 | 200"), "wrong aggregates: {}", result);
+    assert!(result.contains("(1 row)"), "expected one row: {}", result);
+
+    let result = db
+        .execute_sql("SELECT AVG(salary) FROM emp;")
+        .expect("SELECT failed");
+    assert!(result.contains("105"), "wrong average: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_integer_sums_are_numeric() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE big (id INT, value INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO big VALUES (1, 9223372036854775807), (2, 1), (3, NULL);")
+        .expect("INSERT failed");
+
+    // Sums of bigints do not overflow and averages keep every digit
+    let result = db
+        .execute_sql("SELECT SUM(value), AVG(value), AVG(value) FILTER (WHERE id = 2) FROM big;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["9223372036854775808|4611686018427387904|1.0000000000000000"]);
+    let result = db
+        .execute_sql("SELECT id, SUM(value) OVER (ORDER BY id) FROM big ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["1|9223372036854775807", "2|9223372036854775808", "3|9223372036854775808"]
+    );
+}
+
+#[test]
+#[serial]
+fn test_aggregates_on_empty_table() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE empty (id INT, value INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+
+    // Without GROUP BY there is always exactly one row
+    let result = db
+        .execute_sql("SELECT COUNT(*), SUM(value) FROM empty;")
+        .expect("SELECT failed");
+    assert!(result.contains("(1 row)"), "expected one row: {}", result);
+    assert!(result.contains("0 |"), "COUNT(*) should be 0: {}", result);
+
+    // With GROUP BY there are no groups
+    let result = db
+        .execute_sql("SELECT value, COUNT(*) FROM empty GROUP BY value;")
+        .expect("SELECT failed");
+    assert!(!result.contains("(1 row)"), "expected no rows: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_group_by() {
+    let db = TestDb::new();
+    setup_employees(&db);
+
+    let result = db
+        .execute_sql("SELECT dept, COUNT(*) AS n, SUM(salary) FROM emp GROUP BY dept;")
+        .expect("SELECT failed");
+    assert!(result.contains("dept | n | sum"), "wrong column names: {}", result);
+    assert!(result.contains("eng  | 2 | 300"), "wrong eng group: {}", result);
+    assert!(result.contains("ops  | 2 |  50"), "wrong ops group: {}", result);
+    assert!(result.contains("hr   | 1 |  70"), "wrong hr group: {}", result);
+    assert!(result.contains("(3 rows)"), "expected 3 groups: {}", result);
+
+    let result = db
+        .execute_sql("SELECT dept, bool_and(active), bool_or(active), string_agg(dept, ';') FROM emp GROUP BY 1;")
+        .expect("SELECT failed");
+    assert!(result.contains("eng  | f        | t       | eng;eng"), "wrong bool/string aggregates: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_having() {
+    let db = TestDb::new();
+    setup_employees(&db);
+
+    let result = db
+        .execute_sql("SELECT dept, MAX(salary) FROM emp GROUP BY dept HAVING COUNT(*) > 1;")
+        .expect("SELECT failed");
+    assert!(result.contains("(2 rows)"), "expected 2 groups: {}", result);
+    assert!(!result.contains("hr"), "HAVING should drop hr: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_count_distinct() {
+    let db = TestDb::new();
+    setup_employees(&db);
+
+    let result = db
+        .execute_sql("SELECT COUNT(DISTINCT dept) FROM emp;")
+        .expect("SELECT failed");
+    assert!(result.contains("3"), "expected 3 distinct depts: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_ungrouped_column_rejected() {
+    let db = TestDb::new();
+    setup_employees(&db);
+
+    let result = db.execute_sql("SELECT dept, salary FROM emp GROUP BY dept;");
+    assert!(
+        result.is_err() || result.unwrap().contains("ERROR"),
+        "ungrouped column should be rejected"
+    );
+}
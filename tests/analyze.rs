@@ -0,0 +1,93 @@
+mod common;
+
+use common::{data_rows, insert_rows, TestDb};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn test_analyze_table() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE readings (id INT, sensor INT, level FLOAT, note STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    insert_rows(&db, "readings", (1..=500).map(|id| {
+        let note = if id % 10 == 0 { "NULL".to_string() } else { format!("'n{}'", id % 3) };
+        format!("({}, {}, {}, {})", id, id % 7, id as f64 / 4.0, note)
+    }));
+
+    let result = db.execute_sql("ANALYZE readings;").expect("ANALYZE failed");
+    assert!(result.contains("ANALYZE"), "unexpected output: {}", result);
+    db.execute_sql("ANALYZE readings FOR COLUMNS sensor, note;")
+        .expect("ANALYZE FOR COLUMNS failed");
+
+    // Equality on an unindexed column is answered by a scan
+    let result = db.execute_sql("SELECT COUNT(*), MIN(id), MAX(id) FROM readings WHERE sensor = 3;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["72|3|500"]);
+
+    let result = db.execute_sql("SELECT id, sensor FROM readings WHERE id = 250;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["250|5"]);
+
+    let result = db.execute_sql("SELECT COUNT(*) FROM readings WHERE sensor = 2 OR level < 10;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["105"]);
+
+    let result = db.execute_sql("ANALYZE missing;");
+    assert!(result.is_err(), "unknown table should fail: {:?}", result);
+    let result = db.execute_sql("ANALYZE readings FOR COLUMNS nope;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("column \"nope\" of relation \"readings\" does not exist")),
+        "unknown column should fail: {:?}",
+        result
+    );
+}
+
+#[test]
+#[serial]
+fn test_join_order_uses_statistics() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE customers (id INT, region INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("CREATE TABLE orders (id INT, customer INT, amount INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("CREATE TABLE regions (id INT, name STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    insert_rows(&db, "customers", (1..=300).map(|id| format!("({}, {})", id, id % 5)));
+    insert_rows(&db, "orders", (1..=2000).map(|id| format!("({}, {}, {})", id, id % 300 + 1, id % 50)));
+    insert_rows(&db, "regions", (0..5).map(|id| format!("({}, 'r{}')", id, id)));
+
+    let query = "SELECT r.name, COUNT(*), SUM(o.amount) \
+                 FROM orders o, customers c, regions r \
+                 WHERE o.customer = c.id AND c.region = r.id AND r.name = 'r2' AND o.amount > 40 \
+                 GROUP BY r.name;";
+    let before = db.execute_sql(query).expect("SELECT before ANALYZE failed");
+
+    for table in ["customers", "orders", "regions"] {
+        db.execute_sql(&format!("ANALYZE {};", table)).expect("ANALYZE failed");
+    }
+    let after = db.execute_sql(query).expect("SELECT after ANALYZE failed");
+
+    assert_eq!(data_rows(&before), vec!["r2|80|3480"]);
+    assert_eq!(data_rows(&after), data_rows(&before));
+}
+
+#[test]
+#[serial]
+fn test_statistics_survive_restart() {
+    let mut db = TestDb::new();
+    db.execute_sql("CREATE TABLE points (id INT, x INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    insert_rows(&db, "points", (1..=50).map(|id| format!("({}, {})", id, id % 4)));
+    db.execute_sql("ANALYZE points;").expect("ANALYZE failed");
+
+    db.restart().expect("restart failed");
+
+    let result = db.execute_sql("SELECT COUNT(*) FROM points WHERE x = 1;")
+        .expect("SELECT after restart failed");
+    assert_eq!(data_rows(&result), vec!["13"]);
+    db.execute_sql("INSERT INTO points VALUES (51, 1);").expect("INSERT after restart failed");
+    db.execute_sql("ANALYZE points;").expect("ANALYZE after restart failed");
+    let result = db.execute_sql("SELECT x FROM points WHERE id = 51;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1"]);
+}
@@ -100,6 +100,33 @@ fn test_scan_empty_table() {
 
     // Output should not contain error
     assert!(!result.contains("ERROR"), "empty table SELECT should not error");
+
+    // The columns are still described
+    assert!(result.contains("id | data"), "missing column headers: {}", result);
+    assert!(result.contains("(0 rows)"), "unexpected output: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_command_tags() {
+    let db = TestDb::new();
+
+    let result = db.execute_sql("CREATE TABLE tagged (id INT, label STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    assert_eq!(result.trim(), "CREATE TABLE");
+    let result = db.execute_sql("INSERT INTO tagged VALUES (1, 'a'), (2, 'b'), (3, 'c');")
+        .expect("INSERT failed");
+    assert_eq!(result.trim(), "INSERT 0 3");
+    let result = db.execute_sql("INSERT INTO tagged VALUES (4, 'd');").expect("INSERT failed");
+    assert_eq!(result.trim(), "INSERT 0 1");
+    let result = db.execute_sql("CREATE INDEX idx_label ON tagged (label);").expect("CREATE INDEX failed");
+    assert_eq!(result.trim(), "CREATE INDEX");
+
+    // Each statement of a query string completes with its own tag
+    let result = db.execute_sql("INSERT INTO tagged VALUES (5, 'e'); SELECT id FROM tagged WHERE id > 3;")
+        .expect("query failed");
+    assert!(result.contains("INSERT 0 1"), "unexpected output: {}", result);
+    assert!(result.contains("(2 rows)"), "unexpected output: {}", result);
 }
 
 #[test]
@@ -117,4 +144,4 @@ fn test_table_already_exists() {
         result.is_err() || result.unwrap().contains("ERROR"),
         "duplicate CREATE TABLE should fail"
     );
-}
\ No newline at end of file
+}
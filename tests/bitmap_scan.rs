@@ -0,0 +1,112 @@
+mod common;
+
+use common::{data_rows, explain, ids, insert_rows, TestDb};
+use serial_test::serial;
+
+/// `orders` with ids 1 to 3000, indexed by id (primary key) and by code
+fn setup(db: &TestDb) {
+    db.execute_sql("CREATE TABLE orders (id INT, code INT, region STRING, amount INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    insert_rows(db, "orders", (1..=3000).map(|id| format!("({}, {}, 'r{}', {})", id, id * 7, id % 4, id % 50)));
+    db.execute_sql("CREATE INDEX idx_code ON orders (code);").expect("CREATE INDEX failed");
+    db.execute_sql("ANALYZE orders;").expect("ANALYZE failed");
+}
+
+#[test]
+#[serial]
+fn test_or_across_indexes() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let query = "SELECT id FROM orders WHERE id = 5 OR code = 700;";
+    assert_eq!(ids(&db, query), vec![5, 100]);
+    let plan = explain(&db, query);
+    assert!(plan.contains("Bitmap Heap Scan on orders"), "unexpected plan: {}", plan);
+    assert!(plan.contains("Recheck Cond: (id = 5 OR code = 700)"), "unexpected plan: {}", plan);
+    assert!(plan.contains("BitmapOr"), "unexpected plan: {}", plan);
+    assert!(plan.contains("Bitmap Index Scan on orders_pkey"), "unexpected plan: {}", plan);
+    assert!(plan.contains("Index Cond: (code = 700)"), "unexpected plan: {}", plan);
+    assert!(!plan.contains("Seq Scan"), "unexpected plan: {}", plan);
+
+    // Ranges of the primary key, rows found twice and other predicates
+    let query = "SELECT id FROM orders WHERE (code = 70 OR (id >= 20 AND id <= 22) OR id > 2998 OR code = 140) AND region = 'r2';";
+    assert_eq!(ids(&db, query), vec![10, 22]);
+    let plan = explain(&db, query);
+    assert!(plan.contains("Index Cond: ((id >= 20) AND (id <= 22))"), "unexpected plan: {}", plan);
+    assert!(plan.contains("Filter: (region = 'r2')"), "unexpected plan: {}", plan);
+    assert_eq!(ids(&db, "SELECT id FROM orders WHERE id = 5 OR code = 35;"), vec![5]);
+
+    // Rows come back in physical order, whichever index found them
+    assert_eq!(ids(&db, "SELECT id FROM orders WHERE code = 21000 OR id = 1 OR code = 14;"), vec![1, 2, 3000]);
+
+    // Every arm of an OR needs an index
+    let plan = explain(&db, "SELECT id FROM orders WHERE id = 5 OR amount = 3;");
+    assert!(plan.contains("Seq Scan"), "unexpected plan: {}", plan);
+    assert!(!plan.contains("Bitmap"), "unexpected plan: {}", plan);
+}
+
+#[test]
+#[serial]
+fn test_and_within_or() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let query = "SELECT id FROM orders WHERE (id = 5 AND code = 35) OR (code = 700 AND amount = 0);";
+    assert_eq!(ids(&db, query), vec![5, 100]);
+    let plan = explain(&db, query);
+    assert!(plan.contains("BitmapOr"), "unexpected plan: {}", plan);
+    assert!(plan.contains("BitmapAnd"), "unexpected plan: {}", plan);
+
+    // Both indexes must find the row
+    assert_eq!(ids(&db, "SELECT id FROM orders WHERE (id = 5 AND code = 36) OR code = 700;"), vec![100]);
+
+    let result = db.execute_sql(&format!("EXPLAIN ANALYZE {}", query)).expect("EXPLAIN ANALYZE failed");
+    assert!(result.contains("Bitmap Index Scan on idx_code"), "unexpected plan: {}", result);
+    assert!(result.contains("actual time="), "unexpected plan: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_bitmap_scan_sees_changes() {
+    let db = TestDb::new();
+    setup(&db);
+
+    db.execute_sql("DELETE FROM orders WHERE id = 5;").expect("DELETE failed");
+    db.execute_sql("UPDATE orders SET code = 1 WHERE id = 100;").expect("UPDATE failed");
+    db.execute_sql("UPDATE orders SET amount = 99 WHERE id = 200;").expect("UPDATE failed");
+
+    let query = "SELECT id, code, amount FROM orders WHERE id = 5 OR code = 700 OR code = 1 OR id = 200;";
+    let result = db.execute_sql(query).expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["100|1|0", "200|1400|99"]);
+    assert!(explain(&db, query).contains("Bitmap Heap Scan"));
+
+    // Same rows with bitmap scans disabled
+    let disabled = "SET enable_bitmapscan = off;";
+    let plan = db.execute_session(&[disabled, &format!("EXPLAIN {}", query)]).expect("EXPLAIN failed");
+    assert!(!plan.contains("Bitmap"), "unexpected plan: {}", plan);
+    let result = db.execute_session(&[disabled, query]).expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["100|1|0", "200|1400|99"]);
+}
+
+#[test]
+#[serial]
+fn test_bitmap_scan_over_repeated_values() {
+    let db = TestDb::new();
+    setup(&db);
+    db.execute_sql("CREATE INDEX idx_amount ON orders (amount);").expect("CREATE INDEX failed");
+
+    // Each amount is shared by 60 rows, every one of which the bitmap holds
+    let query = "SELECT count(*) FROM orders WHERE amount = 2 OR amount = 3 OR code = 7;";
+    let plan = explain(&db, query);
+    assert!(plan.contains("Bitmap Index Scan on idx_amount"), "unexpected plan: {}", plan);
+    assert_eq!(ids(&db, query), vec![121]);
+
+    let query = "SELECT id FROM orders WHERE amount = 2 OR id = 1;";
+    assert!(explain(&db, query).contains("BitmapOr"));
+    let expected: Vec<i64> = std::iter::once(1).chain((2..=3000).step_by(50)).collect();
+    assert_eq!(ids(&db, query), expected);
+
+    // Updated rows are found once, through either of their index entries
+    db.execute_sql("UPDATE orders SET code = 0 WHERE amount = 2;").expect("UPDATE failed");
+    assert_eq!(ids(&db, "SELECT count(*) FROM orders WHERE amount = 2 OR code = 0;"), vec![60]);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{data_rows, error, TestDb};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn test_explicit_casts() {
+    let db = TestDb::new();
+
+    let result = db
+        .execute_sql("SELECT '42'::int, CAST(' -7 ' AS BIGINT), 2.5::int, 3.5::integer, CAST(1.4 AS INT), 7::float8 / 2;")
+        .expect("SELECT failed");
+    // Floats round half to even
+    assert_eq!(data_rows(&result), vec!["42|-7|2|4|1|3.5"]);
+
+    let result = db
+        .execute_sql("SELECT true::int, 0::bool, 'yes'::boolean, CAST('f' AS BOOLEAN), 12::text || 'x', BOOLEAN 'on';")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1|f|t|f|12x|t"]);
+
+    assert_eq!(error("SELECT 'abc'::int;"), "22P02: invalid input syntax for type bigint: \"abc\"");
+    assert_eq!(error("SELECT '1.5'::int;"), "22P02: invalid input syntax for type bigint: \"1.5\"");
+    assert_eq!(error("SELECT 'maybe'::bool;"), "22P02: invalid input syntax for type boolean: \"maybe\"");
+    assert_eq!(
+        error("SELECT '99999999999999999999'::int;"),
+        "22003: value \"99999999999999999999\" is out of range for type bigint"
+    );
+    assert_eq!(error("SELECT 1e300::int;"), "22003: bigint out of range");
+    assert_eq!(error("SELECT true::float;"), "42846: cannot cast type boolean to double precision");
+    assert_eq!(error("SELECT 1::nosuchtype;"), "42704: type \"nosuchtype\" does not exist");
+}
+
+#[test]
+#[serial]
+fn test_mixed_numeric_comparisons() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE items (id INT, price FLOAT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO items VALUES (1, 1.0), (2, 2.5), (3, 3.0);")
+        .expect("INSERT failed");
+
+    // The primary key lookup probes for the integer key
+    let result = db.execute_sql("SELECT id FROM items WHERE id = 2.0;").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["2"]);
+    let result = db.execute_sql("SELECT id FROM items WHERE id = 2.5;").expect("SELECT failed");
+    assert!(data_rows(&result).is_empty());
+
+    let result = db.execute_sql("SELECT id FROM items WHERE price = 3 OR price = id ORDER BY id;").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1", "3"]);
+}
+
+#[test]
+#[serial]
+fn test_string_literals_take_operand_type() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE e (id INT, sal INT, rate FLOAT, active BOOL, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO e VALUES (1, 50, 1.5, true), (2, 70, 2.5, false), (3, 90, 3.5, true);")
+        .expect("INSERT failed");
+
+    let result = db.execute_sql("SELECT id FROM e WHERE sal > '60' ORDER BY id;").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["2", "3"]);
+    let result = db.execute_sql("SELECT id FROM e WHERE rate < '3' AND active = 'yes';").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1"]);
+    let result = db.execute_sql("SELECT sal + '5', sal || 'x' FROM e WHERE id IN ('1', '4');").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["55|50x"]);
+
+    // Primary key lookups and ranges probe for the integer key
+    let result = db.execute_sql("SELECT sal FROM e WHERE id = '2';").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["70"]);
+    let result = db.execute_sql("SELECT sal FROM e WHERE id > '1' AND id <= '2';").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["70"]);
+
+    assert_eq!(error("SELECT id FROM e WHERE id = '1.5';"), "22P02: invalid input syntax for type bigint: \"1.5\"");
+}
+
+#[test]
+#[serial]
+fn test_assignment_casts() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE t (id INT, qty INT, note TEXT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+
+    // Floats round into integer columns, numbers are stored as text, and
+    // string literals are read by the column type's input function
+    db.execute_sql("INSERT INTO t VALUES (1, 5.7, 10), ('2', '8', 'eight');")
+        .expect("INSERT failed");
+    db.execute_sql("UPDATE t SET qty = qty / 2.0 WHERE id = 2;").expect("UPDATE failed");
+
+    let result = db.execute_sql("SELECT id, qty, note || '!' FROM t ORDER BY id;").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1|6|10!", "2|4|eight!"]);
+
+    assert_eq!(
+        error("INSERT INTO t VALUES (3, true, 'x');"),
+        "42804: column \"qty\" is of type bigint but expression is of type boolean"
+    );
+    assert_eq!(error("INSERT INTO t VALUES (3, 'x', 'x');"), "22P02: invalid input syntax for type bigint: \"x\"");
+    // Only literals are read as the column's type; text expressions need a cast
+    assert_eq!(
+        error("UPDATE t SET qty = note WHERE id = 1;"),
+        "42804: column \"qty\" is of type bigint but expression is of type text"
+    );
+}
+
+#[test]
+#[serial]
+fn test_cast_errors_in_predicates() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE t (id INT, note TEXT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO t VALUES (1, '5'), (2, 'x'), (3, '7');")
+        .expect("INSERT failed");
+
+    let invalid = "22P02: invalid input syntax for type bigint: \"x\"";
+    assert_eq!(error("SELECT id FROM t WHERE note::int > 0;"), invalid);
+    assert_eq!(error("SELECT note, count(*) FROM t GROUP BY note HAVING note::int > 0;"), invalid);
+    assert_eq!(error("SELECT a.id FROM t a JOIN t b ON a.id = b.id AND b.note::int = 5;"), invalid);
+    assert_eq!(
+        error("SELECT id FROM t WHERE 'abc'::int = id + 0;"),
+        "22P02: invalid input syntax for type bigint: \"abc\""
+    );
+
+    let result = db.execute_sql("SELECT id FROM t WHERE note <> 'x' AND note::int > 5;").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["3"]);
+}
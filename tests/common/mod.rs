@@ -1,3 +1,6 @@
+// Not every test binary uses every helper
+#![allow(dead_code)]
+
 use std::fs;
 use std::path::PathBuf;
 use std::process::{Command, Child};
@@ -99,6 +102,27 @@ impl TestDb {
         Ok(stdout)
     }
 
+    /// Execute statements in order over a single connection, so settings
+    /// made by one apply to the ones after it
+    /// psql runs quietly: SET and other commands print nothing, and the
+    /// output holds the rows of the statements that return some.
+    pub fn execute_session(&self, statements: &[&str]) -> Result<String, String> {
+        let mut args = vec!["-h", "127.0.0.1", "-U", "postgres", "-d", "postgres", "-q"];
+        for sql in statements {
+            args.extend(["-c", sql]);
+        }
+        let output = Command::new("psql")
+            .args(&args)
+            .output()
+            .map_err(|e| format!("failed to execute psql: {}", e))?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        if stderr.contains("ERROR") {
+            return Err(stderr);
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
     /// Restart database (kill server, delete files, restart)
     pub fn restart(&mut self) -> Result<(), String> {
         // Kill server
@@ -150,4 +174,52 @@ impl Drop for TestDb {
             thread::sleep(Duration::from_millis(100));
         }
     }
-}
\ No newline at end of file
+}
+
+/// Data rows of psql output, with cells trimmed and joined by `|`
+pub fn data_rows(result: &str) -> Vec<String> {
+    result
+        .lines()
+        .skip(2)
+        .take_while(|line| !line.starts_with('('))
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.split('|').map(str::trim).collect::<Vec<_>>().join("|"))
+        .collect()
+}
+
+/// Run a failing statement and return its SQLSTATE and message, as psql
+/// prints them in verbose mode ("22P02: invalid input syntax ...")
+pub fn error(sql: &str) -> String {
+    let output = Command::new("psql")
+        .args(["-h", "127.0.0.1", "-U", "postgres", "-d", "postgres", "-v", "VERBOSITY=verbose", "-c", sql])
+        .output()
+        .expect("failed to execute psql");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stderr
+        .lines()
+        .find_map(|line| line.strip_prefix("ERROR:"))
+        .unwrap_or_else(|| panic!("{} did not fail: {}", sql, stderr))
+        .trim()
+        .to_string()
+}
+
+/// Insert `rows`, each a parenthesized list of values, into `table` a couple
+/// of hundred rows per statement
+pub fn insert_rows(db: &TestDb, table: &str, rows: impl IntoIterator<Item = String>) {
+    let rows = rows.into_iter().collect::<Vec<_>>();
+    for chunk in rows.chunks(200) {
+        db.execute_sql(&format!("INSERT INTO {} VALUES {};", table, chunk.join(", ")))
+            .expect("INSERT failed");
+    }
+}
+
+/// Integer ids returned by a query selecting only them
+pub fn ids(db: &TestDb, sql: &str) -> Vec<i64> {
+    let result = db.execute_sql(sql).expect("SELECT failed");
+    data_rows(&result).iter().map(|row| row.parse().expect("not an id")).collect()
+}
+
+/// Plan of `sql` as EXPLAIN prints it
+pub fn explain(db: &TestDb, sql: &str) -> String {
+    db.execute_sql(&format!("EXPLAIN {}", sql)).expect("EXPLAIN failed")
+}
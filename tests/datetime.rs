@@ -0,0 +1,149 @@
+mod common;
+
+use common::{data_rows, error, TestDb};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn test_datetime_input_and_output() {
+    let db = TestDb::new();
+
+    let result = db
+        .execute_sql(
+            "SELECT DATE '2024-02-29', '13:05:09.25'::time, TIMESTAMP '2024-03-01 08:30', \
+             '2024-03-01 08:30:00+02'::timestamptz, '0044-03-15'::date - 1;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["2024-02-29|13:05:09.25|2024-03-01 08:30:00|2024-03-01 06:30:00+00|0044-03-14"]
+    );
+
+    let result = db
+        .execute_sql("SELECT INTERVAL '1 year 2 months 3 days 04:05:06', '-90 minutes'::interval, INTERVAL '36' HOUR;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1 year 2 mons 3 days 04:05:06|-01:30:00|36:00:00"]);
+
+    assert_eq!(error("SELECT '2024-02-30'::date;"), "22008: date/time field value out of range: \"2024-02-30\"");
+    assert_eq!(error("SELECT 'soon'::timestamp;"), "22007: invalid input syntax for type timestamp: \"soon\"");
+    assert_eq!(error("SELECT true::date;"), "42846: cannot cast type boolean to date");
+}
+
+#[test]
+#[serial]
+fn test_datetime_arithmetic() {
+    let db = TestDb::new();
+
+    let result = db
+        .execute_sql(
+            "SELECT DATE '2024-01-31' + INTERVAL '1 month', DATE '2024-03-01' - DATE '2024-02-01', \
+             TIMESTAMP '2024-03-01 12:00' - TIMESTAMP '2024-02-28 06:00', TIME '23:30' + INTERVAL '1 hour', \
+             INTERVAL '1 day' * 1.5, DATE '2024-01-01' + 7;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["2024-02-29 00:00:00|29|2 days 06:00:00|00:30:00|1 day 12:00:00|2024-01-08"]
+    );
+
+    let result = db
+        .execute_sql(
+            "SELECT DATE '2024-01-01' < TIMESTAMP '2024-01-01 00:00:01', INTERVAL '1 day' = INTERVAL '24 hours', \
+             TIMESTAMPTZ '2024-01-01 00:00+00' = TIMESTAMP '2024-01-01';",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["t|t|t"]);
+
+    assert_eq!(error("SELECT INTERVAL '1 day' / 0;"), "22012: division by zero");
+}
+
+#[test]
+#[serial]
+fn test_datetime_functions() {
+    let db = TestDb::new();
+
+    let result = db
+        .execute_sql(
+            "SELECT date_trunc('month', TIMESTAMP '2024-05-17 10:11:12'), \
+             extract(year FROM DATE '2024-05-17'), EXTRACT(DOW FROM TIMESTAMP '2024-05-17 10:00'), \
+             date_part('hour', INTERVAL '1 day 05:00'), extract(epoch FROM TIMESTAMPTZ '1970-01-02 00:00+00');",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["2024-05-01 00:00:00|2024|5|5.0|86400.000000"]);
+
+    // extract is an exact numeric, to the microsecond; date_part stays float8
+    let result = db
+        .execute_sql(
+            "SELECT extract(second FROM TIMESTAMP '2024-05-17 10:11:12.5'), extract(ms FROM TIME '10:11:12.5'), \
+             extract(epoch FROM INTERVAL '1 day 05:00'), extract(epoch FROM DATE '1970-01-02'), \
+             date_part('second', TIMESTAMP '2024-05-17 10:11:12.5');",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["12.500000|12500.000|104400.000000|86400|12.5"]);
+
+    let result = db
+        .execute_sql(
+            "SELECT TIMESTAMP '2024-07-01 12:00' AT TIME ZONE 'America/New_York', \
+             TIMESTAMPTZ '2024-01-01 12:00+00' AT TIME ZONE 'Asia/Kolkata', \
+             timezone(INTERVAL '-02:00', TIMESTAMPTZ '2024-01-01 12:00+00');",
+        )
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["2024-07-01 16:00:00+00|2024-01-01 17:30:00|2024-01-01 10:00:00"]
+    );
+
+    // now() and current_timestamp read the clock
+    let result = db
+        .execute_sql("SELECT now() > TIMESTAMPTZ '2024-01-01 00:00+00', current_date >= DATE '2024-01-01', current_timestamp - now() < INTERVAL '1 minute';")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["t|t|t"]);
+
+    assert_eq!(
+        error("SELECT extract(fortnight FROM DATE '2024-01-01');"),
+        "22023: unit \"fortnight\" not recognized for type date"
+    );
+    assert_eq!(
+        error("SELECT TIMESTAMP '2024-01-01' AT TIME ZONE 'Mars/Olympus';"),
+        "22023: time zone \"Mars/Olympus\" not recognized"
+    );
+}
+
+#[test]
+#[serial]
+fn test_datetime_columns_and_keys() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE events (day DATE, at TIMESTAMP, took INTERVAL, PRIMARY KEY (day));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql(
+        "INSERT INTO events VALUES ('2024-01-03', '2024-01-03 09:00', '1 hour'), \
+         ('2024-01-01', '2024-01-01 18:30', '90 minutes'), ('2023-12-30', '2023-12-30 07:15', '2 hours');",
+    )
+    .expect("INSERT failed");
+
+    // Range and equality lookups on the date key, bounds given as literals
+    let plan = db
+        .execute_sql("EXPLAIN SELECT day FROM events WHERE day >= '2024-01-01' ORDER BY day;")
+        .expect("EXPLAIN failed");
+    assert!(plan.contains("Index Scan using events_pkey on events"), "unexpected plan: {}", plan);
+    let result = db
+        .execute_sql("SELECT day FROM events WHERE day >= '2024-01-01' ORDER BY day;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["2024-01-01", "2024-01-03"]);
+    let result = db
+        .execute_sql("SELECT day FROM events WHERE day < TIMESTAMP '2024-01-01 12:00' ORDER BY day DESC;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["2024-01-01", "2023-12-30"]);
+    let result = db.execute_sql("SELECT at FROM events WHERE day = '2024-01-03';").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["2024-01-03 09:00:00"]);
+
+    let result = db
+        .execute_sql("SELECT sum(took), max(at), min(day) FROM events WHERE at BETWEEN '2024-01-01' AND '2024-12-31';")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["02:30:00|2024-01-03 09:00:00|2024-01-01"]);
+
+    let result = db
+        .execute_sql("SELECT day FROM events WHERE at + took > '2024-01-01 19:30' ORDER BY day;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["2024-01-01", "2024-01-03"]);
+}
@@ -0,0 +1,343 @@
+mod common;
+
+use common::{data_rows, TestDb};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn test_update_with_where() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE numbers (id INT, value INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO numbers VALUES (1, 100), (2, 200), (3, 300);")
+        .expect("INSERT failed");
+
+    let result = db
+        .execute_sql("UPDATE numbers SET value = 999 WHERE id = 2;")
+        .expect("UPDATE failed");
+    assert!(result.contains("UPDATE 1"), "expected UPDATE 1, got: {}", result);
+
+    let result = db
+        .execute_sql("SELECT value FROM numbers WHERE id = 2;")
+        .expect("SELECT failed");
+    assert!(result.contains("999"), "updated value not found");
+
+    let result = db
+        .execute_sql("SELECT * FROM numbers;")
+        .expect("SELECT failed");
+    assert!(result.contains("(3 rows)"), "update should not duplicate rows: {}", result);
+    assert!(!result.contains("200"), "old version should not be visible");
+}
+
+#[test]
+#[serial]
+fn test_update_all_rows_with_expression() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE counters (id INT, hits INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO counters VALUES (1, 10), (2, 20), (3, 30);")
+        .expect("INSERT failed");
+
+    let result = db
+        .execute_sql("UPDATE counters SET hits = hits + 1;")
+        .expect("UPDATE failed");
+    assert!(result.contains("UPDATE 3"), "expected UPDATE 3, got: {}", result);
+
+    // Repeated updates of the same rows stay on the HOT path
+    for _ in 0..4 {
+        db.execute_sql("UPDATE counters SET hits = hits + 1;")
+            .expect("UPDATE failed");
+    }
+
+    let result = db
+        .execute_sql("SELECT * FROM counters;")
+        .expect("SELECT failed");
+    assert!(result.contains("(3 rows)"), "expected 3 rows: {}", result);
+    assert!(result.contains("15"), "hits not incremented");
+    assert!(result.contains("25"), "hits not incremented");
+    assert!(result.contains("35"), "hits not incremented");
+}
+
+#[test]
+#[serial]
+fn test_update_no_matching_rows() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE items (id INT, name STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO items VALUES (1, 'a');")
+        .expect("INSERT failed");
+
+    let result = db
+        .execute_sql("UPDATE items SET name = 'b' WHERE id = 42;")
+        .expect("UPDATE failed");
+    assert!(result.contains("UPDATE 0"), "expected UPDATE 0, got: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_update_primary_key() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE accounts (id INT, owner STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO accounts VALUES (1, 'alice'), (2, 'bob');")
+        .expect("INSERT failed");
+
+    db.execute_sql("UPDATE accounts SET id = 10 WHERE id = 1;")
+        .expect("UPDATE failed");
+
+    let result = db
+        .execute_sql("SELECT owner FROM accounts WHERE id = 10;")
+        .expect("SELECT failed");
+    assert!(result.contains("alice"), "row should be reachable by new key");
+
+    let result = db
+        .execute_sql("SELECT owner FROM accounts WHERE id = 1;")
+        .expect("SELECT failed");
+    assert!(!result.contains("alice"), "row should not be reachable by old key");
+
+    // Moving onto an existing key violates the primary key
+    let result = db.execute_sql("UPDATE accounts SET id = 2 WHERE id = 10;");
+    assert!(
+        result.is_err() || result.unwrap().contains("ERROR"),
+        "duplicate PK on UPDATE should fail"
+    );
+}
+
+#[test]
+#[serial]
+fn test_update_primary_key_conflict_writes_nothing() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE at (id INT, name STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO at VALUES (1, 'a'), (5, 'b'), (9, 'c');")
+        .expect("INSERT failed");
+
+    // Row 1 would be written before the conflict on 9 is found
+    let result = db.execute_sql("UPDATE at SET id = CASE WHEN id = 1 THEN 2 ELSE 9 END;");
+    let err = result.expect_err("duplicate PK on UPDATE should fail");
+    assert!(err.contains("duplicate key value violates unique constraint \"at_pkey\""), "{}", err);
+
+    let result = db.execute_sql("SELECT id, name FROM at ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|a", "5|b", "9|c"], "{}", result);
+
+    // Keys the statement moves away from may be reused by other rows
+    db.execute_sql("UPDATE at SET id = CASE WHEN id = 1 THEN 2 WHEN id = 5 THEN 9 ELSE 100 END;")
+        .expect("UPDATE failed");
+    let result = db.execute_sql("SELECT id, name FROM at ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["2|a", "9|b", "100|c"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_update_secondary_indexed_column() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE products (id INT, sku INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO products VALUES (1, 500), (2, 600);")
+        .expect("INSERT failed");
+    db.execute_sql("CREATE INDEX idx_sku ON products (sku);")
+        .expect("CREATE INDEX failed");
+
+    db.execute_sql("UPDATE products SET sku = 700 WHERE id = 1;")
+        .expect("UPDATE failed");
+
+    let result = db
+        .execute_sql("SELECT id FROM products WHERE sku = 700;")
+        .expect("SELECT failed");
+    assert!(result.contains("(1 row)"), "new key should be indexed: {}", result);
+
+    let result = db
+        .execute_sql("SELECT id FROM products WHERE sku = 500;")
+        .expect("SELECT failed");
+    assert!(!result.contains("(1 row)"), "old key should not match: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_secondary_index_duplicate_values() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE items (id INT, g INT, tag STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO items VALUES (1, 10, 'a'), (2, 20, 'b'), (3, 10, 'a'), (4, 10, 'b');")
+        .expect("INSERT failed");
+    // One index built over existing rows, one filled by later inserts
+    db.execute_sql("CREATE INDEX idx_g ON items (g);")
+        .expect("CREATE INDEX failed");
+    db.execute_sql("CREATE INDEX idx_tag ON items USING hash (tag);")
+        .expect("CREATE INDEX failed");
+    db.execute_sql("INSERT INTO items VALUES (5, 10, 'a'), (6, 30, 'a');")
+        .expect("INSERT failed");
+
+    let plan = db
+        .execute_sql("EXPLAIN SELECT id FROM items WHERE g = 10;")
+        .expect("EXPLAIN failed");
+    assert!(plan.contains("Index Scan using idx_g on items"), "unexpected plan: {}", plan);
+    let result = db
+        .execute_sql("SELECT id FROM items WHERE g = 10;")
+        .expect("SELECT failed");
+    assert!(result.contains("(4 rows)"), "every duplicate should be found: {}", result);
+    let result = db
+        .execute_sql("SELECT id FROM items WHERE tag = 'a';")
+        .expect("SELECT failed");
+    assert!(result.contains("(4 rows)"), "every duplicate should be found: {}", result);
+
+    let result = db
+        .execute_sql("UPDATE items SET tag = 'c' WHERE g = 10;")
+        .expect("UPDATE failed");
+    assert!(result.contains("UPDATE 4"), "expected UPDATE 4, got: {}", result);
+    let result = db
+        .execute_sql("SELECT id FROM items WHERE tag = 'c';")
+        .expect("SELECT failed");
+    assert!(result.contains("(4 rows)"), "updated rows should be indexed: {}", result);
+    let result = db
+        .execute_sql("SELECT id FROM items WHERE tag = 'a';")
+        .expect("SELECT failed");
+    assert!(result.contains("(1 row)"), "old keys should not match: {}", result);
+    // Rows reached through both their old and new index entries appear once
+    let result = db
+        .execute_sql("SELECT id FROM items WHERE g = 10;")
+        .expect("SELECT failed");
+    assert!(result.contains("(4 rows)"), "updated rows should appear once: {}", result);
+
+    let result = db
+        .execute_sql("DELETE FROM items WHERE g = 10;")
+        .expect("DELETE failed");
+    assert!(result.contains("DELETE 4"), "expected DELETE 4, got: {}", result);
+    let result = db
+        .execute_sql("SELECT id FROM items;")
+        .expect("SELECT failed");
+    assert!(result.contains("(2 rows)"), "expected 2 rows: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_delete_with_where() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE numbers (id INT, value INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO numbers VALUES (1, 100), (2, 200), (3, 300);")
+        .expect("INSERT failed");
+
+    let result = db
+        .execute_sql("DELETE FROM numbers WHERE id = 2;")
+        .expect("DELETE failed");
+    assert!(result.contains("DELETE 1"), "expected DELETE 1, got: {}", result);
+
+    let result = db
+        .execute_sql("SELECT * FROM numbers;")
+        .expect("SELECT failed");
+    assert!(result.contains("(2 rows)"), "expected 2 rows: {}", result);
+    assert!(!result.contains("200"), "deleted row should not be visible");
+
+    // Deleted rows are hidden from index lookups too
+    let result = db
+        .execute_sql("SELECT * FROM numbers WHERE id = 2;")
+        .expect("SELECT failed");
+    assert!(!result.contains("200"), "deleted row found through index");
+
+    // The key can be reused once the row is gone
+    db.execute_sql("INSERT INTO numbers VALUES (2, 222);")
+        .expect("re-INSERT of deleted key failed");
+    let result = db
+        .execute_sql("SELECT value FROM numbers WHERE id = 2;")
+        .expect("SELECT failed");
+    assert!(result.contains("222"), "re-inserted row not found");
+}
+
+#[test]
+#[serial]
+fn test_delete_non_key_predicate() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE events (id INT, kind STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO events VALUES (1, 'click'), (2, 'view'), (3, 'click'), (4, 'view');")
+        .expect("INSERT failed");
+    db.execute_sql("CREATE INDEX idx_kind ON events (kind);")
+        .expect("CREATE INDEX failed");
+
+    let result = db
+        .execute_sql("DELETE FROM events WHERE id > 2;")
+        .expect("DELETE failed");
+    assert!(result.contains("DELETE 2"), "expected DELETE 2, got: {}", result);
+
+    let result = db
+        .execute_sql("DELETE FROM events WHERE id > 2;")
+        .expect("DELETE failed");
+    assert!(result.contains("DELETE 0"), "already deleted rows matched: {}", result);
+
+    let result = db
+        .execute_sql("SELECT * FROM events;")
+        .expect("SELECT failed");
+    assert!(result.contains("(2 rows)"), "expected 2 rows: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_delete_all_rows() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE items (id INT, name STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO items VALUES (1, 'a'), (2, 'b'), (3, 'c');")
+        .expect("INSERT failed");
+    db.execute_sql("UPDATE items SET name = 'z' WHERE id = 1;")
+        .expect("UPDATE failed");
+
+    let result = db
+        .execute_sql("DELETE FROM items;")
+        .expect("DELETE failed");
+    assert!(result.contains("DELETE 3"), "expected DELETE 3, got: {}", result);
+
+    let result = db
+        .execute_sql("SELECT * FROM items;")
+        .expect("SELECT failed");
+    assert!(!result.contains("z"), "updated row survived DELETE: {}", result);
+    assert!(!result.contains("b"), "row survived DELETE: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_truncate_table() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE logs (id INT, level INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO logs VALUES (1, 10), (2, 20), (3, 30);")
+        .expect("INSERT failed");
+    db.execute_sql("CREATE INDEX idx_level ON logs (level);")
+        .expect("CREATE INDEX failed");
+
+    let result = db
+        .execute_sql("TRUNCATE TABLE logs;")
+        .expect("TRUNCATE failed");
+    assert!(result.contains("TRUNCATE TABLE"), "expected TRUNCATE TABLE, got: {}", result);
+
+    let result = db
+        .execute_sql("SELECT * FROM logs WHERE level = 20;")
+        .expect("SELECT failed");
+    assert!(!result.contains("20"), "truncated row found through index");
+
+    // Table and indexes are usable again after truncation
+    db.execute_sql("INSERT INTO logs VALUES (2, 40);")
+        .expect("INSERT after TRUNCATE failed");
+    let result = db
+        .execute_sql("SELECT id FROM logs WHERE level = 40;")
+        .expect("SELECT failed");
+    assert!(result.contains("(1 row)"), "row not indexed after TRUNCATE: {}", result);
+
+    let result = db
+        .execute_sql("SELECT * FROM logs;")
+        .expect("SELECT failed");
+    assert!(result.contains("(1 row)"), "expected 1 row: {}", result);
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use common::{insert_rows, TestDb};
+use serial_test::serial;
+
+/// Lines of the single `QUERY PLAN` column in psql output
+fn plan_lines(result: &str) -> Vec<String> {
+    result
+        .lines()
+        .skip(2)
+        .take_while(|line| !line.starts_with('('))
+        .map(|line| line.trim_end().to_string())
+        .collect()
+}
+
+fn setup(db: &TestDb) {
+    db.execute_sql("CREATE TABLE accounts (id INT, owner STRING, balance INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("CREATE TABLE transfers (id INT, account INT, amount INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    let accounts = (1..=200).map(|id| format!("({}, 'o{}', {})", id, id % 10, id * 3)).collect::<Vec<_>>();
+    db.execute_sql(&format!("INSERT INTO accounts VALUES {};", accounts.join(", ")))
+        .expect("INSERT failed");
+    let transfers = (1..=400).map(|id| format!("({}, {}, {})", id, id % 200 + 1, id % 17)).collect::<Vec<_>>();
+    db.execute_sql(&format!("INSERT INTO transfers VALUES {};", transfers.join(", ")))
+        .expect("INSERT failed");
+    db.execute_sql("ANALYZE accounts;").expect("ANALYZE failed");
+    db.execute_sql("ANALYZE transfers;").expect("ANALYZE failed");
+}
+
+#[test]
+#[serial]
+fn test_explain_shows_plan() {
+    let db = TestDb::new();
+    setup(&db);
+
+    // Small enough that reading the whole table beats an index lookup
+    let result = db.execute_sql("EXPLAIN SELECT * FROM accounts WHERE id = 7;").expect("EXPLAIN failed");
+    let lines = plan_lines(&result);
+    assert!(lines.iter().any(|line| line.contains("Seq Scan on accounts  (cost=")), "unexpected plan: {:#?}", lines);
+    assert!(lines.iter().any(|line| line.trim() == "Filter: (id = 7)"), "unexpected plan: {:#?}", lines);
+
+    insert_rows(&db, "accounts", (201..=3000).map(|id| format!("({}, 'o{}', {})", id, id % 10, id * 3)));
+    db.execute_sql("ANALYZE accounts;").expect("ANALYZE failed");
+    let result = db.execute_sql("EXPLAIN SELECT * FROM accounts WHERE id = 7;").expect("EXPLAIN failed");
+    let lines = plan_lines(&result);
+    assert!(
+        lines.iter().any(|line| line.contains("->  Index Scan using accounts_pkey on accounts  (cost=") && line.ends_with("rows=1)")),
+        "unexpected plan: {:#?}",
+        lines
+    );
+    assert!(lines.iter().any(|line| line.trim() == "Index Cond: (id = 7)"), "unexpected plan: {:#?}", lines);
+
+    let result = db.execute_sql(
+        "EXPLAIN SELECT owner, COUNT(*) FROM accounts a JOIN transfers t ON t.account = a.id \
+         WHERE a.balance > 300 GROUP BY owner ORDER BY owner;",
+    ).expect("EXPLAIN failed");
+    let lines = plan_lines(&result);
+    for expected in ["->  Sort", "Sort Key: owner", "->  HashAggregate", "Group Key: owner", "Seq Scan on transfers t"] {
+        assert!(lines.iter().any(|line| line.contains(expected)), "missing {:?} in {:#?}", expected, lines);
+    }
+    assert!(!lines.iter().any(|line| line.contains("actual")), "EXPLAIN should not run the query: {:#?}", lines);
+}
+
+#[test]
+#[serial]
+fn test_explain_analyze() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let result = db.execute_sql(
+        "EXPLAIN ANALYZE SELECT a.id, t.amount FROM transfers t JOIN accounts a ON a.id = t.account WHERE t.id < 5;",
+    ).expect("EXPLAIN ANALYZE failed");
+    let lines = plan_lines(&result);
+    let line = |prefix: &str| lines.iter()
+        .find(|line| line.trim_start().starts_with(prefix))
+        .unwrap_or_else(|| panic!("missing {:?} in {:#?}", prefix, lines))
+        .clone();
+
+    assert!(line("Project").contains("rows=4 loops=1)"), "unexpected plan: {:#?}", lines);
+    assert!(line("->  Nested Loop").contains("(actual time="), "unexpected plan: {:#?}", lines);
+    assert!(line("->  Seq Scan on transfers t").contains("rows=400 loops=1)"), "unexpected plan: {:#?}", lines);
+    // The inner index is probed once per outer row
+    let probe = line("->  Index Scan using accounts_pkey on accounts a");
+    assert!(probe.contains("rows=1 loops=4)"), "unexpected plan: {:#?}", lines);
+    assert_eq!(line("Index Cond:"), "               Index Cond: (a.id = t.account)");
+    // The probe shows the cost of one lookup, a share of the join's total
+    let cost = |line: &str| -> f64 {
+        let start = line.find("..").expect("missing cost") + 2;
+        line[start..].split(' ').next().unwrap().parse().expect("invalid cost")
+    };
+    assert!(cost(&probe) * 4.0 <= cost(&line("->  Nested Loop")), "unexpected plan: {:#?}", lines);
+    assert!(line("Blocks: read=").len() > "Blocks: read=".len());
+    assert!(line("Planning Time:").ends_with(" ms"));
+    assert!(line("Execution Time:").ends_with(" ms"));
+
+    // The measured sort method replaces the planned one
+    let query = "SELECT id FROM accounts ORDER BY balance DESC LIMIT 3;";
+    let result = db.execute_sql(&format!("EXPLAIN ANALYZE {}", query)).expect("EXPLAIN ANALYZE failed");
+    assert_eq!(result.matches("Sort Method:").count(), 1, "unexpected plan: {}", result);
+    let result = db.execute_sql(&format!("EXPLAIN (ANALYZE, FORMAT JSON) {}", query)).expect("EXPLAIN ANALYZE failed");
+    assert_eq!(result.matches("\"Sort Method\"").count(), 1, "unexpected plan: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_explain_verbose_and_json() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let result = db.execute_sql(
+        "EXPLAIN VERBOSE SELECT id, (SELECT MAX(amount) FROM transfers t WHERE t.account = a.id) FROM accounts a WHERE id < 4;",
+    ).expect("EXPLAIN VERBOSE failed");
+    let lines = plan_lines(&result);
+    for expected in ["Output: a.id, a.owner, a.balance", "SubPlan 1", "Output: MAX(amount)", "Filter: (t.account = $outer1)"] {
+        assert!(lines.iter().any(|line| line.trim() == expected), "missing {:?} in {:#?}", expected, lines);
+    }
+
+    let result = db.execute_sql(
+        "EXPLAIN (ANALYZE, FORMAT JSON) SELECT id FROM accounts WHERE EXISTS \
+         (SELECT 1 FROM transfers t WHERE t.account = accounts.id AND t.amount = 3) LIMIT 3;",
+    ).expect("EXPLAIN (FORMAT JSON) failed");
+    for expected in [
+        "\"Node Type\": \"Limit\"",
+        "\"Node Type\": \"Hash Semi Join\"",
+        "\"Hash Cond\": \"(accounts.id = t.account)\"",
+        "\"Relation Name\": \"transfers\"",
+        "\"Actual Rows\": 3",
+        "\"Actual Loops\": 1",
+        "\"Execution Time\": ",
+    ] {
+        assert!(result.contains(expected), "missing {:?} in {}", expected, result);
+    }
+    assert!(result.contains("(1 row)"), "JSON output should be one row: {}", result);
+
+    let result = db.execute_sql("EXPLAIN (FORMAT JSON, ANALYZE off) SELECT * FROM accounts;")
+        .expect("EXPLAIN (FORMAT JSON) failed");
+    assert!(result.contains("\"Plan Rows\": 200"), "unexpected output: {}", result);
+    assert!(!result.contains("Actual Rows"), "unexpected output: {}", result);
+}
+
+#[test]
+#[serial]
+fn test_explain_options() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let result = db.execute_sql("EXPLAIN (BUFFERS) SELECT * FROM accounts;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("unrecognized EXPLAIN option \"buffers\"")),
+        "unexpected result: {:?}",
+        result
+    );
+    let result = db.execute_sql("EXPLAIN (FORMAT XML) SELECT * FROM accounts;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("unrecognized value for EXPLAIN option \"format\": \"xml\"")),
+        "unexpected result: {:?}",
+        result
+    );
+    let result = db.execute_sql("EXPLAIN (ANALYZE maybe) SELECT * FROM accounts;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("EXPLAIN option \"analyze\" requires a Boolean value")),
+        "unexpected result: {:?}",
+        result
+    );
+
+    // EXPLAIN ANALYZE runs the query, but only the plan is returned
+    let result = db.execute_sql("EXPLAIN ANALYZE SELECT owner FROM accounts WHERE id = 3;")
+        .expect("EXPLAIN ANALYZE failed");
+    assert!(!result.contains("o3"), "query rows should not be returned: {}", result);
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use common::{data_rows, error, TestDb};
+use serial_test::serial;
+
+fn setup(db: &TestDb) {
+    db.execute_sql("CREATE TABLE people (id INT, age INT, name STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql(
+        "INSERT INTO people VALUES (1, 30, 'Alice'), (2, NULL, 'bob'), (3, 45, 'Carol'), (4, 18, NULL), (5, 30, 'al_x');",
+    )
+    .expect("INSERT failed");
+}
+
+fn ids(db: &TestDb, predicate: &str) -> Vec<String> {
+    let sql = format!("SELECT id FROM people WHERE {} ORDER BY id;", predicate);
+    data_rows(&db.execute_sql(&sql).unwrap_or_else(|e| panic!("{} failed: {}", sql, e)))
+}
+
+#[test]
+#[serial]
+fn test_three_valued_logic() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let result = db
+        .execute_sql("SELECT (NULL = 1) IS NULL, NULL AND false, NULL OR true, (NULL AND true) IS UNKNOWN, NOT (1 = 2);")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["t|f|t|t|t"]);
+
+    // A comparison with NULL is neither true nor false
+    assert_eq!(ids(&db, "age = 30"), vec!["1", "5"]);
+    assert_eq!(ids(&db, "NOT (age = 30)"), vec!["3", "4"]);
+    assert_eq!(ids(&db, "age <> 30 OR name = 'bob'"), vec!["2", "3", "4"]);
+    assert_eq!(ids(&db, "(age > 20) IS NOT TRUE"), vec!["2", "4"]);
+    assert_eq!(ids(&db, "(age > 20) IS FALSE"), vec!["4"]);
+
+    // AND skips its right operand once the left is false
+    assert_eq!(ids(&db, "id > 4 AND 10 / (id - 3) > 1"), vec!["5"]);
+}
+
+#[test]
+#[serial]
+fn test_null_tests_and_lists() {
+    let db = TestDb::new();
+    setup(&db);
+
+    assert_eq!(ids(&db, "age IS NULL"), vec!["2"]);
+    assert_eq!(ids(&db, "name IS NOT NULL AND age IS NOT NULL"), vec!["1", "3", "5"]);
+    assert_eq!(ids(&db, "age IS DISTINCT FROM 30"), vec!["2", "3", "4"]);
+    assert_eq!(ids(&db, "age IS NOT DISTINCT FROM NULL"), vec!["2"]);
+
+    assert_eq!(ids(&db, "age IN (18, 45)"), vec!["3", "4"]);
+    assert_eq!(ids(&db, "age NOT IN (18, 45)"), vec!["1", "5"]);
+    // NOT IN a list holding NULL never holds
+    assert!(ids(&db, "age NOT IN (18, NULL)").is_empty());
+
+    assert_eq!(ids(&db, "age BETWEEN 18 AND 30"), vec!["1", "4", "5"]);
+    assert_eq!(ids(&db, "age NOT BETWEEN 18 AND 30"), vec!["3"]);
+}
+
+#[test]
+#[serial]
+fn test_pattern_matching() {
+    let db = TestDb::new();
+    setup(&db);
+
+    assert_eq!(ids(&db, "name LIKE 'A%'"), vec!["1"]);
+    assert_eq!(ids(&db, "name LIKE '_o%'"), vec!["2"]);
+    assert_eq!(ids(&db, "name ILIKE 'a%'"), vec!["1", "5"]);
+    assert_eq!(ids(&db, "name NOT LIKE '%o%'"), vec!["1", "5"]);
+    assert_eq!(ids(&db, "name LIKE 'al\\_%'"), vec!["5"]);
+    assert_eq!(ids(&db, "name LIKE 'al#_x' ESCAPE '#'"), vec!["5"]);
+    assert_eq!(ids(&db, "name SIMILAR TO '(A|C)%'"), vec!["1", "3"]);
+    assert_eq!(ids(&db, "name SIMILAR TO '[a-z]+'"), vec!["2"]);
+
+    let err = error("SELECT 'abc' LIKE 'ab\\';");
+    assert_eq!(err, "22025: LIKE pattern must not end with escape character");
+}
+
+#[test]
+#[serial]
+fn test_conditional_expressions() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let result = db
+        .execute_sql(
+            "SELECT id, CASE WHEN age < 20 THEN 'young' WHEN age < 40 THEN 'adult' ELSE 'other' END, \
+             CASE age WHEN 30 THEN 'thirty' END, COALESCE(name, 'nobody'), NULLIF(age, 30) \
+             FROM people ORDER BY id;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec![
+            "1|adult|thirty|Alice|",
+            "2|other||bob|",
+            "3|other||Carol|45",
+            "4|young||nobody|18",
+            "5|adult|thirty|al_x|",
+        ]
+    );
+
+    let result = db
+        .execute_sql("SELECT people.id, -age, 'id ' || people.id || ': ' || name FROM people WHERE people.id < 3 ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1|-30|id 1: Alice", "2||id 2: bob"]);
+
+    let result = db.execute_sql("SELECT 17 % 5, -7 % 3, -9223372036854775808;").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["2|-1|-9223372036854775808"]);
+}
+
+#[test]
+#[serial]
+fn test_arithmetic_errors() {
+    let db = TestDb::new();
+    setup(&db);
+
+    assert_eq!(error("SELECT 1 / 0;"), "22012: division by zero");
+    assert_eq!(error("SELECT 1.5 / 0;"), "22012: division by zero");
+    assert_eq!(error("SELECT id % (age - 30) FROM people;"), "22012: division by zero");
+    assert_eq!(error("SELECT 9223372036854775807 + id FROM people;"), "22003: bigint out of range");
+    assert_eq!(error("SELECT -(-9223372036854775808);"), "22003: bigint out of range");
+    assert_eq!(error("SELECT 1e308 * (id + 9) FROM people;"), "22003: value out of range: overflow");
+
+    assert_eq!(error("SELECT name + 1 FROM people;"), "42883: operator does not exist: text + bigint");
+    assert_eq!(
+        error("SELECT age AND true FROM people;"),
+        "42804: argument of AND must be type boolean, not type bigint"
+    );
+}
+
+#[test]
+#[serial]
+fn test_predicate_errors() {
+    let db = TestDb::new();
+    setup(&db);
+
+    // Rows whose predicate fails raise the error instead of being dropped
+    assert_eq!(error("SELECT id FROM people WHERE 10 / (id - 1) > 0;"), "22012: division by zero");
+    assert_eq!(
+        error("SELECT age, count(*) FROM people GROUP BY age HAVING 10 / (age - 30) > 0;"),
+        "22012: division by zero"
+    );
+    assert_eq!(
+        error("SELECT a.id FROM people a JOIN people b ON a.id = b.id AND 1 / (b.id - 3) = 1;"),
+        "22012: division by zero"
+    );
+
+    // Including the conditions a bitmap heap scan rechecks on each row
+    db.execute_sql("CREATE INDEX people_name ON people (name);").expect("CREATE INDEX failed");
+    let plan = db.execute_sql("EXPLAIN SELECT id FROM people WHERE name = 5 OR id = 3;").expect("EXPLAIN failed");
+    assert!(plan.contains("Recheck Cond: (name = 5 OR id = 3)"), "unexpected plan: {}", plan);
+    assert_eq!(
+        error("SELECT id FROM people WHERE name = 5 OR id = 3;"),
+        "42883: operator does not exist: text = bigint"
+    );
+
+    // Operands that are never reached on their own row cannot fail
+    assert_eq!(ids(&db, "id <> 1 AND 10 / (id - 1) > 0"), vec!["2", "3", "4", "5"]);
+    assert_eq!(ids(&db, "CASE WHEN id = 1 THEN false ELSE 10 / (id - 1) > 2 END"), vec!["2", "3", "4"]);
+}
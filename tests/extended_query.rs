@@ -0,0 +1,699 @@
+mod common;
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use common::TestDb;
+use serial_test::serial;
+
+const INT4: u32 = 23;
+const INT8: u32 = 20;
+const VARCHAR: u32 = 1043;
+const BOOL: u32 = 16;
+const TEXT: u32 = 25;
+const FLOAT8: u32 = 701;
+const DATE: u32 = 1082;
+const TIMESTAMPTZ: u32 = 1184;
+const INTERVAL: u32 = 1186;
+const NUMERIC: u32 = 1700;
+const UUID: u32 = 2950;
+const BYTEA: u32 = 17;
+
+/// A backend message: its type byte and body
+struct Message {
+    tag: u8,
+    body: Vec<u8>,
+}
+
+/// Frontend speaking the extended query protocol over a plain socket
+///
+/// psql only sends simple queries, so parameters cannot be bound through it.
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn connect() -> Self {
+        let stream = TcpStream::connect("127.0.0.1:5432").expect("connect failed");
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        let mut client = Client { stream };
+
+        // StartupMessage has no type byte
+        let mut body = 196608i32.to_be_bytes().to_vec();
+        for field in ["user", "postgres", "database", "postgres"] {
+            put_str(&mut body, field);
+        }
+        body.push(0);
+        let mut message = ((body.len() + 4) as i32).to_be_bytes().to_vec();
+        message.extend(body);
+        client.stream.write_all(&message).unwrap();
+        client.until_ready();
+        client
+    }
+
+    fn send(&mut self, tag: u8, body: &[u8]) {
+        let mut message = vec![tag];
+        message.extend(((body.len() + 4) as i32).to_be_bytes());
+        message.extend(body);
+        self.stream.write_all(&message).unwrap();
+    }
+
+    fn parse(&mut self, statement: &str, sql: &str, types: &[u32]) {
+        let mut body = Vec::new();
+        put_str(&mut body, statement);
+        put_str(&mut body, sql);
+        body.extend((types.len() as i16).to_be_bytes());
+        for oid in types {
+            body.extend(oid.to_be_bytes());
+        }
+        self.send(b'P', &body);
+    }
+
+    fn bind(&mut self, portal: &str, statement: &str, formats: &[i16], params: &[Option<&[u8]>]) {
+        self.bind_results(portal, statement, formats, params, &[]);
+    }
+
+    /// Bind with result column formats (none: all text)
+    fn bind_results(
+        &mut self,
+        portal: &str,
+        statement: &str,
+        formats: &[i16],
+        params: &[Option<&[u8]>],
+        result_formats: &[i16],
+    ) {
+        let mut body = Vec::new();
+        put_str(&mut body, portal);
+        put_str(&mut body, statement);
+        body.extend((formats.len() as i16).to_be_bytes());
+        for format in formats {
+            body.extend(format.to_be_bytes());
+        }
+        body.extend((params.len() as i16).to_be_bytes());
+        for param in params {
+            match param {
+                Some(value) => {
+                    body.extend((value.len() as i32).to_be_bytes());
+                    body.extend(*value);
+                }
+                None => body.extend((-1i32).to_be_bytes()),
+            }
+        }
+        body.extend((result_formats.len() as i16).to_be_bytes());
+        for format in result_formats {
+            body.extend(format.to_be_bytes());
+        }
+        self.send(b'B', &body);
+    }
+
+    /// Describe a statement (`S`) or portal (`P`)
+    fn describe(&mut self, kind: u8, name: &str) {
+        let mut body = vec![kind];
+        put_str(&mut body, name);
+        self.send(b'D', &body);
+    }
+
+    fn execute(&mut self, portal: &str, max_rows: i32) {
+        let mut body = Vec::new();
+        put_str(&mut body, portal);
+        body.extend(max_rows.to_be_bytes());
+        self.send(b'E', &body);
+    }
+
+    fn close(&mut self, kind: u8, name: &str) {
+        let mut body = vec![kind];
+        put_str(&mut body, name);
+        self.send(b'C', &body);
+    }
+
+    /// Run a simple query and return the messages received up to ReadyForQuery
+    fn query(&mut self, sql: &str) -> Vec<Message> {
+        let mut body = Vec::new();
+        put_str(&mut body, sql);
+        self.send(b'Q', &body);
+        self.until_ready()
+    }
+
+    /// Send Sync and return the messages received up to ReadyForQuery
+    fn sync(&mut self) -> Vec<Message> {
+        self.send(b'S', &[]);
+        self.until_ready()
+    }
+
+    fn until_ready(&mut self) -> Vec<Message> {
+        let mut messages = Vec::new();
+        loop {
+            let mut header = [0u8; 5];
+            self.stream.read_exact(&mut header).expect("read failed");
+            let len = i32::from_be_bytes(header[1..5].try_into().unwrap()) as usize;
+            let mut body = vec![0u8; len - 4];
+            self.stream.read_exact(&mut body).expect("read failed");
+            if header[0] == b'Z' {
+                return messages;
+            }
+            messages.push(Message { tag: header[0], body });
+        }
+    }
+}
+
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend(s.as_bytes());
+    buf.push(0);
+}
+
+/// Types of the messages, as characters
+fn tags(messages: &[Message]) -> String {
+    messages.iter().map(|message| message.tag as char).collect()
+}
+
+/// Raw cells of the DataRow messages, `None` for NULL
+fn cells(messages: &[Message]) -> Vec<Vec<Option<Vec<u8>>>> {
+    messages.iter()
+        .filter(|message| message.tag == b'D')
+        .map(|message| {
+            let body = &message.body;
+            let count = i16::from_be_bytes([body[0], body[1]]) as usize;
+            let mut pos = 2;
+            let mut cells = Vec::with_capacity(count);
+            for _ in 0..count {
+                let len = i32::from_be_bytes(body[pos..pos + 4].try_into().unwrap());
+                pos += 4;
+                if len < 0 {
+                    cells.push(None);
+                } else {
+                    cells.push(Some(body[pos..pos + len as usize].to_vec()));
+                    pos += len as usize;
+                }
+            }
+            cells
+        })
+        .collect()
+}
+
+/// Cells of the DataRow messages, NULL as `NULL`, joined by `|`
+fn data_rows(messages: &[Message]) -> Vec<String> {
+    cells(messages).into_iter()
+        .map(|row| row.into_iter()
+            .map(|cell| match cell {
+                Some(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+                None => "NULL".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("|"))
+        .collect()
+}
+
+/// Type OIDs of the ParameterDescription message
+fn parameter_types(messages: &[Message]) -> Vec<u32> {
+    let message = messages.iter().find(|message| message.tag == b't').expect("no ParameterDescription");
+    message.body[2..]
+        .chunks(4)
+        .map(|oid| u32::from_be_bytes(oid.try_into().unwrap()))
+        .collect()
+}
+
+/// Names and type OIDs of the RowDescription message's fields
+fn fields(messages: &[Message]) -> Vec<(String, u32)> {
+    let message = messages.iter().find(|message| message.tag == b'T').expect("no RowDescription");
+    let body = &message.body;
+    let count = i16::from_be_bytes([body[0], body[1]]) as usize;
+    let mut pos = 2;
+    let mut fields = Vec::with_capacity(count);
+    for _ in 0..count {
+        let end = pos + body[pos..].iter().position(|b| *b == 0).unwrap();
+        let name = String::from_utf8_lossy(&body[pos..end]).to_string();
+        // Table OID (4), column number (2), then the type OID
+        let oid = u32::from_be_bytes(body[end + 7..end + 11].try_into().unwrap());
+        fields.push((name, oid));
+        pos = end + 19;
+    }
+    fields
+}
+
+/// Message field of the ErrorResponse
+fn error_message(messages: &[Message]) -> String {
+    let message = messages.iter().find(|message| message.tag == b'E').expect("no ErrorResponse");
+    message.body
+        .split(|b| *b == 0)
+        .find(|field| field.first() == Some(&b'M'))
+        .map(|field| String::from_utf8_lossy(&field[1..]).to_string())
+        .unwrap_or_default()
+}
+
+fn command_tag(messages: &[Message]) -> String {
+    let message = messages.iter().find(|message| message.tag == b'C').expect("no CommandComplete");
+    String::from_utf8_lossy(&message.body[..message.body.len() - 1]).to_string()
+}
+
+fn setup(db: &TestDb) {
+    db.execute_sql("CREATE TABLE users (id INT, name STRING, age INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO users VALUES (1, 'alice', 30), (2, 'bob', 25), (3, 'carol', 35), (4, 'dave', 40);")
+        .expect("INSERT failed");
+}
+
+#[test]
+#[serial]
+fn test_named_statement() {
+    let db = TestDb::new();
+    setup(&db);
+    let mut client = Client::connect();
+
+    client.parse("by_age", "SELECT id, name FROM users WHERE age > $1 ORDER BY id", &[]);
+    client.describe(b'S', "by_age");
+    let messages = client.sync();
+    assert_eq!(tags(&messages), "1tT");
+    assert_eq!(parameter_types(&messages), vec![INT8]);
+    assert_eq!(fields(&messages), vec![("id".to_string(), INT8), ("name".to_string(), VARCHAR)]);
+
+    // The statement is bound again with each value
+    client.bind("", "by_age", &[], &[Some(b"30")]);
+    client.execute("", 0);
+    let messages = client.sync();
+    assert_eq!(tags(&messages), "2DDC");
+    assert_eq!(data_rows(&messages), vec!["3|carol", "4|dave"]);
+    assert_eq!(command_tag(&messages), "SELECT 2");
+
+    client.bind("", "by_age", &[], &[Some(b"20")]);
+    client.execute("", 0);
+    assert_eq!(data_rows(&client.sync()), vec!["1|alice", "2|bob", "3|carol", "4|dave"]);
+
+    // Closed statements can no longer be bound
+    client.close(b'S', "by_age");
+    assert_eq!(tags(&client.sync()), "3");
+    client.bind("", "by_age", &[], &[Some(b"30")]);
+    let messages = client.sync();
+    assert!(error_message(&messages).contains("by_age"), "unexpected error: {}", error_message(&messages));
+}
+
+#[test]
+#[serial]
+fn test_unnamed_statement_and_portal() {
+    let db = TestDb::new();
+    setup(&db);
+    let mut client = Client::connect();
+
+    // Declared types win over inferred ones; binary parameters follow them
+    client.parse("", "SELECT name, age FROM users WHERE id = $1", &[INT4]);
+    client.describe(b'S', "");
+    client.bind("", "", &[1], &[Some(&2i32.to_be_bytes())]);
+    client.describe(b'P', "");
+    client.execute("", 0);
+    let messages = client.sync();
+    assert_eq!(tags(&messages), "1tT2TDC");
+    assert_eq!(parameter_types(&messages), vec![INT4]);
+    assert_eq!(fields(&messages), vec![("name".to_string(), VARCHAR), ("age".to_string(), INT8)]);
+    assert_eq!(data_rows(&messages), vec!["bob|25"]);
+
+    // Inferred from the compared column and from the aliased table
+    client.parse("", "SELECT u.id FROM users u WHERE u.name = $1 OR u.age = $2", &[]);
+    client.describe(b'S', "");
+    client.bind("", "", &[], &[Some(b"carol"), Some(b"40")]);
+    client.execute("", 0);
+    let messages = client.sync();
+    assert_eq!(parameter_types(&messages), vec![VARCHAR, INT8]);
+    assert_eq!(data_rows(&messages), vec!["3", "4"]);
+
+    // Parameters in subqueries and LIMIT
+    client.parse(
+        "",
+        "SELECT name, age FROM users WHERE age > (SELECT min(age) FROM users WHERE id <> $1) ORDER BY id LIMIT $2",
+        &[],
+    );
+    client.describe(b'S', "");
+    client.bind("", "", &[], &[Some(b"2"), Some(b"1")]);
+    client.execute("", 0);
+    let messages = client.sync();
+    assert_eq!(parameter_types(&messages), vec![INT8, INT8]);
+    assert_eq!(data_rows(&messages), vec!["carol|35"]);
+
+    // A NULL parameter matches nothing
+    client.parse("", "SELECT id FROM users WHERE age = $1", &[]);
+    client.bind("", "", &[], &[None]);
+    client.execute("", 0);
+    let messages = client.sync();
+    assert!(data_rows(&messages).is_empty());
+    assert!(!tags(&messages).contains('E'), "unexpected messages: {}", tags(&messages));
+}
+
+#[test]
+#[serial]
+fn test_statements_without_rows() {
+    let db = TestDb::new();
+    setup(&db);
+    let mut client = Client::connect();
+
+    client.parse("insert", "INSERT INTO users VALUES ($1, $2, $3)", &[]);
+    client.describe(b'S', "insert");
+    let messages = client.sync();
+    assert_eq!(tags(&messages), "1tn");
+    assert_eq!(parameter_types(&messages), vec![INT8, VARCHAR, INT8]);
+
+    for (id, name, age) in [("5", "erin", "28"), ("6", "frank", "33")] {
+        client.bind("", "insert", &[], &[Some(id.as_bytes()), Some(name.as_bytes()), Some(age.as_bytes())]);
+        client.execute("", 0);
+        let messages = client.sync();
+        assert_eq!(tags(&messages), "2C");
+        assert_eq!(command_tag(&messages), "INSERT 0 1");
+    }
+
+    client.parse("", "UPDATE users SET age = $1 WHERE name = $2", &[]);
+    client.describe(b'S', "");
+    client.bind("", "", &[], &[Some(b"29"), Some(b"erin")]);
+    client.execute("", 0);
+    let messages = client.sync();
+    assert_eq!(parameter_types(&messages), vec![INT8, VARCHAR]);
+    assert_eq!(command_tag(&messages), "UPDATE 1");
+
+    client.parse("", "DELETE FROM users WHERE age < $1", &[]);
+    client.bind("", "", &[], &[Some(b"28")]);
+    client.execute("", 0);
+    assert_eq!(command_tag(&client.sync()), "DELETE 1");
+
+    // A query returning no rows still completes as a SELECT
+    client.parse("", "SELECT name FROM users WHERE age > $1", &[]);
+    client.bind("", "", &[], &[Some(b"100")]);
+    client.describe(b'P', "");
+    client.execute("", 0);
+    let messages = client.sync();
+    assert_eq!(tags(&messages), "12TC");
+    assert_eq!(fields(&messages), vec![("name".to_string(), VARCHAR)]);
+    assert_eq!(command_tag(&messages), "SELECT 0");
+
+    let result = db.execute_sql("SELECT id, name, age FROM users WHERE id > 4 ORDER BY id;").expect("SELECT failed");
+    assert!(result.contains("erin  |  29"), "unexpected result: {}", result);
+    assert!(result.contains("frank |  33"), "unexpected result: {}", result);
+    assert!(!db.execute_sql("SELECT name FROM users;").unwrap().contains("bob"));
+}
+
+#[test]
+#[serial]
+fn test_portal_suspension() {
+    let db = TestDb::new();
+    setup(&db);
+    let mut client = Client::connect();
+
+    client.parse("", "SELECT id FROM users ORDER BY id", &[]);
+    client.bind("cursor", "", &[], &[]);
+    client.execute("cursor", 3);
+    client.execute("cursor", 3);
+    let messages = client.sync();
+    assert_eq!(tags(&messages), "12DDDsDC");
+    assert_eq!(data_rows(&messages), vec!["1", "2", "3", "4"]);
+}
+
+#[test]
+#[serial]
+fn test_errors() {
+    let db = TestDb::new();
+    setup(&db);
+    let mut client = Client::connect();
+
+    client.parse("", "SELEC id FROM users", &[]);
+    let messages = client.sync();
+    assert!(error_message(&messages).contains("Parse error"), "unexpected error: {}", error_message(&messages));
+
+    client.parse("", "SELECT 1; SELECT 2", &[]);
+    let messages = client.sync();
+    assert!(error_message(&messages).contains("multiple commands"), "unexpected error: {}", error_message(&messages));
+
+    client.parse("two", "SELECT id FROM users WHERE id = $1 AND age = $2", &[]);
+    assert_eq!(tags(&client.sync()), "1");
+    client.bind("", "two", &[], &[Some(b"1")]);
+    client.execute("", 0);
+    let messages = client.sync();
+    assert!(
+        error_message(&messages).contains("bind message supplies 1 parameters, but prepared statement \"two\" requires 2"),
+        "unexpected error: {}",
+        error_message(&messages)
+    );
+
+    client.bind("", "two", &[], &[Some(b"one"), Some(b"30")]);
+    client.execute("", 0);
+    let messages = client.sync();
+    assert!(
+        error_message(&messages).contains("invalid input syntax for type integer: \"one\""),
+        "unexpected error: {}",
+        error_message(&messages)
+    );
+
+    // The connection is still usable
+    client.bind("", "two", &[], &[Some(b"1"), Some(b"30")]);
+    client.execute("", 0);
+    assert_eq!(data_rows(&client.sync()), vec!["1"]);
+}
+
+/// Count from `SHOW plan_cache`
+fn plan_count(db: &TestDb, name: &str) -> u64 {
+    let result = db.execute_sql("SHOW plan_cache;").expect("SHOW failed");
+    result
+        .lines()
+        .map(|line| line.split('|').map(str::trim).collect::<Vec<_>>())
+        .find(|cells| cells.first() == Some(&name))
+        .and_then(|cells| cells.get(1).and_then(|value| value.parse().ok()))
+        .unwrap_or_else(|| panic!("no statistic {} in {}", name, result))
+}
+
+#[test]
+#[serial]
+fn test_generic_and_custom_plans() {
+    let db = TestDb::new();
+    setup(&db);
+    let mut client = Client::connect();
+
+    client.parse("by_age", "SELECT name FROM users WHERE age > $1 ORDER BY id", &[]);
+    assert_eq!(tags(&client.sync()), "1");
+
+    // Custom plans first, then the generic plan, which costs no more
+    let (generic, custom) = (plan_count(&db, "generic_plans"), plan_count(&db, "custom_plans"));
+    for age in 20..28 {
+        client.bind("", "by_age", &[], &[Some(age.to_string().as_bytes())]);
+        client.execute("", 0);
+        let expected = if age < 25 { 4 } else { 3 };
+        assert_eq!(data_rows(&client.sync()).len(), expected, "age {}", age);
+    }
+    assert_eq!(plan_count(&db, "custom_plans"), custom + 5);
+    assert_eq!(plan_count(&db, "generic_plans"), generic + 3);
+
+    // The generic plan is replanned after DDL, still with the bound values
+    let invalidations = plan_count(&db, "invalidations");
+    db.execute_sql("CREATE INDEX idx_age ON users (age);").expect("CREATE INDEX failed");
+    client.bind("", "by_age", &[], &[Some(b"35")]);
+    client.execute("", 0);
+    assert_eq!(data_rows(&client.sync()), vec!["dave"]);
+    assert_eq!(plan_count(&db, "invalidations"), invalidations + 1);
+
+    assert_eq!(command_tag(&client.query("SET plan_cache_mode = force_custom_plan;")), "SET");
+    let custom = plan_count(&db, "custom_plans");
+    client.bind("", "by_age", &[], &[Some(b"30")]);
+    client.execute("", 0);
+    assert_eq!(data_rows(&client.sync()), vec!["carol", "dave"]);
+    assert_eq!(plan_count(&db, "custom_plans"), custom + 1);
+
+    // A statement that cannot be planned without its values always gets custom plans
+    assert_eq!(command_tag(&client.query("SET plan_cache_mode = force_generic_plan;")), "SET");
+    client.parse("first", "SELECT id FROM users ORDER BY id LIMIT $1", &[]);
+    let custom = plan_count(&db, "custom_plans");
+    for limit in [1, 3] {
+        client.bind("", "first", &[], &[Some(limit.to_string().as_bytes())]);
+        client.execute("", 0);
+        assert_eq!(data_rows(&client.sync()).len(), limit);
+    }
+    assert_eq!(plan_count(&db, "custom_plans"), custom + 2);
+
+    // Generic plans bind parameters inside subqueries too
+    let generic = plan_count(&db, "generic_plans");
+    client.parse("older", "SELECT name FROM users WHERE age > (SELECT age FROM users WHERE id = $1) ORDER BY id", &[]);
+    client.bind("", "older", &[], &[Some(b"3")]);
+    client.execute("", 0);
+    assert_eq!(data_rows(&client.sync()), vec!["dave"]);
+    assert_eq!(plan_count(&db, "generic_plans"), generic + 1);
+}
+
+#[test]
+#[serial]
+fn test_row_description() {
+    let db = TestDb::new();
+    setup(&db);
+    db.execute_sql("CREATE TABLE scores (id INT, user_id INT, score FLOAT, passed BOOL, PRIMARY KEY (id));").expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO scores VALUES (1, 1, 7.5, true), (2, 1, 9.0, true), (3, 2, 4.0, false);")
+        .expect("INSERT failed");
+    let mut client = Client::connect();
+
+    let cases: Vec<(&str, Vec<(&str, u32)>)> = vec![
+        // A projection describes only the columns it picks
+        ("SELECT name, id FROM users WHERE id = 1", vec![("name", VARCHAR), ("id", INT8)]),
+        ("SELECT id AS key, age * 2, age > 30 AS senior, 1.5, NULL FROM users WHERE id = 1",
+            vec![("key", INT8), ("?column?", INT8), ("senior", BOOL), ("?column?", FLOAT8), ("?column?", TEXT)]),
+        ("SELECT CASE WHEN age > 30 THEN 1 ELSE 0 END, CASE id WHEN 1 THEN true END AS first FROM users WHERE id = 1",
+            vec![("case", INT8), ("first", BOOL)]),
+        ("SELECT u.name, s.score FROM users u JOIN scores s ON u.id = s.user_id WHERE s.score > 8",
+            vec![("name", VARCHAR), ("score", FLOAT8)]),
+        ("SELECT user_id, count(*), avg(score), sum(score), bool_and(passed) FROM scores GROUP BY user_id ORDER BY user_id",
+            vec![("user_id", INT8), ("count", INT8), ("avg", FLOAT8), ("sum", FLOAT8), ("bool_and", BOOL)]),
+        ("SELECT t.total FROM (SELECT sum(score) AS total FROM scores) t", vec![("total", FLOAT8)]),
+        ("WITH ages (years) AS (SELECT age FROM users) SELECT max(years) FROM ages", vec![("max", INT8)]),
+        ("SELECT name, (SELECT max(score) FROM scores WHERE user_id = users.id) AS best FROM users WHERE id = 2",
+            vec![("name", VARCHAR), ("best", FLOAT8)]),
+        ("SELECT id, row_number() OVER (ORDER BY age) FROM users WHERE id = 3", vec![("id", INT8), ("row_number", INT8)]),
+        ("SELECT age FROM users WHERE id = 4 UNION SELECT score FROM scores WHERE user_id = 2",
+            vec![("age", FLOAT8)]),
+    ];
+    for (sql, expected) in cases {
+        let expected = expected.into_iter().map(|(name, oid)| (name.to_string(), oid)).collect::<Vec<_>>();
+
+        // Described before running, as a simple query returns it
+        client.parse("", sql, &[]);
+        client.describe(b'S', "");
+        let described = client.sync();
+        assert_eq!(fields(&described), expected, "describing {}", sql);
+        let messages = client.query(sql);
+        assert_eq!(fields(&messages), expected, "running {}", sql);
+        assert!(!data_rows(&messages).is_empty(), "no rows for {}", sql);
+    }
+
+    // Values are sent as the described types
+    let messages = client.query("SELECT age FROM users WHERE id = 4 UNION SELECT score FROM scores WHERE user_id = 2 ORDER BY age");
+    assert_eq!(data_rows(&messages), vec!["4.0", "40.0"]);
+}
+
+#[test]
+#[serial]
+fn test_binary_results() {
+    let db = TestDb::new();
+    setup(&db);
+    let mut client = Client::connect();
+
+    let sql = "SELECT id, name, age * 100000000, age / 10.0, age > 30, NULL FROM users WHERE id = $1";
+    client.parse("", sql, &[]);
+    client.bind_results("", "", &[], &[Some(b"3")], &[1]);
+    client.describe(b'P', "");
+    client.execute("", 0);
+    let messages = client.sync();
+    let types = fields(&messages).into_iter().map(|(_, oid)| oid).collect::<Vec<_>>();
+    assert_eq!(types, vec![INT8, VARCHAR, INT8, FLOAT8, BOOL, TEXT]);
+    assert_eq!(cells(&messages), vec![vec![
+        Some(3i64.to_be_bytes().to_vec()),
+        Some(b"carol".to_vec()),
+        // Integers are 64-bit, so this does not wrap
+        Some(3_500_000_000i64.to_be_bytes().to_vec()),
+        Some(3.5f64.to_be_bytes().to_vec()),
+        Some(vec![1]),
+        None,
+    ]]);
+
+    // Formats may be chosen per column
+    client.bind_results("", "", &[], &[Some(b"3")], &[0, 0, 1, 0, 0, 1]);
+    client.execute("", 0);
+    let row = cells(&client.sync()).remove(0);
+    assert_eq!(row[0], Some(b"3".to_vec()));
+    assert_eq!(row[2], Some(3_500_000_000i64.to_be_bytes().to_vec()));
+    assert_eq!(row[4], Some(b"t".to_vec()));
+
+    // Text results carry the full value as well
+    client.bind("", "", &[], &[Some(b"3")]);
+    client.execute("", 0);
+    assert_eq!(data_rows(&client.sync()), vec!["3|carol|3500000000|3.5|t|NULL"]);
+
+    // One format per column, or one for all
+    client.bind_results("", "", &[], &[Some(b"3")], &[1, 0]);
+    client.execute("", 0);
+    let messages = client.sync();
+    assert!(error_message(&messages).contains("2 result formats but query has 6 columns"),
+        "unexpected error: {}", error_message(&messages));
+}
+
+#[test]
+#[serial]
+fn test_datetime_binary() {
+    let _db = TestDb::new();
+    let mut client = Client::connect();
+
+    // Dates count days and timestamps microseconds from 2000-01-01
+    let day = 8_800i32;
+    let instant = 760_000_000_000_000i64;
+    client.parse("", "SELECT $1 + 1, $2, INTERVAL '1 mon 2 days 00:00:03'", &[DATE, TIMESTAMPTZ]);
+    client.bind_results("", "", &[1, 1], &[Some(&day.to_be_bytes()), Some(&instant.to_be_bytes())], &[1]);
+    client.describe(b'P', "");
+    client.execute("", 0);
+    let messages = client.sync();
+    let types = fields(&messages).into_iter().map(|(_, oid)| oid).collect::<Vec<_>>();
+    assert_eq!(types, vec![DATE, TIMESTAMPTZ, INTERVAL]);
+    // An interval is its microseconds, days and months
+    let interval = [3_000_000i64.to_be_bytes().to_vec(), 2i32.to_be_bytes().to_vec(), 1i32.to_be_bytes().to_vec()].concat();
+    assert_eq!(cells(&messages), vec![vec![
+        Some((day + 1).to_be_bytes().to_vec()),
+        Some(instant.to_be_bytes().to_vec()),
+        Some(interval),
+    ]]);
+
+    client.bind("", "", &[], &[Some(b"2024-02-03"), Some(b"2024-02-03 04:05:06.5-01")]);
+    client.execute("", 0);
+    assert_eq!(
+        data_rows(&client.sync()),
+        vec!["2024-02-04|2024-02-03 05:05:06.5+00|1 mon 2 days 00:00:03"]
+    );
+}
+
+/// A numeric in binary format: its weight, sign, display scale and
+/// base-10000 digits
+fn numeric_bytes(weight: i16, negative: bool, scale: u16, digits: &[u16]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend((digits.len() as i16).to_be_bytes());
+    bytes.extend(weight.to_be_bytes());
+    bytes.extend(if negative { 0x4000u16 } else { 0 }.to_be_bytes());
+    bytes.extend(scale.to_be_bytes());
+    for digit in digits {
+        bytes.extend(digit.to_be_bytes());
+    }
+    bytes
+}
+
+#[test]
+#[serial]
+fn test_numeric_binary() {
+    let _db = TestDb::new();
+    let mut client = Client::connect();
+
+    // -12345.678 is 1|2345|6780 with the first digit in the 10000s
+    let amount = numeric_bytes(1, true, 3, &[1, 2345, 6780]);
+    client.parse("", "SELECT $1 + 1, $1 * 2, 0.5::numeric(3, 2)", &[NUMERIC]);
+    client.bind_results("", "", &[1], &[Some(&amount)], &[1]);
+    client.describe(b'P', "");
+    client.execute("", 0);
+    let messages = client.sync();
+    let types = fields(&messages).into_iter().map(|(_, oid)| oid).collect::<Vec<_>>();
+    assert_eq!(types, vec![NUMERIC, NUMERIC, NUMERIC]);
+    assert_eq!(cells(&messages), vec![vec![
+        Some(numeric_bytes(1, true, 3, &[1, 2344, 6780])),
+        Some(numeric_bytes(1, true, 3, &[2, 4691, 3560])),
+        Some(numeric_bytes(-1, false, 2, &[5000])),
+    ]]);
+
+    client.bind("", "", &[], &[Some(b"0.10")]);
+    client.execute("", 0);
+    assert_eq!(data_rows(&client.sync()), vec!["1.10|0.20|0.50"]);
+}
+
+#[test]
+#[serial]
+fn test_uuid_and_bytea_binary() {
+    let _db = TestDb::new();
+    let mut client = Client::connect();
+
+    // Binary UUIDs are their 16 bytes, byte strings their raw bytes
+    let id = [0xa0, 0xee, 0xbc, 0x99, 0x9c, 0x0b, 0x4e, 0xf8, 0xbb, 0x6d, 0x6b, 0xb9, 0xbd, 0x38, 0x0a, 0x11];
+    client.parse("", "SELECT $1, $2 || '\\x00'::bytea", &[UUID, BYTEA]);
+    client.bind_results("", "", &[1, 1], &[Some(&id), Some(&[0x41, 0xff])], &[1]);
+    client.describe(b'P', "");
+    client.execute("", 0);
+    let messages = client.sync();
+    let types = fields(&messages).into_iter().map(|(_, oid)| oid).collect::<Vec<_>>();
+    assert_eq!(types, vec![UUID, BYTEA]);
+    assert_eq!(cells(&messages), vec![vec![Some(id.to_vec()), Some(vec![0x41, 0xff, 0x00])]]);
+
+    client.bind("", "", &[], &[Some(b"{A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11}"), Some(b"\\x41ff")]);
+    client.execute("", 0);
+    assert_eq!(data_rows(&client.sync()), vec!["a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11|\\x41ff00"]);
+}
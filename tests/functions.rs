@@ -0,0 +1,20 @@
+mod common;
+
+use common::TestDb;
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn test_undefined_function() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE calls (id INT, name STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO calls VALUES (1, 'a');").expect("INSERT failed");
+
+    // Calls are resolved against argument types while planning
+    let err = db.execute_sql("SELECT nosuch(id, name) FROM calls;").unwrap_err();
+    assert!(err.contains("function nosuch(bigint, text) does not exist"), "unexpected error: {}", err);
+    let err = db.execute_sql("SELECT id FROM calls WHERE id <-> 1 > 0;").unwrap_err();
+    assert!(err.contains("operator does not exist: bigint <-> bigint"), "unexpected error: {}", err);
+}
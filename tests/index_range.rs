@@ -0,0 +1,136 @@
+mod common;
+
+use common::{data_rows, explain, ids, insert_rows, TestDb};
+use serial_test::serial;
+
+/// `accounts` with ids from -1000 to 2000, enough rows to span many index pages
+fn setup(db: &TestDb) {
+    db.execute_sql("CREATE TABLE accounts (id INT, owner STRING, balance INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    insert_rows(db, "accounts", (-1000..=2000i64)
+        .map(|id| format!("({}, 'o{}', {})", int(id), id.rem_euclid(10), int(id * 3))));
+    db.execute_sql("ANALYZE accounts;").expect("ANALYZE failed");
+}
+
+/// An integer literal; negative ones are written as subtractions
+fn int(n: i64) -> String {
+    if n < 0 { format!("0 - {}", -n) } else { n.to_string() }
+}
+
+#[test]
+#[serial]
+fn test_range_predicates_use_index() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let query = "SELECT id FROM accounts WHERE id > 1500 AND id <= 1505;";
+    assert_eq!(ids(&db, query), vec![1501, 1502, 1503, 1504, 1505]);
+    let plan = explain(&db, query);
+    assert!(plan.contains("Index Scan using accounts_pkey on accounts"), "unexpected plan: {}", plan);
+    assert!(plan.contains("Index Cond: ((id > 1500) AND (id <= 1505))"), "unexpected plan: {}", plan);
+    assert!(!plan.contains("Seq Scan"), "unexpected plan: {}", plan);
+
+    // BETWEEN, constants on the left and bounds that are not integers
+    let query = "SELECT COUNT(*), MIN(balance) FROM accounts WHERE id BETWEEN 10 AND 19;";
+    assert_eq!(data_rows(&db.execute_sql(query).expect("SELECT failed")), vec!["10|30"]);
+    assert!(explain(&db, query).contains("Index Cond: ((id >= 10) AND (id <= 19))"));
+    assert_eq!(ids(&db, "SELECT id FROM accounts WHERE 20 >= id AND id > 17.5;"), vec![18, 19, 20]);
+    assert_eq!(ids(&db, "SELECT id FROM accounts WHERE id BETWEEN 3 AND 1;"), Vec::<i64>::new());
+    assert_eq!(ids(&db, "SELECT id FROM accounts WHERE id >= 1999 AND id < NULL;"), Vec::<i64>::new());
+
+    // Other predicates are still checked on the rows the range returns
+    let query = "SELECT id FROM accounts WHERE id >= 100 AND id < 140 AND owner = 'o3' AND id > 0;";
+    assert_eq!(ids(&db, query), vec![103, 113, 123, 133]);
+
+    // Ranges across zero come back in order
+    let query = "SELECT id FROM accounts WHERE id >= 0 - 3 AND id <= 2 ORDER BY id;";
+    assert_eq!(ids(&db, query), vec![-3, -2, -1, 0, 1, 2]);
+    assert!(!explain(&db, query).contains("Sort"));
+}
+
+#[test]
+#[serial]
+fn test_index_order_skips_sort() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let query = "SELECT id FROM accounts ORDER BY id DESC LIMIT 3;";
+    assert_eq!(ids(&db, query), vec![2000, 1999, 1998]);
+    let plan = explain(&db, query);
+    assert!(plan.contains("Index Scan Backward using accounts_pkey on accounts"), "unexpected plan: {}", plan);
+    assert!(!plan.contains("Sort"), "unexpected plan: {}", plan);
+
+    let query = "SELECT id, owner FROM accounts WHERE owner = 'o7' ORDER BY id LIMIT 2 OFFSET 1;";
+    assert_eq!(data_rows(&db.execute_sql(query).expect("SELECT failed")), vec!["-983|o7", "-973|o7"]);
+    assert!(!explain(&db, query).contains("Sort"));
+
+    let query = "SELECT id FROM accounts WHERE id BETWEEN 0 - 2 AND 2 ORDER BY id DESC;";
+    assert_eq!(ids(&db, query), vec![2, 1, 0, -1, -2]);
+    let plan = explain(&db, query);
+    assert!(plan.contains("Index Scan Backward"), "unexpected plan: {}", plan);
+    assert!(!plan.contains("Sort"), "unexpected plan: {}", plan);
+
+    // Ordering by another column still sorts
+    let plan = explain(&db, "SELECT id FROM accounts WHERE id < 5 ORDER BY balance DESC LIMIT 2;");
+    assert!(plan.contains("Sort"), "unexpected plan: {}", plan);
+    assert_eq!(ids(&db, "SELECT id FROM accounts WHERE id < 5 ORDER BY balance DESC LIMIT 2;"), vec![4, 3]);
+}
+
+#[test]
+#[serial]
+fn test_range_scan_sees_changes() {
+    let mut db = TestDb::new();
+    setup(&db);
+
+    db.execute_sql("DELETE FROM accounts WHERE id = 1502;").expect("DELETE failed");
+    db.execute_sql("UPDATE accounts SET balance = 0 WHERE id = 1503;").expect("UPDATE failed");
+    db.execute_sql("UPDATE accounts SET id = 5000 WHERE id = 1504;").expect("UPDATE failed");
+    let result = db.execute_sql("SELECT id, balance FROM accounts WHERE id > 1500 AND id <= 1505;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1501|4503", "1503|0", "1505|4515"]);
+    assert_eq!(ids(&db, "SELECT id FROM accounts WHERE id >= 1999 ORDER BY id;"), vec![1999, 2000, 5000]);
+
+    // Keys far past the first index page are still unique
+    let result = db.execute_sql("INSERT INTO accounts VALUES (1999, 'dup', 0);");
+    assert!(result.is_err(), "duplicate key should fail: {:?}", result);
+
+    db.restart().expect("restart failed");
+    assert_eq!(ids(&db, "SELECT id FROM accounts WHERE id BETWEEN 1500 AND 1503;"), vec![1500, 1501, 1503]);
+    assert_eq!(ids(&db, "SELECT id FROM accounts ORDER BY id LIMIT 2;"), vec![-1000, -999]);
+}
+
+#[test]
+#[serial]
+fn test_secondary_index_ranges() {
+    let db = TestDb::new();
+    setup(&db);
+    // Balances repeat every 500 ids, and a few are NULL
+    db.execute_sql("UPDATE accounts SET balance = id % 500 WHERE id >= 0;").expect("UPDATE failed");
+    db.execute_sql("UPDATE accounts SET balance = NULL WHERE id < 0 - 990;").expect("UPDATE failed");
+    db.execute_sql("CREATE INDEX accounts_balance ON accounts (balance);").expect("CREATE INDEX failed");
+    db.execute_sql("ANALYZE accounts;").expect("ANALYZE failed");
+
+    let query = "SELECT id FROM accounts WHERE balance > 497 ORDER BY id;";
+    assert_eq!(ids(&db, query), vec![498, 499, 998, 999, 1498, 1499, 1998, 1999]);
+    let plan = explain(&db, query);
+    assert!(plan.contains("Index Scan using accounts_balance on accounts"), "unexpected plan: {}", plan);
+    assert!(plan.contains("Index Cond: (balance > 497)"), "unexpected plan: {}", plan);
+    assert!(!plan.contains("Filter"), "unexpected plan: {}", plan);
+
+    // A whole index read in order returns NULLs last, or first backward
+    let query = "SELECT id FROM accounts ORDER BY balance, id LIMIT 1;";
+    assert_eq!(ids(&db, query), vec![-990]);
+    let query = "SELECT balance FROM accounts ORDER BY balance LIMIT 1;";
+    assert_eq!(data_rows(&db.execute_sql(query).expect("SELECT failed")), vec!["-2970"]);
+    let plan = explain(&db, query);
+    assert!(plan.contains("Index Scan using accounts_balance on accounts"), "unexpected plan: {}", plan);
+    assert!(!plan.contains("Sort"), "unexpected plan: {}", plan);
+    let query = "SELECT id, balance FROM accounts ORDER BY balance DESC LIMIT 11;";
+    let rows = data_rows(&db.execute_sql(query).expect("SELECT failed"));
+    assert_eq!(rows.iter().filter(|row| row.ends_with('|')).count(), 10, "{:?}", rows);
+    assert!(rows[10].ends_with("|499"), "{:?}", rows);
+    assert!(explain(&db, query).contains("Index Scan Backward using accounts_balance"));
+    let result = db.execute_sql("SELECT count(*) FROM (SELECT id FROM accounts ORDER BY balance) AS ordered;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["3001"]);
+}
@@ -0,0 +1,240 @@
+mod common;
+
+use common::{data_rows, TestDb};
+use serial_test::serial;
+
+fn setup_company(db: &TestDb) {
+    db.execute_sql("CREATE TABLE emp (id INT, name STRING, dept_id INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("CREATE TABLE dept (id INT, dname STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql(
+        "INSERT INTO emp VALUES (1, 'ann', 10), (2, 'bob', 20), (3, 'cat', NULL), (4, 'dan', 30), (5, 'eve', 10);",
+    )
+    .expect("INSERT failed");
+    db.execute_sql("INSERT INTO dept VALUES (10, 'eng'), (20, 'ops'), (40, 'hr');")
+        .expect("INSERT failed");
+}
+
+#[test]
+#[serial]
+fn test_inner_join() {
+    let db = TestDb::new();
+    setup_company(&db);
+
+    let result = db
+        .execute_sql("SELECT e.name, d.dname FROM emp e JOIN dept d ON e.dept_id = d.id ORDER BY e.name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann|eng", "bob|ops", "eve|eng"], "{}", result);
+
+    // Comma join with the condition in WHERE
+    let result = db
+        .execute_sql("SELECT name, dname FROM emp, dept WHERE dept_id = dept.id AND dname = 'eng' ORDER BY name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann|eng", "eve|eng"], "{}", result);
+
+    // SELECT * keeps both id columns, in FROM order
+    let result = db
+        .execute_sql("SELECT * FROM emp INNER JOIN dept ON emp.dept_id = dept.id WHERE emp.id = 2;")
+        .expect("SELECT failed");
+    assert!(result.contains("id | name | dept_id | id | dname"), "wrong columns: {}", result);
+    assert_eq!(data_rows(&result), ["2|bob|20|20|ops"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_outer_joins() {
+    let db = TestDb::new();
+    setup_company(&db);
+
+    let result = db
+        .execute_sql("SELECT e.name, d.dname FROM emp e LEFT JOIN dept d ON e.dept_id = d.id ORDER BY e.name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann|eng", "bob|ops", "cat|", "dan|", "eve|eng"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT d.dname, e.name FROM emp e RIGHT JOIN dept d ON e.dept_id = d.id ORDER BY d.dname, e.name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["eng|ann", "eng|eve", "hr|", "ops|bob"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT e.id, d.id FROM emp e FULL OUTER JOIN dept d ON e.dept_id = d.id ORDER BY e.id, d.id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|10", "2|20", "3|", "4|", "5|10", "|40"], "{}", result);
+
+    // ON conditions decide matches, WHERE filters the joined rows
+    let result = db
+        .execute_sql("SELECT e.name, d.dname FROM emp e LEFT JOIN dept d ON e.dept_id = d.id AND d.dname = 'ops' ORDER BY e.name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann|", "bob|ops", "cat|", "dan|", "eve|"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT e.name FROM emp e LEFT JOIN dept d ON e.dept_id = d.id WHERE d.dname <> 'ops' ORDER BY e.name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann", "eve"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_cross_join() {
+    let db = TestDb::new();
+    setup_company(&db);
+
+    let result = db
+        .execute_sql("SELECT COUNT(*) FROM emp CROSS JOIN dept;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["15"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT COUNT(*) FROM emp, dept;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["15"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_using_and_natural() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE a (id INT, x STRING, PRIMARY KEY (id));").expect("CREATE TABLE failed");
+    db.execute_sql("CREATE TABLE b (id INT, y STRING, PRIMARY KEY (id));").expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO a VALUES (1, 'a1'), (2, 'a2'), (3, 'a3');").expect("INSERT failed");
+    db.execute_sql("INSERT INTO b VALUES (2, 'b2'), (3, 'b3'), (4, 'b4');").expect("INSERT failed");
+
+    // USING columns are merged into one leading column
+    let result = db
+        .execute_sql("SELECT * FROM a JOIN b USING (id) ORDER BY id;")
+        .expect("SELECT failed");
+    assert!(result.contains("id | x  | y"), "wrong columns: {}", result);
+    assert_eq!(data_rows(&result), ["2|a2|b2", "3|a3|b3"], "{}", result);
+
+    // In a FULL join the merged column takes whichever side is present
+    let result = db
+        .execute_sql("SELECT id, a.id, b.id FROM a FULL JOIN b USING (id) ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|1|", "2|2|2", "3|3|3", "4||4"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT * FROM a NATURAL JOIN b ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["2|a2|b2", "3|a3|b3"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_join_name_resolution() {
+    let db = TestDb::new();
+    setup_company(&db);
+
+    let result = db.execute_sql("SELECT id FROM emp JOIN dept ON emp.dept_id = dept.id;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("ambiguous")),
+        "unqualified shared column should be ambiguous: {:?}",
+        result
+    );
+
+    let result = db.execute_sql("SELECT * FROM emp JOIN emp ON true;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("specified more than once")),
+        "duplicate table name should fail: {:?}",
+        result
+    );
+
+    let result = db.execute_sql("SELECT e.name FROM emp e JOIN dept d ON x.id = d.id;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("missing FROM-clause entry")),
+        "unknown qualifier should fail: {:?}",
+        result
+    );
+
+    // Self join through aliases
+    let result = db
+        .execute_sql("SELECT l.name, r.name FROM emp l JOIN emp r ON l.dept_id = r.dept_id AND l.id < r.id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann|eve"], "{}", result);
+
+    // Qualified wildcard
+    let result = db
+        .execute_sql("SELECT d.* FROM emp e JOIN dept d ON e.dept_id = d.id WHERE e.id = 2;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["20|ops"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_join_methods_agree() {
+    let db = TestDb::new();
+    setup_company(&db);
+    db.execute_sql("CREATE INDEX idx_emp_dept ON emp (dept_id);").expect("CREATE INDEX failed");
+
+    let queries = [
+        "SELECT e.name, d.dname FROM emp e JOIN dept d ON e.dept_id = d.id ORDER BY e.name;",
+        "SELECT d.dname, e.name FROM dept d LEFT JOIN emp e ON e.dept_id = d.id ORDER BY d.dname, e.name;",
+        "SELECT e.name, d.dname FROM emp e FULL JOIN dept d ON e.dept_id = d.id ORDER BY e.name, d.dname;",
+    ];
+    let expected = queries.map(|query| data_rows(&db.execute_sql(query).expect("SELECT failed")));
+
+    // Disabling methods forces hash, merge, index nested loop and nested loop joins
+    let settings = [
+        "SET enable_hashjoin = on; SET enable_mergejoin = off; SET enable_nestloop = off;",
+        "SET enable_hashjoin = off; SET enable_mergejoin = on; SET enable_nestloop = off;",
+        "SET enable_hashjoin = off; SET enable_mergejoin = off; SET enable_nestloop = on;",
+        "SET enable_hashjoin = off; SET enable_mergejoin = off; SET enable_indexscan = off;",
+    ];
+    for setting in settings {
+        let mut statements: Vec<&str> = setting.split_inclusive(';').map(str::trim).collect();
+        for (query, expected) in queries.iter().zip(&expected) {
+            statements.push(query);
+            let result = db.execute_session(&statements).expect("SELECT failed");
+            statements.pop();
+            assert_eq!(&data_rows(&result), expected, "{} with {}", query, setting);
+        }
+    }
+}
+
+#[test]
+#[serial]
+fn test_settings_are_per_session() {
+    let db = TestDb::new();
+    setup_company(&db);
+
+    let query = "EXPLAIN SELECT e.name, d.dname FROM emp e JOIN dept d ON e.dept_id = d.id;";
+    let plan = db
+        .execute_session(&["SET enable_hashjoin = off;", "SET enable_nestloop = off;", query, "SHOW enable_hashjoin;"])
+        .expect("EXPLAIN failed");
+    assert!(plan.contains("Merge Join"), "unexpected plan: {}", plan);
+    assert!(plan.contains("off"), "{}", plan);
+
+    // Another connection still plans with the defaults
+    let plan = db.execute_session(&[query]).expect("EXPLAIN failed");
+    assert!(plan.contains("Hash Join"), "unexpected plan: {}", plan);
+    let result = db.execute_sql("SHOW enable_hashjoin;").expect("SHOW failed");
+    assert_eq!(data_rows(&result), ["on"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_multi_way_join() {
+    let db = TestDb::new();
+    setup_company(&db);
+    db.execute_sql("CREATE TABLE project (pid INT, dept_id INT, title STRING, PRIMARY KEY (pid));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO project VALUES (1, 10, 'db'), (2, 10, 'ui'), (3, 20, 'infra');")
+        .expect("INSERT failed");
+
+    let result = db
+        .execute_sql(
+            "SELECT e.name, d.dname, p.title FROM project p, emp e, dept d \
+             WHERE e.dept_id = d.id AND p.dept_id = d.id AND p.title <> 'ui' ORDER BY e.name, p.title;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann|eng|db", "bob|ops|infra", "eve|eng|db"], "{}", result);
+
+    let result = db
+        .execute_sql(
+            "SELECT d.dname, COUNT(p.pid) FROM dept d LEFT JOIN project p ON p.dept_id = d.id \
+             GROUP BY d.dname ORDER BY d.dname;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["eng|2", "hr|0", "ops|1"], "{}", result);
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use common::{data_rows, error, TestDb};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn test_numeric_input_and_output() {
+    let db = TestDb::new();
+
+    let result = db
+        .execute_sql(
+            "SELECT 1.50::numeric, '-.5'::numeric, '1.2e3'::numeric, CAST(12.345 AS NUMERIC(5, 2)), \
+             '123456789012345678901234567890.123'::numeric, 2.5::float8::numeric;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["1.50|-0.5|1200|12.35|123456789012345678901234567890.123|2.5"]
+    );
+
+    // Numerics convert to integers rounding halves away from zero
+    let result = db
+        .execute_sql("SELECT 2.5::numeric::int, (-2.5)::numeric::int, 1.25::numeric::float8, 7::numeric(4, 1);")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["3|-3|1.25|7.0"]);
+
+    assert_eq!(error("SELECT 'ten'::numeric;"), "22P02: invalid input syntax for type numeric: \"ten\"");
+    assert_eq!(error("SELECT 123.456::numeric(4, 2);"), "22003: numeric field overflow");
+    assert_eq!(error("SELECT 1::numeric(1001);"), "22023: NUMERIC precision 1001 must be between 1 and 1000");
+    assert_eq!(error("SELECT 1::numeric(3, 4);"), "22023: NUMERIC scale 4 must be between 0 and precision 3");
+    assert_eq!(error("SELECT 'NaN'::float8::numeric;"), "22003: cannot convert NaN to numeric");
+}
+
+#[test]
+#[serial]
+fn test_numeric_arithmetic() {
+    let db = TestDb::new();
+
+    // Sums keep the larger scale and products the sum of the scales
+    let result = db
+        .execute_sql(
+            "SELECT 0.1::numeric + 0.2, 5.00::numeric * 1, 2.5::numeric * 1.10, 7.5::numeric % 2, \
+             -(0.005::numeric), 99999999999999999999::numeric + 1;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["0.3|5.00|2.750|1.5|-0.005|100000000000000000000"]
+    );
+
+    // Quotients keep at least 16 significant digits
+    let result = db
+        .execute_sql("SELECT 10::numeric / 3, 1::numeric / 3, 1::numeric / 7000, 2::numeric / 4.00;")
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["3.3333333333333333|0.33333333333333333333|0.00014285714285714286|0.50000000000000000000"]
+    );
+
+    let result = db
+        .execute_sql(
+            "SELECT 0.1::numeric + 0.2 = 0.3, 1.5::numeric = 1.50, 2::numeric > 1.9, \
+             0.1::numeric + 0.1::float8, 3 * '2.50'::numeric;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["t|t|t|0.2|7.50"]);
+
+    let result = db
+        .execute_sql(
+            "SELECT round(2.5::numeric), round(-2.5::numeric), round(1234.5678::numeric, 2), \
+             round(1234.5678::numeric, -2), trunc(-1.99::numeric, 1), ceil(-1.5::numeric), floor(-1.5::numeric), \
+             round(2.5::float8);",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["3|-3|1234.57|1200|-1.9|-1|-2|2.0"]);
+
+    assert_eq!(error("SELECT 1::numeric / 0;"), "22012: division by zero");
+    assert_eq!(error("SELECT 1::numeric % 0.0;"), "22012: division by zero");
+}
+
+#[test]
+#[serial]
+fn test_numeric_columns() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE invoices (id INT, customer TEXT, amount NUMERIC(12, 2), PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql(
+        "INSERT INTO invoices VALUES (1, 'acme', 19.999), (2, 'acme', '0.10'), (3, 'globex', 5), \
+         (4, 'globex', 1234567890.125), (5, 'initech', NULL);",
+    )
+    .expect("INSERT failed");
+
+    // Stored values are rounded to the column's scale
+    let result = db.execute_sql("SELECT id, amount FROM invoices ORDER BY amount, id;").expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["2|0.10", "3|5.00", "1|20.00", "4|1234567890.13", "5|"]
+    );
+
+    assert_eq!(
+        error("INSERT INTO invoices VALUES (6, 'hooli', 10000000000);"),
+        "22003: numeric field overflow"
+    );
+    db.execute_sql("UPDATE invoices SET amount = amount * 1.5 WHERE id = 3;").expect("UPDATE failed");
+
+    let result = db
+        .execute_sql("SELECT id FROM invoices WHERE amount = 7.5 OR amount = '0.1' ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["2", "3"]);
+    let result = db
+        .execute_sql("SELECT id, amount * 1.10 FROM invoices WHERE amount BETWEEN 1 AND 100 ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["1|22.0000", "3|8.2500"]);
+
+    // Aggregates are exact
+    let result = db
+        .execute_sql("SELECT sum(amount), avg(amount), min(amount), max(amount) FROM invoices WHERE id < 4;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["27.60|9.2000000000000000|0.10|20.00"]);
+    let result = db
+        .execute_sql("SELECT customer, sum(amount) FROM invoices GROUP BY customer ORDER BY customer;")
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["acme|20.10", "globex|1234567897.63", "initech|"]
+    );
+}
+
+#[test]
+#[serial]
+fn test_numeric_keys() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE rates (id INT, code TEXT, rate NUMERIC, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO rates VALUES (1, 'a', 1.5), (2, 'b', 1.50), (3, 'c', 2), (4, 'd', 0.333);")
+        .expect("INSERT failed");
+    db.execute_sql("CREATE INDEX rates_rate ON rates (rate);").expect("CREATE INDEX failed");
+
+    // Equal values of different scales group, join and look up together
+    let result = db
+        .execute_sql("SELECT rate, count(*) FROM rates GROUP BY rate ORDER BY rate;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["0.333|1", "1.5|2", "2|1"]);
+    let result = db.execute_sql("SELECT code FROM rates WHERE rate = 0.3330;").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["d"]);
+    let result = db.execute_sql("SELECT code FROM rates WHERE rate = 2.00;").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["c"]);
+    let result = db
+        .execute_sql("SELECT DISTINCT r.code FROM rates r JOIN rates s ON r.rate = s.rate * 1 WHERE s.code <> r.code ORDER BY 1;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["a", "b"]);
+}
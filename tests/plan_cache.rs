@@ -0,0 +1,124 @@
+mod common;
+
+use common::{data_rows, insert_rows, TestDb};
+use serial_test::serial;
+
+fn setup(db: &TestDb) {
+    db.execute_sql("CREATE TABLE items (id INT, code INT, name STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    insert_rows(db, "items", (1..=5000).map(|id| format!("({}, {}, 'item{}')", id, id * 3, id)));
+}
+
+/// Value of one statistic of `SHOW plan_cache`
+fn stat(db: &TestDb, name: &str) -> String {
+    let result = db.execute_sql("SHOW plan_cache;").expect("SHOW failed");
+    data_rows(&result)
+        .into_iter()
+        .find_map(|row| row.strip_prefix(&format!("{}|", name)).map(str::to_string))
+        .unwrap_or_else(|| panic!("no statistic {} in {}", name, result))
+}
+
+fn count(db: &TestDb, name: &str) -> u64 {
+    stat(db, name).parse().expect("not a count")
+}
+
+#[test]
+#[serial]
+fn test_repeated_queries_hit() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let query_hits = count(&db, "query_hits");
+    let plan_hits = count(&db, "plan_hits");
+    let entries = count(&db, "entries");
+
+    // Texts differing only in layout and comments share an entry
+    for sql in [
+        "SELECT name FROM items WHERE id = 7;",
+        "SELECT  name\n  FROM items   WHERE id = 7",
+        "SELECT name FROM items -- by key\nWHERE id = 7;",
+        "SELECT /* again */ name FROM items WHERE id = 7;;",
+    ] {
+        let result = db.execute_sql(sql).expect("SELECT failed");
+        assert_eq!(data_rows(&result), vec!["item7"]);
+    }
+    assert_eq!(count(&db, "entries"), entries + 1);
+    assert_eq!(count(&db, "query_hits"), query_hits + 3);
+    assert_eq!(count(&db, "plan_hits"), plan_hits + 3);
+
+    // Text inside quotes is kept as written
+    let result = db.execute_sql("SELECT id FROM items WHERE name = 'item  8';").expect("SELECT failed");
+    assert!(data_rows(&result).is_empty());
+    let result = db.execute_sql("SELECT id FROM items WHERE name = 'item8';").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["8"]);
+    assert_eq!(count(&db, "entries"), entries + 3);
+    // And so is text inside dollar quotes
+    for (sql, text) in [("SELECT $t$x$$  y$t$;", "x$$  y"), ("SELECT $t$x$$ y$t$;", "x$$ y")] {
+        let err = db.execute_sql(sql).unwrap_err();
+        assert!(err.contains(&format!("{:?}", text)), "unexpected error: {}", err);
+    }
+    assert_eq!(count(&db, "entries"), entries + 5);
+
+    // Statements other than queries are not cached
+    db.execute_sql("INSERT INTO items VALUES (5001, 15003, 'item5001');").expect("INSERT failed");
+    db.execute_sql("INSERT INTO items VALUES (5002, 1, 'item5002');").expect("INSERT failed");
+    assert_eq!(count(&db, "entries"), entries + 5);
+
+    // Cached plans see rows written since they were made
+    let result = db.execute_sql("SELECT count(*) FROM items WHERE code > 14970;").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["11"]);
+    db.execute_sql("DELETE FROM items WHERE id = 5001;").expect("DELETE failed");
+    let result = db.execute_sql("SELECT count(*) FROM items WHERE code > 14970;").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["10"]);
+
+    let rate: f64 = stat(&db, "query_hit_rate").parse().expect("not a rate");
+    assert!(rate > 0.0 && rate <= 1.0, "unexpected hit rate {}", rate);
+}
+
+#[test]
+#[serial]
+fn test_invalidation() {
+    let db = TestDb::new();
+    setup(&db);
+
+    let query = "SELECT id FROM items WHERE code = 126 ORDER BY id;";
+    let explain = "EXPLAIN SELECT id FROM items WHERE code = 126 ORDER BY id;";
+    db.execute_sql(query).expect("SELECT failed");
+    assert!(db.execute_sql(explain).expect("EXPLAIN failed").contains("Seq Scan"));
+
+    // A new index is used once the cached plan is replanned
+    let invalidations = count(&db, "invalidations");
+    db.execute_sql("CREATE INDEX idx_code ON items (code);").expect("CREATE INDEX failed");
+    db.execute_sql("ANALYZE items;").expect("ANALYZE failed");
+    let result = db.execute_sql(query).expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["42"]);
+    assert_eq!(count(&db, "invalidations"), invalidations + 1);
+    let plan = db.execute_sql(explain).expect("EXPLAIN failed");
+    assert!(plan.contains("idx_code"), "unexpected plan: {}", plan);
+
+    // So is a change of planner settings
+    let plan_hits = count(&db, "plan_hits");
+    db.execute_sql(query).expect("SELECT failed");
+    assert_eq!(count(&db, "plan_hits"), plan_hits + 1);
+    let result = db
+        .execute_session(&["SET enable_indexscan = off;", "SET enable_bitmapscan = off;", query])
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["42"]);
+    assert_eq!(count(&db, "invalidations"), invalidations + 2);
+}
+
+#[test]
+#[serial]
+fn test_plan_cache_mode() {
+    let db = TestDb::new();
+
+    let result = db.execute_sql("SHOW plan_cache_mode;").expect("SHOW failed");
+    assert_eq!(data_rows(&result), vec!["auto"]);
+    let result = db
+        .execute_session(&["SET plan_cache_mode = force_generic_plan;", "SHOW plan_cache_mode;"])
+        .expect("SHOW failed");
+    assert_eq!(data_rows(&result), vec!["force_generic_plan"]);
+
+    let err = db.execute_sql("SET plan_cache_mode = sometimes;").unwrap_err();
+    assert!(err.contains("invalid value for parameter \"plan_cache_mode\""), "unexpected error: {}", err);
+}
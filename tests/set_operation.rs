@@ -0,0 +1,170 @@
+mod common;
+
+use common::{data_rows, error, TestDb};
+use serial_test::serial;
+
+fn setup_tables(db: &TestDb) {
+    db.execute_sql("CREATE TABLE a (id INT, v INT, tag STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("CREATE TABLE b (id INT, v INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO a VALUES (1, 1, 'x'), (2, 1, 'y'), (3, 2, 'x'), (4, 3, 'z'), (5, NULL, 'y');")
+        .expect("INSERT failed");
+    db.execute_sql("INSERT INTO b VALUES (1, 1), (2, 2), (3, 2), (4, 4), (5, NULL);")
+        .expect("INSERT failed");
+}
+
+#[test]
+#[serial]
+fn test_union() {
+    let db = TestDb::new();
+    setup_tables(&db);
+
+    // NULLs are not distinct from each other
+    let result = db
+        .execute_sql("SELECT v, 'r' FROM a UNION SELECT v, 'r' FROM b ORDER BY v;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|r", "2|r", "3|r", "4|r", "|r"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT v FROM a WHERE v = 1 UNION ALL SELECT v FROM b WHERE v < 3 ORDER BY 1 DESC LIMIT 3;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["2", "2", "1"], "{}", result);
+
+    // Columns are named after the first query; integers widen to floats
+    let result = db
+        .execute_sql("SELECT id AS n FROM a WHERE id = 1 UNION SELECT 1.0 UNION SELECT 2.5 ORDER BY n;")
+        .expect("SELECT failed");
+    assert!(result.contains(" n"), "wrong column name: {}", result);
+    assert_eq!(data_rows(&result), ["1.0", "2.5"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_union_common_types() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE m2 (id INT, amt NUMERIC(10,2), day DATE, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO m2 VALUES (1, 2.50, '2024-01-01');")
+        .expect("INSERT failed");
+
+    // Numerics match whatever their precision and scale, and integers with them
+    let result = db
+        .execute_sql("SELECT amt FROM m2 UNION SELECT 1.5::numeric ORDER BY 1;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1.5", "2.50"], "{}", result);
+    let result = db
+        .execute_sql("SELECT id FROM m2 UNION ALL SELECT amt FROM m2;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1", "2.50"], "{}", result);
+
+    // Dates meet timestamps as timestamps, so equal values are one row
+    let result = db
+        .execute_sql("SELECT day FROM m2 UNION SELECT TIMESTAMP '2024-01-01' UNION SELECT TIMESTAMP '2024-01-01 10:00' ORDER BY 1;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["2024-01-01 00:00:00", "2024-01-01 10:00:00"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_intersect_except() {
+    let db = TestDb::new();
+    setup_tables(&db);
+
+    let result = db
+        .execute_sql("SELECT v, 'r' FROM a INTERSECT SELECT v, 'r' FROM b ORDER BY v;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|r", "2|r", "|r"], "{}", result);
+
+    // a has 1 twice and 2 once, b has 1 once and 2 twice
+    let result = db
+        .execute_sql("SELECT v FROM a INTERSECT ALL SELECT v FROM b ORDER BY v LIMIT 2;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1", "2"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT v FROM a EXCEPT SELECT v FROM b ORDER BY v;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["3"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT v FROM a EXCEPT ALL SELECT v FROM b ORDER BY v;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1", "3"], "{}", result);
+
+    // INTERSECT binds tighter than UNION
+    let result = db
+        .execute_sql("SELECT 4 UNION SELECT v FROM a INTERSECT SELECT v FROM b ORDER BY 1 LIMIT 3;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1", "2", "4"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_distinct() {
+    let db = TestDb::new();
+    setup_tables(&db);
+
+    let result = db
+        .execute_sql("SELECT DISTINCT tag FROM a ORDER BY tag;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["x", "y", "z"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT DISTINCT v, tag FROM a ORDER BY v, tag LIMIT 2;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|x", "1|y"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT COUNT(*) FROM (SELECT DISTINCT v FROM b) t;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["4"], "{}", result);
+
+    // The first row of each group in ORDER BY order
+    let result = db
+        .execute_sql("SELECT DISTINCT ON (tag) tag, id FROM a ORDER BY tag, id DESC;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["x|3", "y|5", "z|4"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT DISTINCT ON (1) v, id FROM b ORDER BY v, id LIMIT 2;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|1", "2|2"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_set_operation_errors() {
+    let db = TestDb::new();
+    setup_tables(&db);
+
+    let result = db.execute_sql("SELECT id, v FROM a UNION SELECT id FROM b;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("must have the same number of columns")),
+        "column count mismatch should fail: {:?}",
+        result
+    );
+
+    assert_eq!(
+        error("SELECT tag FROM a EXCEPT SELECT v FROM b;"),
+        "42804: EXCEPT types text and bigint cannot be matched"
+    );
+    assert_eq!(
+        error("SELECT id FROM a UNION SELECT DATE '2024-01-01';"),
+        "42804: UNION types bigint and date cannot be matched"
+    );
+
+    let result = db.execute_sql("SELECT DISTINCT tag FROM a ORDER BY id;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("ORDER BY expressions must appear in select list")),
+        "DISTINCT with unselected ORDER BY should fail: {:?}",
+        result
+    );
+
+    let result = db.execute_sql("SELECT DISTINCT ON (tag) tag, id FROM a ORDER BY id;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("must match initial ORDER BY expressions")),
+        "mismatched DISTINCT ON should fail: {:?}",
+        result
+    );
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use common::{data_rows, TestDb};
+use serial_test::serial;
+
+fn setup_scores(db: &TestDb) {
+    db.execute_sql("CREATE TABLE scores (id INT, name STRING, score INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql(
+        "INSERT INTO scores VALUES (1, 'carol', 70), (2, 'alice', 90), (3, 'bob', NULL), \
+         (4, 'dave', 70), (5, 'erin', 80);",
+    )
+    .expect("INSERT failed");
+}
+
+#[test]
+#[serial]
+fn test_order_by_asc_desc() {
+    let db = TestDb::new();
+    setup_scores(&db);
+
+    let result = db
+        .execute_sql("SELECT name FROM scores ORDER BY name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["alice", "bob", "carol", "dave", "erin"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT name FROM scores ORDER BY name DESC;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["erin", "dave", "carol", "bob", "alice"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_order_by_nulls() {
+    let db = TestDb::new();
+    setup_scores(&db);
+
+    // NULLs sort last ascending and first descending by default
+    let result = db
+        .execute_sql("SELECT id FROM scores ORDER BY score, id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1", "4", "5", "2", "3"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT id FROM scores ORDER BY score DESC, id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["3", "2", "5", "1", "4"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT id FROM scores ORDER BY score NULLS FIRST, id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["3", "1", "4", "5", "2"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT id FROM scores ORDER BY score DESC NULLS LAST, id DESC;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["2", "5", "4", "1", "3"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_order_by_position_and_alias() {
+    let db = TestDb::new();
+    setup_scores(&db);
+
+    let result = db
+        .execute_sql("SELECT name, score FROM scores WHERE score > 0 ORDER BY 2 DESC, 1;")
+        .expect("SELECT failed");
+    let lines = data_rows(&result);
+    assert_eq!(lines.len(), 4, "{}", result);
+    assert!(lines[0].starts_with("alice"), "{}", result);
+    assert!(lines[2].starts_with("carol"), "{}", result);
+    assert!(lines[3].starts_with("dave"), "{}", result);
+
+    let result = db
+        .execute_sql("SELECT id AS ident FROM scores ORDER BY ident DESC;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["5", "4", "3", "2", "1"], "{}", result);
+
+    // Sort keys need not be in the select list
+    let result = db
+        .execute_sql("SELECT name FROM scores ORDER BY id DESC;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["erin", "dave", "bob", "alice", "carol"], "{}", result);
+
+    let result = db.execute_sql("SELECT name FROM scores ORDER BY 3;");
+    assert!(result.is_err(), "ORDER BY position out of range should fail");
+}
+
+#[test]
+#[serial]
+fn test_order_by_aggregate() {
+    let db = TestDb::new();
+    setup_scores(&db);
+
+    let result = db
+        .execute_sql("SELECT score, COUNT(*) FROM scores GROUP BY score ORDER BY COUNT(*) DESC, score;")
+        .expect("SELECT failed");
+    let lines = data_rows(&result);
+    assert_eq!(lines.len(), 4, "{}", result);
+    assert!(lines[0].starts_with("70"), "{}", result);
+    assert!(lines[1].starts_with("80"), "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_order_by_limit_offset() {
+    let db = TestDb::new();
+    setup_scores(&db);
+
+    let result = db
+        .execute_sql("SELECT name FROM scores ORDER BY name LIMIT 2;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["alice", "bob"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT name FROM scores ORDER BY name LIMIT 2 OFFSET 2;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["carol", "dave"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT name FROM scores ORDER BY name DESC LIMIT 10 OFFSET 4;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["alice"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_external_sort_spills() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE big (id INT, value INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    let values = (1..=3000)
+        .map(|i| format!("({}, {})", i, (i * 7919) % 1000))
+        .collect::<Vec<_>>()
+        .join(", ");
+    db.execute_sql(&format!("INSERT INTO big VALUES {};", values))
+        .expect("INSERT failed");
+
+    // A small work_mem forces the sort to spill sorted runs to disk
+    let query = "SELECT value, id FROM big ORDER BY value DESC, id;";
+    let plan = db
+        .execute_session(&["SET work_mem = '64kB';", &format!("EXPLAIN ANALYZE {}", query)])
+        .expect("EXPLAIN ANALYZE failed");
+    assert!(plan.contains("Sort Method: external merge  Disk: "), "sort did not spill: {}", plan);
+
+    let result = db
+        .execute_session(&["SET work_mem = '64kB';", query])
+        .expect("SELECT failed");
+    let rows: Vec<(i64, i64)> = data_rows(&result)
+        .iter()
+        .map(|line| {
+            let (value, id) = line.split_once('|').expect("two columns");
+            (value.parse().unwrap(), id.parse().unwrap())
+        })
+        .collect();
+    assert_eq!(rows.len(), 3000, "expected all rows");
+    assert!(
+        rows.windows(2).all(|pair| pair[0].0 > pair[1].0 || (pair[0].0 == pair[1].0 && pair[0].1 < pair[1].1)),
+        "rows not sorted"
+    );
+
+    // Other connections keep the default work_mem, under which it stays in memory
+    let plan = db.execute_sql(&format!("EXPLAIN ANALYZE {}", query)).expect("EXPLAIN ANALYZE failed");
+    assert!(plan.contains("Sort Method: quicksort"), "unexpected plan: {}", plan);
+    let plan = db
+        .execute_sql("EXPLAIN ANALYZE SELECT id FROM big ORDER BY value LIMIT 3;")
+        .expect("EXPLAIN ANALYZE failed");
+    assert!(plan.contains("Sort Method: top-N heapsort"), "unexpected plan: {}", plan);
+}
+
+#[test]
+#[serial]
+fn test_merge_join_spills() {
+    let db = TestDb::new();
+
+    db.execute_sql("CREATE TABLE big (id INT, value INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    let values = (1..=3000)
+        .map(|i| format!("({}, {})", i, (i * 7919) % 3000))
+        .collect::<Vec<_>>()
+        .join(", ");
+    db.execute_sql(&format!("INSERT INTO big VALUES {};", values))
+        .expect("INSERT failed");
+
+    // Both merge join inputs are sorted externally and merged as they are read
+    let result = db
+        .execute_session(&[
+            "SET work_mem = '64kB';",
+            "SET enable_hashjoin = off;",
+            "SET enable_nestloop = off;",
+            "SELECT COUNT(*), SUM(a.id * b.id) FROM big a JOIN big b ON a.value = b.id;",
+        ])
+        .expect("SELECT failed");
+    let plan = db
+        .execute_session(&[
+            "SET enable_hashjoin = off;",
+            "SET enable_nestloop = off;",
+            "EXPLAIN SELECT COUNT(*) FROM big a JOIN big b ON a.value = b.id;",
+        ])
+        .expect("EXPLAIN failed");
+    assert!(plan.contains("Merge Join"), "unexpected plan: {}", plan);
+    // value 0 has no matching id
+    let expected = (1..=3000).filter(|i| (i * 7919) % 3000 != 0).count();
+    let sum: i64 = (1..=3000i64).map(|i| i * ((i * 7919) % 3000)).sum();
+    assert_eq!(data_rows(&result), [format!("{}|{}", expected, sum)], "{}", result);
+}
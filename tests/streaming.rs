@@ -0,0 +1,76 @@
+mod common;
+
+use common::{data_rows, insert_rows, TestDb};
+use serial_test::serial;
+
+fn setup_items(db: &TestDb, count: usize) {
+    db.execute_sql("CREATE TABLE items (id INT, label STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    insert_rows(db, "items", (1..=count).map(|id| format!("({}, 'item {}')", id, id)));
+}
+
+#[test]
+#[serial]
+fn test_limit_stops_pulling_rows() {
+    let db = TestDb::new();
+    setup_items(&db, 5);
+
+    let result = db.execute_sql("SELECT 10 / (id - 3) FROM items;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("division by zero")),
+        "full scan should reach id 3: {:?}",
+        result
+    );
+
+    // Rows after the limit are never projected
+    let result = db
+        .execute_sql("SELECT id, 10 / (id - 3) FROM items LIMIT 2;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|-5", "2|-10"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT id FROM items WHERE id > 1 LIMIT 0;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), Vec::<String>::new(), "{}", result);
+
+    // A limited scan of a table spanning many blocks stops after the first
+    insert_rows(&db, "items", (6..=20000).map(|id| format!("({}, 'item {}')", id, id)));
+    let full = block_reads(&db, "SELECT label FROM items;");
+    let limited = block_reads(&db, "SELECT label FROM items LIMIT 1;");
+    assert!(full >= 10, "expected a multi-block table, read {} blocks", full);
+    assert!(limited * 5 <= full, "LIMIT 1 read {} of {} blocks", limited, full);
+}
+
+/// Blocks the top plan node of `sql` read, from EXPLAIN ANALYZE
+fn block_reads(db: &TestDb, sql: &str) -> u64 {
+    let result = db.execute_sql(&format!("EXPLAIN ANALYZE {}", sql)).expect("EXPLAIN ANALYZE failed");
+    result
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Blocks: read="))
+        .unwrap_or_else(|| panic!("no block reads in {}", result))
+        .parse()
+        .expect("not a count")
+}
+
+#[test]
+#[serial]
+fn test_streaming_scan_across_blocks() {
+    let db = TestDb::new();
+    setup_items(&db, 3000);
+
+    let result = db
+        .execute_sql("SELECT id, label FROM items WHERE id > 2000 LIMIT 3 OFFSET 2;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["2003|item 2003", "2004|item 2004", "2005|item 2005"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT label FROM items WHERE id > 10;")
+        .expect("SELECT failed");
+    assert!(result.contains("(2990 rows)"), "wrong row count: {}", result);
+
+    // Blocking operators above a streamed scan
+    let result = db
+        .execute_sql("SELECT COUNT(*), MAX(id) FROM (SELECT id FROM items WHERE id <= 2500 LIMIT 2400) t;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["2400|2400"], "{}", result);
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use common::{data_rows, TestDb};
+use serial_test::serial;
+
+fn setup_company(db: &TestDb) {
+    db.execute_sql("CREATE TABLE emp (id INT, name STRING, dept_id INT, salary INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql("CREATE TABLE dept (id INT, dname STRING, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql(
+        "INSERT INTO emp VALUES (1, 'ann', 10, 100), (2, 'bob', 20, 80), (3, 'cat', NULL, 90), \
+         (4, 'dan', 30, 70), (5, 'eve', 10, 120);",
+    )
+    .expect("INSERT failed");
+    db.execute_sql("INSERT INTO dept VALUES (10, 'eng'), (20, 'ops'), (40, 'hr');")
+        .expect("INSERT failed");
+}
+
+#[test]
+#[serial]
+fn test_scalar_subquery() {
+    let db = TestDb::new();
+    setup_company(&db);
+
+    let result = db
+        .execute_sql("SELECT name FROM emp WHERE salary > (SELECT AVG(salary) FROM emp) ORDER BY name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann", "eve"], "{}", result);
+
+    // Correlated: each employee's department name, NULL when there is none
+    let result = db
+        .execute_sql("SELECT e.name, (SELECT d.dname FROM dept d WHERE d.id = e.dept_id) FROM emp e ORDER BY e.name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann|eng", "bob|ops", "cat|", "dan|", "eve|eng"], "{}", result);
+
+    // Correlated aggregate, comparing with the department maximum
+    let result = db
+        .execute_sql(
+            "SELECT name FROM emp e WHERE salary = (SELECT MAX(salary) FROM emp x WHERE x.dept_id = e.dept_id) \
+             ORDER BY name;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["bob", "dan", "eve"], "{}", result);
+
+    let result = db.execute_sql("SELECT (SELECT id FROM emp);");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("more than one row returned by a subquery")),
+        "multi-row scalar subquery should fail: {:?}",
+        result
+    );
+
+    let result = db.execute_sql("SELECT (SELECT id, name FROM emp WHERE id = 1);");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("must return only one column")),
+        "multi-column scalar subquery should fail: {:?}",
+        result
+    );
+}
+
+#[test]
+#[serial]
+fn test_in_subquery() {
+    let db = TestDb::new();
+    setup_company(&db);
+
+    let result = db
+        .execute_sql("SELECT name FROM emp WHERE dept_id IN (SELECT id FROM dept WHERE dname <> 'ops') ORDER BY name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann", "eve"], "{}", result);
+
+    // A NULL in the subquery makes NOT IN unknown for every non-matching row
+    let result = db
+        .execute_sql("SELECT name FROM emp WHERE id NOT IN (SELECT dept_id FROM emp) ORDER BY name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), Vec::<String>::new(), "{}", result);
+
+    let result = db
+        .execute_sql("SELECT dname FROM dept WHERE id NOT IN (SELECT dept_id FROM emp WHERE dept_id > 0) ORDER BY dname;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["hr"], "{}", result);
+
+    // IN in the select list yields NULL for a NULL operand
+    let result = db
+        .execute_sql("SELECT name, dept_id IN (SELECT id FROM dept) FROM emp ORDER BY name;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann|t", "bob|t", "cat|", "dan|f", "eve|t"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_exists_subquery() {
+    let db = TestDb::new();
+    setup_company(&db);
+
+    let result = db
+        .execute_sql("SELECT dname FROM dept d WHERE EXISTS (SELECT 1 FROM emp e WHERE e.dept_id = d.id) ORDER BY dname;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["eng", "ops"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT dname FROM dept d WHERE NOT EXISTS (SELECT 1 FROM emp e WHERE e.dept_id = d.id);")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["hr"], "{}", result);
+
+    // Correlated with a non-equality predicate, evaluated as a join filter
+    let result = db
+        .execute_sql(
+            "SELECT name FROM emp e WHERE EXISTS (SELECT 1 FROM emp x WHERE x.dept_id = e.dept_id AND x.salary > e.salary);",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["ann"], "{}", result);
+
+    // Decorrelated and per-row evaluation agree
+    let joined = db
+        .execute_sql("SELECT COUNT(*) FROM emp e WHERE EXISTS (SELECT 1 FROM dept d WHERE d.id = e.dept_id);")
+        .expect("SELECT failed");
+    let per_row = db
+        .execute_sql("SELECT COUNT(*) FROM emp e WHERE EXISTS (SELECT 1 FROM dept d WHERE d.id = e.dept_id LIMIT 1);")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&joined), ["3"], "{}", joined);
+    assert_eq!(data_rows(&joined), data_rows(&per_row));
+}
+
+#[test]
+#[serial]
+fn test_derived_tables_and_ctes() {
+    let db = TestDb::new();
+    setup_company(&db);
+
+    let result = db
+        .execute_sql(
+            "SELECT t.dept_id, t.total FROM (SELECT dept_id, SUM(salary) AS total FROM emp GROUP BY dept_id) t \
+             WHERE t.total > 75 ORDER BY t.total;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["20|80", "|90", "10|220"], "{}", result);
+
+    let result = db.execute_sql("SELECT * FROM (SELECT id FROM emp);");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("must have an alias")),
+        "derived table without alias should fail: {:?}",
+        result
+    );
+
+    let result = db
+        .execute_sql(
+            "WITH eng AS (SELECT * FROM emp WHERE dept_id = 10), \
+             top(who) AS (SELECT name FROM eng WHERE salary > 100) \
+             SELECT who FROM top;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["eve"], "{}", result);
+
+    // A CTE joined with a table
+    let result = db
+        .execute_sql(
+            "WITH counts AS (SELECT dept_id, COUNT(*) AS n FROM emp GROUP BY dept_id) \
+             SELECT d.dname, c.n FROM dept d JOIN counts c ON c.dept_id = d.id ORDER BY d.dname;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["eng|2", "ops|1"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_recursive_cte() {
+    let db = TestDb::new();
+
+    let result = db
+        .execute_sql("WITH RECURSIVE t(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM t WHERE n < 5) SELECT n FROM t;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1", "2", "3", "4", "5"], "{}", result);
+
+    // Walking a hierarchy
+    db.execute_sql("CREATE TABLE node (id INT, parent INT, PRIMARY KEY (id));").expect("CREATE TABLE failed");
+    db.execute_sql("INSERT INTO node VALUES (1, NULL), (2, 1), (3, 2), (4, 1), (5, 9);").expect("INSERT failed");
+    let result = db
+        .execute_sql(
+            "WITH RECURSIVE tree AS (SELECT id, 0 AS depth FROM node WHERE id = 1 \
+             UNION ALL SELECT n.id, t.depth + 1 FROM node n JOIN tree t ON n.parent = t.id) \
+             SELECT id, depth FROM tree ORDER BY id;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|0", "2|1", "3|2", "4|1"], "{}", result);
+
+    // UNION discards repeated rows, which ends an otherwise infinite cycle
+    let result = db
+        .execute_sql("WITH RECURSIVE c(x) AS (SELECT 1 UNION SELECT 3 - x FROM c) SELECT COUNT(*) FROM c;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["2"], "{}", result);
+}
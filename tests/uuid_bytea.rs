@@ -0,0 +1,152 @@
+mod common;
+
+use common::{data_rows, error, TestDb};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn test_uuid_input_and_output() {
+    let db = TestDb::new();
+
+    // Hyphens may follow any group of four digits, and braces may enclose it
+    let result = db
+        .execute_sql(
+            "SELECT 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11'::uuid, '{a0eebc999c0b4ef8bb6d6bb9bd380a11}'::uuid, \
+             'a0ee-bc99-9c0b-4ef8-bb6d-6bb9-bd38-0a11'::uuid::text;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec![
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11|a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11|a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"
+        ]
+    );
+
+    // UUIDs order by their bytes
+    let result = db
+        .execute_sql(
+            "SELECT '00000000-0000-0000-0000-000000000001'::uuid < 'ffffffff-0000-0000-0000-000000000000', \
+             'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'::uuid = '{A0EEBC99-9C0B4EF8-BB6D6BB9-BD380A11}';",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["t|t"]);
+
+    // Random UUIDs are version 4 and differ from call to call
+    let result = db
+        .execute_sql("SELECT gen_random_uuid()::text LIKE '________-____-4___-%', gen_random_uuid() <> gen_random_uuid();")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["t|t"]);
+
+    assert_eq!(
+        error("SELECT 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1'::uuid;"),
+        "22P02: invalid input syntax for type uuid: \"a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1\""
+    );
+    assert_eq!(
+        error("SELECT 'a0eebc99--9c0b-4ef8-bb6d-6bb9bd380a11'::uuid;"),
+        "22P02: invalid input syntax for type uuid: \"a0eebc99--9c0b-4ef8-bb6d-6bb9bd380a11\""
+    );
+    assert_eq!(error("SELECT 1::uuid;"), "42846: cannot cast type bigint to uuid");
+}
+
+#[test]
+#[serial]
+fn test_bytea_input_and_output() {
+    let db = TestDb::new();
+
+    let result = db
+        .execute_sql(
+            "SELECT '\\xDEADbeef'::bytea, '\\x de ad'::bytea, 'a\\\\b\\001'::bytea, ''::bytea, \
+             '\\x0102'::bytea || '\\x03'::bytea, encode('\\x00ff'::bytea, 'hex');",
+        )
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["\\xdeadbeef|\\xdead|\\x615c6201|\\x|\\x010203|00ff"]
+    );
+
+    let escape = "SET bytea_output = 'escape';";
+    let result = db
+        .execute_session(&[escape, "SELECT 'a\\\\b\\001'::bytea, '\\x41ff'::bytea;"])
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["a\\\\b\\001|A\\377"]);
+    let result = db.execute_session(&[escape, "SHOW bytea_output;"]).expect("SHOW failed");
+    assert_eq!(data_rows(&result), vec!["escape"]);
+
+    let result = db
+        .execute_sql(
+            "SELECT encode('\\x48656c6c6f'::bytea, 'base64'), encode('\\x0a5c41'::bytea, 'escape'), \
+             decode('SGVsbG8=', 'base64'), decode('48 65', 'hex'), decode('a\\101', 'escape');",
+        )
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        vec!["SGVsbG8=|\\012\\\\A|\\x48656c6c6f|\\x4865|\\x6141"]
+    );
+
+    let result = db
+        .execute_sql("SELECT '\\x0100'::bytea < '\\x02', '\\x01'::bytea < '\\x0100', '\\xab'::bytea = '\\xAB';")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["t|t|t"]);
+
+    assert_eq!(error("SELECT '\\xabc'::bytea;"), "22023: invalid hexadecimal data: odd number of digits");
+    assert_eq!(error("SELECT '\\xzz'::bytea;"), "22023: invalid hexadecimal digit: \"z\"");
+    assert_eq!(error("SELECT '\\q'::bytea;"), "22P02: invalid input syntax for type bytea: \"\\q\"");
+    assert_eq!(
+        error("SET bytea_output = 'octal';"),
+        "XX000: invalid value for parameter \"bytea_output\": \"octal\""
+    );
+}
+
+#[test]
+#[serial]
+fn test_uuid_and_bytea_columns() {
+    let db = TestDb::new();
+    db.execute_sql("CREATE TABLE files (id UUID, name TEXT, body BYTEA, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql(
+        "INSERT INTO files VALUES ('a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'a', '\\x00ff'), \
+         ('00000000-0000-0000-0000-000000000002', 'b', 'plain'), \
+         ('ffffffff-ffff-ffff-ffff-ffffffffffff', 'c', NULL), (gen_random_uuid(), 'd', '\\x');",
+    )
+    .expect("INSERT failed");
+
+    assert!(
+        error("INSERT INTO files VALUES ('{A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11}', 'e', NULL);")
+            .contains("duplicate key"),
+    );
+
+    // Lookups by key, and by value through btree and hash indexes
+    let plan = db
+        .execute_sql("EXPLAIN SELECT name FROM files WHERE id = 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11';")
+        .expect("EXPLAIN failed");
+    assert!(plan.contains("Index Scan using files_pkey on files"), "unexpected plan: {}", plan);
+    let result = db
+        .execute_sql("SELECT name, body FROM files WHERE id = 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11';")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["a|\\x00ff"]);
+    db.execute_sql("CREATE INDEX files_body ON files USING hash (body);").expect("CREATE INDEX failed");
+    let plan = db.execute_sql("EXPLAIN SELECT name FROM files WHERE body = 'plain';").expect("EXPLAIN failed");
+    assert!(plan.contains("Index Scan using files_body on files"), "unexpected plan: {}", plan);
+    let result = db.execute_sql("SELECT name FROM files WHERE body = 'plain';").expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["b"]);
+
+    let result = db
+        .execute_sql("SELECT name FROM files WHERE name <> 'd' ORDER BY id DESC;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["c", "a", "b"]);
+    let result = db
+        .execute_sql("SELECT body, count(*) FROM files GROUP BY body ORDER BY body;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["\\x|1", "\\x00ff|1", "\\x706c61696e|1", "|1"]);
+
+    db.execute_sql("UPDATE files SET body = body || '\\x01' WHERE name = 'a';").expect("UPDATE failed");
+    let result = db
+        .execute_sql("SELECT body FROM files WHERE id = '{a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11}';")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), vec!["\\x00ff01"]);
+
+    assert_eq!(
+        error("INSERT INTO files VALUES ('nope', 'e', NULL);"),
+        "22P02: invalid input syntax for type uuid: \"nope\""
+    );
+}
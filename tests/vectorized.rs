@@ -0,0 +1,67 @@
+mod common;
+
+use common::{data_rows, insert_rows, TestDb};
+use serial_test::serial;
+
+/// `count` rows spanning several batches; every tenth `v` is NULL
+fn setup_readings(db: &TestDb, count: usize) {
+    db.execute_sql("CREATE TABLE readings (id INT, grp INT, v INT, f FLOAT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    insert_rows(db, "readings", (1..=count).map(|id| {
+        let v = if id % 10 == 0 { "NULL".to_string() } else { (id % 7).to_string() };
+        format!("({}, {}, {}, {}.5)", id, id % 3, v, id % 4)
+    }));
+}
+
+#[test]
+#[serial]
+fn test_batch_filter_and_projection() {
+    let db = TestDb::new();
+    setup_readings(&db, 2500);
+
+    // Mixed integer and float arithmetic in both the predicate and the select list
+    let result = db
+        .execute_sql("SELECT id, v * 2 + 1, f / 2 FROM readings WHERE f > 3 AND id * 2 < 30 ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["3|7|1.75", "7|1|1.75", "11|9|1.75"], "{}", result);
+
+    let result = db
+        .execute_sql("SELECT COUNT(*) FROM readings WHERE v = 0 OR grp = 2;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1048"], "{}", result);
+
+    let result = db.execute_sql("SELECT 9223372036854775807 + id FROM readings;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("bigint out of range")),
+        "overflow should fail: {:?}",
+        result
+    );
+}
+
+#[test]
+#[serial]
+fn test_batch_aggregates() {
+    let db = TestDb::new();
+    setup_readings(&db, 2500);
+
+    let result = db
+        .execute_sql("SELECT grp, COUNT(*), COUNT(v), SUM(v), MIN(f), MAX(v) FROM readings GROUP BY grp ORDER BY grp;")
+        .expect("SELECT failed");
+    assert_eq!(
+        data_rows(&result),
+        ["0|833|750|2247|0.5|6", "1|834|750|2248|0.5|6", "2|833|750|2251|0.5|6"],
+        "{}",
+        result
+    );
+
+    let result = db
+        .execute_sql("SELECT AVG(f), SUM(DISTINCT v), COUNT(*) FILTER (WHERE v > 5) FROM readings;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["2.0|21|321"], "{}", result);
+
+    // Arguments are only evaluated for rows FILTER keeps
+    let result = db
+        .execute_sql("SELECT SUM(60 / v) FILTER (WHERE v <> 0) FROM readings WHERE id <= 14;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["274"], "{}", result);
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::{data_rows, TestDb};
+use serial_test::serial;
+
+fn setup_sales(db: &TestDb) {
+    db.execute_sql("CREATE TABLE sales (id INT, region STRING, day INT, amount INT, PRIMARY KEY (id));")
+        .expect("CREATE TABLE failed");
+    db.execute_sql(
+        "INSERT INTO sales VALUES (1, 'east', 1, 10), (2, 'east', 2, 30), (3, 'east', 2, 20), \
+         (4, 'east', 4, 40), (5, 'west', 1, 5), (6, 'west', 3, 15);",
+    )
+    .expect("INSERT failed");
+}
+
+#[test]
+#[serial]
+fn test_ranking_functions() {
+    let db = TestDb::new();
+    setup_sales(&db);
+
+    let result = db
+        .execute_sql(
+            "SELECT id, ROW_NUMBER() OVER (PARTITION BY region ORDER BY day, id), \
+             RANK() OVER (PARTITION BY region ORDER BY day), \
+             DENSE_RANK() OVER (PARTITION BY region ORDER BY day) \
+             FROM sales ORDER BY id;",
+        )
+        .expect("SELECT failed");
+    assert!(result.contains("row_number | rank | dense_rank"), "wrong columns: {}", result);
+    assert_eq!(
+        data_rows(&result),
+        ["1|1|1|1", "2|2|2|2", "3|3|2|2", "4|4|4|3", "5|1|1|1", "6|2|2|2"],
+        "{}",
+        result
+    );
+
+    let result = db
+        .execute_sql("SELECT id, NTILE(3) OVER (ORDER BY id), CUME_DIST() OVER (ORDER BY id) FROM sales WHERE region = 'east';")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|1|0.25", "2|1|0.5", "3|2|0.75", "4|3|1.0"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_lag_lead_and_values() {
+    let db = TestDb::new();
+    setup_sales(&db);
+
+    let result = db
+        .execute_sql(
+            "SELECT id, LAG(amount) OVER w, LEAD(amount, 1, 0) OVER w, FIRST_VALUE(amount) OVER w \
+             FROM sales WHERE region = 'east' WINDOW w AS (ORDER BY id) ORDER BY id;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1||30|10", "2|10|20|10", "3|30|40|10", "4|20|0|10"], "{}", result);
+
+    // The default frame ends at the current row, so LAST_VALUE needs an explicit one
+    let result = db
+        .execute_sql(
+            "SELECT id, LAST_VALUE(amount) OVER (ORDER BY id ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING), \
+             NTH_VALUE(amount, 2) OVER (ORDER BY id) FROM sales WHERE region = 'west';",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["5|15|", "6|15|15"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_window_aggregates_and_frames() {
+    let db = TestDb::new();
+    setup_sales(&db);
+
+    // Running total: the default RANGE frame includes peers of the current row
+    let result = db
+        .execute_sql("SELECT id, SUM(amount) OVER (PARTITION BY region ORDER BY day) FROM sales ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|10", "2|60", "3|60", "4|100", "5|5", "6|20"], "{}", result);
+
+    let result = db
+        .execute_sql(
+            "SELECT id, SUM(amount) OVER (ORDER BY id ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING) \
+             FROM sales WHERE region = 'east' ORDER BY id;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|40", "2|60", "3|90", "4|60"], "{}", result);
+
+    // RANGE offsets are distances in ORDER BY values
+    let result = db
+        .execute_sql(
+            "SELECT id, COUNT(*) OVER (ORDER BY day RANGE BETWEEN 1 PRECEDING AND CURRENT ROW) \
+             FROM sales WHERE region = 'east' ORDER BY id;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|1", "2|3", "3|3", "4|1"], "{}", result);
+
+    // Without ORDER BY the whole partition is the frame
+    let result = db
+        .execute_sql("SELECT id, MAX(amount) OVER (PARTITION BY region), COUNT(*) OVER () FROM sales ORDER BY id;")
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["1|40|6", "2|40|6", "3|40|6", "4|40|6", "5|15|6", "6|15|6"], "{}", result);
+
+    // Windows over grouped rows
+    let result = db
+        .execute_sql(
+            "SELECT region, SUM(amount), RANK() OVER (ORDER BY SUM(amount) DESC) FROM sales GROUP BY region ORDER BY region;",
+        )
+        .expect("SELECT failed");
+    assert_eq!(data_rows(&result), ["east|100|1", "west|20|2"], "{}", result);
+}
+
+#[test]
+#[serial]
+fn test_window_errors() {
+    let db = TestDb::new();
+    setup_sales(&db);
+
+    let result = db.execute_sql("SELECT id FROM sales WHERE ROW_NUMBER() OVER () > 1;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("window functions are not allowed in WHERE")),
+        "window function in WHERE should fail: {:?}",
+        result
+    );
+
+    let result = db.execute_sql("SELECT UPPER(region) OVER () FROM sales;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("is not a window function")),
+        "OVER on a plain function should fail: {:?}",
+        result
+    );
+
+    let result = db.execute_sql("SELECT SUM(amount) OVER (ORDER BY day, id RANGE 1 PRECEDING) FROM sales;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("requires exactly one ORDER BY column")),
+        "RANGE offset with two keys should fail: {:?}",
+        result
+    );
+
+    let result = db.execute_sql("SELECT SUM(amount) OVER (ROWS BETWEEN CURRENT ROW AND 1 PRECEDING) FROM sales;");
+    assert!(
+        result.as_ref().is_err_and(|e| e.contains("cannot have preceding rows")),
+        "inverted frame should fail: {:?}",
+        result
+    );
+}